@@ -1,8 +1,8 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 use crate::{
     event::StarfishEvent,
-    interface::config::ModuleConfiguration,
+    interface::{config::ModuleConfiguration, css3::UserStylesheet},
     shared::types::Result,
     tabs::Tab,
     window::Window,
@@ -49,6 +49,7 @@ impl WindowOptions {
 pub struct Application<'a, C: ModuleConfiguration> {
     windows: HashMap<WindowId, Window<'a, C>>,
     open_windows: Vec<(Url, WindowOptions)>,
+    user_stylesheets: Vec<UserStylesheet>,
     backend: C::RenderBackend,
     #[allow(clippy::type_complexity)]
     proxy: Option<EventLoopProxy<StarfishEvent<C>>>,
@@ -59,6 +60,7 @@ impl<'a, C: ModuleConfiguration> Application<'a, C> {
         Self {
             windows: HashMap::new(),
             open_windows: Vec::new(),
+            user_stylesheets: Vec::new(),
             backend,
             proxy: None,
         }
@@ -81,6 +83,16 @@ impl<'a, C: ModuleConfiguration> Application<'a, C> {
     pub fn initial_tab(&mut self, url: Url, opts: WindowOptions) {
         self.open_windows.push((url, opts));
     }
+
+    /// Reads a user stylesheet from disk. User stylesheets apply to all tabs opened afterwards.
+    pub fn add_user_stylesheet(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let stylesheet = UserStylesheet::from_path(path)
+            .map_err(|e| anyhow!("Could not load user stylesheet {}: {e}", path.display()))?;
+        self.user_stylesheets.push(stylesheet);
+
+        Ok(())
+    }
 }
 
 impl<C: ModuleConfiguration> ApplicationHandler<StarfishEvent<C>> for Application<'_, C> {
@@ -142,10 +154,11 @@ impl<C: ModuleConfiguration> ApplicationHandler<StarfishEvent<C>> for Applicatio
                 let Some(proxy) = self.proxy.clone() else {
                     return;
                 };
+                let user_stylesheets = self.user_stylesheets.clone();
 
                 std::thread::spawn(move || {
                     futures::executor::block_on(async move {
-                        let tab = match Tab::from_url(url).await {
+                        let tab = match Tab::from_url(url, user_stylesheets).await {
                             Ok(tab) => tab,
                             Err(e) => {
                                 tracing::error!("Error opening tab: {e:?}");
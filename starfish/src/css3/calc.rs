@@ -0,0 +1,905 @@
+use std::f32::consts::{E, PI};
+
+use crate::{
+    css3::{
+        matcher::computed::{is_viewport_unit, length_to_px},
+        stylesheet::CssValue,
+    },
+    shared::types::Size,
+};
+
+/// Units of the `<length>` data type
+pub const LENGTH_UNITS: &[&str] = &[
+    "px", "cm", "mm", "q", "in", "pt", "pc", "em", "rem", "ex", "rex", "ch", "rch", "cap", "rcap", "ic", "ric", "lh",
+    "rlh", "vw", "vh", "vi", "vb", "vmin", "vmax", "svw", "svh", "svi", "svb", "svmin", "svmax", "lvw", "lvh", "lvi",
+    "lvb", "lvmin", "lvmax", "dvw", "dvh", "dvi", "dvb", "dvmin", "dvmax", "cqw", "cqh", "cqi", "cqb", "cqmin",
+    "cqmax",
+];
+
+/// Math functions, which can be used in place of any numeric value (CSS Values 4, 10)
+pub const MATH_FUNCTIONS: &[&str] = &[
+    "calc", "min", "max", "clamp", "round", "mod", "rem", "sin", "cos", "tan", "asin", "acos", "atan", "atan2", "pow",
+    "sqrt", "hypot", "log", "exp", "abs", "sign",
+];
+
+/// Returns true when the value is a math function like `calc()`
+pub fn is_math_function(value: &CssValue) -> bool {
+    matches!(value, CssValue::Function(name, _) if MATH_FUNCTIONS.contains(&name.as_str()))
+}
+
+/// The base types of numeric values (CSS Values 4, 10.7.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseType {
+    Length,
+    Angle,
+    Time,
+    Frequency,
+    Resolution,
+    Flex,
+    Percent,
+}
+
+impl BaseType {
+    /// Returns the base type of a unit
+    pub fn of_unit(unit: &str) -> Option<Self> {
+        Some(match unit {
+            _ if LENGTH_UNITS.contains(&unit) => Self::Length,
+            "deg" | "grad" | "rad" | "turn" => Self::Angle,
+            "s" | "ms" => Self::Time,
+            "hz" | "khz" => Self::Frequency,
+            "dpi" | "dpcm" | "dppx" | "x" => Self::Resolution,
+            "fr" => Self::Flex,
+            _ => return None,
+        })
+    }
+}
+
+/// The type of a math expression: the exponent of each base type. A plain number has all
+/// exponents at zero, `10px * 2px` has a length exponent of two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CalcType([i8; 7]);
+
+impl CalcType {
+    pub fn number() -> Self {
+        Self::default()
+    }
+
+    pub fn of(base: BaseType) -> Self {
+        let mut exponents = [0; 7];
+        exponents[base as usize] = 1;
+        Self(exponents)
+    }
+
+    /// Returns true when the type is a plain number
+    pub fn is_number(&self) -> bool {
+        self.0 == [0; 7]
+    }
+
+    /// Returns true when the type is exactly the given base type
+    pub fn is(&self, base: BaseType) -> bool {
+        *self == Self::of(base)
+    }
+
+    /// Adding values (and comparing them in `min()` etc.) requires the same type
+    fn add(self, other: Self) -> Option<Self> {
+        (self == other).then_some(self)
+    }
+
+    fn multiply(self, other: Self) -> Self {
+        let mut exponents = self.0;
+        for (exponent, other) in exponents.iter_mut().zip(other.0) {
+            *exponent += other;
+        }
+        Self(exponents)
+    }
+
+    fn invert(self) -> Self {
+        Self(self.0.map(|exponent| -exponent))
+    }
+}
+
+/// How `round()` rounds its value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundingStrategy {
+    Nearest,
+    Up,
+    Down,
+    ToZero,
+}
+
+/// The math functions other than `calc()`, which is just a grouping of its expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MathFunction {
+    Min,
+    Max,
+    Clamp,
+    Round(RoundingStrategy),
+    Mod,
+    Rem,
+    Abs,
+    Sign,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Pow,
+    Sqrt,
+    Hypot,
+    Log,
+    Exp,
+}
+
+impl MathFunction {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "clamp" => Self::Clamp,
+            "round" => Self::Round(RoundingStrategy::Nearest),
+            "mod" => Self::Mod,
+            "rem" => Self::Rem,
+            "abs" => Self::Abs,
+            "sign" => Self::Sign,
+            "sin" => Self::Sin,
+            "cos" => Self::Cos,
+            "tan" => Self::Tan,
+            "asin" => Self::Asin,
+            "acos" => Self::Acos,
+            "atan" => Self::Atan,
+            "atan2" => Self::Atan2,
+            "pow" => Self::Pow,
+            "sqrt" => Self::Sqrt,
+            "hypot" => Self::Hypot,
+            "log" => Self::Log,
+            "exp" => Self::Exp,
+            _ => return None,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Clamp => "clamp",
+            Self::Round(_) => "round",
+            Self::Mod => "mod",
+            Self::Rem => "rem",
+            Self::Abs => "abs",
+            Self::Sign => "sign",
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::Tan => "tan",
+            Self::Asin => "asin",
+            Self::Acos => "acos",
+            Self::Atan => "atan",
+            Self::Atan2 => "atan2",
+            Self::Pow => "pow",
+            Self::Sqrt => "sqrt",
+            Self::Hypot => "hypot",
+            Self::Log => "log",
+            Self::Exp => "exp",
+        }
+    }
+
+    /// Returns the allowed number of arguments
+    fn arity(&self) -> (usize, usize) {
+        match self {
+            Self::Min | Self::Max | Self::Hypot => (1, usize::MAX),
+            Self::Clamp => (3, 3),
+            Self::Round(_) => (1, 2),
+            Self::Mod | Self::Rem | Self::Atan2 | Self::Pow => (2, 2),
+            Self::Log => (1, 2),
+            _ => (1, 1),
+        }
+    }
+}
+
+/// A node of a math expression (CSS Values 4, 10.9)
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcNode {
+    Number(f32),
+    /// A dimension with its (lowercase) unit, like `2rem`
+    Dimension(f32, String),
+    Percentage(f32),
+    Sum(Vec<CalcNode>),
+    Product(Vec<CalcNode>),
+    Negate(Box<CalcNode>),
+    /// `1 / value`, used for divisions
+    Invert(Box<CalcNode>),
+    Function(MathFunction, Vec<CalcNode>),
+}
+
+/// What is needed to resolve the relative parts of an expression
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CalcContext {
+    /// Font size for `em` and friends. When None, font relative units are kept.
+    pub font_size: Option<f32>,
+    /// Font size of the root element for `rem` and friends
+    pub root_font_size: Option<f32>,
+    /// The size percentages refer to. When None, percentages are kept.
+    pub percentage_basis: Option<f32>,
+    /// Size of the viewport for `vw` and friends. When None, viewport units are kept.
+    pub viewport: Option<Size<f32>>,
+}
+
+impl CalcNode {
+    /// Parses a math function like `calc(100% - 2rem)` into an expression. Returns None when the
+    /// value is not a math function or its expression is invalid. Types are not checked here,
+    /// see [`CalcNode::calc_type`].
+    pub fn parse(value: &CssValue) -> Option<Self> {
+        let CssValue::Function(name, args) = value else {
+            return None;
+        };
+
+        if name == "calc" {
+            return parse_expression(args);
+        }
+
+        let mut function = MathFunction::from_name(name)?;
+        let mut args = args.as_slice();
+        if let MathFunction::Round(strategy) = &mut function {
+            if let [CssValue::Keyword(keyword), CssValue::Comma, rest @ ..] = args {
+                *strategy = match keyword.to_ascii_lowercase().as_str() {
+                    "nearest" => RoundingStrategy::Nearest,
+                    "up" => RoundingStrategy::Up,
+                    "down" => RoundingStrategy::Down,
+                    "to-zero" => RoundingStrategy::ToZero,
+                    _ => return None,
+                };
+                args = rest;
+            }
+        }
+
+        let args = args
+            .split(|arg| *arg == CssValue::Comma)
+            .map(parse_expression)
+            .collect::<Option<Vec<_>>>()?;
+
+        let (min, max) = function.arity();
+        if args.len() < min || args.len() > max {
+            return None;
+        }
+
+        Some(Self::Function(function, args))
+    }
+
+    /// Returns the type of the expression, or None when the types do not match (like adding a
+    /// length to a number). `percent_as` is the type percentages resolve to in the context of the
+    /// property, like `Length` for `width`. Without it, percentages are their own type.
+    pub fn calc_type(&self, percent_as: Option<BaseType>) -> Option<CalcType> {
+        let ty = |node: &CalcNode| node.calc_type(percent_as);
+        let same_type = |args: &[CalcNode]| args.iter().map(ty).reduce(|a, b| a?.add(b?)).flatten();
+
+        match self {
+            Self::Number(_) => Some(CalcType::number()),
+            Self::Dimension(_, unit) => BaseType::of_unit(unit).map(CalcType::of),
+            Self::Percentage(_) => Some(CalcType::of(percent_as.unwrap_or(BaseType::Percent))),
+            Self::Sum(terms) => same_type(terms),
+            Self::Product(factors) => factors
+                .iter()
+                .try_fold(CalcType::number(), |acc, factor| Some(acc.multiply(ty(factor)?))),
+            Self::Negate(node) => ty(node),
+            Self::Invert(node) => ty(node).map(CalcType::invert),
+            Self::Function(function, args) => match function {
+                MathFunction::Min
+                | MathFunction::Max
+                | MathFunction::Clamp
+                | MathFunction::Mod
+                | MathFunction::Rem
+                | MathFunction::Abs
+                | MathFunction::Hypot => same_type(args),
+                // `round(A)` is only allowed for numbers, the step defaults to 1
+                MathFunction::Round(_) => {
+                    let ty = same_type(args)?;
+                    (args.len() == 2 || ty.is_number()).then_some(ty)
+                }
+                MathFunction::Sign => same_type(args).map(|_| CalcType::number()),
+                MathFunction::Sin | MathFunction::Cos | MathFunction::Tan => {
+                    let ty = same_type(args)?;
+                    (ty.is_number() || ty.is(BaseType::Angle)).then(CalcType::number)
+                }
+                MathFunction::Asin | MathFunction::Acos | MathFunction::Atan => {
+                    same_type(args)?.is_number().then(|| CalcType::of(BaseType::Angle))
+                }
+                MathFunction::Atan2 => same_type(args).map(|_| CalcType::of(BaseType::Angle)),
+                MathFunction::Pow | MathFunction::Sqrt | MathFunction::Log | MathFunction::Exp => args
+                    .iter()
+                    .all(|arg| ty(arg).is_some_and(|ty| ty.is_number()))
+                    .then(CalcType::number),
+            },
+        }
+    }
+
+    /// Simplifies the expression as far as the context allows (CSS Values 4, 10.10). Relative
+    /// lengths are converted into pixels, and all values that have the same unit are combined.
+    /// A fully resolved expression becomes a single number, dimension or percentage.
+    pub fn simplify(&self, ctx: &CalcContext) -> CalcNode {
+        match self {
+            Self::Number(_) => self.clone(),
+            Self::Dimension(value, unit) => canonicalize(*value, unit, ctx),
+            Self::Percentage(pct) => match ctx.percentage_basis {
+                Some(basis) => Self::Dimension(basis * pct / 100.0, "px".into()),
+                None => self.clone(),
+            },
+            Self::Sum(terms) => simplify_sum(terms.iter().map(|term| term.simplify(ctx)).collect()),
+            Self::Product(factors) => simplify_product(factors.iter().map(|factor| factor.simplify(ctx)).collect()),
+            Self::Negate(node) => negate(node.simplify(ctx)),
+            Self::Invert(node) => match node.simplify(ctx) {
+                Self::Number(n) => Self::Number(1.0 / n),
+                node => Self::Invert(Box::new(node)),
+            },
+            Self::Function(function, args) => {
+                let args = args.iter().map(|arg| arg.simplify(ctx)).collect::<Vec<_>>();
+                evaluate_function(*function, &args).unwrap_or(Self::Function(*function, args))
+            }
+        }
+    }
+
+    /// Resolves the expression into pixels, with percentages relative to `percentage_basis`.
+    /// Returns None when the expression cannot be resolved into a length.
+    pub fn resolve_px(&self, percentage_basis: Option<f32>) -> Option<f32> {
+        let ctx = CalcContext {
+            percentage_basis,
+            ..Default::default()
+        };
+        match self.simplify(&ctx) {
+            Self::Dimension(px, unit) if unit == "px" => Some(px),
+            Self::Number(0.0) => Some(0.0),
+            _ => None,
+        }
+    }
+
+    /// Returns true when the expression still contains percentages
+    pub fn has_percentage(&self) -> bool {
+        match self {
+            Self::Percentage(_) => true,
+            Self::Number(_) | Self::Dimension(..) => false,
+            Self::Sum(nodes) | Self::Product(nodes) | Self::Function(_, nodes) => {
+                nodes.iter().any(Self::has_percentage)
+            }
+            Self::Negate(node) | Self::Invert(node) => node.has_percentage(),
+        }
+    }
+
+    /// Converts the expression back into a css value. Single values are returned as-is, other
+    /// expressions are wrapped in `calc()` (or their own math function).
+    pub fn to_css_value(&self) -> CssValue {
+        match self {
+            Self::Number(n) => CssValue::Number(*n),
+            Self::Dimension(value, unit) => CssValue::Unit(*value, unit.clone()),
+            Self::Percentage(pct) => CssValue::Percentage(*pct),
+            Self::Function(function, args) => function_value(*function, args),
+            _ => CssValue::Function("calc".into(), self.to_values()),
+        }
+    }
+
+    /// Converts the expression into the values of a `calc()`
+    fn to_values(&self) -> Vec<CssValue> {
+        match self {
+            Self::Sum(terms) => {
+                let mut values = Vec::new();
+                for (i, term) in terms.iter().enumerate() {
+                    // Negative terms are written as subtractions
+                    let (negative, term) = match term {
+                        Self::Negate(node) => (true, node.as_ref().clone()),
+                        Self::Number(n) if *n < 0.0 && i > 0 => (true, Self::Number(-n)),
+                        Self::Dimension(v, unit) if *v < 0.0 && i > 0 => (true, Self::Dimension(-v, unit.clone())),
+                        Self::Percentage(p) if *p < 0.0 && i > 0 => (true, Self::Percentage(-p)),
+                        _ => (false, term.clone()),
+                    };
+                    if i > 0 {
+                        values.push(CssValue::Delim(if negative { '-' } else { '+' }));
+                    } else if negative {
+                        values.push(CssValue::Number(-1.0));
+                        values.push(CssValue::Delim('*'));
+                    }
+                    values.push(term.nested_value());
+                }
+                values
+            }
+            Self::Product(factors) => {
+                let mut values = Vec::new();
+                for (i, factor) in factors.iter().enumerate() {
+                    match factor {
+                        Self::Invert(node) => {
+                            values.push(if i > 0 {
+                                CssValue::Delim('/')
+                            } else {
+                                CssValue::Number(1.0)
+                            });
+                            if i == 0 {
+                                values.push(CssValue::Delim('/'));
+                            }
+                            values.push(node.nested_value());
+                        }
+                        _ => {
+                            if i > 0 {
+                                values.push(CssValue::Delim('*'));
+                            }
+                            values.push(factor.nested_value());
+                        }
+                    }
+                }
+                values
+            }
+            Self::Negate(node) => vec![CssValue::Number(-1.0), CssValue::Delim('*'), node.nested_value()],
+            Self::Invert(node) => vec![CssValue::Number(1.0), CssValue::Delim('/'), node.nested_value()],
+            _ => vec![self.to_css_value()],
+        }
+    }
+
+    /// Converts a nested node into a single value, using parentheses for sums and products
+    fn nested_value(&self) -> CssValue {
+        match self {
+            Self::Sum(_) | Self::Product(_) | Self::Negate(_) | Self::Invert(_) => {
+                CssValue::Function("(".into(), self.to_values())
+            }
+            _ => self.to_css_value(),
+        }
+    }
+}
+
+fn function_value(function: MathFunction, args: &[CalcNode]) -> CssValue {
+    let mut values = Vec::new();
+    if let MathFunction::Round(strategy) = function {
+        if strategy != RoundingStrategy::Nearest {
+            let name = match strategy {
+                RoundingStrategy::Up => "up",
+                RoundingStrategy::Down => "down",
+                _ => "to-zero",
+            };
+            values.push(CssValue::Keyword(name.into()));
+            values.push(CssValue::Comma);
+        }
+    }
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            values.push(CssValue::Comma);
+        }
+        values.extend(arg.to_values());
+    }
+    CssValue::Function(function.name().into(), values)
+}
+
+/// Parses a complete expression: `<sum>`. All values must be consumed.
+fn parse_expression(values: &[CssValue]) -> Option<CalcNode> {
+    let mut pos = 0;
+    let node = parse_sum(values, &mut pos)?;
+    (pos == values.len()).then_some(node)
+}
+
+/// `<product> [ [ '+' | '-' ] <product> ]*`
+fn parse_sum(values: &[CssValue], pos: &mut usize) -> Option<CalcNode> {
+    let mut terms = vec![parse_product(values, pos)?];
+
+    while let Some(CssValue::Delim(op @ ('+' | '-'))) = values.get(*pos) {
+        *pos += 1;
+        let term = parse_product(values, pos)?;
+        terms.push(if *op == '-' {
+            CalcNode::Negate(Box::new(term))
+        } else {
+            term
+        });
+    }
+
+    Some(if terms.len() == 1 {
+        terms.remove(0)
+    } else {
+        CalcNode::Sum(terms)
+    })
+}
+
+/// `<value> [ [ '*' | '/' ] <value> ]*`
+fn parse_product(values: &[CssValue], pos: &mut usize) -> Option<CalcNode> {
+    let mut factors = vec![parse_value(values, pos)?];
+
+    while let Some(CssValue::Delim(op @ ('*' | '/'))) = values.get(*pos) {
+        *pos += 1;
+        let factor = parse_value(values, pos)?;
+        factors.push(if *op == '/' {
+            CalcNode::Invert(Box::new(factor))
+        } else {
+            factor
+        });
+    }
+
+    Some(if factors.len() == 1 {
+        factors.remove(0)
+    } else {
+        CalcNode::Product(factors)
+    })
+}
+
+/// A number, dimension, percentage, constant, parenthesized expression or nested math function
+fn parse_value(values: &[CssValue], pos: &mut usize) -> Option<CalcNode> {
+    let value = values.get(*pos)?;
+    *pos += 1;
+
+    match value {
+        CssValue::Number(n) => Some(CalcNode::Number(*n)),
+        CssValue::Percentage(pct) => Some(CalcNode::Percentage(*pct)),
+        CssValue::Unit(v, unit) => Some(CalcNode::Dimension(*v, unit.clone())),
+        CssValue::Keyword(keyword) => {
+            let n = match keyword.to_ascii_lowercase().as_str() {
+                "e" => E,
+                "pi" => PI,
+                "infinity" => f32::INFINITY,
+                "-infinity" => f32::NEG_INFINITY,
+                "nan" => f32::NAN,
+                _ => return None,
+            };
+            Some(CalcNode::Number(n))
+        }
+        CssValue::Function(name, args) if name == "(" => parse_expression(args),
+        CssValue::Function(..) => CalcNode::parse(value),
+        _ => None,
+    }
+}
+
+/// Converts a dimension into its canonical unit (px, deg, s, hz or dppx) where possible
+fn canonicalize(value: f32, unit: &str, ctx: &CalcContext) -> CalcNode {
+    let canonical = match unit {
+        "deg" => Some((value, "deg")),
+        "grad" => Some((value * 0.9, "deg")),
+        "rad" => Some((value.to_degrees(), "deg")),
+        "turn" => Some((value * 360.0, "deg")),
+        "s" => Some((value, "s")),
+        "ms" => Some((value / 1000.0, "s")),
+        "hz" => Some((value, "hz")),
+        "khz" => Some((value * 1000.0, "hz")),
+        "dppx" | "x" => Some((value, "dppx")),
+        "dpi" => Some((value / 96.0, "dppx")),
+        "dpcm" => Some((value * 2.54 / 96.0, "dppx")),
+        _ => {
+            // Font relative units can only be resolved when the font sizes are known
+            let is_relative = !matches!(unit, "px" | "cm" | "mm" | "q" | "in" | "pt" | "pc") && !is_viewport_unit(unit);
+            if is_relative && (ctx.font_size.is_none() || ctx.root_font_size.is_none()) {
+                None
+            } else {
+                length_to_px(
+                    value,
+                    unit,
+                    ctx.font_size.unwrap_or_default(),
+                    ctx.root_font_size.unwrap_or_default(),
+                    ctx.viewport,
+                )
+                .map(|px| (px, "px"))
+            }
+        }
+    };
+
+    match canonical {
+        Some((value, unit)) => CalcNode::Dimension(value, unit.into()),
+        None => CalcNode::Dimension(value, unit.into()),
+    }
+}
+
+fn negate(node: CalcNode) -> CalcNode {
+    match node {
+        CalcNode::Number(n) => CalcNode::Number(-n),
+        CalcNode::Dimension(v, unit) => CalcNode::Dimension(-v, unit),
+        CalcNode::Percentage(p) => CalcNode::Percentage(-p),
+        CalcNode::Negate(node) => *node,
+        CalcNode::Sum(terms) => CalcNode::Sum(terms.into_iter().map(negate).collect()),
+        node => CalcNode::Negate(Box::new(node)),
+    }
+}
+
+/// Flattens nested sums and combines the terms with the same unit
+fn simplify_sum(terms: Vec<CalcNode>) -> CalcNode {
+    let mut flattened = Vec::new();
+    for term in terms {
+        match term {
+            CalcNode::Sum(nested) => flattened.extend(nested),
+            term => flattened.push(term),
+        }
+    }
+
+    let mut result: Vec<CalcNode> = Vec::new();
+    for term in flattened {
+        let existing = result.iter_mut().find(|existing| match (&**existing, &term) {
+            (CalcNode::Number(_), CalcNode::Number(_)) => true,
+            (CalcNode::Percentage(_), CalcNode::Percentage(_)) => true,
+            (CalcNode::Dimension(_, a), CalcNode::Dimension(_, b)) => a == b,
+            _ => false,
+        });
+
+        match (existing, term) {
+            (Some(CalcNode::Number(a)), CalcNode::Number(b)) => *a += b,
+            (Some(CalcNode::Percentage(a)), CalcNode::Percentage(b)) => *a += b,
+            (Some(CalcNode::Dimension(a, _)), CalcNode::Dimension(b, _)) => *a += b,
+            (_, term) => result.push(term),
+        }
+    }
+
+    // Percentages come first, then dimensions sorted by unit (CSS Values 4, 10.13)
+    result.sort_by_key(|term| match term {
+        CalcNode::Number(_) => (0, String::new()),
+        CalcNode::Percentage(_) => (1, String::new()),
+        CalcNode::Dimension(_, unit) => (2, unit.clone()),
+        _ => (3, String::new()),
+    });
+
+    if result.len() == 1 {
+        return result.remove(0);
+    }
+    CalcNode::Sum(result)
+}
+
+/// Multiplies the numbers of a product into the other factor when possible
+fn simplify_product(factors: Vec<CalcNode>) -> CalcNode {
+    let mut flattened = Vec::new();
+    for factor in factors {
+        match factor {
+            CalcNode::Product(nested) => flattened.extend(nested),
+            factor => flattened.push(factor),
+        }
+    }
+
+    let mut number = 1.0;
+    let mut rest = Vec::new();
+    for factor in flattened {
+        match factor {
+            CalcNode::Number(n) => number *= n,
+            factor => rest.push(factor),
+        }
+    }
+
+    // Dimensions divided by dimensions of the same unit cancel out, like `10px / 2px`
+    let mut i = 0;
+    while i < rest.len() {
+        let matching = match &rest[i] {
+            CalcNode::Dimension(_, unit) => rest.iter().position(|other| {
+                matches!(other, CalcNode::Invert(node) if matches!(&**node, CalcNode::Dimension(_, u) if u == unit))
+            }),
+            _ => None,
+        };
+        match matching {
+            Some(j) => {
+                let (CalcNode::Dimension(a, _), CalcNode::Invert(b)) = (&rest[i], &rest[j]) else {
+                    unreachable!()
+                };
+                let CalcNode::Dimension(b, _) = **b else { unreachable!() };
+                number *= a / b;
+                let (first, second) = (i.max(j), i.min(j));
+                rest.remove(first);
+                rest.remove(second);
+                i = 0;
+            }
+            None => i += 1,
+        }
+    }
+
+    match rest.len() {
+        0 => CalcNode::Number(number),
+        1 => scale(rest.remove(0), number),
+        _ => {
+            if number != 1.0 {
+                rest.insert(0, CalcNode::Number(number));
+            }
+            CalcNode::Product(rest)
+        }
+    }
+}
+
+/// Multiplies a node by a number
+fn scale(node: CalcNode, factor: f32) -> CalcNode {
+    match node {
+        _ if factor == 1.0 => node,
+        CalcNode::Number(n) => CalcNode::Number(n * factor),
+        CalcNode::Dimension(v, unit) => CalcNode::Dimension(v * factor, unit),
+        CalcNode::Percentage(p) => CalcNode::Percentage(p * factor),
+        CalcNode::Sum(terms) => CalcNode::Sum(terms.into_iter().map(|term| scale(term, factor)).collect()),
+        CalcNode::Negate(node) => scale(*node, -factor),
+        node => CalcNode::Product(vec![CalcNode::Number(factor), node]),
+    }
+}
+
+/// Returns the value and unit of a fully resolved node
+fn numeric(node: &CalcNode) -> Option<(f32, &str)> {
+    match node {
+        CalcNode::Number(n) => Some((*n, "")),
+        CalcNode::Dimension(v, unit) => Some((*v, unit.as_str())),
+        CalcNode::Percentage(p) => Some((*p, "%")),
+        _ => None,
+    }
+}
+
+fn with_unit(value: f32, unit: &str) -> CalcNode {
+    match unit {
+        "" => CalcNode::Number(value),
+        "%" => CalcNode::Percentage(value),
+        unit => CalcNode::Dimension(value, unit.into()),
+    }
+}
+
+/// Evaluates a math function when all its arguments are resolved into the same unit
+fn evaluate_function(function: MathFunction, args: &[CalcNode]) -> Option<CalcNode> {
+    let values = args.iter().map(numeric).collect::<Option<Vec<_>>>()?;
+    let unit = values[0].1;
+    let same_unit = values.iter().all(|(_, u)| *u == unit);
+    let v = values.iter().map(|(v, _)| *v).collect::<Vec<_>>();
+
+    let (value, unit) = match function {
+        MathFunction::Min if same_unit => (v.iter().copied().fold(f32::INFINITY, f32::min), unit),
+        MathFunction::Max if same_unit => (v.iter().copied().fold(f32::NEG_INFINITY, f32::max), unit),
+        // The minimum wins over the maximum
+        MathFunction::Clamp if same_unit => (v[1].min(v[2]).max(v[0]), unit),
+        MathFunction::Round(strategy) if same_unit => {
+            let step = v.get(1).copied().unwrap_or(1.0);
+            let n = v[0] / step;
+            let rounded = match strategy {
+                RoundingStrategy::Nearest => (n + 0.5).floor(),
+                RoundingStrategy::Up => n.ceil(),
+                RoundingStrategy::Down => n.floor(),
+                RoundingStrategy::ToZero => n.trunc(),
+            };
+            (rounded * step, unit)
+        }
+        MathFunction::Mod if same_unit => (v[0] - v[1] * (v[0] / v[1]).floor(), unit),
+        MathFunction::Rem if same_unit => (v[0] % v[1], unit),
+        MathFunction::Abs => (v[0].abs(), unit),
+        MathFunction::Sign => (if v[0] == 0.0 { 0.0 } else { v[0].signum() }, ""),
+        MathFunction::Sin | MathFunction::Cos | MathFunction::Tan => {
+            let radians = if unit == "deg" { v[0].to_radians() } else { v[0] };
+            let value = match function {
+                MathFunction::Sin => radians.sin(),
+                MathFunction::Cos => radians.cos(),
+                _ => radians.tan(),
+            };
+            (value, "")
+        }
+        MathFunction::Asin => (v[0].asin().to_degrees(), "deg"),
+        MathFunction::Acos => (v[0].acos().to_degrees(), "deg"),
+        MathFunction::Atan => (v[0].atan().to_degrees(), "deg"),
+        MathFunction::Atan2 if same_unit => (v[0].atan2(v[1]).to_degrees(), "deg"),
+        MathFunction::Pow => (v[0].powf(v[1]), ""),
+        MathFunction::Sqrt => (v[0].sqrt(), ""),
+        MathFunction::Hypot if same_unit => (v.iter().map(|v| v * v).sum::<f32>().sqrt(), unit),
+        MathFunction::Log => match v.get(1) {
+            Some(base) => (v[0].ln() / base.ln(), ""),
+            None => (v[0].ln(), ""),
+        },
+        MathFunction::Exp => (v[0].exp(), ""),
+        _ => return None,
+    };
+
+    Some(with_unit(value, unit))
+}
+
+/// Computes a math function value: relative lengths are resolved against the font sizes and the
+/// viewport, and the expression is simplified. Percentages are kept (as `calc()`) until layout.
+pub fn compute_math_value(
+    value: &CssValue,
+    font_size: f32,
+    root_font_size: f32,
+    viewport: Size<f32>,
+) -> Option<CssValue> {
+    let node = CalcNode::parse(value)?;
+    let ctx = CalcContext {
+        font_size: Some(font_size),
+        root_font_size: Some(root_font_size),
+        percentage_basis: None,
+        viewport: Some(viewport),
+    };
+    Some(node.simplify(&ctx).to_css_value())
+}
+
+/// Resolves a computed length, percentage or math function into pixels, with percentages relative
+/// to `percentage_basis`. Returns None when the value cannot be resolved (like a percentage
+/// without a basis).
+pub fn resolve_length(value: &CssValue, percentage_basis: Option<f32>) -> Option<f32> {
+    match value {
+        CssValue::Unit(px, unit) if unit == "px" => Some(*px),
+        CssValue::Number(n) if *n == 0.0 => Some(0.0),
+        CssValue::Percentage(pct) => percentage_basis.map(|basis| basis * pct / 100.0),
+        CssValue::Function(..) => CalcNode::parse(value)?.resolve_px(percentage_basis),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> Option<CalcNode> {
+        CalcNode::parse(&crate::css3::parse_value(value).unwrap()[0])
+    }
+
+    /// Simplifies the expression with a font size of 10px and a 800x600 viewport
+    fn simplify(value: &str, percentage_basis: Option<f32>) -> String {
+        let ctx = CalcContext {
+            font_size: Some(10.0),
+            root_font_size: Some(16.0),
+            percentage_basis,
+            viewport: Some(Size::new(800.0, 600.0)),
+        };
+        parse(value).unwrap().simplify(&ctx).to_css_value().to_string()
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            parse("calc(1px + 2 * 3%)"),
+            Some(CalcNode::Sum(vec![
+                CalcNode::Dimension(1.0, "px".into()),
+                CalcNode::Product(vec![CalcNode::Number(2.0), CalcNode::Percentage(3.0)]),
+            ]))
+        );
+        assert!(parse("calc((1px + 2px) / 3)").is_some());
+        assert!(parse("clamp(1px, 2em, 3rem)").is_some());
+        assert!(parse("round(up, 7px, 5px)").is_some());
+
+        assert_eq!(parse("calc(1px +)"), None);
+        assert_eq!(parse("calc()"), None);
+        assert_eq!(parse("clamp(1px, 2px)"), None);
+        assert_eq!(parse("round(sideways, 7px, 5px)"), None);
+        assert_eq!(parse("translate(1px)"), None);
+    }
+
+    #[test]
+    fn type_checking() {
+        let length = Some(CalcType::of(BaseType::Length));
+        let ty = |value: &str, percent_as: Option<BaseType>| parse(value).unwrap().calc_type(percent_as);
+
+        assert_eq!(ty("calc(2px * 3)", None), length);
+        assert_eq!(ty("calc(10px / 2 + 1em)", None), length);
+        assert_eq!(ty("calc(50% - 1px)", Some(BaseType::Length)), length);
+        assert_eq!(ty("min(1px, 2em, 3vw)", None), length);
+        assert_eq!(ty("calc(1px / 1px)", None), Some(CalcType::number()));
+        assert_eq!(ty("sin(90deg)", None), Some(CalcType::number()));
+        assert_eq!(ty("atan2(1px, 1px)", None), Some(CalcType::of(BaseType::Angle)));
+
+        assert_eq!(ty("calc(1px + 2)", None), None);
+        assert_eq!(ty("calc(50% - 1px)", None), None);
+        assert_eq!(ty("calc(1px + 1deg)", None), None);
+        assert_ne!(ty("calc(1px * 1px)", None), length);
+        assert_eq!(ty("pow(2px, 2)", None), None);
+        assert_eq!(ty("round(2.5px)", None), None);
+    }
+
+    #[test]
+    fn simplification() {
+        assert_eq!(simplify("calc(1px + 2px + 1em)", None), "13px");
+        assert_eq!(simplify("calc(2 * (3px + 1px))", None), "8px");
+        assert_eq!(simplify("calc(1rem / 4)", None), "4px");
+        assert_eq!(simplify("calc(10vw + 1vh)", None), "86px");
+        assert_eq!(simplify("calc(1in - 24pt)", None), "64px");
+        assert_eq!(simplify("min(10px, 2em)", None), "10px");
+        assert_eq!(simplify("max(10px, 2em)", None), "20px");
+        assert_eq!(simplify("clamp(1px, 5px, 3px)", None), "3px");
+        assert_eq!(simplify("round(up, 7px, 5px)", None), "10px");
+        assert_eq!(simplify("round(2.5)", None), "3");
+        assert_eq!(simplify("mod(-7px, 3px)", None), "2px");
+        assert_eq!(simplify("rem(-7px, 3px)", None), "-1px");
+        assert_eq!(simplify("abs(-3px)", None), "3px");
+        assert_eq!(simplify("sign(-2px)", None), "-1");
+        assert_eq!(simplify("hypot(3px, 4px)", None), "5px");
+        assert_eq!(simplify("calc(pow(2, 3) + sqrt(16))", None), "12");
+        assert_eq!(simplify("calc(sin(90deg) * 2)", None), "2");
+    }
+
+    #[test]
+    fn percentages_are_kept_until_layout() {
+        assert_eq!(simplify("calc(100% - 2rem)", None), "calc(100% - 32px)");
+        assert_eq!(simplify("calc(50% + 10% + 1em)", None), "calc(60% + 10px)");
+        assert_eq!(simplify("clamp(100px, 50%, 300px)", None), "clamp(100px, 50%, 300px)");
+        assert_eq!(simplify("calc(100% - 2rem)", Some(784.0)), "752px");
+        assert_eq!(simplify("clamp(100px, 50%, 300px)", Some(784.0)), "300px");
+
+        let computed = compute_math_value(
+            &crate::css3::parse_value("calc(100% - 2rem)").unwrap()[0],
+            10.0,
+            16.0,
+            Size::new(800.0, 600.0),
+        )
+        .unwrap();
+        assert_eq!(resolve_length(&computed, None), None);
+        assert_eq!(resolve_length(&computed, Some(784.0)), Some(752.0));
+        assert!(parse("min(50%, 10px)").unwrap().has_percentage());
+        assert!(!parse("min(5em, 10px)").unwrap().has_percentage());
+    }
+}
@@ -0,0 +1,679 @@
+use crate::{
+    css3::{
+        colors::spaces::{Channels, ColorSpace},
+        stylesheet::CssValue,
+    },
+    interface::render_backend::Color,
+};
+
+mod spaces;
+
+/// The named colors from CSS Color 4, 6.1 as 0xRRGGBB
+pub const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+/// System colors (CSS Color 4, 6.2) with the (light color scheme) value used for them. These
+/// depend on the user agent and platform.
+pub const SYSTEM_COLORS: &[(&str, u32)] = &[
+    ("accentcolor", 0x0075ff),
+    ("accentcolortext", 0xffffff),
+    ("activetext", 0xff0000),
+    ("buttonborder", 0x767676),
+    ("buttonface", 0xefefef),
+    ("buttontext", 0x000000),
+    ("canvas", 0xffffff),
+    ("canvastext", 0x000000),
+    ("field", 0xffffff),
+    ("fieldtext", 0x000000),
+    ("graytext", 0x808080),
+    ("highlight", 0xb4d5fe),
+    ("highlighttext", 0x000000),
+    ("linktext", 0x0000ee),
+    ("mark", 0xffff00),
+    ("marktext", 0x000000),
+    ("selecteditem", 0x0075ff),
+    ("selecteditemtext", 0xffffff),
+    ("visitedtext", 0x551a8b),
+];
+
+/// Functions that produce a color
+pub const COLOR_FUNCTIONS: &[&str] = &[
+    "rgb",
+    "rgba",
+    "hsl",
+    "hsla",
+    "hwb",
+    "lab",
+    "lch",
+    "oklab",
+    "oklch",
+    "color",
+    "color-mix",
+    "light-dark",
+];
+
+/// Returns the named color as 0xRRGGBB
+pub fn named_color(name: &str) -> Option<u32> {
+    NAMED_COLORS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, rgb)| *rgb)
+}
+
+/// Returns the system color as 0xRRGGBB
+pub fn system_color(name: &str) -> Option<u32> {
+    SYSTEM_COLORS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, rgb)| *rgb)
+}
+
+/// Returns true when the keyword is a valid color keyword
+pub fn is_color_keyword(name: &str) -> bool {
+    name.eq_ignore_ascii_case("currentcolor")
+        || name.eq_ignore_ascii_case("transparent")
+        || named_color(name).is_some()
+        || system_color(name).is_some()
+}
+
+/// A resolved color in the sRGB color space. The channels are in the range 0..1, but may lie outside
+/// of it for colors that are outside of the sRGB gamut.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RgbaColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl RgbaColor {
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Creates an opaque color from 0xRRGGBB
+    pub fn from_rgb(rgb: u32) -> Self {
+        let channel = |shift: u32| ((rgb >> shift) & 0xff) as f32 / 255.0;
+        Self::new(channel(16), channel(8), channel(0), 1.0)
+    }
+
+    fn from_channels(c: Channels, alpha: f64) -> Self {
+        Self::new(c[0] as f32, c[1] as f32, c[2] as f32, alpha.clamp(0.0, 1.0) as f32)
+    }
+
+    fn channels(&self) -> Channels {
+        [self.r as f64, self.g as f64, self.b as f64]
+    }
+
+    /// Returns the color as 8-bit RGBA. Colors outside of the sRGB gamut are clipped.
+    pub fn to_rgba8(&self) -> (u8, u8, u8, u8) {
+        let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        (channel(self.r), channel(self.g), channel(self.b), channel(self.a))
+    }
+
+    /// Converts the color into the color type of a render backend
+    pub fn to_color<C: Color>(&self) -> C {
+        C::tuple4(self.to_rgba8())
+    }
+
+    /// Returns the color as `rgb()` or `rgba()` value, as computed colors are serialized
+    pub fn to_css_value(&self) -> CssValue {
+        let (r, g, b, _) = self.to_rgba8();
+        let mut args = vec![
+            CssValue::Number(r as f32),
+            CssValue::Comma,
+            CssValue::Number(g as f32),
+            CssValue::Comma,
+            CssValue::Number(b as f32),
+        ];
+
+        if self.a >= 1.0 {
+            return CssValue::Function("rgb".into(), args);
+        }
+
+        args.push(CssValue::Comma);
+        args.push(CssValue::Number((self.a * 1000.0).round() / 1000.0));
+        CssValue::Function("rgba".into(), args)
+    }
+}
+
+/// Resolves a color value (CSS Color 4) into an sRGB color. `current_color` is the value of
+/// `currentcolor`; when it is None, colors that depend on it cannot be resolved. Returns None
+/// for values that are not (supported) colors.
+pub fn resolve_color(value: &CssValue, current_color: Option<RgbaColor>) -> Option<RgbaColor> {
+    match value {
+        CssValue::List(values) => match values.as_slice() {
+            [value] => resolve_color(value, current_color),
+            _ => None,
+        },
+        CssValue::Keyword(name) => {
+            if name.eq_ignore_ascii_case("currentcolor") {
+                current_color
+            } else if name.eq_ignore_ascii_case("transparent") {
+                Some(RgbaColor::TRANSPARENT)
+            } else {
+                named_color(name)
+                    .or_else(|| system_color(name))
+                    .map(RgbaColor::from_rgb)
+            }
+        }
+        CssValue::Hash(hex) => parse_hex(hex),
+        CssValue::Function(name, args) => match name.as_str() {
+            "rgb" | "rgba" => parse_color_function(ColorSpace::Srgb, args, &[255.0, 255.0, 255.0])
+                .map(|c| RgbaColor::new(c.r / 255.0, c.g / 255.0, c.b / 255.0, c.a)),
+            "hsl" | "hsla" => parse_color_function(ColorSpace::Hsl, args, &[0.0, 100.0, 100.0]),
+            "hwb" => parse_color_function(ColorSpace::Hwb, args, &[0.0, 100.0, 100.0]),
+            "lab" => parse_color_function(ColorSpace::Lab, args, &[100.0, 125.0, 125.0]),
+            "lch" => parse_color_function(ColorSpace::Lch, args, &[100.0, 150.0, 0.0]),
+            "oklab" => parse_color_function(ColorSpace::Oklab, args, &[1.0, 0.4, 0.4]),
+            "oklch" => parse_color_function(ColorSpace::Oklch, args, &[1.0, 0.4, 0.0]),
+            "color" => {
+                let (space, rest) = args.split_first()?;
+                let space = ColorSpace::from_name(space.as_keyword()?)?;
+                if space.hue_index().is_some() || matches!(space, ColorSpace::Lab | ColorSpace::Oklab) {
+                    return None;
+                }
+                parse_color_function(space, rest, &[1.0, 1.0, 1.0])
+            }
+            "color-mix" => parse_color_mix(args, current_color),
+            // There is no dark color scheme, so the light color is always used
+            "light-dark" => {
+                let light = args.split(|arg| *arg == CssValue::Comma).next()?;
+                resolve_color(&CssValue::List(light.to_vec()), current_color)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses `#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`
+fn parse_hex(hex: &str) -> Option<RgbaColor> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digits = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 | 8 => hex.to_string(),
+        _ => return None,
+    };
+
+    let channel = |i: usize| {
+        u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16)
+            .ok()
+            .map(|v| v as f32 / 255.0)
+    };
+    let alpha = if digits.len() == 8 { channel(3)? } else { 1.0 };
+    Some(RgbaColor::new(channel(0)?, channel(1)?, channel(2)?, alpha))
+}
+
+/// Parses the arguments of a color function in both the modern (`rgb(255 0 0 / 50%)`) and the
+/// legacy (`rgba(255, 0, 0, 0.5)`) syntax. `percentages` holds the value that 100% refers to for
+/// each channel; a reference of 0 marks a hue channel. The channels are returned as-is for sRGB,
+/// which is used by `rgb()` to scale them afterwards.
+fn parse_color_function(space: ColorSpace, args: &[CssValue], percentages: &[f64; 3]) -> Option<RgbaColor> {
+    let (channels, alpha) = if args.contains(&CssValue::Comma) {
+        let items = args.split(|arg| *arg == CssValue::Comma).collect::<Vec<_>>();
+        if !(3..=4).contains(&items.len()) || items.iter().any(|item| item.len() != 1) {
+            return None;
+        }
+        let channels = items[..3].iter().map(|item| &item[0]).collect::<Vec<_>>();
+        (channels, items.get(3).map(|item| &item[0]))
+    } else {
+        match args.iter().position(|arg| *arg == CssValue::Delim('/')) {
+            Some(pos) => match &args[pos + 1..] {
+                [alpha] => (args[..pos].iter().collect(), Some(alpha)),
+                _ => return None,
+            },
+            None => (args.iter().collect(), None),
+        }
+    };
+
+    if channels.len() != 3 {
+        return None;
+    }
+
+    let mut c = [0.0; 3];
+    for (i, value) in channels.into_iter().enumerate() {
+        c[i] = if Some(i) == space.hue_index() {
+            parse_hue(value)?
+        } else {
+            parse_channel(value, percentages[i])?
+        };
+    }
+
+    let alpha = match alpha {
+        Some(alpha) => parse_channel(alpha, 1.0)?,
+        None => 1.0,
+    };
+
+    // Channels that cannot be negative are clamped at parse time
+    match space {
+        ColorSpace::Srgb => return Some(RgbaColor::from_channels(c.map(|v| v.clamp(0.0, 255.0)), alpha)),
+        // Both percentages and plain numbers are on a 0..100 scale
+        ColorSpace::Hsl | ColorSpace::Hwb => {
+            c[1] = c[1].max(0.0) / 100.0;
+            c[2] = c[2].max(0.0) / 100.0;
+        }
+        ColorSpace::Lab | ColorSpace::Oklab => c[0] = c[0].max(0.0),
+        ColorSpace::Lch | ColorSpace::Oklch => {
+            c[0] = c[0].max(0.0);
+            c[1] = c[1].max(0.0);
+        }
+        _ => {}
+    }
+
+    Some(RgbaColor::from_channels(space.to_srgb(c), alpha))
+}
+
+/// Parses a channel, which is a number, a percentage (of `percentage`) or `none` (zero)
+fn parse_channel(value: &CssValue, percentage: f64) -> Option<f64> {
+    match value {
+        CssValue::Number(n) => Some(*n as f64),
+        CssValue::Percentage(p) => Some(*p as f64 / 100.0 * percentage),
+        CssValue::Keyword(k) if k.eq_ignore_ascii_case("none") => Some(0.0),
+        _ => None,
+    }
+}
+
+/// Parses a hue into degrees
+fn parse_hue(value: &CssValue) -> Option<f64> {
+    let degrees = match value {
+        CssValue::Number(n) => *n as f64,
+        CssValue::Unit(v, unit) => {
+            let v = *v as f64;
+            match unit.as_str() {
+                "deg" => v,
+                "grad" => v * 0.9,
+                "rad" => v.to_degrees(),
+                "turn" => v * 360.0,
+                _ => return None,
+            }
+        }
+        CssValue::Keyword(k) if k.eq_ignore_ascii_case("none") => 0.0,
+        _ => return None,
+    };
+    Some(degrees)
+}
+
+/// Parses `color-mix(in <colorspace> [<hue-method> hue]?, <color> <percentage>?, <color> <percentage>?)`
+fn parse_color_mix(args: &[CssValue], current_color: Option<RgbaColor>) -> Option<RgbaColor> {
+    let parts = args.split(|arg| *arg == CssValue::Comma).collect::<Vec<_>>();
+    let [interpolation, first, second] = parts.as_slice() else {
+        return None;
+    };
+
+    let (space, hue_method) = match interpolation {
+        [in_, space] if in_.is_keyword("in") => (ColorSpace::from_name(space.as_keyword()?)?, "shorter"),
+        [in_, space, method, hue] if in_.is_keyword("in") && hue.is_keyword("hue") => {
+            let space = ColorSpace::from_name(space.as_keyword()?)?;
+            space.hue_index()?;
+            (space, method.as_keyword()?)
+        }
+        _ => return None,
+    };
+
+    let parse_mix_color = |values: &[CssValue]| -> Option<(RgbaColor, Option<f64>)> {
+        match values {
+            [color] => Some((resolve_color(color, current_color)?, None)),
+            [color, CssValue::Percentage(p)] | [CssValue::Percentage(p), color] => {
+                Some((resolve_color(color, current_color)?, Some(*p as f64 / 100.0)))
+            }
+            _ => None,
+        }
+    };
+    let (color1, p1) = parse_mix_color(first)?;
+    let (color2, p2) = parse_mix_color(second)?;
+
+    // Percentages are normalized to sum up to 100%. A sum below 100% makes the result transparent.
+    let (p1, p2) = match (p1, p2) {
+        (None, None) => (0.5, 0.5),
+        (Some(p1), None) => (p1, 1.0 - p1),
+        (None, Some(p2)) => (1.0 - p2, p2),
+        (Some(p1), Some(p2)) => (p1, p2),
+    };
+    if !(0.0..=1.0).contains(&p1) || !(0.0..=1.0).contains(&p2) {
+        return None;
+    }
+    let sum = p1 + p2;
+    if sum == 0.0 {
+        return None;
+    }
+    let alpha_multiplier = sum.min(1.0);
+    let (p1, p2) = (p1 / sum, p2 / sum);
+
+    let mut c1 = space.srgb_into(color1.channels());
+    let mut c2 = space.srgb_into(color2.channels());
+    let (a1, a2) = (color1.a as f64, color2.a as f64);
+
+    if let Some(hue) = space.hue_index() {
+        // An achromatic color has no hue, so the hue of the other color is used
+        let achromatic = |c: &Channels| match space {
+            ColorSpace::Hsl => c[1] == 0.0,
+            ColorSpace::Hwb => c[1] + c[2] >= 1.0,
+            _ => c[1].abs() < 1e-6,
+        };
+        if achromatic(&c1) {
+            c1[hue] = c2[hue];
+        } else if achromatic(&c2) {
+            c2[hue] = c1[hue];
+        }
+        adjust_hues(&mut c1[hue], &mut c2[hue], hue_method)?;
+    }
+
+    // Interpolation happens with premultiplied alpha (the hue is not premultiplied)
+    let alpha = a1 * p1 + a2 * p2;
+    let mut mixed = [0.0; 3];
+    for i in 0..3 {
+        mixed[i] = if Some(i) == space.hue_index() {
+            c1[i] * p1 + c2[i] * p2
+        } else if alpha == 0.0 {
+            0.0
+        } else {
+            (c1[i] * a1 * p1 + c2[i] * a2 * p2) / alpha
+        };
+    }
+
+    Some(RgbaColor::from_channels(space.to_srgb(mixed), alpha * alpha_multiplier))
+}
+
+/// Adjusts two hues (in degrees) for the given hue interpolation method (CSS Color 4, 12.4)
+fn adjust_hues(h1: &mut f64, h2: &mut f64, method: &str) -> Option<()> {
+    *h1 = h1.rem_euclid(360.0);
+    *h2 = h2.rem_euclid(360.0);
+    let diff = *h2 - *h1;
+
+    match method.to_ascii_lowercase().as_str() {
+        "shorter" => {
+            if diff > 180.0 {
+                *h1 += 360.0;
+            } else if diff < -180.0 {
+                *h2 += 360.0;
+            }
+        }
+        "longer" => {
+            if 0.0 < diff && diff < 180.0 {
+                *h1 += 360.0;
+            } else if -180.0 < diff && diff <= 0.0 {
+                *h2 += 360.0;
+            }
+        }
+        "increasing" => {
+            if diff < 0.0 {
+                *h2 += 360.0;
+            }
+        }
+        "decreasing" => {
+            if diff > 0.0 {
+                *h1 += 360.0;
+            }
+        }
+        _ => return None,
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{css3::parse_value, interface::render_backend::RenderBackend, vello::VelloBackend};
+
+    const CURRENT: RgbaColor = RgbaColor::new(0.0, 0.5, 0.0, 1.0);
+
+    fn rgba(value: &str) -> Option<(u8, u8, u8, u8)> {
+        let value = CssValue::List(parse_value(value).unwrap());
+        resolve_color(&value, Some(CURRENT)).map(|color| color.to_rgba8())
+    }
+
+    /// Compares the channels with a tolerance of one, for colors converted between color spaces
+    fn assert_near(value: &str, expected: (u8, u8, u8, u8)) {
+        let (r, g, b, a) = rgba(value).unwrap_or_else(|| panic!("{value} is not a color"));
+        let near = |x: u8, y: u8| x.abs_diff(y) <= 1;
+        assert!(
+            near(r, expected.0) && near(g, expected.1) && near(b, expected.2) && a == expected.3,
+            "{value} resolved to {:?}, expected {expected:?}",
+            (r, g, b, a)
+        );
+    }
+
+    #[test]
+    fn keywords_and_hex() {
+        assert_eq!(rgba("red"), Some((255, 0, 0, 255)));
+        assert_eq!(rgba("RebeccaPurple"), Some((102, 51, 153, 255)));
+        assert_eq!(rgba("transparent"), Some((0, 0, 0, 0)));
+        assert_eq!(rgba("currentcolor"), Some((0, 128, 0, 255)));
+        assert_eq!(rgba("#f00"), Some((255, 0, 0, 255)));
+        assert_eq!(rgba("#0f08"), Some((0, 255, 0, 136)));
+        assert_eq!(rgba("#123456"), Some((0x12, 0x34, 0x56, 255)));
+        assert_eq!(rgba("#ff000080"), Some((255, 0, 0, 128)));
+
+        assert_eq!(rgba("#12345"), None);
+        assert_eq!(rgba("#ggg"), None);
+        assert_eq!(rgba("reddish"), None);
+
+        let current = CssValue::Keyword("currentColor".into());
+        assert_eq!(resolve_color(&current, None), None);
+    }
+
+    #[test]
+    fn rgb_hsl_and_hwb() {
+        assert_eq!(rgba("rgb(255 0 0)"), Some((255, 0, 0, 255)));
+        assert_eq!(rgba("rgb(255 0 0 / 50%)"), Some((255, 0, 0, 128)));
+        assert_eq!(rgba("rgb(100%, 0%, 0%)"), Some((255, 0, 0, 255)));
+        assert_eq!(rgba("rgba(0, 0, 255, 0.5)"), Some((0, 0, 255, 128)));
+        assert_eq!(rgba("rgb(none 128 0)"), Some((0, 128, 0, 255)));
+
+        assert_eq!(rgba("hsl(120deg 100% 50%)"), Some((0, 255, 0, 255)));
+        assert_eq!(rgba("hsl(0, 100%, 50%)"), Some((255, 0, 0, 255)));
+        assert_eq!(rgba("hsla(240, 100%, 50%, 0.25)"), Some((0, 0, 255, 64)));
+        assert_eq!(rgba("hsl(0.5turn 100% 25%)"), Some((0, 128, 128, 255)));
+
+        assert_eq!(rgba("hwb(0 0% 0%)"), Some((255, 0, 0, 255)));
+        assert_eq!(rgba("hwb(0 50% 50%)"), Some((128, 128, 128, 255)));
+
+        assert_eq!(rgba("rgb(255 0)"), None);
+        assert_eq!(rgba("rgb(255, 0 0)"), None);
+        assert_eq!(rgba("hsl(red 100% 50%)"), None);
+    }
+
+    #[test]
+    fn lab_lch_and_oklab() {
+        assert_near("lab(50% 0 0)", (119, 119, 119, 255));
+        assert_near("lch(50% 0 0)", (119, 119, 119, 255));
+        assert_near("lab(54.29 80.8 69.89)", (255, 0, 0, 255));
+        assert_near("lch(54.29 106.84 40.85)", (255, 0, 0, 255));
+        assert_near("oklab(1 0 0)", (255, 255, 255, 255));
+        assert_near("oklch(0.628 0.2577 29.23 / 0.5)", (255, 0, 0, 128));
+        assert_near("color(srgb 1 0.5 0)", (255, 128, 0, 255));
+        assert_near("color(srgb-linear 0.2159 0.2159 0.2159)", (128, 128, 128, 255));
+        // Out of gamut colors are clipped
+        assert_near("color(display-p3 1 0 0)", (255, 0, 0, 255));
+
+        assert_eq!(rgba("color(lab 50 0 0)"), None);
+    }
+
+    #[test]
+    fn color_mix() {
+        assert_eq!(rgba("color-mix(in srgb, red, blue)"), Some((128, 0, 128, 255)));
+        assert_eq!(rgba("color-mix(in srgb, red 25%, blue)"), Some((64, 0, 191, 255)));
+        assert_eq!(
+            rgba("color-mix(in srgb, currentcolor, white)"),
+            Some((128, 191, 128, 255))
+        );
+        // Transparent colors are mixed with premultiplied alpha
+        assert_eq!(rgba("color-mix(in srgb, red, transparent)"), Some((255, 0, 0, 128)));
+        assert_near("color-mix(in hsl, red, blue)", (255, 0, 255, 255));
+        assert_near("color-mix(in hsl longer hue, red, blue)", (0, 255, 0, 255));
+
+        assert_eq!(rgba("color-mix(in srgb, red)"), None);
+        assert_eq!(rgba("color-mix(in nowhere, red, blue)"), None);
+    }
+
+    #[test]
+    fn conversion() {
+        let color = RgbaColor::new(1.0, 0.5, 0.0, 0.5);
+        let converted = color.to_color::<<VelloBackend as RenderBackend>::Color>();
+        assert_eq!(
+            (converted.r(), converted.g(), converted.b(), converted.a()),
+            (255, 128, 0, 128)
+        );
+        assert_eq!(color.to_css_value().to_string(), "rgba(255, 128, 0, 0.5)");
+        assert_eq!(
+            RgbaColor::from_rgb(0x336699).to_css_value().to_string(),
+            "rgb(51, 102, 153)"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+/// The three channels of a color in some color space. Channels are not clamped, so colors outside
+/// of the sRGB gamut survive intermediate conversions.
+pub(crate) type Channels = [f64; 3];
+
+/// The D50 white point, used by `lab()` and `lch()`
+const D50: Channels = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+const LAB_EPSILON: f64 = 216.0 / 24389.0;
+const LAB_KAPPA: f64 = 24389.0 / 27.0;
+
+const LINEAR_SRGB_TO_XYZ: [Channels; 3] = [
+    [0.412_390_799_265_959_3, 0.357_584_339_383_878, 0.180_480_788_401_834_3],
+    [0.212_639_005_871_510_3, 0.715_168_678_767_756, 0.072_192_315_360_733_7],
+    [0.019_330_818_715_591_82, 0.119_194_779_794_626, 0.950_532_152_249_660_6],
+];
+
+const XYZ_TO_LINEAR_SRGB: [Channels; 3] = [
+    [
+        3.240_969_941_904_522_6,
+        -1.537_383_177_570_094,
+        -0.498_610_760_293_003_4,
+    ],
+    [
+        -0.969_243_636_280_879_6,
+        1.875_967_501_507_720_2,
+        0.041_555_057_407_175_59,
+    ],
+    [
+        0.055_630_079_696_993_66,
+        -0.203_976_958_888_976_52,
+        1.056_971_514_242_878_6,
+    ],
+];
+
+const LINEAR_P3_TO_XYZ: [Channels; 3] = [
+    [
+        0.486_570_948_648_216_2,
+        0.265_667_693_169_093_06,
+        0.198_217_285_234_362_5,
+    ],
+    [0.228_974_564_069_748_8, 0.691_738_521_836_506_4, 0.079_286_914_093_745],
+    [0.0, 0.045_113_381_858_902_64, 1.043_944_368_900_976],
+];
+
+/// Bradford chromatic adaptation from D50 to D65
+const D50_TO_D65: [Channels; 3] = [
+    [
+        0.955_473_452_704_218_2,
+        -0.023_098_536_874_261_423,
+        0.063_259_308_661_021_7,
+    ],
+    [
+        -0.028_369_706_963_208_136,
+        1.009_995_458_005_822_6,
+        0.021_041_398_966_943_008,
+    ],
+    [
+        0.012_314_001_688_319_899,
+        -0.020_507_696_433_477_912,
+        1.330_365_936_608_075_3,
+    ],
+];
+
+/// Bradford chromatic adaptation from D65 to D50
+const D65_TO_D50: [Channels; 3] = [
+    [
+        1.047_929_820_840_548_8,
+        0.022_946_793_341_019_088,
+        -0.050_192_229_543_135_57,
+    ],
+    [
+        0.029_627_815_688_159_344,
+        0.990_434_484_573_249,
+        -0.017_073_825_029_385_14,
+    ],
+    [
+        -0.009_243_058_152_591_178,
+        0.015_055_144_896_577_895,
+        0.751_874_289_958_000_8,
+    ],
+];
+
+/// The color spaces that colors can be given in (or mixed in). All conversions go through sRGB, as
+/// that is the color space colors are rendered in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ColorSpace {
+    Srgb,
+    SrgbLinear,
+    DisplayP3,
+    XyzD50,
+    XyzD65,
+    Hsl,
+    Hwb,
+    Lab,
+    Lch,
+    Oklab,
+    Oklch,
+}
+
+impl ColorSpace {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "srgb" => Self::Srgb,
+            "srgb-linear" => Self::SrgbLinear,
+            "display-p3" => Self::DisplayP3,
+            "xyz-d50" => Self::XyzD50,
+            "xyz" | "xyz-d65" => Self::XyzD65,
+            "hsl" => Self::Hsl,
+            "hwb" => Self::Hwb,
+            "lab" => Self::Lab,
+            "lch" => Self::Lch,
+            "oklab" => Self::Oklab,
+            "oklch" => Self::Oklch,
+            _ => return None,
+        })
+    }
+
+    /// Returns the index of the hue channel for polar color spaces
+    pub(crate) fn hue_index(self) -> Option<usize> {
+        match self {
+            Self::Hsl | Self::Hwb => Some(0),
+            Self::Lch | Self::Oklch => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Converts channels in this color space to sRGB
+    pub(crate) fn to_srgb(self, c: Channels) -> Channels {
+        match self {
+            Self::Srgb => c,
+            Self::SrgbLinear => gamma_encode(c),
+            Self::DisplayP3 => xyz_d65_to_srgb(multiply(&LINEAR_P3_TO_XYZ, gamma_decode(c))),
+            Self::XyzD50 => xyz_d65_to_srgb(multiply(&D50_TO_D65, c)),
+            Self::XyzD65 => xyz_d65_to_srgb(c),
+            Self::Hsl => hsl_to_srgb(c),
+            Self::Hwb => hwb_to_srgb(c),
+            Self::Lab => xyz_d65_to_srgb(multiply(&D50_TO_D65, lab_to_xyz_d50(c))),
+            Self::Lch => Self::Lab.to_srgb(polar_to_rectangular(c)),
+            Self::Oklab => gamma_encode(oklab_to_linear_srgb(c)),
+            Self::Oklch => Self::Oklab.to_srgb(polar_to_rectangular(c)),
+        }
+    }
+
+    /// Converts sRGB channels into channels of this color space
+    pub(crate) fn srgb_into(self, c: Channels) -> Channels {
+        match self {
+            Self::Srgb => c,
+            Self::SrgbLinear => gamma_decode(c),
+            Self::DisplayP3 => gamma_encode(multiply(&invert(&LINEAR_P3_TO_XYZ), srgb_to_xyz_d65(c))),
+            Self::XyzD50 => multiply(&D65_TO_D50, srgb_to_xyz_d65(c)),
+            Self::XyzD65 => srgb_to_xyz_d65(c),
+            Self::Hsl => srgb_to_hsl(c),
+            Self::Hwb => srgb_to_hwb(c),
+            Self::Lab => xyz_d50_to_lab(multiply(&D65_TO_D50, srgb_to_xyz_d65(c))),
+            Self::Lch => rectangular_to_polar(Self::Lab.srgb_into(c)),
+            Self::Oklab => linear_srgb_to_oklab(gamma_decode(c)),
+            Self::Oklch => rectangular_to_polar(Self::Oklab.srgb_into(c)),
+        }
+    }
+}
+
+fn multiply(m: &[Channels; 3], c: Channels) -> Channels {
+    [0, 1, 2].map(|row| m[row][0] * c[0] + m[row][1] * c[1] + m[row][2] * c[2])
+}
+
+/// Inverts a 3x3 matrix
+fn invert(m: &[Channels; 3]) -> [Channels; 3] {
+    let [[a, b, c], [d, e, f], [g, h, i]] = *m;
+    let det = a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g);
+    [
+        [(e * i - f * h) / det, (c * h - b * i) / det, (b * f - c * e) / det],
+        [(f * g - d * i) / det, (a * i - c * g) / det, (c * d - a * f) / det],
+        [(d * h - e * g) / det, (b * g - a * h) / det, (a * e - b * d) / det],
+    ]
+}
+
+/// The sRGB transfer function, from linear light to gamma encoded
+fn gamma_encode(c: Channels) -> Channels {
+    c.map(|v| {
+        let abs = v.abs();
+        if abs > 0.003_130_8 {
+            v.signum() * (1.055 * abs.powf(1.0 / 2.4) - 0.055)
+        } else {
+            12.92 * v
+        }
+    })
+}
+
+/// The inverse sRGB transfer function, from gamma encoded to linear light
+fn gamma_decode(c: Channels) -> Channels {
+    c.map(|v| {
+        let abs = v.abs();
+        if abs <= 0.04045 {
+            v / 12.92
+        } else {
+            v.signum() * ((abs + 0.055) / 1.055).powf(2.4)
+        }
+    })
+}
+
+fn srgb_to_xyz_d65(c: Channels) -> Channels {
+    multiply(&LINEAR_SRGB_TO_XYZ, gamma_decode(c))
+}
+
+fn xyz_d65_to_srgb(c: Channels) -> Channels {
+    gamma_encode(multiply(&XYZ_TO_LINEAR_SRGB, c))
+}
+
+/// Converts `[hue, saturation, lightness]` (hue in degrees, others 0..1) to sRGB
+fn hsl_to_srgb([h, s, l]: Channels) -> Channels {
+    let h = h.rem_euclid(360.0);
+    let f = |n: f64| {
+        let k = (n + h / 30.0) % 12.0;
+        let a = s * l.min(1.0 - l);
+        l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [f(0.0), f(8.0), f(4.0)]
+}
+
+fn srgb_to_hsl([r, g, b]: Channels) -> Channels {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+
+    let mut h = 0.0;
+    let mut s = 0.0;
+    if d != 0.0 {
+        s = if l == 0.0 || l == 1.0 {
+            0.0
+        } else {
+            (max - l) / l.min(1.0 - l)
+        };
+        h = if max == r {
+            (g - b) / d + if g < b { 6.0 } else { 0.0 }
+        } else if max == g {
+            (b - r) / d + 2.0
+        } else {
+            (r - g) / d + 4.0
+        };
+        h *= 60.0;
+    }
+
+    [h, s, l]
+}
+
+/// Converts `[hue, whiteness, blackness]` (hue in degrees, others 0..1) to sRGB
+fn hwb_to_srgb([h, w, b]: Channels) -> Channels {
+    if w + b >= 1.0 {
+        let gray = w / (w + b);
+        return [gray; 3];
+    }
+    hsl_to_srgb([h, 1.0, 0.5]).map(|v| v * (1.0 - w - b) + w)
+}
+
+fn srgb_to_hwb(c: Channels) -> Channels {
+    let [h, _, _] = srgb_to_hsl(c);
+    let w = c[0].min(c[1]).min(c[2]);
+    let b = 1.0 - c[0].max(c[1]).max(c[2]);
+    [h, w, b]
+}
+
+fn lab_to_xyz_d50([l, a, b]: Channels) -> Channels {
+    let f1 = (l + 16.0) / 116.0;
+    let f0 = a / 500.0 + f1;
+    let f2 = f1 - b / 200.0;
+
+    let x = if f0.powi(3) > LAB_EPSILON {
+        f0.powi(3)
+    } else {
+        (116.0 * f0 - 16.0) / LAB_KAPPA
+    };
+    let y = if l > LAB_KAPPA * LAB_EPSILON {
+        f1.powi(3)
+    } else {
+        l / LAB_KAPPA
+    };
+    let z = if f2.powi(3) > LAB_EPSILON {
+        f2.powi(3)
+    } else {
+        (116.0 * f2 - 16.0) / LAB_KAPPA
+    };
+
+    [x * D50[0], y * D50[1], z * D50[2]]
+}
+
+fn xyz_d50_to_lab(c: Channels) -> Channels {
+    let f = |i: usize| {
+        let v = c[i] / D50[i];
+        if v > LAB_EPSILON {
+            v.cbrt()
+        } else {
+            (LAB_KAPPA * v + 16.0) / 116.0
+        }
+    };
+    let (f0, f1, f2) = (f(0), f(1), f(2));
+    [116.0 * f1 - 16.0, 500.0 * (f0 - f1), 200.0 * (f1 - f2)]
+}
+
+fn oklab_to_linear_srgb([l, a, b]: Channels) -> Channels {
+    let l_ = (l + 0.396_337_777_4 * a + 0.215_803_757_3 * b).powi(3);
+    let m_ = (l - 0.105_561_345_8 * a - 0.063_854_172_8 * b).powi(3);
+    let s_ = (l - 0.089_484_177_5 * a - 1.291_485_548 * b).powi(3);
+
+    [
+        4.076_741_662_1 * l_ - 3.307_711_591_3 * m_ + 0.230_969_929_2 * s_,
+        -1.268_438_004_6 * l_ + 2.609_757_401_1 * m_ - 0.341_319_396_5 * s_,
+        -0.004_196_086_3 * l_ - 0.703_418_614_7 * m_ + 1.707_614_701 * s_,
+    ]
+}
+
+fn linear_srgb_to_oklab([r, g, b]: Channels) -> Channels {
+    let l = (0.412_221_470_8 * r + 0.536_332_536_3 * g + 0.051_445_992_9 * b).cbrt();
+    let m = (0.211_903_498_2 * r + 0.680_699_545_1 * g + 0.107_396_956_6 * b).cbrt();
+    let s = (0.088_302_461_9 * r + 0.281_718_837_6 * g + 0.629_978_700_5 * b).cbrt();
+
+    [
+        0.210_454_255_3 * l + 0.793_617_785 * m - 0.004_072_046_8 * s,
+        1.977_998_495_1 * l - 2.428_592_205 * m + 0.450_593_709_9 * s,
+        0.025_904_037_1 * l + 0.782_771_766_2 * m - 0.808_675_766 * s,
+    ]
+}
+
+/// Converts `[lightness, chroma, hue]` into `[lightness, a, b]`
+fn polar_to_rectangular([l, c, h]: Channels) -> Channels {
+    let h = h.to_radians();
+    [l, c * h.cos(), c * h.sin()]
+}
+
+fn rectangular_to_polar([l, a, b]: Channels) -> Channels {
+    let h = b.atan2(a).to_degrees().rem_euclid(360.0);
+    [l, (a * a + b * b).sqrt(), h]
+}
@@ -0,0 +1,7 @@
+pub mod cascade;
+pub mod computed;
+pub mod logical;
+pub mod property_definitions;
+pub mod selector;
+pub mod styling;
+pub mod variables;
@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use crate::{
+    css3::{
+        matcher::{
+            selector::match_selector,
+            styling::{CssProperties, CssProperty, DeclarationProperty},
+        },
+        media::MediaQueryList,
+        stylesheet::{CssStylesheet, CssValue, Specificity},
+    },
+    interface::{
+        config::HasDocument,
+        css3::{CssOrigin, MediaEnvironment},
+    },
+    shared::node::NodeId,
+};
+
+/// Precedence of the cascade layers of all stylesheets, per origin (CSS Cascade 5, 6.4).
+///
+/// Layers are ordered by their first appearance. Sub-layers come before their parent layer, and
+/// unlayered declarations have the highest precedence for normal declarations. For important
+/// declarations the order is reversed.
+#[derive(Debug, Default)]
+pub struct CascadeLayers {
+    ranks: HashMap<CssOrigin, HashMap<String, usize>>,
+}
+
+/// Tree of layer names, used to find the precedence of nested layers
+#[derive(Default)]
+struct LayerTree {
+    children: Vec<(String, LayerTree)>,
+}
+
+impl LayerTree {
+    fn insert(&mut self, path: &[&str]) {
+        let Some((first, rest)) = path.split_first() else {
+            return;
+        };
+
+        let idx = match self.children.iter().position(|(name, _)| name == first) {
+            Some(idx) => idx,
+            None => {
+                self.children.push((first.to_string(), LayerTree::default()));
+                self.children.len() - 1
+            }
+        };
+
+        self.children[idx].1.insert(rest);
+    }
+
+    /// Assigns ranks in post order: sub-layers before their parent layer
+    fn assign_ranks(&self, prefix: &str, ranks: &mut HashMap<String, usize>) {
+        for (name, subtree) in &self.children {
+            let full_name = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{prefix}.{name}")
+            };
+
+            subtree.assign_ranks(&full_name, ranks);
+
+            let rank = ranks.len();
+            ranks.insert(full_name, rank);
+        }
+    }
+}
+
+impl CascadeLayers {
+    pub fn new(sheets: &[CssStylesheet]) -> Self {
+        let mut trees: HashMap<CssOrigin, LayerTree> = HashMap::new();
+
+        for sheet in sheets {
+            let tree = trees.entry(sheet.origin).or_default();
+            for layer in &sheet.layers {
+                tree.insert(&layer.split('.').collect::<Vec<_>>());
+            }
+        }
+
+        let mut ranks = HashMap::new();
+        for (origin, tree) in trees {
+            let mut origin_ranks = HashMap::new();
+            tree.assign_ranks("", &mut origin_ranks);
+            ranks.insert(origin, origin_ranks);
+        }
+
+        Self { ranks }
+    }
+
+    /// Returns the precedence of the given layer for a declaration. Higher values win.
+    pub fn rank(&self, origin: CssOrigin, layer: Option<&str>, important: bool) -> usize {
+        let Some(origin_ranks) = self.ranks.get(&origin) else {
+            return 0;
+        };
+
+        let unlayered = origin_ranks.len();
+        let rank = layer.and_then(|l| origin_ranks.get(l).copied()).unwrap_or(unlayered);
+
+        if important {
+            unlayered - rank
+        } else {
+            rank
+        }
+    }
+}
+
+/// Media query lists of all rules inside `@media`. The cascade keeps the declarations of these
+/// rules together with the index of their conditions, so styles can be recomputed for another
+/// media environment without matching selectors again.
+#[derive(Debug, Default)]
+pub struct MediaConditions {
+    lists: Vec<Vec<MediaQueryList>>,
+    rules: HashMap<(usize, usize), usize>,
+}
+
+impl MediaConditions {
+    pub fn new(sheets: &[CssStylesheet]) -> Self {
+        let mut conditions = Self::default();
+
+        for (sheet_idx, sheet) in sheets.iter().enumerate() {
+            for (rule_idx, rule) in sheet.rules.iter().enumerate() {
+                if rule.media.is_empty() {
+                    continue;
+                }
+
+                let idx = match conditions.lists.iter().position(|media| *media == rule.media) {
+                    Some(idx) => idx,
+                    None => {
+                        conditions.lists.push(rule.media.clone());
+                        conditions.lists.len() - 1
+                    }
+                };
+                conditions.rules.insert((sheet_idx, rule_idx), idx);
+            }
+        }
+
+        conditions
+    }
+
+    /// Returns the index of the conditions of the given rule, if it is inside `@media`
+    pub fn index(&self, sheet: usize, rule: usize) -> Option<usize> {
+        self.rules.get(&(sheet, rule)).copied()
+    }
+
+    /// Evaluates all conditions. A rule applies when all its enclosing `@media` rules match.
+    pub fn evaluate(&self, env: &MediaEnvironment) -> Vec<bool> {
+        self.lists
+            .iter()
+            .map(|media| media.iter().all(|list| list.matches(env)))
+            .collect()
+    }
+}
+
+/// The selectors of all rules that target a pseudo element, by the name of the pseudo element. Only
+/// the elements that match one of them are run through the cascade for the pseudo element.
+#[derive(Debug, Default)]
+pub struct PseudoElementSelectors {
+    selectors: HashMap<String, Vec<(usize, usize, usize)>>,
+}
+
+impl PseudoElementSelectors {
+    pub fn new(sheets: &[CssStylesheet]) -> Self {
+        let mut selectors: HashMap<String, Vec<_>> = HashMap::new();
+
+        for (sheet_idx, sheet) in sheets.iter().enumerate() {
+            // Declarations of a `style` attribute never apply to pseudo elements
+            if sheet.element.is_some() {
+                continue;
+            }
+            for (rule_idx, rule) in sheet.rules.iter().enumerate() {
+                for (selector_idx, selector) in rule.selectors.iter().enumerate() {
+                    if let Some(name) = selector.pseudo_element() {
+                        selectors
+                            .entry(name.to_string())
+                            .or_default()
+                            .push((sheet_idx, rule_idx, selector_idx));
+                    }
+                }
+            }
+        }
+
+        Self { selectors }
+    }
+
+    /// Returns true when any selector for the given pseudo element matches the node
+    pub fn matches<C: HasDocument>(
+        &self,
+        doc: &C::Document,
+        sheets: &[CssStylesheet],
+        node_id: NodeId,
+        pseudo_element: &str,
+    ) -> bool {
+        let Some(selectors) = self.selectors.get(pseudo_element) else {
+            return false;
+        };
+
+        selectors.iter().any(|&(sheet, rule, selector)| {
+            match_selector::<C>(doc, node_id, &sheets[sheet].rules[rule].selectors[selector])
+        })
+    }
+}
+
+/// Runs the cascade for a single node (or one of its pseudo elements) and returns all declared
+/// properties with their winning value.
+pub fn cascade_node<C: HasDocument>(
+    doc: &C::Document,
+    sheets: &[CssStylesheet],
+    layers: &CascadeLayers,
+    media: &MediaConditions,
+    node_id: NodeId,
+    pseudo_element: Option<&str>,
+) -> CssProperties {
+    let mut properties = CssProperties::new();
+    let mut order = 0;
+
+    for (sheet_idx, sheet) in sheets.iter().enumerate() {
+        for (rule_idx, rule) in sheet.rules.iter().enumerate() {
+            let specificity = match sheet.element {
+                // Declarations of a `style` attribute only apply to their own element
+                Some(element) => (element == node_id && pseudo_element.is_none()).then(Specificity::default),
+                None => rule
+                    .selectors
+                    .iter()
+                    .filter(|selector| selector.pseudo_element() == pseudo_element)
+                    .filter(|selector| match_selector::<C>(doc, node_id, selector))
+                    .map(|selector| selector.specificity())
+                    .max(),
+            };
+
+            let Some(specificity) = specificity else {
+                order += rule.declarations.len();
+                continue;
+            };
+
+            for declaration in &rule.declarations {
+                order += 1;
+
+                let declared = DeclarationProperty {
+                    value: CssValue::from_list(declaration.value.clone()),
+                    origin: sheet.origin,
+                    important: declaration.important,
+                    location: declaration.location,
+                    specificity,
+                    layer: layers.rank(sheet.origin, rule.layer.as_deref(), declaration.important),
+                    order,
+                    media: media.index(sheet_idx, rule_idx),
+                    element_attached: sheet.element.is_some(),
+                };
+
+                properties
+                    .properties
+                    .entry(declaration.property.clone())
+                    .or_insert_with(|| CssProperty::new(&declaration.property))
+                    .declared
+                    .push(declared);
+            }
+        }
+    }
+
+    for property in properties.properties.values_mut() {
+        property.declared.sort_by(|a, b| a.cascade_cmp(b));
+        property.cascaded = find_cascaded_value(&property.declared);
+    }
+
+    properties
+}
+
+/// Drops the declarations of `@media` rules that do not match, given the result of
+/// `MediaConditions::evaluate`, and runs the cascade again
+pub fn apply_media(properties: &mut CssProperties, active: &[bool]) {
+    properties.properties.retain(|_, property| {
+        property.declared.retain(|declaration| {
+            declaration
+                .media
+                .is_none_or(|idx| active.get(idx).copied().unwrap_or(false))
+        });
+        property.cascaded = find_cascaded_value(&property.declared);
+        !property.declared.is_empty()
+    });
+}
+
+/// Finds the winning declaration from the sorted list of declarations, taking `revert` and
+/// `revert-layer` into account. Returns None when all declarations are reverted.
+pub(crate) fn find_cascaded_value(declared: &[DeclarationProperty]) -> Option<DeclarationProperty> {
+    // Reverted origins: any declaration from this origin level (or above) is skipped
+    let mut max_origin_level = u8::MAX;
+    // Reverted layer: any declaration in the same origin/importance and layer (or above) is skipped
+    let mut reverted_layer: Option<(u8, usize)> = None;
+
+    for declaration in declared.iter().rev() {
+        if origin_level(declaration.origin) >= max_origin_level {
+            continue;
+        }
+        if let Some((precedence, layer)) = reverted_layer {
+            if declaration.origin_precedence() == precedence && declaration.layer >= layer {
+                continue;
+            }
+        }
+
+        match declaration.value {
+            CssValue::Revert => max_origin_level = origin_level(declaration.origin),
+            CssValue::RevertLayer => reverted_layer = Some((declaration.origin_precedence(), declaration.layer)),
+            _ => return Some(declaration.clone()),
+        }
+    }
+
+    None
+}
+
+/// Level of the origin used for rolling back with `revert`
+fn origin_level(origin: CssOrigin) -> u8 {
+    match origin {
+        CssOrigin::UserAgent => 0,
+        CssOrigin::User => 1,
+        CssOrigin::Author => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interface::css3::{CssOrigin, CssProperty, CssPropertyMap},
+        testing::TestDocument,
+    };
+
+    const RED: (u8, u8, u8, u8) = (255, 0, 0, 255);
+    const GREEN: (u8, u8, u8, u8) = (0, 128, 0, 255);
+
+    /// Returns the computed color of the element with id `t` in `<div id=t class="a b">`, styled by
+    /// the given stylesheets in order
+    fn color(sheets: &[(CssOrigin, &str)]) -> (u8, u8, u8, u8) {
+        let doc = TestDocument::parse(r#"<div id="t" class="a b"></div>"#);
+        for (origin, css) in sheets {
+            doc.add_css(css, *origin);
+        }
+        doc.style("t").get("color").and_then(|color| color.as_color()).unwrap()
+    }
+
+    fn author(css: &str) -> (u8, u8, u8, u8) {
+        color(&[(CssOrigin::Author, css)])
+    }
+
+    #[test]
+    fn specificity_and_source_order() {
+        assert_eq!(author("#t { color: green } div { color: red }"), GREEN);
+        assert_eq!(author(".a.b { color: green } .a { color: red }"), GREEN);
+        assert_eq!(author("div.a { color: green } .b { color: red }"), GREEN);
+        // The most specific selector of a list counts
+        assert_eq!(author("p, #t { color: green } .a.b { color: red }"), GREEN);
+        assert_eq!(author(".a { color: red } .b { color: green }"), GREEN);
+    }
+
+    #[test]
+    fn importance() {
+        assert_eq!(author("div { color: green !important } #t { color: red }"), GREEN);
+        assert_eq!(
+            author("#t { color: red !important } div { color: green !important }"),
+            RED
+        );
+    }
+
+    #[test]
+    fn origins() {
+        let ua = (CssOrigin::UserAgent, "#t { color: red }");
+        assert_eq!(color(&[ua, (CssOrigin::Author, "div { color: green }")]), GREEN);
+
+        let ua = (CssOrigin::UserAgent, "div { color: green !important }");
+        assert_eq!(color(&[ua, (CssOrigin::Author, "#t { color: red !important }")]), GREEN);
+
+        // The origin wins regardless of the order of the stylesheets
+        let ua = (CssOrigin::UserAgent, "#t { color: red }");
+        assert_eq!(color(&[(CssOrigin::Author, "div { color: green }"), ua]), GREEN);
+    }
+
+    #[test]
+    fn user_origin() {
+        let ua = (CssOrigin::UserAgent, "#t { color: red }");
+        assert_eq!(color(&[ua, (CssOrigin::User, "div { color: green }")]), GREEN);
+
+        let user = (CssOrigin::User, "#t { color: red }");
+        assert_eq!(color(&[user, (CssOrigin::Author, "div { color: green }")]), GREEN);
+
+        // Important user declarations win over important author declarations...
+        let user = (CssOrigin::User, "div { color: green !important }");
+        assert_eq!(color(&[user, (CssOrigin::Author, "#t { color: red !important }")]), GREEN);
+
+        // ...but lose to important user agent declarations
+        let ua = (CssOrigin::UserAgent, "div { color: green !important }");
+        assert_eq!(color(&[ua, (CssOrigin::User, "#t { color: red !important }")]), GREEN);
+    }
+
+    #[test]
+    fn layers() {
+        // Unlayered declarations win over layered ones
+        assert_eq!(author("div { color: green } @layer base { #t { color: red } }"), GREEN);
+        // Layers are ordered by their first appearance
+        assert_eq!(
+            author("@layer low, high; @layer high { div { color: green } } @layer low { #t { color: red } }"),
+            GREEN
+        );
+        // Sub-layers come before their parent layer
+        assert_eq!(
+            author("@layer a { div { color: green } @layer b { #t { color: red } } }"),
+            GREEN
+        );
+        // The order is reversed for important declarations
+        assert_eq!(
+            author("#t { color: red !important } @layer base { div { color: green !important } }"),
+            GREEN
+        );
+        assert_eq!(
+            author("@layer low, high; @layer low { div { color: green !important } } @layer high { div { color: red !important } }"),
+            GREEN
+        );
+        // Anonymous layers are distinct layers
+        assert_eq!(
+            author("@layer { #t { color: red } } @layer { div { color: green } }"),
+            GREEN
+        );
+    }
+
+    #[test]
+    fn revert() {
+        let ua = (CssOrigin::UserAgent, "div { color: green }");
+        assert_eq!(color(&[ua, (CssOrigin::Author, "#t { color: revert }")]), GREEN);
+
+        assert_eq!(
+            author("@layer a, b; @layer a { div { color: green } } @layer b { #t { color: revert-layer } }"),
+            GREEN
+        );
+    }
+}
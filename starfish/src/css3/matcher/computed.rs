@@ -0,0 +1,623 @@
+use crate::{
+    css3::{
+        calc::{compute_math_value, is_math_function, CalcContext, CalcNode},
+        colors::{resolve_color, RgbaColor},
+        matcher::{
+            logical::{element_writing_mode, map_logical_properties, sync_logical_properties},
+            property_definitions::{get_css_definition, get_css_definitions},
+            styling::{CssProperties, CssProperty},
+            variables::{compute_custom_properties, substitute_variables, PropertyRegistry},
+        },
+        stylesheet::CssValue,
+    },
+    interface::css3::MediaEnvironment,
+    shared::types::Size,
+};
+
+/// Font size of the `medium` keyword (and the initial font size)
+pub const MEDIUM_FONT_SIZE: f32 = 16.0;
+
+/// Absolute font size keywords and their size in pixels
+const FONT_SIZE_KEYWORDS: &[(&str, f32)] = &[
+    ("xx-small", 9.0),
+    ("x-small", 10.0),
+    ("small", 13.0),
+    ("medium", MEDIUM_FONT_SIZE),
+    ("large", 18.0),
+    ("x-large", 24.0),
+    ("xx-large", 32.0),
+    ("xxx-large", 48.0),
+];
+
+/// Ratio between two consecutive font sizes for `larger` and `smaller`
+const FONT_SIZE_RATIO: f32 = 1.2;
+
+/// Properties that must be computed before all others, as other values depend on them
+const PRIORITY_PROPERTIES: &[&str] = &["font-size", "color", "position", "float"];
+
+/// Information about the element's surroundings needed to compute values
+struct ComputeContext<'a> {
+    parent: Option<&'a CssProperties>,
+    root_font_size: f32,
+    is_root: bool,
+    /// Size of the viewport, which viewport units like `vw` refer to
+    viewport: Size<f32>,
+}
+
+/// Turns the cascaded values of an element into computed values (CSS Cascade 5, 4).
+///
+/// `parent` holds the computed values of the parent element and `root` the computed values of the
+/// root element. Both are None for the root element itself. All known properties are present in
+/// the result, even when they are not declared for the element. `registry` holds the custom
+/// properties registered with `@property`, and viewport units are resolved against the size of
+/// the viewport in `media`.
+pub fn compute_properties(
+    mut properties: CssProperties,
+    parent: Option<&CssProperties>,
+    root: Option<&CssProperties>,
+    registry: &PropertyRegistry,
+    media: &MediaEnvironment,
+) -> CssProperties {
+    let is_root = parent.is_none();
+
+    let mut ctx = ComputeContext {
+        parent,
+        root_font_size: MEDIUM_FONT_SIZE,
+        is_root,
+        viewport: Size::new(media.width, media.height),
+    };
+
+    if let Some(CssValue::Unit(size, _)) = root.and_then(|root| root.computed_value("font-size")) {
+        ctx.root_font_size = *size;
+    }
+
+    // Custom properties are needed first, as any other property can refer to them with `var()`
+    compute_custom_properties(&mut properties, parent, registry);
+    substitute_variables(&mut properties);
+
+    // Logical properties cascade together with the physical properties they map to
+    let writing_mode = element_writing_mode(&properties, parent);
+    map_logical_properties(&mut properties, &writing_mode);
+
+    for (name, definition) in get_css_definitions() {
+        if !definition.is_shorthand() && !properties.properties.contains_key(name) {
+            properties.properties.insert(name.clone(), CssProperty::new(name));
+        }
+    }
+
+    // Resolve the css-wide keywords and defaulting into specified values. Custom properties already
+    // have their value.
+    for property in properties.properties.values_mut() {
+        if !property.name.starts_with("--") {
+            property.specified = specified_value(property, &ctx);
+            property.computed = property.specified.clone();
+        }
+    }
+
+    // The root element resolves `rem` against its own font size
+    let font_size = resolve_font_size(&properties, &ctx);
+    if is_root {
+        ctx.root_font_size = font_size;
+    }
+    set_computed(&mut properties, "font-size", CssValue::Unit(font_size, "px".into()));
+
+    for name in PRIORITY_PROPERTIES.iter().skip(1) {
+        let value = compute_value(name, &properties, &ctx, font_size);
+        set_computed(&mut properties, name, value);
+    }
+
+    let names = properties
+        .properties
+        .keys()
+        .filter(|name| !PRIORITY_PROPERTIES.contains(&name.as_str()))
+        // Unregistered custom properties are token streams, which are not computed any further
+        .filter(|name| !name.starts_with("--") || registry.is_typed(name))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for name in names {
+        let value = compute_value(&name, &properties, &ctx, font_size);
+        set_computed(&mut properties, &name, value);
+    }
+
+    sync_logical_properties(&mut properties, &writing_mode);
+
+    properties
+}
+
+fn set_computed(properties: &mut CssProperties, name: &str, value: CssValue) {
+    if let Some(property) = properties.properties.get_mut(name) {
+        property.computed = value;
+    }
+}
+
+/// Finds the specified value of a property by resolving defaulting and the css-wide keywords
+fn specified_value(property: &CssProperty, ctx: &ComputeContext) -> CssValue {
+    let definition = get_css_definition(&property.name);
+    let inherited = definition.is_some_and(|def| def.inherited);
+
+    let inherit = || {
+        ctx.parent
+            .and_then(|parent| parent.computed_value(&property.name))
+            .filter(|value| **value != CssValue::None)
+            .cloned()
+    };
+    let initial = || {
+        definition
+            .map(|def| def.initial_value.clone())
+            .unwrap_or(CssValue::None)
+    };
+
+    match property.cascaded_value() {
+        Some(CssValue::Inherit) => inherit().unwrap_or_else(initial),
+        Some(CssValue::Initial) => initial(),
+        Some(value) if !matches!(value, CssValue::Unset | CssValue::Revert | CssValue::RevertLayer) => value.clone(),
+        // No cascaded value, `unset` and a `revert` that rolled back all origins
+        _ if inherited => inherit().unwrap_or_else(initial),
+        _ => initial(),
+    }
+}
+
+/// Computes the font size of the element in pixels
+fn resolve_font_size(properties: &CssProperties, ctx: &ComputeContext) -> f32 {
+    let parent_size = match ctx.parent.and_then(|parent| parent.computed_value("font-size")) {
+        Some(CssValue::Unit(size, _)) => *size,
+        _ => MEDIUM_FONT_SIZE,
+    };
+
+    let Some(value) = properties.computed_value("font-size") else {
+        return parent_size;
+    };
+
+    match value {
+        CssValue::Keyword(keyword) => {
+            let keyword = keyword.to_ascii_lowercase();
+            match keyword.as_str() {
+                "larger" => parent_size * FONT_SIZE_RATIO,
+                "smaller" => parent_size / FONT_SIZE_RATIO,
+                _ => FONT_SIZE_KEYWORDS
+                    .iter()
+                    .find(|(name, _)| *name == keyword)
+                    .map(|(_, size)| *size)
+                    .unwrap_or(parent_size),
+            }
+        }
+        CssValue::Percentage(pct) => parent_size * pct / 100.0,
+        // Percentages and font relative units refer to the parent font size
+        CssValue::Function(..) => CalcNode::parse(value)
+            .map(|node| {
+                node.simplify(&CalcContext {
+                    font_size: Some(parent_size),
+                    root_font_size: Some(ctx.root_font_size),
+                    percentage_basis: Some(parent_size),
+                    viewport: Some(ctx.viewport),
+                })
+            })
+            .and_then(|node| match node {
+                CalcNode::Dimension(px, unit) if unit == "px" => Some(px.max(0.0)),
+                _ => None,
+            })
+            .unwrap_or(parent_size),
+        // Font relative units refer to the font size of the parent element when used in font-size
+        CssValue::Unit(value, unit) => {
+            length_to_px(*value, unit, parent_size, ctx.root_font_size, Some(ctx.viewport)).unwrap_or(parent_size)
+        }
+        CssValue::Number(value) if *value == 0.0 => 0.0,
+        _ => parent_size,
+    }
+}
+
+/// Computes the value of a single property from its specified value
+fn compute_value(name: &str, properties: &CssProperties, ctx: &ComputeContext, font_size: f32) -> CssValue {
+    let Some(value) = properties.computed_value(name) else {
+        return CssValue::None;
+    };
+
+    match name {
+        "color" => {
+            // `currentcolor` in the color property itself means the inherited color
+            let inherited = ctx
+                .parent
+                .and_then(|parent| parent.computed_value("color"))
+                .and_then(|color| resolve_color(color, None))
+                .unwrap_or(RgbaColor::BLACK);
+            compute_color(value, inherited)
+        }
+        _ if name.ends_with("-color") => {
+            let color = properties
+                .computed_value("color")
+                .and_then(|color| resolve_color(color, None))
+                .unwrap_or(RgbaColor::BLACK);
+            compute_color(value, color)
+        }
+        "display" => compute_display(value, properties, ctx),
+        "float" if is_out_of_flow(properties) => CssValue::Keyword("none".into()),
+        "font-weight" => compute_font_weight(value, ctx),
+        "line-height" => match value {
+            CssValue::Percentage(pct) => CssValue::Unit(font_size * pct / 100.0, "px".into()),
+            CssValue::Unit(..) => compute_lengths(value, font_size, ctx),
+            // Percentages in line-height refer to the font size
+            CssValue::Function(..) if is_math_function(value) => CalcNode::parse(value)
+                .map(|node| {
+                    let ctx = CalcContext {
+                        font_size: Some(font_size),
+                        root_font_size: Some(ctx.root_font_size),
+                        percentage_basis: Some(font_size),
+                        viewport: Some(ctx.viewport),
+                    };
+                    node.simplify(&ctx).to_css_value()
+                })
+                .unwrap_or_else(|| value.clone()),
+            _ => value.clone(),
+        },
+        "opacity" => match compute_lengths(value, font_size, ctx) {
+            CssValue::Percentage(pct) => CssValue::Number((pct / 100.0).clamp(0.0, 1.0)),
+            CssValue::Number(n) => CssValue::Number(n.clamp(0.0, 1.0)),
+            value => value,
+        },
+        "border-top-width" | "border-right-width" | "border-bottom-width" | "border-left-width" => {
+            let side = name.trim_end_matches("-width");
+            let style = properties.computed_value(&format!("{side}-style"));
+            if style.is_none_or(|style| style.is_keyword("none") || style.is_keyword("hidden")) {
+                return CssValue::Unit(0.0, "px".into());
+            }
+            compute_line_width(value, font_size, ctx)
+        }
+        _ => compute_lengths(value, font_size, ctx),
+    }
+}
+
+/// Colors compute to an `rgb()` value, with `currentcolor` resolved. Other values (like `auto`)
+/// are kept as-is.
+fn compute_color(value: &CssValue, current_color: RgbaColor) -> CssValue {
+    resolve_color(value, Some(current_color))
+        .map(|color| color.to_css_value())
+        .unwrap_or_else(|| value.clone())
+}
+
+/// Converts all lengths in the value to pixels and simplifies math functions. Percentages are kept,
+/// as they can only be resolved during layout.
+fn compute_lengths(value: &CssValue, font_size: f32, ctx: &ComputeContext) -> CssValue {
+    match value {
+        CssValue::Function(..) if is_math_function(value) => {
+            compute_math_value(value, font_size, ctx.root_font_size, ctx.viewport).unwrap_or_else(|| value.clone())
+        }
+        CssValue::Unit(v, unit) => match length_to_px(*v, unit, font_size, ctx.root_font_size, Some(ctx.viewport)) {
+            Some(px) => CssValue::Unit(px, "px".into()),
+            None => value.clone(),
+        },
+        CssValue::List(values) => CssValue::List(
+            values
+                .iter()
+                .map(|value| compute_lengths(value, font_size, ctx))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+/// Converts a length into pixels. Viewport units are resolved against `viewport`. Returns None for
+/// unknown units, and for viewport units without a viewport.
+pub fn length_to_px(
+    value: f32,
+    unit: &str,
+    font_size: f32,
+    root_font_size: f32,
+    viewport: Option<Size<f32>>,
+) -> Option<f32> {
+    if is_viewport_unit(unit) {
+        return viewport.and_then(|viewport| viewport_length(value, unit, viewport));
+    }
+
+    let px = match unit {
+        "px" => value,
+        "cm" => value * 96.0 / 2.54,
+        "mm" => value * 96.0 / 25.4,
+        "q" => value * 96.0 / 101.6,
+        "in" => value * 96.0,
+        "pt" => value * 96.0 / 72.0,
+        "pc" => value * 16.0,
+        "em" => value * font_size,
+        "rem" => value * root_font_size,
+        // Without font metrics, the x-height and the advance of "0" are approximated as 0.5em
+        "ex" | "ch" => value * font_size * 0.5,
+        "rex" | "rch" => value * root_font_size * 0.5,
+        _ => return None,
+    };
+
+    Some(px)
+}
+
+/// Returns true for the units that are relative to the size of the viewport, like `vw` and `svh`
+pub fn is_viewport_unit(unit: &str) -> bool {
+    viewport_length(0.0, unit, Size::new(0.0, 0.0)).is_some()
+}
+
+/// Converts a length in a viewport unit into pixels (CSS Values 4, 6.1.2). The small, large and
+/// dynamic viewport units are the same, as the viewport has no interface that expands or
+/// retracts. The inline and block axes are horizontal and vertical.
+fn viewport_length(value: f32, unit: &str, viewport: Size<f32>) -> Option<f32> {
+    let unit = unit
+        .strip_prefix(['s', 'l', 'd'])
+        .filter(|unit| unit.starts_with('v'))
+        .unwrap_or(unit);
+
+    let basis = match unit {
+        "vw" | "vi" => viewport.width,
+        "vh" | "vb" => viewport.height,
+        "vmin" => viewport.width.min(viewport.height),
+        "vmax" => viewport.width.max(viewport.height),
+        _ => return None,
+    };
+
+    Some(value * basis / 100.0)
+}
+
+/// Computes a border width (thin, medium, thick or a length)
+fn compute_line_width(value: &CssValue, font_size: f32, ctx: &ComputeContext) -> CssValue {
+    let px = match value.as_keyword().map(|k| k.to_ascii_lowercase()).as_deref() {
+        Some("thin") => 1.0,
+        Some("medium") => 3.0,
+        Some("thick") => 5.0,
+        _ => return compute_lengths(value, font_size, ctx),
+    };
+
+    CssValue::Unit(px, "px".into())
+}
+
+/// Computes the numeric font weight, with `bolder` and `lighter` relative to the parent
+fn compute_font_weight(value: &CssValue, ctx: &ComputeContext) -> CssValue {
+    let parent_weight = match ctx.parent.and_then(|parent| parent.computed_value("font-weight")) {
+        Some(CssValue::Number(weight)) => *weight,
+        _ => 400.0,
+    };
+
+    let weight = match value {
+        CssValue::Number(weight) => *weight,
+        CssValue::Keyword(k) => match k.to_ascii_lowercase().as_str() {
+            "normal" => 400.0,
+            "bold" => 700.0,
+            // CSS Fonts 4, 2.2.1: bolder and lighter weights
+            "bolder" if parent_weight < 350.0 => 400.0,
+            "bolder" if parent_weight < 550.0 => 700.0,
+            "bolder" => parent_weight.max(900.0),
+            "lighter" if parent_weight < 100.0 => parent_weight,
+            "lighter" if parent_weight < 550.0 => 100.0,
+            "lighter" if parent_weight < 750.0 => 400.0,
+            "lighter" => 700.0,
+            _ => return value.clone(),
+        },
+        _ => return value.clone(),
+    };
+
+    CssValue::Number(weight)
+}
+
+/// Replaces a multi-keyword display value by its short form, like `inline flow-root` by
+/// `inline-block` (CSS Display 3, 2.8). Values without a short form are kept as they are.
+fn display_short_form(value: &CssValue) -> CssValue {
+    let CssValue::List(values) = value else {
+        return match value.as_keyword() {
+            Some(keyword) => CssValue::Keyword(keyword.to_ascii_lowercase()),
+            None => value.clone(),
+        };
+    };
+
+    let mut outside = None;
+    let mut inside = None;
+    let mut list_item = false;
+    for item in values {
+        let Some(keyword) = item.as_keyword().map(str::to_ascii_lowercase) else {
+            return value.clone();
+        };
+        match keyword.as_str() {
+            "block" | "inline" | "run-in" => outside = Some(keyword),
+            "list-item" => list_item = true,
+            _ => inside = Some(keyword),
+        }
+    }
+
+    let inside = inside.unwrap_or_else(|| "flow".to_string());
+    let outside = outside.unwrap_or_else(|| if inside == "ruby" { "inline" } else { "block" }.to_string());
+
+    let short = match (outside.as_str(), inside.as_str(), list_item) {
+        ("block", "flow", true) => "list-item",
+        (_, _, true) => return value.clone(),
+        (outside, "flow", _) => outside,
+        ("block", "flow-root", _) => "flow-root",
+        ("inline", "flow-root", _) => "inline-block",
+        ("inline", "ruby", _) => "ruby",
+        ("block", inside, _) if inside != "ruby" => inside,
+        ("inline", "table", _) => "inline-table",
+        ("inline", "flex", _) => "inline-flex",
+        ("inline", "grid", _) => "inline-grid",
+        _ => return value.clone(),
+    };
+
+    CssValue::Keyword(short.to_string())
+}
+
+/// Returns true when the element is absolutely positioned
+fn is_out_of_flow(properties: &CssProperties) -> bool {
+    properties
+        .computed_value("position")
+        .is_some_and(|pos| pos.is_keyword("absolute") || pos.is_keyword("fixed"))
+}
+
+/// Computes the display value, blockifying the root element, floats and absolutely positioned
+/// elements (CSS Display 3, 2.7)
+fn compute_display(value: &CssValue, properties: &CssProperties, ctx: &ComputeContext) -> CssValue {
+    let value = &display_short_form(value);
+
+    let floated = properties
+        .computed_value("float")
+        .is_some_and(|float| !float.is_keyword("none"));
+
+    if !(ctx.is_root || floated || is_out_of_flow(properties)) {
+        return value.clone();
+    }
+
+    let Some(keyword) = value.as_keyword() else {
+        return value.clone();
+    };
+
+    let blockified = match keyword.to_ascii_lowercase().as_str() {
+        "inline" | "inline-block" | "run-in" | "table-row-group" | "table-column" | "table-column-group"
+        | "table-header-group" | "table-footer-group" | "table-row" | "table-cell" | "table-caption" => "block",
+        "contents" if ctx.is_root => "block",
+        "inline-flex" => "flex",
+        "inline-grid" => "grid",
+        "inline-table" => "table",
+        "inline-flow-root" => "flow-root",
+        _ => return value.clone(),
+    };
+
+    CssValue::Keyword(blockified.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interface::css3::{CssOrigin, CssProperty, CssPropertyMap, MediaEnvironment},
+        shared::geo::SizeU32,
+        testing::{TestConfig, TestDocument},
+        util::render_tree::RenderTree,
+    };
+
+    /// Builds `<div id=p><span id=c>` with the given author stylesheet
+    fn doc(css: &str) -> TestDocument {
+        let doc = TestDocument::parse(r#"<div id="p"><span id="c">text</span></div>"#);
+        doc.add_css(css, CssOrigin::Author);
+        doc
+    }
+
+    fn px(doc: &TestDocument, id: &str, property: &str) -> Option<f32> {
+        doc.style(id).get(property).and_then(|value| value.as_px())
+    }
+
+    fn keyword(doc: &TestDocument, id: &str, property: &str) -> Option<String> {
+        doc.style(id)
+            .get(property)
+            .and_then(|value| value.as_keyword().map(str::to_string))
+    }
+
+    #[test]
+    fn inheritance_and_initial_values() {
+        let d = doc("#p { color: rgb(0, 0, 255); border-top: 2px solid; visibility: hidden; z-index: 3 }");
+        // Inherited properties take the value of the parent, others their initial value
+        assert_eq!(d.style("c").get("color").unwrap().as_color(), Some((0, 0, 255, 255)));
+        assert_eq!(keyword(&d, "c", "visibility").as_deref(), Some("hidden"));
+        assert_eq!(keyword(&d, "c", "z-index").as_deref(), Some("auto"));
+        assert_eq!(px(&d, "c", "border-top-width"), Some(0.0));
+        assert_eq!(keyword(&d, "c", "display").as_deref(), Some("inline"));
+        assert_eq!(keyword(&d, "c", "position").as_deref(), Some("static"));
+
+        // A border without a style has no width
+        let d = doc("#p { border-top-width: 4px }");
+        assert_eq!(px(&d, "p", "border-top-width"), Some(0.0));
+    }
+
+    #[test]
+    fn css_wide_keywords() {
+        let d = doc("#p { z-index: 3; color: rgb(0, 0, 255) } #c { z-index: inherit; color: initial }");
+        assert_eq!(d.style("c").get("z-index").unwrap().as_number(), Some(3.0));
+        assert_eq!(d.style("c").get("color").unwrap().as_color(), Some((0, 0, 0, 255)));
+
+        let d = doc("#p { z-index: 3; visibility: hidden } #c { z-index: unset; visibility: unset }");
+        assert_eq!(keyword(&d, "c", "z-index").as_deref(), Some("auto"));
+        assert_eq!(keyword(&d, "c", "visibility").as_deref(), Some("hidden"));
+
+        // `revert` rolls back to the user agent origin
+        let d = doc("");
+        d.add_css("span { display: block }", CssOrigin::UserAgent);
+        d.add_css(
+            "#c { display: inline-flex } span { display: revert }",
+            CssOrigin::Author,
+        );
+        assert_eq!(keyword(&d, "c", "display").as_deref(), Some("inline-flex"));
+        let d = doc("#c { display: revert }");
+        d.add_css("span { display: block }", CssOrigin::UserAgent);
+        assert_eq!(keyword(&d, "c", "display").as_deref(), Some("block"));
+    }
+
+    #[test]
+    fn relative_lengths() {
+        let d = doc("html { font-size: 10px } #p { font-size: 20px; margin-left: 2em } #c { padding-left: 1.5rem; margin-left: 2ex; width: 3ch; font-size: 50% }");
+        assert_eq!(px(&d, "p", "margin-left"), Some(40.0));
+        assert_eq!(px(&d, "c", "font-size"), Some(10.0));
+        assert_eq!(px(&d, "c", "padding-left"), Some(15.0));
+        // The x-height and the advance of "0" are approximated as half the font size
+        assert_eq!(px(&d, "c", "margin-left"), Some(10.0));
+        assert_eq!(px(&d, "c", "width"), Some(15.0));
+
+        // Em in font-size refers to the parent font size
+        let d = doc("#p { font-size: 20px } #c { font-size: 1.5em; line-height: 150% }");
+        assert_eq!(px(&d, "c", "font-size"), Some(30.0));
+        assert_eq!(px(&d, "c", "line-height"), Some(45.0));
+
+        let d = doc("#p { font-size: large } #c { font-size: larger }");
+        assert_eq!(px(&d, "p", "font-size"), Some(18.0));
+        assert_eq!(px(&d, "c", "font-size"), Some(18.0 * 1.2));
+
+        let d = doc("#p { margin-left: 1in; margin-right: 12pt; padding-left: 2.54cm }");
+        assert_eq!(px(&d, "p", "margin-left"), Some(96.0));
+        assert_eq!(px(&d, "p", "margin-right"), Some(16.0));
+        assert_eq!(px(&d, "p", "padding-left"), Some(96.0));
+    }
+
+    #[test]
+    fn percentages_are_kept_for_layout() {
+        let d = doc("#p { width: 50%; margin-left: 10% }");
+        assert_eq!(d.style("p").get("width").unwrap().as_percentage(), Some(50.0));
+        assert_eq!(d.style("p").get("margin-left").unwrap().as_percentage(), Some(10.0));
+    }
+
+    #[test]
+    fn viewport_units() {
+        let d = doc("#p { width: 50vw; height: 25vh; margin-left: 10vmin; margin-right: 10vmax } #c { font-size: 2svw; padding-left: calc(10vh + 1px) }");
+        let media = MediaEnvironment::from_size(SizeU32::new(800, 600));
+        let styles = d.styles_for(&media);
+        let style = |id: &str, property: &str| styles[&d.id(id)].get(property).and_then(|value| value.as_px());
+
+        assert_eq!(style("p", "width"), Some(400.0));
+        assert_eq!(style("p", "height"), Some(150.0));
+        assert_eq!(style("p", "margin-left"), Some(60.0));
+        assert_eq!(style("p", "margin-right"), Some(80.0));
+        assert_eq!(style("c", "font-size"), Some(16.0));
+        assert_eq!(style("c", "padding-left"), Some(61.0));
+    }
+
+    #[test]
+    fn resizing_recomputes_viewport_units() {
+        let d = doc("#p { width: 50vw }");
+        let mut tree = d.render_tree();
+        let width = |tree: &RenderTree<TestConfig>| {
+            let id = tree.node_for_dom(d.id("p")).unwrap();
+            tree.nodes[&id].properties.get("width").and_then(|value| value.as_px())
+        };
+
+        assert!(tree.set_media(MediaEnvironment::from_size(SizeU32::new(800, 600))));
+        assert_eq!(width(&tree), Some(400.0));
+        assert!(tree.set_media(MediaEnvironment::from_size(SizeU32::new(600, 600))));
+        assert_eq!(width(&tree), Some(300.0));
+
+        // Styles without viewport units or media queries do not change
+        let d = doc("#p { width: 50% }");
+        let mut tree = d.render_tree();
+        assert!(!tree.set_media(MediaEnvironment::from_size(SizeU32::new(800, 600))));
+    }
+
+    #[test]
+    fn blockification() {
+        let d = doc("#c { float: left }");
+        assert_eq!(keyword(&d, "c", "display").as_deref(), Some("block"));
+        let d = doc("#c { position: absolute; display: inline-flex; float: left }");
+        assert_eq!(keyword(&d, "c", "display").as_deref(), Some("flex"));
+        // Floats do not apply to absolutely positioned elements
+        assert_eq!(keyword(&d, "c", "float").as_deref(), Some("none"));
+
+        let d = TestDocument::parse(r#"<html id="root"><body></body></html>"#);
+        d.add_css("html { display: inline }", CssOrigin::Author);
+        assert_eq!(keyword(&d, "root", "display").as_deref(), Some("block"));
+    }
+}
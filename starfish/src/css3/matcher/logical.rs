@@ -0,0 +1,297 @@
+use crate::css3::{
+    matcher::{
+        cascade::find_cascaded_value,
+        property_definitions::get_css_definitions,
+        styling::{CssProperties, CssProperty},
+    },
+    stylesheet::CssValue,
+};
+
+/// The physical sides and axes that the logical directions map to, based on the `writing-mode`
+/// and `direction` of an element (CSS Writing Modes 4, 6)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WritingMode {
+    pub block_start: &'static str,
+    pub block_end: &'static str,
+    pub inline_start: &'static str,
+    pub inline_end: &'static str,
+    /// True when the inline axis is vertical
+    pub vertical: bool,
+}
+
+impl Default for WritingMode {
+    fn default() -> Self {
+        Self::new("horizontal-tb", "ltr")
+    }
+}
+
+impl WritingMode {
+    pub fn new(writing_mode: &str, direction: &str) -> Self {
+        let rtl = direction.eq_ignore_ascii_case("rtl");
+
+        let (block_start, block_end, vertical) = match writing_mode.to_ascii_lowercase().as_str() {
+            "vertical-rl" | "sideways-rl" => ("right", "left", true),
+            "vertical-lr" | "sideways-lr" => ("left", "right", true),
+            _ => ("top", "bottom", false),
+        };
+
+        let (inline_start, inline_end) = match (writing_mode.eq_ignore_ascii_case("sideways-lr"), vertical, rtl) {
+            // sideways-lr is rotated counter-clockwise, so the inline axis runs from bottom to top
+            (true, _, false) => ("bottom", "top"),
+            (true, _, true) => ("top", "bottom"),
+            (false, true, false) => ("top", "bottom"),
+            (false, true, true) => ("bottom", "top"),
+            (false, false, false) => ("left", "right"),
+            (false, false, true) => ("right", "left"),
+        };
+
+        Self {
+            block_start,
+            block_end,
+            inline_start,
+            inline_end,
+            vertical,
+        }
+    }
+
+    /// Returns the physical property for a logical property, like `margin-top` for
+    /// `margin-block-start` in horizontal writing modes. Returns None for other properties.
+    pub fn physical_property(&self, name: &str) -> Option<String> {
+        let (width, height) = if self.vertical {
+            ("height", "width")
+        } else {
+            ("width", "height")
+        };
+
+        for prefix in ["", "min-", "max-"] {
+            if name.strip_prefix(prefix) == Some("inline-size") {
+                return Some(format!("{prefix}{width}"));
+            }
+            if name.strip_prefix(prefix) == Some("block-size") {
+                return Some(format!("{prefix}{height}"));
+            }
+        }
+
+        match name {
+            "overflow-inline" => return Some(if self.vertical { "overflow-y" } else { "overflow-x" }.to_string()),
+            "overflow-block" => return Some(if self.vertical { "overflow-x" } else { "overflow-y" }.to_string()),
+            _ => {}
+        }
+
+        let sides = [
+            ("block-start", self.block_start),
+            ("block-end", self.block_end),
+            ("inline-start", self.inline_start),
+            ("inline-end", self.inline_end),
+        ];
+        for (logical, physical) in sides {
+            if !name.contains(logical) {
+                continue;
+            }
+            // The physical insets have no prefix: `inset-block-start` maps to `top`
+            if let Some(rest) = name.strip_prefix("inset-") {
+                return (rest == logical).then(|| physical.to_string());
+            }
+            return Some(name.replacen(logical, physical, 1));
+        }
+
+        None
+    }
+}
+
+/// Determines the writing mode of an element from its own cascaded values, or the values
+/// inherited from its parent
+pub fn element_writing_mode(properties: &CssProperties, parent: Option<&CssProperties>) -> WritingMode {
+    let value = |name: &str| {
+        let value = match properties
+            .properties
+            .get(name)
+            .and_then(|property| property.cascaded_value())
+        {
+            Some(value) if !value.is_css_wide_keyword() => Some(value),
+            Some(CssValue::Initial) => None,
+            _ => parent.and_then(|parent| parent.computed_value(name)),
+        };
+        value
+            .and_then(|value| value.as_keyword())
+            .map(|value| value.to_string())
+    };
+
+    WritingMode::new(
+        &value("writing-mode").unwrap_or_else(|| "horizontal-tb".into()),
+        &value("direction").unwrap_or_else(|| "ltr".into()),
+    )
+}
+
+/// Merges the declarations of logical properties into their physical counterparts, so they cascade
+/// together as one property (CSS Logical 1, 4). The cascaded value of each physical property is
+/// found again after merging.
+pub fn map_logical_properties(properties: &mut CssProperties, mode: &WritingMode) {
+    let logical = properties
+        .properties
+        .keys()
+        .filter_map(|name| mode.physical_property(name).map(|physical| (name.clone(), physical)))
+        .collect::<Vec<_>>();
+
+    for (name, physical) in logical {
+        let Some(property) = properties.properties.remove(&name) else {
+            continue;
+        };
+
+        let target = properties
+            .properties
+            .entry(physical.clone())
+            .or_insert_with(|| CssProperty::new(&physical));
+        target.declared.extend(property.declared);
+        target.declared.sort_by(|a, b| a.cascade_cmp(b));
+        target.cascaded = find_cascaded_value(&target.declared);
+    }
+}
+
+/// Sets the computed value of all logical properties to the computed value of the physical
+/// property they map to
+pub fn sync_logical_properties(properties: &mut CssProperties, mode: &WritingMode) {
+    for name in get_css_definitions().keys() {
+        let Some(physical) = mode.physical_property(name) else {
+            continue;
+        };
+        let Some(value) = properties.computed_value(&physical).cloned() else {
+            continue;
+        };
+
+        properties
+            .properties
+            .entry(name.clone())
+            .or_insert_with(|| CssProperty::new(name))
+            .computed = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interface::css3::{CssOrigin, CssProperty as _, CssPropertyMap},
+        testing::TestDocument,
+    };
+
+    fn px(doc: &TestDocument, id: &str, property: &str) -> Option<f32> {
+        doc.style(id).get(property).and_then(|value| value.as_px())
+    }
+
+    #[test]
+    fn physical_properties() {
+        let mode = WritingMode::default();
+        assert_eq!(
+            mode.physical_property("margin-block-start").as_deref(),
+            Some("margin-top")
+        );
+        assert_eq!(
+            mode.physical_property("padding-inline-end").as_deref(),
+            Some("padding-right")
+        );
+        assert_eq!(
+            mode.physical_property("border-inline-start-width").as_deref(),
+            Some("border-left-width")
+        );
+        assert_eq!(mode.physical_property("inset-block-end").as_deref(), Some("bottom"));
+        assert_eq!(mode.physical_property("max-inline-size").as_deref(), Some("max-width"));
+        assert_eq!(mode.physical_property("block-size").as_deref(), Some("height"));
+        assert_eq!(mode.physical_property("margin-top"), None);
+        assert_eq!(mode.physical_property("inset-block"), None);
+
+        let rtl = WritingMode::new("horizontal-tb", "rtl");
+        assert_eq!(
+            rtl.physical_property("margin-inline-start").as_deref(),
+            Some("margin-right")
+        );
+        assert_eq!(rtl.physical_property("inset-inline-end").as_deref(), Some("left"));
+        assert_eq!(
+            rtl.physical_property("margin-block-start").as_deref(),
+            Some("margin-top")
+        );
+
+        let vertical = WritingMode::new("vertical-rl", "ltr");
+        assert_eq!(
+            vertical.physical_property("margin-block-start").as_deref(),
+            Some("margin-right")
+        );
+        assert_eq!(
+            vertical.physical_property("margin-inline-start").as_deref(),
+            Some("margin-top")
+        );
+        assert_eq!(vertical.physical_property("inline-size").as_deref(), Some("height"));
+        assert_eq!(
+            vertical.physical_property("overflow-block").as_deref(),
+            Some("overflow-x")
+        );
+
+        let vertical = WritingMode::new("vertical-lr", "rtl");
+        assert_eq!(
+            vertical.physical_property("padding-block-end").as_deref(),
+            Some("padding-right")
+        );
+        assert_eq!(
+            vertical.physical_property("padding-inline-start").as_deref(),
+            Some("padding-bottom")
+        );
+
+        let sideways = WritingMode::new("sideways-lr", "ltr");
+        assert_eq!(
+            sideways.physical_property("margin-inline-start").as_deref(),
+            Some("margin-bottom")
+        );
+    }
+
+    #[test]
+    fn logical_properties_cascade_with_physical_ones() {
+        let doc = TestDocument::parse(r#"<p id="a">a</p><p id="b">b</p><p id="c">c</p>"#);
+        doc.add_css(
+            "p { margin-block-start: 10px; margin-inline-start: 20px } #a { margin-left: 5px } \
+             #b { direction: rtl } #c { margin-top: 1px; margin-block-start: 30px }",
+            CssOrigin::Author,
+        );
+
+        // The later declaration wins, whether it is logical or physical
+        assert_eq!(px(&doc, "a", "margin-top"), Some(10.0));
+        assert_eq!(px(&doc, "a", "margin-left"), Some(5.0));
+        assert_eq!(px(&doc, "c", "margin-top"), Some(30.0));
+
+        assert_eq!(px(&doc, "b", "margin-right"), Some(20.0));
+        assert_eq!(px(&doc, "b", "margin-left"), Some(0.0));
+
+        // Logical properties compute to the value of their physical property
+        assert_eq!(px(&doc, "a", "margin-inline-start"), Some(5.0));
+        assert_eq!(px(&doc, "b", "margin-inline-end"), Some(0.0));
+    }
+
+    #[test]
+    fn dir_attribute() {
+        // The user agent stylesheet maps the `dir` attribute to `direction`, which author styles override
+        let doc = TestDocument::with_css(
+            r#"<ul id="u" dir="RTL"><li id="i">a</li></ul><div dir="rtl"><p id="p" class="l">b</p></div>"#,
+            ".l { direction: ltr; padding-inline-start: 10px }",
+        );
+
+        assert_eq!(px(&doc, "u", "padding-right"), Some(40.0));
+        assert_eq!(px(&doc, "u", "padding-left"), Some(0.0));
+        assert_eq!(
+            doc.style("i").get("direction").and_then(|value| value.as_keyword()),
+            Some("rtl")
+        );
+        assert_eq!(px(&doc, "p", "padding-left"), Some(10.0));
+    }
+
+    #[test]
+    fn writing_mode_is_inherited() {
+        let doc = TestDocument::parse(r#"<div id="p"><div id="c">text</div></div>"#);
+        doc.add_css(
+            "#p { writing-mode: vertical-rl } #c { padding-block-start: 4px; inline-size: 50px }",
+            CssOrigin::Author,
+        );
+
+        assert_eq!(px(&doc, "c", "padding-right"), Some(4.0));
+        assert_eq!(px(&doc, "c", "padding-top"), Some(0.0));
+        assert_eq!(px(&doc, "c", "height"), Some(50.0));
+    }
+}
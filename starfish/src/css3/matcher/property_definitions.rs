@@ -0,0 +1,381 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use crate::css3::{parse_value, stylesheet::CssValue, syntax::CssSyntax};
+
+/// Definition of a CSS property
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyDefinition {
+    /// Name of the property
+    pub name: String,
+    /// Syntax the value of the property must match
+    pub syntax: CssSyntax,
+    /// True when the property inherits its value from the parent element by default
+    pub inherited: bool,
+    /// The initial value of the property. Shorthands have no initial value of their own.
+    pub initial_value: CssValue,
+}
+
+impl PropertyDefinition {
+    pub fn is_shorthand(&self) -> bool {
+        self.initial_value == CssValue::None
+    }
+}
+
+/// All known properties with their syntax, whether they are inherited and their initial value.
+/// Shorthands have an empty initial value.
+const PROPERTIES: &[(&str, &str, bool, &str)] = &[
+    // display and positioning
+    ("display", "[ <display-outside> || <display-inside> ] | <display-listitem> | <display-internal> | <display-box> | <display-legacy>", false, "inline"),
+    ("position", "static | relative | absolute | sticky | fixed", false, "static"),
+    ("top", "auto | <length-percentage>", false, "auto"),
+    ("right", "auto | <length-percentage>", false, "auto"),
+    ("bottom", "auto | <length-percentage>", false, "auto"),
+    ("left", "auto | <length-percentage>", false, "auto"),
+    ("inset-block-start", "auto | <length-percentage>", false, "auto"),
+    ("inset-block-end", "auto | <length-percentage>", false, "auto"),
+    ("inset-inline-start", "auto | <length-percentage>", false, "auto"),
+    ("inset-inline-end", "auto | <length-percentage>", false, "auto"),
+    ("inset", "<'top'>{1,4}", false, ""),
+    ("inset-block", "<'top'>{1,2}", false, ""),
+    ("inset-inline", "<'top'>{1,2}", false, ""),
+    ("float", "left | right | none | inline-start | inline-end", false, "none"),
+    ("clear", "none | left | right | both | inline-start | inline-end", false, "none"),
+    ("z-index", "auto | <integer>", false, "auto"),
+    ("overflow-x", "<overflow-keyword>", false, "visible"),
+    ("overflow-y", "<overflow-keyword>", false, "visible"),
+    ("overflow-block", "<overflow-keyword>", false, "visible"),
+    ("overflow-inline", "<overflow-keyword>", false, "visible"),
+    ("overflow", "<overflow-keyword>{1,2}", false, ""),
+    ("overflow-anchor", "auto | none", false, "auto"),
+    ("overflow-clip-margin", "<visual-box> || <length [0,∞]>", false, "0px"),
+    ("visibility", "visible | hidden | collapse", true, "visible"),
+    ("opacity", "<alpha-value>", false, "1"),
+    ("contain", "none | strict | content | [ [ size | inline-size ] || layout || style || paint ]", false, "none"),
+    ("content-visibility", "visible | auto | hidden", false, "visible"),
+    ("isolation", "auto | isolate", false, "auto"),
+    // box model
+    ("box-sizing", "content-box | border-box", false, "content-box"),
+    ("width", "<size>", false, "auto"),
+    ("height", "<size>", false, "auto"),
+    ("inline-size", "<size>", false, "auto"),
+    ("block-size", "<size>", false, "auto"),
+    ("min-width", "<size>", false, "auto"),
+    ("min-height", "<size>", false, "auto"),
+    ("min-inline-size", "<size>", false, "auto"),
+    ("min-block-size", "<size>", false, "auto"),
+    ("max-width", "<max-size>", false, "none"),
+    ("max-height", "<max-size>", false, "none"),
+    ("max-inline-size", "<max-size>", false, "none"),
+    ("max-block-size", "<max-size>", false, "none"),
+    ("aspect-ratio", "auto || <ratio>", false, "auto"),
+    ("contain-intrinsic-size", "[ auto? [ none | <length [0,∞]> ] ]{1,2}", false, "none"),
+    ("margin-top", "<length-percentage> | auto", false, "0"),
+    ("margin-right", "<length-percentage> | auto", false, "0"),
+    ("margin-bottom", "<length-percentage> | auto", false, "0"),
+    ("margin-left", "<length-percentage> | auto", false, "0"),
+    ("margin-block-start", "<length-percentage> | auto", false, "0"),
+    ("margin-block-end", "<length-percentage> | auto", false, "0"),
+    ("margin-inline-start", "<length-percentage> | auto", false, "0"),
+    ("margin-inline-end", "<length-percentage> | auto", false, "0"),
+    ("margin", "<'margin-top'>{1,4}", false, ""),
+    ("margin-block", "<'margin-top'>{1,2}", false, ""),
+    ("margin-inline", "<'margin-top'>{1,2}", false, ""),
+    ("padding-top", "<length-percentage [0,∞]>", false, "0"),
+    ("padding-right", "<length-percentage [0,∞]>", false, "0"),
+    ("padding-bottom", "<length-percentage [0,∞]>", false, "0"),
+    ("padding-left", "<length-percentage [0,∞]>", false, "0"),
+    ("padding-block-start", "<length-percentage [0,∞]>", false, "0"),
+    ("padding-block-end", "<length-percentage [0,∞]>", false, "0"),
+    ("padding-inline-start", "<length-percentage [0,∞]>", false, "0"),
+    ("padding-inline-end", "<length-percentage [0,∞]>", false, "0"),
+    ("padding", "<'padding-top'>{1,4}", false, ""),
+    ("padding-block", "<'padding-top'>{1,2}", false, ""),
+    ("padding-inline", "<'padding-top'>{1,2}", false, ""),
+    // borders and outlines
+    ("border-top-width", "<line-width>", false, "medium"),
+    ("border-right-width", "<line-width>", false, "medium"),
+    ("border-bottom-width", "<line-width>", false, "medium"),
+    ("border-left-width", "<line-width>", false, "medium"),
+    ("border-block-start-width", "<line-width>", false, "medium"),
+    ("border-block-end-width", "<line-width>", false, "medium"),
+    ("border-inline-start-width", "<line-width>", false, "medium"),
+    ("border-inline-end-width", "<line-width>", false, "medium"),
+    ("border-top-style", "<line-style>", false, "none"),
+    ("border-right-style", "<line-style>", false, "none"),
+    ("border-bottom-style", "<line-style>", false, "none"),
+    ("border-left-style", "<line-style>", false, "none"),
+    ("border-block-start-style", "<line-style>", false, "none"),
+    ("border-block-end-style", "<line-style>", false, "none"),
+    ("border-inline-start-style", "<line-style>", false, "none"),
+    ("border-inline-end-style", "<line-style>", false, "none"),
+    ("border-top-color", "<color>", false, "currentcolor"),
+    ("border-right-color", "<color>", false, "currentcolor"),
+    ("border-bottom-color", "<color>", false, "currentcolor"),
+    ("border-left-color", "<color>", false, "currentcolor"),
+    ("border-block-start-color", "<color>", false, "currentcolor"),
+    ("border-block-end-color", "<color>", false, "currentcolor"),
+    ("border-inline-start-color", "<color>", false, "currentcolor"),
+    ("border-inline-end-color", "<color>", false, "currentcolor"),
+    ("border-top", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-right", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-bottom", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-left", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-block-start", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-block-end", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-inline-start", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-inline-end", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-block", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-inline", "<line-width> || <line-style> || <color>", false, ""),
+    ("border", "<line-width> || <line-style> || <color>", false, ""),
+    ("border-width", "<line-width>{1,4}", false, ""),
+    ("border-style", "<line-style>{1,4}", false, ""),
+    ("border-color", "<color>{1,4}", false, ""),
+    ("border-top-left-radius", "<length-percentage [0,∞]>{1,2}", false, "0"),
+    ("border-top-right-radius", "<length-percentage [0,∞]>{1,2}", false, "0"),
+    ("border-bottom-right-radius", "<length-percentage [0,∞]>{1,2}", false, "0"),
+    ("border-bottom-left-radius", "<length-percentage [0,∞]>{1,2}", false, "0"),
+    ("border-radius", "<length-percentage [0,∞]>{1,4} [ / <length-percentage [0,∞]>{1,4} ]?", false, ""),
+    ("border-collapse", "separate | collapse", true, "separate"),
+    ("border-spacing", "<length [0,∞]>{1,2}", true, "0px"),
+    ("outline-width", "<line-width>", false, "medium"),
+    ("outline-style", "auto | <line-style>", false, "none"),
+    ("outline-color", "auto | <color>", false, "auto"),
+    ("outline-offset", "<length>", false, "0"),
+    ("outline", "<'outline-width'> || <'outline-style'> || <'outline-color'>", false, ""),
+    ("box-shadow", "none | <shadow>#", false, "none"),
+    // colors and backgrounds
+    ("color", "<color>", true, "canvastext"),
+    ("color-scheme", "normal | [ light | dark | <custom-ident> ]+ && only?", true, "normal"),
+    ("background-color", "<color>", false, "transparent"),
+    ("background-image", "<bg-image>#", false, "none"),
+    ("background-repeat", "<repeat-style>#", false, "repeat"),
+    ("background-position-x", "[ center | [ [ left | right | x-start | x-end ]? <length-percentage>? ]! ]#", false, "0%"),
+    ("background-position-y", "[ center | [ [ top | bottom | y-start | y-end ]? <length-percentage>? ]! ]#", false, "0%"),
+    ("background-position", "<bg-position>#", false, ""),
+    ("background-size", "<bg-size>#", false, "auto"),
+    ("background-attachment", "<attachment>#", false, "scroll"),
+    ("background-origin", "<visual-box>#", false, "padding-box"),
+    ("background-clip", "<bg-clip>#", false, "border-box"),
+    ("background", "[ <bg-layer> , ]* <final-bg-layer>", false, ""),
+    ("mask-image", "<mask-reference>#", false, "none"),
+    ("mask-mode", "<masking-mode>#", false, "match-source"),
+    ("mask-repeat", "<repeat-style>#", false, "repeat"),
+    ("mask-position", "<bg-position>#", false, "0% 0%"),
+    ("mask-clip", "[ <coord-box> | no-clip ]#", false, "border-box"),
+    ("mask-origin", "<coord-box>#", false, "border-box"),
+    ("mask-size", "<bg-size>#", false, "auto"),
+    ("mask-composite", "<compositing-operator>#", false, "add"),
+    ("mask", "<mask-layer>#", false, ""),
+    // fonts
+    ("font-family", "[ <family-name> | <generic-family> ]#", true, "serif"),
+    ("font-size", "<absolute-size> | <relative-size> | <length-percentage [0,∞]> | math", true, "medium"),
+    ("font-style", "normal | italic | oblique <angle>?", true, "normal"),
+    ("font-weight", "<font-weight-absolute> | bolder | lighter", true, "normal"),
+    ("font-variant", "normal | none | [ small-caps | all-small-caps | petite-caps | all-petite-caps | unicase | titling-caps ]", true, "normal"),
+    ("font-stretch", "normal | <percentage [0,∞]> | <font-width-keyword>", true, "normal"),
+    ("font-feature-settings", "normal | <feature-tag-value>#", true, "normal"),
+    ("line-height", "normal | <number [0,∞]> | <length-percentage [0,∞]>", true, "normal"),
+    ("font", "[ [ <'font-style'> || <font-variant-css2> || <'font-weight'> || <font-width-css3> ]? <'font-size'> [ / <'line-height'> ]? <'font-family'> ] | caption | icon | menu | message-box | small-caption | status-bar", true, ""),
+    // text
+    ("text-align", "start | end | left | right | center | justify | match-parent | justify-all", true, "start"),
+    ("text-indent", "<length-percentage> && hanging? && each-line?", true, "0"),
+    ("text-transform", "none | [ capitalize | uppercase | lowercase ] || full-width || full-size-kana | math-auto", true, "none"),
+    ("text-decoration-line", "none | [ underline || overline || line-through || blink ]", false, "none"),
+    ("text-decoration-style", "solid | double | dotted | dashed | wavy", false, "solid"),
+    ("text-decoration-color", "<color>", false, "currentcolor"),
+    ("text-decoration-thickness", "auto | from-font | <length-percentage>", false, "auto"),
+    ("text-decoration", "<'text-decoration-line'> || <'text-decoration-style'> || <'text-decoration-color'> || <'text-decoration-thickness'>", false, ""),
+    ("text-shadow", "none | [ <length>{2,3} && <color>? ]#", true, "none"),
+    ("text-overflow", "[ clip | ellipsis | <string> ]{1,2}", false, "clip"),
+    ("text-rendering", "auto | optimizespeed | optimizelegibility | geometricprecision", true, "auto"),
+    ("text-emphasis-style", "none | [ [ filled | open ] || [ dot | circle | double-circle | triangle | sesame ] ] | <string>", true, "none"),
+    ("text-emphasis-color", "<color>", true, "currentcolor"),
+    ("text-emphasis", "<'text-emphasis-style'> || <'text-emphasis-color'>", true, ""),
+    ("text-autospace", "normal | auto | no-autospace | [ ideograph-alpha || ideograph-numeric || punctuation ]", true, "normal"),
+    ("letter-spacing", "normal | <length-percentage>", true, "normal"),
+    ("word-spacing", "normal | <length-percentage>", true, "normal"),
+    ("white-space-collapse", "collapse | discard | preserve | preserve-breaks | preserve-spaces | break-spaces", true, "collapse"),
+    ("text-wrap-mode", "wrap | nowrap", true, "wrap"),
+    ("text-wrap-style", "auto | balance | stable | pretty", true, "auto"),
+    ("text-wrap", "<'text-wrap-mode'> || <'text-wrap-style'>", true, ""),
+    ("white-space", "normal | pre | pre-wrap | pre-line | <'white-space-collapse'> || <'text-wrap-mode'>", true, ""),
+    ("overflow-wrap", "normal | break-word | anywhere", true, "normal"),
+    ("word-wrap", "normal | break-word | anywhere", true, "normal"),
+    ("word-break", "normal | keep-all | break-all | break-word", true, "normal"),
+    ("hyphens", "none | manual | auto", true, "manual"),
+    ("tab-size", "<number [0,∞]> | <length [0,∞]>", true, "8"),
+    ("vertical-align", "baseline | sub | super | text-top | text-bottom | middle | top | bottom | <length-percentage>", false, "baseline"),
+    ("writing-mode", "horizontal-tb | vertical-rl | vertical-lr | sideways-rl | sideways-lr", true, "horizontal-tb"),
+    ("text-orientation", "mixed | upright | sideways", true, "mixed"),
+    ("direction", "ltr | rtl", true, "ltr"),
+    ("unicode-bidi", "normal | embed | isolate | bidi-override | isolate-override | plaintext", false, "normal"),
+    // flexbox and alignment
+    ("flex-direction", "row | row-reverse | column | column-reverse", false, "row"),
+    ("flex-wrap", "nowrap | wrap | wrap-reverse", false, "nowrap"),
+    ("flex-flow", "<'flex-direction'> || <'flex-wrap'>", false, ""),
+    ("flex-grow", "<number [0,∞]>", false, "0"),
+    ("flex-shrink", "<number [0,∞]>", false, "1"),
+    ("flex-basis", "content | <'width'>", false, "auto"),
+    ("flex", "none | [ <'flex-grow'> <'flex-shrink'>? || <'flex-basis'> ]", false, ""),
+    ("order", "<integer>", false, "0"),
+    ("justify-content", "normal | <content-distribution> | <overflow-position>? [ <content-position> | left | right ]", false, "normal"),
+    ("justify-items", "normal | stretch | <baseline-position> | <overflow-position>? [ <self-position> | left | right ] | legacy | legacy && [ left | right | center ]", false, "legacy"),
+    ("justify-self", "auto | normal | stretch | <baseline-position> | <overflow-position>? [ <self-position> | left | right ]", false, "auto"),
+    ("align-content", "normal | <baseline-position> | <content-distribution> | <overflow-position>? <content-position>", false, "normal"),
+    ("align-items", "normal | stretch | anchor-center | <baseline-position> | <overflow-position>? <self-position>", false, "normal"),
+    ("align-self", "auto | normal | stretch | anchor-center | <baseline-position> | <overflow-position>? <self-position>", false, "auto"),
+    ("row-gap", "normal | <length-percentage [0,∞]>", false, "normal"),
+    ("column-gap", "normal | <length-percentage [0,∞]>", false, "normal"),
+    ("gap", "<'row-gap'> <'column-gap'>?", false, ""),
+    // grid
+    ("grid-template-columns", "none | <track-list> | subgrid <line-names>*", false, "none"),
+    ("grid-template-rows", "none | <track-list> | subgrid <line-names>*", false, "none"),
+    ("grid-template-areas", "none | <string>+", false, "none"),
+    ("grid-template", "none | [ <'grid-template-rows'> / <'grid-template-columns'> ] | [ <line-names>? <string> <track-size>? <line-names>? ]+ [ / <explicit-track-list> ]?", false, ""),
+    ("grid-auto-columns", "<track-size>+", false, "auto"),
+    ("grid-auto-rows", "<track-size>+", false, "auto"),
+    ("grid-auto-flow", "[ row | column ] || dense", false, "row"),
+    ("grid-row-start", "<grid-line>", false, "auto"),
+    ("grid-row-end", "<grid-line>", false, "auto"),
+    ("grid-column-start", "<grid-line>", false, "auto"),
+    ("grid-column-end", "<grid-line>", false, "auto"),
+    ("grid-row", "<grid-line> [ / <grid-line> ]?", false, ""),
+    ("grid-column", "<grid-line> [ / <grid-line> ]?", false, ""),
+    ("grid-area", "<grid-line> [ / <grid-line> ]{0,3}", false, ""),
+    ("column-count", "auto | <integer [1,∞]>", false, "auto"),
+    // fragmentation
+    ("break-before", "auto | avoid | always | all | avoid-page | page | left | right | recto | verso | avoid-column | column | avoid-region | region", false, "auto"),
+    ("break-after", "auto | avoid | always | all | avoid-page | page | left | right | recto | verso | avoid-column | column | avoid-region | region", false, "auto"),
+    ("break-inside", "auto | avoid | avoid-page | avoid-column | avoid-region", false, "auto"),
+    // tables
+    ("table-layout", "auto | fixed", false, "auto"),
+    ("caption-side", "top | bottom", true, "top"),
+    ("empty-cells", "show | hide", true, "show"),
+    // lists and generated content
+    ("list-style-type", "<counter-style> | <string> | none", true, "disc"),
+    ("list-style-position", "inside | outside", true, "outside"),
+    ("list-style-image", "<image> | none", true, "none"),
+    ("list-style", "<'list-style-position'> || <'list-style-image'> || <'list-style-type'>", true, ""),
+    ("content", "normal | none | [ <content-item> ]+ [ / [ <string> | <counter> | <attr()> ]+ ]?", false, "normal"),
+    ("quotes", "auto | none | [ <string> <string> ]+", true, "auto"),
+    ("counter-increment", "[ <custom-ident> <integer>? ]+ | none", false, "none"),
+    ("counter-reset", "[ <custom-ident> <integer>? ]+ | none", false, "none"),
+    ("counter-set", "[ <custom-ident> <integer>? ]+ | none", false, "none"),
+    // replaced elements
+    ("object-fit", "fill | contain | cover | none | scale-down", false, "fill"),
+    ("object-position", "<bg-position>", false, "50% 50%"),
+    // user interface
+    ("appearance", "none | auto | base | <custom-ident>", false, "none"),
+    ("cursor", "[ <url> [ <number> <number> ]? , ]* <cursor-keyword>", true, "auto"),
+    ("pointer-events", "auto | none | visiblepainted | visiblefill | visiblestroke | visible | painted | fill | stroke | all", true, "auto"),
+    ("user-select", "auto | text | none | contain | all", false, "auto"),
+    ("resize", "none | both | horizontal | vertical | block | inline", false, "none"),
+    ("overlay", "none | auto", false, "none"),
+    ("image-rendering", "auto | smooth | high-quality | pixelated | crisp-edges", true, "auto"),
+    ("view-transition-name", "none | <custom-ident>", false, "none"),
+];
+
+/// Data types that are defined by their own syntax
+const TYPES: &[(&str, &str)] = &[
+    ("display-outside", "block | inline | run-in"),
+    ("display-inside", "flow | flow-root | table | flex | grid | ruby"),
+    ("display-listitem", "<display-outside>? && [ flow | flow-root ]? && list-item"),
+    ("display-internal", "table-row-group | table-header-group | table-footer-group | table-row | table-cell | table-column-group | table-column | table-caption | ruby-base | ruby-text | ruby-base-container | ruby-text-container"),
+    ("display-box", "contents | none"),
+    ("display-legacy", "inline-block | inline-table | inline-flex | inline-grid"),
+    ("overflow-keyword", "visible | hidden | clip | scroll | auto"),
+    ("visual-box", "content-box | padding-box | border-box"),
+    ("alpha-value", "<number> | <percentage>"),
+    ("ratio", "<number [0,∞]> [ / <number [0,∞]> ]?"),
+    ("size", "auto | <length-percentage [0,∞]> | min-content | max-content | fit-content | fit-content( <length-percentage [0,∞]> ) | stretch"),
+    ("max-size", "none | <length-percentage [0,∞]> | min-content | max-content | fit-content | fit-content( <length-percentage [0,∞]> ) | stretch"),
+    ("line-width", "<length [0,∞]> | thin | medium | thick"),
+    ("line-style", "none | hidden | dotted | dashed | solid | double | groove | ridge | inset | outset"),
+    ("shadow", "<color>? && [ <length>{2} <length [0,∞]>? <length>? ] && inset?"),
+    ("bg-image", "<image> | none"),
+    ("repeat-style", "repeat-x | repeat-y | [ repeat | space | round | no-repeat ]{1,2}"),
+    ("bg-position", "[ left | center | right | top | bottom | <length-percentage> ] | [ left | center | right | <length-percentage> ] [ top | center | bottom | <length-percentage> ] | [ center | [ left | right ] <length-percentage>? ] && [ center | [ top | bottom ] <length-percentage>? ]"),
+    ("bg-size", "[ <length-percentage [0,∞]> | auto ]{1,2} | cover | contain"),
+    ("bg-clip", "<visual-box> | border-area | text"),
+    ("attachment", "scroll | fixed | local"),
+    ("bg-layer", "<bg-image> || <bg-position> [ / <bg-size> ]? || <repeat-style> || <attachment> || <visual-box> || <visual-box>"),
+    ("final-bg-layer", "<bg-image> || <bg-position> [ / <bg-size> ]? || <repeat-style> || <attachment> || <visual-box> || <visual-box> || <'background-color'>"),
+    ("mask-reference", "none | <image>"),
+    ("masking-mode", "alpha | luminance | match-source"),
+    ("coord-box", "content-box | padding-box | border-box | fill-box | stroke-box | view-box"),
+    ("compositing-operator", "add | subtract | intersect | exclude"),
+    ("mask-layer", "<mask-reference> || <bg-position> [ / <bg-size> ]? || <repeat-style> || <coord-box> || [ <coord-box> | no-clip ] || <compositing-operator> || <masking-mode>"),
+    ("family-name", "<string> | <custom-ident>+"),
+    ("generic-family", "serif | sans-serif | cursive | fantasy | monospace | system-ui | emoji | math | fangsong | ui-serif | ui-sans-serif | ui-monospace | ui-rounded"),
+    ("absolute-size", "xx-small | x-small | small | medium | large | x-large | xx-large | xxx-large"),
+    ("relative-size", "larger | smaller"),
+    ("font-weight-absolute", "normal | bold | <number [1,1000]>"),
+    ("font-width-keyword", "ultra-condensed | extra-condensed | condensed | semi-condensed | semi-expanded | expanded | extra-expanded | ultra-expanded"),
+    ("font-width-css3", "normal | <font-width-keyword>"),
+    ("font-variant-css2", "normal | small-caps"),
+    ("feature-tag-value", "<string> [ <integer [0,∞]> | on | off ]?"),
+    ("content-distribution", "space-between | space-around | space-evenly | stretch"),
+    ("content-position", "center | start | end | flex-start | flex-end"),
+    ("self-position", "center | start | end | self-start | self-end | flex-start | flex-end"),
+    ("overflow-position", "unsafe | safe"),
+    ("baseline-position", "[ first | last ]? && baseline"),
+    ("track-breadth", "<length-percentage [0,∞]> | <flex [0,∞]> | min-content | max-content | auto"),
+    ("inflexible-breadth", "<length-percentage [0,∞]> | min-content | max-content | auto"),
+    ("track-size", "<track-breadth> | minmax( <inflexible-breadth> , <track-breadth> ) | fit-content( <length-percentage [0,∞]> )"),
+    ("track-repeat", "repeat( [ <integer [1,∞]> | auto-fill | auto-fit ] , [ <line-names>? <track-size> ]+ <line-names>? )"),
+    ("track-list", "[ <line-names>? [ <track-size> | <track-repeat> ] ]+ <line-names>?"),
+    ("explicit-track-list", "[ <line-names>? <track-size> ]+ <line-names>?"),
+    ("grid-line", "auto | <custom-ident> | [ <integer> && <custom-ident>? ] | [ span && [ <integer [1,∞]> || <custom-ident> ] ]"),
+    ("counter-style", "<custom-ident> | symbols( <custom-ident>? <string>+ )"),
+    ("counter", "counter( <custom-ident> [ , <counter-style> ]? ) | counters( <custom-ident> , <string> [ , <counter-style> ]? )"),
+    ("quote", "open-quote | close-quote | no-open-quote | no-close-quote"),
+    ("content-item", "<image> | <string> | <counter> | <quote> | <attr()>"),
+    ("cursor-keyword", "auto | default | none | context-menu | help | pointer | progress | wait | cell | crosshair | text | vertical-text | alias | copy | move | no-drop | not-allowed | grab | grabbing | all-scroll | col-resize | row-resize | n-resize | e-resize | s-resize | w-resize | ne-resize | nw-resize | se-resize | sw-resize | ew-resize | ns-resize | nesw-resize | nwse-resize | zoom-in | zoom-out"),
+];
+
+static PROPERTY_DEFINITIONS: LazyLock<HashMap<String, PropertyDefinition>> = LazyLock::new(|| {
+    PROPERTIES
+        .iter()
+        .map(|(name, syntax, inherited, initial)| {
+            let syntax = CssSyntax::new(syntax).expect("invalid syntax in property definitions");
+            let initial_value = parse_value(initial)
+                .map(CssValue::from_list)
+                .expect("invalid initial value in property definitions");
+
+            (
+                name.to_string(),
+                PropertyDefinition {
+                    name: name.to_string(),
+                    syntax,
+                    inherited: *inherited,
+                    initial_value,
+                },
+            )
+        })
+        .collect()
+});
+
+static TYPE_DEFINITIONS: LazyLock<HashMap<String, CssSyntax>> = LazyLock::new(|| {
+    TYPES
+        .iter()
+        .map(|(name, syntax)| {
+            let syntax = CssSyntax::new(syntax).expect("invalid syntax in type definitions");
+            (name.to_string(), syntax)
+        })
+        .collect()
+});
+
+/// Returns the definition of the given property, or None when the property is unknown
+pub fn get_css_definition(name: &str) -> Option<&'static PropertyDefinition> {
+    PROPERTY_DEFINITIONS.get(name)
+}
+
+/// Returns all known property definitions
+pub fn get_css_definitions() -> &'static HashMap<String, PropertyDefinition> {
+    &PROPERTY_DEFINITIONS
+}
+
+/// Returns the syntax of a data type that is defined by its own syntax, like `<line-width>`
+pub fn get_type_definition(name: &str) -> Option<&'static CssSyntax> {
+    TYPE_DEFINITIONS.get(name)
+}
@@ -0,0 +1,360 @@
+use crate::{
+    css3::stylesheet::{
+        AttributeMatcher, AttributeNamespace, AttributeSelector, Combinator, CssSelector, CssSelectorPart, Nth,
+        PseudoClass,
+    },
+    interface::{
+        config::HasDocument,
+        document::Document,
+        node::{ElementDataType, Node, NodeType},
+    },
+    shared::node::NodeId,
+};
+
+/// Returns true when the given selector matches the node. Pseudo elements in the selector are
+/// ignored, so a selector like `p::before` matches the `p` element.
+pub fn match_selector<C: HasDocument>(doc: &C::Document, node_id: NodeId, selector: &CssSelector) -> bool {
+    match_complex::<C>(doc, node_id, &selector.parts, None)
+}
+
+/// Matches the parts of a complex selector from right to left. When `scope` is given, the parts
+/// form a relative selector (as used by `:has()`), whose leading combinator is relative to the scope.
+fn match_complex<C: HasDocument>(
+    doc: &C::Document,
+    node_id: NodeId,
+    parts: &[CssSelectorPart],
+    scope: Option<NodeId>,
+) -> bool {
+    let split = parts.iter().rposition(|p| matches!(p, CssSelectorPart::Combinator(_)));
+    let compound = match split {
+        Some(idx) => &parts[idx + 1..],
+        None => parts,
+    };
+
+    if !match_compound::<C>(doc, node_id, compound) {
+        return false;
+    }
+
+    let Some(idx) = split else {
+        return true;
+    };
+    let CssSelectorPart::Combinator(combinator) = parts[idx] else {
+        return false;
+    };
+    let rest = &parts[..idx];
+
+    // Leading combinator of a relative selector: check the relation with the scoping element
+    if rest.is_empty() {
+        let Some(scope) = scope else {
+            return true;
+        };
+        return match combinator {
+            Combinator::Descendant => ancestors::<C>(doc, node_id).any(|id| id == scope),
+            Combinator::Child => parent_element::<C>(doc, node_id) == Some(scope),
+            Combinator::NextSibling => previous_siblings::<C>(doc, node_id).first() == Some(&scope),
+            Combinator::SubsequentSibling => previous_siblings::<C>(doc, node_id).contains(&scope),
+        };
+    }
+
+    match combinator {
+        Combinator::Descendant => ancestors::<C>(doc, node_id).any(|id| match_complex::<C>(doc, id, rest, scope)),
+        Combinator::Child => {
+            parent_element::<C>(doc, node_id).is_some_and(|id| match_complex::<C>(doc, id, rest, scope))
+        }
+        Combinator::NextSibling => previous_siblings::<C>(doc, node_id)
+            .first()
+            .is_some_and(|id| match_complex::<C>(doc, *id, rest, scope)),
+        Combinator::SubsequentSibling => previous_siblings::<C>(doc, node_id)
+            .iter()
+            .any(|id| match_complex::<C>(doc, *id, rest, scope)),
+    }
+}
+
+/// Matches all simple selectors of a compound selector against a single element
+fn match_compound<C: HasDocument>(doc: &C::Document, node_id: NodeId, parts: &[CssSelectorPart]) -> bool {
+    let Some(node) = doc.node_by_id(node_id) else {
+        return false;
+    };
+    let Some(element) = node.get_element_data() else {
+        return false;
+    };
+
+    parts.iter().all(|part| match part {
+        CssSelectorPart::Universal | CssSelectorPart::PseudoElement(_) => true,
+        CssSelectorPart::Type(name) => element.name().eq_ignore_ascii_case(name),
+        CssSelectorPart::Namespace(namespace) => element.namespace() == namespace,
+        CssSelectorPart::Class(name) => element.has_class(name),
+        CssSelectorPart::Id(id) => element.attribute("id") == Some(id.as_str()),
+        CssSelectorPart::Attribute(attr) => match_attribute(attribute_value(element, attr), attr),
+        CssSelectorPart::PseudoClass(pseudo) => match_pseudo_class::<C>(doc, node_id, pseudo),
+        CssSelectorPart::Combinator(_) => false,
+    })
+}
+
+/// Attributes in a namespace are stored with their prefix, like `xlink:href`
+const ATTRIBUTE_PREFIXES: [(&str, &str); 3] = [
+    ("xlink", "http://www.w3.org/1999/xlink"),
+    ("xml", "http://www.w3.org/XML/1998/namespace"),
+    ("xmlns", "http://www.w3.org/2000/xmlns/"),
+];
+
+/// Finds the value of the attribute of an attribute selector, taking its namespace into account
+fn attribute_value<'a, C: HasDocument>(
+    element: &'a impl ElementDataType<C>,
+    attr: &AttributeSelector,
+) -> Option<&'a str> {
+    let prefixed = |prefix: &str| element.attribute(&format!("{prefix}:{}", attr.name));
+
+    match &attr.namespace {
+        AttributeNamespace::None => element.attribute(&attr.name),
+        AttributeNamespace::Any => element
+            .attribute(&attr.name)
+            .or_else(|| ATTRIBUTE_PREFIXES.iter().find_map(|(prefix, _)| prefixed(prefix))),
+        AttributeNamespace::Url(url) => ATTRIBUTE_PREFIXES
+            .iter()
+            .find(|(_, namespace)| namespace == url)
+            .and_then(|(prefix, _)| prefixed(prefix)),
+    }
+}
+
+fn match_attribute(value: Option<&str>, attr: &AttributeSelector) -> bool {
+    let Some(value) = value else {
+        return false;
+    };
+
+    let (value, expected) = if attr.case_insensitive {
+        (value.to_lowercase(), attr.value.to_lowercase())
+    } else {
+        (value.to_string(), attr.value.clone())
+    };
+
+    match attr.matcher {
+        AttributeMatcher::Exists => true,
+        AttributeMatcher::Equals => value == expected,
+        AttributeMatcher::Includes => value.split_ascii_whitespace().any(|v| v == expected),
+        AttributeMatcher::DashMatch => value == expected || value.starts_with(&format!("{expected}-")),
+        AttributeMatcher::PrefixMatch => !expected.is_empty() && value.starts_with(&expected),
+        AttributeMatcher::SuffixMatch => !expected.is_empty() && value.ends_with(&expected),
+        AttributeMatcher::SubstringMatch => !expected.is_empty() && value.contains(&expected),
+    }
+}
+
+fn match_pseudo_class<C: HasDocument>(doc: &C::Document, node_id: NodeId, pseudo: &PseudoClass) -> bool {
+    let match_any = |list: &[CssSelector], id: NodeId| list.iter().any(|s| match_selector::<C>(doc, id, s));
+
+    match pseudo {
+        PseudoClass::Not(list) => !match_any(list, node_id),
+        PseudoClass::Is(list) | PseudoClass::Where(list) => match_any(list, node_id),
+        PseudoClass::Has(list) => match_has::<C>(doc, node_id, list),
+        PseudoClass::NthChild(nth, of) => {
+            let siblings = element_siblings::<C>(doc, node_id);
+            let siblings: Vec<NodeId> = siblings
+                .into_iter()
+                .filter(|id| of.is_empty() || match_any(of, *id))
+                .collect();
+            match_nth(nth, &siblings, node_id, false)
+        }
+        PseudoClass::NthLastChild(nth, of) => {
+            let siblings = element_siblings::<C>(doc, node_id);
+            let siblings: Vec<NodeId> = siblings
+                .into_iter()
+                .filter(|id| of.is_empty() || match_any(of, *id))
+                .collect();
+            match_nth(nth, &siblings, node_id, true)
+        }
+        PseudoClass::NthOfType(nth) => match_nth(nth, &same_type_siblings::<C>(doc, node_id), node_id, false),
+        PseudoClass::NthLastOfType(nth) => match_nth(nth, &same_type_siblings::<C>(doc, node_id), node_id, true),
+        PseudoClass::Lang(langs) => {
+            let lang = inherited_attribute::<C>(doc, node_id, "lang")
+                .unwrap_or_default()
+                .to_lowercase();
+            langs.iter().any(|l| {
+                let l = l.to_lowercase();
+                lang == l || lang.starts_with(&format!("{l}-"))
+            })
+        }
+        PseudoClass::Dir(dir) => {
+            let value = inherited_attribute::<C>(doc, node_id, "dir").unwrap_or_else(|| "ltr".to_string());
+            value.eq_ignore_ascii_case(dir)
+        }
+        PseudoClass::Named(name) => match_named_pseudo_class::<C>(doc, node_id, name),
+        PseudoClass::Function(_, _) => false,
+    }
+}
+
+fn match_named_pseudo_class<C: HasDocument>(doc: &C::Document, node_id: NodeId, name: &str) -> bool {
+    let Some(element) = doc.node_by_id(node_id).and_then(|n| n.get_element_data()) else {
+        return false;
+    };
+    let element_name = element.name().to_ascii_lowercase();
+
+    match name {
+        "root" => doc
+            .node_by_id(node_id)
+            .and_then(|n| n.parent_id())
+            .and_then(|id| doc.node_by_id(id))
+            .is_some_and(|parent| parent.type_of() == NodeType::DocumentNode),
+        "empty" => doc.node_by_id(node_id).is_some_and(|n| {
+            n.children().iter().all(|id| {
+                doc.node_by_id(*id)
+                    .is_some_and(|child| matches!(child.type_of(), NodeType::CommentNode))
+            })
+        }),
+        "first-child" => previous_siblings::<C>(doc, node_id).is_empty(),
+        "last-child" => next_siblings::<C>(doc, node_id).is_empty(),
+        "only-child" => element_siblings::<C>(doc, node_id).len() == 1,
+        "first-of-type" => same_type_siblings::<C>(doc, node_id).first() == Some(&node_id),
+        "last-of-type" => same_type_siblings::<C>(doc, node_id).last() == Some(&node_id),
+        "only-of-type" => same_type_siblings::<C>(doc, node_id).len() == 1,
+        "link" | "any-link" => matches!(element_name.as_str(), "a" | "area") && element.attribute("href").is_some(),
+        "checked" => match element_name.as_str() {
+            "input" => element.attribute("checked").is_some(),
+            "option" => element.attribute("selected").is_some(),
+            _ => false,
+        },
+        "disabled" => is_form_element(&element_name) && element.attribute("disabled").is_some(),
+        "enabled" => is_form_element(&element_name) && element.attribute("disabled").is_none(),
+        "required" => element.attribute("required").is_some(),
+        "optional" => is_form_element(&element_name) && element.attribute("required").is_none(),
+        "read-only" => element.attribute("readonly").is_some(),
+        "defined" => true,
+        // Dynamic states (:hover, :focus, :visited etc.) and unknown pseudo classes never match
+        _ => false,
+    }
+}
+
+fn is_form_element(name: &str) -> bool {
+    matches!(
+        name,
+        "button" | "input" | "select" | "textarea" | "optgroup" | "option" | "fieldset"
+    )
+}
+
+/// `:has()` matches when any element relative to the node matches one of the relative selectors
+fn match_has<C: HasDocument>(doc: &C::Document, node_id: NodeId, list: &[CssSelector]) -> bool {
+    list.iter().any(|selector| {
+        let sibling_relative = matches!(
+            selector.parts.first(),
+            Some(CssSelectorPart::Combinator(
+                Combinator::NextSibling | Combinator::SubsequentSibling
+            ))
+        );
+
+        let mut candidates = Vec::new();
+        if sibling_relative {
+            for sibling in next_siblings::<C>(doc, node_id) {
+                candidates.push(sibling);
+                descendants::<C>(doc, sibling, &mut candidates);
+            }
+        } else {
+            descendants::<C>(doc, node_id, &mut candidates);
+        }
+
+        candidates
+            .iter()
+            .any(|id| match_complex::<C>(doc, *id, &selector.parts, Some(node_id)))
+    })
+}
+
+/// Matches the An+B notation against the position of the node in the list of siblings
+fn match_nth(nth: &Nth, siblings: &[NodeId], node_id: NodeId, from_end: bool) -> bool {
+    let Some(pos) = siblings.iter().position(|id| *id == node_id) else {
+        return false;
+    };
+
+    let index = if from_end { siblings.len() - pos } else { pos + 1 };
+    nth.matches(index as i32)
+}
+
+fn is_element<C: HasDocument>(doc: &C::Document, node_id: NodeId) -> bool {
+    doc.node_by_id(node_id).is_some_and(|n| n.is_element_node())
+}
+
+fn parent_element<C: HasDocument>(doc: &C::Document, node_id: NodeId) -> Option<NodeId> {
+    let parent_id = doc.node_by_id(node_id)?.parent_id()?;
+    is_element::<C>(doc, parent_id).then_some(parent_id)
+}
+
+/// Returns all ancestor elements of the node, starting with the parent
+fn ancestors<C: HasDocument>(doc: &C::Document, node_id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    std::iter::successors(parent_element::<C>(doc, node_id), move |id| {
+        parent_element::<C>(doc, *id)
+    })
+}
+
+/// Returns all element children of the parent of the node (including the node itself)
+fn element_siblings<C: HasDocument>(doc: &C::Document, node_id: NodeId) -> Vec<NodeId> {
+    let Some(parent_id) = doc.node_by_id(node_id).and_then(|n| n.parent_id()) else {
+        return vec![node_id];
+    };
+    let Some(parent) = doc.node_by_id(parent_id) else {
+        return vec![node_id];
+    };
+
+    parent
+        .children()
+        .iter()
+        .copied()
+        .filter(|id| is_element::<C>(doc, *id))
+        .collect()
+}
+
+/// Returns all element siblings with the same element name as the node (including the node itself)
+fn same_type_siblings<C: HasDocument>(doc: &C::Document, node_id: NodeId) -> Vec<NodeId> {
+    let name = element_name::<C>(doc, node_id);
+
+    element_siblings::<C>(doc, node_id)
+        .into_iter()
+        .filter(|id| element_name::<C>(doc, *id) == name)
+        .collect()
+}
+
+/// Returns the element siblings before the node, closest sibling first
+fn previous_siblings<C: HasDocument>(doc: &C::Document, node_id: NodeId) -> Vec<NodeId> {
+    let siblings = element_siblings::<C>(doc, node_id);
+    let pos = siblings.iter().position(|id| *id == node_id).unwrap_or(0);
+
+    siblings[..pos].iter().rev().copied().collect()
+}
+
+/// Returns the element siblings after the node, closest sibling first
+fn next_siblings<C: HasDocument>(doc: &C::Document, node_id: NodeId) -> Vec<NodeId> {
+    let siblings = element_siblings::<C>(doc, node_id);
+    let pos = siblings.iter().position(|id| *id == node_id).unwrap_or(siblings.len());
+
+    siblings.get(pos + 1..).unwrap_or_default().to_vec()
+}
+
+/// Collects all element descendants of the node in tree order
+fn descendants<C: HasDocument>(doc: &C::Document, node_id: NodeId, result: &mut Vec<NodeId>) {
+    let Some(node) = doc.node_by_id(node_id) else {
+        return;
+    };
+
+    for child in node.children() {
+        if is_element::<C>(doc, *child) {
+            result.push(*child);
+            descendants::<C>(doc, *child, result);
+        }
+    }
+}
+
+fn element_name<C: HasDocument>(doc: &C::Document, node_id: NodeId) -> Option<String> {
+    doc.node_by_id(node_id)
+        .and_then(|n| n.get_element_data())
+        .map(|e| e.name().to_ascii_lowercase())
+}
+
+/// Returns the value of the attribute on the node or its closest ancestor that has it
+pub(crate) fn inherited_attribute<C: HasDocument>(doc: &C::Document, node_id: NodeId, name: &str) -> Option<String> {
+    let mut current = Some(node_id);
+    while let Some(id) = current {
+        let node = doc.node_by_id(id)?;
+        if let Some(value) = node.get_element_data().and_then(|e| e.attribute(name)) {
+            return Some(value.to_string());
+        }
+        current = node.parent_id();
+    }
+
+    None
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use crate::{
+    css3::{
+        calc::resolve_length,
+        colors::resolve_color,
+        stylesheet::{CssValue, Specificity},
+    },
+    interface::css3::{CssOrigin, CssProperty as TCssProperty, CssPropertyMap},
+    shared::byte_stream::Location,
+};
+
+/// A declaration that applies to an element, together with everything the cascade needs to
+/// decide its precedence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclarationProperty {
+    /// Declared value
+    pub value: CssValue,
+    /// Origin of the stylesheet the declaration is found in
+    pub origin: CssOrigin,
+    /// True when the declaration is marked with !important
+    pub important: bool,
+    /// Location of the declaration in its stylesheet
+    pub location: Location,
+    /// Specificity of the selector that matched the element
+    pub specificity: Specificity,
+    /// Precedence of the cascade layer of the declaration within its origin and importance.
+    /// Higher values win.
+    pub layer: usize,
+    /// Order of appearance over all stylesheets
+    pub order: usize,
+    /// Index of the media conditions the declaration depends on, if it is inside `@media`
+    pub media: Option<usize>,
+    /// True for declarations of a `style` attribute, which win from all selectors
+    pub element_attached: bool,
+}
+
+impl DeclarationProperty {
+    /// Returns the precedence of the origin and importance of this declaration (CSS Cascade 5, 6.1).
+    /// Higher values win.
+    pub fn origin_precedence(&self) -> u8 {
+        match (self.origin, self.important) {
+            (CssOrigin::UserAgent, false) => 1,
+            (CssOrigin::User, false) => 2,
+            (CssOrigin::Author, false) => 3,
+            (CssOrigin::Author, true) => 4,
+            (CssOrigin::User, true) => 5,
+            (CssOrigin::UserAgent, true) => 6,
+        }
+    }
+
+    /// Compares two declarations for the same property by their cascade precedence
+    pub fn cascade_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.origin_precedence()
+            .cmp(&other.origin_precedence())
+            .then(self.element_attached.cmp(&other.element_attached))
+            .then(self.layer.cmp(&other.layer))
+            .then(self.specificity.cmp(&other.specificity))
+            .then(self.order.cmp(&other.order))
+    }
+}
+
+/// A single CSS property of an element with the values found by the cascade
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssProperty {
+    /// Name of the property
+    pub name: String,
+    /// All declarations for this property that apply to the element, sorted from the lowest to the
+    /// highest precedence
+    pub declared: Vec<DeclarationProperty>,
+    /// The winning declaration of the cascade (if any)
+    pub cascaded: Option<DeclarationProperty>,
+    /// The specified value, after the cascade and defaulting. It differs from the computed value
+    /// when that is adjusted, like the `display` of floats and absolutely positioned boxes.
+    pub specified: CssValue,
+    /// The computed value, after inheritance, defaulting and resolving relative values
+    pub computed: CssValue,
+}
+
+impl CssProperty {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            declared: Vec::new(),
+            cascaded: None,
+            specified: CssValue::None,
+            computed: CssValue::None,
+        }
+    }
+
+    /// Returns the cascaded value of the property
+    pub fn cascaded_value(&self) -> Option<&CssValue> {
+        self.cascaded.as_ref().map(|decl| &decl.value)
+    }
+
+    /// Returns a property with the same name and the given computed value
+    fn with_computed(&self, computed: CssValue) -> Self {
+        Self {
+            computed,
+            ..Self::new(&self.name)
+        }
+    }
+
+    /// Splits values at their commas. Items of more than one value become a list.
+    fn split_commas(&self, values: &[CssValue]) -> Vec<Self> {
+        values
+            .split(|value| matches!(value, CssValue::Comma))
+            .map(|item| match item {
+                [value] => self.with_computed(value.clone()),
+                values => self.with_computed(CssValue::List(values.to_vec())),
+            })
+            .collect()
+    }
+}
+
+impl TCssProperty for CssProperty {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_px(&self) -> Option<f32> {
+        match &self.computed {
+            CssValue::Unit(value, unit) if unit == "px" => Some(*value),
+            CssValue::Number(value) if *value == 0.0 => Some(0.0),
+            _ => None,
+        }
+    }
+
+    fn resolve_px(&self, percentage_basis: Option<f32>) -> Option<f32> {
+        resolve_length(&self.computed, percentage_basis)
+    }
+
+    fn as_percentage(&self) -> Option<f32> {
+        match self.computed {
+            CssValue::Percentage(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> Option<f32> {
+        match self.computed {
+            CssValue::Number(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_keyword(&self) -> Option<&str> {
+        self.computed.as_keyword()
+    }
+
+    fn as_specified_keyword(&self) -> Option<&str> {
+        self.specified.as_keyword()
+    }
+
+    fn as_string(&self) -> Option<&str> {
+        match &self.computed {
+            CssValue::String(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    fn as_color(&self) -> Option<(u8, u8, u8, u8)> {
+        resolve_color(&self.computed, None).map(|color| color.to_rgba8())
+    }
+
+    fn as_dimension(&self) -> Option<(f32, &str)> {
+        match &self.computed {
+            CssValue::Unit(value, unit) => Some((*value, unit.as_str())),
+            _ => None,
+        }
+    }
+
+    fn as_list(&self) -> Option<Vec<Self>> {
+        match &self.computed {
+            CssValue::List(values) => Some(values.iter().map(|value| self.with_computed(value.clone())).collect()),
+            _ => None,
+        }
+    }
+
+    fn as_function(&self) -> Option<(&str, Vec<Self>)> {
+        let CssValue::Function(name, args) = &self.computed else {
+            return None;
+        };
+        if args.is_empty() {
+            return Some((name.as_str(), Vec::new()));
+        }
+
+        Some((name.as_str(), self.split_commas(args)))
+    }
+
+    fn as_comma_list(&self) -> Vec<Self> {
+        match &self.computed {
+            CssValue::List(values) => self.split_commas(values),
+            value => vec![self.with_computed(value.clone())],
+        }
+    }
+}
+
+/// Map of all properties of an element
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CssProperties {
+    pub properties: HashMap<String, CssProperty>,
+}
+
+impl CssProperties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the computed value of the given property
+    pub fn computed_value(&self, name: &str) -> Option<&CssValue> {
+        self.properties.get(name).map(|prop| &prop.computed)
+    }
+}
+
+impl CssPropertyMap for CssProperties {
+    type Property = CssProperty;
+
+    fn get(&self, name: &str) -> Option<&Self::Property> {
+        self.properties.get(name)
+    }
+
+    fn insert(&mut self, name: &str, property: Self::Property) {
+        self.properties.insert(name.to_string(), property);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&str, &Self::Property)> {
+        self.properties.iter().map(|(name, prop)| (name.as_str(), prop))
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::css3::{
+    matcher::{
+        property_definitions::get_css_definition,
+        styling::{CssProperties, CssProperty},
+    },
+    shorthands::expand_shorthand,
+    stylesheet::{CssPropertyRule, CssStylesheet, CssValue},
+};
+
+/// All custom properties registered with `@property`. When a property is registered more than
+/// once, the last registration wins.
+#[derive(Debug, Default)]
+pub struct PropertyRegistry {
+    properties: HashMap<String, CssPropertyRule>,
+}
+
+impl PropertyRegistry {
+    pub fn new(sheets: &[CssStylesheet]) -> Self {
+        let mut properties = HashMap::new();
+
+        for sheet in sheets {
+            for rule in &sheet.property_rules {
+                properties.insert(rule.name.clone(), rule.clone());
+            }
+        }
+
+        Self { properties }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CssPropertyRule> {
+        self.properties.get(name)
+    }
+
+    /// Returns true when the custom property has a typed syntax, so its value is computed like the
+    /// value of any other property
+    pub fn is_typed(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|rule| rule.syntax.is_some())
+    }
+
+    fn inherits(&self, name: &str) -> bool {
+        self.get(name).is_none_or(|rule| rule.inherits)
+    }
+
+    /// Initial value of a custom property. Unregistered properties start with the
+    /// guaranteed-invalid value, which is represented as `CssValue::None`.
+    fn initial_value(&self, name: &str) -> CssValue {
+        self.get(name)
+            .and_then(|rule| rule.initial_value.clone())
+            .unwrap_or(CssValue::None)
+    }
+}
+
+/// Computes the custom properties of an element (CSS Variables 1, 2). The custom properties of the
+/// parent are inherited, and `var()` references between custom properties are substituted.
+/// Properties that are part of a reference cycle get the guaranteed-invalid value.
+pub fn compute_custom_properties(
+    properties: &mut CssProperties,
+    parent: Option<&CssProperties>,
+    registry: &PropertyRegistry,
+) {
+    let mut names = properties
+        .properties
+        .keys()
+        .filter(|name| name.starts_with("--"))
+        .cloned()
+        .collect::<HashSet<_>>();
+    if let Some(parent) = parent {
+        names.extend(parent.properties.keys().filter(|name| name.starts_with("--")).cloned());
+    }
+    names.extend(registry.properties.keys().cloned());
+
+    let mut resolver = Resolver {
+        properties,
+        parent,
+        registry,
+        computed: HashMap::new(),
+        stack: Vec::new(),
+        cyclic: HashSet::new(),
+    };
+    for name in &names {
+        resolver.resolve(name);
+    }
+
+    let computed = resolver.computed;
+    for (name, value) in computed {
+        properties
+            .properties
+            .entry(name.clone())
+            .or_insert_with(|| CssProperty::new(&name))
+            .computed = value;
+    }
+}
+
+/// Resolves the custom properties of a single element, keeping track of the properties that are
+/// being resolved to detect cycles
+struct Resolver<'a> {
+    properties: &'a CssProperties,
+    parent: Option<&'a CssProperties>,
+    registry: &'a PropertyRegistry,
+    computed: HashMap<String, CssValue>,
+    stack: Vec<String>,
+    cyclic: HashSet<String>,
+}
+
+impl Resolver<'_> {
+    fn resolve(&mut self, name: &str) -> CssValue {
+        if let Some(value) = self.computed.get(name) {
+            return value.clone();
+        }
+
+        // A reference back to a property that is being resolved: all properties in between are
+        // part of the cycle
+        if let Some(pos) = self.stack.iter().position(|n| n == name) {
+            self.cyclic.extend(self.stack[pos..].iter().cloned());
+            return CssValue::None;
+        }
+
+        let cascaded = self.properties.properties.get(name).and_then(|p| p.cascaded.as_ref());
+        let value = match cascaded.map(|decl| &decl.value) {
+            Some(CssValue::Inherit) => self.inherited(name),
+            Some(CssValue::Initial) => self.registry.initial_value(name),
+            Some(CssValue::Unset | CssValue::Revert | CssValue::RevertLayer) | None => self.unset(name),
+            // An empty value is valid for custom properties
+            Some(CssValue::None) => CssValue::List(Vec::new()),
+            Some(value) => {
+                let value = value.clone();
+                self.stack.push(name.to_string());
+                let substituted = substitute(&to_values(&value), &mut |name| self.resolve(name));
+                self.stack.pop();
+
+                let registration = self.registry.get(name);
+                let valid = |values: &[CssValue]| {
+                    registration
+                        .and_then(|rule| rule.syntax.as_ref())
+                        .is_none_or(|syntax| syntax.matches(values))
+                };
+
+                match substituted {
+                    _ if self.cyclic.contains(name) => CssValue::None,
+                    // Invalid at computed-value time, which behaves like `unset`
+                    Some(values) if !valid(&values) => self.unset(name),
+                    None => self.unset(name),
+                    Some(values) if values.is_empty() => CssValue::List(values),
+                    Some(values) => CssValue::from_list(values),
+                }
+            }
+        };
+
+        self.computed.insert(name.to_string(), value.clone());
+        value
+    }
+
+    fn inherited(&self, name: &str) -> CssValue {
+        self.parent
+            .and_then(|parent| parent.computed_value(name))
+            .cloned()
+            .unwrap_or_else(|| self.registry.initial_value(name))
+    }
+
+    /// Inherited properties take the value of the parent, others their initial value
+    fn unset(&self, name: &str) -> CssValue {
+        if self.registry.inherits(name) {
+            self.inherited(name)
+        } else {
+            self.registry.initial_value(name)
+        }
+    }
+}
+
+/// Substitutes `var()` in the cascaded values of all other properties, using the computed values of
+/// the custom properties. The substituted value is matched against the grammar of the property
+/// again. When it does not match, the property is invalid at computed-value time and the value
+/// becomes `unset` (CSS Variables 1, 3.1).
+pub fn substitute_variables(properties: &mut CssProperties) {
+    let custom = properties
+        .properties
+        .iter()
+        .filter(|(name, _)| name.starts_with("--"))
+        .map(|(name, property)| (name.clone(), property.computed.clone()))
+        .collect::<HashMap<_, _>>();
+    let mut lookup = |name: &str| custom.get(name).cloned().unwrap_or(CssValue::None);
+
+    for (name, property) in properties.properties.iter_mut() {
+        if name.starts_with("--") {
+            continue;
+        }
+
+        let declarations = property.declared.iter_mut().chain(property.cascaded.as_mut());
+        for declaration in declarations {
+            if needs_substitution(&declaration.value) {
+                declaration.value = substitute_value(name, &declaration.value, &mut lookup);
+            }
+        }
+    }
+}
+
+fn needs_substitution(value: &CssValue) -> bool {
+    match value {
+        CssValue::PendingSubstitution(..) => true,
+        CssValue::Function(name, args) => name == "var" || args.iter().any(needs_substitution),
+        CssValue::List(values) => values.iter().any(needs_substitution),
+        _ => false,
+    }
+}
+
+/// Substitutes the value of a single property. Longhands with a pending-substitution value take
+/// their part of the substituted shorthand.
+fn substitute_value(name: &str, value: &CssValue, lookup: &mut impl FnMut(&str) -> CssValue) -> CssValue {
+    let (property, values) = match value {
+        CssValue::PendingSubstitution(shorthand, values) => (shorthand.as_str(), values.clone()),
+        value => (name, to_values(value)),
+    };
+
+    let Some(values) = substitute(&values, lookup) else {
+        return CssValue::Unset;
+    };
+
+    // A css-wide keyword from a variable is not allowed, it must be written in the declaration
+    let valid = get_css_definition(property).is_none_or(|def| def.syntax.matches(&values));
+    if !valid {
+        return CssValue::Unset;
+    }
+
+    if property == name {
+        return CssValue::from_list(values);
+    }
+
+    expand_shorthand(property, &values)
+        .and_then(|longhands| longhands.into_iter().find(|(longhand, _)| *longhand == name))
+        .map(|(_, values)| CssValue::from_list(values))
+        .unwrap_or(CssValue::Unset)
+}
+
+/// Replaces all `var()` functions in the values by the values of the custom properties (or their
+/// fallback). Returns None when a custom property has the guaranteed-invalid value and there is no
+/// fallback.
+fn substitute(values: &[CssValue], lookup: &mut impl FnMut(&str) -> CssValue) -> Option<Vec<CssValue>> {
+    let mut result = Vec::with_capacity(values.len());
+
+    for value in values {
+        match value {
+            CssValue::Function(fname, args) if fname == "var" => {
+                let (name, fallback) = match args.as_slice() {
+                    [CssValue::Keyword(name)] if name.starts_with("--") => (name, None),
+                    [CssValue::Keyword(name), CssValue::Comma, fallback @ ..] if name.starts_with("--") => {
+                        (name, Some(fallback))
+                    }
+                    _ => return None,
+                };
+
+                match lookup(name) {
+                    CssValue::None => result.extend(substitute(fallback?, lookup)?),
+                    value => result.extend(to_values(&value)),
+                }
+            }
+            CssValue::Function(fname, args) => {
+                result.push(CssValue::Function(fname.clone(), substitute(args, lookup)?));
+            }
+            CssValue::List(values) => result.push(CssValue::List(substitute(values, lookup)?)),
+            value => result.push(value.clone()),
+        }
+    }
+
+    Some(result)
+}
+
+fn to_values(value: &CssValue) -> Vec<CssValue> {
+    match value {
+        CssValue::None => Vec::new(),
+        CssValue::List(values) => values.clone(),
+        value => vec![value.clone()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interface::css3::{CssOrigin, CssProperty, CssPropertyMap},
+        testing::TestDocument,
+    };
+
+    /// Builds `<div id=p><div id=c>` with the given author stylesheet
+    fn doc(css: &str) -> TestDocument {
+        let doc = TestDocument::parse(r#"<div id="p"><div id="c">text</div></div>"#);
+        doc.add_css(css, CssOrigin::Author);
+        doc
+    }
+
+    fn px(doc: &TestDocument, id: &str, property: &str) -> Option<f32> {
+        doc.style(id).get(property).and_then(|value| value.as_px())
+    }
+
+    #[test]
+    fn substitution_and_inheritance() {
+        let d = doc("#p { --gap: 10px; --pair: 1px 2px } #c { margin-left: var(--gap); padding: var(--pair) }");
+        assert_eq!(px(&d, "c", "margin-left"), Some(10.0));
+        // Shorthands are expanded after substitution
+        assert_eq!(px(&d, "c", "padding-top"), Some(1.0));
+        assert_eq!(px(&d, "c", "padding-left"), Some(2.0));
+
+        // References between custom properties are resolved on the element that declares them
+        let d =
+            doc("#p { font-size: 10px; --a: calc(var(--b) * 2); --b: 3em } #c { font-size: 20px; width: var(--a) }");
+        assert_eq!(px(&d, "c", "width"), Some(120.0));
+    }
+
+    #[test]
+    fn fallbacks() {
+        let d = doc("#c { margin-left: var(--missing, 5px); margin-right: var(--a, var(--b, 3px)); --b: 4px }");
+        assert_eq!(px(&d, "c", "margin-left"), Some(5.0));
+        assert_eq!(px(&d, "c", "margin-right"), Some(4.0));
+
+        // A value that does not match the grammar of the property is invalid at computed-value
+        // time, and the property behaves like `unset`
+        let d = doc("#p { color: rgb(0, 0, 255) } \
+             #c { --color: 12px; color: var(--color); margin-left: var(--color, 5px) }");
+        assert_eq!(d.style("c").get("color").unwrap().as_color(), Some((0, 0, 255, 255)));
+        assert_eq!(px(&d, "c", "margin-left"), Some(12.0));
+        let d = doc("#c { --color: 12px; background-color: var(--color) }");
+        assert_eq!(
+            d.style("c").get("background-color").unwrap().as_color(),
+            Some((0, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        let d = doc(
+            "#c { --a: var(--b); --b: var(--a); --c: var(--c, 1px); --d: var(--a, 2px); \
+             margin-left: var(--a, 7px); margin-right: var(--c, 8px); width: var(--d, 9px) }",
+        );
+        assert_eq!(px(&d, "c", "margin-left"), Some(7.0));
+        assert_eq!(px(&d, "c", "margin-right"), Some(8.0));
+        // A property that refers to a cycle without being part of it uses its fallback
+        assert_eq!(px(&d, "c", "width"), Some(2.0));
+    }
+
+    #[test]
+    fn registered_properties() {
+        let d = doc(
+            "@property --size { syntax: '<length>'; inherits: false; initial-value: 4px } \
+             #p { font-size: 10px; --size: 2em; width: var(--size) } \
+             #c { height: var(--size); --size: red; margin-left: var(--size) }",
+        );
+        // Typed properties are computed, so `em` refers to the element that declares them
+        assert_eq!(px(&d, "p", "width"), Some(20.0));
+        // Not inherited, and an invalid value falls back to the initial value
+        assert_eq!(px(&d, "c", "height"), Some(4.0));
+        assert_eq!(px(&d, "c", "margin-left"), Some(4.0));
+
+        let d = doc(
+            "@property --inherited { syntax: '<length>'; inherits: true; initial-value: 0px } \
+             #p { --inherited: 6px } #c { width: var(--inherited) }",
+        );
+        assert_eq!(px(&d, "c", "width"), Some(6.0));
+    }
+}
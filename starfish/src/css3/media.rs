@@ -0,0 +1,696 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    css3::{
+        matcher::computed::{length_to_px, MEDIUM_FONT_SIZE},
+        stylesheet::CssValue,
+    },
+    interface::css3::{ColorScheme, MediaEnvironment},
+};
+
+/// A comma separated list of media queries (Media Queries 4, 2). The list matches when any of its
+/// queries match. An empty list matches everything.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MediaQueryList(pub Vec<MediaQuery>);
+
+/// A single media query: `[not | only]? <media-type> [and <media-condition>]?` or a condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    /// True for `not screen and (...)`, which negates the whole query
+    pub negated: bool,
+    /// Media type like `screen` or `print`. None when the query is only a condition.
+    pub media_type: Option<String>,
+    pub condition: Option<MediaCondition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaCondition {
+    Not(Box<MediaCondition>),
+    And(Vec<MediaCondition>),
+    Or(Vec<MediaCondition>),
+    Feature(MediaFeature),
+    /// Syntax that is not understood (`<general-enclosed>`), which evaluates to unknown
+    Unknown(String),
+}
+
+/// A media feature test, like `(min-width: 600px)` or `(400px <= width < 800px)`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaFeature {
+    /// `(name)`: the feature is tested in a boolean context
+    Boolean(String),
+    /// `(name: value)`, where `min-` and `max-` prefixes are converted into range comparisons
+    Plain(String, MediaValue),
+    /// A range test. Every comparison is read as `name <op> value`.
+    Range(String, Vec<(MediaComparison, MediaValue)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaValue {
+    Number(f32),
+    Dimension(f32, String),
+    Ident(String),
+    Ratio(f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaComparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl MediaComparison {
+    /// The comparison with its operands swapped: `a < b` is `b > a`
+    fn flip(self) -> Self {
+        match self {
+            Self::Lt => Self::Gt,
+            Self::Le => Self::Ge,
+            Self::Gt => Self::Lt,
+            Self::Ge => Self::Le,
+            Self::Eq => Self::Eq,
+        }
+    }
+
+    fn operator(self) -> &'static str {
+        match self {
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Eq => "=",
+        }
+    }
+
+    fn compare(self, a: f32, b: f32) -> bool {
+        match self {
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+            Self::Eq => (a - b).abs() < f32::EPSILON,
+        }
+    }
+}
+
+impl MediaQueryList {
+    /// Parses the prelude of an `@media` rule. Invalid queries are replaced by `not all`, so they
+    /// never match without affecting the other queries in the list (Media Queries 4, 3.2).
+    pub fn parse(values: &[CssValue]) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+
+        let queries = values
+            .split(|value| *value == CssValue::Comma)
+            .map(|values| MediaQuery::parse(values).unwrap_or_else(MediaQuery::not_all))
+            .collect();
+
+        Self(queries)
+    }
+
+    /// Returns true when any of the queries match the environment
+    pub fn matches(&self, env: &MediaEnvironment) -> bool {
+        self.0.is_empty() || self.0.iter().any(|query| query.matches(env))
+    }
+}
+
+impl MediaQuery {
+    fn not_all() -> Self {
+        Self {
+            negated: true,
+            media_type: Some("all".into()),
+            condition: None,
+        }
+    }
+
+    fn parse(values: &[CssValue]) -> Option<Self> {
+        // A query that starts with a condition has no media type
+        let starts_with_condition = match values {
+            [CssValue::Function(name, _), ..] => name == "(",
+            [not, CssValue::Function(name, _), ..] => not.is_keyword("not") && name == "(",
+            _ => false,
+        };
+        if starts_with_condition {
+            return Some(Self {
+                negated: false,
+                media_type: None,
+                condition: Some(parse_condition(values, true)?),
+            });
+        }
+
+        let (negated, values) = match values {
+            [modifier, rest @ ..] if modifier.is_keyword("not") => (true, rest),
+            [modifier, rest @ ..] if modifier.is_keyword("only") => (false, rest),
+            _ => (false, values),
+        };
+
+        let (media_type, rest) = match values {
+            [CssValue::Keyword(media_type), rest @ ..] => (media_type.to_ascii_lowercase(), rest),
+            _ => return None,
+        };
+        if ["not", "and", "or", "only", "layer"].contains(&media_type.as_str()) {
+            return None;
+        }
+
+        let condition = match rest {
+            [] => None,
+            [and, rest @ ..] if and.is_keyword("and") => Some(parse_condition(rest, false)?),
+            _ => return None,
+        };
+
+        Some(Self {
+            negated,
+            media_type: Some(media_type),
+            condition,
+        })
+    }
+
+    fn matches(&self, env: &MediaEnvironment) -> bool {
+        let type_matches = matches!(self.media_type.as_deref(), None | Some("all" | "screen"));
+
+        // An unknown result is handled as false, also when the query is negated
+        let result = match &self.condition {
+            Some(condition) if type_matches => condition.evaluate(env),
+            _ => Some(type_matches),
+        };
+
+        match result {
+            Some(result) => result != self.negated,
+            None => false,
+        }
+    }
+}
+
+impl MediaCondition {
+    /// Evaluates the condition with three-valued logic: None means unknown (Media Queries 4, 3.2)
+    pub fn evaluate(&self, env: &MediaEnvironment) -> Option<bool> {
+        match self {
+            Self::Not(condition) => condition.evaluate(env).map(|result| !result),
+            Self::And(conditions) => {
+                let results = conditions.iter().map(|c| c.evaluate(env)).collect::<Vec<_>>();
+                if results.contains(&Some(false)) {
+                    Some(false)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(true)
+                }
+            }
+            Self::Or(conditions) => {
+                let results = conditions.iter().map(|c| c.evaluate(env)).collect::<Vec<_>>();
+                if results.contains(&Some(true)) {
+                    Some(true)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(false)
+                }
+            }
+            Self::Feature(feature) => feature.evaluate(env),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+/// `<media-condition>`: `not <media-in-parens>`, or media-in-parens joined by only `and` or only
+/// `or`. `allow_or` is false for the condition after a media type.
+fn parse_condition(values: &[CssValue], allow_or: bool) -> Option<MediaCondition> {
+    if let [not, value] = values {
+        if not.is_keyword("not") {
+            return Some(MediaCondition::Not(Box::new(parse_in_parens(value)?)));
+        }
+    }
+
+    let (first, mut rest) = values.split_first()?;
+    let mut conditions = vec![parse_in_parens(first)?];
+    let mut operator = None;
+
+    while let [CssValue::Keyword(keyword), value, tail @ ..] = rest {
+        let keyword = keyword.to_ascii_lowercase();
+        if keyword != "and" && (keyword != "or" || !allow_or) {
+            return None;
+        }
+        // `and` and `or` cannot be mixed without parentheses
+        if operator.get_or_insert(keyword.clone()) != &keyword {
+            return None;
+        }
+
+        conditions.push(parse_in_parens(value)?);
+        rest = tail;
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+
+    Some(match operator.as_deref() {
+        None => conditions.remove(0),
+        Some("and") => MediaCondition::And(conditions),
+        _ => MediaCondition::Or(conditions),
+    })
+}
+
+/// `<media-in-parens>`: a nested condition, a media feature or anything else in parentheses
+fn parse_in_parens(value: &CssValue) -> Option<MediaCondition> {
+    match value {
+        CssValue::Function(name, inner) if name == "(" => {
+            let nested = match inner.first() {
+                Some(CssValue::Function(name, _)) => name == "(",
+                Some(first) => first.is_keyword("not"),
+                None => false,
+            };
+
+            let condition = if nested {
+                parse_condition(inner, true)
+            } else {
+                parse_feature(inner).map(MediaCondition::Feature)
+            };
+            Some(condition.unwrap_or_else(|| MediaCondition::Unknown(value.to_string())))
+        }
+        // Other functions are `<general-enclosed>`
+        CssValue::Function(..) => Some(MediaCondition::Unknown(value.to_string())),
+        _ => None,
+    }
+}
+
+/// Parses the contents of `( <media-feature> )`
+fn parse_feature(values: &[CssValue]) -> Option<MediaFeature> {
+    match values {
+        [CssValue::Keyword(name)] => return Some(MediaFeature::Boolean(name.to_ascii_lowercase())),
+        [CssValue::Keyword(name), CssValue::Delim(':'), value @ ..] => {
+            let name = name.to_ascii_lowercase();
+            let value = parse_value(value)?;
+            return Some(match (name.strip_prefix("min-"), name.strip_prefix("max-")) {
+                (Some(name), _) => MediaFeature::Range(name.into(), vec![(MediaComparison::Ge, value)]),
+                (_, Some(name)) => MediaFeature::Range(name.into(), vec![(MediaComparison::Le, value)]),
+                _ => MediaFeature::Plain(name, value),
+            });
+        }
+        _ => {}
+    }
+
+    // Range syntax: split the values at the comparison operators
+    let mut parts: Vec<&[CssValue]> = Vec::new();
+    let mut operators = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < values.len() {
+        let operator = match (&values[i], values.get(i + 1)) {
+            (CssValue::Delim('<'), Some(CssValue::Delim('='))) => Some((MediaComparison::Le, 2)),
+            (CssValue::Delim('>'), Some(CssValue::Delim('='))) => Some((MediaComparison::Ge, 2)),
+            (CssValue::Delim('<'), _) => Some((MediaComparison::Lt, 1)),
+            (CssValue::Delim('>'), _) => Some((MediaComparison::Gt, 1)),
+            (CssValue::Delim('='), _) => Some((MediaComparison::Eq, 1)),
+            _ => None,
+        };
+        match operator {
+            Some((operator, len)) => {
+                parts.push(&values[start..i]);
+                operators.push(operator);
+                i += len;
+                start = i;
+            }
+            None => i += 1,
+        }
+    }
+    parts.push(&values[start..]);
+
+    match (parts.as_slice(), operators.as_slice()) {
+        // `name < value` or `value < name`
+        ([a, b], [op]) => match feature_name(a) {
+            Some(name) => Some(MediaFeature::Range(name, vec![(*op, parse_value(b)?)])),
+            None => Some(MediaFeature::Range(
+                feature_name(b)?,
+                vec![(op.flip(), parse_value(a)?)],
+            )),
+        },
+        // `value < name < value`, where both operators must point the same way
+        ([a, b, c], [op1, op2]) => {
+            let increasing = matches!(op1, MediaComparison::Lt | MediaComparison::Le)
+                && matches!(op2, MediaComparison::Lt | MediaComparison::Le);
+            let decreasing = matches!(op1, MediaComparison::Gt | MediaComparison::Ge)
+                && matches!(op2, MediaComparison::Gt | MediaComparison::Ge);
+            if !increasing && !decreasing {
+                return None;
+            }
+            Some(MediaFeature::Range(
+                feature_name(b)?,
+                vec![(op1.flip(), parse_value(a)?), (*op2, parse_value(c)?)],
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn feature_name(part: &[CssValue]) -> Option<String> {
+    match part {
+        [CssValue::Keyword(name)] => Some(name.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+/// `<mf-value>`: a number, dimension, ident or ratio
+fn parse_value(values: &[CssValue]) -> Option<MediaValue> {
+    Some(match values {
+        [CssValue::Number(n)] => MediaValue::Number(*n),
+        [CssValue::Unit(v, unit)] => MediaValue::Dimension(*v, unit.clone()),
+        [CssValue::Keyword(ident)] => MediaValue::Ident(ident.to_ascii_lowercase()),
+        [CssValue::Number(a), CssValue::Delim('/'), CssValue::Number(b)] => MediaValue::Ratio(*a, *b),
+        _ => return None,
+    })
+}
+
+impl MediaValue {
+    /// Converts a length into pixels. Relative lengths use the initial font size (Media Queries 4,
+    /// 1.3).
+    fn to_px(&self) -> Option<f32> {
+        match self {
+            Self::Number(n) if *n == 0.0 => Some(0.0),
+            Self::Dimension(v, unit) => length_to_px(*v, unit, MEDIUM_FONT_SIZE, MEDIUM_FONT_SIZE, None),
+            _ => None,
+        }
+    }
+
+    fn to_ratio(&self) -> Option<f32> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::Ratio(a, b) => Some(a / b),
+            _ => None,
+        }
+    }
+
+    fn to_dppx(&self) -> Option<f32> {
+        match self {
+            Self::Dimension(v, unit) => match unit.as_str() {
+                "dppx" | "x" => Some(*v),
+                "dpi" => Some(v / 96.0),
+                "dpcm" => Some(v * 2.54 / 96.0),
+                _ => None,
+            },
+            Self::Ident(ident) if ident == "infinite" => Some(f32::INFINITY),
+            _ => None,
+        }
+    }
+}
+
+impl MediaFeature {
+    fn evaluate(&self, env: &MediaEnvironment) -> Option<bool> {
+        match self {
+            Self::Boolean(name) => evaluate_boolean(name, env),
+            Self::Plain(name, value) => match discrete_value(name, env) {
+                Some(expected) => match value {
+                    MediaValue::Ident(ident) => Some(*ident == expected),
+                    _ => None,
+                },
+                None => evaluate_range(name, &[(MediaComparison::Eq, value.clone())], env),
+            },
+            Self::Range(name, comparisons) => evaluate_range(name, comparisons, env),
+        }
+    }
+}
+
+/// Returns the value of a feature with discrete (keyword) values
+fn discrete_value(name: &str, env: &MediaEnvironment) -> Option<&'static str> {
+    Some(match name {
+        "orientation" if env.height >= env.width => "portrait",
+        "orientation" => "landscape",
+        "prefers-color-scheme" => match env.color_scheme {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        },
+        "prefers-reduced-motion" if env.reduced_motion => "reduce",
+        "prefers-reduced-motion" => "no-preference",
+        "hover" | "any-hover" => "hover",
+        "pointer" | "any-pointer" => "fine",
+        "scan" => "progressive",
+        "update" => "fast",
+        _ => return None,
+    })
+}
+
+/// Evaluates a feature in a boolean context: true unless the value is zero or `none`
+fn evaluate_boolean(name: &str, env: &MediaEnvironment) -> Option<bool> {
+    match name {
+        "width" => Some(env.width != 0.0),
+        "height" => Some(env.height != 0.0),
+        "aspect-ratio" | "resolution" | "color" => Some(true),
+        "monochrome" | "grid" | "color-index" => Some(false),
+        "prefers-reduced-motion" => Some(env.reduced_motion),
+        _ => discrete_value(name, env).map(|_| true),
+    }
+}
+
+fn evaluate_range(name: &str, comparisons: &[(MediaComparison, MediaValue)], env: &MediaEnvironment) -> Option<bool> {
+    let actual = match name {
+        "width" | "device-width" => env.width,
+        "height" | "device-height" => env.height,
+        "aspect-ratio" | "device-aspect-ratio" => env.width / env.height,
+        "resolution" => env.resolution,
+        // 8 bits per color channel, and not a monochrome or indexed device
+        "color" => 8.0,
+        "monochrome" | "color-index" | "grid" => 0.0,
+        _ => return None,
+    };
+
+    for (comparison, value) in comparisons {
+        let expected = match name {
+            "aspect-ratio" | "device-aspect-ratio" => value.to_ratio()?,
+            "resolution" => value.to_dppx()?,
+            "color" | "monochrome" | "color-index" | "grid" => match value {
+                MediaValue::Number(n) => *n,
+                _ => return None,
+            },
+            _ => value.to_px()?,
+        };
+        if !comparison.compare(actual, expected) {
+            return Some(false);
+        }
+    }
+
+    Some(true)
+}
+
+impl Display for MediaQueryList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, query) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{query}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for MediaQuery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "not ")?;
+        }
+        match (&self.media_type, &self.condition) {
+            (Some(media_type), Some(condition)) => write!(f, "{media_type} and {condition}"),
+            (Some(media_type), None) => write!(f, "{media_type}"),
+            (None, Some(condition)) => write!(f, "{condition}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl Display for MediaCondition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut Formatter<'_>, conditions: &[MediaCondition], operator: &str| {
+            for (i, condition) in conditions.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {operator} ")?;
+                }
+                match condition {
+                    Self::Feature(_) | Self::Unknown(_) => write!(f, "{condition}")?,
+                    _ => write!(f, "({condition})")?,
+                }
+            }
+            Ok(())
+        };
+
+        match self {
+            Self::Not(condition) => match condition.as_ref() {
+                Self::Feature(_) | Self::Unknown(_) => write!(f, "not {condition}"),
+                _ => write!(f, "not ({condition})"),
+            },
+            Self::And(conditions) => join(f, conditions, "and"),
+            Self::Or(conditions) => join(f, conditions, "or"),
+            Self::Feature(feature) => write!(f, "({feature})"),
+            Self::Unknown(source) => write!(f, "{source}"),
+        }
+    }
+}
+
+impl Display for MediaFeature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Boolean(name) => write!(f, "{name}"),
+            Self::Plain(name, value) => write!(f, "{name}: {value}"),
+            // A range with two comparisons is written as `value < name < value`
+            Self::Range(name, comparisons) => match comparisons.as_slice() {
+                [(first, min), (second, max)] => {
+                    write!(
+                        f,
+                        "{min} {} {name} {} {max}",
+                        first.flip().operator(),
+                        second.operator()
+                    )
+                }
+                _ => {
+                    write!(f, "{name}")?;
+                    for (comparison, value) in comparisons {
+                        write!(f, " {} {value}", comparison.operator())?;
+                    }
+                    Ok(())
+                }
+            },
+        }
+    }
+}
+
+impl Display for MediaValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Dimension(v, unit) => write!(f, "{v}{unit}"),
+            Self::Ident(ident) => write!(f, "{ident}"),
+            Self::Ratio(a, b) => write!(f, "{a}/{b}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        css3::parse_value,
+        interface::css3::{CssOrigin, CssProperty, CssPropertyMap},
+        shared::geo::SizeU32,
+        testing::{TestConfig, TestDocument},
+        util::render_tree::RenderTree,
+    };
+
+    fn parse(query: &str) -> MediaQueryList {
+        MediaQueryList::parse(&parse_value(query).unwrap())
+    }
+
+    fn env(width: f32, height: f32) -> MediaEnvironment {
+        MediaEnvironment {
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    fn matches(query: &str, env: &MediaEnvironment) -> bool {
+        parse(query).matches(env)
+    }
+
+    #[test]
+    fn size_features() {
+        let env = env(800.0, 600.0);
+        assert!(matches("(min-width: 600px)", &env));
+        assert!(!matches("(max-width: 600px)", &env));
+        assert!(matches("(width: 800px)", &env));
+        assert!(matches("(width >= 50em)", &env));
+        assert!(matches("(400px <= width < 801px)", &env));
+        assert!(!matches("(400px <= width < 800px)", &env));
+        assert!(matches("(600px >= height)", &env));
+        assert!(matches("(aspect-ratio: 4/3)", &env));
+        assert!(matches("(min-aspect-ratio: 1)", &env));
+        assert!(!matches("(aspect-ratio > 16/9)", &env));
+        assert!(matches("(orientation: landscape)", &env));
+        assert!(matches("(width)", &env));
+    }
+
+    #[test]
+    fn other_features() {
+        let mut env = env(800.0, 600.0);
+        assert!(matches("(resolution: 1dppx)", &env));
+        assert!(matches("(resolution: 96dpi)", &env));
+        assert!(!matches("(min-resolution: 2x)", &env));
+        assert!(matches("(prefers-color-scheme: light)", &env));
+        assert!(!matches("(prefers-reduced-motion)", &env));
+        assert!(matches("(color)", &env));
+
+        env.resolution = 2.0;
+        env.color_scheme = ColorScheme::Dark;
+        env.reduced_motion = true;
+        assert!(matches("(min-resolution: 2x)", &env));
+        assert!(matches("(prefers-color-scheme: dark)", &env));
+        assert!(matches("(prefers-reduced-motion: reduce)", &env));
+    }
+
+    #[test]
+    fn logic() {
+        let env = env(800.0, 600.0);
+        assert!(matches(
+            "screen and (min-width: 600px) and (orientation: landscape)",
+            &env
+        ));
+        assert!(matches("(max-width: 600px) or (min-height: 500px)", &env));
+        assert!(matches("not (max-width: 600px)", &env));
+        assert!(matches("not print and (min-width: 600px)", &env));
+        assert!(matches("only screen", &env));
+        assert!(!matches("print", &env));
+        assert!(matches("print, (min-width: 600px)", &env));
+        assert!(matches("", &env));
+
+        // `or` is not allowed after a media type, and invalid queries become `not all`
+        assert!(!matches("screen and (min-width: 1px) or (color)", &env));
+        assert!(matches("screen and, all", &env));
+        // Unknown features evaluate to unknown, which does not match, even when negated
+        assert!(!matches("(frobnicate: 1)", &env));
+        assert!(!matches("not (frobnicate: 1)", &env));
+        assert!(matches("(frobnicate: 1) or (color)", &env));
+    }
+
+    #[test]
+    fn serialization() {
+        assert_eq!(
+            parse("screen and (min-width: 600px)").to_string(),
+            "screen and (width >= 600px)"
+        );
+        assert_eq!(
+            parse("not all and (monochrome)").to_string(),
+            "not all and (monochrome)"
+        );
+        assert_eq!(
+            parse("(400px <= width < 800px)").to_string(),
+            "(400px <= width < 800px)"
+        );
+    }
+
+    #[test]
+    fn restyle_when_queries_change() {
+        let doc = TestDocument::parse(r#"<div id="a">a</div><div id="b">b</div>"#);
+        doc.add_css(
+            "#b { margin-left: 1px } @media (max-width: 600px) { #a { display: none } #b { margin-left: 2px } }",
+            CssOrigin::Author,
+        );
+        let mut tree = doc.render_tree();
+        let margin = |tree: &RenderTree<TestConfig>| {
+            let node = tree.node_for_dom(doc.id("b")).unwrap();
+            tree.nodes[&node]
+                .properties
+                .get("margin-left")
+                .and_then(|value| value.as_px())
+        };
+
+        assert!(!tree.set_media(MediaEnvironment::from_size(SizeU32::new(800, 600))));
+        assert!(tree.node_for_dom(doc.id("a")).is_some());
+        assert_eq!(margin(&tree), Some(1.0));
+
+        assert!(tree.set_media(MediaEnvironment::from_size(SizeU32::new(500, 600))));
+        assert!(tree.node_for_dom(doc.id("a")).is_none());
+        assert_eq!(margin(&tree), Some(2.0));
+
+        assert!(!tree.set_media(MediaEnvironment::from_size(SizeU32::new(400, 600))));
+        assert!(tree.set_media(MediaEnvironment::from_size(SizeU32::new(700, 600))));
+        assert_eq!(margin(&tree), Some(1.0));
+    }
+}
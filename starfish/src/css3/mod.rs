@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 
 use crate::{
     interface::{
@@ -5,16 +6,25 @@ use crate::{
         ParserConfig,
     },
     shared::{
-        byte_stream::{ByteStream, Encoding, Location},
-        errors::{CssError, CssResult},
+        byte_stream::{ByteStream, Encoding},
+        errors::CssResult,
     },
 };
 
+pub mod calc;
+pub mod colors;
+pub mod matcher;
+pub mod media;
+pub mod parser;
+pub mod shorthands;
 pub mod stylesheet;
+pub mod supports;
+pub mod syntax;
 pub mod system;
 pub mod tokenizer;
+pub mod vendor;
 
-use stylesheet::CssStylesheet;
+use stylesheet::{CssStylesheet, CssValue};
 use tokenizer::Tokenizer;
 
 pub struct Css3<'stream> {
@@ -28,16 +38,39 @@ pub struct Css3<'stream> {
     origin: CssOrigin,
     /// Source of the stream (filename, url, etc.)
     source: String,
+    /// Number of anonymous cascade layers found so far
+    anonymous_layers: usize,
+    /// Namespace prefixes declared with `@namespace`
+    namespaces: HashMap<String, String>,
+    /// Default namespace for type selectors, declared with `@namespace` without prefix
+    default_namespace: Option<String>,
+    /// Which rules are still allowed, as `@import` and `@namespace` must come first
+    position: SheetPosition,
+}
+
+/// Position in the stylesheet, to find out if `@import` and `@namespace` rules are still allowed
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum SheetPosition {
+    /// Only `@charset`, `@layer` statements and `@import` rules were found so far
+    Imports,
+    /// `@namespace` rules were found
+    Namespaces,
+    /// Any other rule was found
+    Body,
 }
 
 impl<'stream> Css3<'stream> {
     fn new(stream: &'stream mut ByteStream, config: ParserConfig, origin: CssOrigin, source: &str) -> Self {
         Self {
-            tokenizer: Tokenizer::new(stream, Location::default()),
+            tokenizer: Tokenizer::new(stream, config.location),
             allow_values_in_argument_list: Vec::new(),
             config,
             origin,
             source: source.to_string(),
+            anonymous_layers: 0,
+            namespaces: HashMap::new(),
+            default_namespace: None,
+            position: SheetPosition::Imports,
         }
     }
 
@@ -66,8 +99,27 @@ impl<'stream> Css3<'stream> {
     }
 
     fn parse(&mut self) -> CssResult<CssStylesheet> {
-        Err(CssError::new("No node tree found"))
+        self.parse_stylesheet_internal()
+    }
+}
+
+/// Parses a single (property) value like `1px solid red` into a list of css values
+pub fn parse_value(data: &str) -> CssResult<Vec<CssValue>> {
+    let mut stream = ByteStream::new(Encoding::UTF8, None);
+    stream.read_from_str(data, Some(Encoding::UTF8));
+    stream.close();
+
+    let mut parser = Css3::new(&mut stream, ParserConfig::default(), CssOrigin::Author, "");
+    let mut tokens = Vec::new();
+    loop {
+        let t = parser.tokenizer.consume();
+        if t.is_eof() {
+            break;
+        }
+        tokens.push(t);
     }
+
+    parser.parse_component_values(tokens)
 }
 
 // Loads the default user agent stylesheet
@@ -0,0 +1,96 @@
+use crate::{
+    css3::{
+        tokenizer::{Token, TokenType},
+        Css3,
+    },
+    shared::errors::{CssError, CssResult},
+};
+
+mod at_rule;
+mod declaration;
+mod selector;
+mod stylesheet;
+mod value;
+
+impl Css3<'_> {
+    /// Skips over whitespace and comments
+    pub(crate) fn consume_whitespace_comments(&mut self) {
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            if !t.is_whitespace() && !t.is_comment() {
+                return;
+            }
+            self.tokenizer.consume();
+        }
+    }
+
+    /// Consumes the next token and returns an error when it's not of the given type
+    pub(crate) fn consume(&mut self, token_type: TokenType) -> CssResult<Token> {
+        let t = self.tokenizer.consume();
+        if t.token_type != token_type {
+            return Err(CssError::with_location(
+                format!("Expected {:?}, got {:?}", token_type, t.token_type).as_str(),
+                t.location,
+            ));
+        }
+
+        Ok(t)
+    }
+
+    /// Consumes an ident token and returns its value
+    pub(crate) fn consume_any_ident(&mut self) -> CssResult<String> {
+        let t = self.tokenizer.consume();
+        match t.token_type {
+            TokenType::Ident(s) => Ok(s),
+            _ => Err(CssError::with_location(
+                format!("Expected ident, got {:?}", t.token_type).as_str(),
+                t.location,
+            )),
+        }
+    }
+
+    /// Skips a complete component value. When the next token opens a block or function, the
+    /// whole (nested) block is skipped.
+    pub(crate) fn skip_component_value(&mut self) {
+        let t = self.tokenizer.consume();
+        let closing = match t.token_type {
+            TokenType::LCurly => TokenType::RCurly,
+            TokenType::LParen | TokenType::Function(_) => TokenType::RParen,
+            TokenType::LBracket => TokenType::RBracket,
+            _ => return,
+        };
+
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            if t.is_eof() {
+                return;
+            }
+            if t.token_type == closing {
+                self.tokenizer.consume();
+                return;
+            }
+            self.skip_component_value();
+        }
+    }
+
+    /// Skips component values until (and including) a semicolon or a block at the current nesting
+    /// level. A closing curly bracket will not be consumed. This is used to recover from errors
+    /// in rules and declarations.
+    pub(crate) fn skip_until_semicolon_or_block(&mut self) {
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Eof | TokenType::RCurly => return,
+                TokenType::Semicolon => {
+                    self.tokenizer.consume();
+                    return;
+                }
+                TokenType::LCurly => {
+                    self.skip_component_value();
+                    return;
+                }
+                _ => self.skip_component_value(),
+            }
+        }
+    }
+}
@@ -0,0 +1,524 @@
+use crate::{
+    css3::{
+        calc::LENGTH_UNITS,
+        media::MediaQueryList,
+        parser::stylesheet::log_warning,
+        stylesheet::{CssImport, CssPropertyRule, CssStylesheet, CssValue},
+        supports::{evaluate_import_supports, evaluate_supports},
+        syntax::CssSyntax,
+        tokenizer::{Token, TokenType},
+        Css3, SheetPosition,
+    },
+    shared::errors::{CssError, CssResult},
+};
+
+impl Css3<'_> {
+    /// Parses an at-rule. The at-keyword has already been consumed.
+    pub(crate) fn parse_at_rule(
+        &mut self,
+        name: &str,
+        sheet: &mut CssStylesheet,
+        layer: Option<&str>,
+        media: &[MediaQueryList],
+    ) -> CssResult<()> {
+        let name = name.to_ascii_lowercase();
+        if !matches!(name.as_str(), "charset" | "import" | "layer" | "namespace") {
+            self.position = SheetPosition::Body;
+        }
+
+        match name.as_str() {
+            "charset" => {
+                self.skip_until_semicolon_or_block();
+                Ok(())
+            }
+            "import" => self.parse_at_rule_import(sheet),
+            "layer" => self.parse_at_rule_layer(sheet, layer, media),
+            "media" => self.parse_at_rule_media(sheet, layer, media),
+            "namespace" => self.parse_at_rule_namespace(),
+            "property" => self.parse_at_rule_property(sheet),
+            "supports" => self.parse_at_rule_supports(sheet, layer, media),
+            _ => {
+                // Unknown at-rules are ignored (CSS Syntax 3, 9)
+                let location = self.tokenizer.current_location();
+                self.skip_until_semicolon_or_block();
+                log_warning(sheet, &format!("Unsupported at-rule @{} ignored", name), location);
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Parses `@layer a, b.c;` (layer statement) or `@layer name { ... }` (layer block)
+    fn parse_at_rule_layer(
+        &mut self,
+        sheet: &mut CssStylesheet,
+        parent: Option<&str>,
+        media: &[MediaQueryList],
+    ) -> CssResult<()> {
+        let mut names = Vec::new();
+
+        loop {
+            self.consume_whitespace_comments();
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Ident(_) => names.push(self.parse_layer_name()?),
+                TokenType::Semicolon | TokenType::Eof => {
+                    self.tokenizer.consume();
+                    if names.is_empty() {
+                        return Err(CssError::with_location("Empty @layer statement", t.location));
+                    }
+                    for name in names {
+                        register_layer(sheet, parent, &name);
+                    }
+                    return Ok(());
+                }
+                TokenType::LCurly => {
+                    self.tokenizer.consume();
+                    self.position = SheetPosition::Body;
+                    if names.len() > 1 {
+                        self.skip_block_contents();
+                        return Err(CssError::with_location(
+                            "Only one layer name allowed in @layer block",
+                            t.location,
+                        ));
+                    }
+
+                    let name = match names.pop() {
+                        Some(name) => name,
+                        None => {
+                            // Anonymous layers cannot be referenced, so they get a unique name
+                            self.anonymous_layers += 1;
+                            format!("#anonymous-{}", self.anonymous_layers)
+                        }
+                    };
+                    let full_name = register_layer(sheet, parent, &name);
+
+                    return self.parse_rule_list(sheet, Some(&full_name), media, false);
+                }
+                TokenType::Comma if !names.is_empty() => {
+                    self.tokenizer.consume();
+                }
+                _ => {
+                    self.skip_until_semicolon_or_block();
+                    return Err(CssError::with_location("Invalid @layer rule", t.location));
+                }
+            }
+        }
+    }
+
+    /// Parses `@media <media-query-list> { <rules> }`. The rules only apply when the queries (and those
+    /// of all enclosing `@media` rules) match.
+    fn parse_at_rule_media(
+        &mut self,
+        sheet: &mut CssStylesheet,
+        layer: Option<&str>,
+        media: &[MediaQueryList],
+    ) -> CssResult<()> {
+        let tokens = self.consume_prelude_tokens();
+
+        let t = self.tokenizer.consume();
+        if t.token_type != TokenType::LCurly {
+            return Err(CssError::with_location("Expected block after @media", t.location));
+        }
+
+        let queries = match self.parse_component_values(tokens) {
+            Ok(values) => MediaQueryList::parse(&values),
+            Err(err) => {
+                self.skip_block_contents();
+                return Err(err);
+            }
+        };
+
+        let mut media = media.to_vec();
+        media.push(queries);
+
+        self.parse_rule_list(sheet, layer, &media, false)
+    }
+
+    /// Parses `@import <url> [layer | layer(<name>)]? [supports(<condition>)]? <media-query-list>?;`.
+    /// The imported stylesheet is not loaded here, it is added to `CssStylesheet::imports`.
+    fn parse_at_rule_import(&mut self, sheet: &mut CssStylesheet) -> CssResult<()> {
+        let location = self.tokenizer.current_location();
+        let tokens = self.consume_statement_prelude("@import")?;
+        if self.position != SheetPosition::Imports {
+            return Err(CssError::with_location(
+                "@import is only allowed before all other rules",
+                location,
+            ));
+        }
+
+        let mut pos = skip_whitespace(&tokens, 0);
+        let Some((url, len)) = parse_url(&tokens[pos..]) else {
+            return Err(CssError::with_location("Expected url in @import", location));
+        };
+        pos = skip_whitespace(&tokens, pos + len);
+
+        let mut layer = None;
+        match tokens.get(pos).map(|t| &t.token_type) {
+            Some(TokenType::Ident(ident)) if ident.eq_ignore_ascii_case("layer") => {
+                // Anonymous layers cannot be referenced, so they get a unique name
+                self.anonymous_layers += 1;
+                layer = Some(format!("#anonymous-{}", self.anonymous_layers));
+                pos = skip_whitespace(&tokens, pos + 1);
+            }
+            Some(TokenType::Function(name)) if name.eq_ignore_ascii_case("layer") => {
+                let end =
+                    block_end(&tokens, pos).ok_or_else(|| CssError::with_location("Invalid layer()", location))?;
+                let name = tokens[pos + 1..end]
+                    .iter()
+                    .filter(|t| !t.is_whitespace() && !t.is_comment())
+                    .map(|t| t.to_string())
+                    .collect::<String>();
+                if name.is_empty() {
+                    return Err(CssError::with_location("Empty layer() in @import", location));
+                }
+                layer = Some(name);
+                pos = skip_whitespace(&tokens, end + 1);
+            }
+            _ => {}
+        }
+
+        if let Some(TokenType::Function(name)) = tokens.get(pos).map(|t| &t.token_type) {
+            if name.eq_ignore_ascii_case("supports") {
+                let end =
+                    block_end(&tokens, pos).ok_or_else(|| CssError::with_location("Invalid supports()", location))?;
+                if !evaluate_import_supports(&tokens[pos + 1..end]) {
+                    return Ok(());
+                }
+                pos = end + 1;
+            }
+        }
+
+        let media = match self.parse_component_values(tokens[pos..].to_vec())? {
+            values if values.is_empty() => MediaQueryList::default(),
+            values => MediaQueryList::parse(&values),
+        };
+
+        let layer_position = sheet.layers.len();
+        let layer = layer.map(|name| register_layer(sheet, None, &name));
+        sheet.imports.push(CssImport {
+            url,
+            layer,
+            layer_position,
+            media,
+            location,
+        });
+
+        Ok(())
+    }
+
+    /// Parses `@namespace <prefix>? [<string> | <url>];`
+    fn parse_at_rule_namespace(&mut self) -> CssResult<()> {
+        let location = self.tokenizer.current_location();
+        let tokens = self.consume_statement_prelude("@namespace")?;
+        if self.position == SheetPosition::Body {
+            return Err(CssError::with_location(
+                "@namespace is only allowed before all style rules",
+                location,
+            ));
+        }
+        self.position = SheetPosition::Namespaces;
+
+        let mut pos = skip_whitespace(&tokens, 0);
+        let prefix = match tokens.get(pos).map(|t| &t.token_type) {
+            Some(TokenType::Ident(prefix)) => {
+                pos = skip_whitespace(&tokens, pos + 1);
+                Some(prefix.clone())
+            }
+            _ => None,
+        };
+
+        let url = match parse_url(&tokens[pos..]) {
+            Some((url, len)) if skip_whitespace(&tokens, pos + len) == tokens.len() => url,
+            _ => return Err(CssError::with_location("Invalid @namespace rule", location)),
+        };
+
+        match prefix {
+            Some(prefix) => {
+                self.namespaces.insert(prefix, url);
+            }
+            None => self.default_namespace = Some(url),
+        }
+
+        Ok(())
+    }
+
+    /// Parses `@supports <supports-condition> { <rules> }`. The rules are only added when the condition
+    /// is true.
+    fn parse_at_rule_supports(
+        &mut self,
+        sheet: &mut CssStylesheet,
+        layer: Option<&str>,
+        media: &[MediaQueryList],
+    ) -> CssResult<()> {
+        let tokens = self.consume_prelude_tokens();
+
+        let t = self.tokenizer.consume();
+        if t.token_type != TokenType::LCurly {
+            return Err(CssError::with_location("Expected block after @supports", t.location));
+        }
+
+        match evaluate_supports(&tokens) {
+            Some(true) => self.parse_rule_list(sheet, layer, media, false),
+            Some(false) => {
+                self.skip_block_contents();
+                Ok(())
+            }
+            None => {
+                self.skip_block_contents();
+                Err(CssError::with_location("Invalid @supports condition", t.location))
+            }
+        }
+    }
+
+    /// Collects the prelude of an at-rule that must end with a semicolon
+    fn consume_statement_prelude(&mut self, name: &str) -> CssResult<Vec<Token>> {
+        let tokens = self.consume_prelude_tokens();
+
+        let t = self.tokenizer.consume();
+        match t.token_type {
+            TokenType::Semicolon | TokenType::Eof => Ok(tokens),
+            _ => {
+                self.skip_block_contents();
+                Err(CssError::with_location(
+                    &format!("{name} can not have a block"),
+                    t.location,
+                ))
+            }
+        }
+    }
+
+    /// Collects the tokens of an at-rule prelude. This stops (without consuming) at the block of the
+    /// at-rule, or the semicolon that ends it.
+    pub(crate) fn consume_prelude_tokens(&mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut depth = 0_usize;
+
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Eof => break,
+                TokenType::LCurly | TokenType::Semicolon if depth == 0 => break,
+                TokenType::LCurly | TokenType::LParen | TokenType::LBracket | TokenType::Function(_) => depth += 1,
+                TokenType::RCurly | TokenType::RParen | TokenType::RBracket => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            tokens.push(self.tokenizer.consume());
+        }
+
+        tokens
+    }
+
+    /// Parses `@property --name { syntax: "<length>"; inherits: false; initial-value: 0px }`. Invalid
+    /// registrations are ignored as a whole.
+    fn parse_at_rule_property(&mut self, sheet: &mut CssStylesheet) -> CssResult<()> {
+        let location = self.tokenizer.current_location();
+
+        self.consume_whitespace_comments();
+        let name = self.consume_any_ident()?;
+        self.consume_whitespace_comments();
+        if !name.starts_with("--") {
+            self.skip_until_semicolon_or_block();
+            return Err(CssError::with_location(
+                format!("Invalid custom property name '{name}' in @property").as_str(),
+                location,
+            ));
+        }
+        self.consume(TokenType::LCurly)?;
+
+        let mut syntax = None;
+        let mut inherits = None;
+        let mut initial_value = None;
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Eof => break,
+                TokenType::RCurly => {
+                    self.tokenizer.consume();
+                    break;
+                }
+                TokenType::Whitespace(_) | TokenType::Comment(_) | TokenType::Semicolon => {
+                    self.tokenizer.consume();
+                }
+                TokenType::Ident(_) => {
+                    let declaration = match self.parse_declaration() {
+                        Ok(declaration) => declaration,
+                        Err(err) => {
+                            self.skip_block_contents();
+                            return Err(err);
+                        }
+                    };
+                    match declaration.property.as_str() {
+                        "syntax" => syntax = Some(declaration.value),
+                        "inherits" => inherits = Some(declaration.value),
+                        "initial-value" => initial_value = Some(CssValue::from_list(declaration.value)),
+                        descriptor => log_warning(
+                            sheet,
+                            &format!("Unknown descriptor '{descriptor}' in @property ignored"),
+                            declaration.location,
+                        ),
+                    }
+                }
+                _ => self.skip_until_semicolon_or_block(),
+            }
+        }
+
+        let invalid = |reason: &str| {
+            Err(CssError::with_location(
+                format!("Invalid @property {name}: {reason}").as_str(),
+                location,
+            ))
+        };
+
+        // The syntax and inherits descriptors are required (CSS Properties and Values API 1, 3)
+        let syntax = match syntax.as_deref() {
+            Some([CssValue::String(syntax)]) if syntax.trim() == "*" => None,
+            Some([CssValue::String(syntax)]) => match CssSyntax::new(syntax.trim()) {
+                Ok(syntax) => Some(syntax),
+                Err(_) => return invalid("invalid syntax"),
+            },
+            _ => return invalid("missing syntax"),
+        };
+        let inherits = match inherits.as_deref() {
+            Some([value]) if value.is_keyword("true") => true,
+            Some([value]) if value.is_keyword("false") => false,
+            _ => return invalid("missing inherits"),
+        };
+
+        // The initial value is only optional for the universal syntax, and must be computationally
+        // independent
+        if let Some(syntax) = &syntax {
+            let Some(value) = &initial_value else {
+                return invalid("missing initial-value");
+            };
+            let values = match value {
+                CssValue::List(values) => values.clone(),
+                value => vec![value.clone()],
+            };
+            if !syntax.matches(&values) || !is_computationally_independent(&values) {
+                return invalid("invalid initial-value");
+            }
+        }
+
+        sheet.property_rules.push(CssPropertyRule {
+            name,
+            syntax,
+            inherits,
+            initial_value,
+            location,
+        });
+
+        Ok(())
+    }
+
+    /// Parses a (dotted) layer name like `framework.base`
+    fn parse_layer_name(&mut self) -> CssResult<String> {
+        let mut name = self.consume_any_ident()?;
+
+        while self.tokenizer.lookahead(0).is_delim('.') {
+            self.tokenizer.consume();
+            name.push('.');
+            name.push_str(&self.consume_any_ident()?);
+        }
+
+        Ok(name)
+    }
+
+    /// Skips everything up to and including the closing curly bracket of the current block
+    pub(crate) fn skip_block_contents(&mut self) {
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Eof => return,
+                TokenType::RCurly => {
+                    self.tokenizer.consume();
+                    return;
+                }
+                _ => self.skip_component_value(),
+            }
+        }
+    }
+}
+
+fn skip_whitespace(tokens: &[Token], pos: usize) -> usize {
+    let skipped = tokens[pos.min(tokens.len())..]
+        .iter()
+        .take_while(|t| t.is_whitespace() || t.is_comment())
+        .count();
+    pos + skipped
+}
+
+/// Returns the position of the token that closes the function or block at `start`
+fn block_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+
+    for (pos, token) in tokens.iter().enumerate().skip(start) {
+        match token.token_type {
+            TokenType::LParen | TokenType::LBracket | TokenType::LCurly | TokenType::Function(_) => depth += 1,
+            TokenType::RParen | TokenType::RBracket | TokenType::RCurly => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(pos);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Parses a `<string>` or `<url>` at the start of the tokens. Returns the url and the number of
+/// tokens it takes.
+fn parse_url(tokens: &[Token]) -> Option<(String, usize)> {
+    match tokens.first().map(|t| &t.token_type)? {
+        TokenType::QuotedString(url) | TokenType::Url(url) => Some((url.clone(), 1)),
+        TokenType::Function(name) if name.eq_ignore_ascii_case("url") => {
+            let end = block_end(tokens, 0)?;
+            let args = tokens[1..end]
+                .iter()
+                .filter(|t| !t.is_whitespace() && !t.is_comment())
+                .collect::<Vec<_>>();
+            match args.as_slice() {
+                [Token {
+                    token_type: TokenType::QuotedString(url),
+                    ..
+                }] => Some((url.clone(), end + 1)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Registers the layer (and all its implicit parent layers) in the stylesheet. Returns the full
+/// name of the layer.
+fn register_layer(sheet: &mut CssStylesheet, parent: Option<&str>, name: &str) -> String {
+    let full_name = match parent {
+        Some(parent) => format!("{parent}.{name}"),
+        None => name.to_string(),
+    };
+
+    let mut prefix = String::new();
+    for (i, part) in full_name.split('.').enumerate() {
+        if i > 0 {
+            prefix.push('.');
+        }
+        prefix.push_str(part);
+        sheet.add_layer(&prefix);
+    }
+
+    full_name
+}
+
+/// Returns true when the values do not depend on other values, like relative lengths or `var()`
+fn is_computationally_independent(values: &[CssValue]) -> bool {
+    values.iter().all(|value| match value {
+        CssValue::Unit(_, unit) => {
+            !LENGTH_UNITS.contains(&unit.as_str()) || ["px", "cm", "mm", "q", "in", "pt", "pc"].contains(&unit.as_str())
+        }
+        CssValue::Function(name, args) => name != "var" && name != "attr" && is_computationally_independent(args),
+        CssValue::List(values) => is_computationally_independent(values),
+        _ => true,
+    })
+}
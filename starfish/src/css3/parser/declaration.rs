@@ -0,0 +1,302 @@
+use crate::{
+    css3::{
+        matcher::property_definitions::get_css_definition,
+        parser::stylesheet::{log_error, log_warning},
+        shorthands::expand_declaration,
+        stylesheet::{CssDeclaration, CssStylesheet, CssValue},
+        tokenizer::{Token, TokenType},
+        vendor::{is_vendor_prefixed, property_alias},
+        Css3,
+    },
+    shared::errors::{CssError, CssResult},
+};
+
+impl Css3<'_> {
+    /// Parses a list of declarations until EOF or the closing curly bracket of the current block.
+    /// Invalid declarations are dropped (and logged), as browsers do. Shorthands are expanded into
+    /// their longhands.
+    pub(crate) fn parse_declaration_list(&mut self, sheet: &mut CssStylesheet) -> CssResult<Vec<CssDeclaration>> {
+        let mut declarations = Vec::new();
+
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Eof => break,
+                TokenType::RCurly => {
+                    self.tokenizer.consume();
+                    break;
+                }
+                TokenType::Whitespace(_) | TokenType::Comment(_) | TokenType::Semicolon => {
+                    self.tokenizer.consume();
+                }
+                TokenType::Ident(_) => match self.parse_declaration() {
+                    Ok(declaration) if self.config.match_values => match match_property_value(&declaration) {
+                        Ok(()) => declarations.extend(expand_declaration(declaration)),
+                        // Unsupported vendor extensions are dropped, but are no reason to stop parsing
+                        Err(err) if is_vendor_declaration(&declaration) => {
+                            log_warning(sheet, &err.message, declaration.location);
+                        }
+                        Err(err) => {
+                            if !self.config.ignore_errors {
+                                return Err(err);
+                            }
+                            log_error(sheet, &err);
+                        }
+                    },
+                    Ok(declaration) => declarations.extend(expand_declaration(declaration)),
+                    Err(err) => {
+                        self.skip_until_semicolon_or_block();
+                        if !self.config.ignore_errors {
+                            return Err(err);
+                        }
+                        log_error(sheet, &err);
+                    }
+                },
+                _ => {
+                    // Nested rules and at-rules inside a declaration block are not supported
+                    self.skip_until_semicolon_or_block();
+                    let err = CssError::with_location("Unexpected token in declaration list", t.location);
+                    if !self.config.ignore_errors {
+                        return Err(err);
+                    }
+                    log_error(sheet, &err);
+                }
+            }
+        }
+
+        Ok(declarations)
+    }
+
+    /// Parses a single declaration: `<ident> : <value> [!important]`. The value is everything up to
+    /// the next semicolon (or end of the block).
+    pub(crate) fn parse_declaration(&mut self) -> CssResult<CssDeclaration> {
+        let location = self.tokenizer.current_location();
+
+        let name = self.consume_any_ident()?;
+        let property = if name.starts_with("--") {
+            name
+        } else {
+            let name = name.to_ascii_lowercase();
+            property_alias(&name).map(str::to_string).unwrap_or(name)
+        };
+
+        self.consume_whitespace_comments();
+        let t = self.tokenizer.consume();
+        if !t.is_colon() {
+            return Err(CssError::with_location(
+                format!("Expected colon after property '{}'", property).as_str(),
+                t.location,
+            ));
+        }
+
+        let mut tokens = self.consume_declaration_value_tokens();
+        let important = strip_important(&mut tokens);
+
+        let value = self.parse_component_values(tokens)?;
+        if value.is_empty() && !property.starts_with("--") {
+            return Err(CssError::with_location(
+                format!("Missing value for property '{}'", property).as_str(),
+                location,
+            ));
+        }
+
+        Ok(CssDeclaration {
+            property,
+            value,
+            important,
+            location,
+        })
+    }
+
+    /// Collects the tokens of a declaration value. This stops (without consuming) at a semicolon or
+    /// a closing curly bracket that is not nested in a block or function.
+    fn consume_declaration_value_tokens(&mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut depth = 0_usize;
+
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Eof => break,
+                TokenType::Semicolon | TokenType::RCurly if depth == 0 => break,
+                TokenType::LCurly | TokenType::LParen | TokenType::LBracket | TokenType::Function(_) => depth += 1,
+                TokenType::RCurly | TokenType::RParen | TokenType::RBracket => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            tokens.push(self.tokenizer.consume());
+        }
+
+        tokens
+    }
+}
+
+/// Checks the value of a declaration against the syntax of its property. Custom properties and
+/// values with `var()` or `attr()` can only be checked after substitution, so they always pass.
+fn match_property_value(declaration: &CssDeclaration) -> CssResult<()> {
+    let (property, value, location) = (
+        &declaration.property,
+        declaration.value.as_slice(),
+        declaration.location,
+    );
+
+    if property.starts_with("--") || contains_substitution(value) {
+        return Ok(());
+    }
+
+    let Some(definition) = get_css_definition(property) else {
+        return Err(CssError::with_location(
+            format!("Unknown property '{}'", property).as_str(),
+            location,
+        ));
+    };
+
+    // The css-wide keywords are valid for every property, but only on their own
+    if let [value] = value {
+        if value.is_css_wide_keyword() {
+            return Ok(());
+        }
+    }
+
+    if !definition.syntax.matches(value) {
+        return Err(CssError::with_location(
+            format!(
+                "Invalid value '{}' for property '{}'",
+                CssValue::List(value.to_vec()),
+                property
+            )
+            .as_str(),
+            location,
+        ));
+    }
+
+    Ok(())
+}
+
+fn contains_substitution(values: &[CssValue]) -> bool {
+    values.iter().any(|value| match value {
+        CssValue::Function(name, args) => name == "var" || name == "attr" || contains_substitution(args),
+        CssValue::List(values) => contains_substitution(values),
+        _ => false,
+    })
+}
+
+/// Returns true when the declaration uses a vendor-prefixed property, keyword, function or unit
+fn is_vendor_declaration(declaration: &CssDeclaration) -> bool {
+    fn has_vendor_value(values: &[CssValue]) -> bool {
+        values.iter().any(|value| match value {
+            CssValue::Keyword(name) | CssValue::Unit(_, name) => is_vendor_prefixed(name),
+            CssValue::Function(name, args) => is_vendor_prefixed(name) || has_vendor_value(args),
+            CssValue::List(values) => has_vendor_value(values),
+            _ => false,
+        })
+    }
+
+    is_vendor_prefixed(&declaration.property) || has_vendor_value(&declaration.value)
+}
+
+/// Removes a trailing `!important` from the tokens. Returns true when it was found.
+fn strip_important(tokens: &mut Vec<Token>) -> bool {
+    let significant: Vec<usize> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| !t.is_whitespace() && !t.is_comment())
+        .map(|(i, _)| i)
+        .collect();
+
+    if significant.len() < 2 {
+        return false;
+    }
+
+    let last = significant[significant.len() - 1];
+    let bang = significant[significant.len() - 2];
+    if tokens[bang].is_delim('!') && tokens[last].is_ident_named("important") {
+        tokens.truncate(bang);
+        return true;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        css3::{
+            stylesheet::{CssLogLevel, CssStylesheet},
+            Css3,
+        },
+        interface::{css3::CssOrigin, ParserConfig},
+    };
+
+    fn parse(css: &str, match_values: bool) -> CssStylesheet {
+        let config = ParserConfig {
+            ignore_errors: true,
+            match_values,
+            ..Default::default()
+        };
+        Css3::parse_str(css, config, CssOrigin::Author, "test.css").unwrap()
+    }
+
+    fn properties(sheet: &CssStylesheet) -> Vec<String> {
+        sheet.rules[0]
+            .declarations
+            .iter()
+            .map(|declaration| declaration.property.clone())
+            .collect()
+    }
+
+    #[test]
+    fn invalid_declarations_are_dropped() {
+        let sheet = parse(
+            "p {\n  color: 12px;\n  width: 10px;\n  display: blokc;\n  z-index: 2;\n  opacity: red\n}",
+            true,
+        );
+        assert_eq!(properties(&sheet), ["width", "z-index"]);
+
+        let errors = sheet
+            .parse_log
+            .iter()
+            .filter(|log| log.level == CssLogLevel::Error)
+            .map(|log| (log.message.as_str(), log.location.line, log.location.column))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                ("Invalid value '12px' for property 'color'", 2, 3),
+                ("Invalid value 'blokc' for property 'display'", 4, 3),
+                ("Invalid value 'red' for property 'opacity'", 6, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn valid_declarations_are_kept() {
+        let sheet = parse(
+            "p { width: calc(100% - 2rem); color: inherit; margin-top: var(--gap); --anything: 12 } ",
+            true,
+        );
+        assert_eq!(properties(&sheet), ["width", "color", "margin-top", "--anything"]);
+        assert!(sheet.parse_log.is_empty());
+
+        let sheet = parse("p { colour: red; width: 10px }", true);
+        assert_eq!(properties(&sheet), ["width"]);
+        assert_eq!(sheet.parse_log[0].message, "Unknown property 'colour'");
+    }
+
+    #[test]
+    fn values_are_not_matched_without_match_values() {
+        let sheet = parse("p { color: 12px }", false);
+        assert_eq!(properties(&sheet), ["color"]);
+    }
+
+    #[test]
+    fn errors_are_returned_when_not_ignored() {
+        let config = ParserConfig {
+            ignore_errors: false,
+            match_values: true,
+            ..Default::default()
+        };
+        let err = Css3::parse_str("p {\n  color: 12px }", config, CssOrigin::Author, "test.css").unwrap_err();
+        let location = err.location.unwrap();
+        assert_eq!((location.line, location.column), (2, 3));
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{
+    css3::{
+        stylesheet::{
+            AttributeMatcher, AttributeNamespace, AttributeSelector, Combinator, CssSelector, CssSelectorPart,
+            CssValue, Nth, PseudoClass,
+        },
+        tokenizer::{Token, TokenType},
+        vendor::pseudo_class_alias,
+        Css3,
+    },
+    shared::errors::{CssError, CssResult},
+};
+
+/// Pseudo elements that may also be written with a single colon (CSS2 syntax)
+const LEGACY_PSEUDO_ELEMENTS: [&str; 4] = ["before", "after", "first-line", "first-letter"];
+
+impl Css3<'_> {
+    /// Parses a comma separated list of complex selectors, up to (but not including) the opening
+    /// curly bracket of the declaration block.
+    pub(crate) fn parse_selector_list(&mut self) -> CssResult<Vec<CssSelector>> {
+        self.parse_selector_list_until(&TokenType::LCurly, false)
+    }
+
+    /// Parses a selector list until the given (unconsumed) token is found. When `relative` is true,
+    /// the selectors may start with a combinator (as in `:has(> img)`).
+    fn parse_selector_list_until(&mut self, end: &TokenType, relative: bool) -> CssResult<Vec<CssSelector>> {
+        let mut selectors = Vec::new();
+
+        loop {
+            selectors.push(self.parse_complex_selector(end, relative)?);
+
+            let t = self.tokenizer.lookahead(0);
+            if t.token_type == *end {
+                return Ok(selectors);
+            }
+            if t.is_comma() {
+                self.tokenizer.consume();
+                continue;
+            }
+
+            return Err(unexpected(&t, "selector list"));
+        }
+    }
+
+    /// Parses a single complex selector: compound selectors separated by combinators. Stops at a
+    /// comma or the given end token.
+    fn parse_complex_selector(&mut self, end: &TokenType, relative: bool) -> CssResult<CssSelector> {
+        let mut parts = Vec::new();
+
+        self.consume_whitespace_comments();
+        if relative {
+            if let Some(combinator) = self.parse_combinator() {
+                parts.push(CssSelectorPart::Combinator(combinator));
+                self.consume_whitespace_comments();
+            }
+        }
+
+        loop {
+            let compound = self.parse_compound_selector()?;
+            if compound.is_empty() {
+                let t = self.tokenizer.lookahead(0);
+                return Err(unexpected(&t, "selector"));
+            }
+            parts.extend(compound);
+
+            // Find out if there is a combinator, or if the selector ends here
+            let had_whitespace =
+                self.tokenizer.lookahead(0).is_whitespace() || self.tokenizer.lookahead(0).is_comment();
+            self.consume_whitespace_comments();
+
+            let t = self.tokenizer.lookahead(0);
+            if t.token_type == *end || t.is_comma() {
+                return Ok(CssSelector { parts });
+            }
+
+            match self.parse_combinator() {
+                Some(combinator) => {
+                    parts.push(CssSelectorPart::Combinator(combinator));
+                    self.consume_whitespace_comments();
+                }
+                None if had_whitespace => parts.push(CssSelectorPart::Combinator(Combinator::Descendant)),
+                None => return Err(unexpected(&t, "selector")),
+            }
+        }
+    }
+
+    /// Consumes an explicit combinator (`>`, `+` or `~`), if present
+    fn parse_combinator(&mut self) -> Option<Combinator> {
+        let t = self.tokenizer.lookahead(0);
+        let combinator = match t.token_type {
+            TokenType::Delim('>') => Combinator::Child,
+            TokenType::Delim('+') => Combinator::NextSibling,
+            TokenType::Delim('~') => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.tokenizer.consume();
+
+        Some(combinator)
+    }
+
+    /// Parses a compound selector: a sequence of simple selectors without whitespace in between
+    fn parse_compound_selector(&mut self) -> CssResult<Vec<CssSelectorPart>> {
+        let mut parts = Vec::new();
+
+        let prefix = self.parse_namespace_prefix(true)?;
+        let t = self.tokenizer.lookahead(0);
+        match t.token_type {
+            TokenType::Ident(name) => {
+                self.tokenizer.consume();
+                parts.push(CssSelectorPart::Type(name.to_ascii_lowercase()));
+            }
+            TokenType::Delim('*') => {
+                self.tokenizer.consume();
+                parts.push(CssSelectorPart::Universal);
+            }
+            _ if prefix.is_some() => return Err(unexpected(&t, "type selector")),
+            _ => {}
+        }
+
+        self.parse_subclass_selectors(&mut parts)?;
+
+        // Without a prefix, the default namespace applies (Selectors 4, 5.2)
+        let namespace = match prefix {
+            Some(namespace) => namespace,
+            None => self.default_namespace.clone(),
+        };
+        if let Some(namespace) = namespace.filter(|_| !parts.is_empty()) {
+            parts.insert(0, CssSelectorPart::Namespace(namespace));
+        }
+
+        Ok(parts)
+    }
+
+    /// Parses a namespace prefix (`ns|`, `*|` or `|`) when it is followed by a name, or by `*` when
+    /// `universal` is true. Returns None when there is no prefix, and `Some(None)` for any namespace.
+    /// A namespace url is empty for elements or attributes without a namespace.
+    fn parse_namespace_prefix(&mut self, universal: bool) -> CssResult<Option<Option<String>>> {
+        let (prefix_len, prefix) = match self.tokenizer.lookahead(0).token_type {
+            TokenType::Delim('|') => (1, Some(String::new())),
+            TokenType::Delim('*') if self.tokenizer.lookahead(1).is_delim('|') => (2, None),
+            TokenType::Ident(name) if self.tokenizer.lookahead(1).is_delim('|') => (2, Some(name)),
+            _ => return Ok(None),
+        };
+
+        let name = self.tokenizer.lookahead(prefix_len);
+        let has_name = matches!(name.token_type, TokenType::Ident(_)) || (universal && name.is_delim('*'));
+        if !has_name {
+            return Ok(None);
+        }
+
+        let t = self.tokenizer.lookahead(0);
+        for _ in 0..prefix_len {
+            self.tokenizer.consume();
+        }
+
+        match prefix {
+            None => Ok(Some(None)),
+            Some(prefix) if prefix.is_empty() => Ok(Some(Some(String::new()))),
+            Some(prefix) => match self.namespaces.get(&prefix) {
+                Some(url) => Ok(Some(Some(url.clone()))),
+                None => Err(CssError::with_location(
+                    &format!("Unknown namespace prefix '{prefix}'"),
+                    t.location,
+                )),
+            },
+        }
+    }
+
+    /// Parses the simple selectors that can follow a type selector in a compound selector
+    fn parse_subclass_selectors(&mut self, parts: &mut Vec<CssSelectorPart>) -> CssResult<()> {
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            let part = match t.token_type {
+                TokenType::IDHash(id) => {
+                    self.tokenizer.consume();
+                    CssSelectorPart::Id(id)
+                }
+                TokenType::Delim('.') => {
+                    self.tokenizer.consume();
+                    let name = self.consume_any_ident()?;
+                    CssSelectorPart::Class(name)
+                }
+                TokenType::LBracket => {
+                    self.tokenizer.consume();
+                    self.parse_attribute_selector()?
+                }
+                TokenType::Colon => {
+                    self.tokenizer.consume();
+                    self.parse_pseudo_selector()?
+                }
+                _ => return Ok(()),
+            };
+
+            parts.push(part);
+        }
+    }
+
+    /// Parses an attribute selector. The opening bracket has already been consumed.
+    fn parse_attribute_selector(&mut self) -> CssResult<CssSelectorPart> {
+        self.consume_whitespace_comments();
+        let namespace = match self.parse_namespace_prefix(false)? {
+            None => AttributeNamespace::None,
+            Some(None) => AttributeNamespace::Any,
+            Some(Some(url)) if url.is_empty() => AttributeNamespace::None,
+            Some(Some(url)) => AttributeNamespace::Url(url),
+        };
+        let name = self.consume_any_ident()?.to_ascii_lowercase();
+        self.consume_whitespace_comments();
+
+        let t = self.tokenizer.consume();
+        let matcher = match t.token_type {
+            TokenType::RBracket => {
+                return Ok(CssSelectorPart::Attribute(Box::new(AttributeSelector {
+                    name,
+                    namespace,
+                    matcher: AttributeMatcher::Exists,
+                    value: String::new(),
+                    case_insensitive: false,
+                })));
+            }
+            TokenType::Delim('=') => AttributeMatcher::Equals,
+            TokenType::Delim(c) if self.tokenizer.lookahead(0).is_delim('=') => {
+                self.tokenizer.consume();
+                match c {
+                    '~' => AttributeMatcher::Includes,
+                    '|' => AttributeMatcher::DashMatch,
+                    '^' => AttributeMatcher::PrefixMatch,
+                    '$' => AttributeMatcher::SuffixMatch,
+                    '*' => AttributeMatcher::SubstringMatch,
+                    _ => return Err(unexpected(&t, "attribute selector")),
+                }
+            }
+            _ => return Err(unexpected(&t, "attribute selector")),
+        };
+
+        self.consume_whitespace_comments();
+        let t = self.tokenizer.consume();
+        let value = match t.token_type {
+            TokenType::Ident(value) | TokenType::QuotedString(value) => value,
+            _ => return Err(unexpected(&t, "attribute selector")),
+        };
+
+        self.consume_whitespace_comments();
+        let mut case_insensitive = false;
+        let t = self.tokenizer.lookahead(0);
+        if let TokenType::Ident(flag) = &t.token_type {
+            match flag.to_ascii_lowercase().as_str() {
+                "i" => case_insensitive = true,
+                "s" => case_insensitive = false,
+                _ => return Err(unexpected(&t, "attribute selector")),
+            }
+            self.tokenizer.consume();
+            self.consume_whitespace_comments();
+        }
+
+        let t = self.tokenizer.consume();
+        if t.token_type != TokenType::RBracket {
+            return Err(unexpected(&t, "attribute selector"));
+        }
+
+        Ok(CssSelectorPart::Attribute(Box::new(AttributeSelector {
+            name,
+            namespace,
+            matcher,
+            value,
+            case_insensitive,
+        })))
+    }
+
+    /// Parses a pseudo class or pseudo element. The first colon has already been consumed.
+    fn parse_pseudo_selector(&mut self) -> CssResult<CssSelectorPart> {
+        let is_element = self.tokenizer.lookahead(0).is_colon();
+        if is_element {
+            self.tokenizer.consume();
+        }
+
+        let t = self.tokenizer.consume();
+        match t.token_type {
+            TokenType::Ident(name) => {
+                let name = name.to_ascii_lowercase();
+                if is_element || LEGACY_PSEUDO_ELEMENTS.contains(&name.as_str()) {
+                    return Ok(CssSelectorPart::PseudoElement(name));
+                }
+                let name = pseudo_class_alias(&name).map(str::to_string).unwrap_or(name);
+                Ok(CssSelectorPart::PseudoClass(PseudoClass::Named(name)))
+            }
+            TokenType::Function(name) => {
+                let name = name.to_ascii_lowercase();
+                if is_element {
+                    // Functional pseudo elements (like ::part()) are kept by name only
+                    self.parse_function_arguments()?;
+                    return Ok(CssSelectorPart::PseudoElement(name));
+                }
+
+                let pseudo = self.parse_functional_pseudo_class(&name)?;
+                Ok(CssSelectorPart::PseudoClass(pseudo))
+            }
+            _ => Err(unexpected(&t, "pseudo selector")),
+        }
+    }
+
+    /// Parses the arguments of a functional pseudo class. The function token has been consumed, and
+    /// this will consume up to and including the closing parenthesis.
+    fn parse_functional_pseudo_class(&mut self, name: &str) -> CssResult<PseudoClass> {
+        let pseudo = match name {
+            "not" => PseudoClass::Not(self.parse_selector_list_until(&TokenType::RParen, false)?),
+            "is" | "matches" | "-webkit-any" => {
+                PseudoClass::Is(self.parse_selector_list_until(&TokenType::RParen, false)?)
+            }
+            "where" => PseudoClass::Where(self.parse_selector_list_until(&TokenType::RParen, false)?),
+            "has" => PseudoClass::Has(self.parse_selector_list_until(&TokenType::RParen, true)?),
+            "nth-child" | "nth-last-child" => {
+                let nth = self.parse_nth()?;
+                let mut of = Vec::new();
+                self.consume_whitespace_comments();
+                if self.tokenizer.lookahead(0).is_ident_named("of") {
+                    self.tokenizer.consume();
+                    of = self.parse_selector_list_until(&TokenType::RParen, false)?;
+                }
+                if name == "nth-child" {
+                    PseudoClass::NthChild(nth, of)
+                } else {
+                    PseudoClass::NthLastChild(nth, of)
+                }
+            }
+            "nth-of-type" => PseudoClass::NthOfType(self.parse_nth()?),
+            "nth-last-of-type" => PseudoClass::NthLastOfType(self.parse_nth()?),
+            "lang" => {
+                let args = self.parse_function_arguments()?;
+                let langs = args
+                    .iter()
+                    .filter_map(|v| match v {
+                        CssValue::Keyword(s) | CssValue::String(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                return Ok(PseudoClass::Lang(langs));
+            }
+            "dir" => {
+                let args = self.parse_function_arguments()?;
+                return match args.as_slice() {
+                    [CssValue::Keyword(dir)] => Ok(PseudoClass::Dir(dir.to_ascii_lowercase())),
+                    _ => Err(CssError::new("Invalid argument for :dir()")),
+                };
+            }
+            _ => {
+                return Ok(PseudoClass::Function(
+                    name.to_string(),
+                    self.parse_function_arguments()?,
+                ))
+            }
+        };
+
+        self.consume_whitespace_comments();
+        let t = self.tokenizer.consume();
+        if t.token_type != TokenType::RParen {
+            return Err(unexpected(&t, "pseudo class"));
+        }
+
+        Ok(pseudo)
+    }
+
+    /// Parses the An+B microsyntax (https://www.w3.org/TR/css-syntax-3/#anb-microsyntax)
+    fn parse_nth(&mut self) -> CssResult<Nth> {
+        self.consume_whitespace_comments();
+        let t = self.tokenizer.consume();
+        let location = t.location;
+
+        // Parses the "n", "n-" and "n-<digits>" part of an ident or dimension
+        let parse_n = |s: &str, a: i32| -> Option<(i32, Option<i32>)> {
+            let s = s.to_ascii_lowercase();
+            let rest = s.strip_prefix('n')?;
+            if rest.is_empty() {
+                return Some((a, None));
+            }
+            if rest == "-" {
+                return Some((a, Some(i32::MIN)));
+            }
+            let b = rest.strip_prefix('-')?.parse::<i32>().ok()?;
+            Some((a, Some(-b)))
+        };
+
+        let (a, b) = match &t.token_type {
+            TokenType::Ident(s) if s.eq_ignore_ascii_case("odd") => return Ok(Nth { a: 2, b: 1 }),
+            TokenType::Ident(s) if s.eq_ignore_ascii_case("even") => return Ok(Nth { a: 2, b: 0 }),
+            TokenType::Number(n) => return Ok(Nth { a: 0, b: *n as i32 }),
+            TokenType::Ident(s) if s.starts_with('-') => parse_n(&s[1..], -1),
+            TokenType::Ident(s) => parse_n(s, 1),
+            TokenType::Delim('+') => match self.tokenizer.consume().token_type {
+                TokenType::Ident(s) => parse_n(&s, 1),
+                _ => None,
+            },
+            TokenType::Dimension { value, unit } => parse_n(unit, *value as i32),
+            _ => None,
+        }
+        .ok_or_else(|| CssError::with_location("Invalid An+B notation", location))?;
+
+        let b = match b {
+            // "n-" followed by a number, like "2n- 1"
+            Some(i32::MIN) => {
+                self.consume_whitespace_comments();
+                match self.tokenizer.consume().token_type {
+                    TokenType::Number(n) if n >= 0.0 => -(n as i32),
+                    _ => return Err(CssError::with_location("Invalid An+B notation", location)),
+                }
+            }
+            Some(b) => b,
+            None => {
+                // Optional "+ <number>", "- <number>" or "<signed number>"
+                self.consume_whitespace_comments();
+                match self.tokenizer.lookahead(0).token_type {
+                    TokenType::Number(n) => {
+                        self.tokenizer.consume();
+                        n as i32
+                    }
+                    TokenType::Delim(c @ ('+' | '-')) => {
+                        self.tokenizer.consume();
+                        self.consume_whitespace_comments();
+                        match self.tokenizer.consume().token_type {
+                            TokenType::Number(n) if n >= 0.0 => {
+                                if c == '-' {
+                                    -(n as i32)
+                                } else {
+                                    n as i32
+                                }
+                            }
+                            _ => return Err(CssError::with_location("Invalid An+B notation", location)),
+                        }
+                    }
+                    _ => 0,
+                }
+            }
+        };
+
+        Ok(Nth { a, b })
+    }
+}
+
+fn unexpected(t: &Token, context: &str) -> CssError {
+    CssError::with_location(
+        format!("Unexpected token {:?} in {}", t.token_type, context).as_str(),
+        t.location,
+    )
+}
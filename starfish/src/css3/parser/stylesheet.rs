@@ -0,0 +1,134 @@
+use crate::{
+    css3::{
+        media::MediaQueryList,
+        stylesheet::{CssLog, CssRule, CssStylesheet},
+        tokenizer::TokenType,
+        Css3, SheetPosition,
+    },
+    interface::Context,
+    shared::{
+        byte_stream::Location,
+        errors::{CssError, CssResult},
+    },
+};
+
+impl Css3<'_> {
+    /// Parses the complete stream, based on the context given in the parser configuration
+    pub(crate) fn parse_stylesheet_internal(&mut self) -> CssResult<CssStylesheet> {
+        let location = self.tokenizer.current_location();
+        let mut sheet = CssStylesheet::new(self.origin, &self.source, location);
+
+        match self.config.context {
+            Context::Stylesheet => self.parse_rule_list(&mut sheet, None, &[], true)?,
+            Context::Rule | Context::AtRule => self.parse_rule_list(&mut sheet, None, &[], false)?,
+            Context::Declaration => {
+                let declarations = self.parse_declaration_list(&mut sheet)?;
+                sheet.rules.push(CssRule {
+                    selectors: Vec::new(),
+                    declarations,
+                    layer: None,
+                    media: Vec::new(),
+                    location,
+                });
+            }
+        }
+
+        Ok(sheet)
+    }
+
+    /// Parses a list of rules until EOF, or until the closing curly bracket of the current block
+    /// when `top_level` is false. Rules are added to the stylesheet in the given cascade layer, and
+    /// only apply when all media query lists of the enclosing `@media` rules match.
+    pub(crate) fn parse_rule_list(
+        &mut self,
+        sheet: &mut CssStylesheet,
+        layer: Option<&str>,
+        media: &[MediaQueryList],
+        top_level: bool,
+    ) -> CssResult<()> {
+        loop {
+            let t = self.tokenizer.lookahead(0);
+            match t.token_type {
+                TokenType::Eof => return Ok(()),
+                TokenType::Whitespace(_) | TokenType::Comment(_) => {
+                    self.tokenizer.consume();
+                }
+                TokenType::CDO | TokenType::CDC if top_level => {
+                    self.tokenizer.consume();
+                }
+                TokenType::RCurly if !top_level => {
+                    self.tokenizer.consume();
+                    return Ok(());
+                }
+                TokenType::AtKeyword(name) => {
+                    self.tokenizer.consume();
+                    let result = self.parse_at_rule(&name, sheet, layer, media);
+                    self.handle_error(sheet, result)?;
+                }
+                _ => {
+                    self.position = SheetPosition::Body;
+                    let result = self.parse_qualified_rule(sheet, layer, media);
+                    self.handle_error(sheet, result)?;
+                }
+            }
+        }
+    }
+
+    /// Parses a qualified (style) rule: a selector list followed by a declaration block
+    fn parse_qualified_rule(
+        &mut self,
+        sheet: &mut CssStylesheet,
+        layer: Option<&str>,
+        media: &[MediaQueryList],
+    ) -> CssResult<()> {
+        let location = self.tokenizer.current_location();
+
+        let selectors = match self.parse_selector_list() {
+            Ok(selectors) => selectors,
+            Err(err) => {
+                // Skip the prelude and its block, as an invalid selector invalidates the whole rule
+                self.skip_until_semicolon_or_block();
+                return Err(err);
+            }
+        };
+
+        self.consume(TokenType::LCurly)?;
+        let declarations = self.parse_declaration_list(sheet)?;
+
+        sheet.rules.push(CssRule {
+            selectors,
+            declarations,
+            layer: layer.map(|l| l.to_string()),
+            media: media.to_vec(),
+            location,
+        });
+
+        Ok(())
+    }
+
+    /// When errors are ignored, the error is logged into the stylesheet and parsing can continue.
+    /// Otherwise, the error is returned.
+    pub(crate) fn handle_error(&mut self, sheet: &mut CssStylesheet, result: CssResult<()>) -> CssResult<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) if self.config.ignore_errors => {
+                log_error(sheet, &err);
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Adds the given error to the parse log of the stylesheet
+pub(crate) fn log_error(sheet: &mut CssStylesheet, err: &CssError) {
+    let location = err.location.unwrap_or(sheet.location);
+    tracing::debug!("css: {} at {}", err.message, location);
+    sheet.parse_log.push(CssLog::error(&err.message, location));
+}
+
+/// Adds a warning to the parse log of the stylesheet
+pub(crate) fn log_warning(sheet: &mut CssStylesheet, message: &str, location: Location) {
+    tracing::debug!("css: {} at {}", message, location);
+    sheet.parse_log.push(CssLog::warning(message, location));
+}
@@ -0,0 +1,127 @@
+use crate::{
+    css3::{
+        stylesheet::CssValue,
+        tokenizer::{Token, TokenType},
+        vendor::{function_alias, keyword_alias, unit_alias},
+        Css3,
+    },
+    shared::errors::{CssError, CssResult},
+};
+
+impl Css3<'_> {
+    /// Converts a list of (declaration value) tokens into css values. Whitespace and comments are
+    /// dropped, functions and blocks are converted into nested values.
+    pub(crate) fn parse_component_values(&self, tokens: Vec<Token>) -> CssResult<Vec<CssValue>> {
+        let mut iter = tokens.into_iter().peekable();
+        parse_values_until(&mut iter, None)
+    }
+
+    /// Consumes the arguments of a function (up to and including the closing parenthesis) from the
+    /// tokenizer and converts them into css values.
+    pub(crate) fn parse_function_arguments(&mut self) -> CssResult<Vec<CssValue>> {
+        let mut tokens = Vec::new();
+        let mut depth = 0_usize;
+
+        loop {
+            let t = self.tokenizer.consume();
+            match t.token_type {
+                TokenType::Eof => break,
+                TokenType::RParen if depth == 0 => break,
+                TokenType::LCurly | TokenType::LParen | TokenType::LBracket | TokenType::Function(_) => depth += 1,
+                TokenType::RCurly | TokenType::RParen | TokenType::RBracket => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            tokens.push(t);
+        }
+
+        self.parse_component_values(tokens)
+    }
+}
+
+/// Converts tokens into values until the given closing token (or the end of the tokens) is found
+fn parse_values_until<I: Iterator<Item = Token>>(
+    iter: &mut std::iter::Peekable<I>,
+    closing: Option<&TokenType>,
+) -> CssResult<Vec<CssValue>> {
+    let mut values = Vec::new();
+
+    while let Some(t) = iter.next() {
+        if closing == Some(&t.token_type) {
+            return Ok(values);
+        }
+
+        let value = match t.token_type {
+            TokenType::Whitespace(_) | TokenType::Comment(_) => continue,
+            TokenType::Ident(ident) => keyword_value(ident),
+            TokenType::Number(value) => CssValue::Number(value),
+            TokenType::Percentage(value) => CssValue::Percentage(value),
+            TokenType::Dimension { value, unit } => {
+                let unit = unit.to_ascii_lowercase();
+                match unit_alias(&unit) {
+                    Some(alias) => CssValue::Unit(value, alias.to_string()),
+                    None => CssValue::Unit(value, unit),
+                }
+            }
+            TokenType::QuotedString(s) => CssValue::String(s),
+            TokenType::Hash(h) | TokenType::IDHash(h) => CssValue::Hash(h),
+            TokenType::Url(url) => CssValue::Url(url),
+            TokenType::Comma => CssValue::Comma,
+            TokenType::Delim(c) => CssValue::Delim(c),
+            TokenType::Colon => CssValue::Delim(':'),
+            TokenType::Function(name) => {
+                let name = name.to_ascii_lowercase();
+                let name = function_alias(&name).map(str::to_string).unwrap_or(name);
+                let args = parse_values_until(iter, Some(&TokenType::RParen))?;
+                if name == "url" {
+                    match args.as_slice() {
+                        [CssValue::String(url)] => CssValue::Url(url.clone()),
+                        _ => return Err(CssError::with_location("Invalid url() function", t.location)),
+                    }
+                } else {
+                    CssValue::Function(name, args)
+                }
+            }
+            TokenType::LParen => CssValue::Function("(".into(), parse_values_until(iter, Some(&TokenType::RParen))?),
+            TokenType::LBracket => {
+                CssValue::Function("[".into(), parse_values_until(iter, Some(&TokenType::RBracket))?)
+            }
+            TokenType::BadString(_) | TokenType::BadUrl(_) => {
+                return Err(CssError::with_location("Invalid string or url in value", t.location));
+            }
+            TokenType::LCurly
+            | TokenType::RCurly
+            | TokenType::RParen
+            | TokenType::RBracket
+            | TokenType::Semicolon
+            | TokenType::AtKeyword(_)
+            | TokenType::CDO
+            | TokenType::CDC => {
+                return Err(CssError::with_location(
+                    format!("Unexpected token {} in value", t.token_type).as_str(),
+                    t.location,
+                ));
+            }
+            TokenType::Eof => break,
+        };
+
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+/// Converts an identifier into a value, taking the css-wide keywords and vendor aliases into account
+fn keyword_value(ident: String) -> CssValue {
+    if let Some(alias) = keyword_alias(&ident) {
+        return CssValue::Keyword(alias.to_string());
+    }
+
+    match ident.to_ascii_lowercase().as_str() {
+        "initial" => CssValue::Initial,
+        "inherit" => CssValue::Inherit,
+        "unset" => CssValue::Unset,
+        "revert" => CssValue::Revert,
+        "revert-layer" => CssValue::RevertLayer,
+        _ => CssValue::Keyword(ident),
+    }
+}
@@ -0,0 +1,1507 @@
+use crate::css3::{
+    matcher::property_definitions::{get_css_definition, get_type_definition},
+    stylesheet::{CssDeclaration, CssValue},
+    syntax::CssSyntax,
+};
+
+/// A longhand property with its value
+type Longhand = (&'static str, Vec<CssValue>);
+
+/// All shorthand properties with the longhands they set
+const SHORTHANDS: &[(&str, &[&str])] = &[
+    (
+        "margin",
+        &["margin-top", "margin-right", "margin-bottom", "margin-left"],
+    ),
+    ("margin-block", &["margin-block-start", "margin-block-end"]),
+    ("margin-inline", &["margin-inline-start", "margin-inline-end"]),
+    (
+        "padding",
+        &["padding-top", "padding-right", "padding-bottom", "padding-left"],
+    ),
+    ("padding-block", &["padding-block-start", "padding-block-end"]),
+    ("padding-inline", &["padding-inline-start", "padding-inline-end"]),
+    ("inset", &["top", "right", "bottom", "left"]),
+    ("inset-block", &["inset-block-start", "inset-block-end"]),
+    ("inset-inline", &["inset-inline-start", "inset-inline-end"]),
+    ("overflow", &["overflow-x", "overflow-y"]),
+    (
+        "border-width",
+        &[
+            "border-top-width",
+            "border-right-width",
+            "border-bottom-width",
+            "border-left-width",
+        ],
+    ),
+    (
+        "border-style",
+        &[
+            "border-top-style",
+            "border-right-style",
+            "border-bottom-style",
+            "border-left-style",
+        ],
+    ),
+    (
+        "border-color",
+        &[
+            "border-top-color",
+            "border-right-color",
+            "border-bottom-color",
+            "border-left-color",
+        ],
+    ),
+    (
+        "border-top",
+        &["border-top-width", "border-top-style", "border-top-color"],
+    ),
+    (
+        "border-right",
+        &["border-right-width", "border-right-style", "border-right-color"],
+    ),
+    (
+        "border-bottom",
+        &["border-bottom-width", "border-bottom-style", "border-bottom-color"],
+    ),
+    (
+        "border-left",
+        &["border-left-width", "border-left-style", "border-left-color"],
+    ),
+    (
+        "border-block-start",
+        &[
+            "border-block-start-width",
+            "border-block-start-style",
+            "border-block-start-color",
+        ],
+    ),
+    (
+        "border-block-end",
+        &[
+            "border-block-end-width",
+            "border-block-end-style",
+            "border-block-end-color",
+        ],
+    ),
+    (
+        "border-inline-start",
+        &[
+            "border-inline-start-width",
+            "border-inline-start-style",
+            "border-inline-start-color",
+        ],
+    ),
+    (
+        "border-inline-end",
+        &[
+            "border-inline-end-width",
+            "border-inline-end-style",
+            "border-inline-end-color",
+        ],
+    ),
+    (
+        "border-block",
+        &[
+            "border-block-start-width",
+            "border-block-start-style",
+            "border-block-start-color",
+            "border-block-end-width",
+            "border-block-end-style",
+            "border-block-end-color",
+        ],
+    ),
+    (
+        "border-inline",
+        &[
+            "border-inline-start-width",
+            "border-inline-start-style",
+            "border-inline-start-color",
+            "border-inline-end-width",
+            "border-inline-end-style",
+            "border-inline-end-color",
+        ],
+    ),
+    (
+        "border",
+        &[
+            "border-top-width",
+            "border-top-style",
+            "border-top-color",
+            "border-right-width",
+            "border-right-style",
+            "border-right-color",
+            "border-bottom-width",
+            "border-bottom-style",
+            "border-bottom-color",
+            "border-left-width",
+            "border-left-style",
+            "border-left-color",
+        ],
+    ),
+    (
+        "border-radius",
+        &[
+            "border-top-left-radius",
+            "border-top-right-radius",
+            "border-bottom-right-radius",
+            "border-bottom-left-radius",
+        ],
+    ),
+    ("outline", &["outline-width", "outline-style", "outline-color"]),
+    (
+        "background",
+        &[
+            "background-image",
+            "background-position-x",
+            "background-position-y",
+            "background-size",
+            "background-repeat",
+            "background-attachment",
+            "background-origin",
+            "background-clip",
+            "background-color",
+        ],
+    ),
+    (
+        "background-position",
+        &["background-position-x", "background-position-y"],
+    ),
+    (
+        "mask",
+        &[
+            "mask-image",
+            "mask-position",
+            "mask-size",
+            "mask-repeat",
+            "mask-origin",
+            "mask-clip",
+            "mask-composite",
+            "mask-mode",
+        ],
+    ),
+    (
+        "font",
+        &[
+            "font-style",
+            "font-variant",
+            "font-weight",
+            "font-stretch",
+            "font-size",
+            "line-height",
+            "font-family",
+            "font-feature-settings",
+        ],
+    ),
+    (
+        "text-decoration",
+        &[
+            "text-decoration-line",
+            "text-decoration-style",
+            "text-decoration-color",
+            "text-decoration-thickness",
+        ],
+    ),
+    ("text-emphasis", &["text-emphasis-style", "text-emphasis-color"]),
+    ("text-wrap", &["text-wrap-mode", "text-wrap-style"]),
+    ("white-space", &["white-space-collapse", "text-wrap-mode"]),
+    ("flex-flow", &["flex-direction", "flex-wrap"]),
+    ("flex", &["flex-grow", "flex-shrink", "flex-basis"]),
+    ("gap", &["row-gap", "column-gap"]),
+    (
+        "grid-template",
+        &["grid-template-rows", "grid-template-columns", "grid-template-areas"],
+    ),
+    ("grid-row", &["grid-row-start", "grid-row-end"]),
+    ("grid-column", &["grid-column-start", "grid-column-end"]),
+    (
+        "grid-area",
+        &["grid-row-start", "grid-column-start", "grid-row-end", "grid-column-end"],
+    ),
+    (
+        "list-style",
+        &["list-style-position", "list-style-image", "list-style-type"],
+    ),
+];
+
+/// Returns the longhands of the given shorthand property, or None when it is not a shorthand
+pub fn shorthand_longhands(name: &str) -> Option<&'static [&'static str]> {
+    SHORTHANDS
+        .iter()
+        .find(|(shorthand, _)| *shorthand == name)
+        .map(|(_, longhands)| *longhands)
+}
+
+/// Expands a shorthand declaration into declarations for each of its longhands. Longhands that
+/// are not given in the shorthand are reset to their initial value. Other declarations are returned
+/// as-is. Shorthands with `var()` can only be expanded after substitution, so their longhands get a
+/// pending-substitution value.
+pub fn expand_declaration(declaration: CssDeclaration) -> Vec<CssDeclaration> {
+    if contains_var(&declaration.value) {
+        let Some(longhands) = shorthand_longhands(&declaration.property) else {
+            return vec![declaration];
+        };
+
+        let pending = CssValue::PendingSubstitution(declaration.property.clone(), declaration.value.clone());
+        return longhands
+            .iter()
+            .map(|longhand| CssDeclaration {
+                property: longhand.to_string(),
+                value: vec![pending.clone()],
+                important: declaration.important,
+                location: declaration.location,
+            })
+            .collect();
+    }
+
+    let Some(longhands) = expand_shorthand(&declaration.property, &declaration.value) else {
+        return vec![declaration];
+    };
+
+    longhands
+        .into_iter()
+        .map(|(property, value)| CssDeclaration {
+            property: property.to_string(),
+            value,
+            important: declaration.important,
+            location: declaration.location,
+        })
+        .collect()
+}
+
+/// Expands the value of a shorthand into the values of its longhands. Returns None when the
+/// property is not a shorthand, or the value cannot be expanded.
+pub fn expand_shorthand(name: &str, values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let longhands = shorthand_longhands(name)?;
+
+    // A css-wide keyword sets all longhands to that keyword
+    if let [value] = values {
+        if value.is_css_wide_keyword() {
+            return Some(
+                longhands
+                    .iter()
+                    .map(|longhand| (*longhand, vec![value.clone()]))
+                    .collect(),
+            );
+        }
+    }
+
+    match name {
+        "margin" | "padding" | "inset" | "border-width" | "border-style" | "border-color" => {
+            let [top, right, bottom, left] = expand_box(values)?;
+            Some(zip(longhands, vec![top, right, bottom, left]))
+        }
+        "margin-block" | "margin-inline" | "padding-block" | "padding-inline" | "inset-block" | "inset-inline"
+        | "overflow" | "gap" => {
+            let [first, second] = expand_pair(values)?;
+            Some(zip(longhands, vec![first, second]))
+        }
+        "border-top"
+        | "border-right"
+        | "border-bottom"
+        | "border-left"
+        | "border-block-start"
+        | "border-block-end"
+        | "border-inline-start"
+        | "border-inline-end"
+        | "outline"
+        | "text-decoration"
+        | "text-emphasis"
+        | "text-wrap"
+        | "flex-flow" => expand_any_order(longhands, values),
+        "border" | "border-block" | "border-inline" => {
+            // All sides get the same width, style and color
+            let side = expand_any_order(&longhands[0..3], values)?;
+            Some(
+                longhands
+                    .iter()
+                    .enumerate()
+                    .map(|(i, longhand)| (*longhand, side[i % 3].1.clone()))
+                    .collect(),
+            )
+        }
+        "border-radius" => expand_border_radius(longhands, values),
+        "background" => expand_background(values),
+        "mask" => expand_mask(longhands, values),
+        "background-position" => {
+            let (x, y) = split_layers(values)
+                .iter()
+                .map(|layer| split_position(layer))
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .unzip();
+            Some(zip(longhands, vec![join_layers(x), join_layers(y)]))
+        }
+        "font" => expand_font(values),
+        "white-space" => expand_white_space(longhands, values),
+        "flex" => expand_flex(values),
+        "grid-template" => expand_grid_template(values),
+        "grid-row" | "grid-column" | "grid-area" => expand_grid_lines(longhands, values),
+        "list-style" => expand_list_style(longhands, values),
+        _ => None,
+    }
+}
+
+fn zip(longhands: &'static [&'static str], values: Vec<Vec<CssValue>>) -> Vec<Longhand> {
+    longhands.iter().copied().zip(values).collect()
+}
+
+fn contains_var(values: &[CssValue]) -> bool {
+    values.iter().any(|value| match value {
+        CssValue::Function(name, args) => name == "var" || contains_var(args),
+        CssValue::List(values) => contains_var(values),
+        _ => false,
+    })
+}
+
+/// Returns the initial value of a longhand as a list of values
+fn initial(longhand: &str) -> Vec<CssValue> {
+    get_css_definition(longhand)
+        .map(|def| to_values(&def.initial_value))
+        .unwrap_or_default()
+}
+
+/// Converts a single value into a list of values
+fn to_values(value: &CssValue) -> Vec<CssValue> {
+    match value {
+        CssValue::None => Vec::new(),
+        CssValue::List(values) => values.clone(),
+        value => vec![value.clone()],
+    }
+}
+
+/// Returns the end of the longest match of the syntax at `pos` that consumes at least one value
+fn longest_match(syntax: &CssSyntax, values: &[CssValue], pos: usize) -> Option<usize> {
+    syntax.match_at(values, pos).into_iter().filter(|end| *end > pos).max()
+}
+
+fn is_slash(value: &CssValue) -> bool {
+    matches!(value, CssValue::Delim('/'))
+}
+
+/// Splits the values on the given separator
+fn split_on(values: &[CssValue], separator: impl Fn(&CssValue) -> bool) -> Vec<&[CssValue]> {
+    values.split(separator).collect()
+}
+
+/// Splits comma separated values into layers
+fn split_layers(values: &[CssValue]) -> Vec<&[CssValue]> {
+    split_on(values, |value| *value == CssValue::Comma)
+}
+
+/// Joins the values of each layer with commas
+fn join_layers(layers: Vec<Vec<CssValue>>) -> Vec<CssValue> {
+    let mut values = Vec::new();
+    for (i, layer) in layers.into_iter().enumerate() {
+        if i > 0 {
+            values.push(CssValue::Comma);
+        }
+        values.extend(layer);
+    }
+    values
+}
+
+/// Expands 1 to 4 values into top, right, bottom and left
+fn expand_box(values: &[CssValue]) -> Option<[Vec<CssValue>; 4]> {
+    let v = |i: usize| vec![values[i].clone()];
+    match values.len() {
+        1 => Some([v(0), v(0), v(0), v(0)]),
+        2 => Some([v(0), v(1), v(0), v(1)]),
+        3 => Some([v(0), v(1), v(2), v(1)]),
+        4 => Some([v(0), v(1), v(2), v(3)]),
+        _ => None,
+    }
+}
+
+/// Expands 1 or 2 values into a start and end value
+fn expand_pair(values: &[CssValue]) -> Option<[Vec<CssValue>; 2]> {
+    match values {
+        [first] => Some([vec![first.clone()], vec![first.clone()]]),
+        [first, second] => Some([vec![first.clone()], vec![second.clone()]]),
+        _ => None,
+    }
+}
+
+/// Expands a shorthand whose longhands may appear in any order (`a || b || c`). Each value is
+/// assigned to the first longhand that accepts it.
+fn expand_any_order(longhands: &'static [&'static str], values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let mut assigned: Vec<Option<Vec<CssValue>>> = vec![None; longhands.len()];
+
+    let mut pos = 0;
+    while pos < values.len() {
+        let (idx, end) = longhands.iter().enumerate().find_map(|(idx, longhand)| {
+            if assigned[idx].is_some() {
+                return None;
+            }
+            let syntax = &get_css_definition(longhand)?.syntax;
+            longest_match(syntax, values, pos).map(|end| (idx, end))
+        })?;
+
+        assigned[idx] = Some(values[pos..end].to_vec());
+        pos = end;
+    }
+
+    Some(
+        longhands
+            .iter()
+            .zip(assigned)
+            .map(|(longhand, value)| (*longhand, value.unwrap_or_else(|| initial(longhand))))
+            .collect(),
+    )
+}
+
+fn expand_border_radius(longhands: &'static [&'static str], values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let parts = split_on(values, is_slash);
+    let horizontal = expand_box(parts.first()?)?;
+    let vertical = match parts.len() {
+        1 => horizontal.clone(),
+        2 => expand_box(parts[1])?,
+        _ => return None,
+    };
+
+    let corners = horizontal
+        .into_iter()
+        .zip(vertical)
+        .map(|(h, v)| if h == v { h } else { [h, v].concat() })
+        .collect();
+
+    Some(zip(longhands, corners))
+}
+
+fn is_keyword_in(value: &CssValue, keywords: &[&str]) -> bool {
+    keywords.iter().any(|keyword| value.is_keyword(keyword))
+}
+
+/// Splits a `<bg-position>` into its horizontal and vertical components
+fn split_position(values: &[CssValue]) -> Option<(Vec<CssValue>, Vec<CssValue>)> {
+    let center = || vec![CssValue::Keyword("center".into())];
+    let is_vertical = |value: &CssValue| is_keyword_in(value, &["top", "bottom"]);
+    let is_horizontal = |value: &CssValue| is_keyword_in(value, &["left", "right"]);
+
+    match values {
+        [value] if is_vertical(value) => Some((center(), vec![value.clone()])),
+        [value] if value.is_keyword("center") => Some((center(), center())),
+        [value] => Some((vec![value.clone()], center())),
+        [first, second] if is_vertical(first) || is_horizontal(second) => {
+            Some((vec![second.clone()], vec![first.clone()]))
+        }
+        [first, second] => Some((vec![first.clone()], vec![second.clone()])),
+        _ if values.len() <= 4 => {
+            // Three and four value syntax: keywords, each optionally followed by an offset
+            let mut groups: Vec<Vec<CssValue>> = Vec::new();
+            for value in values {
+                match (value, groups.last_mut()) {
+                    (CssValue::Keyword(_), _) => groups.push(vec![value.clone()]),
+                    (_, Some(group)) if group.len() == 1 => group.push(value.clone()),
+                    _ => return None,
+                }
+            }
+
+            let [first, second] = <[Vec<CssValue>; 2]>::try_from(groups).ok()?;
+            if is_vertical(&first[0]) || is_horizontal(&second[0]) {
+                Some((second, first))
+            } else {
+                Some((first, second))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Expands the `background` shorthand. Each longhand gets a comma separated list with a value
+/// for each layer.
+fn expand_background(values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let layers = split_layers(values);
+    let syntax = |name: &str| get_type_definition(name);
+
+    let mut images = Vec::new();
+    let mut positions_x = Vec::new();
+    let mut positions_y = Vec::new();
+    let mut sizes = Vec::new();
+    let mut repeats = Vec::new();
+    let mut attachments = Vec::new();
+    let mut origins = Vec::new();
+    let mut clips = Vec::new();
+    let mut color = initial("background-color");
+
+    for (i, layer) in layers.iter().enumerate() {
+        let is_final = i == layers.len() - 1;
+
+        let mut image = None;
+        let mut position = None;
+        let mut size = None;
+        let mut repeat = None;
+        let mut attachment = None;
+        let mut boxes: Vec<Vec<CssValue>> = Vec::new();
+        let mut layer_color = None;
+
+        let mut pos = 0;
+        while pos < layer.len() {
+            if image.is_none() {
+                if let Some(end) = longest_match(syntax("bg-image")?, layer, pos) {
+                    image = Some(layer[pos..end].to_vec());
+                    pos = end;
+                    continue;
+                }
+            }
+            if position.is_none() {
+                if let Some(end) = longest_match(syntax("bg-position")?, layer, pos) {
+                    position = Some(split_position(&layer[pos..end])?);
+                    pos = end;
+                    if layer.get(pos).is_some_and(is_slash) {
+                        let end = longest_match(syntax("bg-size")?, layer, pos + 1)?;
+                        size = Some(layer[pos + 1..end].to_vec());
+                        pos = end;
+                    }
+                    continue;
+                }
+            }
+            if repeat.is_none() {
+                if let Some(end) = longest_match(syntax("repeat-style")?, layer, pos) {
+                    repeat = Some(layer[pos..end].to_vec());
+                    pos = end;
+                    continue;
+                }
+            }
+            if attachment.is_none() {
+                if let Some(end) = longest_match(syntax("attachment")?, layer, pos) {
+                    attachment = Some(layer[pos..end].to_vec());
+                    pos = end;
+                    continue;
+                }
+            }
+            if boxes.len() < 2 {
+                if let Some(end) = longest_match(syntax("visual-box")?, layer, pos) {
+                    boxes.push(layer[pos..end].to_vec());
+                    pos = end;
+                    continue;
+                }
+            }
+            if is_final && layer_color.is_none() {
+                let color_syntax = &get_css_definition("background-color")?.syntax;
+                if let Some(end) = longest_match(color_syntax, layer, pos) {
+                    layer_color = Some(layer[pos..end].to_vec());
+                    pos = end;
+                    continue;
+                }
+            }
+            return None;
+        }
+
+        let first_value = |name: &str| initial(name).into_iter().take(1).collect::<Vec<_>>();
+        let (x, y) = position.unwrap_or_else(|| {
+            (
+                first_value("background-position-x"),
+                first_value("background-position-y"),
+            )
+        });
+
+        images.push(image.unwrap_or_else(|| initial("background-image")));
+        positions_x.push(x);
+        positions_y.push(y);
+        sizes.push(size.unwrap_or_else(|| initial("background-size")));
+        repeats.push(repeat.unwrap_or_else(|| initial("background-repeat")));
+        attachments.push(attachment.unwrap_or_else(|| initial("background-attachment")));
+        // A single box sets both the origin and the clip
+        match boxes.as_slice() {
+            [] => {
+                origins.push(initial("background-origin"));
+                clips.push(initial("background-clip"));
+            }
+            [both] => {
+                origins.push(both.clone());
+                clips.push(both.clone());
+            }
+            [origin, clip, ..] => {
+                origins.push(origin.clone());
+                clips.push(clip.clone());
+            }
+        }
+        if let Some(layer_color) = layer_color {
+            color = layer_color;
+        }
+    }
+
+    Some(vec![
+        ("background-image", join_layers(images)),
+        ("background-position-x", join_layers(positions_x)),
+        ("background-position-y", join_layers(positions_y)),
+        ("background-size", join_layers(sizes)),
+        ("background-repeat", join_layers(repeats)),
+        ("background-attachment", join_layers(attachments)),
+        ("background-origin", join_layers(origins)),
+        ("background-clip", join_layers(clips)),
+        ("background-color", color),
+    ])
+}
+
+/// Expands the `mask` shorthand. Like `background`, each longhand gets a value for each layer.
+fn expand_mask(longhands: &'static [&'static str], values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let syntax = |name: &str| get_type_definition(name);
+    let mut layers: Vec<Vec<Vec<CssValue>>> = vec![Vec::new(); longhands.len()];
+
+    for layer in split_layers(values) {
+        let mut image = None;
+        let mut position = None;
+        let mut size = None;
+        let mut repeat = None;
+        let mut boxes: Vec<Vec<CssValue>> = Vec::new();
+        let mut composite = None;
+        let mut mode = None;
+
+        let mut pos = 0;
+        while pos < layer.len() {
+            let mut try_match = |slot: &mut Option<Vec<CssValue>>, syntax: &CssSyntax| {
+                if slot.is_some() {
+                    return false;
+                }
+                match longest_match(syntax, layer, pos) {
+                    Some(end) => {
+                        *slot = Some(layer[pos..end].to_vec());
+                        pos = end;
+                        true
+                    }
+                    None => false,
+                }
+            };
+
+            if try_match(&mut image, syntax("mask-reference")?)
+                || try_match(&mut repeat, syntax("repeat-style")?)
+                || try_match(&mut composite, syntax("compositing-operator")?)
+                || try_match(&mut mode, syntax("masking-mode")?)
+            {
+                continue;
+            }
+            if position.is_none() {
+                if let Some(end) = longest_match(syntax("bg-position")?, layer, pos) {
+                    position = Some(layer[pos..end].to_vec());
+                    pos = end;
+                    if layer.get(pos).is_some_and(is_slash) {
+                        let end = longest_match(syntax("bg-size")?, layer, pos + 1)?;
+                        size = Some(layer[pos + 1..end].to_vec());
+                        pos = end;
+                    }
+                    continue;
+                }
+            }
+            // The first box sets the origin, the second the clip (which may also be `no-clip`)
+            if boxes.len() < 2 {
+                if let Some(end) = longest_match(syntax("coord-box")?, layer, pos) {
+                    boxes.push(layer[pos..end].to_vec());
+                    pos = end;
+                    continue;
+                }
+                if !boxes.is_empty() && layer[pos].is_keyword("no-clip") {
+                    boxes.push(vec![layer[pos].clone()]);
+                    pos += 1;
+                    continue;
+                }
+            }
+            return None;
+        }
+
+        let (origin, clip) = match boxes.as_slice() {
+            [] => (initial("mask-origin"), initial("mask-clip")),
+            [both] => (both.clone(), both.clone()),
+            [origin, clip, ..] => (origin.clone(), clip.clone()),
+        };
+
+        let layer_values = [
+            image.unwrap_or_else(|| initial("mask-image")),
+            position.unwrap_or_else(|| initial("mask-position")),
+            size.unwrap_or_else(|| initial("mask-size")),
+            repeat.unwrap_or_else(|| initial("mask-repeat")),
+            origin,
+            clip,
+            composite.unwrap_or_else(|| initial("mask-composite")),
+            mode.unwrap_or_else(|| initial("mask-mode")),
+        ];
+        for (i, value) in layer_values.into_iter().enumerate() {
+            layers[i].push(value);
+        }
+    }
+
+    Some(zip(longhands, layers.into_iter().map(join_layers).collect()))
+}
+
+/// Expands the `font` shorthand: `[ style || variant || weight || stretch ]? size [ / line-height ]? family`
+fn expand_font(values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let mut result = vec![
+        ("font-style", initial("font-style")),
+        ("font-variant", initial("font-variant")),
+        ("font-weight", initial("font-weight")),
+        ("font-stretch", initial("font-stretch")),
+        ("font-size", initial("font-size")),
+        ("line-height", initial("line-height")),
+        ("font-family", initial("font-family")),
+        ("font-feature-settings", initial("font-feature-settings")),
+    ];
+
+    // System fonts are not supported, so they use the default system font
+    if let [value] = values {
+        if is_keyword_in(
+            value,
+            &["caption", "icon", "menu", "message-box", "small-caption", "status-bar"],
+        ) {
+            result[6].1 = vec![CssValue::Keyword("system-ui".into())];
+            return Some(result);
+        }
+    }
+
+    let size_syntax = &get_css_definition("font-size")?.syntax;
+    let prefix = [
+        (0, &get_css_definition("font-style")?.syntax),
+        (1, get_type_definition("font-variant-css2")?),
+        (2, &get_css_definition("font-weight")?.syntax),
+        (3, get_type_definition("font-width-css3")?),
+    ];
+
+    let mut pos = 0;
+    let mut assigned = [false; 4];
+    loop {
+        let value = values.get(pos)?;
+        // `normal` is valid for all of the optional values, and leaves them at their initial value
+        if value.is_keyword("normal") {
+            pos += 1;
+            continue;
+        }
+        if longest_match(size_syntax, values, pos).is_some() && !matches!(value, CssValue::Number(_)) {
+            break;
+        }
+
+        let (idx, end) = prefix
+            .iter()
+            .filter(|(idx, _)| !assigned[*idx])
+            .find_map(|(idx, syntax)| longest_match(syntax, values, pos).map(|end| (*idx, end)))?;
+        assigned[idx] = true;
+        result[idx].1 = values[pos..end].to_vec();
+        pos = end;
+    }
+
+    result[4].1 = vec![values[pos].clone()];
+    pos += 1;
+
+    if values.get(pos).is_some_and(is_slash) {
+        result[5].1 = vec![values.get(pos + 1)?.clone()];
+        pos += 2;
+    }
+
+    if pos >= values.len() {
+        return None;
+    }
+    result[6].1 = values[pos..].to_vec();
+
+    Some(result)
+}
+
+fn expand_white_space(longhands: &'static [&'static str], values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let keyword = |k: &str| vec![CssValue::Keyword(k.into())];
+
+    let (collapse, mode) = match values {
+        [value] if value.is_keyword("normal") => ("collapse", "wrap"),
+        [value] if value.is_keyword("pre") => ("preserve", "nowrap"),
+        [value] if value.is_keyword("pre-wrap") => ("preserve", "wrap"),
+        [value] if value.is_keyword("pre-line") => ("preserve-breaks", "wrap"),
+        _ => return expand_any_order(longhands, values),
+    };
+
+    Some(zip(longhands, vec![keyword(collapse), keyword(mode)]))
+}
+
+/// Expands the `flex` shorthand (CSS Flexbox 1, 7.1)
+fn expand_flex(values: &[CssValue]) -> Option<Vec<Longhand>> {
+    if let [value] = values {
+        if value.is_keyword("none") {
+            return Some(vec![
+                ("flex-grow", vec![CssValue::Number(0.0)]),
+                ("flex-shrink", vec![CssValue::Number(0.0)]),
+                ("flex-basis", vec![CssValue::Keyword("auto".into())]),
+            ]);
+        }
+    }
+
+    let mut grow = None;
+    let mut shrink = None;
+    let mut basis = None;
+
+    let mut prev_was_grow = false;
+    for value in values {
+        let is_factor = matches!(value, CssValue::Number(_));
+        if is_factor && grow.is_none() {
+            grow = Some(value.clone());
+            prev_was_grow = true;
+            continue;
+        }
+        if is_factor && shrink.is_none() && prev_was_grow {
+            shrink = Some(value.clone());
+            prev_was_grow = false;
+            continue;
+        }
+        if basis.is_some() {
+            return None;
+        }
+        basis = Some(value.clone());
+        prev_was_grow = false;
+    }
+
+    // An omitted basis is 0 when a flex factor is given
+    let basis = match (&grow, basis) {
+        (_, Some(basis)) => basis,
+        (Some(_), None) => CssValue::Percentage(0.0),
+        (None, None) => return None,
+    };
+
+    Some(vec![
+        ("flex-grow", vec![grow.unwrap_or(CssValue::Number(1.0))]),
+        ("flex-shrink", vec![shrink.unwrap_or(CssValue::Number(1.0))]),
+        ("flex-basis", vec![basis]),
+    ])
+}
+
+/// Expands the `grid-template` shorthand (CSS Grid 1, 7.4)
+fn expand_grid_template(values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let none = || vec![CssValue::Keyword("none".into())];
+
+    if let [value] = values {
+        if value.is_keyword("none") {
+            return Some(vec![
+                ("grid-template-rows", none()),
+                ("grid-template-columns", none()),
+                ("grid-template-areas", none()),
+            ]);
+        }
+    }
+
+    let parts = split_on(values, is_slash);
+    if !values.iter().any(|value| matches!(value, CssValue::String(_))) {
+        // <'grid-template-rows'> / <'grid-template-columns'>
+        let [rows, columns] = <[&[CssValue]; 2]>::try_from(parts).ok()?;
+        return Some(vec![
+            ("grid-template-rows", rows.to_vec()),
+            ("grid-template-columns", columns.to_vec()),
+            ("grid-template-areas", none()),
+        ]);
+    }
+
+    // [ <line-names>? <string> <track-size>? <line-names>? ]+ [ / <explicit-track-list> ]?
+    let columns = match parts.len() {
+        1 => none(),
+        2 => parts[1].to_vec(),
+        _ => return None,
+    };
+
+    let mut areas = Vec::new();
+    let mut rows = Vec::new();
+    let mut row_has_size = true;
+    for value in parts[0] {
+        match value {
+            CssValue::String(_) => {
+                if !row_has_size {
+                    rows.push(CssValue::Keyword("auto".into()));
+                }
+                areas.push(value.clone());
+                row_has_size = false;
+            }
+            CssValue::Function(name, _) if name == "[" => rows.push(value.clone()),
+            _ => {
+                rows.push(value.clone());
+                row_has_size = true;
+            }
+        }
+    }
+    if !row_has_size {
+        rows.push(CssValue::Keyword("auto".into()));
+    }
+
+    Some(vec![
+        ("grid-template-rows", rows),
+        ("grid-template-columns", columns),
+        ("grid-template-areas", areas),
+    ])
+}
+
+/// Expands `grid-row`, `grid-column` and `grid-area`. An omitted line copies a preceding custom
+/// identifier, or is `auto` otherwise.
+fn expand_grid_lines(longhands: &'static [&'static str], values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let parts = split_on(values, is_slash);
+    if parts.len() > longhands.len() || parts.iter().any(|part| part.is_empty()) {
+        return None;
+    }
+
+    let is_ident = |part: &[CssValue]| match part {
+        [CssValue::Keyword(k)] => !k.eq_ignore_ascii_case("auto") && !k.eq_ignore_ascii_case("span"),
+        _ => false,
+    };
+
+    // The line each omitted line copies from: the end lines copy their start lines, and the
+    // column start of `grid-area` copies the row start
+    let half = longhands.len() / 2;
+    let source = |idx: usize| {
+        if idx >= half {
+            Some(idx - half)
+        } else {
+            idx.checked_sub(1).map(|_| 0)
+        }
+    };
+
+    let mut result: Vec<Vec<CssValue>> = Vec::new();
+    for idx in 0..longhands.len() {
+        let value = match parts.get(idx) {
+            Some(part) => part.to_vec(),
+            None => match source(idx).map(|src| result[src].clone()) {
+                Some(src) if is_ident(&src) => src,
+                _ => vec![CssValue::Keyword("auto".into())],
+            },
+        };
+        result.push(value);
+    }
+
+    Some(zip(longhands, result))
+}
+
+/// Expands `list-style`. A `none` sets the image and/or type, whichever is not given otherwise.
+fn expand_list_style(longhands: &'static [&'static str], values: &[CssValue]) -> Option<Vec<Longhand>> {
+    let nones = values.iter().filter(|value| value.is_keyword("none")).count();
+    let others = values
+        .iter()
+        .filter(|value| !value.is_keyword("none"))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut image = None;
+    let mut type_ = None;
+    let mut position = None;
+    let mut pos = 0;
+    while pos < others.len() {
+        let position_end = longest_match(&get_css_definition("list-style-position")?.syntax, &others, pos);
+        let image_end = longest_match(&get_css_definition("list-style-image")?.syntax, &others, pos);
+        let type_end = longest_match(&get_css_definition("list-style-type")?.syntax, &others, pos);
+
+        let (slot, end) = match (position_end, image_end, type_end) {
+            (Some(end), _, _) if position.is_none() => (&mut position, end),
+            (_, Some(end), _) if image.is_none() => (&mut image, end),
+            (_, _, Some(end)) if type_.is_none() => (&mut type_, end),
+            _ => return None,
+        };
+        *slot = Some(others[pos..end].to_vec());
+        pos = end;
+    }
+
+    let none = || vec![CssValue::Keyword("none".into())];
+    match nones {
+        0 => {}
+        1 if image.is_none() => {
+            image = Some(none());
+            if type_.is_none() {
+                type_ = Some(none());
+            }
+        }
+        1 if type_.is_none() => type_ = Some(none()),
+        2 if image.is_none() && type_.is_none() => {
+            image = Some(none());
+            type_ = Some(none());
+        }
+        _ => return None,
+    }
+
+    let values = vec![
+        position.unwrap_or_else(|| initial(longhands[0])),
+        image.unwrap_or_else(|| initial(longhands[1])),
+        type_.unwrap_or_else(|| initial(longhands[2])),
+    ];
+    Some(zip(longhands, values))
+}
+
+/// Serializes the values of the longhands back into the value of the shorthand. `lookup` returns
+/// the value of a longhand. Returns None when a longhand is missing or the values cannot be
+/// represented by the shorthand.
+pub fn serialize_shorthand(name: &str, lookup: impl Fn(&str) -> Option<CssValue>) -> Option<String> {
+    let longhands = shorthand_longhands(name)?;
+    let values = longhands
+        .iter()
+        .map(|longhand| lookup(longhand))
+        .collect::<Option<Vec<_>>>()?;
+
+    let first = &values[0];
+    // Longhands set by the same shorthand with `var()` serialize to its original value
+    if let CssValue::PendingSubstitution(shorthand, value) = first {
+        let all_pending = shorthand == name && values.iter().all(|value| value == first);
+        return all_pending.then(|| CssValue::List(value.clone()).to_string());
+    }
+    if first.is_css_wide_keyword() {
+        return values.iter().all(|value| value == first).then(|| first.to_string());
+    }
+    if values.iter().any(|value| value.is_css_wide_keyword()) {
+        return None;
+    }
+
+    let serialized = match name {
+        "margin" | "padding" | "inset" | "border-width" | "border-style" | "border-color" => serialize_box(&values),
+        "margin-block" | "margin-inline" | "padding-block" | "padding-inline" | "inset-block" | "inset-inline"
+        | "overflow" | "gap" => {
+            if values[0] == values[1] {
+                values[0].to_string()
+            } else {
+                format!("{} {}", values[0], values[1])
+            }
+        }
+        "border" | "border-block" | "border-inline" => {
+            // Only possible when all sides are the same
+            let side = &values[0..3];
+            if values.chunks(3).any(|chunk| chunk != side) {
+                return None;
+            }
+            serialize_any_order(&longhands[0..3], side)
+        }
+        "border-radius" => serialize_border_radius(&values)?,
+        "background-position" => {
+            let layers = |value: &CssValue| {
+                split_layers(&to_values(value))
+                    .iter()
+                    .map(|layer| CssValue::List(layer.to_vec()))
+                    .collect::<Vec<_>>()
+            };
+            let (x, y) = (layers(&values[0]), layers(&values[1]));
+            if x.len() != y.len() {
+                return None;
+            }
+            x.iter()
+                .zip(&y)
+                .map(|(x, y)| format!("{x} {y}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        "background" => serialize_background(&values)?,
+        "mask" => serialize_mask(&values)?,
+        "font" => serialize_font(&values)?,
+        "white-space" => {
+            let pair = (values[0].as_keyword()?, values[1].as_keyword()?);
+            match pair {
+                ("collapse", "wrap") => "normal".to_string(),
+                ("preserve", "nowrap") => "pre".to_string(),
+                ("preserve", "wrap") => "pre-wrap".to_string(),
+                ("preserve-breaks", "wrap") => "pre-line".to_string(),
+                _ => serialize_any_order(longhands, &values),
+            }
+        }
+        "flex" => format!("{} {} {}", values[0], values[1], values[2]),
+        "grid-template" => {
+            if !values[2].is_keyword("none") {
+                return None;
+            }
+            if values[0].is_keyword("none") && values[1].is_keyword("none") {
+                "none".to_string()
+            } else {
+                format!("{} / {}", values[0], values[1])
+            }
+        }
+        "grid-row" | "grid-column" | "grid-area" => {
+            // Trailing lines are left out when the expansion would produce them anyway
+            let mut count = values.len();
+            while count > 1 {
+                let expanded = expand_grid_lines(longhands, &join_lines(&values[..count - 1]))?;
+                if to_values(&values[count - 1]) != expanded[count - 1].1 {
+                    break;
+                }
+                count -= 1;
+            }
+
+            values[..count]
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(" / ")
+        }
+        _ => serialize_any_order(longhands, &values),
+    };
+
+    Some(serialized)
+}
+
+/// Joins grid lines with slashes
+fn join_lines(lines: &[CssValue]) -> Vec<CssValue> {
+    let mut values = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            values.push(CssValue::Delim('/'));
+        }
+        values.extend(to_values(line));
+    }
+    values
+}
+
+/// Serializes 4 values for top, right, bottom and left into the shortest form
+fn serialize_box(values: &[CssValue]) -> String {
+    let [top, right, bottom, left] = [&values[0], &values[1], &values[2], &values[3]];
+
+    if left != right {
+        format!("{top} {right} {bottom} {left}")
+    } else if top != bottom {
+        format!("{top} {right} {bottom}")
+    } else if top != right {
+        format!("{top} {right}")
+    } else {
+        top.to_string()
+    }
+}
+
+/// Serializes a shorthand with longhands in any order, leaving out the longhands with their
+/// initial value
+fn serialize_any_order(longhands: &[&str], values: &[CssValue]) -> String {
+    let parts = longhands
+        .iter()
+        .zip(values)
+        .filter(|(longhand, value)| to_values(value) != initial(longhand))
+        .map(|(_, value)| value.to_string())
+        .collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        return values[0].to_string();
+    }
+    parts.join(" ")
+}
+
+fn serialize_border_radius(values: &[CssValue]) -> Option<String> {
+    let mut horizontal = Vec::new();
+    let mut vertical = Vec::new();
+    for value in values {
+        match to_values(value).as_slice() {
+            [both] => {
+                horizontal.push(both.clone());
+                vertical.push(both.clone());
+            }
+            [h, v] => {
+                horizontal.push(h.clone());
+                vertical.push(v.clone());
+            }
+            _ => return None,
+        }
+    }
+
+    let h = serialize_box(&horizontal);
+    if horizontal == vertical {
+        return Some(h);
+    }
+    Some(format!("{} / {}", h, serialize_box(&vertical)))
+}
+
+fn serialize_background(values: &[CssValue]) -> Option<String> {
+    let longhands = shorthand_longhands("background")?;
+    let layered = values[..8]
+        .iter()
+        .map(|value| {
+            split_layers(&to_values(value))
+                .iter()
+                .map(|layer| layer.to_vec())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let count = layered[0].len();
+    if layered.iter().any(|layers| layers.len() != count) {
+        return None;
+    }
+
+    let mut layers = Vec::new();
+    for i in 0..count {
+        let layer = layered.iter().map(|layers| &layers[i]).collect::<Vec<_>>();
+        let value = |idx: usize| layer[idx];
+        let is_initial = |idx: usize| *value(idx) == initial(longhands[idx]);
+
+        let mut parts = Vec::new();
+        if !is_initial(0) {
+            parts.push(CssValue::List(value(0).clone()).to_string());
+        }
+        let position = format!(
+            "{} {}",
+            CssValue::List(value(1).clone()),
+            CssValue::List(value(2).clone())
+        );
+        if !is_initial(3) {
+            parts.push(format!("{} / {}", position, CssValue::List(value(3).clone())));
+        } else if !is_initial(1) || !is_initial(2) {
+            parts.push(position);
+        }
+        for idx in [4, 5] {
+            if !is_initial(idx) {
+                parts.push(CssValue::List(value(idx).clone()).to_string());
+            }
+        }
+        if !is_initial(6) || !is_initial(7) {
+            parts.push(CssValue::List(value(6).clone()).to_string());
+            if value(6) != value(7) {
+                parts.push(CssValue::List(value(7).clone()).to_string());
+            }
+        }
+        if i == count - 1 && to_values(&values[8]) != initial("background-color") {
+            parts.push(values[8].to_string());
+        }
+
+        if parts.is_empty() {
+            parts.push("none".to_string());
+        }
+        layers.push(parts.join(" "));
+    }
+
+    Some(layers.join(", "))
+}
+
+fn serialize_mask(values: &[CssValue]) -> Option<String> {
+    let longhands = shorthand_longhands("mask")?;
+    let layered = values
+        .iter()
+        .map(|value| {
+            split_layers(&to_values(value))
+                .iter()
+                .map(|layer| layer.to_vec())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let count = layered[0].len();
+    if layered.iter().any(|layers| layers.len() != count) {
+        return None;
+    }
+
+    let mut layers = Vec::new();
+    for i in 0..count {
+        let layer = layered.iter().map(|layers| &layers[i]).collect::<Vec<_>>();
+        let value = |idx: usize| CssValue::List(layer[idx].clone()).to_string();
+        let is_initial = |idx: usize| *layer[idx] == initial(longhands[idx]);
+
+        let mut parts = Vec::new();
+        if !is_initial(0) {
+            parts.push(value(0));
+        }
+        if !is_initial(2) {
+            parts.push(format!("{} / {}", value(1), value(2)));
+        } else if !is_initial(1) {
+            parts.push(value(1));
+        }
+        if !is_initial(3) {
+            parts.push(value(3));
+        }
+        if !is_initial(4) || !is_initial(5) {
+            parts.push(value(4));
+            if layer[4] != layer[5] {
+                parts.push(value(5));
+            }
+        }
+        for idx in [6, 7] {
+            if !is_initial(idx) {
+                parts.push(value(idx));
+            }
+        }
+
+        if parts.is_empty() {
+            parts.push("none".to_string());
+        }
+        layers.push(parts.join(" "));
+    }
+
+    Some(layers.join(", "))
+}
+
+fn serialize_font(values: &[CssValue]) -> Option<String> {
+    let [style, variant, weight, stretch, size, line_height, family, feature_settings] = values else {
+        return None;
+    };
+
+    // Values that cannot be set by the shorthand
+    if !(variant.is_keyword("normal") || variant.is_keyword("small-caps"))
+        || matches!(stretch, CssValue::Percentage(_))
+        || !feature_settings.is_keyword("normal")
+    {
+        return None;
+    }
+
+    let mut parts = Vec::new();
+    for value in [style, variant, weight, stretch] {
+        if !value.is_keyword("normal") {
+            parts.push(value.to_string());
+        }
+    }
+    if line_height.is_keyword("normal") {
+        parts.push(size.to_string());
+    } else {
+        parts.push(format!("{size}/{line_height}"));
+    }
+    parts.push(family.to_string());
+
+    Some(parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        css3::Css3,
+        interface::{css3::CssOrigin, ParserConfig},
+    };
+
+    /// Parses a declaration into the declarations of its longhands. Invalid declarations are dropped.
+    fn declarations(declaration: &str) -> Vec<CssDeclaration> {
+        let config = ParserConfig {
+            ignore_errors: true,
+            ..Default::default()
+        };
+        let css = format!("p {{ {declaration} }}");
+        let sheet = Css3::parse_str(&css, config, CssOrigin::Author, "test.css").unwrap();
+        sheet.rules[0].declarations.clone()
+    }
+
+    /// Parses a declaration and returns the resulting longhands with their serialized values
+    fn expand(declaration: &str) -> Vec<(String, String)> {
+        declarations(declaration)
+            .into_iter()
+            .map(|declaration| (declaration.property, CssValue::List(declaration.value).to_string()))
+            .collect()
+    }
+
+    fn value<'a>(longhands: &'a [(String, String)], property: &str) -> &'a str {
+        longhands
+            .iter()
+            .find(|(name, _)| name == property)
+            .map(|(_, value)| value.as_str())
+            .unwrap_or_else(|| panic!("{property} was not set"))
+    }
+
+    /// Expands the declaration and serializes its longhands back into the shorthand
+    fn round_trip(property: &str, value: &str) -> Option<String> {
+        let declarations = declarations(&format!("{property}: {value}"));
+        serialize_shorthand(property, |longhand| {
+            declarations
+                .iter()
+                .find(|declaration| declaration.property == longhand)
+                .map(|declaration| match declaration.value.as_slice() {
+                    [value] => value.clone(),
+                    values => CssValue::List(values.to_vec()),
+                })
+        })
+    }
+
+    #[test]
+    fn box_shorthands() {
+        let margin = expand("margin: 1px 2px 3px");
+        assert_eq!(
+            margin,
+            [
+                ("margin-top".to_string(), "1px".to_string()),
+                ("margin-right".to_string(), "2px".to_string()),
+                ("margin-bottom".to_string(), "3px".to_string()),
+                ("margin-left".to_string(), "2px".to_string()),
+            ]
+        );
+
+        let padding = expand("padding: 4px");
+        assert_eq!(value(&padding, "padding-left"), "4px");
+        assert_eq!(value(&padding, "padding-bottom"), "4px");
+
+        let inset = expand("inset: 0 auto");
+        assert_eq!(value(&inset, "top"), "0");
+        assert_eq!(value(&inset, "right"), "auto");
+        assert_eq!(value(&inset, "left"), "auto");
+
+        let margin = expand("margin-inline: 1em 2em");
+        assert_eq!(value(&margin, "margin-inline-start"), "1em");
+        assert_eq!(value(&margin, "margin-inline-end"), "2em");
+    }
+
+    #[test]
+    fn missing_longhands_are_reset_to_initial() {
+        let border = expand("border: 2px solid");
+        assert_eq!(border.len(), 12);
+        assert_eq!(value(&border, "border-top-width"), "2px");
+        assert_eq!(value(&border, "border-left-style"), "solid");
+        assert_eq!(value(&border, "border-bottom-color"), "currentcolor");
+
+        let font = expand("font: italic bold 12px/1.5 serif");
+        assert_eq!(value(&font, "font-style"), "italic");
+        assert_eq!(value(&font, "font-weight"), "bold");
+        assert_eq!(value(&font, "font-size"), "12px");
+        assert_eq!(value(&font, "line-height"), "1.5");
+        assert_eq!(value(&font, "font-family"), "serif");
+        assert_eq!(value(&font, "font-variant"), "normal");
+
+        let font = expand("font: 10px sans-serif");
+        assert_eq!(value(&font, "font-style"), "normal");
+        assert_eq!(value(&font, "line-height"), "normal");
+
+        let list_style = expand("list-style: square");
+        assert_eq!(value(&list_style, "list-style-type"), "square");
+        assert_eq!(value(&list_style, "list-style-position"), "outside");
+        assert_eq!(value(&list_style, "list-style-image"), "none");
+    }
+
+    #[test]
+    fn other_shorthands() {
+        let radius = expand("border-radius: 1px 2px / 3px");
+        assert_eq!(value(&radius, "border-top-left-radius"), "1px 3px");
+        assert_eq!(value(&radius, "border-top-right-radius"), "2px 3px");
+        assert_eq!(value(&radius, "border-bottom-right-radius"), "1px 3px");
+
+        let background = expand("background: url(a.png) no-repeat red");
+        assert_eq!(value(&background, "background-image"), "url(\"a.png\")");
+        assert_eq!(value(&background, "background-repeat"), "no-repeat");
+        assert_eq!(value(&background, "background-color"), "red");
+
+        let flex = expand("flex: 1");
+        assert_eq!(value(&flex, "flex-grow"), "1");
+        assert_eq!(value(&flex, "flex-shrink"), "1");
+        assert_eq!(value(&flex, "flex-basis"), "0%");
+
+        let flex = expand("flex: none");
+        assert_eq!(value(&flex, "flex-grow"), "0");
+        assert_eq!(value(&flex, "flex-shrink"), "0");
+        assert_eq!(value(&flex, "flex-basis"), "auto");
+
+        let grid = expand("grid-template: auto 1fr / 100px 1fr");
+        assert_eq!(value(&grid, "grid-template-rows"), "auto 1fr");
+        assert_eq!(value(&grid, "grid-template-columns"), "100px 1fr");
+    }
+
+    #[test]
+    fn css_wide_keywords_and_var() {
+        let margin = expand("margin: inherit");
+        assert!(margin.iter().all(|(_, value)| value == "inherit"));
+
+        let declarations = declarations("margin: var(--gap) 0");
+        assert_eq!(declarations.len(), 4);
+        assert!(declarations.iter().all(
+            |declaration| matches!(declaration.value[0], CssValue::PendingSubstitution(ref name, _) if name == "margin")
+        ));
+    }
+
+    #[test]
+    fn invalid_shorthands_are_not_expanded() {
+        assert_eq!(expand_shorthand("margin", &[]), None);
+        assert_eq!(expand("margin: 1px 2px 3px 4px 5px").len(), 0);
+        assert_eq!(expand("border: solid solid").len(), 0);
+    }
+
+    #[test]
+    fn serialization() {
+        assert_eq!(round_trip("margin", "1px").as_deref(), Some("1px"));
+        assert_eq!(round_trip("margin", "1px 2px").as_deref(), Some("1px 2px"));
+        assert_eq!(
+            round_trip("margin", "1px 2px 3px 4px").as_deref(),
+            Some("1px 2px 3px 4px")
+        );
+        assert_eq!(round_trip("border", "2px solid red").as_deref(), Some("2px solid red"));
+        assert_eq!(round_trip("margin", "inherit").as_deref(), Some("inherit"));
+        assert_eq!(round_trip("margin", "var(--gap) 0").as_deref(), Some("var(--gap) 0"));
+
+        // Sides with different values cannot be written as `border`
+        let values = |longhand: &str| {
+            Some(match longhand {
+                "border-top-width" => CssValue::Unit(3.0, "px".to_string()),
+                _ if longhand.ends_with("width") => CssValue::Unit(1.0, "px".to_string()),
+                _ if longhand.ends_with("style") => CssValue::Keyword("solid".to_string()),
+                _ => CssValue::Keyword("currentcolor".to_string()),
+            })
+        };
+        assert_eq!(serialize_shorthand("border", values), None);
+        // Missing longhands cannot be serialized
+        assert_eq!(serialize_shorthand("margin", |_| None), None);
+    }
+}
@@ -1,10 +1,670 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    ops::Add,
+};
+
+use crate::{
+    css3::{media::MediaQueryList, parse_value, syntax::CssSyntax},
+    interface::css3::CssOrigin,
+    shared::{byte_stream::Location, node::NodeId},
+};
 
 /// Defines a complete stylesheet with all its rules and the location where it was found
 #[derive(Debug, PartialEq)]
 pub struct CssStylesheet {
+    /// List of rules found in this stylesheet
+    pub rules: Vec<CssRule>,
+    /// Origin of the stylesheet (user agent, author, user)
+    pub origin: CssOrigin,
+    /// Url or file path where the stylesheet was found
+    pub url: String,
+    /// Location of the stylesheet in the source
+    pub location: Location,
+    /// Element whose `style` attribute holds the declarations of this stylesheet. Such a stylesheet
+    /// only applies to that element.
+    pub element: Option<NodeId>,
+    /// Names of all cascade layers in this stylesheet, in order of first appearance
+    pub layers: Vec<String>,
+    /// Custom properties registered with `@property`
+    pub property_rules: Vec<CssPropertyRule>,
+    /// Stylesheets imported with `@import`, which still have to be loaded
+    pub imports: Vec<CssImport>,
+    /// Any errors or warnings found while parsing the stylesheet
+    pub parse_log: Vec<CssLog>,
+}
 
+impl CssStylesheet {
+    pub fn new(origin: CssOrigin, url: &str, location: Location) -> Self {
+        Self {
+            rules: Vec::new(),
+            origin,
+            url: url.to_string(),
+            location,
+            element: None,
+            layers: Vec::new(),
+            property_rules: Vec::new(),
+            imports: Vec::new(),
+            parse_log: Vec::new(),
+        }
+    }
+
+    /// Registers a cascade layer name (if not already known)
+    pub fn add_layer(&mut self, name: &str) {
+        if !self.layers.iter().any(|layer| layer == name) {
+            self.layers.push(name.to_string());
+        }
+    }
 }
 
 impl crate::interface::css3::CssStylesheet for CssStylesheet {
+    fn origin(&self) -> CssOrigin {
+        self.origin
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn location(&self) -> Location {
+        self.location
+    }
+
+    fn imports(&self) -> Vec<&str> {
+        self.imports.iter().map(|import| import.url.as_str()).collect()
+    }
+
+    fn resolve_imports(&mut self, imported: Vec<Option<Self>>) {
+        let imports = std::mem::take(&mut self.imports);
+        let mut rules = Vec::new();
+        let mut property_rules = Vec::new();
+
+        // Layers of later imports are inserted first, so the positions of earlier imports stay valid
+        for (import, sheet) in imports.iter().zip(imported).rev() {
+            let Some(sheet) = sheet else {
+                continue;
+            };
+
+            let mut sheet_rules = sheet.rules;
+            for rule in &mut sheet_rules {
+                rule.layer = match (&import.layer, rule.layer.take()) {
+                    (Some(outer), Some(inner)) => Some(format!("{outer}.{inner}")),
+                    (outer, inner) => inner.or(outer.clone()),
+                };
+                if !import.media.0.is_empty() {
+                    rule.media.insert(0, import.media.clone());
+                }
+            }
+            rules.insert(0, sheet_rules);
+            property_rules.insert(0, sheet.property_rules);
+
+            let layers = sheet.layers.into_iter().map(|layer| match &import.layer {
+                Some(outer) => format!("{outer}.{layer}"),
+                None => layer,
+            });
+            self.insert_layers(import.layer_position, layers);
+            self.parse_log.extend(sheet.parse_log);
+        }
+
+        // Imported rules come before the rules of the importing stylesheet
+        rules.push(std::mem::take(&mut self.rules));
+        self.rules = rules.into_iter().flatten().collect();
+        property_rules.push(std::mem::take(&mut self.property_rules));
+        self.property_rules = property_rules.into_iter().flatten().collect();
+    }
+
+    fn restrict_media(&mut self, media: &str) {
+        let media = MediaQueryList::parse(&parse_value(media).unwrap_or_default());
+        if media.0.is_empty() {
+            return;
+        }
+
+        for rule in &mut self.rules {
+            rule.media.insert(0, media.clone());
+        }
+    }
+}
+
+impl CssStylesheet {
+    /// Inserts layer names at the given position. Names that already appear before that position
+    /// keep their place, as layers are ordered by their first appearance.
+    fn insert_layers(&mut self, position: usize, names: impl Iterator<Item = String>) {
+        let mut position = position;
+
+        for name in names {
+            match self.layers.iter().position(|layer| *layer == name) {
+                Some(existing) if existing < position => continue,
+                Some(existing) => {
+                    self.layers.remove(existing);
+                }
+                None => {}
+            }
+
+            self.layers.insert(position, name);
+            position += 1;
+        }
+    }
+}
+
+/// An `@import` rule (CSS Cascade 5, 2)
+#[derive(Debug, PartialEq, Clone)]
+pub struct CssImport {
+    /// Url of the imported stylesheet, as written
+    pub url: String,
+    /// Full name of the cascade layer the imported rules are placed in
+    pub layer: Option<String>,
+    /// Number of layer names in the importing stylesheet when the rule was found. The layers of
+    /// the imported stylesheet are inserted at this position.
+    pub layer_position: usize,
+    /// Media queries that must match for the imported rules to apply
+    pub media: MediaQueryList,
+    pub location: Location,
+}
+
+/// Severity of a parse log entry
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CssLogLevel {
+    Warning,
+    Error,
+}
+
+/// A message generated during parsing of a stylesheet
+#[derive(Debug, PartialEq, Clone)]
+pub struct CssLog {
+    pub level: CssLogLevel,
+    pub message: String,
+    pub location: Location,
+}
+
+impl CssLog {
+    pub fn warning(message: &str, location: Location) -> Self {
+        Self {
+            level: CssLogLevel::Warning,
+            message: message.to_string(),
+            location,
+        }
+    }
+
+    pub fn error(message: &str, location: Location) -> Self {
+        Self {
+            level: CssLogLevel::Error,
+            message: message.to_string(),
+            location,
+        }
+    }
+}
+
+impl Display for CssLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}] {} at {}", self.level, self.message, self.location)
+    }
+}
+
+/// A style rule: a list of selectors and the declarations that apply when any of them match
+#[derive(Debug, PartialEq, Clone)]
+pub struct CssRule {
+    /// Selectors (comma separated) that must match for the declarations to apply
+    pub selectors: Vec<CssSelector>,
+    /// Declarations of this rule
+    pub declarations: Vec<CssDeclaration>,
+    /// Full (dotted) name of the cascade layer this rule belongs to, or None when unlayered
+    pub layer: Option<String>,
+    /// Media query lists of all enclosing `@media` rules, which must all match for the rule to apply
+    pub media: Vec<MediaQueryList>,
+    /// Location of the rule in the source
+    pub location: Location,
+}
+
+impl CssRule {
+    pub fn selectors(&self) -> &[CssSelector] {
+        &self.selectors
+    }
+
+    pub fn declarations(&self) -> &[CssDeclaration] {
+        &self.declarations
+    }
+}
+
+/// A custom property registration (`@property --name { ... }`, CSS Properties and Values API 1, 3)
+#[derive(Debug, PartialEq, Clone)]
+pub struct CssPropertyRule {
+    /// Name of the custom property, including the leading dashes
+    pub name: String,
+    /// Syntax the values of the property must match, or None for the universal syntax `*`
+    pub syntax: Option<CssSyntax>,
+    /// True when the property is inherited
+    pub inherits: bool,
+    /// Initial value, which is only optional for the universal syntax
+    pub initial_value: Option<CssValue>,
+    /// Location of the rule in the source
+    pub location: Location,
+}
+
+/// A single declaration (`property: value !important`)
+#[derive(Debug, PartialEq, Clone)]
+pub struct CssDeclaration {
+    /// Name of the property (lowercased, except for custom properties)
+    pub property: String,
+    /// List of values of the declaration
+    pub value: Vec<CssValue>,
+    /// True when the declaration is marked with !important
+    pub important: bool,
+    /// Location of the declaration in the source
+    pub location: Location,
+}
+
+/// Complex selector, consisting of compound selectors separated by combinators
+#[derive(Debug, PartialEq, Clone)]
+pub struct CssSelector {
+    /// Parts of the selector in source order. Compound selectors are separated by
+    /// `CssSelectorPart::Combinator` parts.
+    pub parts: Vec<CssSelectorPart>,
+}
+
+impl CssSelector {
+    /// Returns the specificity of the selector
+    pub fn specificity(&self) -> Specificity {
+        let mut specificity = Specificity::default();
+
+        for part in &self.parts {
+            specificity = specificity + part.specificity();
+        }
+
+        specificity
+    }
+
+    /// Returns the pseudo element this selector targets (if any)
+    pub fn pseudo_element(&self) -> Option<&str> {
+        self.parts.iter().find_map(|part| match part {
+            CssSelectorPart::PseudoElement(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+impl Display for CssSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            write!(f, "{part}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Combinator {
+    /// `a b`
+    Descendant,
+    /// `a > b`
+    Child,
+    /// `a + b`
+    NextSibling,
+    /// `a ~ b`
+    SubsequentSibling,
+}
 
-}
\ No newline at end of file
+impl Display for Combinator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Combinator::Descendant => write!(f, " "),
+            Combinator::Child => write!(f, " > "),
+            Combinator::NextSibling => write!(f, " + "),
+            Combinator::SubsequentSibling => write!(f, " ~ "),
+        }
+    }
+}
+
+/// How the value of an attribute selector must be matched
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AttributeMatcher {
+    /// `[attr]`
+    Exists,
+    /// `[attr=value]`
+    Equals,
+    /// `[attr~=value]`
+    Includes,
+    /// `[attr|=value]`
+    DashMatch,
+    /// `[attr^=value]`
+    PrefixMatch,
+    /// `[attr$=value]`
+    SuffixMatch,
+    /// `[attr*=value]`
+    SubstringMatch,
+}
+
+impl Display for AttributeMatcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeMatcher::Exists => Ok(()),
+            AttributeMatcher::Equals => write!(f, "="),
+            AttributeMatcher::Includes => write!(f, "~="),
+            AttributeMatcher::DashMatch => write!(f, "|="),
+            AttributeMatcher::PrefixMatch => write!(f, "^="),
+            AttributeMatcher::SuffixMatch => write!(f, "$="),
+            AttributeMatcher::SubstringMatch => write!(f, "*="),
+        }
+    }
+}
+
+/// Namespace of the attribute in an attribute selector
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum AttributeNamespace {
+    /// `[attr]` or `[|attr]`: attributes without a namespace
+    #[default]
+    None,
+    /// `[*|attr]`: attributes in any namespace
+    Any,
+    /// `[ns|attr]`: attributes in the namespace with the given url
+    Url(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AttributeSelector {
+    pub name: String,
+    pub namespace: AttributeNamespace,
+    pub matcher: AttributeMatcher,
+    pub value: String,
+    /// True when the value must be matched case-insensitive (`[attr=value i]`)
+    pub case_insensitive: bool,
+}
+
+/// The An+B notation as used in :nth-child() and friends
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Nth {
+    pub a: i32,
+    pub b: i32,
+}
+
+impl Nth {
+    /// Returns true when the given (1-based) index matches An+B for some n >= 0
+    pub fn matches(&self, index: i32) -> bool {
+        if self.a == 0 {
+            return index == self.b;
+        }
+
+        let diff = index - self.b;
+        diff % self.a == 0 && diff / self.a >= 0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PseudoClass {
+    /// Non-functional pseudo class like `:root` or `:hover`
+    Named(String),
+    /// `:not(<selector-list>)`
+    Not(Vec<CssSelector>),
+    /// `:is(<selector-list>)` (and the legacy `:matches()` / `:-webkit-any()`)
+    Is(Vec<CssSelector>),
+    /// `:where(<selector-list>)`
+    Where(Vec<CssSelector>),
+    /// `:has(<relative-selector-list>)`
+    Has(Vec<CssSelector>),
+    /// `:nth-child(An+B [of S])`
+    NthChild(Nth, Vec<CssSelector>),
+    /// `:nth-last-child(An+B [of S])`
+    NthLastChild(Nth, Vec<CssSelector>),
+    /// `:nth-of-type(An+B)`
+    NthOfType(Nth),
+    /// `:nth-last-of-type(An+B)`
+    NthLastOfType(Nth),
+    /// `:lang(<ident>#)`
+    Lang(Vec<String>),
+    /// `:dir(ltr|rtl)`
+    Dir(String),
+    /// Any other functional pseudo class, with its raw arguments
+    Function(String, Vec<CssValue>),
+}
+
+fn max_specificity(selectors: &[CssSelector]) -> Specificity {
+    selectors.iter().map(|s| s.specificity()).max().unwrap_or_default()
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CssSelectorPart {
+    /// `*`
+    Universal,
+    /// Type (element name) selector, like `div`
+    Type(String),
+    /// The element must be in the namespace with the given url (empty for no namespace). Added
+    /// for `ns|div`, `|div` and for selectors without prefix when a default namespace is declared.
+    Namespace(String),
+    /// `.class`
+    Class(String),
+    /// `#id`
+    Id(String),
+    /// `[attr=value]`
+    Attribute(Box<AttributeSelector>),
+    /// `:pseudo-class`
+    PseudoClass(PseudoClass),
+    /// `::pseudo-element`
+    PseudoElement(String),
+    /// Combinator between two compound selectors
+    Combinator(Combinator),
+}
+
+impl CssSelectorPart {
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            CssSelectorPart::Id(_) => Specificity::new(1, 0, 0),
+            CssSelectorPart::Class(_) | CssSelectorPart::Attribute(_) => Specificity::new(0, 1, 0),
+            CssSelectorPart::Type(_) | CssSelectorPart::PseudoElement(_) => Specificity::new(0, 0, 1),
+            CssSelectorPart::Universal | CssSelectorPart::Namespace(_) | CssSelectorPart::Combinator(_) => {
+                Specificity::default()
+            }
+            CssSelectorPart::PseudoClass(pseudo) => match pseudo {
+                PseudoClass::Where(_) => Specificity::default(),
+                PseudoClass::Not(list) | PseudoClass::Is(list) | PseudoClass::Has(list) => max_specificity(list),
+                PseudoClass::NthChild(_, list) | PseudoClass::NthLastChild(_, list) => {
+                    Specificity::new(0, 1, 0) + max_specificity(list)
+                }
+                _ => Specificity::new(0, 1, 0),
+            },
+        }
+    }
+}
+
+impl Display for CssSelectorPart {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CssSelectorPart::Universal => write!(f, "*"),
+            CssSelectorPart::Type(name) => write!(f, "{name}"),
+            // Prefixes are resolved while parsing, so the namespace can not be written back
+            CssSelectorPart::Namespace(_) => Ok(()),
+            CssSelectorPart::Class(name) => write!(f, ".{name}"),
+            CssSelectorPart::Id(name) => write!(f, "#{name}"),
+            CssSelectorPart::Attribute(attr) => {
+                write!(f, "[")?;
+                if attr.namespace == AttributeNamespace::Any {
+                    write!(f, "*|")?;
+                }
+                write!(f, "{}", attr.name)?;
+                if attr.matcher != AttributeMatcher::Exists {
+                    write!(f, "{}\"{}\"", attr.matcher, attr.value)?;
+                }
+                if attr.case_insensitive {
+                    write!(f, " i")?;
+                }
+                write!(f, "]")
+            }
+            CssSelectorPart::PseudoClass(pseudo) => match pseudo {
+                PseudoClass::Named(name) => write!(f, ":{name}"),
+                PseudoClass::Not(list) => write!(f, ":not({})", SelectorList(list)),
+                PseudoClass::Is(list) => write!(f, ":is({})", SelectorList(list)),
+                PseudoClass::Where(list) => write!(f, ":where({})", SelectorList(list)),
+                PseudoClass::Has(list) => write!(f, ":has({})", SelectorList(list)),
+                PseudoClass::NthChild(nth, _) => write!(f, ":nth-child({}n+{})", nth.a, nth.b),
+                PseudoClass::NthLastChild(nth, _) => write!(f, ":nth-last-child({}n+{})", nth.a, nth.b),
+                PseudoClass::NthOfType(nth) => write!(f, ":nth-of-type({}n+{})", nth.a, nth.b),
+                PseudoClass::NthLastOfType(nth) => write!(f, ":nth-last-of-type({}n+{})", nth.a, nth.b),
+                PseudoClass::Lang(langs) => write!(f, ":lang({})", langs.join(", ")),
+                PseudoClass::Dir(dir) => write!(f, ":dir({dir})"),
+                PseudoClass::Function(name, _) => write!(f, ":{name}(...)"),
+            },
+            CssSelectorPart::PseudoElement(name) => write!(f, "::{name}"),
+            CssSelectorPart::Combinator(combinator) => write!(f, "{combinator}"),
+        }
+    }
+}
+
+struct SelectorList<'a>(&'a [CssSelector]);
+
+impl Display for SelectorList<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, selector) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{selector}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Specificity of a selector: (ids, classes/attributes/pseudo-classes, types/pseudo-elements)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub struct Specificity(u32, u32, u32);
+
+impl Specificity {
+    pub fn new(a: u32, b: u32, c: u32) -> Self {
+        Self(a, b, c)
+    }
+}
+
+impl Add for Specificity {
+    type Output = Specificity;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Specificity(self.0 + rhs.0, self.1 + rhs.1, self.2 + rhs.2)
+    }
+}
+
+impl PartialOrd for Specificity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Specificity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .cmp(&other.0)
+            .then(self.1.cmp(&other.1))
+            .then(self.2.cmp(&other.2))
+    }
+}
+
+/// Actual value of a declaration (or a single component of it)
+#[derive(Debug, PartialEq, Clone)]
+pub enum CssValue {
+    /// No value
+    None,
+    /// The `initial` css-wide keyword
+    Initial,
+    /// The `inherit` css-wide keyword
+    Inherit,
+    /// The `unset` css-wide keyword
+    Unset,
+    /// The `revert` css-wide keyword
+    Revert,
+    /// The `revert-layer` css-wide keyword
+    RevertLayer,
+    /// A comma separating values
+    Comma,
+    /// A single delimiter, like the `/` in `font: 12px/1.5 serif`
+    Delim(char),
+    /// A plain number
+    Number(f32),
+    /// A percentage
+    Percentage(f32),
+    /// A number with a unit (lowercased), like `12px`
+    Unit(f32, String),
+    /// An identifier, like `block`
+    Keyword(String),
+    /// A quoted string
+    String(String),
+    /// A hash value, like `#fff`
+    Hash(String),
+    /// An url, either from `url(foo)` or `url("foo")`
+    Url(String),
+    /// A function with its arguments (commas are included as `CssValue::Comma`). Simple blocks
+    /// like `(...)` and `[...]` are stored as functions named `(` and `[`.
+    Function(String, Vec<CssValue>),
+    /// A (space separated) list of values
+    List(Vec<CssValue>),
+    /// The value of a longhand that is set by a shorthand containing `var()`: the name and value of
+    /// the shorthand. The longhand value is found by expanding the shorthand after substitution.
+    PendingSubstitution(String, Vec<CssValue>),
+}
+
+impl CssValue {
+    /// Converts a list of values into a single value. Single item lists are unwrapped.
+    pub fn from_list(mut values: Vec<CssValue>) -> CssValue {
+        match values.len() {
+            0 => CssValue::None,
+            1 => values.remove(0),
+            _ => CssValue::List(values),
+        }
+    }
+
+    /// Returns true when this value is one of the css-wide keywords
+    pub fn is_css_wide_keyword(&self) -> bool {
+        matches!(
+            self,
+            CssValue::Initial | CssValue::Inherit | CssValue::Unset | CssValue::Revert | CssValue::RevertLayer
+        )
+    }
+
+    /// Returns true when the value is the given keyword (case-insensitive)
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, CssValue::Keyword(k) if k.eq_ignore_ascii_case(keyword))
+    }
+
+    pub fn as_keyword(&self) -> Option<&str> {
+        match self {
+            CssValue::Keyword(k) => Some(k.as_str()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for CssValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CssValue::None => Ok(()),
+            CssValue::Initial => write!(f, "initial"),
+            CssValue::Inherit => write!(f, "inherit"),
+            CssValue::Unset => write!(f, "unset"),
+            CssValue::Revert => write!(f, "revert"),
+            CssValue::RevertLayer => write!(f, "revert-layer"),
+            CssValue::Comma => write!(f, ","),
+            CssValue::Delim(c) => write!(f, "{c}"),
+            CssValue::Number(n) => write!(f, "{n}"),
+            CssValue::Percentage(p) => write!(f, "{p}%"),
+            CssValue::Unit(v, unit) => write!(f, "{v}{unit}"),
+            CssValue::Keyword(k) => write!(f, "{k}"),
+            CssValue::String(s) => write!(f, "\"{s}\""),
+            CssValue::Hash(h) => write!(f, "#{h}"),
+            CssValue::Url(url) => write!(f, "url(\"{url}\")"),
+            CssValue::Function(name, args) => {
+                write!(f, "{name}(")?;
+                write_value_list(f, args)?;
+                write!(f, ")")
+            }
+            CssValue::List(values) => write_value_list(f, values),
+            // Longhands do not have a value of their own until the shorthand is substituted
+            CssValue::PendingSubstitution(..) => Ok(()),
+        }
+    }
+}
+
+/// Writes a list of values separated by spaces (but without a space in front of commas)
+fn write_value_list(f: &mut Formatter<'_>, values: &[CssValue]) -> fmt::Result {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 && *value != CssValue::Comma {
+            write!(f, " ")?;
+        }
+        write!(f, "{value}")?;
+    }
+    Ok(())
+}
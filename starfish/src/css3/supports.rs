@@ -0,0 +1,235 @@
+use crate::{
+    css3::{
+        tokenizer::{Token, TokenType},
+        Css3,
+    },
+    interface::{css3::CssOrigin, Context, ParserConfig},
+};
+
+/// Evaluates a `<supports-condition>` (CSS Conditional 3, 6), like the prelude of an `@supports`
+/// rule. A declaration is supported when the property is known and its value matches the grammar
+/// of the property. Returns None when the condition is not valid.
+pub fn evaluate_supports(tokens: &[Token]) -> Option<bool> {
+    let mut parser = SupportsParser::new(tokens);
+    let result = parser.condition()?;
+    parser.at_end().then_some(result)
+}
+
+/// Evaluates the arguments of `supports()` in an `@import` rule, which can be a condition or a
+/// single declaration
+pub fn evaluate_import_supports(tokens: &[Token]) -> bool {
+    evaluate_supports(tokens).unwrap_or_else(|| supports_declaration(tokens))
+}
+
+struct SupportsParser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> SupportsParser<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .tokens
+            .get(self.pos)
+            .is_some_and(|t| t.is_whitespace() || t.is_comment())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.pos >= self.tokens.len()
+    }
+
+    /// Consumes the given keyword (case-insensitive), if it is the next token
+    fn keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        match self.tokens.get(self.pos).map(|t| &t.token_type) {
+            Some(TokenType::Ident(ident)) if ident.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// `not <supports-in-parens> | <supports-in-parens> [ and <supports-in-parens> ]* |
+    /// <supports-in-parens> [ or <supports-in-parens> ]*`
+    fn condition(&mut self) -> Option<bool> {
+        if self.keyword("not") {
+            return self.in_parens().map(|result| !result);
+        }
+
+        let mut result = self.in_parens()?;
+        let operator = if self.keyword("and") {
+            "and"
+        } else if self.keyword("or") {
+            "or"
+        } else {
+            return Some(result);
+        };
+
+        loop {
+            let next = self.in_parens()?;
+            result = if operator == "and" {
+                result && next
+            } else {
+                result || next
+            };
+
+            if !self.keyword(operator) {
+                return Some(result);
+            }
+        }
+    }
+
+    /// `( <supports-condition> ) | ( <declaration> ) | selector( ... ) | <general-enclosed>`
+    fn in_parens(&mut self) -> Option<bool> {
+        self.skip_whitespace();
+        let token = self.tokens.get(self.pos)?;
+        let end = self.block_end(self.pos)?;
+        let inner = &self.tokens[self.pos + 1..end];
+        self.pos = end + 1;
+
+        match &token.token_type {
+            TokenType::LParen => {
+                let mut nested = SupportsParser::new(inner);
+                match nested.condition() {
+                    Some(result) if nested.at_end() => Some(result),
+                    _ => Some(supports_declaration(inner)),
+                }
+            }
+            TokenType::Function(name) if name.eq_ignore_ascii_case("selector") => Some(supports_selector(inner)),
+            // Unknown functions (including `font-tech()` and `font-format()`) are not supported
+            TokenType::Function(_) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns the position of the token that closes the block or function at `start`
+    fn block_end(&self, start: usize) -> Option<usize> {
+        let mut depth = 0_usize;
+
+        for (pos, token) in self.tokens.iter().enumerate().skip(start) {
+            match token.token_type {
+                TokenType::LParen | TokenType::LBracket | TokenType::LCurly | TokenType::Function(_) => depth += 1,
+                TokenType::RParen | TokenType::RBracket | TokenType::RCurly => {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return Some(pos);
+                    }
+                }
+                _ if depth == 0 => return None,
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
+
+fn to_css(tokens: &[Token]) -> String {
+    tokens.iter().map(|t| t.to_string()).collect()
+}
+
+/// A declaration is supported when it parses without errors, including the grammar of its value.
+/// Shorthands are expanded into several declarations, so any number of them is fine, but only a
+/// single declaration can be tested.
+fn supports_declaration(tokens: &[Token]) -> bool {
+    if tokens.iter().any(|t| t.token_type == TokenType::Semicolon) {
+        return false;
+    }
+
+    let config = ParserConfig {
+        context: Context::Declaration,
+        match_values: true,
+        ..Default::default()
+    };
+
+    Css3::parse_str(&to_css(tokens), config, CssOrigin::Author, "")
+        .is_ok_and(|sheet| sheet.rules.iter().any(|rule| !rule.declarations.is_empty()))
+}
+
+fn supports_selector(tokens: &[Token]) -> bool {
+    let css = format!("{} {{}}", to_css(tokens));
+
+    Css3::parse_str(&css, ParserConfig::default(), CssOrigin::Author, "").is_ok_and(|sheet| sheet.rules.len() == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supports(condition: &str) -> bool {
+        let css = format!("@supports {condition} {{ .a {{}} }}");
+        let config = ParserConfig {
+            ignore_errors: true,
+            ..Default::default()
+        };
+        let sheet = Css3::parse_str(&css, config, CssOrigin::Author, "test.css").unwrap();
+        sheet.rules.len() == 1
+    }
+
+    #[test]
+    fn declarations() {
+        assert!(supports("(display: grid)"));
+        assert!(supports("(color: rgb(0 0 0 / 50%))"));
+        assert!(supports("(--anything: 12 things)"));
+        assert!(supports("(width: calc(100% - 2rem))"));
+
+        assert!(!supports("(display: blokc)"));
+        assert!(!supports("(color: 12px)"));
+        assert!(!supports("(colour: red)"));
+        assert!(!supports("(margin)"));
+        assert!(!supports("(color: red; margin: 0)"));
+    }
+
+    #[test]
+    fn shorthands() {
+        assert!(supports("(margin: 0)"));
+        assert!(supports("(border: 1px solid)"));
+        assert!(supports("(font: 12px serif)"));
+        assert!(supports("(flex: 1)"));
+        assert!(supports("(background: url(a.png) no-repeat red)"));
+
+        assert!(!supports("(margin: 1px 2px 3px 4px 5px)"));
+        assert!(!supports("(border: solid solid)"));
+        assert!(!supports("(font: serif)"));
+    }
+
+    #[test]
+    fn conditions() {
+        assert!(supports("not (display: blokc)"));
+        assert!(supports("(display: grid) and (margin: 0)"));
+        assert!(!supports("(display: grid) and (display: blokc)"));
+        assert!(supports("(display: blokc) or (display: grid)"));
+        assert!(supports("((display: grid) and (not (color: 12px)))"));
+        assert!(supports("selector(a > b)"));
+        assert!(!supports("selector(a >> b)"));
+        assert!(!supports("font-tech(color-COLRv1)"));
+
+        // Mixing `and` and `or` without parentheses is invalid, so the rule is dropped
+        assert!(!supports("(margin: 0) and (color: red) or (display: grid)"));
+        assert!(!supports("display: grid"));
+
+        let imports = |css: &str| {
+            let sheet = Css3::parse_str(
+                &format!("@import url(a.css) supports({css});"),
+                ParserConfig::default(),
+                CssOrigin::Author,
+                "test.css",
+            )
+            .unwrap();
+            sheet.imports.len()
+        };
+        assert_eq!(imports("display: grid"), 1);
+        assert_eq!(imports("margin: 0"), 1);
+        assert_eq!(imports("display: blokc"), 0);
+        assert_eq!(imports("(display: grid) and (margin: 0)"), 1);
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{css3::stylesheet::CssValue, shared::errors::CssResult};
+
+mod matcher;
+mod parser;
+
+/// A compiled CSS value definition syntax (CSS Values 4, 2), like `<length> | auto`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssSyntax {
+    /// The syntax as written in the definition
+    pub source: String,
+    /// The root component of the syntax
+    pub root: SyntaxComponent,
+}
+
+impl CssSyntax {
+    /// Compiles the given value definition syntax
+    pub fn new(source: &str) -> CssResult<Self> {
+        Ok(Self {
+            source: source.to_string(),
+            root: parser::parse_syntax(source)?,
+        })
+    }
+
+    /// Returns true when the (complete) list of values matches the syntax
+    pub fn matches(&self, values: &[CssValue]) -> bool {
+        matcher::match_component(&self.root, values, 0).contains(&values.len())
+    }
+
+    /// Matches the syntax against the values starting at `pos`, and returns all positions where
+    /// the match could end
+    pub fn match_at(&self, values: &[CssValue], pos: usize) -> Vec<usize> {
+        matcher::match_component(&self.root, values, pos)
+    }
+}
+
+impl Display for CssSyntax {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// How the components of a group are combined (CSS Values 4, 2.2)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupCombinator {
+    /// `a b`: all components must occur, in the given order
+    Juxtaposition,
+    /// `a && b`: all components must occur, in any order
+    AllAnyOrder,
+    /// `a || b`: one or more of the components must occur, in any order
+    AtLeastOneAnyOrder,
+    /// `a | b`: exactly one of the components must occur
+    ExactlyOne,
+}
+
+/// How often a component may be repeated (CSS Values 4, 2.3)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Multiplier {
+    /// `?`: zero or one time
+    Optional,
+    /// `*`: zero or more times
+    ZeroOrMore,
+    /// `+`: one or more times
+    OneOrMore,
+    /// `{A}`, `{A,}` and `{A,B}`: between A and B times (no upper bound when B is None)
+    Between(usize, Option<usize>),
+    /// `#` and `#{A,B}`: a comma separated list of between A and B items
+    CommaSeparated(usize, Option<usize>),
+    /// `!`: the group must produce at least one value
+    Required,
+}
+
+/// A single component of a value definition syntax
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxComponent {
+    /// A keyword that must appear literally (case-insensitive), like `auto`
+    Keyword(String),
+    /// A literal character like `/` or `,`
+    Literal(char),
+    /// A data type like `<length>`, optionally with a range restriction like `<length [0,∞]>`
+    DataType { name: String, range: Option<(f32, f32)> },
+    /// The syntax of another property, like `<'margin-top'>`
+    Property(String),
+    /// A function with the syntax of its arguments, like `fit-content( <length-percentage> )`
+    Function {
+        name: String,
+        arguments: Box<SyntaxComponent>,
+    },
+    /// A group of components combined with a combinator
+    Group {
+        combinator: GroupCombinator,
+        components: Vec<SyntaxComponent>,
+    },
+    /// A component with a multiplier
+    Multiplied {
+        component: Box<SyntaxComponent>,
+        multiplier: Multiplier,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css3::parse_value;
+
+    fn matches(syntax: &str, value: &str) -> bool {
+        let syntax = CssSyntax::new(syntax).unwrap();
+        syntax.matches(&parse_value(value).unwrap())
+    }
+
+    #[test]
+    fn combinators() {
+        assert!(matches("a b", "a b"));
+        assert!(!matches("a b", "b a"));
+        assert!(matches("a && b", "b a"));
+        assert!(!matches("a && b", "a"));
+        assert!(matches("a || b", "b"));
+        assert!(matches("a || b", "b a"));
+        assert!(!matches("a || b", "a a"));
+        assert!(matches("a | b", "b"));
+        assert!(!matches("a | b", "a b"));
+        // Juxtaposition binds tighter than `&&`, which binds tighter than `||` and `|`
+        assert!(matches("a b | c", "c"));
+        assert!(matches("a b | c", "a b"));
+        assert!(matches("[ a | b ] c", "b c"));
+    }
+
+    #[test]
+    fn multipliers() {
+        assert!(matches("a?", ""));
+        assert!(!matches("a?", "a a"));
+        assert!(matches("a*", "a a a"));
+        assert!(!matches("a+", ""));
+        assert!(matches("a+", "a a"));
+        assert!(matches("a{2,3}", "a a a"));
+        assert!(!matches("a{2,3}", "a"));
+        assert!(!matches("a{2,3}", "a a a a"));
+        assert!(matches("a{2,}", "a a a a"));
+        assert!(matches("a#", "a, a, a"));
+        assert!(!matches("a#", "a a"));
+        assert!(!matches("a#{1,2}", "a, a, a"));
+        // `!` requires at least one value from a group of optional components
+        assert!(matches("[ a? b? ]!", "b"));
+        assert!(!matches("[ a? b? ]!", ""));
+    }
+
+    #[test]
+    fn data_types() {
+        assert!(matches("<length>", "12px"));
+        assert!(matches("<length>", "0"));
+        assert!(!matches("<length>", "12"));
+        assert!(!matches("<length>", "50%"));
+        assert!(matches("<length-percentage>", "50%"));
+        assert!(matches("<length-percentage>", "calc(100% - 2rem)"));
+        assert!(!matches("<length>", "calc(1px + 2s)"));
+        assert!(matches("<length [0,∞]>", "1px"));
+        assert!(!matches("<length [0,∞]>", "-1px"));
+        assert!(matches("<number>", "1.5"));
+        assert!(matches("<integer>", "2"));
+        assert!(!matches("<integer>", "2.5"));
+        assert!(matches("<color>", "red"));
+        assert!(matches("<color>", "#fff"));
+        assert!(matches("<color>", "rgb(1 2 3 / 50%)"));
+        assert!(!matches("<color>", "12px"));
+        assert!(matches("<string>", "\"text\""));
+        assert!(matches("<url>", "url(a.png)"));
+    }
+
+    #[test]
+    fn functions_and_properties() {
+        assert!(matches("fit-content( <length-percentage> )", "fit-content(10px)"));
+        assert!(!matches("fit-content( <length-percentage> )", "fit-content(auto)"));
+        assert!(matches("<'margin-top'>{1,4}", "1px auto 2% 0"));
+        assert!(!matches("<'margin-top'>{1,4}", "1px red"));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::css3::{
+    calc::{is_math_function, BaseType, CalcNode, LENGTH_UNITS},
+    colors::{is_color_keyword, COLOR_FUNCTIONS},
+    matcher::property_definitions::{get_css_definition, get_type_definition},
+    stylesheet::CssValue,
+    syntax::{GroupCombinator, Multiplier, SyntaxComponent},
+};
+
+/// Functions that produce an image
+const IMAGE_FUNCTIONS: &[&str] = &[
+    "linear-gradient",
+    "radial-gradient",
+    "conic-gradient",
+    "repeating-linear-gradient",
+    "repeating-radial-gradient",
+    "repeating-conic-gradient",
+    "image",
+    "image-set",
+    "cross-fade",
+    "element",
+    "paint",
+    "light-dark",
+];
+
+/// Matches the component against the values starting at `pos`. Returns all positions where a
+/// match could end, so callers can backtrack over the alternatives.
+pub(crate) fn match_component(component: &SyntaxComponent, values: &[CssValue], pos: usize) -> Vec<usize> {
+    match component {
+        SyntaxComponent::Keyword(keyword) => match values.get(pos) {
+            Some(value) if value.is_keyword(keyword) => vec![pos + 1],
+            _ => vec![],
+        },
+        SyntaxComponent::Literal(c) => match values.get(pos) {
+            Some(CssValue::Comma) if *c == ',' => vec![pos + 1],
+            Some(CssValue::Delim(d)) if d == c => vec![pos + 1],
+            _ => vec![],
+        },
+        SyntaxComponent::DataType { name, range } => match_data_type(name, *range, values, pos),
+        SyntaxComponent::Property(name) => match get_css_definition(name).map(|def| &def.syntax) {
+            Some(syntax) => match_component(&syntax.root, values, pos),
+            None => vec![],
+        },
+        SyntaxComponent::Function { name, arguments } => match values.get(pos) {
+            Some(CssValue::Function(fname, args)) if fname.eq_ignore_ascii_case(name) => {
+                if match_component(arguments, args, 0).contains(&args.len()) {
+                    vec![pos + 1]
+                } else {
+                    vec![]
+                }
+            }
+            _ => vec![],
+        },
+        SyntaxComponent::Group { combinator, components } => match combinator {
+            GroupCombinator::Juxtaposition => {
+                let mut positions = vec![pos];
+                for component in components {
+                    positions = dedup(
+                        positions
+                            .iter()
+                            .flat_map(|p| match_component(component, values, *p))
+                            .collect(),
+                    );
+                    if positions.is_empty() {
+                        break;
+                    }
+                }
+                positions
+            }
+            GroupCombinator::ExactlyOne => dedup(
+                components
+                    .iter()
+                    .flat_map(|component| match_component(component, values, pos))
+                    .collect(),
+            ),
+            GroupCombinator::AllAnyOrder | GroupCombinator::AtLeastOneAnyOrder => {
+                let mut result = Vec::new();
+                let all = *combinator == GroupCombinator::AllAnyOrder;
+                match_any_order(components, values, pos, 0, all, &mut result);
+                dedup(result)
+            }
+        },
+        SyntaxComponent::Multiplied { component, multiplier } => match multiplier {
+            Multiplier::Optional => match_repeated(component, values, pos, 0, Some(1), false),
+            Multiplier::ZeroOrMore => match_repeated(component, values, pos, 0, None, false),
+            Multiplier::OneOrMore => match_repeated(component, values, pos, 1, None, false),
+            Multiplier::Between(min, max) => match_repeated(component, values, pos, *min, *max, false),
+            Multiplier::CommaSeparated(min, max) => match_repeated(component, values, pos, *min, *max, true),
+            Multiplier::Required => match_component(component, values, pos)
+                .into_iter()
+                .filter(|end| *end > pos)
+                .collect(),
+        },
+    }
+}
+
+fn dedup(mut positions: Vec<usize>) -> Vec<usize> {
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
+/// Matches the components in any order. With `all`, every component must match, otherwise at
+/// least one component must match.
+fn match_any_order(
+    components: &[SyntaxComponent],
+    values: &[CssValue],
+    pos: usize,
+    used: u64,
+    all: bool,
+    result: &mut Vec<usize>,
+) {
+    let all_used = (1_u64 << components.len()) - 1;
+    if used == all_used || (!all && used != 0) {
+        result.push(pos);
+    }
+
+    for (i, component) in components.iter().enumerate() {
+        if used & (1 << i) != 0 {
+            continue;
+        }
+        for end in match_component(component, values, pos) {
+            match_any_order(components, values, end, used | (1 << i), all, result);
+        }
+    }
+}
+
+/// Matches a component between `min` and `max` times, optionally separated by commas
+fn match_repeated(
+    component: &SyntaxComponent,
+    values: &[CssValue],
+    pos: usize,
+    min: usize,
+    max: Option<usize>,
+    comma_separated: bool,
+) -> Vec<usize> {
+    let mut result = Vec::new();
+    if min == 0 {
+        result.push(pos);
+    }
+
+    let mut frontier = vec![pos];
+    let mut count = 0;
+    while !frontier.is_empty() && max.is_none_or(|max| count < max) {
+        let mut next = Vec::new();
+        for p in frontier {
+            let start = if comma_separated && count > 0 {
+                match values.get(p) {
+                    Some(CssValue::Comma) => p + 1,
+                    _ => continue,
+                }
+            } else {
+                p
+            };
+
+            // Only repetitions that consume values are useful, this also prevents endless loops
+            next.extend(
+                match_component(component, values, start)
+                    .into_iter()
+                    .filter(|end| *end > p),
+            );
+        }
+
+        frontier = dedup(next);
+        count += 1;
+        if count >= min {
+            result.extend(frontier.iter().copied());
+        }
+    }
+
+    dedup(result)
+}
+
+/// Matches a data type. Basic data types match a single value, other types are defined by their
+/// own syntax.
+fn match_data_type(name: &str, range: Option<(f32, f32)>, values: &[CssValue], pos: usize) -> Vec<usize> {
+    if let Some(syntax) = get_type_definition(name) {
+        return match_component(&syntax.root, values, pos);
+    }
+
+    match values.get(pos) {
+        Some(value) if match_basic_data_type(name, range, value) => vec![pos + 1],
+        _ => vec![],
+    }
+}
+
+fn match_basic_data_type(name: &str, range: Option<(f32, f32)>, value: &CssValue) -> bool {
+    if is_numeric_type(name) && is_math_function(value) {
+        return match_math_function(name, value);
+    }
+
+    let in_range = |n: f32| range.is_none_or(|(min, max)| n >= min && n <= max);
+
+    match name {
+        "length" => match value {
+            CssValue::Unit(n, unit) => LENGTH_UNITS.contains(&unit.as_str()) && in_range(*n),
+            CssValue::Number(n) => *n == 0.0,
+            _ => false,
+        },
+        "percentage" => matches!(value, CssValue::Percentage(n) if in_range(*n)),
+        "length-percentage" => {
+            match_basic_data_type("length", range, value) || match_basic_data_type("percentage", range, value)
+        }
+        "number" => matches!(value, CssValue::Number(n) if in_range(*n)),
+        "integer" => matches!(value, CssValue::Number(n) if n.fract() == 0.0 && in_range(*n)),
+        "angle" => match value {
+            CssValue::Unit(n, unit) => ["deg", "grad", "rad", "turn"].contains(&unit.as_str()) && in_range(*n),
+            CssValue::Number(n) => *n == 0.0,
+            _ => false,
+        },
+        "time" => matches!(value, CssValue::Unit(n, unit) if (unit == "s" || unit == "ms") && in_range(*n)),
+        "resolution" => {
+            matches!(value, CssValue::Unit(n, unit) if ["dpi", "dpcm", "dppx", "x"].contains(&unit.as_str()) && in_range(*n))
+        }
+        "flex" => matches!(value, CssValue::Unit(n, unit) if unit == "fr" && in_range(*n)),
+        "string" => matches!(value, CssValue::String(_)),
+        "url" => matches!(value, CssValue::Url(_)),
+        "image" => match value {
+            CssValue::Url(_) => true,
+            CssValue::Function(fname, _) => IMAGE_FUNCTIONS.contains(&fname.as_str()),
+            _ => false,
+        },
+        "color" => match value {
+            CssValue::Hash(hash) => [3, 4, 6, 8].contains(&hash.len()) && hash.chars().all(|c| c.is_ascii_hexdigit()),
+            CssValue::Keyword(keyword) => is_color_keyword(keyword),
+            CssValue::Function(fname, _) => COLOR_FUNCTIONS.contains(&fname.as_str()),
+            _ => false,
+        },
+        "ident" => matches!(value, CssValue::Keyword(_)),
+        "custom-ident" => matches!(value, CssValue::Keyword(k) if !k.eq_ignore_ascii_case("default")),
+        "dashed-ident" => matches!(value, CssValue::Keyword(k) if k.starts_with("--")),
+        "line-names" => match value {
+            CssValue::Function(fname, args) if fname == "[" => {
+                args.iter().all(|arg| matches!(arg, CssValue::Keyword(_)))
+            }
+            _ => false,
+        },
+        "attr()" => matches!(value, CssValue::Function(fname, _) if fname == "attr"),
+        _ => {
+            tracing::warn!("css: unknown data type <{name}> in property syntax");
+            false
+        }
+    }
+}
+
+fn is_numeric_type(name: &str) -> bool {
+    matches!(
+        name,
+        "length" | "percentage" | "length-percentage" | "number" | "integer" | "angle" | "time" | "resolution" | "flex"
+    )
+}
+
+/// Type-checks a math function against a numeric data type. Ranges are not checked, as values
+/// outside the range are clamped when computing the value (CSS Values 4, 10.12).
+fn match_math_function(name: &str, value: &CssValue) -> bool {
+    let Some(node) = CalcNode::parse(value) else {
+        return false;
+    };
+
+    let percent_as = match name {
+        "length-percentage" => Some(BaseType::Length),
+        _ => None,
+    };
+    let Some(ty) = node.calc_type(percent_as) else {
+        return false;
+    };
+
+    match name {
+        "length" | "length-percentage" => ty.is(BaseType::Length),
+        "percentage" => ty.is(BaseType::Percent),
+        "number" | "integer" => ty.is_number(),
+        "angle" => ty.is(BaseType::Angle),
+        "time" => ty.is(BaseType::Time),
+        "resolution" => ty.is(BaseType::Resolution),
+        "flex" => ty.is(BaseType::Flex),
+        _ => false,
+    }
+}
@@ -0,0 +1,295 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::{
+    css3::syntax::{GroupCombinator, Multiplier, SyntaxComponent},
+    shared::errors::{CssError, CssResult},
+};
+
+/// Parses a value definition syntax into its root component
+pub(crate) fn parse_syntax(source: &str) -> CssResult<SyntaxComponent> {
+    let mut parser = SyntaxParser {
+        chars: source.chars().peekable(),
+        source,
+    };
+
+    let component = parser.parse_alternatives()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.chars.next() {
+        return Err(parser.error(&format!("unexpected '{c}'")));
+    }
+
+    Ok(component)
+}
+
+struct SyntaxParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    source: &'a str,
+}
+
+impl SyntaxParser<'_> {
+    fn error(&self, message: &str) -> CssError {
+        CssError::new(format!("Invalid syntax '{}': {}", self.source, message).as_str())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Consumes the given combinator (`|`, `||` or `&&`) when it is next in the input
+    fn consume_combinator(&mut self, combinator: &str) -> bool {
+        self.skip_whitespace();
+
+        let mut lookahead = self.chars.clone();
+        for expected in combinator.chars() {
+            if lookahead.next() != Some(expected) {
+                return false;
+            }
+        }
+        // `|` must not match the start of `||`
+        if combinator == "|" && lookahead.peek() == Some(&'|') {
+            return false;
+        }
+
+        self.chars = lookahead;
+        true
+    }
+
+    /// Parses components separated by `|`, which has the lowest precedence
+    fn parse_alternatives(&mut self) -> CssResult<SyntaxComponent> {
+        self.parse_combined(GroupCombinator::ExactlyOne, "|", Self::parse_any_order)
+    }
+
+    /// Parses components separated by `||`
+    fn parse_any_order(&mut self) -> CssResult<SyntaxComponent> {
+        self.parse_combined(GroupCombinator::AtLeastOneAnyOrder, "||", Self::parse_all)
+    }
+
+    /// Parses components separated by `&&`
+    fn parse_all(&mut self) -> CssResult<SyntaxComponent> {
+        self.parse_combined(GroupCombinator::AllAnyOrder, "&&", Self::parse_sequence)
+    }
+
+    fn parse_combined(
+        &mut self,
+        combinator: GroupCombinator,
+        separator: &str,
+        parse_next: fn(&mut Self) -> CssResult<SyntaxComponent>,
+    ) -> CssResult<SyntaxComponent> {
+        let mut components = vec![parse_next(self)?];
+        while self.consume_combinator(separator) {
+            components.push(parse_next(self)?);
+        }
+
+        if components.len() == 1 {
+            return Ok(components.remove(0));
+        }
+
+        Ok(SyntaxComponent::Group { combinator, components })
+    }
+
+    /// Parses juxtaposed components, which have the highest precedence
+    fn parse_sequence(&mut self) -> CssResult<SyntaxComponent> {
+        let mut components = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                None | Some('|') | Some('&') | Some(']') | Some(')') => break,
+                _ => components.push(self.parse_multiplied()?),
+            }
+        }
+
+        if components.len() == 1 {
+            return Ok(components.remove(0));
+        }
+
+        Ok(SyntaxComponent::Group {
+            combinator: GroupCombinator::Juxtaposition,
+            components,
+        })
+    }
+
+    /// Parses a single component with its (optional) multipliers
+    fn parse_multiplied(&mut self) -> CssResult<SyntaxComponent> {
+        let mut component = self.parse_component()?;
+
+        loop {
+            let multiplier = match self.chars.peek() {
+                Some('?') => Multiplier::Optional,
+                Some('*') => Multiplier::ZeroOrMore,
+                Some('+') => Multiplier::OneOrMore,
+                Some('!') => Multiplier::Required,
+                Some('{') => {
+                    let (min, max) = self.parse_range_multiplier()?;
+                    component = SyntaxComponent::Multiplied {
+                        component: Box::new(component),
+                        multiplier: Multiplier::Between(min, max),
+                    };
+                    continue;
+                }
+                Some('#') => {
+                    self.chars.next();
+                    let (min, max) = if self.chars.peek() == Some(&'{') {
+                        self.parse_range_multiplier()?
+                    } else {
+                        (1, None)
+                    };
+                    component = SyntaxComponent::Multiplied {
+                        component: Box::new(component),
+                        multiplier: Multiplier::CommaSeparated(min, max),
+                    };
+                    continue;
+                }
+                _ => return Ok(component),
+            };
+
+            self.chars.next();
+            component = SyntaxComponent::Multiplied {
+                component: Box::new(component),
+                multiplier,
+            };
+        }
+    }
+
+    /// Parses `{A}`, `{A,}` or `{A,B}`
+    fn parse_range_multiplier(&mut self) -> CssResult<(usize, Option<usize>)> {
+        self.chars.next();
+
+        let mut contents = String::new();
+        loop {
+            match self.chars.next() {
+                Some('}') => break,
+                Some(c) => contents.push(c),
+                None => return Err(self.error("unterminated multiplier")),
+            }
+        }
+
+        let parse = |s: &str| s.trim().parse::<usize>().map_err(|_| self.error("invalid multiplier"));
+        match contents.split_once(',') {
+            None => {
+                let n = parse(&contents)?;
+                Ok((n, Some(n)))
+            }
+            Some((min, max)) if max.trim().is_empty() => Ok((parse(min)?, None)),
+            Some((min, max)) => Ok((parse(min)?, Some(parse(max)?))),
+        }
+    }
+
+    fn parse_component(&mut self) -> CssResult<SyntaxComponent> {
+        match self.chars.peek().copied() {
+            Some('[') => {
+                self.chars.next();
+                let group = self.parse_alternatives()?;
+                self.skip_whitespace();
+                if self.chars.next() != Some(']') {
+                    return Err(self.error("expected ']'"));
+                }
+                Ok(group)
+            }
+            Some('<') => {
+                self.chars.next();
+                self.parse_data_type()
+            }
+            Some(c @ (',' | '/')) => {
+                self.chars.next();
+                Ok(SyntaxComponent::Literal(c))
+            }
+            Some(c) if is_name_char(c) => {
+                let name = self.consume_name();
+                if self.chars.next_if_eq(&'(').is_none() {
+                    return Ok(SyntaxComponent::Keyword(name));
+                }
+
+                self.skip_whitespace();
+                let arguments = if self.chars.peek() == Some(&')') {
+                    SyntaxComponent::Group {
+                        combinator: GroupCombinator::Juxtaposition,
+                        components: Vec::new(),
+                    }
+                } else {
+                    self.parse_alternatives()?
+                };
+
+                self.skip_whitespace();
+                if self.chars.next() != Some(')') {
+                    return Err(self.error("expected ')'"));
+                }
+
+                Ok(SyntaxComponent::Function {
+                    name: name.to_ascii_lowercase(),
+                    arguments: Box::new(arguments),
+                })
+            }
+            Some(c) => Err(self.error(&format!("unexpected '{c}'"))),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    /// Parses `<name>`, `<name [min,max]>` or `<'property'>`. The `<` is already consumed.
+    fn parse_data_type(&mut self) -> CssResult<SyntaxComponent> {
+        if self.chars.next_if_eq(&'\'').is_some() {
+            let name = self.consume_name();
+            if self.chars.next() != Some('\'') || self.chars.next() != Some('>') {
+                return Err(self.error("unterminated property reference"));
+            }
+            return Ok(SyntaxComponent::Property(name));
+        }
+
+        let mut name = self.consume_name();
+        // Functional notations like `<attr()>` refer to the function as a data type
+        if self.chars.next_if_eq(&'(').is_some() {
+            if self.chars.next() != Some(')') {
+                return Err(self.error("expected ')'"));
+            }
+            name.push_str("()");
+        }
+
+        self.skip_whitespace();
+        let range = if self.chars.next_if_eq(&'[').is_some() {
+            let mut contents = String::new();
+            loop {
+                match self.chars.next() {
+                    Some(']') => break,
+                    Some(c) => contents.push(c),
+                    None => return Err(self.error("unterminated range")),
+                }
+            }
+            self.skip_whitespace();
+
+            let (min, max) = contents.split_once(',').ok_or_else(|| self.error("invalid range"))?;
+            Some((
+                parse_range_bound(min).ok_or_else(|| self.error("invalid range"))?,
+                parse_range_bound(max).ok_or_else(|| self.error("invalid range"))?,
+            ))
+        } else {
+            None
+        };
+
+        if self.chars.next() != Some('>') {
+            return Err(self.error("unterminated data type"));
+        }
+
+        Ok(SyntaxComponent::DataType { name, range })
+    }
+
+    fn consume_name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.chars.next_if(|c| is_name_char(*c)) {
+            name.push(c);
+        }
+        name
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Parses a bound of a range restriction like `0`, `-∞` or `∞`
+fn parse_range_bound(bound: &str) -> Option<f32> {
+    match bound.trim() {
+        "∞" | "+∞" => Some(f32::INFINITY),
+        "-∞" => Some(f32::NEG_INFINITY),
+        bound => bound.parse().ok(),
+    }
+}
@@ -1,19 +1,234 @@
+use std::collections::HashMap;
 
 use crate::{
     css3::{
-        stylesheet::CssStylesheet,
         load_default_useragent_stylesheet,
+        matcher::{
+            cascade::{apply_media, cascade_node, CascadeLayers, MediaConditions, PseudoElementSelectors},
+            computed::{compute_properties, is_viewport_unit},
+            styling::CssProperties,
+            variables::PropertyRegistry,
+        },
+        stylesheet::{CssStylesheet, CssValue},
+        Css3,
     },
-    interface::css3::CssSystem,
+    interface::{
+        config::HasDocument,
+        css3::{CssOrigin, CssSystem, MediaEnvironment, PseudoElement},
+        document::Document,
+        node::{Node, NodeType},
+        Context, ParserConfig,
+    },
+    shared::{errors::CssResult, node::NodeId},
 };
 
 #[derive(Debug, Clone)]
 pub struct Css3System;
 
+/// Cascaded properties of all elements of a document, before media queries are evaluated
+#[derive(Debug, Default)]
+pub struct MatchedStyles {
+    /// All elements and text nodes in tree order, together with their parent element
+    nodes: Vec<(NodeId, Option<NodeId>)>,
+    /// The root element of the document
+    root: Option<NodeId>,
+    properties: HashMap<NodeId, CssProperties>,
+    /// Cascaded properties of the pseudo elements that any rule applies to
+    pseudo_elements: HashMap<(NodeId, PseudoElement), CssProperties>,
+    media: MediaConditions,
+    registry: PropertyRegistry,
+    /// True when any declaration uses viewport units, so the styles depend on the viewport size
+    viewport_units: bool,
+}
+
 impl CssSystem for Css3System {
     type Stylesheet = CssStylesheet;
 
+    type PropertyMap = CssProperties;
+
+    type MatchedStyles = MatchedStyles;
+
     fn load_default_useragent_stylesheet() -> Self::Stylesheet {
         load_default_useragent_stylesheet()
     }
+
+    fn parse_stylesheet(css: &str, origin: CssOrigin, url: &str) -> CssResult<Self::Stylesheet> {
+        let config = ParserConfig {
+            ignore_errors: true,
+            ..Default::default()
+        };
+
+        Css3::parse_str(css, config, origin, url)
+    }
+
+    fn parse_style_attribute(css: &str, url: &str, element: NodeId) -> CssResult<Self::Stylesheet> {
+        let config = ParserConfig {
+            context: Context::Declaration,
+            ignore_errors: true,
+            ..Default::default()
+        };
+
+        let mut sheet = Css3::parse_str(css, config, CssOrigin::Author, url)?;
+        sheet.element = Some(element);
+
+        Ok(sheet)
+    }
+
+    fn match_styles<C: HasDocument<CssSystem = Self>>(doc: &C::Document) -> Self::MatchedStyles {
+        let sheets = doc.stylesheets();
+        let layers = CascadeLayers::new(sheets);
+        let pseudo_selectors = PseudoElementSelectors::new(sheets);
+
+        let mut matched = MatchedStyles {
+            media: MediaConditions::new(sheets),
+            registry: PropertyRegistry::new(sheets),
+            viewport_units: sheets.iter().any(uses_viewport_units),
+            ..Default::default()
+        };
+
+        // Elements are visited in tree order, so the parent element always comes first
+        let mut stack = vec![(doc.get_root().id(), None)];
+        while let Some((node_id, parent_id)) = stack.pop() {
+            let Some(node) = doc.node_by_id(node_id) else {
+                continue;
+            };
+
+            let mut element_parent = parent_id;
+            if node.is_element_node() {
+                let cascaded = cascade_node::<C>(doc, sheets, &layers, &matched.media, node_id, None);
+                for pseudo in PseudoElement::ALL {
+                    // Only list items have a marker, so other elements skip the cascade for it
+                    if pseudo == PseudoElement::Marker && !declares_list_item(&cascaded) {
+                        continue;
+                    }
+                    if !pseudo_selectors.matches::<C>(doc, sheets, node_id, pseudo.name()) {
+                        continue;
+                    }
+
+                    let properties =
+                        cascade_node::<C>(doc, sheets, &layers, &matched.media, node_id, Some(pseudo.name()));
+                    matched.pseudo_elements.insert((node_id, pseudo), properties);
+                }
+                matched.properties.insert(node_id, cascaded);
+                matched.nodes.push((node_id, parent_id));
+                matched.root.get_or_insert(node_id);
+
+                element_parent = Some(node_id);
+            } else if node.type_of() == NodeType::TextNode {
+                // Text has no declarations of its own, it only inherits from its parent element
+                matched.properties.insert(node_id, CssProperties::default());
+                matched.nodes.push((node_id, parent_id));
+            }
+
+            for child_id in node.children().iter().rev() {
+                stack.push((*child_id, element_parent));
+            }
+        }
+
+        matched
+    }
+
+    fn compute_matched_styles(
+        matched: &Self::MatchedStyles,
+        media: &MediaEnvironment,
+    ) -> HashMap<NodeId, Self::PropertyMap> {
+        let active = matched.media.evaluate(media);
+        let mut styles: HashMap<NodeId, CssProperties> = HashMap::new();
+        for (node_id, parent_id) in &matched.nodes {
+            let Some(cascaded) = matched.properties.get(node_id) else {
+                continue;
+            };
+
+            let mut cascaded = cascaded.clone();
+            apply_media(&mut cascaded, &active);
+
+            let parent = parent_id.and_then(|id| styles.get(&id));
+            let root = matched.root.and_then(|id| styles.get(&id));
+
+            let computed = compute_properties(cascaded, parent, root, &matched.registry, media);
+            styles.insert(*node_id, computed);
+        }
+
+        styles
+    }
+
+    fn compute_pseudo_element_styles(
+        matched: &Self::MatchedStyles,
+        styles: &HashMap<NodeId, Self::PropertyMap>,
+        media: &MediaEnvironment,
+    ) -> HashMap<(NodeId, PseudoElement), Self::PropertyMap> {
+        let active = matched.media.evaluate(media);
+        let root = matched.root.and_then(|id| styles.get(&id));
+
+        let mut pseudo_styles = HashMap::new();
+        for ((node_id, pseudo), cascaded) in &matched.pseudo_elements {
+            let Some(parent) = styles.get(node_id) else {
+                continue;
+            };
+
+            let mut cascaded = cascaded.clone();
+            apply_media(&mut cascaded, &active);
+            if cascaded.properties.is_empty() {
+                continue;
+            }
+
+            let computed = compute_properties(cascaded, Some(parent), root, &matched.registry, media);
+            pseudo_styles.insert((*node_id, *pseudo), computed);
+        }
+
+        pseudo_styles
+    }
+
+    fn media_changed(matched: &Self::MatchedStyles, from: &MediaEnvironment, to: &MediaEnvironment) -> bool {
+        let resized = from.width != to.width || from.height != to.height;
+
+        (resized && matched.viewport_units) || matched.media.evaluate(from) != matched.media.evaluate(to)
+    }
+
+    fn anonymous_box_style(
+        matched: &Self::MatchedStyles,
+        parent: &Self::PropertyMap,
+        display: &str,
+    ) -> Self::PropertyMap {
+        // Without declarations, nothing depends on the viewport
+        let media = MediaEnvironment::default();
+        let mut properties =
+            compute_properties(CssProperties::default(), Some(parent), None, &matched.registry, &media);
+        if let Some(property) = properties.properties.get_mut("display") {
+            property.computed = CssValue::Keyword(display.to_string());
+        }
+
+        properties
+    }
+}
+
+/// Returns true when any declaration of `display` makes the element a list item, whether or not it
+/// wins the cascade
+fn declares_list_item(properties: &CssProperties) -> bool {
+    let list_item = CssValue::Keyword("list-item".to_string());
+    properties.properties.get("display").is_some_and(|display| {
+        display.declared.iter().any(|declared| match &declared.value {
+            CssValue::List(values) => values.contains(&list_item),
+            value => *value == list_item,
+        })
+    })
+}
+
+/// Returns true when any declaration of the stylesheet has a length in a viewport unit
+fn uses_viewport_units(sheet: &CssStylesheet) -> bool {
+    sheet
+        .rules
+        .iter()
+        .flat_map(|rule| &rule.declarations)
+        .any(|declaration| declaration.value.iter().any(has_viewport_unit))
+}
+
+fn has_viewport_unit(value: &CssValue) -> bool {
+    match value {
+        CssValue::Unit(_, unit) => is_viewport_unit(unit),
+        CssValue::Function(_, values) | CssValue::List(values) | CssValue::PendingSubstitution(_, values) => {
+            values.iter().any(has_viewport_unit)
+        }
+        _ => false,
+    }
 }
@@ -1,15 +1,682 @@
+use std::fmt::{self, Display, Formatter};
 
-use crate::shared::byte_stream::{ByteStream, Location};
+use crate::shared::byte_stream::{ByteStream, Character::Ch, Location, LocationHandler, Stream};
 
-#[allow(dead_code)]
+/// Types of tokens as defined by the CSS Syntax Module Level 3 (https://www.w3.org/TR/css-syntax-3/#tokenization)
+#[derive(Debug, PartialEq, Clone)]
+pub enum TokenType {
+    AtKeyword(String),
+    BadString(String),
+    BadUrl(String),
+    /// `-->`
+    CDC,
+    /// `<!--`
+    CDO,
+    Colon,
+    Comma,
+    Comment(String),
+    Delim(char),
+    Dimension {
+        value: f32,
+        unit: String,
+    },
+    Eof,
+    Function(String),
+    /// Hash token with the "unrestricted" type flag
+    Hash(String),
+    /// Hash token with the "id" type flag (can be used as an id selector)
+    IDHash(String),
+    Ident(String),
+    LBracket,
+    LCurly,
+    LParen,
+    Number(f32),
+    Percentage(f32),
+    QuotedString(String),
+    RBracket,
+    RCurly,
+    RParen,
+    Semicolon,
+    Url(String),
+    Whitespace(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub location: Location,
+}
+
+impl Token {
+    pub fn new(token_type: TokenType, location: Location) -> Self {
+        Self { token_type, location }
+    }
+
+    pub fn is_eof(&self) -> bool {
+        matches!(self.token_type, TokenType::Eof)
+    }
+
+    pub fn is_whitespace(&self) -> bool {
+        matches!(self.token_type, TokenType::Whitespace(_))
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.token_type, TokenType::Comment(_))
+    }
+
+    pub fn is_ident(&self) -> bool {
+        matches!(self.token_type, TokenType::Ident(_))
+    }
+
+    pub fn is_delim(&self, c: char) -> bool {
+        matches!(self.token_type, TokenType::Delim(d) if d == c)
+    }
+
+    pub fn is_colon(&self) -> bool {
+        matches!(self.token_type, TokenType::Colon)
+    }
+
+    pub fn is_comma(&self) -> bool {
+        matches!(self.token_type, TokenType::Comma)
+    }
+
+    /// Returns true when the token is an ident with the given (case-insensitive) name
+    pub fn is_ident_named(&self, name: &str) -> bool {
+        matches!(&self.token_type, TokenType::Ident(ident) if ident.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.token_type)
+    }
+}
+
+impl Display for TokenType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenType::AtKeyword(name) => write!(f, "@{name}"),
+            TokenType::BadString(s) => write!(f, "\"{s}"),
+            TokenType::BadUrl(url) => write!(f, "url({url}"),
+            TokenType::CDC => write!(f, "-->"),
+            TokenType::CDO => write!(f, "<!--"),
+            TokenType::Colon => write!(f, ":"),
+            TokenType::Comma => write!(f, ","),
+            TokenType::Comment(comment) => write!(f, "/*{comment}*/"),
+            TokenType::Delim(c) => write!(f, "{c}"),
+            TokenType::Dimension { value, unit } => write!(f, "{value}{unit}"),
+            TokenType::Eof => Ok(()),
+            TokenType::Function(name) => write!(f, "{name}("),
+            TokenType::Hash(hash) | TokenType::IDHash(hash) => write!(f, "#{hash}"),
+            TokenType::Ident(ident) => write!(f, "{ident}"),
+            TokenType::LBracket => write!(f, "["),
+            TokenType::LCurly => write!(f, "{{"),
+            TokenType::LParen => write!(f, "("),
+            TokenType::Number(value) => write!(f, "{value}"),
+            TokenType::Percentage(value) => write!(f, "{value}%"),
+            TokenType::QuotedString(s) => write!(f, "\"{s}\""),
+            TokenType::RBracket => write!(f, "]"),
+            TokenType::RCurly => write!(f, "}}"),
+            TokenType::RParen => write!(f, ")"),
+            TokenType::Semicolon => write!(f, ";"),
+            TokenType::Url(url) => write!(f, "url({url})"),
+            TokenType::Whitespace(_) => write!(f, " "),
+        }
+    }
+}
+
+/// The tokenizer reads the complete stream into tokens, and allows the parser to walk (and backtrack)
+/// through them.
 pub struct Tokenizer<'stream> {
     stream: &'stream mut ByteStream,
+    location_handler: LocationHandler,
+    /// All tokens read so far
+    tokens: Vec<Token>,
+    /// Position of the next token that will be consumed
+    position: usize,
+    /// True when the stream has been fully tokenized
+    tokenized: bool,
 }
 
 impl<'stream> Tokenizer<'stream> {
     pub fn new(stream: &'stream mut ByteStream, start_location: Location) -> Self {
+        let mut location_handler = LocationHandler::new(start_location);
+        location_handler.cur_location = start_location;
+
         Self {
             stream,
+            location_handler,
+            tokens: Vec::new(),
+            position: 0,
+            tokenized: false,
+        }
+    }
+
+    /// Returns the location of the next token that will be consumed
+    pub fn current_location(&mut self) -> Location {
+        self.lookahead(0).location
+    }
+
+    /// Returns the next token and moves the cursor
+    pub fn consume(&mut self) -> Token {
+        let token = self.lookahead(0);
+        if self.position < self.tokens.len() {
+            self.position += 1;
+        }
+        token
+    }
+
+    /// Moves the cursor one token back, so the previous token will be consumed again
+    pub fn reconsume(&mut self) {
+        self.position = self.position.saturating_sub(1);
+    }
+
+    /// Returns the token at the given offset from the cursor without consuming it
+    pub fn lookahead(&mut self, offset: usize) -> Token {
+        self.tokenize();
+
+        match self.tokens.get(self.position + offset) {
+            Some(token) => token.clone(),
+            None => Token::new(TokenType::Eof, self.location_handler.cur_location),
+        }
+    }
+
+    /// Returns the next non-whitespace (and non-comment) token without consuming it
+    pub fn lookahead_sc(&mut self, offset: usize) -> Token {
+        let mut skipped = 0;
+        let mut i = 0;
+        loop {
+            let token = self.lookahead(i);
+            if token.is_eof() {
+                return token;
+            }
+            if !token.is_whitespace() && !token.is_comment() {
+                if skipped == offset {
+                    return token;
+                }
+                skipped += 1;
+            }
+            i += 1;
+        }
+    }
+
+    /// Returns the current cursor position, which can be used to restore the cursor with `seek()`
+    pub fn tell(&self) -> usize {
+        self.position
+    }
+
+    pub fn seek(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    pub fn eof(&mut self) -> bool {
+        self.lookahead(0).is_eof()
+    }
+
+    /// Reads all tokens from the stream
+    fn tokenize(&mut self) {
+        if self.tokenized {
+            return;
+        }
+        self.tokenized = true;
+
+        loop {
+            let token = self.consume_token();
+            if token.is_eof() {
+                self.tokens.push(token);
+                break;
+            }
+            self.tokens.push(token);
+        }
+    }
+
+    fn current_char(&self) -> char {
+        self.stream.read().into()
+    }
+
+    fn next_char(&self, offset: usize) -> char {
+        self.stream.look_ahead(offset).into()
+    }
+
+    fn stream_end(&self) -> bool {
+        self.stream.exhausted()
+    }
+
+    fn consume_char(&mut self) -> char {
+        let c = self.stream.read_and_next();
+        self.location_handler.inc(c);
+        match c {
+            Ch(c) => c,
+            _ => '\0',
+        }
+    }
+
+    /// 4.3.1. Consume a token
+    fn consume_token(&mut self) -> Token {
+        let location = self.location_handler.cur_location;
+
+        if self.stream_end() {
+            return Token::new(TokenType::Eof, location);
+        }
+
+        let c = self.current_char();
+        let token_type = match c {
+            '/' if self.next_char(1) == '*' => self.consume_comment(),
+            c if is_whitespace(c) => {
+                let mut ws = String::new();
+                while !self.stream_end() && is_whitespace(self.current_char()) {
+                    ws.push(self.consume_char());
+                }
+                TokenType::Whitespace(ws)
+            }
+            '"' | '\'' => {
+                self.consume_char();
+                self.consume_string_token(c)
+            }
+            '#' => {
+                if is_ident_char(self.next_char(1)) || self.is_valid_escape(1) {
+                    self.consume_char();
+                    let is_id = self.would_start_ident(0);
+                    let name = self.consume_ident();
+                    if is_id {
+                        TokenType::IDHash(name)
+                    } else {
+                        TokenType::Hash(name)
+                    }
+                } else {
+                    TokenType::Delim(self.consume_char())
+                }
+            }
+            '(' => {
+                self.consume_char();
+                TokenType::LParen
+            }
+            ')' => {
+                self.consume_char();
+                TokenType::RParen
+            }
+            '+' | '.' => {
+                if self.would_start_number(0) {
+                    self.consume_numeric_token()
+                } else {
+                    TokenType::Delim(self.consume_char())
+                }
+            }
+            ',' => {
+                self.consume_char();
+                TokenType::Comma
+            }
+            '-' => {
+                if self.would_start_number(0) {
+                    self.consume_numeric_token()
+                } else if self.next_char(1) == '-' && self.next_char(2) == '>' {
+                    self.consume_char();
+                    self.consume_char();
+                    self.consume_char();
+                    TokenType::CDC
+                } else if self.would_start_ident(0) {
+                    self.consume_ident_like_token()
+                } else {
+                    TokenType::Delim(self.consume_char())
+                }
+            }
+            ':' => {
+                self.consume_char();
+                TokenType::Colon
+            }
+            ';' => {
+                self.consume_char();
+                TokenType::Semicolon
+            }
+            '<' => {
+                if self.next_char(1) == '!' && self.next_char(2) == '-' && self.next_char(3) == '-' {
+                    for _ in 0..4 {
+                        self.consume_char();
+                    }
+                    TokenType::CDO
+                } else {
+                    TokenType::Delim(self.consume_char())
+                }
+            }
+            '@' => {
+                self.consume_char();
+                if self.would_start_ident(0) {
+                    TokenType::AtKeyword(self.consume_ident())
+                } else {
+                    TokenType::Delim('@')
+                }
+            }
+            '[' => {
+                self.consume_char();
+                TokenType::LBracket
+            }
+            '\\' => {
+                if self.is_valid_escape(0) {
+                    self.consume_ident_like_token()
+                } else {
+                    TokenType::Delim(self.consume_char())
+                }
+            }
+            ']' => {
+                self.consume_char();
+                TokenType::RBracket
+            }
+            '{' => {
+                self.consume_char();
+                TokenType::LCurly
+            }
+            '}' => {
+                self.consume_char();
+                TokenType::RCurly
+            }
+            c if c.is_ascii_digit() => self.consume_numeric_token(),
+            c if is_ident_start_char(c) => self.consume_ident_like_token(),
+            _ => TokenType::Delim(self.consume_char()),
+        };
+
+        Token::new(token_type, location)
+    }
+
+    fn consume_comment(&mut self) -> TokenType {
+        // consume the "/*"
+        self.consume_char();
+        self.consume_char();
+
+        let mut comment = String::new();
+        while !self.stream_end() {
+            if self.current_char() == '*' && self.next_char(1) == '/' {
+                self.consume_char();
+                self.consume_char();
+                break;
+            }
+            comment.push(self.consume_char());
+        }
+
+        TokenType::Comment(comment)
+    }
+
+    /// 4.3.3. Consume a numeric token
+    fn consume_numeric_token(&mut self) -> TokenType {
+        let value = self.consume_number();
+
+        if self.would_start_ident(0) {
+            let unit = self.consume_ident();
+            return TokenType::Dimension { value, unit };
+        }
+
+        if self.current_char() == '%' && !self.stream_end() {
+            self.consume_char();
+            return TokenType::Percentage(value);
+        }
+
+        TokenType::Number(value)
+    }
+
+    /// 4.3.4. Consume an ident-like token
+    fn consume_ident_like_token(&mut self) -> TokenType {
+        let name = self.consume_ident();
+
+        if name.eq_ignore_ascii_case("url") && self.current_char() == '(' && !self.stream_end() {
+            self.consume_char();
+
+            // Skip whitespace, but when we find a quote, this is a url() function instead of a url token
+            let mut offset = 0;
+            while is_whitespace(self.next_char(offset)) {
+                offset += 1;
+            }
+            let c = self.next_char(offset);
+            if c == '"' || c == '\'' {
+                return TokenType::Function(name);
+            }
+
+            return self.consume_url();
+        }
+
+        if self.current_char() == '(' && !self.stream_end() {
+            self.consume_char();
+            return TokenType::Function(name);
+        }
+
+        TokenType::Ident(name)
+    }
+
+    /// 4.3.5. Consume a string token
+    fn consume_string_token(&mut self, ending: char) -> TokenType {
+        let mut value = String::new();
+
+        loop {
+            if self.stream_end() {
+                // parse error: eof in string
+                return TokenType::QuotedString(value);
+            }
+
+            let c = self.current_char();
+            match c {
+                c if c == ending => {
+                    self.consume_char();
+                    return TokenType::QuotedString(value);
+                }
+                '\n' => {
+                    // parse error: newline in string. Don't consume the newline
+                    return TokenType::BadString(value);
+                }
+                '\\' => {
+                    if self.next_char(1) == '\n' {
+                        // escaped newline: continuation of the string
+                        self.consume_char();
+                        self.consume_char();
+                    } else if self.is_valid_escape(0) {
+                        self.consume_char();
+                        value.push(self.consume_escaped_char());
+                    } else {
+                        // escape at EOF
+                        self.consume_char();
+                    }
+                }
+                _ => value.push(self.consume_char()),
+            }
+        }
+    }
+
+    /// 4.3.6. Consume a url token
+    fn consume_url(&mut self) -> TokenType {
+        let mut url = String::new();
+
+        while !self.stream_end() && is_whitespace(self.current_char()) {
+            self.consume_char();
+        }
+
+        loop {
+            if self.stream_end() {
+                return TokenType::Url(url);
+            }
+
+            let c = self.current_char();
+            match c {
+                ')' => {
+                    self.consume_char();
+                    return TokenType::Url(url);
+                }
+                c if is_whitespace(c) => {
+                    while !self.stream_end() && is_whitespace(self.current_char()) {
+                        self.consume_char();
+                    }
+                    if self.stream_end() || self.current_char() == ')' {
+                        if !self.stream_end() {
+                            self.consume_char();
+                        }
+                        return TokenType::Url(url);
+                    }
+                    self.consume_remnants_of_bad_url();
+                    return TokenType::BadUrl(url);
+                }
+                '"' | '\'' | '(' => {
+                    self.consume_remnants_of_bad_url();
+                    return TokenType::BadUrl(url);
+                }
+                c if is_non_printable(c) => {
+                    self.consume_remnants_of_bad_url();
+                    return TokenType::BadUrl(url);
+                }
+                '\\' => {
+                    if self.is_valid_escape(0) {
+                        self.consume_char();
+                        url.push(self.consume_escaped_char());
+                    } else {
+                        self.consume_remnants_of_bad_url();
+                        return TokenType::BadUrl(url);
+                    }
+                }
+                _ => url.push(self.consume_char()),
+            }
+        }
+    }
+
+    /// 4.3.14. Consume the remnants of a bad url
+    fn consume_remnants_of_bad_url(&mut self) {
+        while !self.stream_end() {
+            if self.current_char() == ')' {
+                self.consume_char();
+                return;
+            }
+            if self.is_valid_escape(0) {
+                self.consume_char();
+                self.consume_escaped_char();
+                continue;
+            }
+            self.consume_char();
+        }
+    }
+
+    /// 4.3.7. Consume an escaped code point. Assumes the backslash has already been consumed.
+    fn consume_escaped_char(&mut self) -> char {
+        if self.stream_end() {
+            return char::REPLACEMENT_CHARACTER;
+        }
+
+        let c = self.current_char();
+        if !c.is_ascii_hexdigit() {
+            return self.consume_char();
+        }
+
+        let mut hex = String::new();
+        while hex.len() < 6 && !self.stream_end() && self.current_char().is_ascii_hexdigit() {
+            hex.push(self.consume_char());
+        }
+        if !self.stream_end() && is_whitespace(self.current_char()) {
+            self.consume_char();
+        }
+
+        let code = u32::from_str_radix(&hex, 16).unwrap_or(0);
+        if code == 0 || (0xD800..=0xDFFF).contains(&code) || code > 0x10FFFF {
+            return char::REPLACEMENT_CHARACTER;
+        }
+
+        char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+
+    /// 4.3.11. Consume an ident sequence
+    fn consume_ident(&mut self) -> String {
+        let mut value = String::new();
+
+        while !self.stream_end() {
+            let c = self.current_char();
+            if is_ident_char(c) {
+                value.push(self.consume_char());
+            } else if self.is_valid_escape(0) {
+                self.consume_char();
+                value.push(self.consume_escaped_char());
+            } else {
+                break;
+            }
+        }
+
+        value
+    }
+
+    /// 4.3.12. Consume a number
+    fn consume_number(&mut self) -> f32 {
+        let mut repr = String::new();
+
+        if matches!(self.current_char(), '+' | '-') {
+            repr.push(self.consume_char());
+        }
+
+        while !self.stream_end() && self.current_char().is_ascii_digit() {
+            repr.push(self.consume_char());
+        }
+
+        if self.current_char() == '.' && self.next_char(1).is_ascii_digit() && !self.stream_end() {
+            repr.push(self.consume_char());
+            while !self.stream_end() && self.current_char().is_ascii_digit() {
+                repr.push(self.consume_char());
+            }
+        }
+
+        let c = self.current_char();
+        if (c == 'e' || c == 'E') && !self.stream_end() {
+            let n1 = self.next_char(1);
+            let n2 = self.next_char(2);
+            if n1.is_ascii_digit() || ((n1 == '+' || n1 == '-') && n2.is_ascii_digit()) {
+                repr.push(self.consume_char());
+                repr.push(self.consume_char());
+                while !self.stream_end() && self.current_char().is_ascii_digit() {
+                    repr.push(self.consume_char());
+                }
+            }
+        }
+
+        repr.parse::<f32>().unwrap_or(0.0)
+    }
+
+    /// 4.3.8. Check if two code points are a valid escape
+    fn is_valid_escape(&self, offset: usize) -> bool {
+        self.next_char(offset) == '\\' && self.next_char(offset + 1) != '\n' && !self.is_end_at(offset + 1)
+    }
+
+    /// 4.3.9. Check if three code points would start an ident sequence
+    fn would_start_ident(&self, offset: usize) -> bool {
+        let c = self.next_char(offset);
+        match c {
+            '-' => {
+                let n = self.next_char(offset + 1);
+                is_ident_start_char(n) || n == '-' || self.is_valid_escape(offset + 1)
+            }
+            '\\' => self.is_valid_escape(offset),
+            c => is_ident_start_char(c) && !self.is_end_at(offset),
         }
     }
+
+    /// 4.3.10. Check if three code points would start a number
+    fn would_start_number(&self, offset: usize) -> bool {
+        let c = self.next_char(offset);
+        match c {
+            '+' | '-' => {
+                let n = self.next_char(offset + 1);
+                n.is_ascii_digit() || (n == '.' && self.next_char(offset + 2).is_ascii_digit())
+            }
+            '.' => self.next_char(offset + 1).is_ascii_digit(),
+            c => c.is_ascii_digit(),
+        }
+    }
+
+    fn is_end_at(&self, offset: usize) -> bool {
+        !matches!(self.stream.look_ahead(offset), Ch(_))
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r' | '\u{000C}')
+}
+
+fn is_ident_start_char(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii() && c != '\0'
+}
+
+fn is_ident_char(c: char) -> bool {
+    is_ident_start_char(c) || c.is_ascii_digit() || c == '-'
+}
+
+fn is_non_printable(c: char) -> bool {
+    matches!(c, '\u{0000}'..='\u{0008}' | '\u{000B}' | '\u{000E}'..='\u{001F}' | '\u{007F}')
 }
@@ -0,0 +1,111 @@
+// Policy for vendor-specific (`-webkit-`, `-moz-`, `-internal-` etc.) tokens.
+//
+// Vendor tokens with a standard equivalent are replaced by that equivalent while parsing, so
+// `-webkit-fill-available` is handled as `stretch` and the `__qem` unit as `em`. All other vendor
+// tokens are not supported: declarations using them are dropped like any invalid declaration, but
+// are logged as a warning instead of an error, as they are expected in real-world stylesheets.
+// Unknown vendor pseudo-classes and pseudo-elements are kept in the selector and never match.
+
+/// Vendor keywords and their standard equivalent
+const KEYWORD_ALIASES: &[(&str, &str)] = &[
+    ("-webkit-fill-available", "stretch"),
+    ("-moz-available", "stretch"),
+    ("-webkit-max-content", "max-content"),
+    ("-webkit-min-content", "min-content"),
+    ("-webkit-fit-content", "fit-content"),
+    ("-webkit-center", "center"),
+    ("-internal-center", "center"),
+    ("-webkit-left", "left"),
+    ("-webkit-right", "right"),
+    ("-webkit-match-parent", "match-parent"),
+    ("-webkit-link", "LinkText"),
+    ("-webkit-activelink", "ActiveText"),
+    ("-webkit-focus-ring-color", "Highlight"),
+    ("-webkit-small-control", "small-caption"),
+    ("-webkit-isolate", "isolate"),
+    ("-webkit-plaintext", "plaintext"),
+];
+
+/// Vendor functions and their standard equivalent
+const FUNCTION_ALIASES: &[(&str, &str)] = &[
+    ("-internal-light-dark", "light-dark"),
+    ("-webkit-image-set", "image-set"),
+    ("-webkit-linear-gradient", "linear-gradient"),
+    ("-webkit-radial-gradient", "radial-gradient"),
+    ("-webkit-repeating-linear-gradient", "repeating-linear-gradient"),
+    ("-webkit-repeating-radial-gradient", "repeating-radial-gradient"),
+    ("-webkit-calc", "calc"),
+];
+
+/// Vendor units and their standard equivalent. `__qem` is the "quirky em" of WebKit, which only
+/// differs from `em` for margin collapsing in quirks mode.
+const UNIT_ALIASES: &[(&str, &str)] = &[("__qem", "em")];
+
+/// Legacy vendor properties and the standard property they are an alias of (Compat, 5)
+const PROPERTY_ALIASES: &[(&str, &str)] = &[
+    ("-webkit-mask", "mask"),
+    ("-webkit-mask-image", "mask-image"),
+    ("-webkit-mask-position", "mask-position"),
+    ("-webkit-mask-repeat", "mask-repeat"),
+    ("-webkit-mask-size", "mask-size"),
+    ("-webkit-mask-origin", "mask-origin"),
+    ("-webkit-mask-clip", "mask-clip"),
+    ("-webkit-mask-composite", "mask-composite"),
+    ("-webkit-appearance", "appearance"),
+    ("-moz-appearance", "appearance"),
+    ("-webkit-user-select", "user-select"),
+    ("-webkit-box-sizing", "box-sizing"),
+    ("-webkit-text-emphasis", "text-emphasis"),
+    ("-webkit-text-emphasis-style", "text-emphasis-style"),
+    ("-webkit-text-emphasis-color", "text-emphasis-color"),
+];
+
+/// Vendor pseudo-classes and their standard equivalent
+const PSEUDO_CLASS_ALIASES: &[(&str, &str)] = &[
+    ("-webkit-any-link", "any-link"),
+    ("-moz-any-link", "any-link"),
+    ("-webkit-autofill", "autofill"),
+    ("-moz-read-only", "read-only"),
+    ("-moz-read-write", "read-write"),
+    ("-moz-placeholder-shown", "placeholder-shown"),
+];
+
+/// Returns true for vendor-prefixed names like `-webkit-box` and `-internal-light-dark`. Custom
+/// properties (`--foo`) are not vendor-prefixed.
+pub fn is_vendor_prefixed(name: &str) -> bool {
+    name.strip_prefix('-')
+        .and_then(|rest| rest.split_once('-'))
+        .is_some_and(|(vendor, _)| !vendor.is_empty())
+}
+
+fn find_alias(aliases: &[(&str, &'static str)], name: &str) -> Option<&'static str> {
+    aliases
+        .iter()
+        .find(|(vendor, _)| vendor.eq_ignore_ascii_case(name))
+        .map(|(_, standard)| *standard)
+}
+
+/// Returns the standard keyword for a vendor keyword
+pub fn keyword_alias(name: &str) -> Option<&'static str> {
+    find_alias(KEYWORD_ALIASES, name)
+}
+
+/// Returns the standard function for a vendor function
+pub fn function_alias(name: &str) -> Option<&'static str> {
+    find_alias(FUNCTION_ALIASES, name)
+}
+
+/// Returns the standard unit for a vendor unit
+pub fn unit_alias(unit: &str) -> Option<&'static str> {
+    find_alias(UNIT_ALIASES, unit)
+}
+
+/// Returns the standard property for a legacy vendor property
+pub fn property_alias(name: &str) -> Option<&'static str> {
+    find_alias(PROPERTY_ALIASES, name)
+}
+
+/// Returns the standard pseudo-class for a vendor pseudo-class
+pub fn pseudo_class_alias(name: &str) -> Option<&'static str> {
+    find_alias(PSEUDO_CLASS_ALIASES, name)
+}
@@ -0,0 +1,141 @@
+use std::{fmt::Debug, sync::Arc};
+
+use skrifa::{FontRef, MetadataProvider};
+
+mod database;
+mod fontconfig;
+mod shape;
+mod style;
+
+pub use database::FontDatabase;
+pub use shape::{FontMetrics, GlyphRun, ShapedText, TextStyle};
+pub use style::text_style;
+
+/// The contents of a font file, shared by all faces in it
+pub type FontData = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+/// A loaded font face, which is cheap to clone
+#[derive(Clone)]
+pub struct Font {
+    data: FontData,
+    /// Index of the face in a font collection
+    index: u32,
+    /// Unique id of the font data, which renderers can use to cache glyphs
+    id: u64,
+}
+
+impl Font {
+    pub fn data(&self) -> &FontData {
+        &self.data
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(crate) fn font_ref(&self) -> Option<FontRef<'_>> {
+        FontRef::from_index((*self.data).as_ref(), self.index).ok()
+    }
+
+    /// Returns the glyph of a character, if the face has one
+    pub(crate) fn glyph(&self, c: char) -> Option<u32> {
+        let font = self.font_ref()?;
+        font.charmap()
+            .map(c)
+            .map(|glyph| glyph.to_u32())
+            .filter(|glyph| *glyph != 0)
+    }
+}
+
+impl Debug for Font {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Font")
+            .field("index", &self.index)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl PartialEq for Font {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.index == other.index
+    }
+}
+
+/// A positioned glyph of a font
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    pub id: u32,
+    pub x: f32,
+    pub y: f32,
+    pub advance: f32,
+    /// Byte offset of the first character of the cluster the glyph is generated from
+    pub cluster: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+    Oblique,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontFamily {
+    /// A family name, like `DejaVu Sans`
+    Named(String),
+    /// A generic family, like `serif` or `monospace`
+    Generic(String),
+}
+
+impl FontFamily {
+    const GENERIC: [&'static str; 9] = [
+        "serif",
+        "sans-serif",
+        "monospace",
+        "cursive",
+        "fantasy",
+        "system-ui",
+        "ui-serif",
+        "ui-sans-serif",
+        "ui-monospace",
+    ];
+
+    /// Returns the family of an unquoted family name, which can be a generic family
+    pub fn from_keyword(name: &str) -> Self {
+        let lower = name.to_ascii_lowercase();
+        if Self::GENERIC.contains(&lower.as_str()) {
+            Self::Generic(lower)
+        } else {
+            Self::Named(name.to_string())
+        }
+    }
+}
+
+/// The properties a font face is selected by (CSS Fonts 4, 5.2)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontQuery {
+    /// Families in order of preference
+    pub families: Vec<FontFamily>,
+    /// Weight from 1 to 1000, where 400 is normal and 700 is bold
+    pub weight: f32,
+    pub style: FontStyle,
+    /// Width as a percentage of the normal width
+    pub stretch: f32,
+}
+
+impl Default for FontQuery {
+    fn default() -> Self {
+        Self {
+            families: vec![FontFamily::Generic("sans-serif".to_string())],
+            weight: 400.0,
+            style: FontStyle::Normal,
+            stretch: 100.0,
+        }
+    }
+}
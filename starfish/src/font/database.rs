@@ -0,0 +1,417 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use skrifa::{attribute::Style, raw::FileRef, string::StringId, FontRef, MetadataProvider};
+
+use crate::font::{fontconfig::font_dirs, Font, FontData, FontFamily, FontQuery, FontStyle};
+
+/// Fonts that are always available, so text renders the same on every system
+const BUNDLED_FONTS: [&[u8]; 3] = [
+    include_bytes!("./resources/DejaVuSans.ttf"),
+    include_bytes!("./resources/DejaVuSerif.ttf"),
+    include_bytes!("./resources/DejaVuSansMono.ttf"),
+];
+
+/// Families tried in order for each generic family. The bundled fonts come last, so they are used
+/// when none of the common system fonts is installed.
+const GENERIC_FAMILIES: [(&str, &[&str]); 5] = [
+    (
+        "serif",
+        &[
+            "Noto Serif",
+            "Liberation Serif",
+            "Times New Roman",
+            "Times",
+            "DejaVu Serif",
+        ],
+    ),
+    (
+        "sans-serif",
+        &["Noto Sans", "Liberation Sans", "Arial", "Helvetica", "DejaVu Sans"],
+    ),
+    (
+        "monospace",
+        &[
+            "Noto Sans Mono",
+            "Liberation Mono",
+            "Courier New",
+            "Courier",
+            "DejaVu Sans Mono",
+        ],
+    ),
+    ("cursive", &["Comic Sans MS", "URW Chancery L", "Z003"]),
+    ("fantasy", &["Impact", "Papyrus"]),
+];
+
+static NEXT_FONT_ID: AtomicU64 = AtomicU64::new(1);
+
+enum SourceData {
+    /// A font file, which is only read when one of its faces is used
+    File(PathBuf),
+    Memory(FontData),
+}
+
+/// A font file or font data in memory, with one or more faces
+struct Source {
+    data: SourceData,
+    id: u64,
+    loaded: OnceLock<Option<FontData>>,
+}
+
+impl Source {
+    fn new(data: SourceData) -> Self {
+        Self {
+            data,
+            id: NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed),
+            loaded: OnceLock::new(),
+        }
+    }
+
+    fn data(&self) -> Option<&FontData> {
+        self.loaded
+            .get_or_init(|| match &self.data {
+                SourceData::File(path) => match fs::read(path) {
+                    Ok(data) => Some(Arc::new(data)),
+                    Err(err) => {
+                        tracing::warn!("Could not read font {}: {err}", path.display());
+                        None
+                    }
+                },
+                SourceData::Memory(data) => Some(data.clone()),
+            })
+            .as_ref()
+    }
+}
+
+#[derive(Debug)]
+struct Face {
+    source: usize,
+    index: u32,
+    /// Lowercase family names
+    families: Vec<String>,
+    weight: f32,
+    style: FontStyle,
+    stretch: f32,
+}
+
+/// The fonts that text can be rendered with. Faces are selected with the font matching algorithm
+/// of CSS Fonts 4 (5.2).
+pub struct FontDatabase {
+    sources: Vec<Source>,
+    faces: Vec<Face>,
+    /// Face used for characters that none of the requested families support
+    fallbacks: Mutex<HashMap<(char, FontStyle, u16), Option<usize>>>,
+}
+
+impl Debug for FontDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FontDatabase").field("faces", &self.faces).finish()
+    }
+}
+
+impl Default for FontDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FontDatabase {
+    /// Creates a database without any fonts
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            faces: Vec::new(),
+            fallbacks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the database with the system fonts and the bundled fonts, which is loaded once
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<FontDatabase>> = OnceLock::new();
+
+        SHARED
+            .get_or_init(|| {
+                let mut database = Self::new();
+                database.load_system_fonts();
+                database.load_bundled_fonts();
+                tracing::info!("Loaded {} font faces", database.len());
+                Arc::new(database)
+            })
+            .clone()
+    }
+
+    /// Returns a database with only the bundled fonts, so layout does not depend on the fonts that
+    /// are installed
+    pub fn bundled() -> Arc<Self> {
+        static BUNDLED: OnceLock<Arc<FontDatabase>> = OnceLock::new();
+
+        BUNDLED
+            .get_or_init(|| {
+                let mut database = Self::new();
+                database.load_bundled_fonts();
+                Arc::new(database)
+            })
+            .clone()
+    }
+
+    /// Number of faces in the database
+    pub fn len(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    /// Loads the fonts in the directories of the fontconfig configuration
+    pub fn load_system_fonts(&mut self) {
+        for dir in font_dirs() {
+            self.load_fonts_dir(&dir);
+        }
+    }
+
+    pub fn load_bundled_fonts(&mut self) {
+        for data in BUNDLED_FONTS {
+            self.load_font_data(Arc::new(data));
+        }
+    }
+
+    /// Loads all font files in the directory and its subdirectories
+    pub fn load_fonts_dir(&mut self, dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            if path.is_dir() {
+                self.load_fonts_dir(&path);
+                continue;
+            }
+
+            let is_font = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "ttf" | "otf" | "ttc" | "otc"));
+            if is_font {
+                self.load_font_file(&path);
+            }
+        }
+    }
+
+    /// Loads the faces of a font file. The file is read again when one of its faces is used.
+    /// Returns the number of faces that are loaded.
+    pub fn load_font_file(&mut self, path: &Path) -> usize {
+        match fs::read(path) {
+            Ok(data) => self.load_source(SourceData::File(path.to_path_buf()), &data),
+            Err(err) => {
+                tracing::warn!("Could not read font {}: {err}", path.display());
+                0
+            }
+        }
+    }
+
+    /// Loads the faces of a font file in memory. Returns the number of faces that are loaded.
+    pub fn load_font_data(&mut self, data: FontData) -> usize {
+        let contents = data.clone();
+        self.load_source(SourceData::Memory(data), (*contents).as_ref())
+    }
+
+    fn load_source(&mut self, source: SourceData, data: &[u8]) -> usize {
+        let Ok(file) = FileRef::new(data) else {
+            return 0;
+        };
+
+        let index = self.sources.len();
+        let faces = file
+            .fonts()
+            .enumerate()
+            .filter_map(|(face, font)| Some(face_info(&font.ok()?, index, face as u32)))
+            .collect::<Vec<_>>();
+        if faces.is_empty() {
+            return 0;
+        }
+
+        let count = faces.len();
+        self.sources.push(Source::new(source));
+        self.faces.extend(faces);
+        self.fallbacks.lock().expect("font fallback cache").clear();
+
+        count
+    }
+
+    /// Returns the faces for the families of the query in order of preference, followed by the
+    /// default family
+    pub fn query(&self, query: &FontQuery) -> Vec<Font> {
+        let default = FontFamily::Generic("sans-serif".to_string());
+        let mut faces: Vec<usize> = Vec::new();
+
+        for family in query.families.iter().chain([&default]) {
+            let face = match family {
+                FontFamily::Named(name) => self.match_family(name, query),
+                FontFamily::Generic(generic) => self.match_generic(generic, query),
+            };
+            if let Some(face) = face.filter(|face| !faces.contains(face)) {
+                faces.push(face);
+            }
+        }
+
+        faces.into_iter().filter_map(|face| self.font(face)).collect()
+    }
+
+    /// Returns a face that has a glyph for the character, for characters that none of the faces
+    /// of the query support
+    pub fn fallback(&self, c: char, query: &FontQuery) -> Option<Font> {
+        let key = (c, query.style, query.weight as u16);
+        if let Some(face) = self.fallbacks.lock().expect("font fallback cache").get(&key) {
+            return face.and_then(|face| self.font(face));
+        }
+
+        let candidates = (0..self.faces.len())
+            .filter(|face| self.font(*face).is_some_and(|font| font.glyph(c).is_some()))
+            .collect::<Vec<_>>();
+        let face = self.best_match(candidates, query);
+
+        self.fallbacks.lock().expect("font fallback cache").insert(key, face);
+        face.and_then(|face| self.font(face))
+    }
+
+    fn font(&self, face: usize) -> Option<Font> {
+        let face = &self.faces[face];
+        let source = &self.sources[face.source];
+
+        Some(Font {
+            data: source.data()?.clone(),
+            index: face.index,
+            id: source.id,
+        })
+    }
+
+    fn match_generic(&self, generic: &str, query: &FontQuery) -> Option<usize> {
+        let generic = match generic {
+            "ui-serif" => "serif",
+            "system-ui" | "ui-sans-serif" => "sans-serif",
+            "ui-monospace" => "monospace",
+            generic => generic,
+        };
+        let (_, families) = GENERIC_FAMILIES.iter().find(|(name, _)| *name == generic)?;
+
+        families.iter().find_map(|family| self.match_family(family, query))
+    }
+
+    fn match_family(&self, family: &str, query: &FontQuery) -> Option<usize> {
+        let family = family.to_lowercase();
+        let candidates = (0..self.faces.len())
+            .filter(|face| self.faces[*face].families.contains(&family))
+            .collect();
+
+        self.best_match(candidates, query)
+    }
+
+    /// Narrows the faces down by stretch, style and weight in turn (CSS Fonts 4, 5.2 step 4)
+    fn best_match(&self, mut candidates: Vec<usize>, query: &FontQuery) -> Option<usize> {
+        let stretch = |face: &Face| {
+            let (desired, actual) = (query.stretch, face.stretch);
+            // Narrower widths are preferred for condensed requests, wider ones for expanded
+            // requests
+            let preferred = if desired <= 100.0 {
+                actual <= desired
+            } else {
+                actual >= desired
+            };
+            (!preferred, (actual - desired).abs())
+        };
+        narrow(&mut candidates, &self.faces, stretch);
+
+        let style = |face: &Face| {
+            let order = match query.style {
+                FontStyle::Italic => [FontStyle::Italic, FontStyle::Oblique, FontStyle::Normal],
+                FontStyle::Oblique => [FontStyle::Oblique, FontStyle::Italic, FontStyle::Normal],
+                FontStyle::Normal => [FontStyle::Normal, FontStyle::Oblique, FontStyle::Italic],
+            };
+            (
+                false,
+                order.iter().position(|style| *style == face.style).unwrap_or(3) as f32,
+            )
+        };
+        narrow(&mut candidates, &self.faces, style);
+
+        let weight = |face: &Face| {
+            let (desired, actual) = (query.weight, face.weight);
+            let distance = (actual - desired).abs();
+            let group = if (400.0..=500.0).contains(&desired) {
+                // Weights up to 500 come first, then lighter weights, then heavier weights
+                if actual >= desired && actual <= 500.0 {
+                    0.0
+                } else if actual < desired {
+                    1.0
+                } else {
+                    2.0
+                }
+            } else if desired < 400.0 {
+                if actual <= desired {
+                    0.0
+                } else {
+                    1.0
+                }
+            } else if actual >= desired {
+                0.0
+            } else {
+                1.0
+            };
+            (false, group * 1000.0 + distance)
+        };
+        narrow(&mut candidates, &self.faces, weight);
+
+        candidates.first().copied()
+    }
+}
+
+/// Keeps the candidates with the best (lowest) key
+fn narrow(candidates: &mut Vec<usize>, faces: &[Face], key: impl Fn(&Face) -> (bool, f32)) {
+    let best = candidates
+        .iter()
+        .map(|face| key(&faces[*face]))
+        .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    if let Some(best) = best {
+        candidates.retain(|face| key(&faces[*face]) == best);
+    }
+}
+
+fn face_info(font: &FontRef, source: usize, index: u32) -> Face {
+    let mut families = Vec::new();
+    for id in [StringId::TYPOGRAPHIC_FAMILY_NAME, StringId::FAMILY_NAME] {
+        for name in font.localized_strings(id) {
+            let name = name.chars().collect::<String>().to_lowercase();
+            if !families.contains(&name) {
+                families.push(name);
+            }
+        }
+    }
+
+    let attributes = font.attributes();
+    Face {
+        source,
+        index,
+        families,
+        weight: attributes.weight.value(),
+        style: match attributes.style {
+            Style::Normal => FontStyle::Normal,
+            Style::Italic => FontStyle::Italic,
+            Style::Oblique(_) => FontStyle::Oblique,
+        },
+        stretch: attributes.stretch.percentage(),
+    }
+}
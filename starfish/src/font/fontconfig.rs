@@ -0,0 +1,164 @@
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Directories searched when no fontconfig configuration is found
+const DEFAULT_DIRS: [&str; 5] = [
+    "/usr/share/fonts",
+    "/usr/local/share/fonts",
+    "/System/Library/Fonts",
+    "/Library/Fonts",
+    "C:\\Windows\\Fonts",
+];
+
+/// Returns the font directories of the fontconfig configuration (`FONTCONFIG_FILE`, or
+/// `/etc/fonts/fonts.conf`), including the files it includes. Only the `<dir>` and `<include>`
+/// elements are read.
+pub(super) fn font_dirs() -> Vec<PathBuf> {
+    let config = env::var_os("FONTCONFIG_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/etc/fonts/fonts.conf"));
+
+    let mut dirs = Vec::new();
+    let mut visited = HashSet::new();
+    read_config(&config, &mut dirs, &mut visited);
+
+    if dirs.is_empty() {
+        dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from));
+        dirs.extend(home().map(|home| home.join(".fonts")));
+        dirs.push(xdg_data_home().join("fonts"));
+    }
+
+    let mut seen = HashSet::new();
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
+
+fn read_config(path: &Path, dirs: &mut Vec<PathBuf>, visited: &mut HashSet<PathBuf>) {
+    if !visited.insert(path.to_path_buf()) {
+        return;
+    }
+
+    if path.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        let mut files = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| file.extension().is_some_and(|ext| ext == "conf"))
+            .collect::<Vec<_>>();
+        files.sort();
+        for file in files {
+            read_config(&file, dirs, visited);
+        }
+        return;
+    }
+
+    let Ok(contents) = fs::read_to_string(path) else {
+        return;
+    };
+    let base = path.parent().unwrap_or(Path::new("/"));
+    let contents = strip_comments(&contents);
+
+    for (element, prefix, value) in elements(&contents, &["dir", "include"]) {
+        let resolved = resolve(value.trim(), prefix.as_deref(), base, element == "include");
+        match element {
+            "dir" => dirs.push(resolved),
+            _ => read_config(&resolved, dirs, visited),
+        }
+    }
+}
+
+/// Resolves the path of a `<dir>` or `<include>` element with its `prefix` attribute
+fn resolve(value: &str, prefix: Option<&str>, base: &Path, config: bool) -> PathBuf {
+    if let Some(rest) = value.strip_prefix("~/") {
+        return home().unwrap_or_default().join(rest);
+    }
+
+    match prefix {
+        Some("xdg") if config => xdg_config_home().join(value),
+        Some("xdg") => xdg_data_home().join(value),
+        _ if Path::new(value).is_absolute() => PathBuf::from(value),
+        // Relative includes are relative to the configuration file, relative directories to the
+        // working directory unless the prefix says otherwise
+        Some("relative") => base.join(value),
+        _ if config => base.join(value),
+        _ => PathBuf::from(value),
+    }
+}
+
+fn strip_comments(contents: &str) -> String {
+    let mut result = String::with_capacity(contents.len());
+    let mut rest = contents;
+
+    while let Some(start) = rest.find("<!--") {
+        result.push_str(&rest[..start]);
+        rest = match rest[start..].find("-->") {
+            Some(end) => &rest[start + end + 3..],
+            None => "",
+        };
+    }
+    result.push_str(rest);
+
+    result
+}
+
+/// Finds the elements with the given names, and returns their name, `prefix` attribute and text
+fn elements<'a>(contents: &'a str, names: &[&'a str]) -> Vec<(&'a str, Option<String>, &'a str)> {
+    let mut found = Vec::new();
+    let mut rest = contents;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(name) = names
+            .iter()
+            .find(|name| rest.starts_with(**name) && rest[name.len()..].starts_with([' ', '>', '\t', '\n']))
+        else {
+            continue;
+        };
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let attributes = &rest[name.len()..tag_end];
+        if attributes.ends_with('/') {
+            continue;
+        }
+
+        let close = format!("</{name}>");
+        let Some(text_end) = rest[tag_end..].find(&close) else {
+            break;
+        };
+        let text = &rest[tag_end + 1..tag_end + text_end];
+        found.push((*name, attribute(attributes, "prefix"), text));
+        rest = &rest[tag_end + text_end..];
+    }
+
+    found
+}
+
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let start = attributes.find(&format!("{name}="))? + name.len() + 1;
+    let quote = attributes[start..].chars().next()?;
+    let value = &attributes[start + 1..];
+    let end = value.find(quote)?;
+
+    Some(value[..end].to_string())
+}
+
+fn home() -> Option<PathBuf> {
+    env::var_os("HOME").map(PathBuf::from)
+}
+
+fn xdg_data_home() -> PathBuf {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home().unwrap_or_default().join(".local/share"))
+}
+
+fn xdg_config_home() -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home().unwrap_or_default().join(".config"))
+}
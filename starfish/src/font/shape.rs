@@ -0,0 +1,388 @@
+use icu_properties::{maps, GeneralCategory};
+use rustybuzz::{Direction, Face, UnicodeBuffer};
+use skrifa::{
+    instance::{LocationRef, Size},
+    MetadataProvider,
+};
+
+use crate::font::{Font, FontDatabase, FontQuery, Glyph};
+
+/// The style of a piece of text
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    pub font: FontQuery,
+    /// Font size in pixels
+    pub size: f32,
+    /// Space added after each character
+    pub letter_spacing: f32,
+    /// Space added to each space character
+    pub word_spacing: f32,
+    /// Width of a tab as a number of spaces (`tab-size`)
+    pub tab_size: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font: FontQuery::default(),
+            size: 16.0,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            tab_size: 8.0,
+        }
+    }
+}
+
+/// Vertical metrics of the first available font of a style, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// Distance from the baseline to the top of the em box
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the em box
+    pub descent: f32,
+    /// Recommended space between lines
+    pub line_gap: f32,
+    pub x_height: f32,
+}
+
+/// Glyphs of a single font, with positions relative to the start of the text
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphRun {
+    pub font: Font,
+    /// Font size in pixels
+    pub size: f32,
+    pub glyphs: Vec<Glyph>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShapedText {
+    pub runs: Vec<GlyphRun>,
+    /// Advance of each character, including letter and word spacing. The advance of a cluster, like a
+    /// ligature, belongs to its first character.
+    pub advances: Vec<f32>,
+    pub width: f32,
+}
+
+impl FontDatabase {
+    pub fn metrics(&self, style: &TextStyle) -> FontMetrics {
+        let primary = self.query(&style.font).into_iter().next();
+        let metrics = primary
+            .as_ref()
+            .and_then(|font| font.font_ref())
+            .map(|font| font.metrics(Size::new(style.size), LocationRef::default()));
+
+        match metrics {
+            Some(metrics) => FontMetrics {
+                ascent: metrics.ascent,
+                descent: -metrics.descent,
+                line_gap: metrics.leading,
+                x_height: metrics.x_height.unwrap_or(style.size * 0.5),
+            },
+            // Without any font, the metrics are approximated like the `ex` unit
+            None => FontMetrics {
+                ascent: style.size * 0.8,
+                descent: style.size * 0.2,
+                line_gap: 0.0,
+                x_height: style.size * 0.5,
+            },
+        }
+    }
+
+    /// Converts text into positioned glyphs. Each character is drawn with the first font of the
+    /// style that has a glyph for it, or with a fallback font, and each run of characters with the
+    /// same font is shaped with its OpenType layout features, like kerning and ligatures. Tabs
+    /// advance by a fixed width. Right to left text is shaped from right to left, with mirrored
+    /// characters like brackets, and its glyphs are in visual order.
+    pub fn shape(&self, text: &str, style: &TextStyle, rtl: bool) -> ShapedText {
+        let primary = self.query(&style.font);
+        let charmaps = primary
+            .iter()
+            .map(|font| font.font_ref().map(|font| font.charmap()))
+            .collect::<Vec<_>>();
+
+        // The font of each character, as an index in `fonts`. Characters that have no glyph
+        // anywhere are drawn with the missing glyph of the first font.
+        let mut fonts = primary.clone();
+        let choices = text
+            .chars()
+            .map(|c| {
+                if !has_glyph(c) {
+                    return None;
+                }
+                let supported = charmaps.iter().position(|charmap| {
+                    charmap
+                        .as_ref()
+                        .and_then(|charmap| charmap.map(c))
+                        .is_some_and(|glyph| glyph.to_u32() != 0)
+                });
+                if supported.is_some() {
+                    return supported;
+                }
+
+                match self.fallback(c, &style.font) {
+                    Some(font) => Some(fonts.iter().position(|known| *known == font).unwrap_or_else(|| {
+                        fonts.push(font);
+                        fonts.len() - 1
+                    })),
+                    None => (!fonts.is_empty()).then_some(0),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let faces = fonts
+            .iter()
+            .map(|font| Face::from_slice((**font.data()).as_ref(), font.index()))
+            .collect::<Vec<_>>();
+
+        let space = faces
+            .first()
+            .and_then(Option::as_ref)
+            .and_then(|face| {
+                let advance = face.glyph_hor_advance(face.glyph_index(' ')?)?;
+                Some(advance as f32 * style.size / face.units_per_em() as f32)
+            })
+            .unwrap_or(style.size * 0.25);
+
+        // Shape each run of characters with the same font. The advance of a cluster, like a
+        // ligature or a character with combining marks, belongs to its first character.
+        let mut segments = Vec::new();
+        let mut runs = text.char_indices().map(|(offset, _)| offset).zip(choices).peekable();
+        while let Some((start, choice)) = runs.next() {
+            let mut end = text.len();
+            while let Some((offset, next)) = runs.peek() {
+                if *next != choice {
+                    end = *offset;
+                    break;
+                }
+                runs.next();
+            }
+            segments.push((start, end, choice));
+        }
+        if rtl {
+            segments.reverse();
+        }
+
+        let mut glyphs = Vec::new();
+        let mut cluster_advances = vec![0.0; text.len()];
+        for (start, end, choice) in segments {
+            let Some((font, face)) = choice.and_then(|font| Some((font, faces[font].as_ref()?))) else {
+                continue;
+            };
+            let mut buffer = UnicodeBuffer::new();
+            buffer.push_str(&text[start..end]);
+            buffer.guess_segment_properties();
+            buffer.set_direction(match rtl {
+                true => Direction::RightToLeft,
+                false => Direction::LeftToRight,
+            });
+            let output = rustybuzz::shape(face, &[], buffer);
+
+            let scale = style.size / face.units_per_em() as f32;
+            for (info, position) in output.glyph_infos().iter().zip(output.glyph_positions()) {
+                let cluster = start + info.cluster as usize;
+                let advance = position.x_advance as f32 * scale;
+                cluster_advances[cluster] += advance;
+                let offset = (position.x_offset as f32 * scale, -position.y_offset as f32 * scale);
+                glyphs.push((font, info.glyph_id, cluster, advance, offset));
+            }
+        }
+
+        let mut shaped = ShapedText::default();
+        // Start and advance (with spacing) of each character in logical order
+        let mut extents = vec![(0.0, 0.0); text.len()];
+        for (offset, c) in text.char_indices() {
+            let mut advance = match c {
+                '\t' => space * style.tab_size,
+                _ => cluster_advances[offset],
+            };
+            if advance > 0.0 {
+                advance += style.letter_spacing;
+            }
+            if matches!(c, ' ' | '\u{a0}') {
+                advance += style.word_spacing;
+            }
+
+            extents[offset] = (shaped.width, advance);
+            shaped.advances.push(advance);
+            shaped.width += advance;
+        }
+
+        // Glyphs of a cluster are placed one after the other from the left of the cluster, which is
+        // its start in left to right text and its end in right to left text
+        let mut pen = (usize::MAX, 0.0);
+        for (font, id, cluster, advance, (x, y)) in glyphs {
+            if pen.0 != cluster {
+                let (start, width) = extents[cluster];
+                let left = match rtl {
+                    true => shaped.width - start - width,
+                    false => start,
+                };
+                pen = (cluster, left);
+            }
+            let glyph = Glyph {
+                id,
+                x: pen.1 + x,
+                y,
+                advance,
+                cluster,
+            };
+            pen.1 += advance;
+
+            match shaped.runs.last_mut() {
+                Some(run) if run.font == fonts[font] => run.glyphs.push(glyph),
+                _ => shaped.runs.push(GlyphRun {
+                    font: fonts[font].clone(),
+                    size: style.size,
+                    glyphs: vec![glyph],
+                }),
+            }
+        }
+
+        shaped
+    }
+}
+
+/// Returns false for characters that are not drawn, like control and format characters
+fn has_glyph(c: char) -> bool {
+    !matches!(
+        maps::general_category().get(c),
+        GeneralCategory::Control | GeneralCategory::Format | GeneralCategory::LineSeparator
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::FontFamily;
+
+    fn style(family: &str) -> TextStyle {
+        TextStyle {
+            font: FontQuery {
+                families: vec![FontFamily::Named(family.to_string())],
+                ..FontQuery::default()
+            },
+            size: 20.0,
+            ..TextStyle::default()
+        }
+    }
+
+    fn glyphs(shaped: &ShapedText) -> Vec<(usize, f32)> {
+        shaped
+            .runs
+            .iter()
+            .flat_map(|run| run.glyphs.iter().map(|glyph| (glyph.cluster, glyph.x)))
+            .collect()
+    }
+
+    #[test]
+    fn kerning() {
+        let fonts = FontDatabase::bundled();
+        let style = style("DejaVu Sans");
+
+        let a = fonts.shape("A", &style, false).width;
+        let v = fonts.shape("V", &style, false).width;
+        let shaped = fonts.shape("AV", &style, false);
+        assert!(shaped.width < a + v);
+        assert_eq!(shaped.advances[1], v);
+        assert_eq!(glyphs(&shaped), [(0, 0.0), (1, shaped.advances[0])]);
+    }
+
+    #[test]
+    fn clusters() {
+        let fonts = FontDatabase::bundled();
+        let style = style("DejaVu Sans");
+
+        // A ligature is a single glyph, whose advance belongs to the first character
+        let shaped = fonts.shape("ffi", &style, false);
+        assert_eq!(glyphs(&shaped), [(0, 0.0)]);
+        assert_eq!(shaped.advances, [shaped.width, 0.0, 0.0]);
+
+        // Combining marks are composed with their base, or placed on it
+        let shaped = fonts.shape("e\u{301}", &style, false);
+        assert_eq!(glyphs(&shaped).len(), 1);
+        let shaped = fonts.shape("x\u{301}y", &style, false);
+        let glyphs = glyphs(&shaped);
+        assert_eq!(
+            glyphs.iter().map(|(cluster, _)| *cluster).collect::<Vec<_>>(),
+            [0, 0, 3]
+        );
+        assert!(glyphs[1].1 > 0.0 && glyphs[1].1 < shaped.advances[0]);
+        assert_eq!(shaped.advances[1], 0.0);
+        assert_eq!(glyphs[2].1, shaped.advances[0]);
+    }
+
+    #[test]
+    fn spacing() {
+        let fonts = FontDatabase::bundled();
+        let plain = fonts.shape("a b\tfi", &style("DejaVu Sans"), false);
+        let shaped = fonts.shape(
+            "a b\tfi",
+            &TextStyle {
+                letter_spacing: 1.0,
+                word_spacing: 2.0,
+                ..style("DejaVu Sans")
+            },
+            false,
+        );
+
+        // Letter spacing is added once per cluster, and tabs are as wide as 8 spaces
+        let space = plain.advances[1];
+        assert_eq!(plain.advances[3], space * 8.0);
+        assert_eq!(shaped.advances[0], plain.advances[0] + 1.0);
+        assert_eq!(shaped.advances[1], space + 3.0);
+        assert_eq!(shaped.advances[4..], [plain.advances[4] + 1.0, 0.0]);
+        assert_eq!(shaped.width, shaped.advances.iter().sum::<f32>());
+        // The tab has no glyph
+        assert_eq!(
+            glyphs(&shaped).iter().map(|(cluster, _)| *cluster).collect::<Vec<_>>(),
+            [0, 1, 2, 4]
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        let fonts = FontDatabase::bundled();
+        // DejaVu Sans Mono has no Hebrew letters
+        let shaped = fonts.shape("a\u{5d0}", &style("DejaVu Sans Mono"), false);
+        assert_eq!(shaped.runs.len(), 2);
+        assert_eq!(shaped.runs[1].glyphs[0].x, shaped.advances[0]);
+    }
+
+    #[test]
+    fn right_to_left() {
+        let fonts = FontDatabase::bundled();
+        let sans = style("DejaVu Sans");
+
+        // Glyphs are in visual order, and the first character is on the right
+        let ltr = fonts.shape("ab", &sans, false);
+        let shaped = fonts.shape("ab", &sans, true);
+        assert_eq!(shaped.advances, ltr.advances);
+        assert_eq!(glyphs(&shaped), [(1, 0.0), (0, ltr.advances[1])]);
+
+        // Letter and word spacing is part of the advance of each cluster
+        let spaced = TextStyle {
+            letter_spacing: 1.0,
+            word_spacing: 2.0,
+            ..style("DejaVu Sans")
+        };
+        let shaped = fonts.shape("a b", &spaced, true);
+        let advances = &shaped.advances;
+        assert_eq!(
+            glyphs(&shaped),
+            [(2, 0.0), (1, advances[2]), (0, advances[2] + advances[1])]
+        );
+        assert_eq!(shaped.width, advances[0] + advances[1] + advances[2]);
+
+        // Brackets are mirrored
+        let open = fonts.shape("(", &sans, false).runs[0].glyphs[0].id;
+        let close = fonts.shape(")", &sans, false).runs[0].glyphs[0].id;
+        assert_eq!(fonts.shape("(", &sans, true).runs[0].glyphs[0].id, close);
+        assert_eq!(fonts.shape(")", &sans, true).runs[0].glyphs[0].id, open);
+
+        // Runs of different fonts are in visual order too
+        let shaped = fonts.shape("\u{5d0}a", &style("DejaVu Sans Mono"), true);
+        assert_eq!(shaped.runs.len(), 2);
+        assert_eq!(shaped.runs[0].glyphs[0].cluster, 2);
+        assert_eq!(shaped.runs[1].glyphs[0].x, shaped.advances[1]);
+    }
+}
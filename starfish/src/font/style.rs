@@ -0,0 +1,96 @@
+use crate::{
+    font::{FontFamily, FontQuery, FontStyle, TextStyle},
+    interface::css3::{CssProperty, CssPropertyMap},
+};
+
+/// Converts the computed font and spacing properties of a box into the style of its text
+pub fn text_style<M: CssPropertyMap>(properties: &M) -> TextStyle {
+    let px = |name: &str| properties.get(name).and_then(|property| property.as_px());
+    let defaults = TextStyle::default();
+
+    TextStyle {
+        font: FontQuery {
+            families: properties
+                .get("font-family")
+                .map(font_families)
+                .filter(|families| !families.is_empty())
+                .unwrap_or(defaults.font.families),
+            weight: properties.get("font-weight").map_or(400.0, font_weight),
+            style: properties.get("font-style").map_or(FontStyle::Normal, font_style),
+            stretch: properties.get("font-stretch").map_or(100.0, font_stretch),
+        },
+        size: px("font-size").unwrap_or(defaults.size),
+        letter_spacing: px("letter-spacing").unwrap_or_default(),
+        word_spacing: px("word-spacing").unwrap_or_default(),
+        // Tab sizes given as a length are not supported
+        tab_size: properties
+            .get("tab-size")
+            .and_then(|property| property.as_number())
+            .unwrap_or(defaults.tab_size),
+    }
+}
+
+fn font_families<P: CssProperty>(property: &P) -> Vec<FontFamily> {
+    property
+        .as_comma_list()
+        .iter()
+        .filter_map(|family| {
+            if let Some(name) = family.as_string() {
+                return Some(FontFamily::Named(name.to_string()));
+            }
+            if let Some(keyword) = family.as_keyword() {
+                return Some(FontFamily::from_keyword(keyword));
+            }
+
+            // Unquoted names of more than one word are joined with single spaces
+            let words = family.as_list()?;
+            let words = words.iter().map(|word| word.as_keyword()).collect::<Option<Vec<_>>>()?;
+            Some(FontFamily::Named(words.join(" ")))
+        })
+        .collect()
+}
+
+fn font_weight<P: CssProperty>(property: &P) -> f32 {
+    if let Some(weight) = property.as_number() {
+        return weight.clamp(1.0, 1000.0);
+    }
+
+    // Relative weights are not resolved against the parent, so they map to fixed weights
+    match property.as_keyword() {
+        Some("bold" | "bolder") => 700.0,
+        Some("lighter") => 100.0,
+        _ => 400.0,
+    }
+}
+
+fn font_style<P: CssProperty>(property: &P) -> FontStyle {
+    let keyword = match property.as_list() {
+        // `oblique` can be followed by an angle
+        Some(values) => values.first().and_then(|value| value.as_keyword().map(str::to_string)),
+        None => property.as_keyword().map(str::to_string),
+    };
+
+    match keyword.as_deref() {
+        Some("italic") => FontStyle::Italic,
+        Some("oblique") => FontStyle::Oblique,
+        _ => FontStyle::Normal,
+    }
+}
+
+fn font_stretch<P: CssProperty>(property: &P) -> f32 {
+    if let Some(pct) = property.as_percentage() {
+        return pct;
+    }
+
+    match property.as_keyword().unwrap_or_default() {
+        "ultra-condensed" => 50.0,
+        "extra-condensed" => 62.5,
+        "condensed" => 75.0,
+        "semi-condensed" => 87.5,
+        "semi-expanded" => 112.5,
+        "expanded" => 125.0,
+        "extra-expanded" => 150.0,
+        "ultra-expanded" => 200.0,
+        _ => 100.0,
+    }
+}
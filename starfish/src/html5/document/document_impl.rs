@@ -93,6 +93,10 @@ impl<C: HasDocument<Document = Self>> Document<C> for DocumentImpl<C> {
         self.stylesheets.push(stylesheet);
     }
 
+    fn stylesheets(&self) -> &[C::Stylesheet] {
+        &self.stylesheets
+    }
+
     fn get_root(&self) -> &Self::Node {
         self.arena.node_ref(NodeId::root()).expect("Root node not found !?")
     }
@@ -51,6 +51,11 @@ impl ClassListImpl {
             class_map: HashMap::new(),
         }
     }
+
+    /// Returns true when the given class is present and active
+    pub fn contains(&self, name: &str) -> bool {
+        self.class_map.get(name).copied().unwrap_or(false)
+    }
 }
 
 impl From<&str> for ClassListImpl {
@@ -100,6 +105,18 @@ impl<C: HasDocument> ElementDataType<C> for ElementData<C> {
             None => HTML_NAMESPACE,
         }
     }
+
+    fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(|value| value.as_str())
+    }
+
+    fn has_class(&self, name: &str) -> bool {
+        self.class_list.contains(name)
+    }
 }
 
 impl<C: HasDocument> ElementData<C> {
@@ -74,6 +74,10 @@ impl<C: HasDocument<Document = DocumentImpl<C>>> Node<C> for NodeImpl<C> {
         self.registered
     }
 
+    fn parent_id(&self) -> Option<NodeId> {
+        self.parent
+    }
+
     fn children(&self) -> &[NodeId] {
         self.children.as_slice()
     }
@@ -106,6 +110,13 @@ impl<C: HasDocument<Document = DocumentImpl<C>>> Node<C> for NodeImpl<C> {
         None
     }
 
+    fn text_value(&self) -> Option<&str> {
+        if let NodeDataTypeInternal::Text(data) = &self.data {
+            return Some(&data.value);
+        }
+        None
+    }
+
     fn insert(&mut self, node_id: NodeId, idx: usize) {
         self.children.insert(idx, node_id);
     }
@@ -76,6 +76,9 @@ pub struct Tokenizer<'tokens> {
     pub state: State,
     pub consumed: String,
     pub current_token: Option<Token>,
+    /// Name and value of the attribute that is read, which is added to the current tag when the
+    /// next attribute starts or the tag is emitted
+    current_attribute: Option<(String, String)>,
     pub token_queue: Vec<Token>,
     pub last_start_token: String,
     pub last_token_location: Location,
@@ -97,6 +100,7 @@ impl<'stream> Tokenizer<'stream> {
             state: opts.as_ref().map_or(State::Data, |o| o.initial_state),
             consumed: String::new(),
             current_token: None,
+            current_attribute: None,
             token_queue: vec![],
             last_start_token: opts.map_or(String::new(), |o| o.last_start_tag),
             last_token_location: Location::default(),
@@ -258,6 +262,179 @@ impl<'stream> Tokenizer<'stream> {
                         _ => self.add_to_token_name(c.into()),
                     }
                 }
+                State::BeforeAttributeName => {
+                    let loc = self.get_location();
+                    let c = self.read_char();
+                    match c {
+                        Ch(CHAR_TAB | CHAR_LF | CHAR_FF | CHAR_SPACE) => {}
+                        Ch('/' | '>') | StreamEnd => {
+                            self.stream_prev();
+                            self.state = State::AfterAttributeName;
+                        }
+                        Ch('=') => {
+                            self.parse_error(ParserError::UnexpectedEqualsSignBeforeAttributeName, loc);
+                            self.start_attribute("=");
+                            self.state = State::AttributeName;
+                        }
+                        _ => {
+                            self.start_attribute("");
+                            self.stream_prev();
+                            self.state = State::AttributeName;
+                        }
+                    }
+                }
+                State::AttributeName => {
+                    let loc = self.get_location();
+                    let c = self.read_char();
+                    match c {
+                        Ch(CHAR_TAB | CHAR_LF | CHAR_FF | CHAR_SPACE | '/' | '>') | StreamEnd => {
+                            self.stream_prev();
+                            self.state = State::AfterAttributeName;
+                        }
+                        Ch('=') => self.state = State::BeforeAttributeValue,
+                        Ch(ch @ 'A'..='Z') => self.add_to_attribute_name(to_lowercase!(ch)),
+                        Ch(CHAR_NUL) => {
+                            self.parse_error(ParserError::UnexpectedNullCharacter, loc);
+                            self.add_to_attribute_name(CHAR_REPLACEMENT);
+                        }
+                        Ch(ch @ ('"' | '\'' | '<')) => {
+                            self.parse_error(ParserError::UnexpectedCharacterInAttributeName, loc);
+                            self.add_to_attribute_name(ch);
+                        }
+                        _ => self.add_to_attribute_name(c.into()),
+                    }
+                }
+                State::AfterAttributeName => {
+                    let loc = self.get_location();
+                    let c = self.read_char();
+                    match c {
+                        Ch(CHAR_TAB | CHAR_LF | CHAR_FF | CHAR_SPACE) => {}
+                        Ch('/') => self.state = State::SelfClosingStart,
+                        Ch('=') => self.state = State::BeforeAttributeValue,
+                        Ch('>') => {
+                            self.emit_current_token();
+                            self.state = State::Data;
+                        }
+                        StreamEnd => {
+                            self.parse_error(ParserError::EofInTag, loc);
+                            self.state = State::Data;
+                        }
+                        _ => {
+                            self.start_attribute("");
+                            self.stream_prev();
+                            self.state = State::AttributeName;
+                        }
+                    }
+                }
+                State::BeforeAttributeValue => {
+                    let loc = self.get_location();
+                    let c = self.read_char();
+                    match c {
+                        Ch(CHAR_TAB | CHAR_LF | CHAR_FF | CHAR_SPACE) => {}
+                        Ch('"') => self.state = State::AttributeValueDoubleQuoted,
+                        Ch('\'') => self.state = State::AttributeValueSingleQuoted,
+                        Ch('>') => {
+                            self.parse_error(ParserError::MissingAttributeValue, loc);
+                            self.emit_current_token();
+                            self.state = State::Data;
+                        }
+                        _ => {
+                            self.stream_prev();
+                            self.state = State::AttributeValueUnquoted;
+                        }
+                    }
+                }
+                // Character references in attribute values are kept as they are
+                State::AttributeValueDoubleQuoted | State::AttributeValueSingleQuoted => {
+                    let loc = self.get_location();
+                    let quote = match self.state {
+                        State::AttributeValueDoubleQuoted => '"',
+                        _ => '\'',
+                    };
+                    let c = self.read_char();
+                    match c {
+                        Ch(ch) if ch == quote => self.state = State::AfterAttributeValueQuoted,
+                        Ch(CHAR_NUL) => {
+                            self.parse_error(ParserError::UnexpectedNullCharacter, loc);
+                            self.add_to_attribute_value(CHAR_REPLACEMENT);
+                        }
+                        StreamEnd => {
+                            self.parse_error(ParserError::EofInTag, loc);
+                            self.state = State::Data;
+                        }
+                        _ => self.add_to_attribute_value(c.into()),
+                    }
+                }
+                State::AttributeValueUnquoted => {
+                    let loc = self.get_location();
+                    let c = self.read_char();
+                    match c {
+                        Ch(CHAR_TAB | CHAR_LF | CHAR_FF | CHAR_SPACE) => self.state = State::BeforeAttributeName,
+                        Ch('>') => {
+                            self.emit_current_token();
+                            self.state = State::Data;
+                        }
+                        Ch(CHAR_NUL) => {
+                            self.parse_error(ParserError::UnexpectedNullCharacter, loc);
+                            self.add_to_attribute_value(CHAR_REPLACEMENT);
+                        }
+                        Ch(ch @ ('"' | '\'' | '<' | '=' | '`')) => {
+                            self.parse_error(ParserError::UnexpectedCharacterInUnquotedAttributeValue, loc);
+                            self.add_to_attribute_value(ch);
+                        }
+                        StreamEnd => {
+                            self.parse_error(ParserError::EofInTag, loc);
+                            self.state = State::Data;
+                        }
+                        _ => self.add_to_attribute_value(c.into()),
+                    }
+                }
+                State::AfterAttributeValueQuoted => {
+                    let loc = self.get_location();
+                    let c = self.read_char();
+                    match c {
+                        Ch(CHAR_TAB | CHAR_LF | CHAR_FF | CHAR_SPACE) => self.state = State::BeforeAttributeName,
+                        Ch('/') => self.state = State::SelfClosingStart,
+                        Ch('>') => {
+                            self.emit_current_token();
+                            self.state = State::Data;
+                        }
+                        StreamEnd => {
+                            self.parse_error(ParserError::EofInTag, loc);
+                            self.state = State::Data;
+                        }
+                        _ => {
+                            self.parse_error(ParserError::MissingWhitespaceBetweenAttributes, loc);
+                            self.stream_prev();
+                            self.state = State::BeforeAttributeName;
+                        }
+                    }
+                }
+                State::SelfClosingStart => {
+                    let loc = self.get_location();
+                    let c = self.read_char();
+                    match c {
+                        Ch('>') => {
+                            if let Some(
+                                Token::StartTag { is_self_closing, .. } | Token::EndTag { is_self_closing, .. },
+                            ) = &mut self.current_token
+                            {
+                                *is_self_closing = true;
+                            }
+                            self.emit_current_token();
+                            self.state = State::Data;
+                        }
+                        StreamEnd => {
+                            self.parse_error(ParserError::EofInTag, loc);
+                            self.state = State::Data;
+                        }
+                        _ => {
+                            self.parse_error(ParserError::UnexpectedSolidusInTag, loc);
+                            self.stream_prev();
+                            self.state = State::BeforeAttributeName;
+                        }
+                    }
+                }
                 State::MarkupDeclarationOpen => {
                     if Character::slice_to_string(self.stream.get_slice(2)) == "--" {
                         self.current_token = Some(Token::Comment {
@@ -457,7 +634,50 @@ impl<'stream> Tokenizer<'stream> {
         }
     }
 
+    /// Starts a new attribute of the current tag, after adding the attribute before it
+    fn start_attribute(&mut self, name: &str) {
+        self.store_attribute();
+        self.current_attribute = Some((name.to_string(), String::new()));
+    }
+
+    fn add_to_attribute_name(&mut self, c: char) {
+        if let Some((name, _)) = &mut self.current_attribute {
+            name.push(c);
+        }
+    }
+
+    fn add_to_attribute_value(&mut self, c: char) {
+        if let Some((_, value)) = &mut self.current_attribute {
+            value.push(c);
+        }
+    }
+
+    /// Adds the current attribute to the current tag. When the tag already has an attribute with
+    /// the same name, the first one is kept. End tags have no attributes.
+    fn store_attribute(&mut self) {
+        let Some((name, value)) = self.current_attribute.take() else {
+            return;
+        };
+
+        let error = match &mut self.current_token {
+            Some(Token::StartTag { attributes, .. }) if attributes.contains_key(&name) => {
+                Some(ParserError::DuplicateAttribute)
+            }
+            Some(Token::StartTag { attributes, .. }) => {
+                attributes.insert(name, value);
+                None
+            }
+            Some(Token::EndTag { .. }) => Some(ParserError::EndTagWithAttributes),
+            _ => None,
+        };
+        if let Some(error) = error {
+            let loc = self.get_location();
+            self.parse_error(error, loc);
+        }
+    }
+
     fn emit_current_token(&mut self) {
+        self.store_attribute();
         if let Some(t) = self.current_token.take() {
             self.emit_token(t);
         }
@@ -576,3 +796,132 @@ impl<'stream> Tokenizer<'stream> {
         (0x0001..=0x001F).contains(&num) || (0x007F..=0x009F).contains(&num)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::byte_stream::Encoding;
+
+    /// Returns the tokens of the given HTML up to the end of the stream, and the names of the parse
+    /// errors
+    fn tokenize(html: &str) -> (Vec<Token>, Vec<String>) {
+        let mut stream = ByteStream::new(Encoding::UTF8, None);
+        stream.read_from_str(html, Some(Encoding::UTF8));
+        stream.close();
+
+        let error_logger = Rc::new(RefCell::new(ErrorLogger::new()));
+        let mut tokenizer = Tokenizer::new(&mut stream, None, error_logger.clone(), Location::default());
+
+        let mut tokens = Vec::new();
+        loop {
+            let token = tokenizer.next_token(ParserData::default()).unwrap();
+            if token.is_eof() {
+                break;
+            }
+            tokens.push(token);
+        }
+
+        let errors = error_logger
+            .borrow()
+            .get_errors()
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        (tokens, errors)
+    }
+
+    fn start_tag(html: &str) -> (String, bool, HashMap<String, String>) {
+        let (tokens, _) = tokenize(html);
+        match tokens.into_iter().next() {
+            Some(Token::StartTag {
+                name,
+                is_self_closing,
+                attributes,
+                ..
+            }) => (name, is_self_closing, attributes),
+            token => panic!("expected a start tag, got {token:?}"),
+        }
+    }
+
+    fn attributes(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn attribute_values() {
+        let (name, _, attrs) = start_tag(r#"<link rel="stylesheet" href='a.css' media=screen>"#);
+        assert_eq!(name, "link");
+        assert_eq!(
+            attrs,
+            attributes(&[("rel", "stylesheet"), ("href", "a.css"), ("media", "screen")])
+        );
+
+        let (_, _, attrs) = start_tag(r#"<td colspan = "2" ROWSPAN=3 hidden dir="rtl">"#);
+        assert_eq!(
+            attrs,
+            attributes(&[("colspan", "2"), ("rowspan", "3"), ("hidden", ""), ("dir", "rtl")])
+        );
+
+        let (_, _, attrs) = start_tag(r#"<p title="a > b" class='x "y"'>"#);
+        assert_eq!(attrs, attributes(&[("title", "a > b"), ("class", r#"x "y""#)]));
+    }
+
+    #[test]
+    fn self_closing_tags() {
+        let (name, self_closing, attrs) = start_tag(r#"<img src="a.png"/>"#);
+        assert_eq!(name, "img");
+        assert!(self_closing);
+        assert_eq!(attrs, attributes(&[("src", "a.png")]));
+
+        let (_, self_closing, attrs) = start_tag("<br/>");
+        assert!(self_closing);
+        assert!(attrs.is_empty());
+
+        // The solidus is part of an unquoted value
+        let (_, self_closing, attrs) = start_tag("<a href=/x/>");
+        assert!(!self_closing);
+        assert_eq!(attrs, attributes(&[("href", "/x/")]));
+    }
+
+    #[test]
+    fn tokens_around_tags_with_attributes() {
+        let (tokens, errors) = tokenize(r#"<p class="a">text</p><br>"#);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(tokens.len(), 4);
+        assert!(matches!(&tokens[0], Token::StartTag { name, attributes, .. } if name == "p" && attributes.len() == 1));
+        assert!(matches!(&tokens[1], Token::Text { text, .. } if text == "text"));
+        assert!(matches!(&tokens[2], Token::EndTag { name, .. } if name == "p"));
+        assert!(
+            matches!(&tokens[3], Token::StartTag { name, attributes, .. } if name == "br" && attributes.is_empty())
+        );
+    }
+
+    #[test]
+    fn attribute_errors() {
+        // The first of duplicate attributes is kept
+        let (tokens, errors) = tokenize(r#"<p id="a" id="b">"#);
+        assert!(matches!(&tokens[0], Token::StartTag { attributes, .. } if attributes["id"] == "a"));
+        assert_eq!(errors, ["duplicate-attribute"]);
+
+        let (tokens, errors) = tokenize(r#"</p class="a">"#);
+        assert!(matches!(&tokens[0], Token::EndTag { name, .. } if name == "p"));
+        assert_eq!(errors, ["end-tag-with-attributes"]);
+
+        let (_, errors) = tokenize(r#"<p a="1"b="2">"#);
+        assert_eq!(errors, ["missing-whitespace-between-attributes"]);
+
+        let (_, errors) = tokenize("<p a=>");
+        assert_eq!(errors, ["missing-attribute-value"]);
+
+        let (_, errors) = tokenize("<p a=x\"y>");
+        assert_eq!(errors, ["unexpected-character-in-unquoted-attribute-value"]);
+
+        // A tag that is not closed before the end of the stream is dropped
+        let (tokens, errors) = tokenize(r#"<p a="1"#);
+        assert!(tokens.is_empty());
+        assert_eq!(errors, ["eof-in-tag"]);
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    fmt::Debug,
+    io::Cursor,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use anyhow::bail;
+use futures::channel::oneshot;
+
+use crate::shared::types::Result;
+
+static NEXT_IMAGE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A decoded bitmap, which is cheap to clone
+#[derive(Clone)]
+pub struct Image {
+    /// Rows of RGBA pixels with 8 bits per channel, which are not premultiplied by their alpha
+    data: Arc<Vec<u8>>,
+    width: u32,
+    height: u32,
+    /// Unique id of the pixels, which renderers can use to cache the image
+    id: u64,
+}
+
+impl Image {
+    pub fn new(data: Vec<u8>, width: u32, height: u32) -> Self {
+        Self {
+            data: Arc::new(data),
+            width,
+            height,
+            id: NEXT_IMAGE_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn data(&self) -> &Arc<Vec<u8>> {
+        &self.data
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Debug for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl PartialEq for Image {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+/// Decodes a PNG, JPEG, GIF, WebP or BMP file, whose format is found from its contents. Only the
+/// first frame of animated images is kept. SVG images are not supported yet.
+pub fn decode(bytes: &[u8]) -> Result<Image> {
+    if is_svg(bytes) {
+        bail!("SVG images are not supported");
+    }
+
+    let bitmap = ::image::ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .decode()?
+        .into_rgba8();
+    let (width, height) = bitmap.dimensions();
+
+    Ok(Image::new(bitmap.into_raw(), width, height))
+}
+
+/// Decodes an image on a worker thread, so large images do not hold up the caller
+pub async fn decode_on_worker(bytes: Vec<u8>) -> Result<Image> {
+    let (sender, receiver) = oneshot::channel();
+    thread::Builder::new()
+        .name("image decoder".to_string())
+        .spawn(move || {
+            // The receiver is only gone when the caller is no longer waiting for the image
+            let _ = sender.send(decode(&bytes));
+        })?;
+
+    receiver.await?
+}
+
+fn is_svg(bytes: &[u8]) -> bool {
+    let start = &bytes[..bytes.len().min(256)];
+    let text = String::from_utf8_lossy(start);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ::image::{ImageFormat, Rgba, RgbaImage};
+
+    /// Encodes a 3x2 image whose pixels are red, except for a blue one at the top left
+    fn encode(format: ImageFormat) -> Vec<u8> {
+        let mut bitmap = RgbaImage::from_pixel(3, 2, Rgba([255, 0, 0, 255]));
+        bitmap.put_pixel(0, 0, Rgba([0, 0, 255, 255]));
+        let mut bytes = Cursor::new(Vec::new());
+        bitmap.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn decode_formats() {
+        for format in [ImageFormat::Png, ImageFormat::Bmp, ImageFormat::Gif] {
+            let image = decode(&encode(format)).unwrap();
+            assert_eq!((image.width(), image.height()), (3, 2), "{format:?}");
+            assert_eq!(image.data().len(), 3 * 2 * 4);
+            assert_eq!(image.data()[..8], [0, 0, 255, 255, 255, 0, 0, 255], "{format:?}");
+        }
+
+        assert!(decode(b"not an image").is_err());
+    }
+
+    #[test]
+    fn svg_is_not_decoded() {
+        assert!(is_svg(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"));
+        assert!(is_svg(b"\xef\xbb\xbf  <?xml version=\"1.0\"?>\n<svg/>"));
+        assert!(!is_svg(b"<?xml version=\"1.0\"?><html/>"));
+        assert!(decode(b"<svg/>").is_err());
+    }
+
+    #[test]
+    fn decode_on_worker_thread() {
+        let image = futures::executor::block_on(decode_on_worker(encode(ImageFormat::Png))).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 2));
+
+        // Images are equal when they share their pixels
+        assert_eq!(image, image.clone());
+        assert_ne!(image, Image::new(image.data().to_vec(), 3, 2));
+    }
+}
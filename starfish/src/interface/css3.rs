@@ -1,7 +1,15 @@
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug, path::Path};
+
+use crate::{
+    interface::config::HasDocument,
+    shared::{byte_stream::Location, errors::CssResult, geo::SizeU32, node::NodeId, types::Result},
+};
+
+use anyhow::anyhow;
+use url::Url;
 
 /// Defines the origin of the stylesheet (or declaration)
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum CssOrigin {
     /// Browser/user agent defined stylesheets
     UserAgent,
@@ -11,12 +19,216 @@ pub enum CssOrigin {
     User,
 }
 
+/// Color scheme preferred by the user
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ColorScheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// The environment media queries are evaluated against
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MediaEnvironment {
+    /// Width of the viewport in CSS pixels
+    pub width: f32,
+    /// Height of the viewport in CSS pixels
+    pub height: f32,
+    /// Number of device pixels per CSS pixel
+    pub resolution: f32,
+    pub color_scheme: ColorScheme,
+    /// True when the user prefers less motion in animations and transitions
+    pub reduced_motion: bool,
+}
+
+impl MediaEnvironment {
+    /// Creates the environment for a viewport of the given size
+    pub fn from_size(size: SizeU32) -> Self {
+        Self {
+            width: size.width as f32,
+            height: size.height as f32,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for MediaEnvironment {
+    fn default() -> Self {
+        Self {
+            width: 1024.0,
+            height: 768.0,
+            resolution: 1.0,
+            color_scheme: ColorScheme::default(),
+            reduced_motion: false,
+        }
+    }
+}
+
+/// A pseudo element that generates a box of its own in the render tree
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PseudoElement {
+    /// `::before`, the first child of its element
+    Before,
+    /// `::after`, the last child of its element
+    After,
+    /// `::marker`, the marker box of a list item
+    Marker,
+}
+
+impl PseudoElement {
+    pub const ALL: [PseudoElement; 3] = [PseudoElement::Before, PseudoElement::After, PseudoElement::Marker];
+
+    /// Returns the name of the pseudo element as used in selectors, without the colons
+    pub fn name(self) -> &'static str {
+        match self {
+            PseudoElement::Before => "before",
+            PseudoElement::After => "after",
+            PseudoElement::Marker => "marker",
+        }
+    }
+}
+
+/// A stylesheet supplied by the user, which applies to every page with [`CssOrigin::User`]
+#[derive(Debug, PartialEq, Clone)]
+pub struct UserStylesheet {
+    pub url: Url,
+    pub css: String,
+}
+
+impl UserStylesheet {
+    /// Reads a user stylesheet from disk
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = std::fs::canonicalize(path)?;
+        let url = Url::from_file_path(&path).map_err(|_| anyhow!("Invalid stylesheet path: {}", path.display()))?;
+        let css = std::fs::read_to_string(&path)?;
+
+        Ok(Self { url, css })
+    }
+}
+
 pub trait CssSystem: Clone + Debug + 'static {
     type Stylesheet: CssStylesheet;
 
+    type PropertyMap: CssPropertyMap;
+
+    /// The declarations that apply to each element of a document, see [`CssSystem::match_styles`]
+    type MatchedStyles: Debug + Send + 'static;
+
     fn load_default_useragent_stylesheet() -> Self::Stylesheet;
+
+    /// Parses a stylesheet, like the contents of a `<style>` element or a linked file. Invalid rules
+    /// and declarations are dropped.
+    fn parse_stylesheet(css: &str, origin: CssOrigin, url: &str) -> CssResult<Self::Stylesheet>;
+
+    /// Parses the declarations of the `style` attribute of an element. The stylesheet only applies to
+    /// that element, and its declarations win from any selector.
+    fn parse_style_attribute(css: &str, url: &str, element: NodeId) -> CssResult<Self::Stylesheet>;
+
+    /// Computes the style of all elements and text nodes in the document. The computed values of all
+    /// known properties are resolved against the parent element, so each map is complete. Text nodes
+    /// only inherit from their parent.
+    fn compute_styles<C: HasDocument<CssSystem = Self>>(
+        doc: &C::Document,
+        media: &MediaEnvironment,
+    ) -> HashMap<NodeId, Self::PropertyMap> {
+        Self::compute_matched_styles(&Self::match_styles::<C>(doc), media)
+    }
+
+    /// Matches the selectors of all stylesheets against all elements of the document. Media queries
+    /// are not evaluated yet, so the styles can be computed again for another media environment
+    /// without access to the document.
+    fn match_styles<C: HasDocument<CssSystem = Self>>(doc: &C::Document) -> Self::MatchedStyles;
+
+    /// Computes the style of all elements and text nodes from their matched declarations
+    fn compute_matched_styles(
+        matched: &Self::MatchedStyles,
+        media: &MediaEnvironment,
+    ) -> HashMap<NodeId, Self::PropertyMap>;
+
+    /// Computes the style of the pseudo elements of the elements from their matched declarations,
+    /// given the computed styles of the elements they inherit from. Only the pseudo elements that
+    /// declarations apply to have a style.
+    fn compute_pseudo_element_styles(
+        matched: &Self::MatchedStyles,
+        styles: &HashMap<NodeId, Self::PropertyMap>,
+        media: &MediaEnvironment,
+    ) -> HashMap<(NodeId, PseudoElement), Self::PropertyMap>;
+
+    /// Returns true when the styles must be computed again for another media environment: when any
+    /// media query has a different result, or when the viewport that viewport units refer to is
+    /// resized
+    fn media_changed(matched: &Self::MatchedStyles, from: &MediaEnvironment, to: &MediaEnvironment) -> bool;
+
+    /// Computes the style of an anonymous box with the given display value (CSS 2.1, 9.2.1.1).
+    /// Inherited properties are inherited from the parent box, all others have their initial value.
+    fn anonymous_box_style(
+        matched: &Self::MatchedStyles,
+        parent: &Self::PropertyMap,
+        display: &str,
+    ) -> Self::PropertyMap;
+}
+
+pub trait CssStylesheet: PartialEq + Debug + Send {
+    fn origin(&self) -> CssOrigin;
+    fn url(&self) -> &str;
+    fn location(&self) -> Location;
+    /// Urls of the stylesheets imported with `@import`, as written in the stylesheet
+    fn imports(&self) -> Vec<&str>;
+    /// Places the rules of the imported stylesheets (in the order of [`CssStylesheet::imports`])
+    /// before the rules of this stylesheet. Imports that could not be loaded are None.
+    fn resolve_imports(&mut self, imported: Vec<Option<Self>>)
+    where
+        Self: Sized;
+    /// Restricts all rules to the given media query list, like the `media` attribute of the
+    /// `<style>` or `<link>` element the stylesheet comes from
+    fn restrict_media(&mut self, media: &str);
+}
+
+pub trait CssPropertyMap: Debug + Default + Clone + Send {
+    type Property: CssProperty;
+
+    fn get(&self, name: &str) -> Option<&Self::Property>;
+    fn insert(&mut self, name: &str, property: Self::Property);
+    fn iter(&self) -> impl Iterator<Item = (&str, &Self::Property)>;
 }
 
-pub trait CssStylesheet: PartialEq + Debug {
+pub trait CssProperty: Debug + Clone + PartialEq + Send {
+    fn name(&self) -> &str;
 
+    /// Returns the computed value in pixels when it is an absolute length
+    fn as_px(&self) -> Option<f32>;
+    /// Resolves the computed value into pixels when it is a length, a percentage or a math function
+    /// like `calc(100% - 2rem)`. Percentages are resolved against `percentage_basis` (normally the
+    /// size of the containing block), and cannot be resolved without it.
+    fn resolve_px(&self, percentage_basis: Option<f32>) -> Option<f32>;
+    /// Returns the computed value when it is a percentage
+    fn as_percentage(&self) -> Option<f32>;
+    /// Returns the computed value when it is a plain number
+    fn as_number(&self) -> Option<f32>;
+    /// Returns the computed value when it is a keyword
+    fn as_keyword(&self) -> Option<&str>;
+    /// Returns the specified value when it is a keyword, like the `display` of an absolutely
+    /// positioned box before it is blockified
+    fn as_specified_keyword(&self) -> Option<&str>;
+    /// Returns the computed value when it is a (quoted) string
+    fn as_string(&self) -> Option<&str>;
+    /// Returns the computed value as 8-bit RGBA when it is a color, ready for `Color::tuple4`
+    fn as_color(&self) -> Option<(u8, u8, u8, u8)>;
+    /// Returns the number and (lowercase) unit of the computed value when it is a dimension, like
+    /// `1fr`
+    fn as_dimension(&self) -> Option<(f32, &str)>;
+    /// Returns the items of the computed value when it is a space separated list
+    fn as_list(&self) -> Option<Vec<Self>>
+    where
+        Self: Sized;
+    /// Returns the name and arguments of the computed value when it is a function, like
+    /// `minmax(100px, 1fr)`. Each comma separated argument is returned as a single value.
+    fn as_function(&self) -> Option<(&str, Vec<Self>)>
+    where
+        Self: Sized;
+    /// Returns the comma separated items of the computed value, like the families of
+    /// `font-family`. Each item is returned as a single value.
+    fn as_comma_list(&self) -> Vec<Self>
+    where
+        Self: Sized;
 }
@@ -30,6 +30,7 @@ pub trait Document<C: HasDocument<Document = Self>>: Sized + Display + Debug + P
     fn node_by_id(&self, node_id: NodeId) -> Option<&Self::Node>;
 
     fn add_stylesheet(&mut self, stylesheet: C::Stylesheet);
+    fn stylesheets(&self) -> &[C::Stylesheet];
     /// Return the root node of the document
     fn get_root(&self) -> &Self::Node;
     fn attach_node(&mut self, node_id: NodeId, parent_id: NodeId, position: Option<usize>);
@@ -3,11 +3,12 @@ use std::future::Future;
 use crate::{
     interface::{
         config::HasDrawComponents,
+        css3::UserStylesheet,
         render_backend::{RenderBackend, WindowedEventLoop},
     },
     shared::{
-        geo::SizeU32,
-        types::Result,
+        geo::{FP, SizeU32},
+        types::{Point, Result},
     },
 };
 
@@ -22,7 +23,12 @@ pub trait TreeDrawer<C: HasDrawComponents>: Send + 'static {
         el: &impl WindowedEventLoop,
     ) -> bool;
 
-    fn from_url(url: Url) -> impl Future<Output = Result<Self>> + Send
+    /// Scrolls the page by the given distance, as far as its content reaches. Returns true when the
+    /// page has to be drawn again.
+    fn scroll(&mut self, delta: Point<FP>) -> bool;
+
+    /// Loads the page at the url. The user stylesheets are applied to the page.
+    fn from_url(url: Url, user_stylesheets: Vec<UserStylesheet>) -> impl Future<Output = Result<Self>>
     where
         Self: Sized;
 }
@@ -1,15 +1,31 @@
 use std::fmt::Debug;
 
-use crate::interface::config::HasLayouter;
+use crate::{
+    interface::config::HasLayouter,
+    shared::{
+        geo::{SizeU32, FP},
+        types::{Point, Sides, Size},
+    },
+};
 
 pub trait LayoutTree<C: HasLayouter<LayoutTree = Self>>: Sized + Debug + 'static {
-
+    /// Computes the position and size of all boxes in the tree for a viewport of the given size
+    fn layout(&mut self, viewport: SizeU32);
 }
 
 pub trait Layouter: Sized + Clone + Send + 'static {
     type Layout: Layout + Send;
 }
 
+/// The computed layout of a single box
 pub trait Layout: Default + Debug {
-    
+    /// Position of the border box, relative to the border box of the parent
+    fn position(&self) -> Point<FP>;
+    /// Size of the border box
+    fn size(&self) -> Size<FP>;
+    /// Size of the content of the box, which can be larger than the box when it overflows
+    fn content_size(&self) -> Size<FP>;
+    fn margin(&self) -> Sides<FP>;
+    fn border(&self) -> Sides<FP>;
+    fn padding(&self) -> Sides<FP>;
 }
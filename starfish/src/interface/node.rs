@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 use crate::{
     interface::config::HasDocument,
@@ -25,6 +25,9 @@ pub enum QuirksMode {
 pub trait ElementDataType<C: HasDocument> {
     fn name(&self) -> &str;
     fn namespace(&self) -> &str;
+    fn attributes(&self) -> &HashMap<String, String>;
+    fn attribute(&self, name: &str) -> Option<&str>;
+    fn has_class(&self, name: &str) -> bool;
 }
 
 pub trait Node<C: HasDocument>: Clone + Debug + PartialEq {
@@ -34,11 +37,14 @@ pub trait Node<C: HasDocument>: Clone + Debug + PartialEq {
     fn set_id(&mut self, id: NodeId);
     fn set_registered(&mut self, registered: bool);
     fn is_registered(&self) -> bool;
+    fn parent_id(&self) -> Option<NodeId>;
     fn children(&self) -> &[NodeId];
     fn type_of(&self) -> NodeType;
     fn is_element_node(&self) -> bool;
     fn get_element_data(&self) -> Option<&Self::ElementData>;
     fn get_element_data_mut(&mut self) -> Option<&mut Self::ElementData>;
+    /// Returns the text of a text node
+    fn text_value(&self) -> Option<&str>;
     fn insert(&mut self, node_id: NodeId, idx: usize);
     fn push(&mut self, node_id: NodeId);
 }
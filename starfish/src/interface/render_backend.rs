@@ -3,7 +3,11 @@ use std::{
     ops::{Mul, MulAssign},
 };
 
-use crate::shared::geo::*;
+use crate::{
+    font::{Font, Glyph},
+    image::Image,
+    shared::geo::*,
+};
 
 use anyhow::Result;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
@@ -30,6 +34,7 @@ pub trait RenderBackend: Sized + Debug + 'static {
     type ActiveWindowData<'a>;
 
     fn draw_rect(&mut self, data: &mut Self::WindowData, rect: &RenderRect<Self>);
+    fn draw_text(&mut self, data: &mut Self::WindowData, text: &RenderText<Self>);
     fn apply_scene(&mut self, data: &mut Self::WindowData, scene: &Self::Scene, transform: Option<Self::Transform>);
     fn reset(&mut self, data: &mut Self::WindowData);
 
@@ -65,6 +70,7 @@ pub trait RenderBackend: Sized + Debug + 'static {
 
 pub trait Scene<B: RenderBackend>: Clone + Debug {
     fn draw_rect(&mut self, rect: &RenderRect<B>);
+    fn draw_text(&mut self, text: &RenderText<B>);
     fn apply_scene(&mut self, scene: &B::Scene, transform: Option<B::Transform>);
     fn reset(&mut self);
     fn new() -> Self;
@@ -188,17 +194,31 @@ pub struct RenderRect<B: RenderBackend> {
     pub border: Option<RenderBorder<B>>,
 }
 
+/// A run of glyphs of a single font
+pub struct RenderText<B: RenderBackend> {
+    pub font: Font,
+    /// Font size in pixels
+    pub font_size: FP,
+    /// Glyphs positioned on their baseline
+    pub glyphs: Vec<Glyph>,
+    pub brush: B::Brush,
+    pub transform: Option<B::Transform>,
+}
+
 pub struct RenderBorder<B: RenderBackend> {
     pub border: B::Border,
     pub transform: Option<B::Transform>,
 }
 
 pub trait Transform: Sized + Mul<Self> + MulAssign + Clone + Send + Debug {
-
+    fn translate(x: FP, y: FP) -> Self;
+    fn scale(x: FP, y: FP) -> Self;
 }
 
 pub trait Brush<B: RenderBackend>: Clone {
     fn color(color: B::Color) -> Self;
+    /// A brush that paints the image with its top left corner at the origin, one pixel per unit
+    fn image(image: &Image) -> Self;
 }
 
 pub trait Color {
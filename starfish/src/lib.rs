@@ -5,9 +5,14 @@ mod net;
 mod tabs;
 mod window;
 
+#[cfg(test)]
+mod testing;
+
 pub mod application;
 pub mod css3;
+pub mod font;
 pub mod html5;
+pub mod image;
 pub mod interface;
 pub mod renderer;
 pub mod shared;
@@ -81,6 +81,14 @@ fn main() -> anyhow::Result<()> {
                 .long("log-level")
                 .action(ArgAction::Set),
         )
+        .arg(
+            clap::Arg::new("user-stylesheet")
+                .help("User stylesheet to apply to all pages, can be given multiple times")
+                .short('u')
+                .long("user-stylesheet")
+                .value_name("FILE")
+                .action(ArgAction::Append),
+        )
         .get_matches();
 
     let url: String = matches.get_one::<String>("url").expect("url").to_string();
@@ -102,6 +110,9 @@ fn main() -> anyhow::Result<()> {
     tracing::info!("Hello, Starfish Sleep!");
 
     let mut application: Application<Config> = Application::new(VelloBackend::new());
+    for path in matches.get_many::<String>("user-stylesheet").unwrap_or_default() {
+        application.add_user_stylesheet(path)?;
+    }
     let opts = WindowOptions::new()
         .with_title("Starfish Sleep")
         .with_size(1024, 768);
@@ -1,18 +1,23 @@
 
 use crate::{
+    font::{text_style, FontDatabase, Glyph, GlyphRun, TextStyle},
     interface::{
         config::{HasDrawComponents, HasHtmlParser},
+        css3::{CssProperty, CssPropertyMap, MediaEnvironment, UserStylesheet},
         draw::TreeDrawer,
+        layout::{Layout, LayoutTree},
         render_backend::{
-            Brush, Color, Rect, RenderBackend, RenderRect, Scene as TScene, WindowedEventLoop,
+            Brush, Color, Rect, RenderBackend, RenderRect, RenderText, Scene as TScene, Transform, WindowedEventLoop,
         },
     },
     renderer::render_tree::load_html_rendertree,
     shared::{
         geo::{FP, SizeU32},
-        types::Result,
+        node::NodeId,
+        types::{Point, Result, Size},
     },
-    util::render_tree::RenderTree,
+    taffy::TaffyLayouter,
+    util::render_tree::{FragmentKind, InlineFragment, RenderTree, ReplacedImage},
 };
 
 use url::Url;
@@ -24,9 +29,13 @@ pub struct TreeDrawerImpl<C: HasDrawComponents> {
     pub(crate) dirty: bool,
     pub(crate) tree_scene: Option<<C::RenderBackend as RenderBackend>::Scene>,
     pub(crate) scene_transform: Option<<C::RenderBackend as RenderBackend>::Transform>,
+    /// How far the document is scrolled in the viewport
+    pub(crate) scroll: Point<FP>,
 }
 
-impl<C: HasDrawComponents<RenderTree = RenderTree<C>, LayoutTree = RenderTree<C>> + HasHtmlParser> TreeDrawer<C> for TreeDrawerImpl<C> {
+impl<C: HasDrawComponents<RenderTree = RenderTree<C>, LayoutTree = RenderTree<C>, Layouter = TaffyLayouter> + HasHtmlParser>
+    TreeDrawer<C> for TreeDrawerImpl<C>
+{
     fn draw(
         &mut self,
         backend: &mut C::RenderBackend,
@@ -35,6 +44,12 @@ impl<C: HasDrawComponents<RenderTree = RenderTree<C>, LayoutTree = RenderTree<C>
         el: &impl WindowedEventLoop,
     ) -> bool {
         if self.tree_scene.is_none() || self.size != Some(size) || !self.dirty {
+            // A resized window is redrawn with its new size, which may change the result of media
+            // queries
+            if self.size != Some(size) {
+                self.tree.set_media(MediaEnvironment::from_size(size));
+                self.tree.layout(size);
+            }
             self.size = Some(size);
 
             let mut scene = <C::RenderBackend as RenderBackend>::Scene::new();
@@ -75,8 +90,30 @@ impl<C: HasDrawComponents<RenderTree = RenderTree<C>, LayoutTree = RenderTree<C>
         false
     }
 
-    async fn from_url(url: Url) -> Result<Self> {
-        let (rt, fetcher) = load_html_rendertree::<C>(url.clone()).await?;
+    fn scroll(&mut self, delta: Point<FP>) -> bool {
+        let Some(size) = self.size else {
+            return false;
+        };
+
+        let overflow = self.tree.scrollable_overflow();
+        let max_x = (overflow.width - size.width as FP).max(0.0);
+        let max_y = (overflow.height - size.height as FP).max(0.0);
+        let scroll = Point::new(
+            (self.scroll.x + delta.x).clamp(0.0, max_x),
+            (self.scroll.y + delta.y).clamp(0.0, max_y),
+        );
+        if scroll == self.scroll {
+            return false;
+        }
+
+        self.scroll = scroll;
+        self.tree_scene = None;
+
+        true
+    }
+
+    async fn from_url(url: Url, user_stylesheets: Vec<UserStylesheet>) -> Result<Self> {
+        let (rt, fetcher) = load_html_rendertree::<C>(url.clone(), user_stylesheets).await?;
 
         Ok(Self::new(rt))
     }
@@ -90,6 +127,7 @@ impl<C: HasDrawComponents> TreeDrawerImpl<C> {
             dirty: false,
             tree_scene: None,
             scene_transform: None,
+            scroll: Point::default(),
         }
     }
 }
@@ -99,8 +137,224 @@ pub struct Drawer<'s, 't, C: HasDrawComponents> {
     drawer: &'t mut TreeDrawerImpl<C>,
 }
 
-impl<C: HasDrawComponents> Drawer<'_, '_, C> {
+impl<C: HasDrawComponents<RenderTree = RenderTree<C>, LayoutTree = RenderTree<C>>> Drawer<'_, '_, C> {
+    /// Draws the text of the render tree
     fn render(&mut self, size: SizeU32) {
+        let tree = &self.drawer.tree;
+        let viewport = Size::new(size.width as FP, size.height as FP);
+        Self::render_node(tree, self.scene, tree.root, Point::default(), self.drawer.scroll, viewport);
+    }
+
+    /// Draws a box and its descendants. `parent` is the position of the parent box in the document,
+    /// which is drawn scrolled by `scroll`.
+    fn render_node(
+        tree: &RenderTree<C>,
+        scene: &mut <C::RenderBackend as RenderBackend>::Scene,
+        id: NodeId,
+        parent: Point<FP>,
+        mut scroll: Point<FP>,
+        viewport: Size<FP>,
+    ) {
+        let Some(node) = tree.get_node(id) else {
+            return;
+        };
+        let position = node.layout.position();
+        let mut origin = Point::new(parent.x + position.x, parent.y + position.y);
+
+        match tree.position(id) {
+            // Fixed boxes are laid out relative to the viewport, so they do not move when the
+            // document is scrolled
+            "fixed" => scroll = Point::default(),
+            "sticky" => {
+                let offset = tree.sticky_offset(id, scroll, viewport);
+                origin = Point::new(origin.x + offset.x, origin.y + offset.y);
+            }
+            _ => {}
+        }
+
+        if let Some(inline_layout) = &node.inline_layout {
+            let origin = Point::new(origin.x - scroll.x, origin.y - scroll.y);
+            for fragment in inline_layout.lines.iter().flat_map(|line| &line.fragments) {
+                Self::render_text(tree, scene, fragment, origin);
+            }
+        }
+
+        let hidden = node
+            .properties
+            .get("visibility")
+            .and_then(|property| property.as_keyword())
+            .is_some_and(|visibility| matches!(visibility, "hidden" | "collapse"));
+        if let Some(replaced) = tree.replaced_image(id).filter(|_| !hidden) {
+            Self::render_image(scene, &replaced, Point::new(origin.x - scroll.x, origin.y - scroll.y));
+        }
+
+        for child in &node.children {
+            Self::render_node(tree, scene, *child, origin, scroll, viewport);
+        }
+    }
+
+    /// Draws the image of a replaced element, clipped to its content box. `origin` is the position of
+    /// the border box of the element.
+    fn render_image(
+        scene: &mut <C::RenderBackend as RenderBackend>::Scene,
+        replaced: &ReplacedImage,
+        origin: Point<FP>,
+    ) {
+        let image = replaced.image;
+        if image.width() == 0 || image.height() == 0 {
+            return;
+        }
+
+        let x = origin.x + replaced.origin.x;
+        let y = origin.y + replaced.origin.y;
+        let clip_x = origin.x + replaced.clip_origin.x;
+        let clip_y = origin.y + replaced.clip_origin.y;
+        let left = x.max(clip_x);
+        let top = y.max(clip_y);
+        let right = (x + replaced.size.width).min(clip_x + replaced.clip_size.width);
+        let bottom = (y + replaced.size.height).min(clip_y + replaced.clip_size.height);
+        if right <= left || bottom <= top {
+            return;
+        }
+
+        // The brush paints the image one pixel per unit, so it is scaled to the size of the image
+        let mut brush_transform: <C::RenderBackend as RenderBackend>::Transform = Transform::translate(x, y);
+        brush_transform *= Transform::scale(
+            replaced.size.width / image.width() as FP,
+            replaced.size.height / image.height() as FP,
+        );
+
+        scene.draw_rect(&RenderRect {
+            rect: Rect::new(left, top, right - left, bottom - top),
+            transform: None,
+            radius: None,
+            brush: Brush::image(image),
+            brush_transform: Some(brush_transform),
+            border: None,
+        });
+    }
+
+    /// Draws a text fragment of an inline formatting context, relative to the block container
+    fn render_text(
+        tree: &RenderTree<C>,
+        scene: &mut <C::RenderBackend as RenderBackend>::Scene,
+        fragment: &InlineFragment,
+        origin: Point<FP>,
+    ) {
+        let FragmentKind::Text {
+            range,
+            justification,
+            rtl,
+        } = &fragment.kind
+        else {
+            return;
+        };
+        let Some(node) = tree.get_node(fragment.node) else {
+            return;
+        };
+        let properties = &node.properties;
+        let keyword = |name: &str| properties.get(name).and_then(|property| property.as_keyword());
+        if matches!(keyword("visibility"), Some("hidden" | "collapse")) {
+            return;
+        }
+
+        let Some(text) = node.text().and_then(|text| text.get(range.clone())) else {
+            return;
+        };
+        let color = properties
+            .get("color")
+            .and_then(|color| color.as_color())
+            .unwrap_or((0, 0, 0, 255));
+
+        let x = origin.x + fragment.x;
+        let baseline = origin.y + fragment.y + fragment.baseline;
+        for run in justified_glyphs(&tree.fonts, text, &text_style(properties), *justification, *rtl) {
+            let glyphs = run
+                .glyphs
+                .iter()
+                .map(|glyph| Glyph {
+                    x: x + glyph.x,
+                    y: baseline + glyph.y,
+                    ..*glyph
+                })
+                .collect();
+
+            scene.draw_text(&RenderText {
+                font: run.font,
+                font_size: run.size,
+                glyphs,
+                brush: Brush::color(Color::tuple4(color)),
+                transform: None,
+            });
+        }
+    }
+}
+
+/// Shapes text and moves every glyph right by the space added by justification to the spaces on its
+/// left, which are the spaces before it in left to right text and after it in right to left text
+fn justified_glyphs(
+    fonts: &FontDatabase,
+    text: &str,
+    style: &TextStyle,
+    justification: FP,
+    rtl: bool,
+) -> Vec<GlyphRun> {
+    let mut runs = fonts.shape(text, style, rtl).runs;
+
+    let total = text.chars().filter(|c| *c == ' ').count();
+    let mut spaces = 0;
+    let mut shifts = vec![0.0; text.len()];
+    for (offset, c) in text.char_indices() {
+        let after = total - spaces - usize::from(c == ' ');
+        shifts[offset] = if rtl { after } else { spaces } as FP * justification;
+        if c == ' ' {
+            spaces += 1;
+        }
+    }
+
+    for glyph in runs.iter_mut().flat_map(|run| run.glyphs.iter_mut()) {
+        glyph.x += shifts[glyph.cluster];
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::{FontFamily, FontQuery};
+
+    #[test]
+    fn justified_glyphs_are_in_visual_order() {
+        let fonts = FontDatabase::bundled();
+        let style = TextStyle {
+            font: FontQuery {
+                families: vec![FontFamily::Named("DejaVu Sans Mono".to_string())],
+                ..FontQuery::default()
+            },
+            size: 10.0,
+            ..TextStyle::default()
+        };
+        let positions = |text: &str, rtl: bool| {
+            justified_glyphs(&fonts, text, &style, 5.0, rtl)
+                .iter()
+                .flat_map(|run| run.glyphs.iter().map(|glyph| (glyph.cluster, glyph.x.round())))
+                .collect::<Vec<_>>()
+        };
 
+        // Each space is 5px wider, and right to left text starts on the right
+        assert_eq!(
+            positions("a b c", false),
+            [(0, 0.0), (1, 6.0), (2, 17.0), (3, 23.0), (4, 34.0)]
+        );
+        assert_eq!(
+            positions("a b c", true),
+            [(4, 0.0), (3, 6.0), (2, 17.0), (1, 23.0), (0, 34.0)]
+        );
+        // Brackets are mirrored by the shaper instead of by hand
+        let mirrored = justified_glyphs(&fonts, "(", &style, 5.0, true);
+        assert_eq!(
+            mirrored[0].glyphs[0].id,
+            fonts.shape(")", &style, false).runs[0].glyphs[0].id
+        );
     }
 }
@@ -1,30 +1,37 @@
-
 use crate::{
+    image::{decode_on_worker, Image},
     interface::{
-        config::{HasHtmlParser, HasRenderTree},
+        config::{HasCssSystem, HasHtmlParser, HasRenderTree},
+        css3::{CssOrigin, CssStylesheet, CssSystem, UserStylesheet},
         document::{Document, DocumentBuilder},
         html5::Html5Parser,
+        node::{ElementDataType, Node},
     },
     net::http::fetcher::Fetcher,
     shared::{
         byte_stream::{ByteStream, Encoding},
         document::DocumentHandle,
-        types::Result,
+        node::NodeId,
+        types::{ParseError, Result},
     },
     util::render_tree::{generate_render_tree, RenderTree},
 };
 
+use std::{collections::HashMap, future::Future, pin::Pin};
+
 use anyhow::bail;
+use futures::future::join_all;
 use url::Url;
 
 pub(crate) async fn load_html_rendertree<
     C: HasRenderTree<LayoutTree = RenderTree<C>, RenderTree = RenderTree<C>> + HasHtmlParser,
 >(
     url: Url,
+    user_stylesheets: Vec<UserStylesheet>,
 ) -> Result<(RenderTree<C>, Fetcher)> {
     let fetcher = Fetcher::new(url.clone());
 
-    let rt = load_html_rendertree_fetcher::<C>(url, &fetcher).await?;
+    let rt = load_html_rendertree_fetcher::<C>(url, &user_stylesheets, &fetcher).await?;
 
     Ok((rt, fetcher))
 }
@@ -33,6 +40,7 @@ pub(crate) async fn load_html_rendertree_fetcher<
     C: HasRenderTree<LayoutTree = RenderTree<C>, RenderTree = RenderTree<C>> + HasHtmlParser,
 >(
     url: Url,
+    user_stylesheets: &[UserStylesheet],
     fetcher: &Fetcher,
 ) -> Result<RenderTree<C>> {
     // Fetch the html from the url
@@ -44,22 +52,474 @@ pub(crate) async fn load_html_rendertree_fetcher<
     let html = String::from_utf8(response.body.clone())?;
     tracing::info!("\n{}", html);
 
+    let (mut doc_handle, parse_errors) = parse_document::<C>(&html, &url)?;
+    for error in parse_errors {
+        eprintln!("Parse error: {:?}", error);
+    }
+
+    let (sources, base_href) = collect_style_sources::<C>(&*doc_handle.get());
+    let base_url = match base_href {
+        Some(href) => url.join(&href).unwrap_or_else(|_| url.clone()),
+        None => url.clone(),
+    };
+    let user_stylesheets = load_user_stylesheets::<C>(user_stylesheets, fetcher).await;
+    let stylesheets = load_stylesheets::<C>(sources, &base_url, &url, fetcher).await;
+
+    {
+        let mut doc = doc_handle.get_mut();
+        doc.add_stylesheet(C::CssSystem::load_default_useragent_stylesheet());
+        for stylesheet in user_stylesheets.into_iter().chain(stylesheets) {
+            doc.add_stylesheet(stylesheet);
+        }
+    }
+
+    let mut render_tree = generate_render_tree(doc_handle)?;
+    load_images(&mut render_tree, &base_url, fetcher).await;
+
+    Ok(render_tree)
+}
+
+fn parse_document<C: HasHtmlParser>(html: &str, url: &Url) -> Result<(DocumentHandle<C>, Vec<ParseError>)> {
     let mut stream = ByteStream::new(Encoding::UTF8, None);
-    stream.read_from_str(&html, Some(Encoding::UTF8));
+    stream.read_from_str(html, Some(Encoding::UTF8));
     stream.close();
 
-    let mut doc_handle = C::DocumentBuilder::new_document(Some(url));
+    let doc_handle = C::DocumentBuilder::new_document(Some(url.clone()));
     let parse_errors = C::HtmlParser::parse(&mut stream, DocumentHandle::clone(&doc_handle), None)?;
 
-    for error in parse_errors {
-        eprintln!("Parse error: {:?}", error);
+    Ok((doc_handle, parse_errors))
+}
+
+/// A source of author styles found in the document
+enum StyleSource {
+    /// Contents and `media` attribute of a `<style>` element
+    Style(String, Option<String>),
+    /// Href and `media` attribute of a `<link rel="stylesheet">` element
+    Link(String, Option<String>),
+    /// Value of the `style` attribute of an element
+    Attribute(NodeId, String),
+}
+
+/// Walks the document in tree order and returns all sources of author styles (`<style>` elements,
+/// `<link rel="stylesheet">` elements and `style` attributes), together with the href of the first
+/// `<base>` element
+fn collect_style_sources<C: HasHtmlParser>(doc: &C::Document) -> (Vec<StyleSource>, Option<String>) {
+    let mut sources = Vec::new();
+    let mut base_href = None;
+
+    let mut stack = vec![doc.get_root().id()];
+    while let Some(node_id) = stack.pop() {
+        let Some(node) = doc.node_by_id(node_id) else {
+            continue;
+        };
+
+        if let Some(data) = node.get_element_data() {
+            match data.name() {
+                "style" if data.attribute("type").is_none_or(is_css_type) => {
+                    let css = node
+                        .children()
+                        .iter()
+                        .filter_map(|id| doc.node_by_id(*id)?.text_value())
+                        .collect::<String>();
+                    sources.push(StyleSource::Style(css, data.attribute("media").map(str::to_string)));
+                }
+                "link"
+                    if data.attribute("rel").is_some_and(is_stylesheet_link)
+                        && data.attribute("type").is_none_or(is_css_type) =>
+                {
+                    if let Some(href) = data.attribute("href").map(str::trim).filter(|href| !href.is_empty()) {
+                        let media = data.attribute("media").map(str::to_string);
+                        sources.push(StyleSource::Link(href.to_string(), media));
+                    }
+                }
+                "base" if base_href.is_none() => base_href = data.attribute("href").map(str::to_string),
+                _ => {}
+            }
+
+            if let Some(style) = data.attribute("style") {
+                sources.push(StyleSource::Attribute(node_id, style.to_string()));
+            }
+        }
+
+        for child_id in node.children().iter().rev() {
+            stack.push(*child_id);
+        }
     }
 
-    let mut doc = doc_handle.get_mut();
-    //doc.add_stylesheet(C::CssSystem::load_default_useragent_stylesheet());
+    (sources, base_href)
+}
+
+/// Parses the author styles in document order. Linked stylesheets and the stylesheets they
+/// import are fetched, relative urls are resolved against the base url of the document. The
+/// `media` attribute of a `<style>` or `<link>` element applies to all rules of its stylesheet.
+/// Stylesheets that can not be fetched or parsed are skipped.
+async fn load_stylesheets<C: HasHtmlParser>(
+    sources: Vec<StyleSource>,
+    base_url: &Url,
+    doc_url: &Url,
+    fetcher: &Fetcher,
+) -> Vec<C::Stylesheet> {
+    let mut stylesheets = Vec::with_capacity(sources.len());
+
+    for source in sources {
+        let (sheet, sheet_url, media) = match source {
+            StyleSource::Style(css, media) => {
+                let sheet = C::CssSystem::parse_stylesheet(&css, CssOrigin::Author, doc_url.as_str());
+                (sheet, base_url.clone(), media)
+            }
+            StyleSource::Attribute(node_id, css) => {
+                let sheet = C::CssSystem::parse_style_attribute(&css, doc_url.as_str(), node_id);
+                (sheet, base_url.clone(), None)
+            }
+            StyleSource::Link(href, media) => {
+                let Some((url, css)) = fetch_stylesheet(base_url, &href, fetcher).await else {
+                    continue;
+                };
+                (C::CssSystem::parse_stylesheet(&css, CssOrigin::Author, url.as_str()), url, media)
+            }
+        };
 
-    drop(doc);
+        match sheet {
+            Ok(mut sheet) => {
+                load_imports::<C>(&mut sheet, &sheet_url, &mut vec![sheet_url.clone()], fetcher).await;
+                if let Some(media) = media {
+                    sheet.restrict_media(&media);
+                }
+                stylesheets.push(sheet);
+            }
+            Err(err) => tracing::warn!("Could not parse stylesheet: {err}"),
+        }
+    }
 
-    generate_render_tree(DocumentHandle::clone(&doc_handle))
+    stylesheets
 }
 
+/// Parses the user stylesheets and loads the stylesheets they import
+async fn load_user_stylesheets<C: HasCssSystem>(
+    user_stylesheets: &[UserStylesheet],
+    fetcher: &Fetcher,
+) -> Vec<C::Stylesheet> {
+    let mut stylesheets = Vec::with_capacity(user_stylesheets.len());
+
+    for user_stylesheet in user_stylesheets {
+        let url = &user_stylesheet.url;
+        match C::CssSystem::parse_stylesheet(&user_stylesheet.css, CssOrigin::User, url.as_str()) {
+            Ok(mut sheet) => {
+                load_imports::<C>(&mut sheet, url, &mut vec![url.clone()], fetcher).await;
+                stylesheets.push(sheet);
+            }
+            Err(err) => tracing::warn!("Could not parse user stylesheet {url}: {err}"),
+        }
+    }
+
+    stylesheets
+}
+
+/// Loads the stylesheets imported with `@import`, and the stylesheets they import. `chain` holds
+/// the urls of the stylesheets that import this one, so import cycles are skipped.
+fn load_imports<'a, C: HasCssSystem>(
+    sheet: &'a mut C::Stylesheet,
+    sheet_url: &'a Url,
+    chain: &'a mut Vec<Url>,
+    fetcher: &'a Fetcher,
+) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+    Box::pin(async move {
+        let hrefs = sheet.imports().into_iter().map(str::to_string).collect::<Vec<_>>();
+        if hrefs.is_empty() {
+            return;
+        }
+
+        let mut imported = Vec::with_capacity(hrefs.len());
+        for href in hrefs {
+            let Some((url, css)) = fetch_stylesheet(sheet_url, &href, fetcher).await else {
+                imported.push(None);
+                continue;
+            };
+            if chain.contains(&url) {
+                tracing::warn!("Import cycle found for stylesheet {url}");
+                imported.push(None);
+                continue;
+            }
+
+            match C::CssSystem::parse_stylesheet(&css, sheet.origin(), url.as_str()) {
+                Ok(mut import) => {
+                    chain.push(url.clone());
+                    load_imports::<C>(&mut import, &url, chain, fetcher).await;
+                    chain.pop();
+                    imported.push(Some(import));
+                }
+                Err(err) => {
+                    tracing::warn!("Could not parse stylesheet {url}: {err}");
+                    imported.push(None);
+                }
+            }
+        }
+
+        sheet.resolve_imports(imported);
+    })
+}
+
+/// Fetches the sources of `<img>` elements and the posters of `<video>` elements, and decodes them
+/// on worker threads. Every image is fetched once, however many elements show it. Images that can
+/// not be fetched or decoded are skipped, so their elements have no image.
+async fn load_images<C: HasRenderTree<LayoutTree = RenderTree<C>, RenderTree = RenderTree<C>>>(
+    render_tree: &mut RenderTree<C>,
+    base_url: &Url,
+    fetcher: &Fetcher,
+) {
+    let mut sources = Vec::new();
+    for node in render_tree.nodes.values() {
+        let (Some(element), Some(dom_id)) = (node.element(), node.dom_id) else {
+            continue;
+        };
+        let attribute = match element.name.as_str() {
+            "img" => "src",
+            "video" => "poster",
+            _ => continue,
+        };
+        let Some(src) = element
+            .attribute(attribute)
+            .map(str::trim)
+            .filter(|src| !src.is_empty())
+        else {
+            continue;
+        };
+
+        match base_url.join(src) {
+            Ok(url) => sources.push((dom_id, url)),
+            Err(_) => tracing::warn!("Invalid image url: {src}"),
+        }
+    }
+    sources.sort_by_key(|(dom_id, _)| usize::from(*dom_id));
+
+    let mut urls = Vec::new();
+    let mut decoding = Vec::new();
+    for (_, url) in &sources {
+        if urls.contains(url) {
+            continue;
+        }
+
+        match fetcher.get_url(url).await {
+            Ok(response) if response.status == 200 => {
+                urls.push(url.clone());
+                decoding.push(decode_on_worker(response.body));
+            }
+            Ok(response) => tracing::warn!("Could not load image {url}: status code {}", response.status),
+            Err(err) => tracing::warn!("Could not load image {url}: {err}"),
+        }
+    }
+
+    let mut images: HashMap<Url, Image> = HashMap::with_capacity(urls.len());
+    for (url, decoded) in urls.into_iter().zip(join_all(decoding).await) {
+        match decoded {
+            Ok(image) => {
+                images.insert(url, image);
+            }
+            Err(err) => tracing::warn!("Could not decode image {url}: {err}"),
+        }
+    }
+
+    for (dom_id, url) in sources {
+        if let Some(image) = images.get(&url) {
+            render_tree.set_image(dom_id, image.clone());
+        }
+    }
+}
+
+/// Fetches a stylesheet, returning its url and contents
+async fn fetch_stylesheet(base_url: &Url, href: &str, fetcher: &Fetcher) -> Option<(Url, String)> {
+    let Ok(url) = base_url.join(href) else {
+        tracing::warn!("Invalid stylesheet url: {href}");
+        return None;
+    };
+
+    match fetcher.get_url(&url).await {
+        Ok(response) if response.status == 200 => {
+            let css = String::from_utf8_lossy(&response.body).into_owned();
+            Some((url, css))
+        }
+        Ok(response) => {
+            tracing::warn!("Could not load stylesheet {url}: status code {}", response.status);
+            None
+        }
+        Err(err) => {
+            tracing::warn!("Could not load stylesheet {url}: {err}");
+            None
+        }
+    }
+}
+
+/// Alternate stylesheets are not enabled by default, so they are not loaded
+fn is_stylesheet_link(rel: &str) -> bool {
+    let mut stylesheet = false;
+    for token in rel.split_ascii_whitespace() {
+        if token.eq_ignore_ascii_case("alternate") {
+            return false;
+        }
+        stylesheet |= token.eq_ignore_ascii_case("stylesheet");
+    }
+    stylesheet
+}
+
+/// Returns true when the `type` attribute is missing or empty, or a css mime type. Parameters like
+/// `charset` are ignored.
+fn is_css_type(mime: &str) -> bool {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    mime.trim().is_empty() || essence.eq_ignore_ascii_case("text/css")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interface::css3::{CssProperty, CssPropertyMap},
+        testing::{node, TestConfig, TestDocument},
+    };
+
+    /// Writes the stylesheets into a new directory, and returns its file url
+    fn write_stylesheets(name: &str, sheets: &[(&str, &str)]) -> Url {
+        let dir = std::env::temp_dir().join(format!("starfish-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, css) in sheets {
+            std::fs::write(dir.join(file), css).unwrap();
+        }
+        Url::from_directory_path(&dir).unwrap()
+    }
+
+    /// Loads the stylesheet and its imports, and returns the selectors of its rules
+    fn load(dir: &Url, file: &str) -> Vec<String> {
+        let url = dir.join(file).unwrap();
+        let fetcher = Fetcher::new(url.clone());
+        let css = std::fs::read_to_string(url.to_file_path().unwrap()).unwrap();
+        let mut sheet =
+            <TestConfig as HasCssSystem>::CssSystem::parse_stylesheet(&css, CssOrigin::Author, url.as_str()).unwrap();
+        futures::executor::block_on(load_imports::<TestConfig>(
+            &mut sheet,
+            &url,
+            &mut vec![url.clone()],
+            &fetcher,
+        ));
+
+        sheet.rules.iter().map(|rule| rule.selectors[0].to_string()).collect()
+    }
+
+    #[test]
+    fn imports() {
+        let dir = write_stylesheets(
+            "imports",
+            &[
+                ("a.css", "@import \"b.css\"; .a {}"),
+                ("b.css", "@import url(\"a.css\"); .b {}"),
+                ("self.css", "@import \"self.css\"; .self {}"),
+                (
+                    "diamond.css",
+                    "@import \"c.css\"; @import \"missing.css\"; @import \"d.css\"; .diamond {}",
+                ),
+                ("d.css", "@import \"c.css\"; .d {}"),
+                ("c.css", ".c {}"),
+            ],
+        );
+
+        // The import of `a.css` in `b.css` closes a cycle, so it is skipped
+        assert_eq!(load(&dir, "a.css"), [".b", ".a"]);
+        assert_eq!(load(&dir, "self.css"), [".self"]);
+        // Stylesheets can be imported more than once, as long as they do not import themselves
+        assert_eq!(load(&dir, "diamond.css"), [".c", ".c", ".d", ".diamond"]);
+    }
+
+    #[test]
+    fn user_stylesheets() {
+        let dir = write_stylesheets(
+            "user",
+            &[("user.css", "@import \"base.css\"; .user {}"), ("base.css", ".base {}")],
+        );
+        let path = |file| dir.join(file).unwrap().to_file_path().unwrap();
+
+        let user = UserStylesheet::from_path(path("user.css")).unwrap();
+        assert_eq!(user.url, dir.join("user.css").unwrap());
+        assert!(UserStylesheet::from_path(path("missing.css")).is_err());
+
+        let fetcher = Fetcher::new(user.url.clone());
+        let sheets = futures::executor::block_on(load_user_stylesheets::<TestConfig>(&[user], &fetcher));
+        assert_eq!(sheets.len(), 1);
+        assert_eq!(sheets[0].origin, CssOrigin::User);
+        // Imports are resolved against the url of the user stylesheet
+        let selectors: Vec<_> = sheets[0].rules.iter().map(|rule| rule.selectors[0].to_string()).collect();
+        assert_eq!(selectors, [".base", ".user"]);
+    }
+
+    #[test]
+    fn stylesheet_links() {
+        assert!(is_stylesheet_link("stylesheet"));
+        assert!(is_stylesheet_link("  StyleSheet "));
+        assert!(is_stylesheet_link("preload stylesheet"));
+        assert!(!is_stylesheet_link("alternate stylesheet"));
+        assert!(!is_stylesheet_link("stylesheet ALTERNATE"));
+        assert!(!is_stylesheet_link("icon"));
+        assert!(!is_stylesheet_link("stylesheets"));
+        assert!(!is_stylesheet_link(""));
+    }
+
+    #[test]
+    fn css_types() {
+        assert!(is_css_type(""));
+        assert!(is_css_type(" text/css "));
+        assert!(is_css_type("TEXT/CSS"));
+        assert!(is_css_type("text/css; charset=utf-8"));
+        assert!(!is_css_type("text/less"));
+        assert!(!is_css_type("text/cssx"));
+        assert!(!is_css_type("text/plain; x=text/css"));
+    }
+
+    #[test]
+    fn media_attributes() {
+        let dir = write_stylesheets("media", &[("print.css", "#c { width: 3px }"), ("all.css", "#d { width: 4px }")]);
+        let doc = TestDocument::parse(
+            "<style media=print>#a { width: 1px }</style><style media='screen, print'>#b { width: 2px }</style>\
+             <link rel=stylesheet href=print.css media=print><link rel=stylesheet href=all.css media=''>\
+             <p id=a></p><p id=b></p><p id=c></p><p id=d></p>",
+        );
+        let (sources, _) = collect_style_sources::<TestConfig>(&doc.handle.get());
+        let fetcher = Fetcher::new(dir.clone());
+        let sheets = futures::executor::block_on(load_stylesheets::<TestConfig>(sources, &dir, &dir, &fetcher));
+        for sheet in sheets {
+            doc.add_stylesheet(sheet);
+        }
+
+        let width = |id: &str| doc.style(id).get("width").and_then(|width| width.as_px());
+        assert_eq!(width("a"), None);
+        assert_eq!(width("b"), Some(2.0));
+        assert_eq!(width("c"), None);
+        assert_eq!(width("d"), Some(4.0));
+    }
+
+    #[test]
+    fn images() {
+        let dir = std::env::temp_dir().join(format!("starfish-images-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut png = std::io::Cursor::new(Vec::new());
+        ::image::RgbaImage::new(3, 2)
+            .write_to(&mut png, ::image::ImageFormat::Png)
+            .unwrap();
+        std::fs::write(dir.join("a.png"), png.into_inner()).unwrap();
+        std::fs::write(dir.join("broken.png"), "not an image").unwrap();
+        let base_url = Url::from_directory_path(&dir).unwrap();
+
+        let doc = TestDocument::parse(
+            "<img id=a src=a.png><img id=b src=' a.png '><video id=v poster=a.png></video>\
+             <img id=broken src=broken.png><img id=missing src=missing.png><img id=none>",
+        );
+        let mut tree = doc.render_tree();
+        futures::executor::block_on(load_images(&mut tree, &base_url, &Fetcher::new(base_url.clone())));
+        let image = |id: &str| Some(tree.replaced_image(node(&tree, &doc, id).id)?.image.clone());
+
+        let a = image("a").unwrap();
+        assert_eq!((a.width(), a.height()), (3, 2));
+        // Every url is fetched and decoded once, and its elements share the image
+        assert_eq!(image("b"), Some(a.clone()));
+        assert_eq!(image("v"), Some(a));
+        assert_eq!(image("broken"), None);
+        assert_eq!(image("missing"), None);
+        assert_eq!(image("none"), None);
+    }
+}
@@ -28,6 +28,18 @@ impl NodeId {
     }
 }
 
+impl From<usize> for NodeId {
+    fn from(value: usize) -> Self {
+        Self(value)
+    }
+}
+
+impl From<NodeId> for usize {
+    fn from(value: NodeId) -> Self {
+        value.0
+    }
+}
+
 impl Default for &NodeId {
     /// Returns the default NodeId, which is 0
     fn default() -> Self {
@@ -22,3 +22,24 @@ impl<T: Copy> Size<T> {
         Self { width, height }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Point<T: Copy> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T: Copy> Point<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+/// A value for each side of a box, like its margins or border widths
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Sides<T: Copy> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
@@ -1,6 +1,6 @@
 
 use crate::{
-    interface::{config::ModuleConfiguration, draw::TreeDrawer},
+    interface::{config::ModuleConfiguration, css3::UserStylesheet, draw::TreeDrawer},
     shared::types::Result,
 };
 
@@ -48,8 +48,8 @@ pub struct Tab<C: ModuleConfiguration> {
 }
 
 impl<C: ModuleConfiguration> Tab<C> {
-    pub async fn from_url(url: Url) -> Result<Self> {
-        let data = C::TreeDrawer::from_url(url.clone()).await?;
+    pub async fn from_url(url: Url, user_stylesheets: Vec<UserStylesheet>) -> Result<Self> {
+        let data = C::TreeDrawer::from_url(url.clone(), user_stylesheets).await?;
 
         tracing::info!("Tab created: {}", url.as_str()); 
         Ok(Self {
@@ -1,14 +1,66 @@
+use crate::{
+    interface::layout::{Layout as TLayout, Layouter},
+    shared::{
+        geo::FP,
+        types::{Point, Sides, Size},
+    },
+};
 
-use crate::interface::layout::{Layout as TLayout, Layouter};
+use taffy::{Cache, Layout as TaffyLayout, Point as TaffyPoint, Rect, Size as TaffySize, Style};
 
-use taffy::Layout as TaffyLayout;
+pub mod style;
+pub mod values;
 
-#[repr(transparent)]
-#[derive(Default, Debug)]
-pub struct Layout(TaffyLayout);
+/// The layout of a box, together with the style and cache taffy uses to compute it
+#[derive(Default, Debug, Clone)]
+pub struct Layout {
+    pub style: Style,
+    /// True when the style has math functions with percentages, which are resolved against the
+    /// containing block during layout
+    pub percentage_calc: bool,
+    pub cache: Cache,
+    /// The first baselines computed for each size of the box, which the cache of taffy leaves out
+    /// of the sizes it keeps
+    pub baselines: Vec<(TaffySize<f32>, TaffyPoint<Option<f32>>)>,
+    /// The layout computed by taffy, before it is rounded to whole pixels
+    pub unrounded: TaffyLayout,
+    /// The final layout, rounded to whole pixels
+    pub layout: TaffyLayout,
+}
 
 impl TLayout for Layout {
+    fn position(&self) -> Point<FP> {
+        Point::new(self.layout.location.x, self.layout.location.y)
+    }
+
+    fn size(&self) -> Size<FP> {
+        Size::new(self.layout.size.width, self.layout.size.height)
+    }
+
+    fn content_size(&self) -> Size<FP> {
+        Size::new(self.layout.content_size.width, self.layout.content_size.height)
+    }
+
+    fn margin(&self) -> Sides<FP> {
+        sides(self.layout.margin)
+    }
+
+    fn border(&self) -> Sides<FP> {
+        sides(self.layout.border)
+    }
+
+    fn padding(&self) -> Sides<FP> {
+        sides(self.layout.padding)
+    }
+}
 
+fn sides(rect: Rect<f32>) -> Sides<FP> {
+    Sides {
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
+        left: rect.left,
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -0,0 +1,477 @@
+use taffy::{
+    style::{
+        AlignContent, AlignItems, BoxSizing, Dimension, Display, FlexDirection, FlexWrap, GridAutoFlow, GridPlacement,
+        GridTrackRepetition, LengthPercentage, LengthPercentageAuto, MaxTrackSizingFunction, MinTrackSizingFunction,
+        NonRepeatedTrackSizingFunction, Overflow, Position, Style, TrackSizingFunction,
+    },
+    style_helpers::{line, span},
+    Line, Point, Rect, Size,
+};
+
+use crate::{
+    interface::css3::{CssProperty, CssPropertyMap},
+    taffy::values::{dimension, length_percentage, length_percentage_auto},
+};
+
+/// Properties whose percentages refer to the size of the containing block
+const PERCENTAGE_PROPERTIES: [&str; 18] = [
+    "width",
+    "height",
+    "min-width",
+    "min-height",
+    "max-width",
+    "max-height",
+    "margin-top",
+    "margin-right",
+    "margin-bottom",
+    "margin-left",
+    "padding-top",
+    "padding-right",
+    "padding-bottom",
+    "padding-left",
+    "top",
+    "right",
+    "bottom",
+    "left",
+];
+
+/// Converts the computed style of a box into a taffy style. The size of the containing block is
+/// not known yet, so math functions with percentages are resolved later by `resolve_calc`.
+pub fn taffy_style<M: CssPropertyMap>(properties: &M) -> Style {
+    let style = StyleReader {
+        properties,
+        containing_block: Size::NONE,
+    };
+
+    let display = style.keyword("display");
+    let position = style.keyword("position");
+    // Only relatively positioned boxes are offset by taffy. Absolutely positioned boxes are laid
+    // out at their static position, and moved into their containing block afterwards. Sticky boxes
+    // are offset when they are drawn.
+    let inset = match position {
+        "relative" => inset(properties, Size::NONE),
+        _ => Rect::auto(),
+    };
+
+    Style {
+        display: match display {
+            "none" => Display::None,
+            "flex" | "inline-flex" => Display::Flex,
+            "grid" | "inline-grid" => Display::Grid,
+            _ => Display::Block,
+        },
+        item_is_table: matches!(display, "table" | "inline-table"),
+        box_sizing: match style.keyword("box-sizing") {
+            "border-box" => BoxSizing::BorderBox,
+            _ => BoxSizing::ContentBox,
+        },
+        overflow: Point {
+            x: overflow(style.keyword("overflow-x")),
+            y: overflow(style.keyword("overflow-y")),
+        },
+        scrollbar_width: 0.0,
+        position: match position {
+            "absolute" | "fixed" => Position::Absolute,
+            _ => Position::Relative,
+        },
+        inset,
+        size: Size {
+            width: style.dimension("width"),
+            height: style.dimension("height"),
+        },
+        min_size: Size {
+            width: style.dimension("min-width"),
+            height: style.dimension("min-height"),
+        },
+        max_size: Size {
+            width: style.dimension("max-width"),
+            height: style.dimension("max-height"),
+        },
+        aspect_ratio: style.aspect_ratio(),
+        margin: Rect {
+            left: style.margin("margin-left"),
+            right: style.margin("margin-right"),
+            top: style.margin("margin-top"),
+            bottom: style.margin("margin-bottom"),
+        },
+        padding: Rect {
+            left: style.length_percentage("padding-left"),
+            right: style.length_percentage("padding-right"),
+            top: style.length_percentage("padding-top"),
+            bottom: style.length_percentage("padding-bottom"),
+        },
+        border: Rect {
+            left: style.length_percentage("border-left-width"),
+            right: style.length_percentage("border-right-width"),
+            top: style.length_percentage("border-top-width"),
+            bottom: style.length_percentage("border-bottom-width"),
+        },
+        align_items: style.last_keyword("align-items").and_then(|k| align_items(&k)),
+        align_self: style.last_keyword("align-self").and_then(|k| align_items(&k)),
+        justify_items: style.last_keyword("justify-items").and_then(|k| align_items(&k)),
+        justify_self: style.last_keyword("justify-self").and_then(|k| align_items(&k)),
+        align_content: style.last_keyword("align-content").and_then(|k| align_content(&k)),
+        justify_content: style.last_keyword("justify-content").and_then(|k| align_content(&k)),
+        gap: Size {
+            width: style.length_percentage("column-gap"),
+            height: style.length_percentage("row-gap"),
+        },
+        flex_direction: match style.keyword("flex-direction") {
+            "row-reverse" => FlexDirection::RowReverse,
+            "column" => FlexDirection::Column,
+            "column-reverse" => FlexDirection::ColumnReverse,
+            _ => FlexDirection::Row,
+        },
+        flex_wrap: match style.keyword("flex-wrap") {
+            "wrap" => FlexWrap::Wrap,
+            "wrap-reverse" => FlexWrap::WrapReverse,
+            _ => FlexWrap::NoWrap,
+        },
+        flex_basis: style.dimension("flex-basis"),
+        flex_grow: style.number("flex-grow").unwrap_or(0.0),
+        flex_shrink: style.number("flex-shrink").unwrap_or(1.0),
+        grid_template_rows: style.get("grid-template-rows").map(track_list).unwrap_or_default(),
+        grid_template_columns: style.get("grid-template-columns").map(track_list).unwrap_or_default(),
+        grid_auto_rows: style.get("grid-auto-rows").map(track_sizes).unwrap_or_default(),
+        grid_auto_columns: style.get("grid-auto-columns").map(track_sizes).unwrap_or_default(),
+        grid_auto_flow: style.grid_auto_flow(),
+        grid_row: Line {
+            start: style.get("grid-row-start").map(grid_placement).unwrap_or_default(),
+            end: style.get("grid-row-end").map(grid_placement).unwrap_or_default(),
+        },
+        grid_column: Line {
+            start: style.get("grid-column-start").map(grid_placement).unwrap_or_default(),
+            end: style.get("grid-column-end").map(grid_placement).unwrap_or_default(),
+        },
+        ..Style::DEFAULT
+    }
+}
+
+/// The `top`, `right`, `bottom` and `left` properties of a box
+pub fn inset<M: CssPropertyMap>(properties: &M, containing_block: Size<Option<f32>>) -> Rect<LengthPercentageAuto> {
+    let style = StyleReader {
+        properties,
+        containing_block,
+    };
+
+    Rect {
+        left: style.length_percentage_auto("left"),
+        right: style.length_percentage_auto("right"),
+        top: style.length_percentage_auto("top"),
+        bottom: style.length_percentage_auto("bottom"),
+    }
+}
+
+/// Returns true when a property of the box is a math function that mixes percentages with other
+/// units, like `calc(100% - 2rem)`. Taffy can only store plain lengths and percentages.
+pub fn has_percentage_calc<M: CssPropertyMap>(properties: &M) -> bool {
+    let style = StyleReader {
+        properties,
+        containing_block: Size::NONE,
+    };
+    PERCENTAGE_PROPERTIES.iter().any(|name| style.is_percentage_calc(name))
+}
+
+/// Resolves the math functions with percentages in the style of a box against the size of its
+/// containing block. Other values are left alone, as layout may have adjusted them, like the size
+/// of replaced elements. Returns true when the style changed.
+pub fn resolve_calc<M: CssPropertyMap>(style: &mut Style, properties: &M, containing_block: Size<Option<f32>>) -> bool {
+    let reader = StyleReader {
+        properties,
+        containing_block,
+    };
+    let mut changed = false;
+
+    for (target, name) in [
+        (&mut style.size.width, "width"),
+        (&mut style.size.height, "height"),
+        (&mut style.min_size.width, "min-width"),
+        (&mut style.min_size.height, "min-height"),
+        (&mut style.max_size.width, "max-width"),
+        (&mut style.max_size.height, "max-height"),
+    ] {
+        if reader.is_percentage_calc(name) {
+            update(&mut changed, target, reader.dimension(name));
+        }
+    }
+    for (target, name) in [
+        (&mut style.padding.top, "padding-top"),
+        (&mut style.padding.right, "padding-right"),
+        (&mut style.padding.bottom, "padding-bottom"),
+        (&mut style.padding.left, "padding-left"),
+    ] {
+        if reader.is_percentage_calc(name) {
+            update(&mut changed, target, reader.length_percentage(name));
+        }
+    }
+    for (target, name) in [
+        (&mut style.margin.top, "margin-top"),
+        (&mut style.margin.right, "margin-right"),
+        (&mut style.margin.bottom, "margin-bottom"),
+        (&mut style.margin.left, "margin-left"),
+    ] {
+        if reader.is_percentage_calc(name) {
+            update(&mut changed, target, reader.margin(name));
+        }
+    }
+    let insets = ["top", "right", "bottom", "left"];
+    if reader.keyword("position") == "relative" && insets.iter().any(|name| reader.is_percentage_calc(name)) {
+        update(&mut changed, &mut style.inset, inset(properties, containing_block));
+    }
+
+    changed
+}
+
+fn update<T: PartialEq>(changed: &mut bool, target: &mut T, value: T) {
+    if *target != value {
+        *target = value;
+        *changed = true;
+    }
+}
+
+struct StyleReader<'a, M: CssPropertyMap> {
+    properties: &'a M,
+    /// The size of the containing block, when it is known
+    containing_block: Size<Option<f32>>,
+}
+
+impl<'a, M: CssPropertyMap> StyleReader<'a, M> {
+    fn get(&self, name: &str) -> Option<&'a M::Property> {
+        self.properties.get(name)
+    }
+
+    fn keyword(&self, name: &str) -> &'a str {
+        self.get(name)
+            .and_then(|property| property.as_keyword())
+            .unwrap_or_default()
+    }
+
+    /// Returns the last keyword of the value, which is the one that matters for values like
+    /// `safe center` or `first baseline`
+    fn last_keyword(&self, name: &str) -> Option<String> {
+        let property = self.get(name)?;
+        match property.as_list() {
+            Some(items) => items
+                .iter()
+                .rev()
+                .find_map(|item| item.as_keyword().map(str::to_string)),
+            None => property.as_keyword().map(str::to_string),
+        }
+    }
+
+    fn number(&self, name: &str) -> Option<f32> {
+        self.get(name)?.as_number()
+    }
+
+    fn is_percentage_calc(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|property| {
+            property.as_percentage().is_none()
+                && property.resolve_px(None).is_none()
+                && property.resolve_px(Some(0.0)).is_some()
+        })
+    }
+
+    /// Percentages of heights and vertical insets refer to the height of the containing block, all
+    /// others to its width
+    fn basis(&self, name: &str) -> Option<f32> {
+        match name.ends_with("height") || name == "top" || name == "bottom" {
+            true => self.containing_block.height,
+            false => self.containing_block.width,
+        }
+    }
+
+    fn dimension(&self, name: &str) -> Dimension {
+        self.get(name)
+            .map(|property| dimension(property, self.basis(name)))
+            .unwrap_or(Dimension::Auto)
+    }
+
+    fn length_percentage(&self, name: &str) -> LengthPercentage {
+        self.get(name)
+            .map(|property| length_percentage(property, self.basis(name)))
+            .unwrap_or(LengthPercentage::Length(0.0))
+    }
+
+    fn length_percentage_auto(&self, name: &str) -> LengthPercentageAuto {
+        self.get(name)
+            .map(|property| length_percentage_auto(property, self.basis(name)))
+            .unwrap_or(LengthPercentageAuto::Auto)
+    }
+
+    fn margin(&self, name: &str) -> LengthPercentageAuto {
+        self.get(name)
+            .map(|property| length_percentage_auto(property, self.basis(name)))
+            .unwrap_or(LengthPercentageAuto::Length(0.0))
+    }
+
+    /// `auto || <ratio>`, where the ratio is a single number or `width / height`
+    fn aspect_ratio(&self) -> Option<f32> {
+        let property = self.get("aspect-ratio")?;
+        let numbers = match property.as_list() {
+            Some(items) => items.iter().filter_map(|item| item.as_number()).collect::<Vec<_>>(),
+            None => property.as_number().into_iter().collect(),
+        };
+
+        match numbers.as_slice() {
+            [ratio] if *ratio > 0.0 => Some(*ratio),
+            [width, height] if *width > 0.0 && *height > 0.0 => Some(width / height),
+            _ => None,
+        }
+    }
+
+    fn grid_auto_flow(&self) -> GridAutoFlow {
+        let Some(property) = self.get("grid-auto-flow") else {
+            return GridAutoFlow::Row;
+        };
+        let keywords = match property.as_list() {
+            Some(items) => items
+                .iter()
+                .filter_map(|item| item.as_keyword().map(str::to_string))
+                .collect(),
+            None => property
+                .as_keyword()
+                .map(str::to_string)
+                .into_iter()
+                .collect::<Vec<_>>(),
+        };
+
+        let column = keywords.iter().any(|keyword| keyword == "column");
+        let dense = keywords.iter().any(|keyword| keyword == "dense");
+        match (column, dense) {
+            (false, false) => GridAutoFlow::Row,
+            (false, true) => GridAutoFlow::RowDense,
+            (true, false) => GridAutoFlow::Column,
+            (true, true) => GridAutoFlow::ColumnDense,
+        }
+    }
+}
+
+fn overflow(keyword: &str) -> Overflow {
+    match keyword {
+        "clip" => Overflow::Clip,
+        "hidden" => Overflow::Hidden,
+        "scroll" | "auto" => Overflow::Scroll,
+        _ => Overflow::Visible,
+    }
+}
+
+/// Converts a `<self-position>` or `<baseline-position>`. `normal` and `auto` are None, so taffy
+/// uses the default alignment of the layout mode.
+fn align_items(keyword: &str) -> Option<AlignItems> {
+    Some(match keyword {
+        "start" | "self-start" | "left" => AlignItems::Start,
+        "end" | "self-end" | "right" => AlignItems::End,
+        "flex-start" => AlignItems::FlexStart,
+        "flex-end" => AlignItems::FlexEnd,
+        "center" => AlignItems::Center,
+        "baseline" => AlignItems::Baseline,
+        "stretch" => AlignItems::Stretch,
+        _ => return None,
+    })
+}
+
+/// Converts a `<content-distribution>` or `<content-position>`. `normal` is None.
+fn align_content(keyword: &str) -> Option<AlignContent> {
+    Some(match keyword {
+        "start" | "left" => AlignContent::Start,
+        "end" | "right" => AlignContent::End,
+        "flex-start" => AlignContent::FlexStart,
+        "flex-end" => AlignContent::FlexEnd,
+        "center" => AlignContent::Center,
+        "stretch" => AlignContent::Stretch,
+        "space-between" => AlignContent::SpaceBetween,
+        "space-around" => AlignContent::SpaceAround,
+        "space-evenly" => AlignContent::SpaceEvenly,
+        _ => return None,
+    })
+}
+
+/// Returns the items of a space separated value, or the value itself when it is a single item
+fn items<P: CssProperty>(property: &P) -> Vec<P> {
+    property.as_list().unwrap_or_else(|| vec![property.clone()])
+}
+
+/// Converts a `<track-list>` of `grid-template-rows` or `grid-template-columns`. Line names are
+/// skipped, as grid items can not be placed by name yet.
+fn track_list<P: CssProperty>(property: &P) -> Vec<TrackSizingFunction> {
+    if property.as_keyword() == Some("none") {
+        return Vec::new();
+    }
+
+    let mut tracks = Vec::new();
+    for item in items(property) {
+        match item.as_function() {
+            Some(("repeat", args)) if args.len() == 2 => {
+                let repetition = match (args[0].as_keyword(), args[0].as_number()) {
+                    (Some("auto-fill"), _) => GridTrackRepetition::AutoFill,
+                    (Some("auto-fit"), _) => GridTrackRepetition::AutoFit,
+                    (_, Some(count)) if count >= 1.0 => GridTrackRepetition::Count(count as u16),
+                    _ => continue,
+                };
+                tracks.push(TrackSizingFunction::Repeat(repetition, track_sizes(&args[1])));
+            }
+            Some(("[", _)) => {}
+            _ => tracks.extend(track_size(&item).map(TrackSizingFunction::Single)),
+        }
+    }
+
+    tracks
+}
+
+/// Converts a list of `<track-size>`, like the value of `grid-auto-rows`
+fn track_sizes<P: CssProperty>(property: &P) -> Vec<NonRepeatedTrackSizingFunction> {
+    items(property).iter().filter_map(track_size).collect()
+}
+
+fn track_size<P: CssProperty>(property: &P) -> Option<NonRepeatedTrackSizingFunction> {
+    let (min, max) = match property.as_function() {
+        Some(("minmax", args)) if args.len() == 2 => (min_track_size(&args[0]), max_track_size(&args[1])),
+        Some(("fit-content", args)) if args.len() == 1 => (
+            MinTrackSizingFunction::Auto,
+            MaxTrackSizingFunction::FitContent(length_percentage(&args[0], None)),
+        ),
+        Some(_) if property.resolve_px(None).is_none() && property.as_percentage().is_none() => return None,
+        // Fixed and flexible sizes, and math functions
+        _ => (min_track_size(property), max_track_size(property)),
+    };
+
+    Some(NonRepeatedTrackSizingFunction { min, max })
+}
+
+fn min_track_size<P: CssProperty>(property: &P) -> MinTrackSizingFunction {
+    match property.as_keyword() {
+        Some("min-content") => MinTrackSizingFunction::MinContent,
+        Some("max-content") => MinTrackSizingFunction::MaxContent,
+        Some(_) => MinTrackSizingFunction::Auto,
+        // A flexible size as minimum is invalid, and `1fr` means `minmax(auto, 1fr)`
+        None if property.as_dimension().is_some_and(|(_, unit)| unit == "fr") => MinTrackSizingFunction::Auto,
+        None => MinTrackSizingFunction::Fixed(length_percentage(property, None)),
+    }
+}
+
+fn max_track_size<P: CssProperty>(property: &P) -> MaxTrackSizingFunction {
+    match property.as_keyword() {
+        Some("min-content") => MaxTrackSizingFunction::MinContent,
+        Some("max-content") => MaxTrackSizingFunction::MaxContent,
+        Some(_) => MaxTrackSizingFunction::Auto,
+        None => match property.as_dimension() {
+            Some((flex, "fr")) => MaxTrackSizingFunction::Fraction(flex),
+            _ => MaxTrackSizingFunction::Fixed(length_percentage(property, None)),
+        },
+    }
+}
+
+/// Converts a `<grid-line>`, like the value of `grid-row-start`. Named lines are placed
+/// automatically.
+fn grid_placement<P: CssProperty>(property: &P) -> GridPlacement {
+    let items = items(property);
+    let is_span = items.iter().any(|item| item.as_keyword() == Some("span"));
+    let number = items.iter().find_map(|item| item.as_number());
+
+    match (is_span, number) {
+        (true, Some(count)) if count >= 1.0 => span(count as u16),
+        (true, _) => span(1),
+        (false, Some(index)) if index != 0.0 => line(index as i16),
+        _ => GridPlacement::Auto,
+    }
+}
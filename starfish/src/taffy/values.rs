@@ -0,0 +1,41 @@
+use taffy::style::{Dimension, LengthPercentage, LengthPercentageAuto};
+
+use crate::interface::css3::CssProperty;
+
+// Taffy can only store plain lengths and percentages, so math functions like
+// `calc(100% - 2rem)` are resolved here against the size of the containing block. When that
+// size is not known yet, expressions that depend on percentages fall back to `auto` (or zero),
+// until layout resolves them again with `resolve_calc`.
+
+/// Converts a computed value into a taffy `LengthPercentage`
+pub fn length_percentage(property: &impl CssProperty, containing_block: Option<f32>) -> LengthPercentage {
+    if let Some(pct) = property.as_percentage() {
+        return LengthPercentage::Percent(pct / 100.0);
+    }
+
+    LengthPercentage::Length(property.resolve_px(containing_block).unwrap_or_default())
+}
+
+/// Converts a computed value into a taffy `LengthPercentageAuto`
+pub fn length_percentage_auto(property: &impl CssProperty, containing_block: Option<f32>) -> LengthPercentageAuto {
+    if let Some(pct) = property.as_percentage() {
+        return LengthPercentageAuto::Percent(pct / 100.0);
+    }
+
+    match property.resolve_px(containing_block) {
+        Some(px) => LengthPercentageAuto::Length(px),
+        None => LengthPercentageAuto::Auto,
+    }
+}
+
+/// Converts a computed value into a taffy `Dimension`
+pub fn dimension(property: &impl CssProperty, containing_block: Option<f32>) -> Dimension {
+    if let Some(pct) = property.as_percentage() {
+        return Dimension::Percent(pct / 100.0);
+    }
+
+    match property.resolve_px(containing_block) {
+        Some(px) => Dimension::Length(px),
+        None => Dimension::Auto,
+    }
+}
@@ -0,0 +1,245 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use url::Url;
+
+use crate::{
+    css3::{matcher::styling::CssProperties, system::Css3System},
+    font::FontDatabase,
+    html5::{
+        document::{builder::DocumentBuilderImpl, document_impl::DocumentImpl, fragment::DocumentFragmentImpl},
+        parser::{errors::ErrorLogger, Html5Parser},
+        tokenizer::{token::Token, ParserData, Tokenizer},
+    },
+    interface::{
+        config::{HasCssSystem, HasDocument, HasHtmlParser, HasLayouter, HasRenderTree},
+        css3::{CssOrigin, CssSystem, MediaEnvironment},
+        document::{Document, DocumentBuilder},
+        layout::{Layout, LayoutTree},
+    },
+    shared::{
+        byte_stream::{ByteStream, Encoding, Location},
+        document::DocumentHandle,
+        geo::SizeU32,
+        node::NodeId,
+    },
+    taffy::TaffyLayouter,
+    util::render_tree::{generate_render_tree, RenderNodeData, RenderTree, RenderTreeNode},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TestConfig;
+
+impl HasCssSystem for TestConfig {
+    type CssSystem = Css3System;
+}
+
+impl HasDocument for TestConfig {
+    type Document = DocumentImpl<Self>;
+    type DocumentFragment = DocumentFragmentImpl<Self>;
+    type DocumentBuilder = DocumentBuilderImpl;
+}
+
+impl HasHtmlParser for TestConfig {
+    type HtmlParser = Html5Parser<'static, Self>;
+}
+
+impl HasLayouter for TestConfig {
+    type Layouter = TaffyLayouter;
+    type LayoutTree = RenderTree<Self>;
+}
+
+impl HasRenderTree for TestConfig {
+    type RenderTree = RenderTree<Self>;
+}
+
+/// Elements that never have children
+const VOID_ELEMENTS: [&str; 12] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "wbr",
+];
+
+/// A document built from a snippet of HTML. The tree builder of the HTML parser is not finished, so
+/// the tokens are nested as they are written: every start tag opens an element (except void
+/// elements), and every end tag closes the element with the same name.
+pub(crate) struct TestDocument {
+    pub handle: DocumentHandle<TestConfig>,
+    /// Elements by their `id` attribute
+    ids: HashMap<String, NodeId>,
+}
+
+impl TestDocument {
+    /// Builds a document from the given HTML. Snippets that do not start with `<html>` are placed in
+    /// the `<body>` of a document.
+    pub fn parse(html: &str) -> Self {
+        let html = match html.trim_start().starts_with("<html") {
+            true => html.to_string(),
+            false => format!("<html><body>{html}</body></html>"),
+        };
+
+        let url = Url::parse("https://example.com/index.html").unwrap();
+        let mut handle: DocumentHandle<TestConfig> = DocumentBuilderImpl::new_document(Some(url));
+        let mut ids = HashMap::new();
+
+        let mut stream = ByteStream::new(Encoding::UTF8, None);
+        stream.read_from_str(&html, Some(Encoding::UTF8));
+        stream.close();
+        let error_logger = Rc::new(RefCell::new(ErrorLogger::new()));
+        let mut tokenizer = Tokenizer::new(&mut stream, None, error_logger, Location::default());
+
+        let mut open = vec![(String::new(), NodeId::root())];
+        loop {
+            let token = tokenizer.next_token(ParserData::default()).unwrap();
+            let parent = open.last().map_or(NodeId::root(), |(_, id)| *id);
+            match token {
+                Token::StartTag {
+                    name,
+                    is_self_closing,
+                    attributes,
+                    ..
+                } => {
+                    let id = attributes.get("id").cloned();
+                    let node =
+                        DocumentImpl::new_element_node(handle.clone(), &name, None, attributes, Location::default());
+                    let node_id = handle.get_mut().register_node_at(node, parent, None);
+                    if let Some(id) = id {
+                        ids.insert(id, node_id);
+                    }
+                    if !is_self_closing && !VOID_ELEMENTS.contains(&name.as_str()) {
+                        open.push((name, node_id));
+                    }
+                }
+                Token::EndTag { name, .. } => {
+                    if let Some(index) = open.iter().rposition(|(open_name, _)| *open_name == name) {
+                        open.truncate(index);
+                    }
+                }
+                Token::Text { text, .. } => {
+                    let node = DocumentImpl::new_text_node(handle.clone(), &text, Location::default());
+                    handle.get_mut().register_node_at(node, parent, None);
+                }
+                Token::Comment { comment, .. } => {
+                    let node = DocumentImpl::new_comment_node(handle.clone(), &comment, Location::default());
+                    handle.get_mut().register_node_at(node, parent, None);
+                }
+                Token::DocType { .. } => {}
+                Token::Eof { .. } => break,
+            }
+        }
+
+        Self { handle, ids }
+    }
+
+    /// Builds a document from the given HTML, styled by the user agent stylesheet and the given
+    /// author stylesheet
+    pub fn with_css(html: &str, css: &str) -> Self {
+        let doc = Self::parse(html);
+        doc.add_stylesheet(Css3System::load_default_useragent_stylesheet());
+        doc.add_css(css, CssOrigin::Author);
+        doc
+    }
+
+    pub fn add_css(&self, css: &str, origin: CssOrigin) {
+        let sheet = Css3System::parse_stylesheet(css, origin, "https://example.com/style.css").unwrap();
+        self.add_stylesheet(sheet);
+    }
+
+    pub fn add_stylesheet(&self, sheet: <Css3System as CssSystem>::Stylesheet) {
+        self.handle.clone().get_mut().add_stylesheet(sheet);
+    }
+
+    /// Returns the element with the given `id` attribute
+    pub fn id(&self, id: &str) -> NodeId {
+        *self.ids.get(id).unwrap_or_else(|| panic!("no element with id {id}"))
+    }
+
+    /// Computes the styles of all nodes for the default media environment
+    pub fn styles(&self) -> HashMap<NodeId, CssProperties> {
+        self.styles_for(&MediaEnvironment::default())
+    }
+
+    pub fn styles_for(&self, media: &MediaEnvironment) -> HashMap<NodeId, CssProperties> {
+        Css3System::compute_styles::<TestConfig>(&self.handle.get(), media)
+    }
+
+    /// Computes the style of the element with the given `id` attribute
+    pub fn style(&self, id: &str) -> CssProperties {
+        self.styles().remove(&self.id(id)).unwrap()
+    }
+
+    /// Generates the render tree, which measures text with the bundled fonts only
+    pub fn render_tree(&self) -> RenderTree<TestConfig> {
+        let mut tree = generate_render_tree(self.handle.clone()).unwrap();
+        tree.fonts = FontDatabase::bundled();
+        tree
+    }
+
+    /// Generates the render tree and lays it out in a viewport of the given size
+    pub fn layout(&self, width: u32, height: u32) -> RenderTree<TestConfig> {
+        let mut tree = self.render_tree();
+        tree.set_media(MediaEnvironment::from_size(SizeU32::new(width, height)));
+        tree.layout(SizeU32::new(width, height));
+        tree
+    }
+}
+
+/// Returns the render tree node generated from the element with the given `id` attribute
+pub(crate) fn node<'a>(
+    tree: &'a RenderTree<TestConfig>,
+    doc: &TestDocument,
+    id: &str,
+) -> &'a RenderTreeNode<TestConfig> {
+    let node_id = tree
+        .node_for_dom(doc.id(id))
+        .unwrap_or_else(|| panic!("element {id} has no box"));
+    &tree.nodes[&node_id]
+}
+
+/// Returns the position and size of the border box of the element with the given `id` attribute,
+/// relative to the root of the tree, as `(x, y, width, height)`
+pub(crate) fn rect(tree: &RenderTree<TestConfig>, doc: &TestDocument, id: &str) -> (f32, f32, f32, f32) {
+    let node_id = node(tree, doc, id).id;
+    node_rect(tree, node_id)
+}
+
+pub(crate) fn node_rect(tree: &RenderTree<TestConfig>, node_id: NodeId) -> (f32, f32, f32, f32) {
+    let size = tree.nodes[&node_id].layout.size();
+    let (mut x, mut y) = (0.0, 0.0);
+    let mut current = Some(node_id);
+    while let Some(id) = current {
+        let position = tree.nodes[&id].layout.position();
+        x += position.x;
+        y += position.y;
+        current = tree.nodes[&id].parent;
+    }
+
+    (x, y, size.width, size.height)
+}
+
+/// Describes the boxes in the subtree of the given node, like `div[anon("a") p("b")]`. Elements are
+/// named by their tag, text by its quoted content, pseudo elements like `::before` and anonymous
+/// boxes as `anon`.
+pub(crate) fn box_tree(tree: &RenderTree<TestConfig>, node_id: NodeId) -> String {
+    let node = &tree.nodes[&node_id];
+    let name = match &node.data {
+        RenderNodeData::Document => "#document".to_string(),
+        RenderNodeData::Element(element) => element.name.clone(),
+        RenderNodeData::Text(text) => return format!("{:?}", text.text),
+        RenderNodeData::PseudoElement(pseudo) => format!("::{}", pseudo.name()),
+        RenderNodeData::Anonymous => "anon".to_string(),
+    };
+    if node.children.is_empty() {
+        return name;
+    }
+
+    let children = node
+        .children
+        .iter()
+        .map(|child| box_tree(tree, *child))
+        .collect::<Vec<_>>();
+    format!("{name}[{}]", children.join(" "))
+}
+
+/// Describes the boxes in the body of the document, see [`box_tree`]
+pub(crate) fn body_tree(tree: &RenderTree<TestConfig>) -> String {
+    let html = tree.nodes[&tree.root].children[0];
+    box_tree(tree, tree.nodes[&html].children[0])
+}
@@ -1,27 +1,68 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 
 use crate::{
+    font::FontDatabase,
+    image::Image,
     interface::{
         config::{HasDocument, HasLayouter, HasRenderTree},
+        css3::{CssProperty, CssPropertyMap, CssSystem, MediaEnvironment, PseudoElement},
         document::Document,
         layout::{Layouter, LayoutTree},
+        node::{ElementDataType, Node, NodeType},
         render_tree,
     },
     shared::{
         document::DocumentHandle,
+        geo::SizeU32,
         node::NodeId,
         types::Result,
     },
+    taffy::TaffyLayouter,
+    util::render_tree::{generated::GeneratedContent, inline::LineBreaker},
 };
 
+mod anonymous;
+mod flow;
+mod generated;
+mod inline;
+mod layout;
+mod positioned;
+mod replaced;
+mod table;
+mod whitespace;
+
+pub use inline::{FragmentKind, InlineFragment, InlineLayout, LineBox};
+pub use replaced::ReplacedImage;
+
 #[derive(Debug)]
 pub struct RenderTree<C: HasLayouter> {
     pub nodes: HashMap<NodeId, RenderTreeNode<C>>,
+    /// Id of the root node, which is generated from the document node
+    pub root: NodeId,
+    /// Render tree node generated from each DOM node
+    dom_nodes: HashMap<NodeId, NodeId>,
+    /// Id of the next node that is inserted
+    next_id: NodeId,
+    /// The nodes of the document the tree is generated from. The tree is generated again from
+    /// these when the styles change, as the document itself can not be kept.
+    source: HashMap<NodeId, SourceNode>,
+    /// Matched declarations of the document, used to compute the styles again when the media
+    /// environment changes
+    matched: Option<<C::CssSystem as CssSystem>::MatchedStyles>,
+    /// Media environment the styles are computed for
+    media: MediaEnvironment,
+    /// Fonts used to measure and draw text
+    pub fonts: Arc<FontDatabase>,
+    /// Decoded images of replaced elements, by the DOM node of the element
+    images: HashMap<NodeId, Image>,
+    /// Finds the line breaking opportunities in inline formatting contexts
+    line_breaker: LineBreaker,
 }
 
-#[allow(unused)]
-impl<C: HasLayouter<LayoutTree = Self>> LayoutTree<C> for RenderTree<C> {
-
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> LayoutTree<C> for RenderTree<C> {
+    fn layout(&mut self, viewport: SizeU32) {
+        self.compute_layout(viewport);
+    }
 }
 
 impl<C: HasLayouter<LayoutTree = Self>> render_tree::RenderTree<C> for RenderTree<C> {
@@ -37,8 +78,13 @@ impl<C: HasRenderTree<LayoutTree = Self, RenderTree = Self> + HasDocument> Rende
         render_tree
     }
 
-    fn generate_from(&mut self, mut handle: DocumentHandle<C>) {
+    fn generate_from(&mut self, handle: DocumentHandle<C>) {
+        let doc = handle.get();
+        self.matched = Some(C::CssSystem::match_styles::<C>(&doc));
+        self.source = collect_source_nodes::<C>(&doc);
+        drop(doc);
 
+        self.build();
     }
 }
 
@@ -46,38 +92,329 @@ impl<C: HasLayouter<LayoutTree = Self>> RenderTree<C> {
     pub fn with_capacity(capacity: usize) -> Self {
         let mut tree = Self {
             nodes: HashMap::with_capacity(capacity),
+            root: NodeId::root(),
+            dom_nodes: HashMap::with_capacity(capacity),
+            next_id: NodeId::root(),
+            source: HashMap::new(),
+            matched: None,
+            media: MediaEnvironment::default(),
+            fonts: FontDatabase::shared(),
+            images: HashMap::new(),
+            line_breaker: LineBreaker::default(),
+        };
+
+        tree.insert_node(None, Some(NodeId::root()), Default::default(), RenderNodeData::Document);
+
+        tree
+    }
+
+    /// Sets the media environment (for instance the new size of the window). The styles are only
+    /// computed again when the result of any media query changes, or when viewport units refer to
+    /// the new size. Returns true when they are.
+    pub fn set_media(&mut self, media: MediaEnvironment) -> bool {
+        let old = std::mem::replace(&mut self.media, media);
+
+        let Some(matched) = &self.matched else {
+            return false;
         };
+        if !C::CssSystem::media_changed(matched, &old, &self.media) {
+            return false;
+        }
+
+        // The display of nodes can change as well, so the whole tree is generated again
+        self.build();
+
+        true
+    }
+
+    pub fn get_node(&self, id: NodeId) -> Option<&RenderTreeNode<C>> {
+        self.nodes.get(&id)
+    }
+
+    pub fn get_node_mut(&mut self, id: NodeId) -> Option<&mut RenderTreeNode<C>> {
+        self.nodes.get_mut(&id)
+    }
+
+    /// Returns the render tree node generated from the given DOM node, if it generated one
+    pub fn node_for_dom(&self, dom_id: NodeId) -> Option<NodeId> {
+        self.dom_nodes.get(&dom_id).copied()
+    }
+
+    /// Inserts a new node as the last child of its parent, and returns its id. Anonymous boxes have
+    /// no DOM node.
+    pub fn insert_node(
+        &mut self,
+        parent: Option<NodeId>,
+        dom_id: Option<NodeId>,
+        properties: <C::CssSystem as CssSystem>::PropertyMap,
+        data: RenderNodeData,
+    ) -> NodeId {
+        let id = self.create_node(dom_id, properties, data);
+
+        if let Some(parent_node) = parent.and_then(|parent| self.nodes.get_mut(&parent)) {
+            parent_node.children.push(id);
+        }
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.parent = parent;
+        }
+        if let Some(dom_id) = dom_id {
+            self.dom_nodes.insert(dom_id, id);
+        }
 
-        tree.insert_node(
-            NodeId::root(),
+        id
+    }
+
+    /// Creates a new node that is not attached to the tree yet
+    fn create_node(
+        &mut self,
+        dom_id: Option<NodeId>,
+        properties: <C::CssSystem as CssSystem>::PropertyMap,
+        data: RenderNodeData,
+    ) -> NodeId {
+        let id = self.next_id;
+        self.next_id = id.next();
+
+        self.nodes.insert(
+            id,
             RenderTreeNode {
-                id: NodeId::root(),
+                id,
+                parent: None,
+                children: Vec::new(),
+                dom_id,
+                properties,
+                data,
                 layout: <C::Layouter as Layouter>::Layout::default(),
+                inline_layout: None,
             },
         );
 
-        tree
+        id
     }
 
-    /// Inserts a new node into the render tree, note that you are responsible for the node id
-    /// and the children of the node
-    pub fn insert_node(&mut self, id: NodeId, node: RenderTreeNode<C>) {
-        self.nodes.insert(id, node);
+    /// Generates the nodes of the tree from the source nodes of the document, with the styles
+    /// computed for the current media environment. Nodes with `display: none` are skipped with
+    /// their descendants, and nodes with `display: contents` are replaced by their children.
+    /// Anonymous boxes are generated afterwards, and the white space of text is processed.
+    fn build(&mut self) {
+        let Some(matched) = self.matched.take() else {
+            return;
+        };
+
+        self.generate_nodes(&matched);
+        self.generate_anonymous_boxes(&matched);
+        self.process_white_space();
+
+        self.matched = Some(matched);
+    }
+
+    fn generate_nodes(&mut self, matched: &<C::CssSystem as CssSystem>::MatchedStyles) {
+        let mut styles = C::CssSystem::compute_matched_styles(matched, &self.media);
+        let mut pseudo_styles = C::CssSystem::compute_pseudo_element_styles(matched, &styles, &self.media);
+        let mut content = GeneratedContent::default();
+
+        self.nodes.clear();
+        self.dom_nodes.clear();
+        self.next_id = NodeId::root();
+        self.root = self.insert_node(None, Some(NodeId::root()), Default::default(), RenderNodeData::Document);
+
+        let Some(root) = self.source.get(&NodeId::root()) else {
+            return;
+        };
+        // Elements are visited again after their children (`leave` is then true), to generate their
+        // `::after` box and end the scope of the counters in them
+        let mut stack = root
+            .children
+            .iter()
+            .rev()
+            .map(|id| (*id, self.root, false))
+            .collect::<Vec<_>>();
+
+        while let Some((dom_id, parent, leave)) = stack.pop() {
+            if leave {
+                if let Some(style) = pseudo_styles.remove(&(dom_id, PseudoElement::After)) {
+                    self.generate_pseudo_element(parent, dom_id, PseudoElement::After, style, &mut content, matched);
+                }
+                content.leave();
+                if self.node_for_dom(dom_id) == Some(parent) {
+                    self.place_outside_marker(parent);
+                }
+                continue;
+            }
+
+            let (Some(source), Some(properties)) = (self.source.get(&dom_id), styles.remove(&dom_id)) else {
+                continue;
+            };
+
+            // The content of replaced elements is not rendered, so they have no children, and
+            // `display: contents` hides them (CSS Display 3, 2.7)
+            let replaced = matches!(&source.data, RenderNodeData::Element(element) if element.is_replaced());
+            let display = properties.get("display").and_then(|display| display.as_keyword());
+            if display == Some("none") || (display == Some("contents") && replaced) {
+                continue;
+            }
+
+            // Elements without a box of their own still change counters
+            let is_element = match &source.data {
+                RenderNodeData::Element(element) => {
+                    content.enter(&properties, element);
+                    true
+                }
+                _ => false,
+            };
+            let parent = match display {
+                Some("contents") if is_element => parent,
+                _ => {
+                    let data = source.data.clone();
+                    self.insert_node(Some(parent), Some(dom_id), properties, data)
+                }
+            };
+
+            if replaced {
+                content.leave();
+                continue;
+            }
+
+            if is_element {
+                stack.push((dom_id, parent, true));
+            }
+            let source = &self.source[&dom_id];
+            stack.extend(source.children.iter().rev().map(|id| (*id, parent, false)));
+
+            // The marker and the `::before` box come before the content of the element
+            if let Some(style) = self.marker_style(dom_id, parent, &mut pseudo_styles, matched) {
+                self.generate_pseudo_element(parent, dom_id, PseudoElement::Marker, style, &mut content, matched);
+            }
+            if let Some(style) = pseudo_styles.remove(&(dom_id, PseudoElement::Before)) {
+                self.generate_pseudo_element(parent, dom_id, PseudoElement::Before, style, &mut content, matched);
+            }
+        }
     }
 }
 
+/// A node of the document that can generate a render tree node
+#[derive(Debug, Clone)]
+struct SourceNode {
+    children: Vec<NodeId>,
+    data: RenderNodeData,
+}
+
+/// Copies the nodes of the document that can be rendered. Comments, doctypes and the `<head>` and
+/// `<script>` elements are left out.
+fn collect_source_nodes<C: HasDocument>(doc: &C::Document) -> HashMap<NodeId, SourceNode> {
+    let mut source = HashMap::with_capacity(doc.node_count());
+
+    let mut stack = vec![doc.get_root().id()];
+    while let Some(node_id) = stack.pop() {
+        let Some(node) = doc.node_by_id(node_id) else {
+            continue;
+        };
+
+        let data = match node.type_of() {
+            NodeType::DocumentNode => RenderNodeData::Document,
+            NodeType::ElementNode => {
+                let Some(element) = node.get_element_data() else {
+                    continue;
+                };
+                if matches!(element.name(), "head" | "script") {
+                    continue;
+                }
+
+                RenderNodeData::Element(ElementData {
+                    name: element.name().to_string(),
+                    namespace: element.namespace().to_string(),
+                    attributes: element.attributes().clone(),
+                })
+            }
+            NodeType::TextNode => RenderNodeData::Text(TextData {
+                text: node.text_value().unwrap_or_default().to_string(),
+            }),
+            NodeType::CommentNode | NodeType::DocTypeNode => continue,
+        };
+
+        let children = node.children().to_vec();
+        stack.extend(children.iter().copied());
+        source.insert(node_id, SourceNode { children, data });
+    }
+
+    source
+}
+
 pub struct RenderTreeNode<C: HasLayouter> {
     pub id: NodeId,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+    /// Id of the DOM node this node is generated from, None for anonymous boxes
+    pub dom_id: Option<NodeId>,
+    /// Computed style of the node
+    pub properties: <C::CssSystem as CssSystem>::PropertyMap,
+    pub data: RenderNodeData,
     pub layout: <C::Layouter as Layouter>::Layout,
+    /// The line boxes of the inline formatting context the box establishes, if any
+    pub inline_layout: Option<InlineLayout>,
+}
+
+impl<C: HasLayouter> RenderTreeNode<C> {
+    pub fn element(&self) -> Option<&ElementData> {
+        match &self.data {
+            RenderNodeData::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        match &self.data {
+            RenderNodeData::Text(text) => Some(&text.text),
+            _ => None,
+        }
+    }
 }
 
 impl<C: HasLayouter> Debug for RenderTreeNode<C> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RenderTreeNode")
+            .field("id", &self.id)
+            .field("dom_id", &self.dom_id)
+            .field("data", &self.data)
+            .field("children", &self.children)
             .finish()
     }
 }
 
+/// What a render tree node is generated from
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderNodeData {
+    Document,
+    Element(ElementData),
+    Text(TextData),
+    /// The box of a pseudo element, whose content is generated from its style
+    PseudoElement(PseudoElement),
+    /// An anonymous box, generated to make the box tree well-formed (CSS 2.1, 9.2.1.1)
+    Anonymous,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementData {
+    pub name: String,
+    pub namespace: String,
+    pub attributes: HashMap<String, String>,
+}
+
+impl ElementData {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// Returns true for the elements whose content is replaced by an image or a drawing surface
+    pub fn is_replaced(&self) -> bool {
+        matches!(self.name.as_str(), "img" | "video" | "canvas")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextData {
+    pub text: String,
+}
+
 /// Generates a render tree for the given document based on its loaded stylesheets
 pub fn generate_render_tree<C: HasDocument + HasRenderTree<LayoutTree = RenderTree<C>, RenderTree = RenderTree<C>>>(
     document: DocumentHandle<C>,
@@ -86,3 +423,79 @@ pub fn generate_render_tree<C: HasDocument + HasRenderTree<LayoutTree = RenderTr
 
     Ok(render_tree)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        html5::document::document_impl::DocumentImpl,
+        shared::byte_stream::Location,
+        testing::{body_tree, box_tree, TestDocument},
+    };
+
+    #[test]
+    fn hidden_nodes_are_skipped() {
+        let doc = TestDocument::with_css(
+            "<html><head><title>Title</title></head><body>\
+             <div id=\"a\">one<span class=\"hidden\">two</span><script>three()</script></div>\
+             <p class=\"hidden\"><b>four</b></p></body></html>",
+            ".hidden { display: none }",
+        );
+        // The tokenizer does not read comments yet
+        let comment = DocumentImpl::new_comment_node(doc.handle.clone(), "five", Location::default());
+        doc.handle
+            .clone()
+            .get_mut()
+            .register_node_at(comment, doc.id("a"), None);
+        let tree = doc.render_tree();
+
+        assert_eq!(box_tree(&tree, tree.root), "#document[html[body[div[anon[\"one\"]]]]]");
+    }
+
+    #[test]
+    fn display_contents() {
+        let doc = TestDocument::with_css(
+            "<div id=\"a\"><section id=\"b\"><p>one</p></section><p>two</p></div>",
+            "section { display: contents }",
+        );
+        let tree = doc.render_tree();
+
+        assert_eq!(body_tree(&tree), "body[div[p[anon[\"one\"]] p[anon[\"two\"]]]]");
+        assert_eq!(tree.node_for_dom(doc.id("b")), None);
+    }
+
+    #[test]
+    fn nodes_are_mapped_to_the_dom() {
+        let doc = TestDocument::with_css("<div id=\"a\" title=\"x\">text</div>", "div { color: green }");
+        let tree = doc.render_tree();
+
+        let div = &tree.nodes[&tree.node_for_dom(doc.id("a")).unwrap()];
+        assert_eq!(div.dom_id, Some(doc.id("a")));
+        let element = div.element().unwrap();
+        assert_eq!(element.name, "div");
+        assert_eq!(element.attribute("title"), Some("x"));
+        assert_eq!(
+            div.properties.get("color").and_then(|color| color.as_color()),
+            Some((0, 128, 0, 255))
+        );
+        assert_eq!(
+            div.properties.get("display").and_then(|display| display.as_keyword()),
+            Some("block")
+        );
+
+        // The text is wrapped in an anonymous inline box
+        let anonymous = &tree.nodes[&div.children[0]];
+        assert_eq!(
+            (anonymous.data.clone(), anonymous.dom_id),
+            (RenderNodeData::Anonymous, None)
+        );
+        let text = &tree.nodes[&anonymous.children[0]];
+        assert_eq!(text.parent, Some(anonymous.id));
+        assert_eq!(text.text(), Some("text"));
+        assert_eq!(tree.node_for_dom(text.dom_id.unwrap()), Some(text.id));
+
+        let body = &tree.nodes[&div.parent.unwrap()];
+        assert_eq!(body.element().map(|element| element.name.as_str()), Some("body"));
+        assert_eq!(tree.nodes[&tree.root].data, RenderNodeData::Document);
+    }
+}
@@ -0,0 +1,511 @@
+use crate::{
+    interface::{
+        config::{HasCssSystem, HasLayouter},
+        css3::{CssProperty, CssPropertyMap, CssSystem},
+    },
+    shared::node::NodeId,
+    util::render_tree::{RenderNodeData, RenderTree},
+};
+
+/// Properties of a table that are used on its table wrapper box instead of the table box (CSS 2.1,
+/// 17.4). The table box gets their initial values.
+const TABLE_WRAPPER_PROPERTIES: &[&str] = &[
+    "position",
+    "float",
+    "clear",
+    "z-index",
+    "margin-top",
+    "margin-right",
+    "margin-bottom",
+    "margin-left",
+    "top",
+    "right",
+    "bottom",
+    "left",
+    "flex-grow",
+    "flex-shrink",
+    "flex-basis",
+    "order",
+    "align-self",
+    "justify-self",
+    "grid-row-start",
+    "grid-row-end",
+    "grid-column-start",
+    "grid-column-end",
+];
+
+/// The role of a box in its parent, as far as anonymous box generation is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BoxKind {
+    /// Block-level boxes in normal flow, including table wrapper boxes
+    Block,
+    /// Inline boxes, which are split around the block-level boxes they contain
+    Inline,
+    /// Atomic inline-level boxes, like `inline-block`
+    AtomicInline,
+    Text,
+    /// Floats and absolutely positioned boxes
+    OutOfFlow,
+    Table,
+    InlineTable,
+    RowGroup,
+    Row,
+    Cell,
+    Column,
+    ColumnGroup,
+    Caption,
+}
+
+impl BoxKind {
+    /// Returns true for the boxes that can be a child of a table box (CSS 2.1, 17.2.1)
+    fn is_proper_table_child(self) -> bool {
+        matches!(
+            self,
+            BoxKind::RowGroup | BoxKind::Row | BoxKind::Column | BoxKind::ColumnGroup | BoxKind::Caption
+        )
+    }
+
+    pub(super) fn is_inline_level(self) -> bool {
+        matches!(
+            self,
+            BoxKind::Inline | BoxKind::AtomicInline | BoxKind::Text | BoxKind::InlineTable
+        )
+    }
+}
+
+type MatchedStyles<C> = <<C as HasCssSystem>::CssSystem as CssSystem>::MatchedStyles;
+
+impl<C: HasLayouter<LayoutTree = Self>> RenderTree<C> {
+    /// Generates the anonymous boxes that make the box tree well-formed: inline boxes are split
+    /// around block-level boxes, mixed inline-level and block-level content is wrapped in anonymous
+    /// block boxes, text is wrapped in anonymous inline boxes, and missing table boxes and table
+    /// wrapper boxes are generated (CSS 2.1, 9.2 and 17.2.1).
+    pub(super) fn generate_anonymous_boxes(&mut self, matched: &MatchedStyles<C>) {
+        // Children are fixed up before their parents, so the parent sees the final boxes
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            if let Some(node) = self.nodes.get(&id) {
+                stack.extend(node.children.iter().copied());
+            }
+        }
+
+        for id in order.into_iter().rev() {
+            self.fixup_children(id, matched);
+        }
+    }
+
+    fn fixup_children(&mut self, id: NodeId, matched: &MatchedStyles<C>) {
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return;
+        };
+        let children = std::mem::take(&mut node.children);
+
+        let children = self.split_inline_boxes(children);
+        let children = self.fixup_table_children(id, children, matched);
+        let children = self.wrap_tables(children, matched);
+        let children = self.wrap_inline_content(id, children, matched);
+
+        self.set_children(id, children);
+    }
+
+    /// Breaks inline boxes around the block-level boxes they contain (CSS 2.1, 9.2.1.1). The
+    /// block-level boxes are moved up between the pieces of the inline box, and each piece after
+    /// the first is a copy of the inline box.
+    fn split_inline_boxes(&mut self, children: Vec<NodeId>) -> Vec<NodeId> {
+        let mut result = Vec::with_capacity(children.len());
+
+        for child in children {
+            let contains_block = self.box_kind(child) == BoxKind::Inline
+                && self.nodes[&child]
+                    .children
+                    .iter()
+                    .any(|id| self.box_kind(*id) == BoxKind::Block);
+            if !contains_block {
+                result.push(child);
+                continue;
+            }
+
+            let Some(node) = self.nodes.get_mut(&child) else {
+                continue;
+            };
+            let grandchildren = std::mem::take(&mut node.children);
+            let mut piece = Some(child);
+            let mut piece_children = Vec::new();
+
+            for grandchild in grandchildren {
+                if self.box_kind(grandchild) == BoxKind::Block {
+                    if let Some(piece) = piece.take() {
+                        self.set_children(piece, std::mem::take(&mut piece_children));
+                        result.push(piece);
+                    }
+                    result.push(grandchild);
+                } else {
+                    if piece.is_none() {
+                        piece = Some(self.copy_box(child));
+                    }
+                    piece_children.push(grandchild);
+                }
+            }
+
+            if let Some(piece) = piece {
+                self.set_children(piece, piece_children);
+                result.push(piece);
+            }
+        }
+
+        result
+    }
+
+    /// Removes irrelevant boxes and generates missing table wrappers and parents (CSS 2.1, 17.2.1)
+    fn fixup_table_children(&mut self, id: NodeId, children: Vec<NodeId>, matched: &MatchedStyles<C>) -> Vec<NodeId> {
+        let parent = self.box_kind(id);
+
+        // Columns have no content, column groups only contain columns, and white space between the
+        // parts of a table is not rendered
+        let (children, removed): (Vec<_>, Vec<_>) = children.into_iter().partition(|child| match parent {
+            BoxKind::Column => false,
+            BoxKind::ColumnGroup => self.box_kind(*child) == BoxKind::Column,
+            BoxKind::Table | BoxKind::InlineTable | BoxKind::RowGroup | BoxKind::Row => {
+                !self.is_whitespace_text(*child)
+            }
+            _ => true,
+        });
+        for child in removed {
+            self.remove_subtree(child);
+        }
+
+        let mut children = match parent {
+            BoxKind::Table | BoxKind::InlineTable => {
+                self.wrap_runs(id, children, |kind| !kind.is_proper_table_child(), "table-row", matched)
+            }
+            BoxKind::RowGroup => self.wrap_runs(id, children, |kind| kind != BoxKind::Row, "table-row", matched),
+            BoxKind::Row => self.wrap_runs(id, children, |kind| kind != BoxKind::Cell, "table-cell", matched),
+            _ => children,
+        };
+
+        if parent != BoxKind::Row {
+            children = self.wrap_runs(id, children, |kind| kind == BoxKind::Cell, "table-row", matched);
+        }
+
+        if !matches!(parent, BoxKind::Table | BoxKind::InlineTable) {
+            let display = if parent == BoxKind::Inline {
+                "inline-table"
+            } else {
+                "table"
+            };
+            let misparented = |kind: BoxKind| match (parent, kind) {
+                (BoxKind::RowGroup, BoxKind::Row) | (BoxKind::ColumnGroup, BoxKind::Column) => false,
+                _ => kind.is_proper_table_child(),
+            };
+            children = self.wrap_runs(id, children, misparented, display, matched);
+        }
+
+        children
+    }
+
+    /// Places each table box in a table wrapper box, together with its captions (CSS 2.1, 17.4)
+    fn wrap_tables(&mut self, children: Vec<NodeId>, matched: &MatchedStyles<C>) -> Vec<NodeId> {
+        children
+            .into_iter()
+            .map(|child| match self.box_kind(child) {
+                BoxKind::Table | BoxKind::InlineTable => self.table_wrapper(child, matched),
+                _ => child,
+            })
+            .collect()
+    }
+
+    fn table_wrapper(&mut self, table: NodeId, matched: &MatchedStyles<C>) -> NodeId {
+        let display = match self.box_kind(table) {
+            BoxKind::InlineTable => "inline-block",
+            _ => "block",
+        };
+
+        let Some(table_node) = self.nodes.get_mut(&table) else {
+            return table;
+        };
+        let mut properties = C::CssSystem::anonymous_box_style(matched, &table_node.properties, display);
+        for name in TABLE_WRAPPER_PROPERTIES {
+            if let (Some(outer), Some(inner)) = (table_node.properties.get(name), properties.get(name)) {
+                let (outer, inner) = (outer.clone(), inner.clone());
+                properties.insert(name, outer);
+                table_node.properties.insert(name, inner);
+            }
+        }
+
+        let children = std::mem::take(&mut table_node.children);
+        let (captions, rest): (Vec<_>, Vec<_>) = children
+            .into_iter()
+            .partition(|child| self.box_kind(*child) == BoxKind::Caption);
+        self.set_children(table, rest);
+
+        let (bottom, top): (Vec<_>, Vec<_>) = captions.into_iter().partition(|caption| {
+            self.nodes[caption]
+                .properties
+                .get("caption-side")
+                .and_then(|side| side.as_keyword())
+                == Some("bottom")
+        });
+
+        let wrapper = self.create_node(None, properties, RenderNodeData::Anonymous);
+        self.set_children(wrapper, top.into_iter().chain([table]).chain(bottom).collect());
+
+        wrapper
+    }
+
+    /// Wraps inline-level content in anonymous boxes (CSS 2.1, 9.2.1.1 and 9.2.2.1). In a block
+    /// container with block-level children, runs of inline-level boxes are wrapped in anonymous
+    /// block boxes. Text directly inside a block container is wrapped in anonymous inline boxes,
+    /// and text inside other containers, like flex and grid containers, in anonymous block boxes.
+    fn wrap_inline_content(&mut self, id: NodeId, children: Vec<NodeId>, matched: &MatchedStyles<C>) -> Vec<NodeId> {
+        if !self.is_block_container(id) {
+            return match self.box_kind(id) {
+                BoxKind::Inline | BoxKind::Text => children,
+                _ => self.wrap_runs(id, children, |kind| kind == BoxKind::Text, "block", matched),
+            };
+        }
+
+        if !children.iter().any(|child| self.box_kind(*child) == BoxKind::Block) {
+            return self.wrap_runs(id, children, |kind| kind == BoxKind::Text, "inline", matched);
+        }
+
+        // Floats and absolutely positioned boxes do not need a box of their own, so they join
+        // the surrounding inline-level content
+        let mut result = Vec::with_capacity(children.len());
+        for (inline, run) in self.runs(children, |kind| kind.is_inline_level() || kind == BoxKind::OutOfFlow) {
+            let in_flow = run.iter().any(|child| self.box_kind(*child) != BoxKind::OutOfFlow);
+            if inline && in_flow {
+                result.push(self.anonymous_box(id, "block", run, matched));
+            } else {
+                result.extend(run);
+            }
+        }
+
+        result
+    }
+
+    /// Wraps each run of consecutive children for which `in_run` returns true in a new anonymous box
+    fn wrap_runs(
+        &mut self,
+        parent: NodeId,
+        children: Vec<NodeId>,
+        in_run: impl Fn(BoxKind) -> bool,
+        display: &str,
+        matched: &MatchedStyles<C>,
+    ) -> Vec<NodeId> {
+        let mut result = Vec::with_capacity(children.len());
+
+        for (wrap, run) in self.runs(children, in_run) {
+            if wrap {
+                result.push(self.anonymous_box(parent, display, run, matched));
+            } else {
+                result.extend(run);
+            }
+        }
+
+        result
+    }
+
+    /// Splits the children into runs of consecutive boxes for which `in_run` gives the same result
+    fn runs(&self, children: Vec<NodeId>, in_run: impl Fn(BoxKind) -> bool) -> Vec<(bool, Vec<NodeId>)> {
+        let mut runs: Vec<(bool, Vec<NodeId>)> = Vec::new();
+
+        for child in children {
+            let value = in_run(self.box_kind(child));
+            match runs.last_mut() {
+                Some((last, run)) if *last == value => run.push(child),
+                _ => runs.push((value, vec![child])),
+            }
+        }
+
+        runs
+    }
+
+    /// Creates an anonymous box with the given children, which inherits its style from `parent`.
+    /// The children of the new box are fixed up as well.
+    fn anonymous_box(
+        &mut self,
+        parent: NodeId,
+        display: &str,
+        children: Vec<NodeId>,
+        matched: &MatchedStyles<C>,
+    ) -> NodeId {
+        let properties = C::CssSystem::anonymous_box_style(matched, &self.nodes[&parent].properties, display);
+        let id = self.create_node(None, properties, RenderNodeData::Anonymous);
+        self.set_children(id, children);
+        self.fixup_children(id, matched);
+
+        id
+    }
+
+    /// Creates a copy of a box without its children, for a piece of a split inline box
+    fn copy_box(&mut self, id: NodeId) -> NodeId {
+        let node = &self.nodes[&id];
+        let (dom_id, properties, data) = (node.dom_id, node.properties.clone(), node.data.clone());
+
+        self.create_node(dom_id, properties, data)
+    }
+
+    fn set_children(&mut self, id: NodeId, children: Vec<NodeId>) {
+        for child in &children {
+            if let Some(node) = self.nodes.get_mut(child) {
+                node.parent = Some(id);
+            }
+        }
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.children = children;
+        }
+    }
+
+    /// Removes a box and all its descendants from the tree
+    pub(super) fn remove_subtree(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let Some(node) = self.nodes.remove(&id) else {
+                continue;
+            };
+            if let Some(dom_id) = node.dom_id {
+                if self.dom_nodes.get(&dom_id) == Some(&id) {
+                    self.dom_nodes.remove(&dom_id);
+                }
+            }
+            stack.extend(node.children);
+        }
+    }
+
+    fn display(&self, id: NodeId) -> Option<&str> {
+        self.nodes.get(&id)?.properties.get("display")?.as_keyword()
+    }
+
+    pub(super) fn box_kind(&self, id: NodeId) -> BoxKind {
+        let Some(node) = self.nodes.get(&id) else {
+            return BoxKind::Block;
+        };
+        match node.data {
+            RenderNodeData::Document => return BoxKind::Block,
+            RenderNodeData::Text(_) => return BoxKind::Text,
+            RenderNodeData::Element(_) | RenderNodeData::PseudoElement(_) | RenderNodeData::Anonymous => {}
+        }
+
+        let kind = match self.display(id).unwrap_or_default() {
+            // Replaced elements are laid out as a whole, like inline blocks
+            "inline" if self.is_replaced(id) => BoxKind::AtomicInline,
+            "inline" | "ruby" => BoxKind::Inline,
+            "inline-block" | "inline-flex" | "inline-grid" | "inline-flow-root" => BoxKind::AtomicInline,
+            "table" => return BoxKind::Table,
+            "inline-table" => return BoxKind::InlineTable,
+            "table-row-group" | "table-header-group" | "table-footer-group" => BoxKind::RowGroup,
+            "table-row" => BoxKind::Row,
+            "table-cell" => BoxKind::Cell,
+            "table-column" => BoxKind::Column,
+            "table-column-group" => BoxKind::ColumnGroup,
+            "table-caption" => BoxKind::Caption,
+            _ => BoxKind::Block,
+        };
+
+        let keyword = |name: &str| node.properties.get(name).and_then(|property| property.as_keyword());
+        let floated = keyword("float").is_some_and(|float| float != "none");
+        let positioned = keyword("position").is_some_and(|position| matches!(position, "absolute" | "fixed"));
+
+        if floated || positioned {
+            BoxKind::OutOfFlow
+        } else {
+            kind
+        }
+    }
+
+    /// Returns true when the box contains only block-level boxes or only inline-level boxes
+    pub(super) fn is_block_container(&self, id: NodeId) -> bool {
+        match self.nodes.get(&id).map(|node| &node.data) {
+            Some(RenderNodeData::Document) => true,
+            Some(RenderNodeData::Element(_) | RenderNodeData::PseudoElement(_) | RenderNodeData::Anonymous) => {
+                matches!(
+                    self.display(id),
+                    Some(
+                        "block"
+                            | "flow-root"
+                            | "list-item"
+                            | "run-in"
+                            | "inline-block"
+                            | "inline-flow-root"
+                            | "table-cell"
+                            | "table-caption"
+                    )
+                )
+            }
+            _ => false,
+        }
+    }
+
+    pub(super) fn is_whitespace_text(&self, id: NodeId) -> bool {
+        self.nodes[&id]
+            .text()
+            .is_some_and(|text| text.chars().all(|c| matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0c')))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{body_tree, TestDocument};
+
+    fn boxes(html: &str, css: &str) -> String {
+        body_tree(&TestDocument::with_css(html, css).render_tree())
+    }
+
+    #[test]
+    fn anonymous_blocks() {
+        assert_eq!(
+            boxes("<div>text<p>para</p>more</div>", ""),
+            r#"body[div[anon[anon["text"]] p[anon["para"]] anon[anon["more"]]]]"#
+        );
+        // Inline boxes are split around the blocks they contain
+        assert_eq!(
+            boxes("<div><span>a<p>b</p>c</span></div>", ""),
+            r#"body[div[anon[span["a"]] p[anon["b"]] anon[span["c"]]]]"#
+        );
+        // Floats join the surrounding inline content, but need no anonymous block of their own
+        let css = ".f { float: left }";
+        assert_eq!(
+            boxes(r#"<div>a<i class="f">b</i><p>c</p></div>"#, css),
+            r#"body[div[anon[anon["a"] i[anon["b"]]] p[anon["c"]]]]"#
+        );
+        assert_eq!(
+            boxes(r#"<div><i class="f">a</i><p>b</p></div>"#, css),
+            r#"body[div[i[anon["a"]] p[anon["b"]]]]"#
+        );
+        // Text in a flex container becomes an anonymous flex item
+        assert_eq!(
+            boxes(r#"<div class="flex">a<span>b</span></div>"#, ".flex { display: flex }"),
+            r#"body[div[anon[anon["a"]] span["b"]]]"#
+        );
+    }
+
+    #[test]
+    fn tables() {
+        // Table wrapper box and missing row
+        assert_eq!(
+            boxes("<table><td>a</td></table>", ""),
+            r#"body[anon[table[anon[td[anon["a"]]]]]]"#
+        );
+        // Missing table, in a block and in an inline box
+        assert_eq!(
+            boxes("<div><tr><td>a</td></tr></div>", ""),
+            r#"body[div[anon[anon[tr[td[anon["a"]]]]]]]"#
+        );
+        assert_eq!(
+            boxes("<span><td>a</td></span>", ""),
+            r#"body[span[anon[anon[anon[td[anon["a"]]]]]]]"#
+        );
+        // Captions move to the table wrapper box, and white space between table parts is dropped
+        assert_eq!(
+            boxes("<table> <caption>c</caption> <tr> <td>a</td> </tr> </table>", ""),
+            r#"body[anon[caption[anon["c"]] table[tr[td[anon["a"]]]]]]"#
+        );
+        // Missing cell
+        assert_eq!(
+            boxes("<table><tr>a<td>b</td></tr></table>", ""),
+            r#"body[anon[table[tr[anon[anon["a"]] td[anon["b"]]]]]]"#
+        );
+    }
+}
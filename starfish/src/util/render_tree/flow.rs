@@ -0,0 +1,877 @@
+use taffy::{
+    compute_leaf_layout, AvailableSpace, BoxSizing, CollapsibleMarginSet, Layout, LayoutInput, LayoutOutput,
+    LayoutPartialTree, Line, MaybeResolve, Point, Rect, RequestedAxis, ResolveOrZero, RunMode, Size, SizingMode, Style,
+};
+
+use crate::{
+    interface::{
+        config::HasLayouter,
+        css3::{CssProperty, CssPropertyMap},
+    },
+    shared::node::NodeId,
+    taffy::TaffyLayouter,
+    util::render_tree::{
+        layout::{node_id, taffy_id, LayoutMode},
+        RenderNodeData, RenderTree,
+    },
+};
+
+use floats::{FloatContext, FloatSide};
+
+mod floats;
+
+/// The state of a block formatting context while its boxes are laid out. Positions are relative
+/// to the border box of its root.
+struct Flow {
+    floats: FloatContext,
+    /// The bottom of the content laid out so far
+    y: f32,
+    /// The margins below the content, which collapse with the margins that follow them
+    margins: CollapsibleMarginSet,
+    /// Each position where the margins were resolved, which is where the box that caused it starts
+    resolved: Vec<f32>,
+    /// Floats that are placed when the margins before them are resolved, as their position depends
+    /// on where the margins end up: the float and the containing block it is placed in
+    pending: Vec<(NodeId, f32, f32)>,
+    /// Floats placed in the formatting context, until their parent is laid out
+    placed: Vec<(NodeId, Layout)>,
+    /// The right edge of the widest content
+    right: f32,
+    first_baseline: Option<f32>,
+    run_mode: RunMode,
+}
+
+impl Flow {
+    /// Returns where content would start, below the margins that are not resolved yet
+    fn next_position(&self) -> f32 {
+        self.y + self.margins.resolve()
+    }
+}
+
+/// The size of the content of a block formatting context
+struct FlowOutput {
+    width: f32,
+    height: f32,
+    first_baseline: Option<f32>,
+}
+
+/// The floats of a block formatting context around the lines of an inline formatting context in it
+pub(super) struct FloatArea<'a> {
+    floats: &'a mut FloatContext,
+    /// Position of the border box of the block container of the lines in the formatting context
+    offset: Point<f32>,
+    run_mode: RunMode,
+}
+
+impl FloatArea<'_> {
+    /// Returns the part of the content box from `left` to `right` that is not taken by floats, for
+    /// a line from `y` down to `y + height`. Positions are relative to the block container.
+    pub(super) fn line_space(&self, y: f32, height: f32, left: f32, right: f32) -> (f32, f32) {
+        let offset = self.offset;
+        let (left, right) = self
+            .floats
+            .available(offset.y + y, height, offset.x + left, offset.x + right);
+        (left - offset.x, right - offset.x)
+    }
+
+    /// Returns true when floats are next to a line from `y` down to `y + height`
+    pub(super) fn intrudes(&self, y: f32, height: f32) -> bool {
+        self.floats.intrudes(self.offset.y + y, height)
+    }
+
+    /// Returns the first position below `y` where a float ends
+    pub(super) fn next_edge(&self, y: f32) -> Option<f32> {
+        self.floats
+            .next_edge(self.offset.y + y)
+            .map(|edge| edge - self.offset.y)
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> RenderTree<C> {
+    /// Lays out the root of a block formatting context that contains floats, together with all
+    /// boxes that take part in it. Taffy lays out the contexts without floats.
+    pub(super) fn compute_flow_layout(&mut self, id: taffy::NodeId, inputs: LayoutInput) -> LayoutOutput {
+        let root = node_id(id);
+        let style = self.nodes[&root].layout.style.clone();
+
+        let mut first_baseline = None;
+        let mut output = compute_leaf_layout(inputs, &style, |known, available| {
+            let width = match (known.width, available.width) {
+                (Some(width), _) | (None, AvailableSpace::Definite(width)) => width,
+                (None, available) => self.flow_intrinsic_width(root, available),
+            };
+            let flow = self.layout_flow(root, width, Point::ZERO, known.height, RunMode::ComputeSize);
+            first_baseline = flow.first_baseline;
+            Size {
+                width: flow.width,
+                height: flow.height,
+            }
+        });
+
+        let insets = style.padding.resolve_or_zero(inputs.parent_size.width)
+            + style.border.resolve_or_zero(inputs.parent_size.width);
+        output.first_baselines.y = first_baseline.map(|baseline| insets.top + baseline);
+        if inputs.run_mode != RunMode::PerformLayout {
+            return output;
+        }
+
+        let width = output.size.width - insets.left - insets.right;
+        let height = inputs
+            .known_dimensions
+            .height
+            .map(|_| output.size.height - insets.top - insets.bottom);
+        let origin = Point {
+            x: insets.left,
+            y: insets.top,
+        };
+
+        let flow = self.layout_flow(root, width, origin, height, RunMode::PerformLayout);
+        output.first_baselines.y = flow.first_baseline;
+
+        output
+    }
+
+    /// Lays out the content of the root of a block formatting context, in its content box at
+    /// `origin` with the given width
+    fn layout_flow(
+        &mut self,
+        root: NodeId,
+        width: f32,
+        origin: Point<f32>,
+        height: Option<f32>,
+        run_mode: RunMode,
+    ) -> FlowOutput {
+        let mut flow = Flow {
+            floats: FloatContext::default(),
+            y: origin.y,
+            margins: CollapsibleMarginSet::ZERO,
+            resolved: Vec::new(),
+            pending: Vec::new(),
+            placed: Vec::new(),
+            right: origin.x,
+            first_baseline: None,
+            run_mode,
+        };
+
+        match self.layout_mode(root) {
+            LayoutMode::Inline => {
+                let height = self.flow_lines(root, &mut flow, Point::ZERO, width, origin);
+                flow.y = origin.y + height;
+            }
+            _ => {
+                // The root is at the origin of the formatting context
+                for (child, layout) in self.flow_children(root, &mut flow, origin.x, width, height) {
+                    self.set_unrounded_layout(taffy_id(child), &layout);
+                }
+            }
+        }
+
+        // The root contains the margins of its content, and its floats
+        self.place_pending_floats(&mut flow);
+        let bottom = flow.next_position().max(flow.floats.bottom().unwrap_or(origin.y));
+        let right = flow.right.max(flow.floats.right().unwrap_or(origin.x));
+        FlowOutput {
+            width: right - origin.x,
+            height: bottom - origin.y,
+            first_baseline: flow.first_baseline,
+        }
+    }
+
+    /// Lays out the block-level children of a box in normal flow. `left` and `width` are the content
+    /// box of the box. Returns the layout of the children, positioned in the formatting context.
+    fn flow_children(
+        &mut self,
+        parent: NodeId,
+        flow: &mut Flow,
+        left: f32,
+        width: f32,
+        height: Option<f32>,
+    ) -> Vec<(NodeId, Layout)> {
+        let children = self.nodes[&parent].children.clone();
+        let mut layouts = Vec::with_capacity(children.len());
+
+        for child in children {
+            let layout = if matches!(self.position(child), "absolute" | "fixed") {
+                // Absolutely positioned boxes are placed later, this is their static position
+                let margin = self.nodes[&child].layout.style.margin.resolve_or_zero(Some(width));
+                Layout {
+                    location: Point {
+                        x: left + margin.left,
+                        y: flow.next_position() + margin.top,
+                    },
+                    margin,
+                    ..Layout::new()
+                }
+            } else if self.float_side(child, "float").is_some() {
+                flow.pending.push((child, left, width));
+                Layout::new()
+            } else if self.establishes_bfc(child) || !self.is_block_container(child) {
+                self.flow_root(child, flow, left, width, height)
+            } else {
+                self.flow_block(child, flow, left, width, height)
+            };
+
+            layouts.push((child, layout));
+        }
+
+        // Floats after the last content of the box are placed before the box ends
+        if flow
+            .pending
+            .iter()
+            .any(|(float, _, _)| layouts.iter().any(|(child, _)| child == float))
+        {
+            self.place_pending_floats(flow);
+        }
+        for (child, layout) in &mut layouts {
+            if let Some(index) = flow.placed.iter().position(|(float, _)| float == child) {
+                *layout = flow.placed.swap_remove(index).1;
+            }
+        }
+
+        layouts
+    }
+
+    /// Resolves the collapsed margins, because content follows them, and places the floats that
+    /// come before that content
+    fn resolve_margins(&mut self, flow: &mut Flow) -> f32 {
+        flow.y = flow.next_position();
+        flow.margins = CollapsibleMarginSet::ZERO;
+        flow.resolved.push(flow.y);
+        self.place_pending_floats(flow);
+        flow.y
+    }
+
+    /// Places the floats that wait for the margins before them, at the position after the margins
+    fn place_pending_floats(&mut self, flow: &mut Flow) {
+        let y = flow.next_position();
+        for (id, left, width) in std::mem::take(&mut flow.pending) {
+            let layout = self.place_float(id, &mut flow.floats, y, left, width, flow.run_mode);
+            flow.right = flow
+                .right
+                .max(layout.location.x + layout.size.width + layout.margin.right);
+            flow.placed.push((id, layout));
+        }
+    }
+
+    /// Moves a box with the given top margin below the floats on the given side (CSS 2.1, 9.5.2),
+    /// and returns true when it has clearance. The floats before the box are placed above its
+    /// margin. With clearance, the margins before the box are resolved where they are, its own top
+    /// margin is ignored, and the box starts below the floats.
+    fn clear(&mut self, flow: &mut Flow, side: FloatSide, margin_top: f32) -> bool {
+        self.place_pending_floats(flow);
+        let Some(bottom) = flow.floats.clearance(side) else {
+            return false;
+        };
+        if bottom <= flow.y + flow.margins.collapse_with_margin(margin_top).resolve() {
+            return false;
+        }
+
+        flow.margins = CollapsibleMarginSet::ZERO;
+        flow.resolved.push(flow.y);
+        flow.y = bottom;
+        true
+    }
+
+    /// Lays out a block container in normal flow that takes part in the block formatting context of
+    /// its parent, together with its content. Its margins collapse with the ones of its children
+    /// when nothing separates them (CSS 2.1, 8.3.1).
+    fn flow_block(&mut self, id: NodeId, flow: &mut Flow, left: f32, width: f32, height: Option<f32>) -> Layout {
+        let style = self.nodes[&id].layout.style.clone();
+        let border = style.border.resolve_or_zero(Some(width));
+        let padding = style.padding.resolve_or_zero(Some(width));
+        let edges = border + padding;
+        let box_width = used_width(&style, width, |available| available);
+        let margin = block_margins(&style, width, box_width);
+        let x = left + margin.left;
+        let content_width = (box_width - edges.left - edges.right).max(0.0);
+        let box_height = border_box(
+            &style,
+            style.size.height.maybe_resolve(height),
+            edges.top + edges.bottom,
+        );
+
+        let cleared = self
+            .float_side(id, "clear")
+            .is_some_and(|side| self.clear(flow, side, margin.top));
+        if !cleared {
+            flow.margins = flow.margins.collapse_with_margin(margin.top);
+        }
+        let start = flow.resolved.len();
+        let hypothetical_top = flow.next_position();
+
+        let mode = self.layout_mode(id);
+        if edges.top > 0.0 || matches!(mode, LayoutMode::Inline) {
+            self.resolve_margins(flow);
+            flow.y += edges.top;
+        }
+        let content_height = box_height.map(|height| (height - edges.top - edges.bottom).max(0.0));
+
+        let children = match mode {
+            LayoutMode::Inline => {
+                let top = flow.resolved[start];
+                let origin = Point {
+                    x: edges.left,
+                    y: edges.top,
+                };
+                let height = self.flow_lines(id, flow, Point { x, y: top }, content_width, origin);
+                flow.y += height;
+                Vec::new()
+            }
+            _ => self.flow_children(id, flow, x + edges.left, content_width, content_height),
+        };
+
+        let resolved = flow.resolved.len() > start;
+        let top = flow.resolved.get(start).copied().unwrap_or(hypothetical_top);
+        // Without a bottom border or padding, the margins of the last child collapse with the
+        // bottom margin of the box
+        let auto_height = match (resolved, edges.bottom > 0.0) {
+            (false, _) => 0.0,
+            (true, false) => flow.y - top,
+            (true, true) => flow.next_position() + edges.bottom - top,
+        };
+        let min_height = border_box(
+            &style,
+            style.min_size.height.maybe_resolve(height),
+            edges.top + edges.bottom,
+        );
+        let max_height = border_box(
+            &style,
+            style.max_size.height.maybe_resolve(height),
+            edges.top + edges.bottom,
+        );
+        let used_height = box_height
+            .unwrap_or(auto_height)
+            .min(max_height.unwrap_or(f32::INFINITY))
+            .max(min_height.unwrap_or(0.0));
+
+        let collapses = resolved && box_height.is_none() && used_height == auto_height && edges.bottom == 0.0;
+        if !collapses && (resolved || used_height > 0.0) {
+            // The box separates the margins of its content from the ones that follow it
+            flow.y = top + used_height;
+            flow.margins = CollapsibleMarginSet::ZERO;
+        }
+        flow.margins = flow.margins.collapse_with_margin(margin.bottom);
+        flow.right = flow.right.max(x + box_width + margin.right);
+
+        for (child, mut layout) in children {
+            layout.location.x -= x;
+            layout.location.y -= top;
+            self.set_unrounded_layout(taffy_id(child), &layout);
+        }
+
+        Layout {
+            location: Point { x, y: top },
+            size: Size {
+                width: box_width,
+                height: used_height,
+            },
+            border,
+            padding,
+            margin,
+            ..Layout::new()
+        }
+    }
+
+    /// Lays out a block-level box in normal flow that establishes an independent formatting
+    /// context, like a `flow-root`, a flex container or a table. Its border box does not overlap
+    /// floats, so it is made narrower or moved down until it fits next to them (CSS 2.1, 9.5).
+    fn flow_root(&mut self, id: NodeId, flow: &mut Flow, left: f32, width: f32, height: Option<f32>) -> Layout {
+        let style = self.nodes[&id].layout.style.clone();
+        let parent_size = Size {
+            width: Some(width),
+            height,
+        };
+
+        let margin_top = style.margin.top.resolve_or_zero(Some(width));
+        let cleared = self
+            .float_side(id, "clear")
+            .is_some_and(|side| self.clear(flow, side, margin_top));
+        if !cleared {
+            flow.margins = flow.margins.collapse_with_margin(margin_top);
+        }
+        let mut y = self.resolve_margins(flow);
+
+        let (start, box_width, margin, output) = loop {
+            let (start, end) = flow.floats.available(y, 0.0, left, left + width);
+            let margins =
+                style.margin.left.resolve_or_zero(Some(width)) + style.margin.right.resolve_or_zero(Some(width));
+            let box_width = used_width(&style, width, |available| {
+                let available = available.min(end - start - margins);
+                match style.item_is_table {
+                    true => self.shrink_to_fit_width(id, parent_size, available),
+                    false => available,
+                }
+            });
+            let margin = block_margins(&style, end - start, box_width);
+            let output = self.compute_child_layout(
+                taffy_id(id),
+                LayoutInput {
+                    known_dimensions: Size {
+                        width: Some(box_width),
+                        height: None,
+                    },
+                    parent_size,
+                    available_space: Size {
+                        width: AvailableSpace::Definite(box_width),
+                        height: AvailableSpace::MaxContent,
+                    },
+                    sizing_mode: SizingMode::InherentSize,
+                    axis: RequestedAxis::Both,
+                    run_mode: flow.run_mode,
+                    vertical_margins_are_collapsible: Line::FALSE,
+                },
+            );
+
+            let (start, end) = flow.floats.available(y, output.size.height, left, left + width);
+            let fits = margin.left + box_width + margin.right <= end - start;
+            match flow.floats.next_edge(y) {
+                Some(edge) if !fits && flow.floats.intrudes(y, output.size.height) => y = edge,
+                _ => break (start, box_width, margin, output),
+            }
+        };
+
+        let x = start + margin.left;
+        flow.y = y + output.size.height;
+        flow.margins = CollapsibleMarginSet::from_margin(margin.bottom);
+        flow.right = flow.right.max(x + box_width + margin.right);
+        if flow.first_baseline.is_none() {
+            flow.first_baseline = output.first_baselines.y.map(|baseline| y + baseline);
+        }
+
+        Layout {
+            location: Point { x, y },
+            size: output.size,
+            content_size: output.content_size,
+            border: style.border.resolve_or_zero(Some(width)),
+            padding: style.padding.resolve_or_zero(Some(width)),
+            margin,
+            ..Layout::new()
+        }
+    }
+
+    /// Lays out the lines of a block container around the floats of the formatting context. The
+    /// border box of the container is at `offset` in the formatting context, and its content box at
+    /// `origin` in the container. Returns the height of the lines.
+    fn flow_lines(
+        &mut self,
+        container: NodeId,
+        flow: &mut Flow,
+        offset: Point<f32>,
+        width: f32,
+        origin: Point<f32>,
+    ) -> f32 {
+        let run_mode = flow.run_mode;
+        let mut area = FloatArea {
+            floats: &mut flow.floats,
+            offset,
+            run_mode,
+        };
+        let lines = self.layout_lines(container, width, origin, run_mode, Some(&mut area));
+
+        if flow.first_baseline.is_none() {
+            flow.first_baseline = lines.layout.lines.first().map(|line| offset.y + line.y + line.baseline);
+        }
+        for line in &lines.layout.lines {
+            flow.right = flow.right.max(offset.x + line.x + line.width);
+        }
+
+        let height = lines.height;
+        if run_mode == RunMode::PerformLayout {
+            if let Some(node) = self.nodes.get_mut(&container) {
+                node.inline_layout = Some(lines.layout);
+            }
+        }
+
+        height
+    }
+
+    /// Places a float that is part of the lines of an inline formatting context, at or below `y`.
+    /// Returns its layout, positioned relative to the block container of the lines.
+    pub(super) fn place_inline_float(
+        &mut self,
+        id: NodeId,
+        area: &mut FloatArea,
+        y: f32,
+        left: f32,
+        width: f32,
+    ) -> Layout {
+        let offset = area.offset;
+        let mut layout = self.place_float(id, area.floats, offset.y + y, offset.x + left, width, area.run_mode);
+        layout.location.x -= offset.x;
+        layout.location.y -= offset.y;
+        layout
+    }
+
+    /// Returns the width of the margin box of a float in a containing block of the given width
+    pub(super) fn float_width(&mut self, id: NodeId, width: f32) -> f32 {
+        let style = self.nodes[&id].layout.style.clone();
+        let margin = style.margin.resolve_or_zero(Some(width));
+        margin.left + self.float_box_width(id, &style, width) + margin.right
+    }
+
+    fn float_box_width(&mut self, id: NodeId, style: &Style, width: f32) -> f32 {
+        let margin = style.margin.resolve_or_zero(Some(width));
+        let parent_size = Size {
+            width: Some(width),
+            height: None,
+        };
+        // Floats with an auto width shrink to fit their content (CSS 2.1, 10.3.5)
+        used_width(style, width, |_| {
+            self.shrink_to_fit_width(id, parent_size, width - margin.left - margin.right)
+        })
+    }
+
+    /// Lays out a float and places it in the formatting context, at or below `y` and inside the
+    /// containing block from `left` with the given width. Returns its layout, positioned in the
+    /// formatting context.
+    fn place_float(
+        &mut self,
+        id: NodeId,
+        floats: &mut FloatContext,
+        y: f32,
+        left: f32,
+        width: f32,
+        run_mode: RunMode,
+    ) -> Layout {
+        let side = self.float_side(id, "float").unwrap_or(FloatSide::Left);
+        let style = self.nodes[&id].layout.style.clone();
+        let margin = style.margin.resolve_or_zero(Some(width));
+        let box_width = self.float_box_width(id, &style, width);
+
+        let output = self.compute_child_layout(
+            taffy_id(id),
+            LayoutInput {
+                known_dimensions: Size {
+                    width: Some(box_width),
+                    height: None,
+                },
+                parent_size: Size {
+                    width: Some(width),
+                    height: None,
+                },
+                available_space: Size {
+                    width: AvailableSpace::Definite(box_width),
+                    height: AvailableSpace::MaxContent,
+                },
+                sizing_mode: SizingMode::InherentSize,
+                axis: RequestedAxis::Both,
+                run_mode,
+                vertical_margins_are_collapsible: Line::FALSE,
+            },
+        );
+
+        // A float that clears other floats is placed below them
+        let clearance = self
+            .float_side(id, "clear")
+            .and_then(|side| floats.clearance(side))
+            .unwrap_or(y);
+        let size = Size {
+            width: margin.left + box_width + margin.right,
+            height: margin.top + output.size.height + margin.bottom,
+        };
+        let position = floats.place(side, size, y.max(clearance), left, left + width);
+
+        Layout {
+            location: Point {
+                x: position.x + margin.left,
+                y: position.y + margin.top,
+            },
+            size: output.size,
+            content_size: output.content_size,
+            border: style.border.resolve_or_zero(Some(width)),
+            padding: style.padding.resolve_or_zero(Some(width)),
+            margin,
+            ..Layout::new()
+        }
+    }
+
+    /// Returns the min-content or max-content width of the content of a box whose formatting
+    /// context contains floats. Floats next to each other add up in the max-content width.
+    fn flow_intrinsic_width(&mut self, id: NodeId, available: AvailableSpace) -> f32 {
+        let min_content = matches!(available, AvailableSpace::MinContent);
+        let add_float = |total: &mut f32, floats: &mut f32, width: f32| {
+            *floats = if min_content { width } else { *floats + width };
+            *total = total.max(*floats);
+        };
+
+        let mut total = 0.0_f32;
+        let mut floats = 0.0;
+        if let LayoutMode::Inline = self.layout_mode(id) {
+            let width = if min_content { 0.0 } else { f32::INFINITY };
+            total = self
+                .layout_lines(id, width, Point::ZERO, RunMode::ComputeSize, None)
+                .width;
+            floats = if min_content { 0.0 } else { total };
+
+            let mut stack = self.nodes[&id].children.clone();
+            while let Some(child) = stack.pop() {
+                if self.float_side(child, "float").is_some() {
+                    let width = self.intrinsic_contribution(child, available);
+                    add_float(&mut total, &mut floats, width);
+                } else if !self.establishes_bfc(child) {
+                    stack.extend(self.nodes[&child].children.iter().copied());
+                }
+            }
+
+            return total;
+        }
+
+        for child in self.nodes[&id].children.clone() {
+            if matches!(self.position(child), "absolute" | "fixed") {
+                continue;
+            }
+
+            let width = self.intrinsic_contribution(child, available);
+            if self.float_side(child, "float").is_some() {
+                add_float(&mut total, &mut floats, width);
+            } else {
+                floats = 0.0;
+                total = total.max(width);
+            }
+        }
+
+        total
+    }
+
+    /// Returns the min-content or max-content width of the margin box of a box in a formatting
+    /// context with floats
+    fn intrinsic_contribution(&mut self, id: NodeId, available: AvailableSpace) -> f32 {
+        let style = self.nodes[&id].layout.style.clone();
+        let margin = style.margin.resolve_or_zero(None);
+
+        let width = if self.establishes_bfc(id) || !self.is_block_container(id) {
+            self.compute_child_layout(
+                taffy_id(id),
+                LayoutInput {
+                    known_dimensions: Size::NONE,
+                    parent_size: Size::NONE,
+                    available_space: Size {
+                        width: available,
+                        height: AvailableSpace::MaxContent,
+                    },
+                    sizing_mode: SizingMode::InherentSize,
+                    axis: RequestedAxis::Horizontal,
+                    run_mode: RunMode::ComputeSize,
+                    vertical_margins_are_collapsible: Line::FALSE,
+                },
+            )
+            .size
+            .width
+        } else {
+            let edges = style.border.resolve_or_zero(None) + style.padding.resolve_or_zero(None);
+            let edges = edges.left + edges.right;
+            match border_box(&style, style.size.width.maybe_resolve(None), edges) {
+                Some(width) => width,
+                None => self.flow_intrinsic_width(id, available) + edges,
+            }
+        };
+
+        margin.left + width + margin.right
+    }
+
+    /// Returns true when a box establishes a new block formatting context for its content, which
+    /// contains its floats and keeps the margins of its content inside (CSS 2.1, 9.4.1)
+    pub(super) fn establishes_bfc(&self, id: NodeId) -> bool {
+        let Some(node) = self.nodes.get(&id) else {
+            return false;
+        };
+        match node.data {
+            RenderNodeData::Document => return true,
+            RenderNodeData::Text(_) => return false,
+            RenderNodeData::Element(_) | RenderNodeData::PseudoElement(_) | RenderNodeData::Anonymous => {}
+        }
+
+        let keyword = |id: NodeId, name: &str| {
+            self.nodes
+                .get(&id)
+                .and_then(|node| node.properties.get(name))
+                .and_then(|property| property.as_keyword())
+                .unwrap_or_default()
+        };
+
+        // The root element (CSS 2.1, 8.3.1), and flex and grid items
+        if node.parent == Some(self.root) {
+            return true;
+        }
+        let parent_display = node.parent.map(|parent| keyword(parent, "display")).unwrap_or_default();
+        if matches!(parent_display, "flex" | "inline-flex" | "grid" | "inline-grid") {
+            return true;
+        }
+
+        let scrolls = |name: &str| !matches!(keyword(id, name), "" | "visible" | "clip");
+        matches!(
+            keyword(id, "display"),
+            "flow-root"
+                | "inline-block"
+                | "inline-flow-root"
+                | "table-cell"
+                | "table-caption"
+                | "flex"
+                | "inline-flex"
+                | "grid"
+                | "inline-grid"
+                | "table"
+                | "inline-table"
+        ) || self.is_table_wrapper(id)
+            || self.float_side(id, "float").is_some()
+            || matches!(keyword(id, "position"), "absolute" | "fixed")
+            || scrolls("overflow-x")
+            || scrolls("overflow-y")
+    }
+
+    /// Returns true when floats take part in the block formatting context a box establishes
+    pub(super) fn contains_floats(&self, id: NodeId) -> bool {
+        let mut stack = self.nodes[&id].children.clone();
+        while let Some(child) = stack.pop() {
+            if self.float_side(child, "float").is_some() {
+                return true;
+            }
+            if !self.establishes_bfc(child) {
+                stack.extend(self.nodes[&child].children.iter().copied());
+            }
+        }
+
+        false
+    }
+
+    /// Returns the side of a `float` or `clear` property of a box, None for `none`
+    fn float_side(&self, id: NodeId, property: &str) -> Option<FloatSide> {
+        let keyword = self.nodes.get(&id)?.properties.get(property)?.as_keyword()?;
+        FloatSide::from_keyword(keyword)
+    }
+}
+
+/// Converts a size from the style of a box into the size of its border box
+pub(super) fn border_box(style: &Style, size: Option<f32>, edges: f32) -> Option<f32> {
+    match style.box_sizing {
+        BoxSizing::ContentBox => size.map(|size| size + edges),
+        BoxSizing::BorderBox => size.map(|size| size.max(edges)),
+    }
+}
+
+/// Returns the width of the border box of a box in a containing block of the given width, limited
+/// by its min and max width. `auto_width` gives the width when it is `auto`, given the width that is
+/// available next to its margins.
+fn used_width(style: &Style, width: f32, auto_width: impl FnOnce(f32) -> f32) -> f32 {
+    let edges = style.border.resolve_or_zero(Some(width)) + style.padding.resolve_or_zero(Some(width));
+    let edges = edges.left + edges.right;
+    let margin = style.margin.resolve_or_zero(Some(width));
+
+    let min_width = border_box(style, style.min_size.width.maybe_resolve(Some(width)), edges).unwrap_or(0.0);
+    let max_width = border_box(style, style.max_size.width.maybe_resolve(Some(width)), edges).unwrap_or(f32::INFINITY);
+    border_box(style, style.size.width.maybe_resolve(Some(width)), edges)
+        .unwrap_or_else(|| auto_width(width - margin.left - margin.right))
+        .min(max_width)
+        .max(min_width)
+        .max(edges)
+}
+
+/// Returns the margins of a block-level box in normal flow with the given width. Auto margins take
+/// the remaining space of the containing block (CSS 2.1, 10.3.3).
+fn block_margins(style: &Style, width: f32, box_width: f32) -> Rect<f32> {
+    let left = style.margin.left.maybe_resolve(Some(width));
+    let right = style.margin.right.maybe_resolve(Some(width));
+    let free = |margin: Option<f32>| (width - box_width - margin.unwrap_or(0.0)).max(0.0);
+
+    let (left, right) = match (left, right) {
+        (None, None) => (free(None) / 2.0, free(None) / 2.0),
+        (None, Some(right)) => (free(Some(right)), right),
+        (Some(left), None) => (left, free(Some(left))),
+        // An over-constrained right margin is ignored
+        (Some(left), Some(right)) => (left, right),
+    };
+
+    Rect {
+        left,
+        right,
+        top: style.margin.top.resolve_or_zero(Some(width)),
+        bottom: style.margin.bottom.resolve_or_zero(Some(width)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{node, rect, TestConfig, TestDocument};
+    use crate::util::render_tree::RenderTree;
+
+    fn layout(html: &str, css: &str) -> (TestDocument, RenderTree<TestConfig>) {
+        let doc = TestDocument::with_css(html, &format!("body {{ margin: 0; font: 10px monospace }} {css}"));
+        let tree = doc.layout(800, 600);
+        (doc, tree)
+    }
+
+    #[test]
+    fn float_placement() {
+        let (doc, tree) = layout(
+            "<div id=gallery><div id=a></div><div id=b></div><div id=c></div><div id=d></div></div><div id=next></div>",
+            "#gallery div { float: left; width: 300px; height: 50px }
+             #c { height: 20px !important }
+             #d { float: right !important; width: 100px !important }
+             #next { height: 5px }",
+        );
+        assert_eq!(rect(&tree, &doc, "a"), (0.0, 0.0, 300.0, 50.0));
+        assert_eq!(rect(&tree, &doc, "b"), (300.0, 0.0, 300.0, 50.0));
+        // A float that does not fit next to the others moves below them
+        assert_eq!(rect(&tree, &doc, "c"), (0.0, 50.0, 300.0, 20.0));
+        // A float is not placed above an earlier one
+        assert_eq!(rect(&tree, &doc, "d"), (700.0, 50.0, 100.0, 50.0));
+        // Floats are out of flow
+        assert_eq!(rect(&tree, &doc, "gallery"), (0.0, 0.0, 800.0, 0.0));
+        assert_eq!(rect(&tree, &doc, "next"), (0.0, 0.0, 800.0, 5.0));
+    }
+
+    #[test]
+    fn lines_around_floats() {
+        let (doc, tree) = layout(
+            "<div id=left></div><div id=right></div><p id=p>aaaa bbbb cccc dddd eeee ffff</p>",
+            "#left { float: left; width: 30px; height: 20px }
+             #right { float: right; width: 700px; height: 30px }
+             p { margin: 0 }",
+        );
+        assert_eq!(rect(&tree, &doc, "right"), (100.0, 0.0, 700.0, 30.0));
+
+        // Lines are shortened while they are next to a float
+        let lines = &node(&tree, &doc, "p").inline_layout.as_ref().unwrap().lines;
+        let lines = lines.iter().map(|line| (line.x, line.width)).collect::<Vec<_>>();
+        assert_eq!(lines, [(30.0, 70.0), (30.0, 70.0), (0.0, 100.0)]);
+    }
+
+    #[test]
+    fn clear() {
+        let (doc, tree) = layout(
+            "<div id=left></div><div id=right></div><div id=clear-left></div><div id=clear-right></div>
+             <div id=float></div><div id=clear-both></div>",
+            "#left, #right, #float { float: left; width: 10px; height: 20px }
+             #right { float: right; height: 50px }
+             #float { height: 30px }
+             #clear-left { clear: left; margin-top: 10px; height: 5px }
+             #clear-right { clear: right; height: 5px }
+             #clear-both { clear: both; margin-top: 100px; height: 5px }",
+        );
+        // The floats are placed above the margin of the cleared box, which starts below them
+        assert_eq!(rect(&tree, &doc, "left"), (0.0, 0.0, 10.0, 20.0));
+        assert_eq!(rect(&tree, &doc, "clear-left"), (0.0, 20.0, 800.0, 5.0));
+        assert_eq!(rect(&tree, &doc, "clear-right"), (0.0, 50.0, 800.0, 5.0));
+        // A box whose margin already moves it below the floats has no clearance
+        assert_eq!(rect(&tree, &doc, "float"), (0.0, 55.0, 10.0, 30.0));
+        assert_eq!(rect(&tree, &doc, "clear-both"), (0.0, 155.0, 800.0, 5.0));
+    }
+
+    #[test]
+    fn formatting_context_roots() {
+        let (doc, tree) = layout(
+            "<div id=visible><div id=a></div></div><div id=hidden><div id=b></div></div>
+             <div id=root><div id=c></div></div><div id=d></div><div id=beside></div>",
+            "#a, #b, #c, #d { float: left; width: 100px; height: 40px }
+             #hidden { overflow: hidden }
+             #root { display: flow-root }
+             #beside { display: flow-root; height: 10px }",
+        );
+        // Roots contain their floats, and do not overlap the floats around them
+        assert_eq!(rect(&tree, &doc, "visible"), (0.0, 0.0, 800.0, 0.0));
+        assert_eq!(rect(&tree, &doc, "hidden"), (100.0, 0.0, 700.0, 40.0));
+        assert_eq!(rect(&tree, &doc, "root"), (0.0, 40.0, 800.0, 40.0));
+        assert_eq!(rect(&tree, &doc, "d"), (0.0, 80.0, 100.0, 40.0));
+        assert_eq!(rect(&tree, &doc, "beside"), (100.0, 80.0, 700.0, 10.0));
+    }
+}
@@ -0,0 +1,170 @@
+use taffy::{Point, Rect, Size};
+
+/// The side a float is placed on, or the floats a box is moved below by `clear`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum FloatSide {
+    Left,
+    Right,
+    Both,
+}
+
+impl FloatSide {
+    /// Reads a value of `float` or `clear`. Logical values are for left-to-right text.
+    pub(super) fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "left" | "inline-start" => Some(Self::Left),
+            "right" | "inline-end" => Some(Self::Right),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Float {
+    side: FloatSide,
+    /// The margin box of the float
+    rect: Rect<f32>,
+}
+
+impl Float {
+    /// Returns true when the float is next to the band from `top` to `bottom`. An empty band is next
+    /// to the floats at its position.
+    fn intersects(&self, top: f32, bottom: f32) -> bool {
+        (self.rect.top < bottom || self.rect.top == top) && self.rect.bottom > top
+    }
+}
+
+/// The floats of a block formatting context, in the coordinates of the border box of its root
+#[derive(Debug, Clone, Default)]
+pub(super) struct FloatContext {
+    floats: Vec<Float>,
+}
+
+impl FloatContext {
+    /// Returns the horizontal space from `left` to `right` that is not taken by the floats next to
+    /// the band from `y` down to `y + height`
+    pub(super) fn available(&self, y: f32, height: f32, left: f32, right: f32) -> (f32, f32) {
+        let bottom = y + height;
+        self.floats
+            .iter()
+            .filter(|float| float.intersects(y, bottom))
+            .fold((left, right), |(left, right), float| match float.side {
+                FloatSide::Right => (left, right.min(float.rect.left)),
+                _ => (left.max(float.rect.right), right),
+            })
+    }
+
+    /// Returns true when any float is next to the band from `y` down to `y + height`
+    pub(super) fn intrudes(&self, y: f32, height: f32) -> bool {
+        self.floats.iter().any(|float| float.intersects(y, y + height))
+    }
+
+    /// Returns the first position below `y` where a float ends, and so more space may be available
+    pub(super) fn next_edge(&self, y: f32) -> Option<f32> {
+        self.floats
+            .iter()
+            .map(|float| float.rect.bottom)
+            .filter(|bottom| *bottom > y)
+            .min_by(f32::total_cmp)
+    }
+
+    /// Returns the position below the floats on the given side, if there are any
+    pub(super) fn clearance(&self, side: FloatSide) -> Option<f32> {
+        self.floats
+            .iter()
+            .filter(|float| side == FloatSide::Both || float.side == side)
+            .map(|float| float.rect.bottom)
+            .max_by(f32::total_cmp)
+    }
+
+    /// Returns the bottom of the lowest float
+    pub(super) fn bottom(&self) -> Option<f32> {
+        self.clearance(FloatSide::Both)
+    }
+
+    /// Returns the right edge of the rightmost float
+    pub(super) fn right(&self) -> Option<f32> {
+        self.floats.iter().map(|float| float.rect.right).max_by(f32::total_cmp)
+    }
+
+    /// Places a float with the given margin box size as high as possible, but not above `y`, and as
+    /// far as possible to its side of the containing block from `left` to `right` (CSS 2.1, 9.5.1).
+    /// Returns the position of its margin box.
+    pub(super) fn place(&mut self, side: FloatSide, size: Size<f32>, y: f32, left: f32, right: f32) -> Point<f32> {
+        // A float is not placed above any earlier float
+        let mut y = self.floats.iter().map(|float| float.rect.top).fold(y, f32::max);
+
+        // Move down past floats until it fits next to the others, or there are no others
+        loop {
+            let (start, end) = self.available(y, size.height, left, right);
+            let fits = size.width <= end - start;
+            match self.next_edge(y) {
+                Some(edge) if !fits && self.intrudes(y, size.height) => y = edge,
+                _ => {
+                    let x = match side {
+                        FloatSide::Right => end - size.width,
+                        _ => start,
+                    };
+                    self.floats.push(Float {
+                        side,
+                        rect: Rect {
+                            left: x,
+                            right: x + size.width,
+                            top: y,
+                            bottom: y + size.height,
+                        },
+                    });
+
+                    return Point { x, y };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(width: f32, height: f32) -> Size<f32> {
+        Size { width, height }
+    }
+
+    #[test]
+    fn place() {
+        let mut floats = FloatContext::default();
+        assert_eq!(
+            floats.place(FloatSide::Left, size(30.0, 20.0), 10.0, 0.0, 100.0),
+            Point { x: 0.0, y: 10.0 }
+        );
+        assert_eq!(
+            floats.place(FloatSide::Right, size(30.0, 40.0), 0.0, 0.0, 100.0),
+            Point { x: 70.0, y: 10.0 }
+        );
+        // Too wide next to the others, so below the first one that ends
+        assert_eq!(
+            floats.place(FloatSide::Left, size(50.0, 10.0), 0.0, 0.0, 100.0),
+            Point { x: 0.0, y: 30.0 }
+        );
+
+        assert_eq!(floats.available(10.0, 5.0, 0.0, 100.0), (30.0, 70.0));
+        assert_eq!(floats.available(30.0, 5.0, 0.0, 100.0), (50.0, 70.0));
+        assert_eq!(floats.available(50.0, 5.0, 0.0, 100.0), (0.0, 100.0));
+        assert_eq!(floats.clearance(FloatSide::Left), Some(40.0));
+        assert_eq!(floats.clearance(FloatSide::Right), Some(50.0));
+        assert_eq!(floats.next_edge(30.0), Some(40.0));
+    }
+
+    #[test]
+    fn empty_band() {
+        let mut floats = FloatContext::default();
+        floats.place(FloatSide::Left, size(30.0, 20.0), 85.0, 0.0, 100.0);
+
+        // An empty band is next to the floats that start at its position, but not the ones that end there
+        assert!(floats.intrudes(85.0, 0.0));
+        assert_eq!(floats.available(85.0, 0.0, 0.0, 100.0), (30.0, 100.0));
+        assert!(!floats.intrudes(105.0, 0.0));
+        assert!(!floats.intrudes(80.0, 5.0));
+    }
+}
@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+
+use crate::{
+    interface::{
+        config::{HasCssSystem, HasLayouter},
+        css3::{CssProperty, CssPropertyMap, CssSystem, PseudoElement},
+    },
+    shared::node::NodeId,
+    util::render_tree::{anonymous::BoxKind, ElementData, RenderNodeData, RenderTree, TextData},
+};
+
+use counter_style::{format_counter, marker_text};
+
+mod counter_style;
+
+/// Quotes of `open-quote` and `close-quote` when `quotes` is `auto`, by nesting level
+const DEFAULT_QUOTES: &[(&str, &str)] = &[("\u{201c}", "\u{201d}"), ("\u{2018}", "\u{2019}")];
+
+type MatchedStyles<C> = <<C as HasCssSystem>::CssSystem as CssSystem>::MatchedStyles;
+type PropertyMap<C> = <<C as HasCssSystem>::CssSystem as CssSystem>::PropertyMap;
+
+#[derive(Debug)]
+struct Counter {
+    value: i32,
+    /// Depth of the element that created the counter. The counter is in scope for that element, its
+    /// following siblings and their descendants.
+    depth: usize,
+}
+
+/// The counters and the nesting level of quotes, which change in tree order while the render tree
+/// is generated (CSS Lists 3, 4)
+#[derive(Debug, Default)]
+pub(super) struct GeneratedContent {
+    /// The counters in scope by name, innermost last
+    counters: HashMap<String, Vec<Counter>>,
+    /// Number of elements that are entered and not left yet
+    depth: usize,
+    quote_depth: usize,
+}
+
+impl GeneratedContent {
+    /// Applies the counter properties of an element, and enters it so its content is in the scope of
+    /// the counters it creates
+    pub(super) fn enter(&mut self, properties: &impl CssPropertyMap, element: &ElementData) {
+        self.update_counters(properties, Some(element));
+        self.depth += 1;
+    }
+
+    /// Leaves the element that was entered last, which ends the scope of the counters created by its
+    /// children
+    pub(super) fn leave(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        let depth = self.depth;
+        for counters in self.counters.values_mut() {
+            counters.retain(|counter| counter.depth <= depth);
+        }
+    }
+
+    /// Applies `counter-reset`, then `counter-increment` and then `counter-set` (CSS Lists 3, 4.3).
+    /// List items increment the `list-item` counter unless their `counter-increment` names it, and
+    /// the `start` attribute of `<ol>` and the `value` attribute of `<li>` set its value (HTML,
+    /// 15.3.8).
+    fn update_counters(&mut self, properties: &impl CssPropertyMap, element: Option<&ElementData>) {
+        let attribute = |name: &str| {
+            element
+                .and_then(|element| element.attribute(name))
+                .and_then(|value| value.trim().parse::<i32>().ok())
+        };
+        let list_item = is_list_item(properties);
+
+        let mut resets = counter_values(properties.get("counter-reset"), 0);
+        if let Some(start) = attribute("start").filter(|_| element.is_some_and(|element| element.name == "ol")) {
+            for (name, value) in &mut resets {
+                if name == "list-item" {
+                    *value = start.saturating_sub(1);
+                }
+            }
+        }
+
+        let mut increments = counter_values(properties.get("counter-increment"), 1);
+        if list_item && !increments.iter().any(|(name, _)| name == "list-item") {
+            increments.push(("list-item".to_string(), 1));
+        }
+
+        let mut sets = counter_values(properties.get("counter-set"), 0);
+        let value = attribute("value").filter(|_| list_item && element.is_some_and(|element| element.name == "li"));
+        if let Some(value) = value.filter(|_| !sets.iter().any(|(name, _)| name == "list-item")) {
+            sets.push(("list-item".to_string(), value));
+        }
+
+        for (name, value) in resets {
+            self.create_counter(name, value);
+        }
+        for (name, value) in increments {
+            let counter = self.counter(&name);
+            *counter = counter.saturating_add(value);
+        }
+        for (name, value) in sets {
+            *self.counter(&name) = value;
+        }
+    }
+
+    /// Creates a counter for the current element. A counter with the same name created by a
+    /// preceding sibling is replaced.
+    fn create_counter(&mut self, name: String, value: i32) {
+        let depth = self.depth;
+        let counters = self.counters.entry(name).or_default();
+        match counters.last_mut() {
+            Some(counter) if counter.depth == depth => counter.value = value,
+            _ => counters.push(Counter { value, depth }),
+        }
+    }
+
+    /// Returns the value of the innermost counter with the given name. A counter that is not in
+    /// scope is created with a value of zero.
+    fn counter(&mut self, name: &str) -> &mut i32 {
+        if self.counters.get(name).is_none_or(|counters| counters.is_empty()) {
+            self.create_counter(name.to_string(), 0);
+        }
+
+        let counters = self.counters.get_mut(name).expect("counter is created");
+        &mut counters.last_mut().expect("counter is created").value
+    }
+
+    /// Applies the counter properties of a pseudo element and returns the text of its `content`, or
+    /// None when it generates no box. A marker with `content: normal` shows the `list-item` counter
+    /// in the style of `list-style-type`. Images are not supported, and the alternative text after a
+    /// `/` is not shown, so the content ends at anything but text, quotes, counters and attributes.
+    pub(super) fn pseudo_element_text(
+        &mut self,
+        pseudo: PseudoElement,
+        properties: &impl CssPropertyMap,
+        element: &ElementData,
+    ) -> Option<String> {
+        let content = properties.get("content")?;
+        match content.as_keyword() {
+            Some("none") => return None,
+            Some("normal") if pseudo != PseudoElement::Marker => return None,
+            _ => {}
+        }
+
+        self.update_counters(properties, None);
+        if content.as_keyword() == Some("normal") {
+            return self.marker_text(properties);
+        }
+
+        let mut text = String::new();
+        for item in content.as_list().unwrap_or_else(|| vec![content.clone()]) {
+            if let Some(string) = item.as_string() {
+                text.push_str(string);
+            } else if let Some(keyword) = item.as_keyword() {
+                self.quote(keyword, properties, &mut text);
+            } else if let Some((name, args)) = item.as_function() {
+                match name {
+                    "attr" => text.push_str(&attribute_value(&args, element)),
+                    "counter" => {
+                        let name = args.first().and_then(|arg| arg.as_keyword()).unwrap_or_default();
+                        let value = *self.counter(name);
+                        text.push_str(&format_value(value, args.get(1)));
+                    }
+                    "counters" => {
+                        let name = args.first().and_then(|arg| arg.as_keyword()).unwrap_or_default();
+                        let separator = args.get(1).and_then(|arg| arg.as_string()).unwrap_or_default();
+                        self.counter(name);
+                        let values = self.counters[name]
+                            .iter()
+                            .map(|counter| format_value(counter.value, args.get(2)))
+                            .collect::<Vec<_>>();
+                        text.push_str(&values.join(separator));
+                    }
+                    _ => {}
+                }
+            } else {
+                break;
+            }
+        }
+
+        Some(text)
+    }
+
+    /// Returns the text of a marker from `list-style-type`, which is a string or a counter style
+    fn marker_text(&mut self, properties: &impl CssPropertyMap) -> Option<String> {
+        let list_style_type = properties.get("list-style-type")?;
+        if let Some(string) = list_style_type.as_string() {
+            return Some(string.to_string());
+        }
+
+        let style = list_style_type.as_keyword().unwrap_or("disc");
+        if style == "none" {
+            return None;
+        }
+
+        Some(marker_text(style, *self.counter("list-item")))
+    }
+
+    /// Adds the quote of a quote keyword to the text, and updates the nesting level of quotes (CSS
+    /// Generated Content 3, 2.2)
+    fn quote(&mut self, keyword: &str, properties: &impl CssPropertyMap, text: &mut String) {
+        let quotes = properties.get("quotes");
+        let pairs = match (
+            quotes.and_then(|quotes| quotes.as_keyword()),
+            quotes.and_then(|quotes| quotes.as_list()),
+        ) {
+            (Some("none"), _) => Vec::new(),
+            (_, Some(strings)) => strings
+                .chunks_exact(2)
+                .filter_map(|pair| Some((pair[0].as_string()?.to_string(), pair[1].as_string()?.to_string())))
+                .collect(),
+            _ => DEFAULT_QUOTES
+                .iter()
+                .map(|(open, close)| (open.to_string(), close.to_string()))
+                .collect(),
+        };
+        let pair = |depth: usize| pairs.get(depth.min(pairs.len().saturating_sub(1)));
+
+        match keyword {
+            "open-quote" => {
+                if let Some((open, _)) = pair(self.quote_depth) {
+                    text.push_str(open);
+                }
+                self.quote_depth += 1;
+            }
+            "close-quote" if self.quote_depth > 0 => {
+                self.quote_depth -= 1;
+                if let Some((_, close)) = pair(self.quote_depth) {
+                    text.push_str(close);
+                }
+            }
+            "no-open-quote" => self.quote_depth += 1,
+            "no-close-quote" => self.quote_depth = self.quote_depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self>> RenderTree<C> {
+    /// Returns the style of the marker of an element when its box is a list item. A marker without
+    /// declarations of its own inherits from the list item.
+    pub(super) fn marker_style(
+        &self,
+        dom_id: NodeId,
+        parent: NodeId,
+        pseudo_styles: &mut HashMap<(NodeId, PseudoElement), PropertyMap<C>>,
+        matched: &MatchedStyles<C>,
+    ) -> Option<PropertyMap<C>> {
+        let style = pseudo_styles.remove(&(dom_id, PseudoElement::Marker));
+        if self.node_for_dom(dom_id) != Some(parent) || !is_list_item(&self.nodes[&parent].properties) {
+            return None;
+        }
+
+        style.or_else(|| {
+            Some(C::CssSystem::anonymous_box_style(
+                matched,
+                &self.nodes[&parent].properties,
+                "inline",
+            ))
+        })
+    }
+
+    /// Generates the box of a pseudo element of the element `dom_id` as the last child of `parent`,
+    /// with its content as text
+    pub(super) fn generate_pseudo_element(
+        &mut self,
+        parent: NodeId,
+        dom_id: NodeId,
+        pseudo: PseudoElement,
+        mut properties: PropertyMap<C>,
+        content: &mut GeneratedContent,
+        matched: &MatchedStyles<C>,
+    ) {
+        if properties.get("display").and_then(|display| display.as_keyword()) == Some("none") {
+            return;
+        }
+        let Some(RenderNodeData::Element(element)) = self.source.get(&dom_id).map(|source| &source.data) else {
+            return;
+        };
+        let Some(text) = content.pseudo_element_text(pseudo, &properties, element) else {
+            return;
+        };
+
+        // Markers outside the list item are laid out like inline blocks, which hang before the
+        // first line, and markers inside it are inline boxes on the first line
+        if pseudo == PseudoElement::Marker {
+            let display = match is_outside(&properties) {
+                true => "inline-block",
+                false => "inline",
+            };
+            let style = C::CssSystem::anonymous_box_style(matched, &properties, display);
+            if let Some(display) = style.get("display") {
+                properties.insert("display", display.clone());
+            }
+        }
+
+        let text_properties = C::CssSystem::anonymous_box_style(matched, &properties, "inline");
+        let id = self.insert_node(Some(parent), None, properties, RenderNodeData::PseudoElement(pseudo));
+        if !text.is_empty() {
+            self.insert_node(Some(id), None, text_properties, RenderNodeData::Text(TextData { text }));
+        }
+    }
+
+    /// Moves the outside marker of a list item into the first block container in it, so the marker
+    /// is placed next to the first line of the list item when its content starts with blocks
+    pub(super) fn place_outside_marker(&mut self, list_item: NodeId) {
+        let Some(&marker) = self.nodes.get(&list_item).and_then(|node| node.children.first()) else {
+            return;
+        };
+        if !self.is_outside_marker(marker) {
+            return;
+        }
+
+        let mut target = list_item;
+        loop {
+            let first = self.nodes[&target].children.iter().copied().find(|&child| {
+                child != marker && !self.is_whitespace_text(child) && self.box_kind(child) != BoxKind::OutOfFlow
+            });
+            match first {
+                Some(child) if self.box_kind(child) == BoxKind::Block && self.is_block_container(child) => {
+                    target = child
+                }
+                _ => break,
+            }
+        }
+        if target == list_item {
+            return;
+        }
+
+        if let Some(node) = self.nodes.get_mut(&list_item) {
+            node.children.retain(|child| *child != marker);
+        }
+        if let Some(node) = self.nodes.get_mut(&target) {
+            node.children.insert(0, marker);
+        }
+        if let Some(node) = self.nodes.get_mut(&marker) {
+            node.parent = Some(target);
+        }
+    }
+
+    /// Returns true for the marker of a list item with `list-style-position: outside`, which takes
+    /// no space on its line
+    pub(super) fn is_outside_marker(&self, id: NodeId) -> bool {
+        self.nodes.get(&id).is_some_and(|node| {
+            node.data == RenderNodeData::PseudoElement(PseudoElement::Marker) && is_outside(&node.properties)
+        })
+    }
+}
+
+fn is_list_item(properties: &impl CssPropertyMap) -> bool {
+    let Some(display) = properties.get("display") else {
+        return false;
+    };
+
+    match display.as_list() {
+        Some(values) => values.iter().any(|value| value.as_keyword() == Some("list-item")),
+        None => display.as_keyword() == Some("list-item"),
+    }
+}
+
+fn is_outside(properties: &impl CssPropertyMap) -> bool {
+    properties
+        .get("list-style-position")
+        .and_then(|position| position.as_keyword())
+        != Some("inside")
+}
+
+/// Returns the counters and values of a `counter-reset`, `counter-increment` or `counter-set`
+/// property. Counters without a value get `default`.
+fn counter_values(property: Option<&impl CssProperty>, default: i32) -> Vec<(String, i32)> {
+    let Some(property) = property else {
+        return Vec::new();
+    };
+
+    let mut values: Vec<(String, i32)> = Vec::new();
+    for item in property.as_list().unwrap_or_else(|| vec![property.clone()]) {
+        if let Some(number) = item.as_number() {
+            if let Some((_, value)) = values.last_mut() {
+                *value = number as i32;
+            }
+        } else if let Some(name) = item.as_keyword().filter(|name| *name != "none") {
+            values.push((name.to_string(), default));
+        }
+    }
+
+    values
+}
+
+/// Formats a counter value with the counter style argument of `counter()` or `counters()`
+fn format_value(value: i32, style: Option<&impl CssProperty>) -> String {
+    match style.and_then(|style| style.as_keyword()).unwrap_or("decimal") {
+        "none" => String::new(),
+        style => format_counter(style, value),
+    }
+}
+
+/// Returns the value of the attribute named by the arguments of `attr()`, or its fallback when the
+/// element does not have the attribute
+fn attribute_value(args: &[impl CssProperty], element: &ElementData) -> String {
+    let name = args.first().and_then(|arg| match arg.as_list() {
+        Some(values) => values.first().and_then(|value| value.as_keyword().map(str::to_string)),
+        None => arg.as_keyword().map(str::to_string),
+    });
+    let fallback = args.get(1).and_then(|arg| arg.as_string()).unwrap_or_default();
+
+    name.and_then(|name| element.attribute(&name).map(str::to_string))
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interface::css3::PseudoElement,
+        testing::{body_tree, node, node_rect, rect, TestDocument},
+        util::render_tree::{FragmentKind, RenderNodeData},
+    };
+
+    /// Generates the render tree and describes the boxes in the body
+    fn boxes(html: &str, css: &str) -> String {
+        body_tree(&TestDocument::with_css(html, css).render_tree())
+    }
+
+    #[test]
+    fn content() {
+        assert_eq!(
+            boxes(
+                "<p id=a title=T>x</p><p id=b>y</p><p id=c>z</p>",
+                "#a::before { content: \"[\" attr(title) \"]\" } #a::after { content: attr(missing) } \
+                 #b::before { content: none } #b::after { content: normal } \
+                 #c::before { content: attr(missing, \"fb\") \"!\" }",
+            ),
+            "body[p[::before[\"[T]\"] anon[\"x\"] ::after] p[anon[\"y\"]] p[::before[\"fb!\"] anon[\"z\"]]]"
+        );
+    }
+
+    #[test]
+    fn counters() {
+        assert_eq!(
+            boxes(
+                "<h2>a</h2><h2>b</h2><h2 class=r>c</h2><h2>d</h2>",
+                "body { counter-reset: sec 4 } h2 { counter-increment: sec } .r { counter-set: sec 10 } \
+                 h2::before { content: counter(sec) \":\" counter(sec, lower-roman) \" \" }",
+            ),
+            "body[h2[::before[\"5:v \"] anon[\"a\"]] h2[::before[\"6:vi \"] anon[\"b\"]] \
+             h2[::before[\"10:x \"] anon[\"c\"]] h2[::before[\"11:xi \"] anon[\"d\"]]]"
+        );
+
+        // Every list creates a `list-item` counter of its own, nested in the counters of the outer lists
+        assert_eq!(
+            boxes(
+                "<ol><li>a<ol><li>b</li><li>c<ol><li>d</li></ol></li></ol></li><li>e</li></ol>",
+                "li::marker { content: counters(list-item, \".\") \" \" }",
+            ),
+            "body[ol[li[anon[::marker[anon[\"1 \"]] anon[\"a\"]] ol[li[::marker[anon[\"1.1 \"]] anon[\"b\"]] \
+             li[anon[::marker[anon[\"1.2 \"]] anon[\"c\"]] ol[li[::marker[anon[\"1.2.1 \"]] anon[\"d\"]]]]]] \
+             li[::marker[anon[\"2 \"]] anon[\"e\"]]]]"
+        );
+    }
+
+    #[test]
+    fn quotes() {
+        assert_eq!(
+            boxes(
+                "<span><span>x</span></span><span id=n></span><span id=q></span>",
+                "span::before { content: open-quote } span::after { content: close-quote } \
+                 #n::before { content: no-open-quote close-quote } #q { quotes: \"<\" \">\" }",
+            ),
+            "body[span[::before[\"\u{201c}\"] span[::before[\"\u{2018}\"] \"x\" ::after[\"\u{2019}\"]] \
+             ::after[\"\u{201d}\"]] span[::before[\"\u{201d}\"] ::after] span[::before[\"<\"] ::after[\">\"]]]"
+        );
+    }
+
+    #[test]
+    fn list_markers() {
+        assert_eq!(
+            boxes(
+                "<ol><li>a</li><li value=10>b</li><li>c</li></ol><ol start=3><li>d</li></ol>\
+                 <ul><li>e<ul><li>f</li></ul></li></ul>",
+                "",
+            ),
+            "body[ol[li[::marker[anon[\"1. \"]] anon[\"a\"]] li[::marker[anon[\"10. \"]] anon[\"b\"]] \
+             li[::marker[anon[\"11. \"]] anon[\"c\"]]] ol[li[::marker[anon[\"3. \"]] anon[\"d\"]]] \
+             ul[li[anon[::marker[anon[\"\u{2022} \"]] anon[\"e\"]] ul[li[::marker[anon[\"\u{25e6} \"]] anon[\"f\"]]]]]]"
+        );
+
+        assert_eq!(
+            boxes(
+                "<ul><li id=a>a</li><li id=b>b</li><li id=c>c</li><li id=d>d</li><li id=e>e</li></ul>",
+                "#a { list-style-type: upper-roman } #b { list-style-type: \"- \" } #c { list-style-type: none } \
+                 #d::marker { content: \"x\" } #e { list-style-type: lower-alpha; list-style-position: inside }",
+            ),
+            "body[ul[li[::marker[anon[\"I. \"]] anon[\"a\"]] li[::marker[anon[\"- \"]] anon[\"b\"]] li[anon[\"c\"]] \
+             li[::marker[anon[\"x\"]] anon[\"d\"]] li[::marker[\"e. \"] anon[\"e\"]]]]"
+        );
+    }
+
+    #[test]
+    fn outside_markers() {
+        // The marker moves into the first block of the list item, and any element can be a list item
+        assert_eq!(
+            boxes(
+                "<ul><li><p>a</p></li><li><div><p>b</p></div></li></ul><div id=d>x</div>",
+                "#d { display: list-item }",
+            ),
+            "body[ul[li[p[::marker[anon[\"\u{2022} \"]] anon[\"a\"]]] \
+             li[div[p[::marker[anon[\"\u{2022} \"]] anon[\"b\"]]]]] div[::marker[anon[\"\u{2022} \"]] anon[\"x\"]]]"
+        );
+
+        // An outside marker hangs before the first line, and an inside marker is on it
+        for (position, marker_x, text_x) in [("outside", 28.0, 40.0), ("inside", 40.0, 52.0)] {
+            let doc = TestDocument::with_css(
+                "<ul><li id=l>a</li></ul>",
+                &format!("body {{ margin: 0; font: 10px monospace }} li {{ list-style-position: {position} }}"),
+            );
+            let tree = doc.layout(800, 600);
+            let marker = tree
+                .nodes
+                .values()
+                .find(|node| node.data == RenderNodeData::PseudoElement(PseudoElement::Marker))
+                .unwrap();
+
+            assert_eq!(node_rect(&tree, marker.id), (marker_x, 10.0, 12.0, 12.0));
+            assert_eq!(rect(&tree, &doc, "l"), (40.0, 10.0, 760.0, 12.0));
+            let line = &node(&tree, &doc, "l").inline_layout.as_ref().unwrap().lines[0];
+            let text = line
+                .fragments
+                .iter()
+                .rfind(|fragment| matches!(fragment.kind, FragmentKind::Text { .. }))
+                .unwrap();
+            assert_eq!(40.0 + text.x.round(), text_x);
+        }
+    }
+}
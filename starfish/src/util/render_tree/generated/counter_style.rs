@@ -0,0 +1,242 @@
+/// How a counter style turns a value into text (CSS Counter Styles 3, 3.1.1)
+#[derive(Debug, Clone, Copy)]
+enum System {
+    /// The symbols are used in turn, and the value is not shown
+    Cyclic(&'static str),
+    /// The value in a positional system with one digit per symbol, like decimal
+    Numeric(&'static str),
+    /// Like numeric, but without a symbol for zero: a, b, ..., z, aa, ab, ...
+    Alphabetic(&'static str),
+    /// The value as a sum of weighted symbols, from the largest weight down, like roman numerals
+    Additive(&'static [(i64, &'static str)]),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CounterStyle {
+    system: System,
+    /// The values the style can represent, others are shown in decimal
+    range: (i64, i64),
+    /// Padding with the zero symbol up to the given number of symbols
+    pad: usize,
+    /// Text after the representation of the value in a marker
+    suffix: &'static str,
+}
+
+const ROMAN_UPPER: &[(i64, &str)] = &[
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+const ROMAN_LOWER: &[(i64, &str)] = &[
+    (1000, "m"),
+    (900, "cm"),
+    (500, "d"),
+    (400, "cd"),
+    (100, "c"),
+    (90, "xc"),
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+const ALL: (i64, i64) = (i64::MIN, i64::MAX);
+const POSITIVE: (i64, i64) = (1, i64::MAX);
+
+const DECIMAL: CounterStyle = CounterStyle {
+    system: System::Numeric("0123456789"),
+    range: ALL,
+    pad: 0,
+    suffix: ". ",
+};
+
+/// Returns one of the predefined counter styles (CSS Counter Styles 3, 6 and 7). Unknown styles are
+/// decimal.
+fn counter_style(name: &str) -> CounterStyle {
+    let (system, range, suffix) = match name {
+        "disc" => (System::Cyclic("•"), ALL, " "),
+        "circle" => (System::Cyclic("◦"), ALL, " "),
+        "square" => (System::Cyclic("▪"), ALL, " "),
+        "disclosure-open" => (System::Cyclic("▾"), ALL, " "),
+        "disclosure-closed" => (System::Cyclic("▸"), ALL, " "),
+        "decimal-leading-zero" => {
+            return CounterStyle { pad: 2, ..DECIMAL };
+        }
+        "cjk-decimal" => (System::Numeric("〇一二三四五六七八九"), ALL, "、"),
+        "lower-roman" => (System::Additive(ROMAN_LOWER), (1, 3999), ". "),
+        "upper-roman" => (System::Additive(ROMAN_UPPER), (1, 3999), ". "),
+        "lower-alpha" | "lower-latin" => (System::Alphabetic("abcdefghijklmnopqrstuvwxyz"), POSITIVE, ". "),
+        "upper-alpha" | "upper-latin" => (System::Alphabetic("ABCDEFGHIJKLMNOPQRSTUVWXYZ"), POSITIVE, ". "),
+        "lower-greek" => (System::Alphabetic("αβγδεζηθικλμνξοπρστυφχψω"), POSITIVE, ". "),
+        "hiragana" => (
+            System::Alphabetic(
+                "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよらりるれろわゐゑをん",
+            ),
+            POSITIVE,
+            "、",
+        ),
+        "katakana" => (
+            System::Alphabetic(
+                "アイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワヰヱヲン",
+            ),
+            POSITIVE,
+            "、",
+        ),
+        _ => return DECIMAL,
+    };
+
+    CounterStyle {
+        system,
+        range,
+        pad: 0,
+        suffix,
+    }
+}
+
+/// Formats a counter value in the given counter style, like `iv` for 4 in `lower-roman`
+pub(super) fn format_counter(style: &str, value: i32) -> String {
+    represent(counter_style(style), value)
+}
+
+/// Returns the text of a list marker: the counter value in the given style, followed by the suffix
+/// of the style, like `4. `
+pub(super) fn marker_text(style: &str, value: i32) -> String {
+    let style = counter_style(style);
+    let suffix = match (style.range.0..=style.range.1).contains(&i64::from(value)) {
+        true => style.suffix,
+        false => DECIMAL.suffix,
+    };
+
+    represent(style, value) + suffix
+}
+
+fn represent(style: CounterStyle, value: i32) -> String {
+    let value = i64::from(value);
+    if !(style.range.0..=style.range.1).contains(&value) {
+        return represent(DECIMAL, value as i32);
+    }
+    let magnitude = value.unsigned_abs();
+
+    let text = match style.system {
+        System::Cyclic(symbols) => {
+            let symbols = symbols.chars().collect::<Vec<_>>();
+            let index = (value - 1).rem_euclid(symbols.len() as i64) as usize;
+            return symbols[index].to_string();
+        }
+        System::Numeric(symbols) => {
+            let symbols = symbols.chars().collect::<Vec<_>>();
+            let digits = positional(magnitude, &symbols, false);
+            let padding = style.pad.saturating_sub(digits.chars().count());
+            symbols[0].to_string().repeat(padding) + &digits
+        }
+        System::Alphabetic(symbols) => positional(magnitude, &symbols.chars().collect::<Vec<_>>(), true),
+        System::Additive(symbols) => {
+            let mut rest = magnitude as i64;
+            let mut text = String::new();
+            for &(weight, symbol) in symbols {
+                while rest >= weight {
+                    text.push_str(symbol);
+                    rest -= weight;
+                }
+            }
+            text
+        }
+    };
+
+    match value < 0 {
+        true => format!("-{text}"),
+        false => text,
+    }
+}
+
+/// Writes a number with the given digits. Alphabetic numbers have no digit for zero, so their
+/// first digit counts as one.
+fn positional(mut value: u64, digits: &[char], alphabetic: bool) -> String {
+    let base = digits.len() as u64;
+    if value == 0 && !alphabetic {
+        return digits[0].to_string();
+    }
+
+    let mut text = Vec::new();
+    while value > 0 {
+        if alphabetic {
+            value -= 1;
+        }
+        text.push(digits[(value % base) as usize]);
+        value /= base;
+    }
+
+    text.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric() {
+        assert_eq!(format_counter("decimal", 0), "0");
+        assert_eq!(format_counter("decimal", 42), "42");
+        assert_eq!(format_counter("decimal", -7), "-7");
+        assert_eq!(format_counter("decimal-leading-zero", 5), "05");
+        assert_eq!(format_counter("decimal-leading-zero", 12), "12");
+        assert_eq!(format_counter("decimal-leading-zero", -5), "-05");
+        assert_eq!(format_counter("cjk-decimal", 105), "一〇五");
+        assert_eq!(format_counter("unknown", 3), "3");
+    }
+
+    #[test]
+    fn alphabetic() {
+        assert_eq!(format_counter("lower-alpha", 1), "a");
+        assert_eq!(format_counter("lower-alpha", 26), "z");
+        assert_eq!(format_counter("lower-alpha", 27), "aa");
+        assert_eq!(format_counter("upper-latin", 703), "AAA");
+        assert_eq!(format_counter("lower-greek", 24), "ω");
+        assert_eq!(format_counter("lower-greek", 25), "αα");
+        assert_eq!(format_counter("hiragana", 1), "あ");
+        // Alphabetic styles have no symbol for zero and negative values, which fall back to decimal
+        assert_eq!(format_counter("lower-alpha", 0), "0");
+        assert_eq!(format_counter("lower-alpha", -1), "-1");
+    }
+
+    #[test]
+    fn additive() {
+        assert_eq!(format_counter("lower-roman", 4), "iv");
+        assert_eq!(format_counter("lower-roman", 1994), "mcmxciv");
+        assert_eq!(format_counter("upper-roman", 3999), "MMMCMXCIX");
+        assert_eq!(format_counter("upper-roman", 4000), "4000");
+        assert_eq!(format_counter("upper-roman", 0), "0");
+    }
+
+    #[test]
+    fn cyclic() {
+        assert_eq!(format_counter("disc", 1), "•");
+        assert_eq!(format_counter("circle", 7), "◦");
+        assert_eq!(format_counter("square", 0), "▪");
+    }
+
+    #[test]
+    fn marker_suffixes() {
+        assert_eq!(marker_text("decimal", 3), "3. ");
+        assert_eq!(marker_text("disc", 1), "• ");
+        assert_eq!(marker_text("cjk-decimal", 2), "二、");
+        assert_eq!(marker_text("katakana", 2), "イ、");
+        // Values out of the range of the style are shown in decimal, with the decimal suffix
+        assert_eq!(marker_text("hiragana", 0), "0. ");
+        assert_eq!(marker_text("lower-roman", 4000), "4000. ");
+    }
+}
@@ -0,0 +1,1644 @@
+use std::{collections::HashMap, ops::Range};
+
+use icu_properties::{maps, BidiClass};
+use taffy::{
+    compute_leaf_layout, AvailableSpace, Layout, LayoutInput, LayoutOutput, LayoutPartialTree, Line, Point, Rect,
+    RequestedAxis, ResolveOrZero, RunMode, Size, SizingMode,
+};
+use unicode_bidi::{BidiInfo, Level};
+
+use crate::{
+    font::{text_style, FontDatabase},
+    interface::{
+        config::HasLayouter,
+        css3::{CssProperty, CssPropertyMap},
+    },
+    shared::node::NodeId,
+    taffy::TaffyLayouter,
+    util::render_tree::{
+        anonymous::BoxKind,
+        flow::FloatArea,
+        layout::{node_id, taffy_id},
+        RenderTree,
+    },
+};
+
+use bidi::{Direction, UnicodeBidi};
+use line_break::{Break, WordBreak};
+
+pub(super) use line_break::LineBreaker;
+
+mod bidi;
+mod line_break;
+
+/// The line boxes of an inline formatting context. Positions are relative to the border box of the
+/// block container that establishes it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InlineLayout {
+    pub lines: Vec<LineBox>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Distance from the top of the line box to its baseline
+    pub baseline: f32,
+    /// The fragments on the line in painting order, inline boxes come before their content
+    pub fragments: Vec<InlineFragment>,
+}
+
+/// The part of a text node, inline box or atomic inline that is placed on a line
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineFragment {
+    pub node: NodeId,
+    pub kind: FragmentKind,
+    /// Position and size of the border box. For text, this is the content area of the font.
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Distance from the top of the fragment to its baseline
+    pub baseline: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FragmentKind {
+    /// A piece of text: the byte range in the text of the node, and the space added to each space
+    /// character by `text-align: justify`. Right to left text is shaped and drawn from right to
+    /// left.
+    Text {
+        range: Range<usize>,
+        justification: f32,
+        rtl: bool,
+    },
+    /// An inline box, which is split over the lines it spans. Its start and end edges (margin,
+    /// border and padding) are only on the lines the box starts and ends.
+    InlineBox {
+        start: bool,
+        end: bool,
+    },
+    Atomic,
+}
+
+/// Font metrics and spacing of the text of a box
+#[derive(Debug, Clone)]
+struct TextMetrics {
+    font_size: f32,
+    ascent: f32,
+    descent: f32,
+    x_height: f32,
+    line_height: f32,
+}
+
+impl TextMetrics {
+    fn from_properties<M: CssPropertyMap>(properties: &M, fonts: &FontDatabase) -> Self {
+        let style = text_style(properties);
+        let metrics = fonts.metrics(&style);
+        let font_size = style.size;
+
+        // `normal` uses the line spacing of the font
+        let line_height = match properties.get("line-height") {
+            Some(property) => match (property.as_number(), property.as_px()) {
+                (Some(factor), _) => font_size * factor,
+                (_, Some(px)) => px,
+                _ => metrics.ascent + metrics.descent + metrics.line_gap,
+            },
+            None => metrics.ascent + metrics.descent + metrics.line_gap,
+        };
+
+        Self {
+            font_size,
+            ascent: metrics.ascent,
+            descent: metrics.descent,
+            x_height: metrics.x_height,
+            line_height,
+        }
+    }
+
+    /// Top and bottom of the box that is used for the height of line boxes, relative to the baseline
+    fn line_height_box(&self) -> (f32, f32) {
+        let half_leading = (self.line_height - self.ascent - self.descent) / 2.0;
+        (-self.ascent - half_leading, self.descent + half_leading)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum VerticalAlign {
+    /// Shifts the baseline relative to the baseline of the parent, given the metrics of the parent
+    /// and the line height box of the aligned item
+    Shift(fn(&TextMetrics, (f32, f32)) -> f32),
+    Length(f32),
+    /// Percentage of the line height of the aligned item
+    Percentage(f32),
+    /// Aligned with the top or bottom of the line box
+    Top,
+    Bottom,
+}
+
+impl VerticalAlign {
+    fn from_property(property: Option<&impl CssProperty>) -> Self {
+        let Some(property) = property else {
+            return Self::Shift(|_, _| 0.0);
+        };
+        if let Some(pct) = property.as_percentage() {
+            return Self::Percentage(pct);
+        }
+        if let Some(px) = property.resolve_px(None) {
+            return Self::Length(px);
+        }
+
+        match property.as_keyword().unwrap_or_default() {
+            "sub" => Self::Shift(|parent, _| parent.font_size * 0.2),
+            "super" => Self::Shift(|parent, _| -parent.font_size / 3.0),
+            "text-top" => Self::Shift(|parent, (top, _)| -parent.ascent - top),
+            "text-bottom" => Self::Shift(|parent, (_, bottom)| parent.descent - bottom),
+            // The middle of the box is aligned with the baseline plus half the x-height
+            "middle" => Self::Shift(|parent, (top, bottom)| -parent.x_height / 2.0 - (top + bottom) / 2.0),
+            "top" => Self::Top,
+            "bottom" => Self::Bottom,
+            _ => Self::Shift(|_, _| 0.0),
+        }
+    }
+
+    /// Returns how far the baseline of an item is shifted down from the baseline of its parent
+    fn shift(self, parent: &TextMetrics, line_height: f32, extent: (f32, f32)) -> f32 {
+        match self {
+            Self::Shift(shift) => shift(parent, extent),
+            Self::Length(px) => -px,
+            Self::Percentage(pct) => -line_height * pct / 100.0,
+            Self::Top | Self::Bottom => 0.0,
+        }
+    }
+}
+
+/// An inline box of the inline formatting context. The first box is the root inline box, which
+/// holds the style of the block container.
+struct InlineBox {
+    node: NodeId,
+    parent: usize,
+    metrics: TextMetrics,
+    vertical_align: VerticalAlign,
+    margin: Rect<f32>,
+    /// Border and padding widths together
+    border_padding: Rect<f32>,
+    /// The bidi control characters at the start and end of the box
+    controls: (&'static [char], &'static [char]),
+}
+
+struct TextRun {
+    node: NodeId,
+    parent: usize,
+    /// Byte offset of each character in the text of the node
+    offsets: Vec<usize>,
+    len: usize,
+}
+
+/// The content of an inline formatting context in order. Inline boxes are flattened into their
+/// start and end edges.
+#[derive(Debug, Clone, Copy)]
+enum Atom {
+    /// A character of a text run
+    Char {
+        run: usize,
+        index: usize,
+    },
+    /// Start or end edge of an inline box
+    Start(usize),
+    End(usize),
+    Atomic(usize),
+    /// A forced line break, generated by `<br>`
+    LineBreak,
+}
+
+struct AtomicInline {
+    node: NodeId,
+    parent: usize,
+    vertical_align: VerticalAlign,
+    line_height: f32,
+    margin: Rect<f32>,
+    /// Size of the border box
+    size: Size<f32>,
+    /// Distance from the top of the margin box to the baseline
+    baseline: f32,
+    /// True for the outside marker of a list item, which hangs before the start of the line
+    outside_marker: bool,
+}
+
+struct InlineContent {
+    boxes: Vec<InlineBox>,
+    runs: Vec<TextRun>,
+    atomics: Vec<AtomicInline>,
+    atoms: Vec<AtomData>,
+    /// The first atom and the embedding level of each bidi paragraph
+    paragraphs: Vec<(usize, u8)>,
+    /// The floats in the content, with the index of the atom that follows each
+    floats: Vec<(usize, NodeId)>,
+    text_indent: f32,
+    text_align: String,
+}
+
+struct AtomData {
+    atom: Atom,
+    width: f32,
+    /// Break opportunity before the atom
+    break_before: Break,
+    /// True for a collapsible space, which hangs at the end of a line
+    space: bool,
+    /// True when the line can be broken after the atom when nothing else fits (`overflow-wrap`)
+    overflow_wrap: bool,
+    /// Bidi class of the character, boundary neutral for the edges of inline boxes
+    bidi: BidiClass,
+    /// Resolved bidi level. For the edges of inline boxes, this is the level of the embedding
+    /// inside the box.
+    level: u8,
+}
+
+/// The line breaks and vertical positions of an inline formatting context
+pub(super) struct Lines {
+    pub(super) layout: InlineLayout,
+    /// Width of the widest line, without hanging spaces
+    pub(super) width: f32,
+    pub(super) height: f32,
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> RenderTree<C> {
+    /// Lays out a block container that establishes an inline formatting context. Its content is
+    /// broken into line boxes, and the size of the line boxes is the size of its content.
+    pub(super) fn compute_inline_layout(&mut self, id: taffy::NodeId, inputs: LayoutInput) -> LayoutOutput {
+        let container = node_id(id);
+        let style = self.nodes[&container].layout.style.clone();
+
+        let mut first_baseline = None;
+        let mut output = compute_leaf_layout(inputs, &style, |_, available| {
+            let width = match available.width {
+                AvailableSpace::Definite(width) => width,
+                AvailableSpace::MinContent => 0.0,
+                AvailableSpace::MaxContent => f32::INFINITY,
+            };
+            let lines = self.layout_lines(container, width, Point::ZERO, RunMode::ComputeSize, None);
+            first_baseline = lines.layout.lines.first().map(|line| line.y + line.baseline);
+            Size {
+                width: lines.width,
+                height: lines.height,
+            }
+        });
+
+        let insets = style.padding.resolve_or_zero(inputs.parent_size.width)
+            + style.border.resolve_or_zero(inputs.parent_size.width);
+        output.first_baselines.y = first_baseline.map(|baseline| insets.top + baseline);
+        if inputs.run_mode != RunMode::PerformLayout {
+            return output;
+        }
+
+        let width = output.size.width - insets.left - insets.right;
+        let origin = Point {
+            x: insets.left,
+            y: insets.top,
+        };
+
+        let lines = self.layout_lines(container, width, origin, RunMode::PerformLayout, None);
+        output.first_baselines.y = lines.layout.lines.first().map(|line| line.y + line.baseline);
+        if let Some(node) = self.nodes.get_mut(&container) {
+            node.inline_layout = Some(lines.layout);
+        }
+
+        output
+    }
+
+    /// Breaks the content into lines of the given width. Lines are shortened next to the floats of
+    /// the block formatting context, and the floats in the content are placed. When laying out, the
+    /// atomic inlines are laid out, and all boxes of the inline formatting context get the bounds of
+    /// their fragments.
+    pub(super) fn layout_lines(
+        &mut self,
+        container: NodeId,
+        width: f32,
+        origin: Point<f32>,
+        run_mode: RunMode,
+        mut floats: Option<&mut FloatArea>,
+    ) -> Lines {
+        let content = self.inline_content(container, width);
+        // The floats are avoided by the strut of the line, as its height is not known yet
+        let line_height = content.boxes[0].metrics.line_height;
+
+        let mut layout = InlineLayout::default();
+        let mut placed = Vec::new();
+        let mut max_width = 0.0_f32;
+        let mut y = origin.y;
+        let mut open = vec![0];
+
+        let mut start = 0;
+        let mut next_float = 0;
+        while start < content.atoms.len() {
+            let indent = if start == 0 { content.text_indent } else { 0.0 };
+
+            let (left, right) = match floats.as_deref_mut() {
+                Some(area) => {
+                    // Floats before the content of the line are placed at its top
+                    while let Some(&(_, node)) = content.floats.get(next_float).filter(|(index, _)| *index <= start) {
+                        placed.push((node, self.place_inline_float(node, area, y, origin.x, width)));
+                        next_float += 1;
+                    }
+                    let (left, right) = area.line_space(y, line_height, origin.x, origin.x + width);
+                    (left - origin.x, right - origin.x)
+                }
+                None => (0.0, width),
+            };
+            let line_width = right - left;
+            let (end, forced) = content.line_end(start, line_width - indent);
+
+            if let Some(area) = floats.as_deref_mut() {
+                // Content that does not fit next to the floats moves down below them
+                let overflows = content.content_width(start..end) + indent > line_width;
+                if let Some(edge) = area.next_edge(y).filter(|_| overflows && area.intrudes(y, line_height)) {
+                    y = edge;
+                    continue;
+                }
+
+                // A float in the line is placed next to it when it fits, and otherwise below it
+                if let Some(&(index, node)) = content.floats.get(next_float).filter(|(index, _)| *index < end) {
+                    let float_width = self.float_width(node, width);
+                    if content.content_width(start..index) + indent + float_width <= line_width {
+                        placed.push((node, self.place_inline_float(node, area, y, origin.x, width)));
+                        next_float += 1;
+                        continue;
+                    }
+                }
+            }
+
+            let mut line = content.line_box(start..end, &mut open, line_width, indent, forced);
+            line.x += origin.x + left;
+            line.y = y;
+            for fragment in &mut line.fragments {
+                fragment.x += origin.x + left;
+                fragment.y += y;
+            }
+
+            max_width = max_width.max(content.content_width(start..end) + indent);
+            y += line.height;
+            layout.lines.push(line);
+            start = end;
+        }
+
+        // Floats at the end of the content are placed below the last line
+        if let Some(area) = floats {
+            for &(_, node) in &content.floats[next_float..] {
+                placed.push((node, self.place_inline_float(node, area, y, origin.x, width)));
+            }
+        }
+
+        if run_mode == RunMode::PerformLayout {
+            self.place_inline_boxes(container, &content, &layout);
+
+            // Floats are positioned relative to their parent, which may be an inline box
+            for (node, mut layout) in placed {
+                let mut parent = self.nodes[&node].parent;
+                while let Some(id) = parent.filter(|id| *id != container) {
+                    let location = self.nodes[&id].layout.unrounded.location;
+                    layout.location.x -= location.x;
+                    layout.location.y -= location.y;
+                    parent = self.nodes[&id].parent;
+                }
+                self.set_unrounded_layout(taffy_id(node), &layout);
+            }
+        }
+
+        Lines {
+            layout,
+            width: max_width,
+            height: y - origin.y,
+        }
+    }
+
+    /// Collects the inline boxes, text and atomic inlines of an inline formatting context, with the
+    /// break opportunities between them
+    fn inline_content(&mut self, container: NodeId, width: f32) -> InlineContent {
+        let basis = width.is_finite().then_some(width);
+        let fonts = self.fonts.clone();
+        let direction = self.direction(container);
+        let unicode_bidi = self.unicode_bidi(container);
+        let node = &self.nodes[&container];
+        let properties = &node.properties;
+        let keyword = |name: &str| properties.get(name).and_then(|property| property.as_keyword());
+
+        // The block container sets the paragraph level, and only an override of it affects its content
+        let level = match (direction, unicode_bidi) {
+            (_, UnicodeBidi::Plaintext) => None,
+            (Direction::Rtl, _) => Some(1),
+            (Direction::Ltr, _) => Some(0),
+        };
+        let controls = match unicode_bidi {
+            UnicodeBidi::BidiOverride | UnicodeBidi::IsolateOverride => UnicodeBidi::BidiOverride.controls(direction),
+            _ => (&[][..], &[][..]),
+        };
+
+        let mut content = InlineContent {
+            boxes: vec![InlineBox {
+                node: container,
+                parent: 0,
+                metrics: TextMetrics::from_properties(properties, &fonts),
+                vertical_align: VerticalAlign::Shift(|_, _| 0.0),
+                margin: Rect::zero(),
+                border_padding: Rect::zero(),
+                controls,
+            }],
+            runs: Vec::new(),
+            atomics: Vec::new(),
+            atoms: Vec::new(),
+            paragraphs: Vec::new(),
+            floats: Vec::new(),
+            text_indent: properties
+                .get("text-indent")
+                .and_then(|indent| indent.resolve_px(basis))
+                .unwrap_or_default(),
+            text_align: keyword("text-align").unwrap_or("start").to_string(),
+        };
+
+        let mut chars = Vec::new();
+        let mut word_break = Vec::new();
+        // Atom and wrapping of each character
+        let mut char_atoms = Vec::new();
+        // Character of each atom
+        let mut atom_chars = Vec::new();
+
+        self.collect_inline_content(container, 0, basis, &mut content, &mut |content, atom, text| {
+            if let Some((c, word_break_value, wrap)) = text {
+                chars.push(c);
+                word_break.push(word_break_value);
+                char_atoms.push((content.atoms.len(), wrap));
+            }
+            atom_chars.push(text.map(|(c, _, _)| c));
+            content.atoms.push(atom);
+        });
+        content.resolve_bidi(&atom_chars, level);
+        self.measure_text(&mut content);
+
+        let breaks = self.line_breaker.line_breaks(&chars, &word_break);
+        for (i, brk) in breaks.into_iter().enumerate().skip(1) {
+            let (index, _) = char_atoms[i];
+            let wraps = char_atoms[i - 1].1;
+            content.atoms[index].break_before = match brk {
+                Break::Allowed if !wraps => Break::Prohibited,
+                brk => brk,
+            };
+        }
+
+        // A break before the start of an inline box moves the start edge to the next line as well
+        for index in (0..content.atoms.len().saturating_sub(1)).rev() {
+            if let Atom::Start(_) = content.atoms[index].atom {
+                let next = content.atoms[index + 1].break_before;
+                content.atoms[index].break_before = next;
+                content.atoms[index + 1].break_before = Break::Prohibited;
+            }
+        }
+
+        content
+    }
+
+    fn collect_inline_content(
+        &mut self,
+        parent_node: NodeId,
+        parent: usize,
+        basis: Option<f32>,
+        content: &mut InlineContent,
+        push: &mut impl FnMut(&mut InlineContent, AtomData, Option<(char, WordBreak, bool)>),
+    ) {
+        let children = self.nodes[&parent_node].children.clone();
+        let fonts = self.fonts.clone();
+
+        for child in children {
+            let node = &self.nodes[&child];
+            let properties = &node.properties;
+            let keyword = |name: &str| properties.get(name).and_then(|property| property.as_keyword());
+            let wrap = keyword("text-wrap-mode") != Some("nowrap");
+            let word_break = WordBreak::from_keyword(keyword("word-break").unwrap_or_default());
+            let overflow_wrap = matches!(keyword("overflow-wrap"), Some("anywhere" | "break-word"))
+                || keyword("word-break") == Some("break-word");
+            let preserve_spaces = matches!(keyword("white-space-collapse"), Some("preserve" | "break-spaces"));
+
+            let atom = |atom, width, space| AtomData {
+                atom,
+                width,
+                break_before: Break::Prohibited,
+                space,
+                overflow_wrap,
+                bidi: BidiClass::BoundaryNeutral,
+                level: 0,
+            };
+
+            match self.box_kind(child) {
+                BoxKind::Text => {
+                    let text = node.text().unwrap_or_default();
+                    let run = content.runs.len();
+                    content.runs.push(TextRun {
+                        node: child,
+                        parent,
+                        offsets: text.char_indices().map(|(offset, _)| offset).collect(),
+                        len: text.len(),
+                    });
+
+                    // The characters are measured once their direction is resolved
+                    for (index, c) in text.chars().enumerate() {
+                        let space = c == ' ' && !preserve_spaces;
+                        push(
+                            content,
+                            atom(Atom::Char { run, index }, 0.0, space),
+                            Some((c, word_break, wrap)),
+                        );
+                    }
+                }
+                BoxKind::Inline if node.element().is_some_and(|element| element.name == "br") => {
+                    push(
+                        content,
+                        atom(Atom::LineBreak, 0.0, false),
+                        Some(('\n', word_break, wrap)),
+                    );
+                }
+                BoxKind::Inline => {
+                    let style = &node.layout.style;
+                    let margin = style.margin.resolve_or_zero(basis);
+                    let border_padding = style.border.resolve_or_zero(basis) + style.padding.resolve_or_zero(basis);
+                    let direction = self.direction(child);
+                    let index = content.boxes.len();
+                    content.boxes.push(InlineBox {
+                        node: child,
+                        parent,
+                        metrics: TextMetrics::from_properties(properties, &fonts),
+                        vertical_align: VerticalAlign::from_property(properties.get("vertical-align")),
+                        margin,
+                        border_padding,
+                        controls: self.unicode_bidi(child).controls(direction),
+                    });
+
+                    let (mut start, mut end) = (margin.left + border_padding.left, margin.right + border_padding.right);
+                    if direction == Direction::Rtl {
+                        (start, end) = (end, start);
+                    }
+                    push(content, atom(Atom::Start(index), start, false), None);
+                    self.collect_inline_content(child, index, basis, content, push);
+                    push(content, atom(Atom::End(index), end, false), None);
+                }
+                BoxKind::OutOfFlow => {
+                    if keyword("float").is_some_and(|float| float != "none") {
+                        content.floats.push((content.atoms.len(), child));
+                    }
+                }
+                _ => {
+                    let vertical_align = VerticalAlign::from_property(properties.get("vertical-align"));
+                    let line_height = TextMetrics::from_properties(properties, &fonts).line_height;
+                    let atomic = self.measure_atomic_inline(child, basis);
+                    let outside_marker = self.is_outside_marker(child);
+                    let width = match outside_marker {
+                        true => 0.0,
+                        false => atomic.margin.left + atomic.size.width + atomic.margin.right,
+                    };
+
+                    let index = content.atomics.len();
+                    content.atomics.push(AtomicInline {
+                        parent,
+                        vertical_align,
+                        line_height,
+                        outside_marker,
+                        ..atomic
+                    });
+                    push(
+                        content,
+                        atom(Atom::Atomic(index), width, false),
+                        Some(('\u{fffc}', word_break, wrap)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Sets the width of the characters of the text runs. Each part of a run with the same direction
+    /// is shaped on its own, in that direction.
+    fn measure_text(&self, content: &mut InlineContent) {
+        let mut start = 0;
+        while start < content.atoms.len() {
+            let Atom::Char { run, index } = content.atoms[start].atom else {
+                start += 1;
+                continue;
+            };
+            let rtl = content.atoms[start].level % 2 == 1;
+            let end = content.atoms[start..]
+                .iter()
+                .position(|atom| {
+                    !matches!(atom.atom, Atom::Char { run: next, .. } if next == run) || (atom.level % 2 == 1) != rtl
+                })
+                .map_or(content.atoms.len(), |len| start + len);
+
+            let text_run = &content.runs[run];
+            let node = &self.nodes[&text_run.node];
+            let last = index + end - start;
+            let range = text_run.offsets[index]..text_run.offsets.get(last).copied().unwrap_or(text_run.len);
+            let text = &node.text().unwrap_or_default()[range];
+            let shaped = self.fonts.shape(text, &text_style(&node.properties), rtl);
+            for (atom, advance) in content.atoms[start..end].iter_mut().zip(shaped.advances) {
+                atom.width = advance;
+            }
+            start = end;
+        }
+    }
+
+    /// Returns the direction of a box from its `direction` property. The `dir` attribute is mapped to
+    /// the property by the user agent stylesheet.
+    fn direction(&self, id: NodeId) -> Direction {
+        let direction = self.nodes[&id].properties.get("direction");
+        match direction.and_then(|property| property.as_keyword()) {
+            Some("rtl") => Direction::Rtl,
+            _ => Direction::Ltr,
+        }
+    }
+
+    /// Returns the `unicode-bidi` value of a box
+    fn unicode_bidi(&self, id: NodeId) -> UnicodeBidi {
+        self.nodes[&id]
+            .properties
+            .get("unicode-bidi")
+            .and_then(|property| property.as_keyword())
+            .map_or(UnicodeBidi::Normal, UnicodeBidi::from_keyword)
+    }
+
+    /// Computes the size of an atomic inline, which is shrink-to-fit when its width is auto
+    fn measure_atomic_inline(&mut self, id: NodeId, basis: Option<f32>) -> AtomicInline {
+        let margin = self.nodes[&id].layout.style.margin.resolve_or_zero(basis);
+        let parent_size = Size {
+            width: basis,
+            height: None,
+        };
+
+        let available = basis.map_or(f32::INFINITY, |basis| basis - margin.left - margin.right);
+        let width = self.shrink_to_fit_width(id, parent_size, available);
+        let output = self.compute_child_layout(
+            taffy_id(id),
+            LayoutInput {
+                known_dimensions: Size {
+                    width: Some(width),
+                    height: None,
+                },
+                parent_size,
+                available_space: Size {
+                    width: AvailableSpace::Definite(width),
+                    height: AvailableSpace::MaxContent,
+                },
+                sizing_mode: SizingMode::InherentSize,
+                axis: RequestedAxis::Both,
+                run_mode: RunMode::ComputeSize,
+                vertical_margins_are_collapsible: Line::FALSE,
+            },
+        );
+
+        // Boxes without a baseline are aligned on the bottom of their margin box
+        let baseline = output.first_baselines.y.unwrap_or(output.size.height + margin.bottom);
+
+        AtomicInline {
+            node: id,
+            parent: 0,
+            vertical_align: VerticalAlign::Shift(|_, _| 0.0),
+            line_height: 0.0,
+            margin,
+            size: output.size,
+            baseline: margin.top + baseline,
+            outside_marker: false,
+        }
+    }
+
+    /// Lays out the atomic inlines, and gives every box in the inline formatting context the bounds
+    /// of its fragments, relative to its parent
+    fn place_inline_boxes(&mut self, container: NodeId, content: &InlineContent, layout: &InlineLayout) {
+        let mut bounds: HashMap<NodeId, Rect<f32>> = HashMap::new();
+        for fragment in layout.lines.iter().flat_map(|line| &line.fragments) {
+            let rect = Rect {
+                left: fragment.x,
+                right: fragment.x + fragment.width,
+                top: fragment.y,
+                bottom: fragment.y + fragment.height,
+            };
+            bounds
+                .entry(fragment.node)
+                .and_modify(|bounds| {
+                    bounds.left = bounds.left.min(rect.left);
+                    bounds.right = bounds.right.max(rect.right);
+                    bounds.top = bounds.top.min(rect.top);
+                    bounds.bottom = bounds.bottom.max(rect.bottom);
+                })
+                .or_insert(rect);
+        }
+
+        let origin = |node: NodeId| {
+            bounds
+                .get(&node)
+                .map(|rect| Point {
+                    x: rect.left,
+                    y: rect.top,
+                })
+                .unwrap_or(Point::ZERO)
+        };
+        let parent_origin = |node: NodeId| if node == container { Point::ZERO } else { origin(node) };
+        let relative = |node: NodeId, parent: NodeId| {
+            let (origin, parent) = (origin(node), parent_origin(parent));
+            Point {
+                x: origin.x - parent.x,
+                y: origin.y - parent.y,
+            }
+        };
+
+        for inline_box in content.boxes.iter().skip(1) {
+            let size = bounds
+                .get(&inline_box.node)
+                .map(|rect| Size {
+                    width: rect.right - rect.left,
+                    height: rect.bottom - rect.top,
+                })
+                .unwrap_or(Size::ZERO);
+            let parent = content.boxes[inline_box.parent].node;
+            self.set_unrounded_layout(
+                taffy_id(inline_box.node),
+                &Layout {
+                    location: relative(inline_box.node, parent),
+                    size,
+                    margin: inline_box.margin,
+                    ..Layout::new()
+                },
+            );
+        }
+
+        for run in &content.runs {
+            let size = bounds
+                .get(&run.node)
+                .map(|rect| Size {
+                    width: rect.right - rect.left,
+                    height: rect.bottom - rect.top,
+                })
+                .unwrap_or(Size::ZERO);
+            let parent = content.boxes[run.parent].node;
+            self.set_unrounded_layout(
+                taffy_id(run.node),
+                &Layout {
+                    location: relative(run.node, parent),
+                    size,
+                    ..Layout::new()
+                },
+            );
+        }
+
+        for atomic in &content.atomics {
+            let output = self.compute_child_layout(
+                taffy_id(atomic.node),
+                LayoutInput {
+                    known_dimensions: atomic.size.map(Some),
+                    parent_size: Size::NONE,
+                    available_space: atomic.size.map(AvailableSpace::Definite),
+                    sizing_mode: SizingMode::InherentSize,
+                    axis: RequestedAxis::Both,
+                    run_mode: RunMode::PerformLayout,
+                    vertical_margins_are_collapsible: Line::FALSE,
+                },
+            );
+
+            let style = &self.nodes[&atomic.node].layout.style;
+            let basis = Some(atomic.size.width);
+            let layout = Layout {
+                location: relative(atomic.node, content.boxes[atomic.parent].node),
+                size: output.size,
+                content_size: output.content_size,
+                border: style.border.resolve_or_zero(basis),
+                padding: style.padding.resolve_or_zero(basis),
+                margin: atomic.margin,
+                ..Layout::new()
+            };
+            self.set_unrounded_layout(taffy_id(atomic.node), &layout);
+        }
+    }
+}
+
+impl InlineContent {
+    /// Resolves the bidi class and level of each atom, given the character of each atom and the
+    /// paragraph level, or None to find the level of each paragraph from its text. Paragraphs end
+    /// at forced line breaks, and the embeddings of the boxes that are open at a break continue in
+    /// the next paragraph.
+    fn resolve_bidi(&mut self, chars: &[Option<char>], level: Option<u8>) {
+        let mut open = vec![0];
+        let mut start = 0;
+
+        while start < self.atoms.len() {
+            let mut text = open
+                .iter()
+                .flat_map(|&index| self.boxes[index].controls.0.iter().copied())
+                .collect::<Vec<_>>();
+            // The characters of each atom in the text of the paragraph
+            let mut ranges = Vec::new();
+            let mut end = start;
+
+            while end < self.atoms.len() {
+                let atom_chars = match self.atoms[end].atom {
+                    Atom::Start(index) => {
+                        open.push(index);
+                        self.boxes[index].controls.0
+                    }
+                    Atom::End(index) => {
+                        open.retain(|open| *open != index);
+                        self.boxes[index].controls.1
+                    }
+                    _ => chars[end].as_slice(),
+                };
+                ranges.push(text.len()..text.len() + atom_chars.len());
+                text.extend(atom_chars);
+                end += 1;
+
+                let class = chars[end - 1].map(|c| maps::bidi_class().get(c));
+                if class == Some(BidiClass::ParagraphSeparator) {
+                    break;
+                }
+            }
+
+            let paragraph = bidi::resolve(&text, level);
+            for (atom, range) in self.atoms[start..end].iter_mut().zip(ranges) {
+                (atom.bidi, atom.level) = match atom.atom {
+                    Atom::Start(_) => (BidiClass::BoundaryNeutral, paragraph.embeddings[range.end - 1]),
+                    Atom::End(_) => (BidiClass::BoundaryNeutral, paragraph.embeddings[range.start]),
+                    _ => (maps::bidi_class().get(text[range.start]), paragraph.levels[range.start]),
+                };
+            }
+
+            self.paragraphs.push((start, paragraph.level));
+            start = end;
+        }
+    }
+
+    /// The embedding level of the paragraph an atom is in
+    fn paragraph_level(&self, atom: usize) -> u8 {
+        let index = self.paragraphs.partition_point(|(start, _)| *start <= atom);
+        index.checked_sub(1).map_or(0, |index| self.paragraphs[index].1)
+    }
+
+    /// Returns the levels of the atoms of a line. Whitespace at the end of the line and before
+    /// separators is reset to the paragraph level (L1). The edges of an inline box take the level
+    /// of the content of the box next to them, so they stay next to it when it is reordered, but
+    /// not less than the level of the embedding inside the box. The edges of empty boxes take the
+    /// lower level of the content around them.
+    fn line_levels(&self, atoms: Range<usize>, paragraph: u8) -> Vec<u8> {
+        let line = &self.atoms[atoms];
+        let edge = |atom: &AtomData| matches!(atom.atom, Atom::Start(_) | Atom::End(_));
+        let mut levels = line.iter().map(|atom| atom.level).collect::<Vec<_>>();
+
+        let mut trailing = true;
+        for (index, atom) in line.iter().enumerate().rev() {
+            if edge(atom) {
+                continue;
+            }
+            match atom.bidi {
+                BidiClass::SegmentSeparator | BidiClass::ParagraphSeparator => {
+                    levels[index] = paragraph;
+                    trailing = true;
+                }
+                BidiClass::WhiteSpace
+                | BidiClass::BoundaryNeutral
+                | BidiClass::LeftToRightIsolate
+                | BidiClass::RightToLeftIsolate
+                | BidiClass::FirstStrongIsolate
+                | BidiClass::PopDirectionalIsolate
+                    if trailing =>
+                {
+                    levels[index] = paragraph
+                }
+                _ => trailing = false,
+            }
+        }
+
+        for (index, atom) in line.iter().enumerate() {
+            let (Atom::Start(inline_box) | Atom::End(inline_box)) = atom.atom else {
+                continue;
+            };
+            let before = (0..index).rev().find(|&i| !edge(&line[i]));
+            let after = (index + 1..line.len()).find(|&i| !edge(&line[i]));
+            let inside = match atom.atom {
+                Atom::Start(_) => after,
+                _ => before,
+            }
+            .filter(|&i| self.is_inside(self.owner(&line[i]), inline_box));
+
+            let level = match inside {
+                Some(inside) => levels[inside],
+                None => {
+                    let level = |i: Option<usize>| i.map_or(paragraph, |i| levels[i]);
+                    level(before).min(level(after))
+                }
+            };
+            levels[index] = atom.level.max(level);
+        }
+
+        levels
+    }
+
+    /// Returns true when an inline box is the given box or one of its descendants
+    fn is_inside(&self, mut inline_box: usize, ancestor: usize) -> bool {
+        loop {
+            if inline_box == ancestor {
+                return true;
+            }
+            if inline_box == 0 {
+                return false;
+            }
+            inline_box = self.boxes[inline_box].parent;
+        }
+    }
+
+    /// The inline box an atom belongs to
+    fn owner(&self, atom: &AtomData) -> usize {
+        match atom.atom {
+            Atom::Char { run, .. } => self.runs[run].parent,
+            Atom::Start(inline_box) | Atom::End(inline_box) => inline_box,
+            Atom::Atomic(atomic) => self.atomics[atomic].parent,
+            Atom::LineBreak => 0,
+        }
+    }
+
+    /// Finds where the line that starts at the given atom ends. Returns the first atom of the next
+    /// line, and whether the line ends with a forced break.
+    fn line_end(&self, start: usize, width: f32) -> (usize, bool) {
+        let mut x = 0.0;
+        // Width of the spaces at the end of the line, which hang and do not need to fit
+        let mut hanging = 0.0;
+        let mut opportunity = None;
+
+        for (index, atom) in self.atoms.iter().enumerate().skip(start) {
+            if index > start {
+                match atom.break_before {
+                    Break::Mandatory => return (index, true),
+                    Break::Allowed => opportunity = Some(index),
+                    Break::Prohibited => {}
+                }
+            }
+
+            x += atom.width;
+            match atom.atom {
+                Atom::Start(_) | Atom::End(_) => {}
+                _ if atom.space => hanging += atom.width,
+                _ => hanging = 0.0,
+            }
+
+            if x - hanging <= width || index == start {
+                continue;
+            }
+            if let Some(opportunity) = opportunity {
+                return (opportunity, false);
+            }
+            // Without any break opportunity, a word that does not fit may be broken anywhere
+            if self.atoms[index - 1].overflow_wrap && !matches!(atom.atom, Atom::End(_)) {
+                return (index, false);
+            }
+        }
+
+        (self.atoms.len(), false)
+    }
+
+    /// Width of the content of a line, without the spaces that hang at its end
+    fn content_width(&self, atoms: Range<usize>) -> f32 {
+        let atoms = &self.atoms[atoms];
+        let hanging = atoms
+            .iter()
+            .rev()
+            .filter(|atom| !matches!(atom.atom, Atom::Start(_) | Atom::End(_)))
+            .take_while(|atom| atom.space)
+            .map(|atom| atom.width)
+            .sum::<f32>();
+
+        atoms.iter().map(|atom| atom.width).sum::<f32>() - hanging
+    }
+
+    /// Places the atoms of a line. `open` holds the inline boxes that are open at the start of the
+    /// line, and is updated with the boxes that are open at its end. Positions are relative to the
+    /// top left of the line box.
+    fn line_box(&self, atoms: Range<usize>, open: &mut Vec<usize>, width: f32, indent: f32, forced: bool) -> LineBox {
+        let content_width = self.content_width(atoms.clone());
+        let available = width - indent;
+        let last_line = forced || atoms.end == self.atoms.len();
+
+        // Spaces that do not hang are stretched when the text is justified
+        let hanging_start = atoms.end
+            - self.atoms[atoms.clone()]
+                .iter()
+                .rev()
+                .take_while(|atom| atom.space || matches!(atom.atom, Atom::Start(_) | Atom::End(_)))
+                .count();
+        let spaces = self.atoms[atoms.start..hanging_start]
+            .iter()
+            .filter(|atom| atom.space)
+            .count();
+
+        let free = if available.is_finite() {
+            (available - content_width).max(0.0)
+        } else {
+            0.0
+        };
+        // Text is aligned to the start of the line in its paragraph direction by default, and the
+        // text indent is on that side as well
+        let paragraph = self.paragraph_level(atoms.start);
+        let rtl = paragraph % 2 == 1;
+        let start = if rtl { free } else { 0.0 };
+        let (shift, justification) = match self.text_align.as_str() {
+            "left" => (0.0, 0.0),
+            "right" => (free, 0.0),
+            "end" => (free - start, 0.0),
+            "center" => (free / 2.0, 0.0),
+            "justify" if !last_line && spaces > 0 => (0.0, free / spaces as f32),
+            "justify-all" if spaces > 0 => (0.0, free / spaces as f32),
+            _ => (start, 0.0),
+        };
+
+        let line = &self.atoms[atoms.clone()];
+        let mut has_content = false;
+        // The boxes on the line: the ones that are open at its start, then the ones it starts
+        let mut boxes = open[1..].to_vec();
+        for data in line {
+            match data.atom {
+                Atom::Char { .. } => has_content |= !data.space,
+                Atom::Start(inline_box) => {
+                    boxes.push(inline_box);
+                    open.push(inline_box);
+                    has_content |= data.width > 0.0;
+                }
+                Atom::End(inline_box) => {
+                    open.retain(|open| *open != inline_box);
+                    has_content |= data.width > 0.0;
+                }
+                Atom::Atomic(_) | Atom::LineBreak => has_content = true,
+            }
+        }
+
+        let levels = self.line_levels(atoms.clone(), paragraph);
+        let order = BidiInfo::reorder_visual(&Level::vec(&levels));
+        let mut visual = vec![0; line.len()];
+        for (position, &index) in order.iter().enumerate() {
+            visual[index] = position;
+        }
+
+        // An edge is on the left of its box when nothing else in the box comes before it
+        let left_edge = |index: usize, inline_box: usize| {
+            (0..line.len())
+                .filter(|&other| other != index && self.is_inside(self.owner(&line[other]), inline_box))
+                .all(|other| visual[other] > visual[index])
+        };
+
+        // Spaces that hang at the end of a right to left line are on its left
+        let hanging = line.iter().map(|data| data.width).sum::<f32>() - content_width;
+        let line_width = if available.is_finite() {
+            width
+        } else {
+            content_width + indent
+        };
+        let mut x = if rtl { shift - hanging } else { indent + shift };
+        // Position and width of each atom
+        let mut placed = vec![(0.0, 0.0); line.len()];
+        for &index in &order {
+            let data = &line[index];
+            let width = match data.atom {
+                Atom::Start(inline_box) | Atom::End(inline_box) => {
+                    let edges = &self.boxes[inline_box];
+                    if left_edge(index, inline_box) {
+                        edges.margin.left + edges.border_padding.left
+                    } else {
+                        edges.margin.right + edges.border_padding.right
+                    }
+                }
+                _ if data.space && atoms.start + index < hanging_start => data.width + justification,
+                _ => data.width,
+            };
+            placed[index] = (x, width);
+            x += width;
+        }
+
+        let mut fragments = Vec::new();
+        for &inline_box in &boxes {
+            let edges = &self.boxes[inline_box];
+            let mut bounds: Option<(f32, f32)> = None;
+            let (mut start, mut end) = (false, false);
+
+            for (index, data) in line.iter().enumerate() {
+                if !self.is_inside(self.owner(data), inline_box) {
+                    continue;
+                }
+                let (mut left, mut right) = (placed[index].0, placed[index].0 + placed[index].1);
+                match data.atom {
+                    Atom::Start(edge) | Atom::End(edge) if edge == inline_box => {
+                        start |= matches!(data.atom, Atom::Start(_));
+                        end |= matches!(data.atom, Atom::End(_));
+                        // Margins are outside of the box
+                        if left_edge(index, inline_box) {
+                            left += edges.margin.left;
+                        } else {
+                            right -= edges.margin.right;
+                        }
+                    }
+                    _ => {}
+                }
+                bounds = Some(bounds.map_or((left, right), |(min, max)| (min.min(left), max.max(right))));
+            }
+
+            let (left, right) = bounds.unwrap_or((x, x));
+            fragments.push(InlineFragment {
+                node: edges.node,
+                kind: FragmentKind::InlineBox { start, end },
+                x: left,
+                y: 0.0,
+                width: (right - left).max(0.0),
+                height: 0.0,
+                baseline: 0.0,
+            });
+        }
+
+        // Text is split into fragments of characters that are next to each other, both in the text
+        // and on the line
+        let mut text: Option<(usize, Range<usize>, InlineFragment)> = None;
+        for &index in &order {
+            let data = &line[index];
+            let (x, width) = placed[index];
+
+            if let Atom::Char { run, index: char_index } = data.atom {
+                let rtl = levels[index] % 2 == 1;
+                match &mut text {
+                    Some((current, range, fragment))
+                        if *current == run
+                            && matches!(fragment.kind, FragmentKind::Text { rtl: current, .. } if current == rtl)
+                            && if rtl {
+                                char_index + 1 == range.start
+                            } else {
+                                char_index == range.end
+                            } =>
+                    {
+                        if rtl {
+                            range.start = char_index;
+                        } else {
+                            range.end = char_index + 1;
+                        }
+                        fragment.width += width;
+                    }
+                    _ => {
+                        fragments.extend(
+                            text.take()
+                                .map(|(run, range, fragment)| self.finish_text(run, range, fragment)),
+                        );
+                        let fragment = InlineFragment {
+                            node: self.runs[run].node,
+                            kind: FragmentKind::Text {
+                                range: 0..0,
+                                justification,
+                                rtl,
+                            },
+                            x,
+                            y: 0.0,
+                            width,
+                            height: 0.0,
+                            baseline: 0.0,
+                        };
+                        text = Some((run, char_index..char_index + 1, fragment));
+                    }
+                }
+                continue;
+            }
+
+            fragments.extend(
+                text.take()
+                    .map(|(run, range, fragment)| self.finish_text(run, range, fragment)),
+            );
+            if let Atom::Atomic(atomic) = data.atom {
+                let atomic = &self.atomics[atomic];
+                // Outside markers are placed before the start edge of the line box
+                let x = match (atomic.outside_marker, rtl) {
+                    (false, _) => x + atomic.margin.left,
+                    (true, false) => -atomic.size.width - atomic.margin.right,
+                    (true, true) => line_width + atomic.margin.left,
+                };
+                fragments.push(InlineFragment {
+                    node: atomic.node,
+                    kind: FragmentKind::Atomic,
+                    x,
+                    y: 0.0,
+                    width: atomic.size.width,
+                    height: atomic.size.height,
+                    baseline: atomic.baseline - atomic.margin.top,
+                });
+            }
+        }
+        fragments.extend(
+            text.take()
+                .map(|(run, range, fragment)| self.finish_text(run, range, fragment)),
+        );
+
+        let mut line = LineBox {
+            x: 0.0,
+            y: 0.0,
+            width: line_width,
+            height: 0.0,
+            baseline: 0.0,
+            fragments,
+        };
+        // Lines without any content do not take up space (CSS 2.1, 9.4.2)
+        if has_content {
+            self.align_vertically(&mut line);
+        } else {
+            line.fragments.clear();
+        }
+
+        line
+    }
+
+    /// Converts the character range of a text fragment into a byte range
+    fn finish_text(&self, run: usize, chars: Range<usize>, mut fragment: InlineFragment) -> InlineFragment {
+        let text = &self.runs[run];
+        let offset = |index: usize| text.offsets.get(index).copied().unwrap_or(text.len);
+
+        if let FragmentKind::Text { range, .. } = &mut fragment.kind {
+            *range = offset(chars.start)..offset(chars.end);
+        }
+
+        fragment
+    }
+
+    /// Computes the height and baseline of the line box, and the vertical position of its
+    /// fragments (CSS 2.1, 10.8)
+    fn align_vertically(&self, line: &mut LineBox) {
+        // The baseline of each inline box relative to the baseline of the root inline box, or of the
+        // nearest box that is aligned with the top or bottom of the line box
+        let mut offsets = vec![0.0_f32; self.boxes.len()];
+        // The box each inline box is aligned in: the root box, or a box aligned with the top or
+        // bottom of the line
+        let mut groups = vec![0_usize; self.boxes.len()];
+        let mut extents: HashMap<usize, (f32, f32)> = HashMap::new();
+
+        let mut on_line = vec![false; self.boxes.len()];
+        on_line[0] = true;
+        for fragment in &line.fragments {
+            if let Some(index) = self
+                .boxes
+                .iter()
+                .position(|inline_box| inline_box.node == fragment.node)
+            {
+                on_line[index] = true;
+            }
+        }
+
+        let mut extend = |group: usize, top: f32, bottom: f32| {
+            let extent = extents.entry(group).or_insert((top, bottom));
+            extent.0 = extent.0.min(top);
+            extent.1 = extent.1.max(bottom);
+        };
+
+        for (index, inline_box) in self.boxes.iter().enumerate() {
+            if index == 0 {
+                let (top, bottom) = inline_box.metrics.line_height_box();
+                extend(0, top, bottom);
+                continue;
+            }
+            if !on_line[index] {
+                continue;
+            }
+
+            let parent = &self.boxes[inline_box.parent];
+            let extent = inline_box.metrics.line_height_box();
+            let (offset, group) = match inline_box.vertical_align {
+                VerticalAlign::Top | VerticalAlign::Bottom => (0.0, index),
+                align => (
+                    offsets[inline_box.parent] + align.shift(&parent.metrics, inline_box.metrics.line_height, extent),
+                    groups[inline_box.parent],
+                ),
+            };
+            offsets[index] = offset;
+            groups[index] = group;
+            extend(group, offset + extent.0, offset + extent.1);
+        }
+
+        // Atomic inlines are aligned by their margin box
+        let mut atomic_offsets = HashMap::new();
+        for fragment in &line.fragments {
+            let Some((index, atomic)) = self
+                .atomics
+                .iter()
+                .enumerate()
+                .find(|(_, atomic)| atomic.node == fragment.node && fragment.kind == FragmentKind::Atomic)
+            else {
+                continue;
+            };
+
+            let height = atomic.margin.top + atomic.size.height + atomic.margin.bottom;
+            let extent = (-atomic.baseline, height - atomic.baseline);
+            let parent = &self.boxes[atomic.parent];
+            let (offset, group) = match atomic.vertical_align {
+                VerticalAlign::Top | VerticalAlign::Bottom => (0.0, usize::MAX - index),
+                align => (
+                    offsets[atomic.parent] + align.shift(&parent.metrics, atomic.line_height, extent),
+                    groups[atomic.parent],
+                ),
+            };
+            atomic_offsets.insert(index, (offset, group));
+            extend(group, offset + extent.0, offset + extent.1);
+        }
+
+        let (mut top, mut bottom) = extents[&0];
+        let aligned = |group: usize| {
+            if group == 0 {
+                None
+            } else if group > self.boxes.len() {
+                Some(self.atomics[usize::MAX - group].vertical_align)
+            } else {
+                Some(self.boxes[group].vertical_align)
+            }
+        };
+
+        // Boxes aligned with the top or bottom of the line box can make it taller
+        for (&group, &(group_top, group_bottom)) in &extents {
+            match aligned(group) {
+                Some(VerticalAlign::Top) => bottom = bottom.max(top + group_bottom - group_top),
+                Some(VerticalAlign::Bottom) => top = top.min(bottom - (group_bottom - group_top)),
+                _ => {}
+            }
+        }
+        let base = |group: usize| {
+            let (group_top, group_bottom) = extents[&group];
+            match aligned(group) {
+                Some(VerticalAlign::Top) => top - group_top,
+                Some(VerticalAlign::Bottom) => bottom - group_bottom,
+                _ => 0.0,
+            }
+        };
+
+        line.height = bottom - top;
+        line.baseline = -top;
+
+        // Positions of the fragments, relative to the top of the line box
+        for fragment in &mut line.fragments {
+            match &fragment.kind {
+                FragmentKind::Atomic => {
+                    let Some((index, atomic)) = self
+                        .atomics
+                        .iter()
+                        .enumerate()
+                        .find(|(_, atomic)| atomic.node == fragment.node)
+                    else {
+                        continue;
+                    };
+                    let (offset, group) = atomic_offsets[&index];
+                    let baseline = line.baseline + base(group) + offset;
+                    fragment.y = baseline - atomic.baseline + atomic.margin.top;
+                }
+                FragmentKind::InlineBox { .. } => {
+                    let Some(index) = self
+                        .boxes
+                        .iter()
+                        .position(|inline_box| inline_box.node == fragment.node)
+                    else {
+                        continue;
+                    };
+                    let inline_box = &self.boxes[index];
+                    let metrics = &inline_box.metrics;
+                    let baseline = line.baseline + base(groups[index]) + offsets[index];
+                    let edges = inline_box.border_padding;
+
+                    fragment.y = baseline - metrics.ascent - edges.top;
+                    fragment.height = edges.top + metrics.ascent + metrics.descent + edges.bottom;
+                    fragment.baseline = edges.top + metrics.ascent;
+                }
+                FragmentKind::Text { .. } => {
+                    let Some(run) = self.runs.iter().find(|run| run.node == fragment.node) else {
+                        continue;
+                    };
+                    let metrics = &self.boxes[run.parent].metrics;
+                    let baseline = line.baseline + base(groups[run.parent]) + offsets[run.parent];
+
+                    fragment.y = baseline - metrics.ascent;
+                    fragment.height = metrics.ascent + metrics.descent;
+                    fragment.baseline = metrics.ascent;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{node, TestConfig, TestDocument};
+
+    /// Lays out the element with id `c` in a monospace font, where each character is about 6px wide
+    fn layout(html: &str, css: &str) -> (RenderTree<TestConfig>, NodeId) {
+        let doc = TestDocument::with_css(html, &format!("body {{ margin: 0; font: 10px monospace }} {css}"));
+        let tree = doc.layout(800, 600);
+        let id = node(&tree, &doc, "c").id;
+        (tree, id)
+    }
+
+    fn lines(tree: &RenderTree<TestConfig>, id: NodeId) -> &[LineBox] {
+        &tree.nodes[&id].inline_layout.as_ref().unwrap().lines
+    }
+
+    /// Returns the text on each line
+    fn line_texts(tree: &RenderTree<TestConfig>, id: NodeId) -> Vec<String> {
+        lines(tree, id)
+            .iter()
+            .map(|line| {
+                line.fragments
+                    .iter()
+                    .filter_map(|fragment| match &fragment.kind {
+                        FragmentKind::Text { range, .. } => Some(&tree.nodes[&fragment.node].text()?[range.clone()]),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the fragments of the given kind on each line as `(x, y, width, height)`, rounded
+    fn fragments(
+        tree: &RenderTree<TestConfig>,
+        id: NodeId,
+        kind: impl Fn(&FragmentKind) -> bool,
+    ) -> Vec<Vec<[f32; 4]>> {
+        let round = |value: f32| (value * 10.0).round() / 10.0;
+        lines(tree, id)
+            .iter()
+            .map(|line| {
+                line.fragments
+                    .iter()
+                    .filter(|fragment| kind(&fragment.kind))
+                    .map(|f| [round(f.x), round(f.y), round(f.width), round(f.height)])
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn is_text(kind: &FragmentKind) -> bool {
+        matches!(kind, FragmentKind::Text { .. })
+    }
+
+    #[test]
+    fn line_breaking() {
+        let (tree, id) = layout(r#"<div id="c">aaa bbb <b>ccc ddd</b> eee</div>"#, "div { width: 60px }");
+        assert_eq!(line_texts(&tree, id), ["aaa bbb ", "ccc ddd ", "eee"]);
+        let y = lines(&tree, id).iter().map(|line| line.y).collect::<Vec<_>>();
+        assert_eq!(y, [0.0, 11.640625, 23.28125]);
+
+        // Words that do not fit overflow their line, and `<br>` forces a break
+        let (tree, id) = layout(
+            r#"<div id="c">aaaaaaaaaaaa b<br>c-d e-fff</div>"#,
+            "div { width: 30px }",
+        );
+        assert_eq!(line_texts(&tree, id), ["aaaaaaaaaaaa ", "b", "c-d ", "e-", "fff"]);
+
+        let (tree, id) = layout(
+            r#"<div id="c">aaa bbb ccc</div>"#,
+            "div { width: 30px; white-space: nowrap }",
+        );
+        assert_eq!(line_texts(&tree, id), ["aaa bbb ccc"]);
+    }
+
+    #[test]
+    fn text_align() {
+        // Spaces at the end of a line hang, so they are not aligned
+        let html = r#"<div id="c">aaa bbb ccc ddd</div>"#;
+        let (tree, id) = layout(html, "div { width: 90px; text-align: center }");
+        assert_eq!(
+            fragments(&tree, id, is_text),
+            [[[11.9, 0.0, 72.2, 11.6]], [[36.0, 11.6, 18.1, 11.6]]]
+        );
+
+        let (tree, id) = layout(html, "div { width: 90px; text-align: right }");
+        assert_eq!(
+            fragments(&tree, id, is_text),
+            [[[23.8, 0.0, 72.2, 11.6]], [[71.9, 11.6, 18.1, 11.6]]]
+        );
+
+        // The spaces of all lines but the last are stretched to fill the line
+        let (tree, id) = layout(
+            r#"<div id="c">aaa bbb ccc ddd</div>"#,
+            "div { width: 60px; text-align: justify }",
+        );
+        let justification = |line: &LineBox| match line.fragments.last().map(|fragment| &fragment.kind) {
+            Some(FragmentKind::Text { justification, .. }) => *justification,
+            _ => unreachable!(),
+        };
+        let lines = lines(&tree, id);
+        assert!((justification(&lines[0]) - (60.0 - 7.0 * 6.0205)).abs() < 0.01);
+        assert_eq!(justification(&lines[1]), 0.0);
+    }
+
+    #[test]
+    fn inline_box_edges() {
+        let (tree, id) = layout(
+            r#"<div id="c">aa <span>bb cc</span> dd</div>"#,
+            "div { width: 60px; line-height: 20px } span { margin: 0 5px; padding: 2px; border: 1px solid }",
+        );
+        assert_eq!(line_texts(&tree, id), ["aa bb ", "cc dd"]);
+
+        // The margin, border and padding of the start edge are on the first line, those of the end
+        // edge on the last line
+        let span = |kind: &FragmentKind| {
+            kind == &FragmentKind::InlineBox {
+                start: true,
+                end: false,
+            }
+        };
+        assert_eq!(fragments(&tree, id, span), [vec![[23.1, 1.2, 21.1, 17.6]], vec![]]);
+        let span = |kind: &FragmentKind| {
+            kind == &FragmentKind::InlineBox {
+                start: false,
+                end: true,
+            }
+        };
+        assert_eq!(fragments(&tree, id, span), [vec![], vec![[0.0, 21.2, 15.0, 17.6]]]);
+        assert_eq!(
+            fragments(&tree, id, is_text),
+            [
+                [[0.0, 4.2, 18.1, 11.6], [26.1, 4.2, 18.1, 11.6]],
+                [[0.0, 24.2, 12.0, 11.6], [20.0, 24.2, 18.1, 11.6]]
+            ]
+        );
+    }
+
+    #[test]
+    fn vertical_align() {
+        // Text is centered in its line height
+        let (tree, id) = layout(r#"<div id="c">a</div>"#, "div { line-height: 2 }");
+        assert_eq!(fragments(&tree, id, is_text), [[[0.0, 4.2, 6.0, 11.6]]]);
+        assert_eq!(lines(&tree, id)[0].height, 20.0);
+
+        // Raised and larger text makes the line taller, and the baselines stay aligned
+        let (tree, id) = layout(
+            r#"<div id="c">a<span>b</span>c</div>"#,
+            "span { vertical-align: super; font-size: 20px }",
+        );
+        let line = &lines(&tree, id)[0];
+        assert!((line.height - 24.26).abs() < 0.01);
+        assert_eq!(
+            fragments(&tree, id, is_text),
+            [[[0.0, 12.6, 6.0, 11.6], [6.0, 0.0, 12.0, 23.3], [18.1, 12.6, 6.0, 11.6]]]
+        );
+        let baselines = line
+            .fragments
+            .iter()
+            .filter(|f| is_text(&f.kind))
+            .map(|f| f.y + f.baseline)
+            .collect::<Vec<_>>();
+        assert_eq!(baselines[0], baselines[2]);
+        assert!(baselines[1] < baselines[0]);
+
+        let (tree, id) = layout(
+            r#"<div id="c">a<span>b</span></div>"#,
+            "div { line-height: 30px } span { vertical-align: top; line-height: 10px }",
+        );
+        assert_eq!(
+            fragments(&tree, id, is_text),
+            [[[0.0, 9.2, 6.0, 11.6], [6.0, -0.8, 6.0, 11.6]]]
+        );
+    }
+
+    #[test]
+    fn direction() {
+        // The `dir` attribute sets the direction, unless the `direction` property overrides it
+        let doc = TestDocument::with_css(
+            r#"<div dir="rtl"><p id="r">ab</p><p id="l" class="l">ab</p></div>"#,
+            "body { margin: 0; font: 10px monospace } .l { direction: ltr }",
+        );
+        let tree = doc.layout(800, 600);
+        for (id, x) in [("r", 788.0), ("l", 0.0)] {
+            let id = node(&tree, &doc, id).id;
+            assert_eq!(fragments(&tree, id, is_text), [[[x, 0.0, 12.0, 11.6]]]);
+        }
+    }
+
+    #[test]
+    fn bidi_runs() {
+        let (tree, id) = layout("<p id=c>ab \u{5d0}\u{5d1}(1) cd</p>", "");
+        let fragments = lines(&tree, id)[0]
+            .fragments
+            .iter()
+            .filter(|fragment| is_text(&fragment.kind));
+        let node = &tree.nodes[&fragments.clone().next().unwrap().node];
+        let style = text_style(&node.properties);
+
+        // Right to left runs are in visual order, and each run is measured in its own direction
+        let runs = fragments
+            .map(|fragment| match &fragment.kind {
+                FragmentKind::Text { range, rtl, .. } => (&node.text().unwrap()[range.clone()], *rtl, fragment.width),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            runs.iter().map(|(text, rtl, _)| (*text, *rtl)).collect::<Vec<_>>(),
+            [
+                ("ab ", false),
+                (")", true),
+                ("1", false),
+                ("\u{5d0}\u{5d1}(", true),
+                (" cd", false)
+            ]
+        );
+        for (text, rtl, width) in &runs {
+            assert_eq!(*width, tree.fonts.shape(text, &style, *rtl).width);
+        }
+    }
+}
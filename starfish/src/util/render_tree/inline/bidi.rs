@@ -0,0 +1,198 @@
+use unicode_bidi::{get_base_direction, Level, ParagraphBidiInfo};
+
+/// The deepest explicit embedding level (BD2)
+const MAX_DEPTH: u8 = 125;
+
+const LRE: char = '\u{202a}';
+const RLE: char = '\u{202b}';
+const PDF: char = '\u{202c}';
+const LRO: char = '\u{202d}';
+const RLO: char = '\u{202e}';
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+/// A boundary neutral that stands in for the edges of inline boxes that do not start an embedding
+const BOUNDARY: char = '\u{200b}';
+
+/// The base direction of a box, from the `direction` property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Direction {
+    Ltr,
+    Rtl,
+}
+
+/// How the content of an inline box takes part in the bidi algorithm, from the `unicode-bidi` property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum UnicodeBidi {
+    Normal,
+    Embed,
+    Isolate,
+    BidiOverride,
+    IsolateOverride,
+    Plaintext,
+}
+
+impl UnicodeBidi {
+    pub(super) fn from_keyword(keyword: &str) -> Self {
+        match keyword {
+            "embed" => Self::Embed,
+            "isolate" => Self::Isolate,
+            "bidi-override" => Self::BidiOverride,
+            "isolate-override" => Self::IsolateOverride,
+            "plaintext" => Self::Plaintext,
+            _ => Self::Normal,
+        }
+    }
+
+    /// Returns the control characters that are inserted at the start and end of an inline box
+    /// (CSS Writing Modes 3, 2.4.2)
+    pub(super) fn controls(self, direction: Direction) -> (&'static [char], &'static [char]) {
+        let rtl = direction == Direction::Rtl;
+        match self {
+            Self::Normal => (&[BOUNDARY], &[BOUNDARY]),
+            Self::Embed if rtl => (&[RLE], &[PDF]),
+            Self::Embed => (&[LRE], &[PDF]),
+            Self::Plaintext => (&[FSI], &[PDI]),
+            Self::Isolate if rtl => (&[RLI], &[PDI]),
+            Self::Isolate => (&[LRI], &[PDI]),
+            Self::BidiOverride if rtl => (&[RLO], &[PDF]),
+            Self::BidiOverride => (&[LRO], &[PDF]),
+            Self::IsolateOverride if rtl => (&[RLI, RLO], &[PDF, PDI]),
+            Self::IsolateOverride => (&[LRI, LRO], &[PDF, PDI]),
+        }
+    }
+}
+
+/// The embedding levels of a paragraph. Even levels are left to right, odd levels right to left.
+pub(super) struct Paragraph {
+    /// The paragraph embedding level
+    pub(super) level: u8,
+    /// Resolved level of each character. Characters removed by rule X9 get the level of the
+    /// character before them.
+    pub(super) levels: Vec<u8>,
+    /// The explicit embedding level of each character, where embedding and isolate initiators
+    /// are in the embedding they start and terminators in the embedding they end
+    pub(super) embeddings: Vec<u8>,
+}
+
+/// Resolves the embedding levels of a paragraph. `level` is the paragraph embedding level, or None
+/// to find it from the first strong character.
+pub(super) fn resolve(text: &[char], level: Option<u8>) -> Paragraph {
+    let string = text.iter().collect::<String>();
+    let info = ParagraphBidiInfo::new(&string, level.map(Level::from));
+    let level = info.paragraph_level.number();
+
+    Paragraph {
+        level,
+        levels: string
+            .char_indices()
+            .map(|(offset, _)| info.levels[offset].number())
+            .collect(),
+        embeddings: explicit_embeddings(text, level),
+    }
+}
+
+/// Returns the explicit embedding level of each character (X1 to X8), which the edges of inline
+/// boxes take. An initiator that would go past the deepest level keeps the current level, so its
+/// terminator still ends it.
+fn explicit_embeddings(text: &[char], level: u8) -> Vec<u8> {
+    // The level of each open embedding, and whether it is an isolate
+    let mut stack = vec![(level, false)];
+    let mut embeddings = Vec::with_capacity(text.len());
+
+    for (i, &c) in text.iter().enumerate() {
+        let (current, isolate) = stack[stack.len() - 1];
+        let rtl = match c {
+            LRE | LRO | LRI => Some(false),
+            RLE | RLO | RLI => Some(true),
+            FSI => Some(first_strong_rtl(&text[i + 1..])),
+            _ => None,
+        };
+
+        match (c, rtl) {
+            (_, Some(rtl)) => {
+                let level = match next_level(current, rtl) {
+                    next if next <= MAX_DEPTH => next,
+                    _ => current,
+                };
+                stack.push((level, matches!(c, LRI | RLI | FSI)));
+                embeddings.push(level);
+            }
+            (PDF, _) => {
+                if !isolate && stack.len() > 1 {
+                    stack.pop();
+                }
+                embeddings.push(current);
+            }
+            (PDI, _) => {
+                if let Some(start) = stack.iter().rposition(|(_, isolate)| *isolate) {
+                    stack.truncate(start);
+                }
+                embeddings.push(current);
+            }
+            _ => embeddings.push(current),
+        }
+    }
+
+    embeddings
+}
+
+/// Returns true when the first strong character of an isolate is right to left (P2, P3). `text`
+/// starts after the isolate initiator, and the isolate ends at the matching PDI.
+fn first_strong_rtl(text: &[char]) -> bool {
+    let mut depth = 0;
+    let end = text
+        .iter()
+        .position(|&c| match c {
+            LRI | RLI | FSI => {
+                depth += 1;
+                false
+            }
+            PDI if depth == 0 => true,
+            PDI => {
+                depth -= 1;
+                false
+            }
+            _ => false,
+        })
+        .unwrap_or(text.len());
+
+    get_base_direction(&*text[..end].iter().collect::<String>()) == unicode_bidi::Direction::Rtl
+}
+
+/// The least level above the given one with the given direction
+fn next_level(level: u8, rtl: bool) -> u8 {
+    if rtl {
+        (level + 1) | 1
+    } else {
+        (level + 2) & !1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embeddings() {
+        // Initiators are in the embedding they start and terminators in the one they end, while
+        // removed characters take the level of the character before them
+        let paragraph = resolve(&['a', RLE, 'b', PDF, 'c'], None);
+        assert_eq!(paragraph.level, 0);
+        assert_eq!(paragraph.embeddings, [0, 1, 1, 1, 0]);
+        assert_eq!(paragraph.levels, [0, 0, 2, 2, 0]);
+
+        let paragraph = resolve(&['\u{5d0}', LRI, 'b', PDI], None);
+        assert_eq!(paragraph.level, 1);
+        assert_eq!(paragraph.embeddings, [1, 2, 2, 2]);
+        assert_eq!(paragraph.levels, [1, 1, 2, 1]);
+
+        // The direction of a first strong isolate is found from its own content only
+        let paragraph = resolve(
+            &['a', FSI, '1', PDI, '\u{5d0}', FSI, '\u{5d0}', LRI, 'b', PDI, PDI],
+            Some(0),
+        );
+        assert_eq!(paragraph.embeddings, [0, 2, 2, 2, 0, 1, 1, 2, 2, 2, 1]);
+    }
+}
@@ -0,0 +1,165 @@
+use icu_properties::{maps, LineBreak};
+use icu_segmenter::{LineBreakOptions, LineBreakStrictness, LineBreakWordOption, LineSegmenter};
+
+/// A line breaking opportunity before a character
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Break {
+    /// The line must not break here
+    Prohibited,
+    /// The line can break here
+    Allowed,
+    /// The line must break here, after a hard line break
+    Mandatory,
+}
+
+/// Tailoring of the break opportunities between letters, from the `word-break` property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum WordBreak {
+    Normal,
+    /// Breaks are allowed between any two letters
+    BreakAll,
+    /// Breaks are not allowed between letters, including ideographs
+    KeepAll,
+}
+
+impl WordBreak {
+    pub(super) fn from_keyword(keyword: &str) -> Self {
+        match keyword {
+            "break-all" => Self::BreakAll,
+            "keep-all" => Self::KeepAll,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// The line segmenters for each `word-break` tailoring, which are built once for a render tree as
+/// building them loads their data
+#[derive(Debug)]
+pub(crate) struct LineBreaker {
+    normal: LineSegmenter,
+    break_all: LineSegmenter,
+    keep_all: LineSegmenter,
+}
+
+impl Default for LineBreaker {
+    fn default() -> Self {
+        let segmenter = |word_option| {
+            let mut options = LineBreakOptions::default();
+            options.strictness = LineBreakStrictness::Normal;
+            options.word_option = word_option;
+            LineSegmenter::new_auto_with_options(options)
+        };
+
+        Self {
+            normal: segmenter(LineBreakWordOption::Normal),
+            break_all: segmenter(LineBreakWordOption::BreakAll),
+            keep_all: segmenter(LineBreakWordOption::KeepAll),
+        }
+    }
+}
+
+impl LineBreaker {
+    /// Finds the line breaking opportunities in the text (UAX #14, with the `line-break: normal`
+    /// tailoring of CSS). Returns the opportunity before each character; the first character never
+    /// has one. `word_break` holds the tailoring for each character, and the opportunity before a
+    /// character follows its tailoring.
+    pub(super) fn line_breaks(&self, text: &[char], word_break: &[WordBreak]) -> Vec<Break> {
+        let string = text.iter().collect::<String>();
+        let offsets = string.char_indices().map(|(offset, _)| offset).collect::<Vec<_>>();
+        let mut breaks = vec![Break::Prohibited; text.len()];
+
+        let mut tailorings = Vec::new();
+        for tailoring in word_break {
+            if !tailorings.contains(tailoring) {
+                tailorings.push(*tailoring);
+            }
+        }
+        for tailoring in tailorings {
+            let segmenter = match tailoring {
+                WordBreak::Normal => &self.normal,
+                WordBreak::BreakAll => &self.break_all,
+                WordBreak::KeepAll => &self.keep_all,
+            };
+            for offset in segmenter.segment_str(&string) {
+                let Ok(i) = offsets.binary_search(&offset) else {
+                    continue;
+                };
+                if i == 0 || word_break[i] != tailoring {
+                    continue;
+                }
+                // LB4, LB5: the line always breaks after hard line breaks
+                let hard = matches!(
+                    maps::line_break().get(text[i - 1]),
+                    LineBreak::MandatoryBreak | LineBreak::CarriageReturn | LineBreak::LineFeed | LineBreak::NextLine
+                );
+                breaks[i] = if hard { Break::Mandatory } else { Break::Allowed };
+            }
+        }
+
+        breaks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text with `÷` before allowed breaks and `!` before mandatory breaks
+    fn breaks(text: &str, word_break: WordBreak) -> String {
+        let chars = text.chars().collect::<Vec<_>>();
+        let breaks = LineBreaker::default().line_breaks(&chars, &vec![word_break; chars.len()]);
+        chars
+            .iter()
+            .zip(breaks)
+            .map(|(c, brk)| match brk {
+                Break::Prohibited => c.to_string(),
+                Break::Allowed => format!("÷{c}"),
+                Break::Mandatory => format!("!{c}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn opportunities() {
+        assert_eq!(breaks("hello world", WordBreak::Normal), "hello ÷world");
+        assert_eq!(breaks("(a) b-c 1-2", WordBreak::Normal), "(a) ÷b-÷c ÷1-2");
+        // Hard line breaks, where CR LF is a single break
+        assert_eq!(breaks("a\nb\r\nc", WordBreak::Normal), "a\n!b\r\n!c");
+        // Regional indicators form pairs (LB30a)
+        assert_eq!(
+            breaks("\u{1f1e6}\u{1f1e8}\u{1f1e6}", WordBreak::Normal),
+            "\u{1f1e6}\u{1f1e8}÷\u{1f1e6}"
+        );
+        // Only narrow opening punctuation sticks to the letter before it (LB30)
+        assert_eq!(breaks("a(b a\u{ff08}b", WordBreak::Normal), "a(b ÷a÷\u{ff08}b");
+        // Thai words are found with a dictionary
+        assert_eq!(breaks("ภาษาไทย", WordBreak::Normal), "ภาษา÷ไทย");
+    }
+
+    #[test]
+    fn word_break() {
+        assert_eq!(breaks("ab cd", WordBreak::BreakAll), "a÷b ÷c÷d");
+        assert_eq!(breaks("漢字 かな", WordBreak::Normal), "漢÷字 ÷か÷な");
+        assert_eq!(breaks("漢字 かな", WordBreak::KeepAll), "漢字 ÷かな");
+
+        // The tailoring of the character after an opportunity applies
+        let chars = "ab cd".chars().collect::<Vec<_>>();
+        let word_break = [
+            WordBreak::Normal,
+            WordBreak::Normal,
+            WordBreak::Normal,
+            WordBreak::BreakAll,
+            WordBreak::BreakAll,
+        ];
+        assert_eq!(
+            LineBreaker::default().line_breaks(&chars, &word_break),
+            [
+                Break::Prohibited,
+                Break::Prohibited,
+                Break::Prohibited,
+                Break::Allowed,
+                Break::Allowed
+            ]
+        );
+    }
+}
@@ -0,0 +1,434 @@
+use taffy::{
+    compute_block_layout, compute_cached_layout, compute_flexbox_layout, compute_grid_layout, compute_hidden_layout,
+    compute_leaf_layout, compute_root_layout, round_layout, AvailableSpace, BoxSizing, Cache, Display, Layout,
+    LayoutBlockContainer, LayoutFlexboxContainer, LayoutGridContainer, LayoutInput, LayoutOutput, LayoutPartialTree,
+    Line, MaybeMath, MaybeResolve, RequestedAxis, ResolveOrZero, RoundTree, RunMode, Size, SizingMode, Style,
+    TraversePartialTree, TraverseTree,
+};
+
+use crate::{
+    interface::config::HasLayouter,
+    shared::{geo::SizeU32, node::NodeId},
+    taffy::{
+        style::{has_percentage_calc, resolve_calc, taffy_style},
+        TaffyLayouter,
+    },
+    util::render_tree::{anonymous::BoxKind, RenderNodeData, RenderTree},
+};
+
+/// The layout algorithm used for the contents of a box
+pub(super) enum LayoutMode {
+    Block,
+    Flexbox,
+    Grid,
+    /// A box with inline-level children, which establishes an inline formatting context
+    Inline,
+    Table,
+    /// The anonymous box around a table and its captions
+    TableWrapper,
+    /// An element whose content is an image or a drawing surface, like `<img>`
+    Replaced,
+    Leaf,
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> RenderTree<C> {
+    /// Computes the layout of all boxes. The root box is the initial containing block, which has
+    /// the size of the viewport.
+    pub(super) fn compute_layout(&mut self, viewport: SizeU32) {
+        let viewport = Size {
+            width: viewport.width as f32,
+            height: viewport.height as f32,
+        };
+
+        for node in self.nodes.values_mut() {
+            let layout = &mut node.layout;
+            layout.style = match node.data {
+                RenderNodeData::Document => Style {
+                    display: Display::Block,
+                    size: viewport.map(taffy::Dimension::Length),
+                    ..Style::DEFAULT
+                },
+                _ => taffy_style(&node.properties),
+            };
+            layout.percentage_calc = has_percentage_calc(&node.properties);
+            layout.cache.clear();
+            layout.baselines.clear();
+            node.inline_layout = None;
+        }
+        self.prepare_tables();
+        self.prepare_replaced();
+
+        let root = taffy_id(self.root);
+        compute_root_layout(self, root, viewport.map(AvailableSpace::Definite));
+        self.place_absolute_boxes(viewport);
+        round_layout(self, root);
+    }
+
+    /// Returns the shrink-to-fit width of a box: its max-content width, limited to the available
+    /// width but not narrower than its min-content width (CSS 2.1, 10.3.5)
+    pub(super) fn shrink_to_fit_width(&mut self, id: NodeId, parent_size: Size<Option<f32>>, available: f32) -> f32 {
+        let mut measure = |width: AvailableSpace| {
+            self.compute_child_layout(
+                taffy_id(id),
+                LayoutInput {
+                    known_dimensions: Size::NONE,
+                    parent_size,
+                    available_space: Size {
+                        width,
+                        height: AvailableSpace::MaxContent,
+                    },
+                    sizing_mode: SizingMode::InherentSize,
+                    axis: RequestedAxis::Both,
+                    run_mode: RunMode::ComputeSize,
+                    vertical_margins_are_collapsible: Line::FALSE,
+                },
+            )
+            .size
+            .width
+        };
+
+        let min_content = measure(AvailableSpace::MinContent);
+        let max_content = measure(AvailableSpace::MaxContent);
+        max_content.min(available.max(min_content))
+    }
+
+    pub(super) fn layout_mode(&self, id: NodeId) -> LayoutMode {
+        let Some(node) = self.nodes.get(&id) else {
+            return LayoutMode::Leaf;
+        };
+        if self.is_replaced(id) {
+            return LayoutMode::Replaced;
+        }
+
+        match node.layout.style.display {
+            Display::Flex => return LayoutMode::Flexbox,
+            Display::Grid => return LayoutMode::Grid,
+            Display::Block | Display::None => {}
+        }
+
+        match self.box_kind(id) {
+            BoxKind::Table | BoxKind::InlineTable => return LayoutMode::Table,
+            _ if self.is_table_wrapper(id) => return LayoutMode::TableWrapper,
+            _ => {}
+        }
+
+        if node.children.is_empty() {
+            return LayoutMode::Leaf;
+        }
+
+        if node
+            .children
+            .iter()
+            .any(|child| self.box_kind(*child).is_inline_level())
+        {
+            LayoutMode::Inline
+        } else {
+            LayoutMode::Block
+        }
+    }
+
+    /// Resolves math functions with percentages in the styles of the children of a box, like
+    /// `width: calc(100% - 2rem)`, against the content box of the box. Taffy reads the styles of
+    /// the children before it lays them out, so this happens before the box is laid out.
+    fn resolve_children_calc(&mut self, id: NodeId, inputs: &LayoutInput) {
+        let node = &self.nodes[&id];
+        if !node
+            .children
+            .iter()
+            .any(|child| self.nodes[child].layout.percentage_calc)
+        {
+            return;
+        }
+
+        let style = &node.layout.style;
+        let edges = style.padding.resolve_or_zero(inputs.parent_size.width)
+            + style.border.resolve_or_zero(inputs.parent_size.width);
+        let edges = Size {
+            width: edges.left + edges.right,
+            height: edges.top + edges.bottom,
+        };
+        let styled = style.size.maybe_resolve(inputs.parent_size);
+        let styled = match style.box_sizing {
+            BoxSizing::ContentBox => styled.maybe_add(edges),
+            BoxSizing::BorderBox => styled,
+        };
+        let content_box = inputs
+            .known_dimensions
+            .or(styled)
+            .maybe_sub(edges)
+            .map(|size| size.map(|size| size.max(0.0)));
+
+        for child in node.children.clone() {
+            let Some(child) = self.nodes.get_mut(&child) else {
+                continue;
+            };
+            if child.layout.percentage_calc && resolve_calc(&mut child.layout.style, &child.properties, content_box) {
+                child.layout.cache.clear();
+            }
+        }
+    }
+
+    /// Keeps the baselines of a box for its size, and adds them to a cached size of the box, which
+    /// has none
+    fn cache_baselines(&mut self, id: NodeId, mut output: LayoutOutput) -> LayoutOutput {
+        let Some(node) = self.nodes.get_mut(&id) else {
+            return output;
+        };
+        let baselines = &mut node.layout.baselines;
+
+        match baselines.iter().find(|(size, _)| *size == output.size) {
+            Some((_, cached)) if output.first_baselines.y.is_none() => output.first_baselines = *cached,
+            Some(_) => {}
+            None if output.first_baselines.y.is_some() => baselines.push((output.size, output.first_baselines)),
+            None => {}
+        }
+
+        output
+    }
+
+    fn style(&self, id: taffy::NodeId) -> &Style {
+        &self.nodes[&node_id(id)].layout.style
+    }
+}
+
+pub(super) fn taffy_id(id: NodeId) -> taffy::NodeId {
+    taffy::NodeId::from(usize::from(id))
+}
+
+pub(super) fn node_id(id: taffy::NodeId) -> NodeId {
+    NodeId::from(usize::from(id))
+}
+
+pub struct ChildIter<'a>(std::slice::Iter<'a, NodeId>);
+
+impl Iterator for ChildIter<'_> {
+    type Item = taffy::NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().copied().map(taffy_id)
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> TraversePartialTree for RenderTree<C> {
+    type ChildIter<'a>
+        = ChildIter<'a>
+    where
+        Self: 'a;
+
+    fn child_ids(&self, parent_node_id: taffy::NodeId) -> Self::ChildIter<'_> {
+        ChildIter(self.nodes[&node_id(parent_node_id)].children.iter())
+    }
+
+    fn child_count(&self, parent_node_id: taffy::NodeId) -> usize {
+        self.nodes[&node_id(parent_node_id)].children.len()
+    }
+
+    fn get_child_id(&self, parent_node_id: taffy::NodeId, child_index: usize) -> taffy::NodeId {
+        taffy_id(self.nodes[&node_id(parent_node_id)].children[child_index])
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> TraverseTree for RenderTree<C> {}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> LayoutPartialTree for RenderTree<C> {
+    type CoreContainerStyle<'a>
+        = &'a Style
+    where
+        Self: 'a;
+    type CacheMut<'b>
+        = &'b mut Cache
+    where
+        Self: 'b;
+
+    fn get_core_container_style(&self, node_id: taffy::NodeId) -> Self::CoreContainerStyle<'_> {
+        self.style(node_id)
+    }
+
+    fn set_unrounded_layout(&mut self, id: taffy::NodeId, layout: &Layout) {
+        if let Some(node) = self.nodes.get_mut(&node_id(id)) {
+            node.layout.unrounded = *layout;
+        }
+    }
+
+    fn get_cache_mut(&mut self, id: taffy::NodeId) -> Self::CacheMut<'_> {
+        &mut self
+            .nodes
+            .get_mut(&node_id(id))
+            .expect("node of the render tree")
+            .layout
+            .cache
+    }
+
+    fn compute_child_layout(&mut self, id: taffy::NodeId, inputs: LayoutInput) -> LayoutOutput {
+        if inputs.run_mode == RunMode::PerformHiddenLayout {
+            return compute_hidden_layout(self, id);
+        }
+
+        let output = compute_cached_layout(self, id, inputs, |tree, id, inputs| {
+            tree.resolve_children_calc(node_id(id), &inputs);
+            match tree.layout_mode(node_id(id)) {
+                // Taffy has no floats, so formatting contexts with floats are laid out on their own
+                LayoutMode::Block | LayoutMode::Inline if tree.contains_floats(node_id(id)) => {
+                    tree.compute_flow_layout(id, inputs)
+                }
+                // Taffy collapses the margins of a box with those of its children unless told otherwise
+                LayoutMode::Block if tree.establishes_bfc(node_id(id)) => {
+                    let inputs = LayoutInput {
+                        vertical_margins_are_collapsible: Line::FALSE,
+                        ..inputs
+                    };
+                    compute_block_layout(tree, id, inputs)
+                }
+                LayoutMode::Block => compute_block_layout(tree, id, inputs),
+                LayoutMode::Flexbox => compute_flexbox_layout(tree, id, inputs),
+                LayoutMode::Grid => compute_grid_layout(tree, id, inputs),
+                LayoutMode::Inline => tree.compute_inline_layout(id, inputs),
+                LayoutMode::Table => tree.compute_table_layout(id, inputs),
+                LayoutMode::TableWrapper => tree.compute_table_wrapper_layout(id, inputs),
+                LayoutMode::Replaced => tree.compute_replaced_layout(id, inputs),
+                LayoutMode::Leaf => compute_leaf_layout(inputs, tree.style(id), |_, _| Size::ZERO),
+            }
+        });
+
+        self.cache_baselines(node_id(id), output)
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> LayoutBlockContainer for RenderTree<C> {
+    type BlockContainerStyle<'a>
+        = &'a Style
+    where
+        Self: 'a;
+    type BlockItemStyle<'a>
+        = &'a Style
+    where
+        Self: 'a;
+
+    fn get_block_container_style(&self, node_id: taffy::NodeId) -> Self::BlockContainerStyle<'_> {
+        self.style(node_id)
+    }
+
+    fn get_block_child_style(&self, child_node_id: taffy::NodeId) -> Self::BlockItemStyle<'_> {
+        self.style(child_node_id)
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> LayoutFlexboxContainer for RenderTree<C> {
+    type FlexboxContainerStyle<'a>
+        = &'a Style
+    where
+        Self: 'a;
+    type FlexboxItemStyle<'a>
+        = &'a Style
+    where
+        Self: 'a;
+
+    fn get_flexbox_container_style(&self, node_id: taffy::NodeId) -> Self::FlexboxContainerStyle<'_> {
+        self.style(node_id)
+    }
+
+    fn get_flexbox_child_style(&self, child_node_id: taffy::NodeId) -> Self::FlexboxItemStyle<'_> {
+        self.style(child_node_id)
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> LayoutGridContainer for RenderTree<C> {
+    type GridContainerStyle<'a>
+        = &'a Style
+    where
+        Self: 'a;
+    type GridItemStyle<'a>
+        = &'a Style
+    where
+        Self: 'a;
+
+    fn get_grid_container_style(&self, node_id: taffy::NodeId) -> Self::GridContainerStyle<'_> {
+        self.style(node_id)
+    }
+
+    fn get_grid_child_style(&self, child_node_id: taffy::NodeId) -> Self::GridItemStyle<'_> {
+        self.style(child_node_id)
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> RoundTree for RenderTree<C> {
+    fn get_unrounded_layout(&self, id: taffy::NodeId) -> &Layout {
+        &self.nodes[&node_id(id)].layout.unrounded
+    }
+
+    fn set_final_layout(&mut self, id: taffy::NodeId, layout: &Layout) {
+        if let Some(node) = self.nodes.get_mut(&node_id(id)) {
+            node.layout.layout = *layout;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{rect, TestDocument};
+
+    #[test]
+    fn calc_with_percentages() {
+        let doc = TestDocument::with_css(
+            r#"<div id="a"></div><div id="b"></div><div id="c"></div><div id="d"></div>"#,
+            "div { height: 10px } \
+             #a { width: calc(100% - 2rem) } \
+             #b { width: clamp(100px, 50%, 300px) } \
+             #c { width: clamp(100px, 10%, 300px); margin-left: calc(10% + 4px) } \
+             #d { width: min(50%, 1000px); padding-left: max(5%, 10px) }",
+        );
+        let tree = doc.layout(800, 600);
+
+        // The body is 784px wide
+        assert_eq!(rect(&tree, &doc, "a"), (8.0, 8.0, 752.0, 10.0));
+        assert_eq!(rect(&tree, &doc, "b"), (8.0, 18.0, 300.0, 10.0));
+        assert_eq!(rect(&tree, &doc, "c"), (90.0, 28.0, 100.0, 10.0));
+        assert_eq!(rect(&tree, &doc, "d"), (8.0, 38.0, 431.0, 10.0));
+    }
+
+    #[test]
+    fn calc_against_containing_block() {
+        let doc = TestDocument::with_css(
+            r#"<div id="p"><div id="c"></div></div>
+               <div id="flex"><div id="item"></div></div>
+               <div id="cb"><div id="mid"><div id="abs"></div></div></div>"#,
+            "body { margin: 0 } \
+             #p { width: calc(50% + 8px); height: 200px } \
+             #c { width: calc(50% - 4px); height: calc(50% + 10px) } \
+             #flex { display: flex; width: 500px } \
+             #item { width: calc(20% + 10px); height: 10px } \
+             #cb { position: relative; width: 400px; height: 300px } \
+             #mid { width: 100px } \
+             #abs { position: absolute; top: 0; left: calc(10% + 1px); width: calc(100% - 20px); height: 1px }",
+        );
+        let tree = doc.layout(800, 600);
+
+        assert_eq!(rect(&tree, &doc, "p"), (0.0, 0.0, 408.0, 200.0));
+        assert_eq!(rect(&tree, &doc, "c"), (0.0, 0.0, 200.0, 110.0));
+        assert_eq!(rect(&tree, &doc, "item"), (0.0, 200.0, 110.0, 10.0));
+        assert_eq!(rect(&tree, &doc, "abs"), (41.0, 210.0, 380.0, 1.0));
+    }
+
+    #[test]
+    fn root_establishes_block_formatting_context() {
+        let doc = TestDocument::with_css(
+            r#"<html id="html"><body id="body"><p id="p">a</p></body></html>"#,
+            "p { margin-top: 2em }",
+        );
+        let tree = doc.layout(800, 600);
+
+        // The margins of the body and the paragraph collapse, but not with those of the root
+        assert_eq!(rect(&tree, &doc, "html"), (0.0, 0.0, 800.0, 67.0));
+        assert_eq!(rect(&tree, &doc, "body"), (8.0, 32.0, 784.0, 19.0));
+        assert_eq!(rect(&tree, &doc, "p"), (8.0, 32.0, 784.0, 19.0));
+
+        let doc = TestDocument::with_css(
+            r#"<div id="root"><p id="p">a</p></div>"#,
+            "body { margin: 0 } p { margin: 10px 0 } #root { display: flow-root }",
+        );
+        let tree = doc.layout(800, 600);
+
+        assert_eq!(rect(&tree, &doc, "root"), (0.0, 0.0, 800.0, 39.0));
+        assert_eq!(rect(&tree, &doc, "p"), (0.0, 10.0, 800.0, 19.0));
+    }
+}
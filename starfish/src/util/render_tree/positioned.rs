@@ -0,0 +1,683 @@
+use std::collections::HashMap;
+
+use taffy::{
+    AvailableSpace, BoxSizing, Display, Layout as TaffyLayout, LayoutInput, LayoutPartialTree, Line, MaybeResolve,
+    Point, Rect, RequestedAxis, ResolveOrZero, RunMode, Size, SizingMode,
+};
+
+use crate::{
+    interface::{
+        config::HasLayouter,
+        css3::{CssProperty, CssPropertyMap},
+        layout::Layout,
+    },
+    shared::{geo::FP, node::NodeId, types},
+    taffy::{
+        style::{inset, resolve_calc},
+        TaffyLayouter,
+    },
+    util::render_tree::{anonymous::BoxKind, layout::taffy_id, RenderNodeData, RenderTree},
+};
+
+/// The constraints of an absolutely positioned box along one axis. `None` is `auto`.
+struct Constraints {
+    start: Option<f32>,
+    end: Option<f32>,
+    margin_start: Option<f32>,
+    margin_end: Option<f32>,
+    /// Size of the border box
+    size: Option<f32>,
+    min_size: f32,
+    max_size: f32,
+    /// Start of the margin box when the box would be in normal flow, relative to the containing block
+    static_position: f32,
+    containing_block: f32,
+}
+
+/// The border box of an absolutely positioned box along one axis, relative to its containing block
+struct Placement {
+    offset: f32,
+    size: f32,
+    margin_start: f32,
+    margin_end: f32,
+}
+
+impl Constraints {
+    /// Solves the constraints of CSS 2.1, 10.3.7 and 10.6.4. `fit` gives the size of a box whose
+    /// size is `auto` for an available space.
+    fn solve(&self, fit: &mut dyn FnMut(f32) -> f32) -> Placement {
+        let placement = self.solve_with(self.size, fit);
+        let size = placement.size.min(self.max_size).max(self.min_size);
+        if size == placement.size {
+            return placement;
+        }
+
+        self.solve_with(Some(size), fit)
+    }
+
+    fn solve_with(&self, size: Option<f32>, fit: &mut dyn FnMut(f32) -> f32) -> Placement {
+        let cb = self.containing_block;
+
+        if let (Some(start), Some(size), Some(end)) = (self.start, size, self.end) {
+            let free = cb - start - size - end;
+            let (margin_start, margin_end) = match (self.margin_start, self.margin_end) {
+                // Auto margins center the box, unless it does not fit
+                (None, None) if free >= 0.0 => (free / 2.0, free / 2.0),
+                (None, None) => (0.0, free),
+                (None, Some(margin_end)) => (free - margin_end, margin_end),
+                (Some(margin_start), None) => (margin_start, free - margin_start),
+                // The box is over-constrained, so `end` is ignored
+                (Some(margin_start), Some(margin_end)) => (margin_start, margin_end),
+            };
+
+            return Placement {
+                offset: start + margin_start,
+                size,
+                margin_start,
+                margin_end,
+            };
+        }
+
+        // Auto margins are zero when any of the other values is auto
+        let margin_start = self.margin_start.unwrap_or(0.0);
+        let margin_end = self.margin_end.unwrap_or(0.0);
+        let margins = margin_start + margin_end;
+
+        let (start, size) = match (self.start, size, self.end) {
+            (None, None, None) => {
+                let start = self.static_position;
+                (start, fit(cb - start - margins))
+            }
+            (None, None, Some(end)) => {
+                let size = fit(cb - end - margins);
+                (cb - end - margins - size, size)
+            }
+            (None, Some(size), None) => (self.static_position, size),
+            (Some(start), None, None) => (start, fit(cb - start - margins)),
+            (None, Some(size), Some(end)) => (cb - end - margins - size, size),
+            (Some(start), None, Some(end)) => (start, (cb - start - end - margins).max(0.0)),
+            (Some(start), Some(size), None) => (start, size),
+            (Some(_), Some(_), Some(_)) => unreachable!("solved above"),
+        };
+
+        Placement {
+            offset: start + margin_start,
+            size,
+            margin_start,
+            margin_end,
+        }
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> RenderTree<C> {
+    /// Lays out the absolutely and fixed positioned boxes in their containing blocks (CSS Positioned
+    /// Layout 3, 4). Taffy has laid them out at their static position, which is where auto insets
+    /// put them. Boxes are placed in tree order, so their ancestors are already in place.
+    pub(super) fn place_absolute_boxes(&mut self, viewport: Size<f32>) {
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[&id];
+            if node.layout.style.display == Display::None {
+                continue;
+            }
+            stack.extend(node.children.iter().rev());
+
+            if matches!(self.position(id), "absolute" | "fixed") {
+                self.place_absolute_box(id, viewport);
+            }
+        }
+    }
+
+    fn place_absolute_box(&mut self, id: NodeId, viewport: Size<f32>) {
+        let Some(parent) = self.nodes[&id].parent else {
+            return;
+        };
+        let containing_block = self.absolute_containing_block(id, viewport);
+        let static_position = self.static_position(id);
+        let cb_size = Size {
+            width: Some(containing_block.size.width),
+            height: Some(containing_block.size.height),
+        };
+
+        // Math functions with percentages were resolved against the parent, which need not be
+        // the containing block
+        if let Some(node) = self.nodes.get_mut(&id) {
+            if node.layout.percentage_calc && resolve_calc(&mut node.layout.style, &node.properties, cb_size) {
+                node.layout.cache.clear();
+            }
+        }
+
+        let node = &self.nodes[&id];
+        let style = &node.layout.style;
+        let inset = inset(&node.properties, cb_size);
+        // Percentages of margins, borders and padding refer to the width of the containing block
+        let basis = cb_size.width;
+        let border = style.border.resolve_or_zero(basis);
+        let padding = style.padding.resolve_or_zero(basis);
+        let border_padding = Size {
+            width: border.left + border.right + padding.left + padding.right,
+            height: border.top + border.bottom + padding.top + padding.bottom,
+        };
+        let border_box = |size: Option<f32>, border_padding: f32| match style.box_sizing {
+            BoxSizing::ContentBox => size.map(|size| size + border_padding),
+            BoxSizing::BorderBox => size.map(|size| size.max(border_padding)),
+        };
+        let size = style.size.maybe_resolve(cb_size);
+        let min_size = style.min_size.maybe_resolve(cb_size);
+        let max_size = style.max_size.maybe_resolve(cb_size);
+
+        let horizontal = Constraints {
+            start: inset.left.maybe_resolve(cb_size.width),
+            end: inset.right.maybe_resolve(cb_size.width),
+            margin_start: style.margin.left.maybe_resolve(basis),
+            margin_end: style.margin.right.maybe_resolve(basis),
+            size: border_box(size.width, border_padding.width),
+            min_size: border_box(min_size.width, border_padding.width).unwrap_or(border_padding.width),
+            max_size: border_box(max_size.width, border_padding.width).unwrap_or(f32::INFINITY),
+            static_position: static_position.x - containing_block.location.x,
+            containing_block: containing_block.size.width,
+        };
+        let vertical = Constraints {
+            start: inset.top.maybe_resolve(cb_size.height),
+            end: inset.bottom.maybe_resolve(cb_size.height),
+            margin_start: style.margin.top.maybe_resolve(basis),
+            margin_end: style.margin.bottom.maybe_resolve(basis),
+            size: border_box(size.height, border_padding.height),
+            min_size: border_box(min_size.height, border_padding.height).unwrap_or(border_padding.height),
+            max_size: border_box(max_size.height, border_padding.height).unwrap_or(f32::INFINITY),
+            static_position: static_position.y - containing_block.location.y,
+            containing_block: containing_block.size.height,
+        };
+
+        let x = horizontal.solve(&mut |available| self.shrink_to_fit_width(id, cb_size, available));
+        let y = vertical.solve(&mut |_| {
+            self.compute_child_layout(
+                taffy_id(id),
+                LayoutInput {
+                    known_dimensions: Size {
+                        width: Some(x.size),
+                        height: None,
+                    },
+                    parent_size: cb_size,
+                    available_space: Size {
+                        width: AvailableSpace::Definite(x.size),
+                        height: AvailableSpace::MaxContent,
+                    },
+                    sizing_mode: SizingMode::InherentSize,
+                    axis: RequestedAxis::Vertical,
+                    run_mode: RunMode::ComputeSize,
+                    vertical_margins_are_collapsible: Line::FALSE,
+                },
+            )
+            .size
+            .height
+        });
+
+        let output = self.compute_child_layout(
+            taffy_id(id),
+            LayoutInput {
+                known_dimensions: Size {
+                    width: Some(x.size),
+                    height: Some(y.size),
+                },
+                parent_size: cb_size,
+                available_space: Size {
+                    width: AvailableSpace::Definite(x.size),
+                    height: AvailableSpace::Definite(y.size),
+                },
+                sizing_mode: SizingMode::InherentSize,
+                axis: RequestedAxis::Both,
+                run_mode: RunMode::PerformLayout,
+                vertical_margins_are_collapsible: Line::FALSE,
+            },
+        );
+
+        let parent_position = self.unrounded_position(parent);
+        let layout = TaffyLayout {
+            order: self.nodes[&id].layout.unrounded.order,
+            location: Point {
+                x: containing_block.location.x + x.offset - parent_position.x,
+                y: containing_block.location.y + y.offset - parent_position.y,
+            },
+            size: output.size,
+            content_size: output.content_size,
+            scrollbar_size: Size::ZERO,
+            border,
+            padding,
+            margin: Rect {
+                left: x.margin_start,
+                right: x.margin_end,
+                top: y.margin_start,
+                bottom: y.margin_end,
+            },
+        };
+        self.set_unrounded_layout(taffy_id(id), &layout);
+    }
+
+    /// Returns the padding box of the containing block of an absolutely positioned box, relative to
+    /// the initial containing block. Fixed boxes are positioned relative to the viewport, which is
+    /// at the origin before the document is scrolled.
+    fn absolute_containing_block(&self, id: NodeId, viewport: Size<f32>) -> TaffyLayout {
+        let viewport = TaffyLayout {
+            size: viewport,
+            ..TaffyLayout::new()
+        };
+        if self.position(id) == "fixed" {
+            return viewport;
+        }
+
+        let mut ancestor = self.nodes[&id].parent;
+        while let Some(id) = ancestor {
+            let node = &self.nodes[&id];
+            let element = matches!(node.data, RenderNodeData::Element(_) | RenderNodeData::PseudoElement(_));
+            if element && self.position(id) != "static" {
+                let position = self.unrounded_position(id);
+                let layout = &node.layout.unrounded;
+                return TaffyLayout {
+                    location: Point {
+                        x: position.x + layout.border.left,
+                        y: position.y + layout.border.top,
+                    },
+                    size: Size {
+                        width: layout.size.width - layout.border.left - layout.border.right,
+                        height: layout.size.height - layout.border.top - layout.border.bottom,
+                    },
+                    ..TaffyLayout::new()
+                };
+            }
+            ancestor = node.parent;
+        }
+
+        viewport
+    }
+
+    /// Returns the top left corner of the margin box an absolutely positioned box would have in
+    /// normal flow, relative to the initial containing block
+    fn static_position(&self, id: NodeId) -> Point<f32> {
+        let node = &self.nodes[&id];
+        let Some(parent) = node.parent else {
+            return Point::ZERO;
+        };
+
+        // Boxes in an inline formatting context are not laid out by taffy, so their static position
+        // is found from the content around them
+        let mut container = parent;
+        while self.box_kind(container) == BoxKind::Inline {
+            match self.nodes[&container].parent {
+                Some(parent) => container = parent,
+                None => break,
+            }
+        }
+        let display = node.properties.get("display");
+        let inline_level = display
+            .and_then(|display| display.as_specified_keyword())
+            .is_some_and(|display| display.starts_with("inline") || display == "ruby");
+        if let Some(position) = self.inline_static_position(id, container, inline_level) {
+            let origin = self.unrounded_position(container);
+            return Point {
+                x: origin.x + position.x,
+                y: origin.y + position.y,
+            };
+        }
+
+        let origin = self.unrounded_position(parent);
+        let layout = &node.layout.unrounded;
+        Point {
+            x: origin.x + layout.location.x - layout.margin.left,
+            y: origin.y + layout.location.y - layout.margin.top,
+        }
+    }
+
+    /// Returns the static position of a box in the inline formatting context of the container,
+    /// relative to the container. A box that was inline-level before it was blockified is at the
+    /// start of the first fragment that follows it, or the end of the last fragment before it. A
+    /// block-level box starts a new line after the last fragment before it.
+    fn inline_static_position(&self, id: NodeId, container: NodeId, inline_level: bool) -> Option<Point<f32>> {
+        let inline_layout = self.nodes[&container].inline_layout.as_ref()?;
+        let layout = &self.nodes[&container].layout.unrounded;
+        let content_start = Point {
+            x: layout.border.left + layout.padding.left,
+            y: layout.border.top + layout.padding.top,
+        };
+
+        let mut first = HashMap::new();
+        let mut last = HashMap::new();
+        for line in &inline_layout.lines {
+            for fragment in &line.fragments {
+                first.entry(fragment.node).or_insert(Point {
+                    x: fragment.x,
+                    y: line.y,
+                });
+                let end = match inline_level {
+                    true => Point {
+                        x: fragment.x + fragment.width,
+                        y: line.y,
+                    },
+                    false => Point {
+                        x: content_start.x,
+                        y: line.y + line.height,
+                    },
+                };
+                last.insert(fragment.node, end);
+            }
+        }
+
+        // The boxes of the formatting context in tree order, without the content of the box itself
+        let mut order = Vec::new();
+        let mut stack = vec![container];
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            if node != id {
+                stack.extend(self.nodes[&node].children.iter().rev());
+            }
+        }
+        let index = order.iter().position(|node| *node == id)?;
+
+        let before = order[..index].iter().rev().find_map(|node| last.get(node));
+        let position = match inline_level {
+            true => order[index + 1..].iter().find_map(|node| first.get(node)).or(before),
+            false => before,
+        };
+
+        // Without any content, the box is at the start of the content box
+        Some(*position.unwrap_or(&content_start))
+    }
+
+    /// Returns the position of the border box of a box relative to the initial containing block,
+    /// before it is rounded
+    fn unrounded_position(&self, id: NodeId) -> Point<f32> {
+        let mut position = Point::ZERO;
+        let mut ancestor = Some(id);
+        while let Some(id) = ancestor {
+            let node = &self.nodes[&id];
+            position.x += node.layout.unrounded.location.x;
+            position.y += node.layout.unrounded.location.y;
+            ancestor = node.parent;
+        }
+
+        position
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self>> RenderTree<C> {
+    /// Returns the `position` of a box
+    pub fn position(&self, id: NodeId) -> &str {
+        self.nodes
+            .get(&id)
+            .and_then(|node| node.properties.get("position"))
+            .and_then(|property| property.as_keyword())
+            .unwrap_or("static")
+    }
+
+    /// Returns the position of the border box of a box relative to the initial containing block
+    pub fn document_position(&self, id: NodeId) -> types::Point<FP> {
+        let mut position = types::Point::new(0.0, 0.0);
+        let mut ancestor = Some(id);
+        while let Some(id) = ancestor {
+            let Some(node) = self.nodes.get(&id) else {
+                break;
+            };
+            let location = node.layout.position();
+            position.x += location.x;
+            position.y += location.y;
+            ancestor = node.parent;
+        }
+
+        position
+    }
+
+    /// Returns the size of the area the document can be scrolled over, which holds all boxes
+    /// except the ones that are fixed to the viewport or clipped by their ancestors
+    pub fn scrollable_overflow(&self) -> types::Size<FP> {
+        let mut overflow: types::Size<FP> = types::Size::new(0.0, 0.0);
+        let mut stack = vec![(self.root, types::Point::new(0.0, 0.0))];
+        while let Some((id, parent)) = stack.pop() {
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+            if self.position(id) == "fixed" {
+                continue;
+            }
+
+            let position = node.layout.position();
+            let size = node.layout.size();
+            let origin = types::Point::new(parent.x + position.x, parent.y + position.y);
+            overflow.width = overflow.width.max(origin.x + size.width);
+            overflow.height = overflow.height.max(origin.y + size.height);
+
+            if self.scroll_container(id).is_none() {
+                stack.extend(node.children.iter().map(|child| (*child, origin)));
+            }
+        }
+
+        overflow
+    }
+
+    /// Returns the scrollport of a box whose overflow is not visible: its padding box
+    fn scroll_container(&self, id: NodeId) -> Option<Rect<FP>> {
+        let node = self.nodes.get(&id)?;
+        let keyword = |name: &str| node.properties.get(name).and_then(|property| property.as_keyword());
+        let clips = |name: &str| !matches!(keyword(name), None | Some("visible" | "clip"));
+        let element = matches!(node.data, RenderNodeData::Element(_) | RenderNodeData::PseudoElement(_));
+        if !element || !(clips("overflow-x") || clips("overflow-y")) {
+            return None;
+        }
+
+        let position = self.document_position(id);
+        let size = node.layout.size();
+        let border = node.layout.border();
+        Some(Rect {
+            left: position.x + border.left,
+            right: position.x + size.width - border.right,
+            top: position.y + border.top,
+            bottom: position.y + size.height - border.bottom,
+        })
+    }
+
+    /// Returns how far a sticky positioned box is moved from its position in normal flow to stay in
+    /// view, when the document is scrolled by `scroll` in a viewport of the given size (CSS
+    /// Positioned Layout 3, 3.4)
+    pub fn sticky_offset(&self, id: NodeId, scroll: types::Point<FP>, viewport: types::Size<FP>) -> types::Point<FP> {
+        let none = types::Point::new(0.0, 0.0);
+        let Some(node) = self.nodes.get(&id) else {
+            return none;
+        };
+
+        // The nearest scroll container, or the viewport
+        let mut ancestor = node.parent;
+        let scrollport = loop {
+            match ancestor {
+                Some(id) => match self.scroll_container(id) {
+                    Some(scrollport) => break scrollport,
+                    None => ancestor = self.nodes.get(&id).and_then(|node| node.parent),
+                },
+                None => {
+                    break Rect {
+                        left: scroll.x,
+                        right: scroll.x + viewport.width,
+                        top: scroll.y,
+                        bottom: scroll.y + viewport.height,
+                    }
+                }
+            }
+        };
+
+        // The box stays within the content box of its containing block, the nearest block container
+        let mut parent = node.parent;
+        while let Some(id) = parent.filter(|id| self.box_kind(*id) == BoxKind::Inline) {
+            parent = self.nodes.get(&id).and_then(|node| node.parent);
+        }
+        let Some(containing_block) = parent.and_then(|id| self.nodes.get(&id)) else {
+            return none;
+        };
+        let cb_position = self.document_position(containing_block.id);
+        let cb_size = containing_block.layout.size();
+        let cb_border = containing_block.layout.border();
+        let cb_padding = containing_block.layout.padding();
+        let cb_start = types::Point::new(
+            cb_position.x + cb_border.left + cb_padding.left,
+            cb_position.y + cb_border.top + cb_padding.top,
+        );
+        let cb_end = types::Point::new(
+            cb_position.x + cb_size.width - cb_border.right - cb_padding.right,
+            cb_position.y + cb_size.height - cb_border.bottom - cb_padding.bottom,
+        );
+
+        let position = self.document_position(id);
+        let size = node.layout.size();
+        let margin = node.layout.margin();
+
+        let width = Some(scrollport.right - scrollport.left);
+        let height = Some(scrollport.bottom - scrollport.top);
+        let inset = inset(&node.properties, Size { width, height });
+
+        let x = sticky_shift(
+            position.x,
+            position.x + size.width,
+            inset.left.maybe_resolve(width).map(|left| scrollport.left + left),
+            inset.right.maybe_resolve(width).map(|right| scrollport.right - right),
+            cb_start.x + margin.left,
+            cb_end.x - margin.right,
+        );
+        let y = sticky_shift(
+            position.y,
+            position.y + size.height,
+            inset.top.maybe_resolve(height).map(|top| scrollport.top + top),
+            inset
+                .bottom
+                .maybe_resolve(height)
+                .map(|bottom| scrollport.bottom - bottom),
+            cb_start.y + margin.top,
+            cb_end.y - margin.bottom,
+        );
+
+        types::Point::new(x, y)
+    }
+}
+
+/// Returns how far a sticky box from `start` to `end` moves along one axis to stay between the
+/// edges of its sticky view rectangle, without leaving the area between `min` and `max`. The start
+/// edge wins when the rectangle is too small.
+fn sticky_shift(start: f32, end: f32, view_start: Option<f32>, view_end: Option<f32>, min: f32, max: f32) -> f32 {
+    let mut shift = 0.0;
+    if let Some(view_end) = view_end {
+        shift = (view_end - end).min(0.0);
+    }
+    if let Some(view_start) = view_start {
+        if start + shift < view_start {
+            shift = view_start - start;
+        }
+    }
+
+    if shift > 0.0 {
+        shift.min((max - end).max(0.0))
+    } else {
+        shift.max((min - start).min(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{node, rect, TestConfig, TestDocument};
+
+    fn layout(html: &str, css: &str) -> (TestDocument, RenderTree<TestConfig>) {
+        let doc = TestDocument::with_css(html, &format!("body {{ margin: 0; font: 10px monospace }} {css}"));
+        let tree = doc.layout(800, 600);
+        (doc, tree)
+    }
+
+    #[test]
+    fn relative() {
+        let (doc, tree) = layout(
+            "<div id=a></div><div id=b></div>",
+            "div { height: 10px } #a { position: relative; top: 5px; left: 10px }",
+        );
+        assert_eq!(rect(&tree, &doc, "a"), (10.0, 5.0, 800.0, 10.0));
+        // The box keeps its place in normal flow
+        assert_eq!(rect(&tree, &doc, "b"), (0.0, 10.0, 800.0, 10.0));
+    }
+
+    #[test]
+    fn containing_block() {
+        let (doc, tree) = layout(
+            "<div id=cb><div id=abs></div><div id=percent></div></div><div id=icb></div>",
+            "#cb { position: relative; margin: 20px; border: 5px solid; padding: 10px; height: 100px }
+             #abs { position: absolute; top: 0; left: 0; width: 50px; height: 50px }
+             #percent { position: absolute; top: 10%; left: 50%; width: 50%; height: 10px }
+             #icb { position: absolute; right: 0; bottom: 0; width: 10px; height: 10px }",
+        );
+        // The padding box of the nearest positioned ancestor, which is 750px by 120px
+        assert_eq!(rect(&tree, &doc, "abs"), (25.0, 25.0, 50.0, 50.0));
+        assert_eq!(rect(&tree, &doc, "percent"), (400.0, 37.0, 375.0, 10.0));
+        // Without a positioned ancestor, the initial containing block
+        assert_eq!(rect(&tree, &doc, "icb"), (790.0, 590.0, 10.0, 10.0));
+    }
+
+    #[test]
+    fn static_position() {
+        let (doc, tree) = layout(
+            "<div id=before></div><div id=block></div><p>abc<span id=inline></span>def</p>",
+            "#before { height: 30px } p { margin: 0 }
+             #block { position: absolute; margin-left: 5px; width: 10px; height: 10px }
+             #inline { position: absolute; width: 10px; height: 10px }",
+        );
+        assert_eq!(rect(&tree, &doc, "block"), (5.0, 30.0, 10.0, 10.0));
+        // In an inline formatting context, the position of the content that follows
+        assert_eq!(rect(&tree, &doc, "inline"), (18.0, 30.0, 10.0, 10.0));
+
+        // A block-level box starts a new line after the content before it
+        let (doc, tree) = layout(
+            "<div id=r><span>aaa</span><div id=a></div></div>",
+            "#r { position: relative } #a { position: absolute }",
+        );
+        assert_eq!(rect(&tree, &doc, "a"), (0.0, 12.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn constraints() {
+        let (doc, tree) = layout(
+            "<div id=stretch></div><div id=center></div><div id=fit>abc</div><div id=max></div>",
+            "div { position: absolute; height: 10px }
+             #stretch { left: 10px; right: 20px }
+             #center { left: 0; right: 0; width: 100px; margin: 0 auto }
+             #fit { right: 0 }
+             #max { left: 0; right: 0; max-width: 100px }",
+        );
+        assert_eq!(rect(&tree, &doc, "stretch"), (10.0, 0.0, 770.0, 10.0));
+        assert_eq!(rect(&tree, &doc, "center"), (350.0, 0.0, 100.0, 10.0));
+        // An auto width shrinks to fit the content
+        assert_eq!(rect(&tree, &doc, "fit"), (782.0, 0.0, 18.0, 10.0));
+        // A width that is clamped over-constrains the box, so `right` is ignored
+        assert_eq!(rect(&tree, &doc, "max"), (0.0, 0.0, 100.0, 10.0));
+    }
+
+    #[test]
+    fn fixed() {
+        let (doc, tree) = layout(
+            "<div id=cb><div id=fixed></div><div id=far></div></div><div id=tall></div>",
+            "#cb { position: relative; margin: 50px }
+             #fixed { position: fixed; left: 0; bottom: 0; width: 10px; height: 10px }
+             #far { position: fixed; top: 2000px; width: 10px; height: 10px }
+             #tall { height: 1000px }",
+        );
+        assert_eq!(rect(&tree, &doc, "fixed"), (0.0, 590.0, 10.0, 10.0));
+        // Fixed boxes do not make the document scroll
+        assert_eq!(tree.scrollable_overflow(), types::Size::new(800.0, 1050.0));
+    }
+
+    #[test]
+    fn sticky() {
+        let (doc, tree) = layout(
+            "<div id=cb><div id=sticky></div></div><div id=after></div>",
+            "#cb { height: 300px } #sticky { position: sticky; top: 10px; height: 20px } #after { height: 1000px }",
+        );
+        let sticky = node(&tree, &doc, "sticky").id;
+        let offset = |y| tree.sticky_offset(sticky, types::Point::new(0.0, y), types::Size::new(800.0, 600.0));
+
+        assert_eq!(offset(0.0), types::Point::new(0.0, 10.0));
+        assert_eq!(offset(100.0), types::Point::new(0.0, 110.0));
+        // The box does not leave its containing block
+        assert_eq!(offset(500.0), types::Point::new(0.0, 280.0));
+    }
+}
@@ -0,0 +1,568 @@
+use taffy::{BoxSizing, Dimension, LayoutInput, LayoutOutput, MaybeResolve, ResolveOrZero, Size, SizingMode};
+
+use crate::{
+    image::Image,
+    interface::{
+        config::HasLayouter,
+        css3::{CssProperty, CssPropertyMap},
+        layout::Layout,
+    },
+    shared::{geo::FP, node::NodeId, types},
+    taffy::TaffyLayouter,
+    util::render_tree::{layout::node_id, RenderTree},
+};
+
+/// The size of a replaced element without a natural size, like a video without a poster (CSS 2.1,
+/// 10.3.2)
+const DEFAULT_WIDTH: f32 = 300.0;
+const DEFAULT_HEIGHT: f32 = 150.0;
+
+/// The natural dimensions of the content of a replaced element (CSS Images 3, 4.1), any of which
+/// can be missing
+#[derive(Debug, Clone, Copy, Default)]
+struct NaturalSize {
+    width: Option<f32>,
+    height: Option<f32>,
+    ratio: Option<f32>,
+}
+
+impl NaturalSize {
+    fn new(width: f32, height: f32) -> Self {
+        Self {
+            width: Some(width),
+            height: Some(height),
+            ratio: (width > 0.0 && height > 0.0).then(|| width / height),
+        }
+    }
+}
+
+/// The image of a replaced element and where it is drawn, relative to the border box of the
+/// element
+#[derive(Debug, Clone)]
+pub struct ReplacedImage<'a> {
+    pub image: &'a Image,
+    /// The area the image is scaled into, which can reach out of the content box
+    pub origin: types::Point<FP>,
+    pub size: types::Size<FP>,
+    /// The content box of the element, which the image is clipped to
+    pub clip_origin: types::Point<FP>,
+    pub clip_size: types::Size<FP>,
+}
+
+impl<C: HasLayouter<LayoutTree = Self>> RenderTree<C> {
+    /// Sets the decoded image of a replaced element, which is the source of an `<img>` or the
+    /// poster of a `<video>`. The image is kept when the tree is generated again.
+    pub fn set_image(&mut self, dom_id: NodeId, image: Image) {
+        self.images.insert(dom_id, image);
+    }
+
+    pub(super) fn is_replaced(&self, id: NodeId) -> bool {
+        self.nodes
+            .get(&id)
+            .and_then(|node| node.element())
+            .is_some_and(|element| element.is_replaced())
+    }
+
+    /// Returns the image of a replaced element, fitted into its content box with `object-fit` and
+    /// `object-position` (CSS Images 3, 5.5 and 5.6)
+    pub fn replaced_image(&self, id: NodeId) -> Option<ReplacedImage<'_>> {
+        let node = self.nodes.get(&id)?;
+        let image = self.images.get(&node.dom_id?)?;
+
+        let size = node.layout.size();
+        let border = node.layout.border();
+        let padding = node.layout.padding();
+        let clip_origin = types::Point::new(border.left + padding.left, border.top + padding.top);
+        let clip_size = types::Size::new(
+            (size.width - clip_origin.x - padding.right - border.right).max(0.0),
+            (size.height - clip_origin.y - padding.bottom - border.bottom).max(0.0),
+        );
+
+        let fit = node
+            .properties
+            .get("object-fit")
+            .and_then(|property| property.as_keyword())
+            .unwrap_or("fill");
+        let fitted = object_size(
+            fit,
+            Size {
+                width: image.width() as f32,
+                height: image.height() as f32,
+            },
+            Size {
+                width: clip_size.width,
+                height: clip_size.height,
+            },
+        );
+        let (x, y) = self.object_position(id, clip_size.width - fitted.width, clip_size.height - fitted.height);
+
+        Some(ReplacedImage {
+            image,
+            origin: types::Point::new(clip_origin.x + x, clip_origin.y + y),
+            size: types::Size::new(fitted.width, fitted.height),
+            clip_origin,
+            clip_size,
+        })
+    }
+
+    /// Returns the offset of the image in the content box, given the space left next to and below
+    /// it. The image is placed like `background-position` places a background.
+    fn object_position(&self, id: NodeId, free_x: f32, free_y: f32) -> (f32, f32) {
+        let center = (free_x / 2.0, free_y / 2.0);
+        let Some(property) = self
+            .nodes
+            .get(&id)
+            .and_then(|node| node.properties.get("object-position"))
+        else {
+            return center;
+        };
+        let items = property.as_list().unwrap_or_else(|| vec![property.clone()]);
+        let keyword = |index: usize| items.get(index).and_then(|item| item.as_keyword());
+        let vertical = |keyword: Option<&str>| matches!(keyword, Some("top" | "bottom"));
+        let horizontal = |keyword: Option<&str>| matches!(keyword, Some("left" | "right"));
+
+        match items.as_slice() {
+            [item] if vertical(keyword(0)) => (center.0, position_offset(item, free_y)),
+            [item] => (position_offset(item, free_x), center.1),
+            [first, second] if vertical(keyword(0)) || horizontal(keyword(1)) => {
+                (position_offset(second, free_x), position_offset(first, free_y))
+            }
+            [first, second] => (position_offset(first, free_x), position_offset(second, free_y)),
+            // Offsets from the edges, like `right 10px bottom 20%`
+            [_, first_offset, _, second_offset] => {
+                let first = edge_offset(keyword(0), first_offset, free_x, free_y);
+                let second = edge_offset(keyword(2), second_offset, free_x, free_y);
+                match vertical(keyword(0)) {
+                    true => (second, first),
+                    false => (first, second),
+                }
+            }
+            _ => center,
+        }
+    }
+
+    /// Returns the natural size of the content of a replaced element. Images that are not loaded
+    /// have no size, and canvases are as large as their `width` and `height` attributes.
+    fn natural_size(&self, id: NodeId) -> NaturalSize {
+        let Some(node) = self.nodes.get(&id) else {
+            return NaturalSize::default();
+        };
+        let image = node
+            .dom_id
+            .and_then(|dom_id| self.images.get(&dom_id))
+            .map(|image| NaturalSize::new(image.width() as f32, image.height() as f32));
+        let attribute = |name: &str, default: f32| {
+            node.element()
+                .and_then(|element| element.attribute(name))
+                .and_then(|value| value.trim().parse::<u32>().ok())
+                .map_or(default, |value| value as f32)
+        };
+
+        match node.element().map(|element| element.name.as_str()) {
+            Some("img") => image.unwrap_or(NaturalSize::new(0.0, 0.0)),
+            Some("video") => image.unwrap_or(NaturalSize::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)),
+            Some("canvas") => NaturalSize::new(attribute("width", DEFAULT_WIDTH), attribute("height", DEFAULT_HEIGHT)),
+            _ => NaturalSize::default(),
+        }
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> RenderTree<C> {
+    /// Prepares the styles of replaced elements for layout. The `width` and `height` attributes of
+    /// images and videos are used when their width and height are auto (HTML, 15.4.3), and
+    /// `aspect-ratio: auto` uses the ratio of the content, or else the ratio of the attributes.
+    pub(super) fn prepare_replaced(&mut self) {
+        let replaced = self
+            .nodes
+            .keys()
+            .copied()
+            .filter(|id| self.is_replaced(*id))
+            .collect::<Vec<_>>();
+
+        for id in replaced {
+            let natural = self.natural_size(id);
+            let node = self.nodes.get_mut(&id).expect("node of the render tree");
+            let element = node.element().expect("replaced element");
+            let hints = match element.name.as_str() {
+                "canvas" => Size {
+                    width: None,
+                    height: None,
+                },
+                _ => Size {
+                    width: element.attribute("width").and_then(dimension_attribute),
+                    height: element.attribute("height").and_then(dimension_attribute),
+                },
+            };
+            let hint_ratio = match hints {
+                Size {
+                    width: Some(Dimension::Length(width)),
+                    height: Some(Dimension::Length(height)),
+                } if width > 0.0 && height > 0.0 => Some(width / height),
+                _ => None,
+            };
+            let auto_ratio = match node.properties.get("aspect-ratio") {
+                Some(property) => match property.as_list() {
+                    Some(items) => items.iter().any(|item| item.as_keyword() == Some("auto")),
+                    None => property.as_keyword() == Some("auto"),
+                },
+                None => true,
+            };
+
+            let style = &mut node.layout.style;
+            if style.size.width == Dimension::Auto {
+                style.size.width = hints.width.unwrap_or(Dimension::Auto);
+            }
+            if style.size.height == Dimension::Auto {
+                style.size.height = hints.height.unwrap_or(Dimension::Auto);
+            }
+            if auto_ratio {
+                style.aspect_ratio = natural.ratio.or(style.aspect_ratio).or(hint_ratio);
+            }
+            // Replaced elements are not stretched to the width of their containing block
+            style.item_is_table = true;
+        }
+    }
+
+    /// Lays out a replaced element. An auto width or height is found from the other one and the
+    /// aspect ratio, or else from the natural size of the content (CSS 2.1, 10.3.2 and 10.6.2).
+    pub(super) fn compute_replaced_layout(&mut self, id: taffy::NodeId, inputs: LayoutInput) -> LayoutOutput {
+        let natural = self.natural_size(node_id(id));
+        let style = &self.nodes[&node_id(id)].layout.style;
+        let parent_size = inputs.parent_size;
+
+        let edges = style.padding.resolve_or_zero(parent_size.width) + style.border.resolve_or_zero(parent_size.width);
+        let edges = Size {
+            width: edges.left + edges.right,
+            height: edges.top + edges.bottom,
+        };
+        let content_box =
+            |size: Size<Option<f32>>| size.zip_map(edges, |size, edges| size.map(|size| (size - edges).max(0.0)));
+        let styled = |size: Size<Option<f32>>| match style.box_sizing {
+            BoxSizing::BorderBox => content_box(size),
+            BoxSizing::ContentBox => size,
+        };
+
+        let (size, min_size, max_size) = match inputs.sizing_mode {
+            SizingMode::ContentSize => (Size::NONE, Size::NONE, Size::NONE),
+            SizingMode::InherentSize => (
+                styled(style.size.maybe_resolve(parent_size)),
+                styled(style.min_size.maybe_resolve(parent_size)),
+                styled(style.max_size.maybe_resolve(parent_size)),
+            ),
+        };
+        let known = content_box(inputs.known_dimensions);
+        let size = match known {
+            Size {
+                width: Some(width),
+                height: Some(height),
+            } => Size { width, height },
+            _ => used_size(known.or(size), min_size, max_size, natural, style.aspect_ratio),
+        };
+
+        LayoutOutput::from_outer_size(Size {
+            width: size.width + edges.width,
+            height: size.height + edges.height,
+        })
+    }
+}
+
+/// Returns the size of the content box of a replaced element, from its specified size, its min
+/// and max sizes and the natural size of its content (CSS 2.1, 10.3.2, 10.4 and 10.6.2)
+fn used_size(
+    size: Size<Option<f32>>,
+    min_size: Size<Option<f32>>,
+    max_size: Size<Option<f32>>,
+    natural: NaturalSize,
+    ratio: Option<f32>,
+) -> Size<f32> {
+    let min_size = min_size.map(|size| size.unwrap_or(0.0));
+    let max_size = max_size.zip_map(min_size, |max, min| max.unwrap_or(f32::INFINITY).max(min));
+    let clamp_width = |width: f32| width.min(max_size.width).max(min_size.width);
+    let clamp_height = |height: f32| height.min(max_size.height).max(min_size.height);
+
+    match (size.width, size.height) {
+        (Some(width), Some(height)) => Size {
+            width: clamp_width(width),
+            height: clamp_height(height),
+        },
+        (Some(width), None) => {
+            let width = clamp_width(width);
+            let height = ratio.map(|ratio| width / ratio).or(natural.height);
+            Size {
+                width,
+                height: clamp_height(height.unwrap_or(DEFAULT_HEIGHT)),
+            }
+        }
+        (None, Some(height)) => {
+            let height = clamp_height(height);
+            let width = ratio.map(|ratio| height * ratio).or(natural.width);
+            Size {
+                width: clamp_width(width.unwrap_or(DEFAULT_WIDTH)),
+                height,
+            }
+        }
+        (None, None) => {
+            let width = natural
+                .width
+                .or(natural.height.zip(ratio).map(|(height, ratio)| height * ratio))
+                .unwrap_or(DEFAULT_WIDTH);
+            let height = ratio
+                .map(|ratio| width / ratio)
+                .or(natural.height)
+                .unwrap_or(DEFAULT_HEIGHT);
+            match ratio {
+                Some(_) => constrain(Size { width, height }, min_size, max_size),
+                None => Size {
+                    width: clamp_width(width),
+                    height: clamp_height(height),
+                },
+            }
+        }
+    }
+}
+
+/// Applies min and max sizes to the size of a replaced element, keeping its aspect ratio as far as
+/// they allow (CSS 2.1, 10.4)
+fn constrain(size: Size<f32>, min: Size<f32>, max: Size<f32>) -> Size<f32> {
+    let Size { width: w, height: h } = size;
+    if w <= 0.0 || h <= 0.0 {
+        return Size {
+            width: w.min(max.width).max(min.width),
+            height: h.min(max.height).max(min.height),
+        };
+    }
+
+    let (width, height) = match (w > max.width, w < min.width, h > max.height, h < min.height) {
+        (true, _, true, _) if max.width / w <= max.height / h => (max.width, min.height.max(max.width * h / w)),
+        (true, _, true, _) => (min.width.max(max.height * w / h), max.height),
+        (_, true, _, true) if min.width / w <= min.height / h => (max.width.min(min.height * w / h), min.height),
+        (_, true, _, true) => (min.width, max.height.min(min.width * h / w)),
+        (_, true, true, _) => (min.width, max.height),
+        (true, _, _, true) => (max.width, min.height),
+        (true, ..) => (max.width, min.height.max(max.width * h / w)),
+        (_, true, ..) => (min.width, max.height.min(min.width * h / w)),
+        (.., true, _) => (min.width.max(max.height * w / h), max.height),
+        (.., true) => (max.width.min(min.height * w / h), min.height),
+        _ => (w, h),
+    };
+
+    Size { width, height }
+}
+
+/// Returns the size of the image in the content box for `object-fit` (CSS Images 3, 5.5)
+fn object_size(fit: &str, natural: Size<f32>, content: Size<f32>) -> Size<f32> {
+    if natural.width <= 0.0 || natural.height <= 0.0 {
+        return content;
+    }
+
+    let contain = (content.width / natural.width).min(content.height / natural.height);
+    let scale = match fit {
+        "contain" => contain,
+        "cover" => (content.width / natural.width).max(content.height / natural.height),
+        "none" => 1.0,
+        "scale-down" => contain.min(1.0),
+        _ => return content,
+    };
+
+    Size {
+        width: natural.width * scale,
+        height: natural.height * scale,
+    }
+}
+
+/// Returns the offset of one value of `object-position`: a keyword, a length or a percentage of the
+/// free space
+fn position_offset<P: CssProperty>(item: &P, free: f32) -> f32 {
+    match item.as_keyword() {
+        Some("left" | "top") => 0.0,
+        Some("right" | "bottom") => free,
+        Some(_) => free / 2.0,
+        None => item.resolve_px(Some(free)).unwrap_or(free / 2.0),
+    }
+}
+
+/// Returns the offset of an edge keyword followed by an offset from that edge
+fn edge_offset<P: CssProperty>(edge: Option<&str>, offset: &P, free_x: f32, free_y: f32) -> f32 {
+    let free = match edge {
+        Some("top" | "bottom") => free_y,
+        _ => free_x,
+    };
+    let offset = offset.resolve_px(Some(free)).unwrap_or(0.0);
+    match edge {
+        Some("right" | "bottom") => free - offset,
+        _ => offset,
+    }
+}
+
+/// Parses the `width` or `height` attribute of an image, which is a number of pixels or a
+/// percentage (HTML, 2.3.4.4)
+fn dimension_attribute(value: &str) -> Option<Dimension> {
+    let value = value.trim_start_matches([' ', '\t', '\n', '\x0c', '\r']);
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let number = value[..digits].trim_end_matches('.').parse::<f32>().ok()?;
+
+    match value[digits..].starts_with('%') {
+        true => Some(Dimension::Percent(number / 100.0)),
+        false => Some(Dimension::Length(number)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use taffy::Dimension;
+
+    use super::dimension_attribute;
+    use crate::{
+        image::Image,
+        interface::{css3::MediaEnvironment, layout::LayoutTree},
+        shared::geo::SizeU32,
+        testing::{node, rect, TestConfig, TestDocument},
+        util::render_tree::RenderTree,
+    };
+
+    /// Lays out the document with images of the given sizes for the elements with the given ids
+    fn layout(html: &str, css: &str, images: &[(&str, u32, u32)]) -> (TestDocument, RenderTree<TestConfig>) {
+        let doc = TestDocument::with_css(html, &format!("body {{ margin: 0 }} {css}"));
+        let mut tree = doc.render_tree();
+        for (id, width, height) in images {
+            let data = vec![0; (width * height * 4) as usize];
+            tree.set_image(doc.id(id), Image::new(data, *width, *height));
+        }
+        tree.set_media(MediaEnvironment::from_size(SizeU32::new(800, 600)));
+        tree.layout(SizeU32::new(800, 600));
+        (doc, tree)
+    }
+
+    fn size(tree: &RenderTree<TestConfig>, doc: &TestDocument, id: &str) -> (f32, f32) {
+        let (_, _, width, height) = rect(tree, doc, id);
+        (width, height)
+    }
+
+    fn fitted(tree: &RenderTree<TestConfig>, doc: &TestDocument, id: &str) -> (f32, f32, f32, f32) {
+        let image = tree.replaced_image(node(tree, doc, id).id).unwrap();
+        (image.origin.x, image.origin.y, image.size.width, image.size.height)
+    }
+
+    #[test]
+    fn natural_size() {
+        let (doc, tree) = layout(
+            "<img id=a><img id=b><img id=c><img id=d><img id=e><img id=f>",
+            "img { display: block } #b { width: 100px } #c { height: 10px } #d { max-width: 100px } \
+             #e { min-width: 20px; max-height: 60px } #f { width: 100px; height: 10px }",
+            &[
+                ("a", 40, 20),
+                ("b", 40, 20),
+                ("c", 40, 20),
+                ("d", 400, 200),
+                ("e", 10, 40),
+                ("f", 40, 20),
+            ],
+        );
+
+        assert_eq!(size(&tree, &doc, "a"), (40.0, 20.0));
+        // An auto width or height follows from the other one and the aspect ratio of the image
+        assert_eq!(size(&tree, &doc, "b"), (100.0, 50.0));
+        assert_eq!(size(&tree, &doc, "c"), (20.0, 10.0));
+        // Min and max sizes keep the aspect ratio as far as they allow
+        assert_eq!(size(&tree, &doc, "d"), (100.0, 50.0));
+        assert_eq!(size(&tree, &doc, "e"), (20.0, 60.0));
+        assert_eq!(size(&tree, &doc, "f"), (100.0, 10.0));
+    }
+
+    #[test]
+    fn size_attributes() {
+        let (doc, tree) = layout(
+            "<img id=a width=60><img id=b width=60 height=30><img id=c width=60 height=60>\
+             <img id=d width=60 height=60><img id=e width=50%>",
+            "img { display: block } #d { width: 30px }",
+            &[("a", 40, 20), ("c", 40, 20), ("d", 40, 20), ("e", 40, 20)],
+        );
+
+        assert_eq!(size(&tree, &doc, "a"), (60.0, 30.0));
+        // Images that are not loaded are as large as their attributes
+        assert_eq!(size(&tree, &doc, "b"), (60.0, 30.0));
+        assert_eq!(size(&tree, &doc, "c"), (60.0, 60.0));
+        // The `width` property wins over the attribute, but the `height` attribute still applies
+        assert_eq!(size(&tree, &doc, "d"), (30.0, 60.0));
+        assert_eq!(size(&tree, &doc, "e"), (400.0, 200.0));
+    }
+
+    #[test]
+    fn default_size() {
+        let (doc, tree) = layout(
+            "<canvas id=a></canvas><canvas id=b width=40 height=30></canvas><video id=c></video><img id=d>",
+            "canvas, video, img { display: block }",
+            &[],
+        );
+
+        assert_eq!(size(&tree, &doc, "a"), (300.0, 150.0));
+        assert_eq!(size(&tree, &doc, "b"), (40.0, 30.0));
+        assert_eq!(size(&tree, &doc, "c"), (300.0, 150.0));
+        assert_eq!(size(&tree, &doc, "d"), (0.0, 0.0));
+        assert!(tree.replaced_image(node(&tree, &doc, "d").id).is_none());
+    }
+
+    #[test]
+    fn object_fit() {
+        let (doc, tree) = layout(
+            "<img id=fill><img id=contain><img id=cover><img id=none><img id=scale-down><img id=edges>",
+            "img { display: block; width: 100px; height: 100px } #contain { object-fit: contain } \
+             #cover { object-fit: cover } #none { object-fit: none } #scale-down { object-fit: scale-down } \
+             #edges { object-fit: contain; padding: 5px; border: 1px solid }",
+            &[
+                ("fill", 40, 20),
+                ("contain", 40, 20),
+                ("cover", 40, 20),
+                ("none", 40, 20),
+                ("scale-down", 40, 20),
+                ("edges", 40, 20),
+            ],
+        );
+
+        assert_eq!(fitted(&tree, &doc, "fill"), (0.0, 0.0, 100.0, 100.0));
+        assert_eq!(fitted(&tree, &doc, "contain"), (0.0, 25.0, 100.0, 50.0));
+        assert_eq!(fitted(&tree, &doc, "cover"), (-50.0, 0.0, 200.0, 100.0));
+        assert_eq!(fitted(&tree, &doc, "none"), (30.0, 40.0, 40.0, 20.0));
+        assert_eq!(fitted(&tree, &doc, "scale-down"), (30.0, 40.0, 40.0, 20.0));
+
+        // The image is fitted into the content box, and clipped to it
+        let edges = tree.replaced_image(node(&tree, &doc, "edges").id).unwrap();
+        assert_eq!((edges.origin.x, edges.origin.y), (6.0, 31.0));
+        assert_eq!((edges.clip_origin.x, edges.clip_origin.y), (6.0, 6.0));
+        assert_eq!((edges.clip_size.width, edges.clip_size.height), (100.0, 100.0));
+    }
+
+    #[test]
+    fn object_position() {
+        let (doc, tree) = layout(
+            "<img id=a><img id=b><img id=c><img id=d><img id=e>",
+            "img { display: block; width: 100px; height: 100px; object-fit: none } \
+             #a { object-position: left top } #b { object-position: right 10px bottom 20% } \
+             #c { object-position: 25% 10px } #d { object-position: bottom } #e { object-position: top right }",
+            &[
+                ("a", 40, 20),
+                ("b", 40, 20),
+                ("c", 40, 20),
+                ("d", 40, 20),
+                ("e", 40, 20),
+            ],
+        );
+
+        assert_eq!(fitted(&tree, &doc, "a"), (0.0, 0.0, 40.0, 20.0));
+        assert_eq!(fitted(&tree, &doc, "b"), (50.0, 64.0, 40.0, 20.0));
+        assert_eq!(fitted(&tree, &doc, "c"), (15.0, 10.0, 40.0, 20.0));
+        assert_eq!(fitted(&tree, &doc, "d"), (30.0, 80.0, 40.0, 20.0));
+        assert_eq!(fitted(&tree, &doc, "e"), (60.0, 0.0, 40.0, 20.0));
+    }
+
+    #[test]
+    fn dimension_attributes() {
+        assert_eq!(dimension_attribute(" 60"), Some(Dimension::Length(60.0)));
+        assert_eq!(dimension_attribute("60px"), Some(Dimension::Length(60.0)));
+        assert_eq!(dimension_attribute("12.5%"), Some(Dimension::Percent(0.125)));
+        assert_eq!(dimension_attribute("60."), Some(Dimension::Length(60.0)));
+        assert_eq!(dimension_attribute("auto"), None);
+    }
+}
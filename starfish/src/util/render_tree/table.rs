@@ -0,0 +1,1095 @@
+use std::ops::Range;
+
+use taffy::{
+    AvailableSpace, Dimension, Layout, LayoutInput, LayoutOutput, LayoutPartialTree, LengthPercentage, Line,
+    MaybeResolve, Point, Rect, RequestedAxis, ResolveOrZero, RunMode, Size, SizingMode, Style,
+};
+
+use crate::{
+    interface::{
+        config::HasLayouter,
+        css3::{CssProperty, CssPropertyMap},
+    },
+    shared::node::NodeId,
+    taffy::TaffyLayouter,
+    util::render_tree::{
+        anonymous::BoxKind,
+        flow::border_box,
+        layout::{node_id, taffy_id},
+        RenderTree,
+    },
+};
+
+use tracks::{CellWidths, SpecifiedWidth};
+
+mod tracks;
+
+/// The most columns a cell or column element can span (HTML, 4.9.11)
+const MAX_COLSPAN: usize = 1000;
+
+/// A cell placed in the grid of a table
+#[derive(Debug, Clone)]
+struct GridCell {
+    id: NodeId,
+    row: usize,
+    column: usize,
+    rows: usize,
+    columns: usize,
+}
+
+/// The rows and columns of a table, and the slots of the cells in them (HTML, 4.9.12.1)
+#[derive(Debug, Default)]
+struct TableGrid {
+    /// Row groups in the order they are laid out, with the rows in them. Rows that are children of
+    /// the table itself are in groups without a box.
+    groups: Vec<(Option<NodeId>, Range<usize>)>,
+    rows: Vec<NodeId>,
+    /// Column and column group boxes, with the columns they span
+    columns: Vec<(NodeId, Range<usize>)>,
+    column_count: usize,
+    cells: Vec<GridCell>,
+}
+
+/// The intrinsic size of the content of a cell
+#[derive(Debug, Clone, Copy)]
+struct CellContent {
+    /// The least height of the cell, from its content and its specified height
+    height: f32,
+    /// The height of the cell as if its height were auto, which is aligned in its rows
+    auto_height: f32,
+    /// The first baseline of the cell, or the bottom of its content box when it has no line boxes
+    baseline: f32,
+    align: CellAlign,
+}
+
+/// The `vertical-align` of a cell, which aligns its content in its rows (CSS 2.1, 17.5.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellAlign {
+    Baseline,
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl CellAlign {
+    fn from_keyword(keyword: &str) -> Self {
+        match keyword {
+            "top" => Self::Top,
+            "middle" => Self::Middle,
+            "bottom" => Self::Bottom,
+            _ => Self::Baseline,
+        }
+    }
+}
+
+impl<C: HasLayouter<LayoutTree = Self, Layouter = TaffyLayouter>> RenderTree<C> {
+    /// Prepares the styles of tables for layout. Table wrapper boxes are sized like tables instead
+    /// of stretched, and tables with `border-collapse: collapse` share the borders of their cells.
+    pub(super) fn prepare_tables(&mut self) {
+        let tables = self
+            .nodes
+            .keys()
+            .copied()
+            .filter(|id| matches!(self.box_kind(*id), BoxKind::Table | BoxKind::InlineTable))
+            .collect::<Vec<_>>();
+
+        for table in tables {
+            if let Some(wrapper) = self.nodes[&table].parent.and_then(|parent| self.nodes.get_mut(&parent)) {
+                wrapper.layout.style.item_is_table = true;
+            }
+            if self.keyword(table, "border-collapse") == "collapse" {
+                self.collapse_borders(table);
+            }
+        }
+    }
+
+    /// Lays out a table wrapper box: the table, with its captions above and below it (CSS 2.1,
+    /// 17.4). The wrapper is as wide as the table, or as its widest caption at min-content width.
+    pub(super) fn compute_table_wrapper_layout(&mut self, id: taffy::NodeId, inputs: LayoutInput) -> LayoutOutput {
+        let wrapper = node_id(id);
+        let children = self.nodes[&wrapper].children.clone();
+        let Some(table) = children
+            .iter()
+            .copied()
+            .find(|child| matches!(self.box_kind(*child), BoxKind::Table | BoxKind::InlineTable))
+        else {
+            return LayoutOutput::HIDDEN;
+        };
+
+        let available = match inputs.known_dimensions.width {
+            Some(width) => AvailableSpace::Definite(width),
+            None => inputs.available_space.width,
+        };
+        let measure = |tree: &mut Self, child: NodeId, known: Size<Option<f32>>, available: AvailableSpace| {
+            tree.compute_child_layout(
+                taffy_id(child),
+                LayoutInput {
+                    known_dimensions: known,
+                    parent_size: inputs.parent_size,
+                    available_space: Size {
+                        width: available,
+                        height: AvailableSpace::MaxContent,
+                    },
+                    sizing_mode: SizingMode::InherentSize,
+                    axis: RequestedAxis::Both,
+                    run_mode: RunMode::ComputeSize,
+                    vertical_margins_are_collapsible: Line::FALSE,
+                },
+            )
+        };
+
+        let table_size = measure(self, table, Size::NONE, available).size;
+        let mut width = table_size.width;
+        for caption in children.iter().filter(|child| **child != table) {
+            let margin = self.nodes[caption]
+                .layout
+                .style
+                .margin
+                .resolve_or_zero(Some(table_size.width));
+            let min_content = measure(self, *caption, Size::NONE, AvailableSpace::MinContent)
+                .size
+                .width;
+            width = width.max(min_content + margin.left + margin.right);
+        }
+        let width = inputs.known_dimensions.width.unwrap_or(width);
+
+        let mut y = 0.0;
+        let mut first_baseline = None;
+        for (order, child) in children.iter().copied().enumerate() {
+            let style = self.nodes[&child].layout.style.clone();
+            let margin = match child == table {
+                true => Rect::zero(),
+                false => style.margin.resolve_or_zero(Some(width)),
+            };
+            let known = Size {
+                width: Some(match child == table {
+                    true => table_size.width,
+                    false => width - margin.left - margin.right,
+                }),
+                height: None,
+            };
+            let output = self.compute_child_layout(
+                taffy_id(child),
+                LayoutInput {
+                    known_dimensions: known,
+                    parent_size: inputs.parent_size,
+                    available_space: known.map(|size| AvailableSpace::Definite(size.unwrap_or_default())),
+                    sizing_mode: SizingMode::InherentSize,
+                    axis: RequestedAxis::Both,
+                    run_mode: inputs.run_mode,
+                    vertical_margins_are_collapsible: Line::FALSE,
+                },
+            );
+
+            y += margin.top;
+            if child == table {
+                first_baseline = output.first_baselines.y.map(|baseline| y + baseline);
+            }
+            if inputs.run_mode == RunMode::PerformLayout {
+                let layout = Layout {
+                    order: order as u32,
+                    location: Point { x: margin.left, y },
+                    size: output.size,
+                    content_size: output.content_size,
+                    border: style.border.resolve_or_zero(Some(width)),
+                    padding: style.padding.resolve_or_zero(Some(width)),
+                    margin,
+                    ..Layout::new()
+                };
+                self.set_unrounded_layout(taffy_id(child), &layout);
+            }
+            y += output.size.height + margin.bottom;
+        }
+
+        let size = Size {
+            width,
+            height: inputs.known_dimensions.height.unwrap_or(y),
+        };
+        LayoutOutput::from_sizes_and_baselines(
+            size,
+            size,
+            Point {
+                x: None,
+                y: first_baseline,
+            },
+        )
+    }
+
+    /// Lays out a table box and its rows, columns and cells (CSS 2.1, 17.5)
+    pub(super) fn compute_table_layout(&mut self, id: taffy::NodeId, inputs: LayoutInput) -> LayoutOutput {
+        let table = node_id(id);
+        let style = self.nodes[&table].layout.style.clone();
+        let grid = self.table_grid(table);
+
+        let parent_width = inputs.parent_size.width;
+        let insets = style.padding.resolve_or_zero(parent_width) + style.border.resolve_or_zero(parent_width);
+        let spacing = self.border_spacing(table);
+        let spacing = Size {
+            width: if grid.column_count > 0 { spacing.width } else { 0.0 },
+            height: if grid.rows.is_empty() { 0.0 } else { spacing.height },
+        };
+        let edges = Size {
+            width: insets.left + insets.right + spacing.width * (grid.column_count + 1) as f32,
+            height: insets.top + insets.bottom + spacing.height * (grid.rows.len() + 1) as f32,
+        };
+
+        let specified = inputs.known_dimensions.width.or_else(|| {
+            let edges = insets.left + insets.right;
+            let min_width = border_box(&style, style.min_size.width.maybe_resolve(parent_width), edges);
+            let max_width = border_box(&style, style.max_size.width.maybe_resolve(parent_width), edges);
+            border_box(&style, style.size.width.maybe_resolve(parent_width), edges).map(|width| {
+                width
+                    .min(max_width.unwrap_or(f32::INFINITY))
+                    .max(min_width.unwrap_or(0.0))
+            })
+        });
+
+        // Columns
+        let (width, widths) = match specified {
+            Some(width) if self.keyword(table, "table-layout") == "fixed" => {
+                let widths = tracks::fixed_widths(&self.fixed_column_widths(&grid), (width - edges.width).max(0.0));
+                (width.max(widths.iter().sum::<f32>() + edges.width), widths)
+            }
+            _ => {
+                let columns = self.auto_columns(&grid, spacing.width);
+                let min = tracks::min_width(&columns) + edges.width;
+                let max = tracks::max_width(&columns) + edges.width;
+                let width = match (specified, inputs.available_space.width) {
+                    (Some(width), _) => width.max(min),
+                    (None, AvailableSpace::Definite(available)) => max.min(available).max(min),
+                    (None, AvailableSpace::MinContent) => min,
+                    (None, AvailableSpace::MaxContent) => max,
+                };
+                (width, tracks::distribute_width(&columns, width - edges.width))
+            }
+        };
+
+        let rtl = self.keyword(table, "direction") == "rtl";
+        let mut x = Vec::with_capacity(widths.len());
+        let mut position = insets.left + spacing.width;
+        for width in &widths {
+            x.push(position);
+            position += width + spacing.width;
+        }
+        if rtl {
+            for (x, column_width) in x.iter_mut().zip(&widths) {
+                *x = width - *x - column_width;
+            }
+        }
+        let span_width =
+            |column: usize, count: usize| sum(&widths[column..column + count]) + spacing.width * (count - 1) as f32;
+
+        if inputs.run_mode == RunMode::ComputeSize && inputs.axis == RequestedAxis::Horizontal {
+            return LayoutOutput::from_outer_size(Size {
+                width,
+                height: inputs.known_dimensions.height.unwrap_or(0.0),
+            });
+        }
+
+        // Rows
+        let contents = grid
+            .cells
+            .iter()
+            .map(|cell| self.cell_content(cell.id, span_width(cell.column, cell.columns)))
+            .collect::<Vec<_>>();
+        let mut heights = grid
+            .rows
+            .iter()
+            .map(|row| {
+                self.nodes[row]
+                    .layout
+                    .style
+                    .size
+                    .height
+                    .maybe_resolve(None::<f32>)
+                    .unwrap_or(0.0)
+            })
+            .collect::<Vec<_>>();
+        let mut ascents = vec![None::<f32>; grid.rows.len()];
+        let mut descents = vec![0.0_f32; grid.rows.len()];
+
+        for (cell, content) in grid.cells.iter().zip(&contents) {
+            if cell.rows != 1 {
+                continue;
+            }
+            match content.align {
+                CellAlign::Baseline => {
+                    let ascent = ascents[cell.row].get_or_insert(0.0);
+                    *ascent = ascent.max(content.baseline);
+                    descents[cell.row] = descents[cell.row].max(content.height - content.baseline);
+                }
+                _ => heights[cell.row] = heights[cell.row].max(content.height),
+            }
+        }
+        for (row, height) in heights.iter_mut().enumerate() {
+            *height = height.max(ascents[row].unwrap_or(0.0) + descents[row]);
+        }
+
+        let mut spanning = grid
+            .cells
+            .iter()
+            .zip(&contents)
+            .filter(|(cell, _)| cell.rows > 1)
+            .collect::<Vec<_>>();
+        spanning.sort_by_key(|(cell, _)| cell.rows);
+        for (cell, content) in spanning {
+            let rows = cell.row..cell.row + cell.rows;
+            let height = sum(&heights[rows.clone()]) + spacing.height * (cell.rows - 1) as f32;
+            let weights = heights[rows.clone()].to_vec();
+            tracks::distribute(&mut heights[rows], content.height - height, &weights);
+        }
+
+        let content_height = sum(&heights) + edges.height;
+        let specified_height = inputs.known_dimensions.height.or_else(|| {
+            border_box(
+                &style,
+                style.size.height.maybe_resolve(inputs.parent_size.height),
+                insets.top + insets.bottom,
+            )
+        });
+        if let Some(height) = specified_height {
+            let weights = heights.clone();
+            tracks::distribute(&mut heights, height - content_height, &weights);
+        }
+        let height = specified_height.unwrap_or(content_height).max(content_height);
+
+        let mut y = Vec::with_capacity(heights.len());
+        let mut position = insets.top + spacing.height;
+        for height in &heights {
+            y.push(position);
+            position += height + spacing.height;
+        }
+        let span_height =
+            |row: usize, count: usize| sum(&heights[row..row + count]) + spacing.height * (count - 1) as f32;
+
+        // The baseline of a row is the baseline of its cells that are aligned to it, or else the
+        // lowest baseline of its cells
+        let baselines = (0..grid.rows.len())
+            .map(|row| {
+                ascents[row].or_else(|| {
+                    grid.cells
+                        .iter()
+                        .zip(&contents)
+                        .filter(|(cell, _)| cell.row == row)
+                        .map(|(cell, content)| {
+                            cell_offset(content, span_height(cell.row, cell.rows), None) + content.baseline
+                        })
+                        .reduce(f32::max)
+                })
+            })
+            .collect::<Vec<_>>();
+        let first_baseline = baselines
+            .iter()
+            .zip(&y)
+            .find_map(|(baseline, y)| baseline.map(|baseline| y + baseline));
+
+        let size = Size { width, height };
+        let output = LayoutOutput::from_sizes_and_baselines(
+            size,
+            size,
+            Point {
+                x: None,
+                y: first_baseline,
+            },
+        );
+        if inputs.run_mode != RunMode::PerformLayout {
+            return output;
+        }
+
+        // The rows and row groups span all columns, and the column boxes span all rows
+        let left = x
+            .iter()
+            .copied()
+            .reduce(f32::min)
+            .unwrap_or(insets.left + spacing.width);
+        let rows_width = sum(&widths) + spacing.width * grid.column_count.saturating_sub(1) as f32;
+        let row_top = |row: usize| y.get(row).copied().unwrap_or(position - spacing.height);
+        let rows_height = match grid.rows.is_empty() {
+            true => 0.0,
+            false => span_height(0, grid.rows.len()),
+        };
+        let columns_x =
+            |columns: &Range<usize>| columns.clone().map(|column| x[column]).reduce(f32::min).unwrap_or(left);
+
+        for (order, (group, rows)) in grid.groups.iter().enumerate() {
+            let top = row_top(rows.start);
+            let group_origin = match group {
+                Some(group) => {
+                    let height = match rows.is_empty() {
+                        true => 0.0,
+                        false => span_height(rows.start, rows.len()),
+                    };
+                    let location = Point { x: left, y: top };
+                    self.set_table_part_layout(
+                        *group,
+                        order,
+                        location,
+                        Size {
+                            width: rows_width,
+                            height,
+                        },
+                    );
+                    location
+                }
+                None => Point::ZERO,
+            };
+
+            for row in rows.clone() {
+                let location = Point {
+                    x: left - group_origin.x,
+                    y: y[row] - group_origin.y,
+                };
+                let size = Size {
+                    width: rows_width,
+                    height: heights[row],
+                };
+                self.set_table_part_layout(grid.rows[row], row, location, size);
+            }
+        }
+
+        for (order, (column, columns)) in grid.columns.iter().enumerate() {
+            let parent = self.nodes[column].parent;
+            let origin = match grid.columns.iter().find(|(group, _)| Some(*group) == parent) {
+                Some((_, columns)) => Point {
+                    x: columns_x(columns),
+                    y: row_top(0),
+                },
+                None => Point::ZERO,
+            };
+            let width = match columns.is_empty() {
+                true => 0.0,
+                false => span_width(columns.start, columns.len()),
+            };
+            let location = Point {
+                x: columns_x(columns) - origin.x,
+                y: row_top(0) - origin.y,
+            };
+            let size = Size {
+                width,
+                height: rows_height,
+            };
+            self.set_table_part_layout(*column, order, location, size);
+        }
+
+        for (order, (cell, content)) in grid.cells.iter().zip(&contents).enumerate() {
+            let size = Size {
+                width: span_width(cell.column, cell.columns),
+                height: span_height(cell.row, cell.rows),
+            };
+            let output = self.compute_child_layout(
+                taffy_id(cell.id),
+                LayoutInput {
+                    known_dimensions: size.map(Some),
+                    parent_size: size.map(Some),
+                    available_space: size.map(AvailableSpace::Definite),
+                    sizing_mode: SizingMode::InherentSize,
+                    axis: RequestedAxis::Both,
+                    run_mode: RunMode::PerformLayout,
+                    vertical_margins_are_collapsible: Line::FALSE,
+                },
+            );
+            self.shift_content(cell.id, cell_offset(content, size.height, baselines[cell.row]));
+
+            let cell_style = &self.nodes[&cell.id].layout.style;
+            let layout = Layout {
+                order: order as u32,
+                location: Point {
+                    x: x[cell.column..cell.column + cell.columns]
+                        .iter()
+                        .copied()
+                        .fold(f32::INFINITY, f32::min)
+                        - left,
+                    y: 0.0,
+                },
+                size,
+                content_size: output.content_size,
+                border: cell_style.border.resolve_or_zero(Some(size.width)),
+                padding: cell_style.padding.resolve_or_zero(Some(size.width)),
+                ..Layout::new()
+            };
+            self.set_unrounded_layout(taffy_id(cell.id), &layout);
+        }
+
+        output
+    }
+
+    /// Returns true for the anonymous box that wraps a table and its captions
+    pub(super) fn is_table_wrapper(&self, id: NodeId) -> bool {
+        self.nodes.get(&id).is_some_and(|node| {
+            node.children
+                .iter()
+                .any(|child| matches!(self.box_kind(*child), BoxKind::Table | BoxKind::InlineTable))
+        })
+    }
+
+    /// Places the rows, columns and cells of a table in its grid of slots
+    fn table_grid(&self, table: NodeId) -> TableGrid {
+        let mut grid = TableGrid::default();
+        let children = &self.nodes[&table].children;
+
+        // The first header group is laid out first and the first footer group last, the other row
+        // groups and the rows in document order
+        let header = children
+            .iter()
+            .copied()
+            .find(|child| self.keyword(*child, "display") == "table-header-group");
+        let footer = children
+            .iter()
+            .copied()
+            .find(|child| self.keyword(*child, "display") == "table-footer-group");
+        let body = children
+            .iter()
+            .copied()
+            .filter(|child| Some(*child) != header && Some(*child) != footer);
+
+        let mut occupied: Vec<Vec<bool>> = Vec::new();
+        // Rows directly in the table are grouped with the rows next to them
+        let mut loose_rows = Vec::new();
+        for child in header.into_iter().chain(body).chain(footer) {
+            if self.box_kind(child) == BoxKind::Row {
+                loose_rows.push(child);
+                continue;
+            }
+            if !loose_rows.is_empty() {
+                let rows = std::mem::take(&mut loose_rows);
+                grid.add_group(None, rows, &mut occupied, |row| self.table_cells(row));
+            }
+
+            match self.box_kind(child) {
+                BoxKind::RowGroup => {
+                    let rows = self.nodes[&child].children.clone();
+                    grid.add_group(Some(child), rows, &mut occupied, |row| self.table_cells(row));
+                }
+                BoxKind::ColumnGroup => {
+                    let start = grid.column_count_of_columns();
+                    let columns = self.nodes[&child].children.clone();
+                    if columns.is_empty() {
+                        grid.columns.push((child, start..start + self.span(child, "span")));
+                    } else {
+                        let mut end = start;
+                        for column in columns {
+                            let span = self.span(column, "span");
+                            grid.columns.push((column, end..end + span));
+                            end += span;
+                        }
+                        grid.columns.push((child, start..end));
+                    }
+                }
+                BoxKind::Column => {
+                    let start = grid.column_count_of_columns();
+                    grid.columns.push((child, start..start + self.span(child, "span")));
+                }
+                _ => {}
+            }
+        }
+        if !loose_rows.is_empty() {
+            grid.add_group(None, loose_rows, &mut occupied, |row| self.table_cells(row));
+        }
+
+        grid.column_count = grid
+            .cells
+            .iter()
+            .map(|cell| cell.column + cell.columns)
+            .chain(grid.columns.iter().map(|(_, columns)| columns.end))
+            .max()
+            .unwrap_or(0);
+
+        grid
+    }
+
+    /// Returns the cells of a row with their `colspan` and `rowspan`, where a `rowspan` of zero spans
+    /// the rest of the row group (HTML, 4.9.11)
+    fn table_cells(&self, row: NodeId) -> Vec<(NodeId, usize, usize)> {
+        self.nodes[&row]
+            .children
+            .iter()
+            .map(|cell| {
+                let rowspan = self.attribute_number(*cell, "rowspan").unwrap_or(1).min(65534);
+                (*cell, self.span(*cell, "colspan"), rowspan)
+            })
+            .collect()
+    }
+
+    /// Returns the number of columns a cell or column element spans, from one of its attributes
+    fn span(&self, id: NodeId, attribute: &str) -> usize {
+        self.attribute_number(id, attribute).unwrap_or(1).clamp(1, MAX_COLSPAN)
+    }
+
+    fn attribute_number(&self, id: NodeId, attribute: &str) -> Option<usize> {
+        self.nodes[&id].element()?.attribute(attribute)?.trim().parse().ok()
+    }
+
+    /// Returns the widths of the columns given by the column elements of the table. The width of a
+    /// column element is the width of each column it spans.
+    fn column_widths(&self, grid: &TableGrid) -> Vec<Option<SpecifiedWidth>> {
+        let mut widths = vec![None; grid.column_count];
+        // Column groups come after their columns, so the width of a column is used before the
+        // width of its group
+        for (column, columns) in &grid.columns {
+            let width = specified_width(&self.nodes[column].layout.style, 0.0);
+            for slot in &mut widths[columns.clone()] {
+                if slot.is_none() {
+                    *slot = width;
+                }
+            }
+        }
+
+        widths
+    }
+
+    /// Returns the widths of the columns for the fixed table layout, from the column elements and
+    /// the cells in the first row
+    fn fixed_column_widths(&self, grid: &TableGrid) -> Vec<Option<SpecifiedWidth>> {
+        let mut widths = self.column_widths(grid);
+        for cell in grid.cells.iter().filter(|cell| cell.row == 0) {
+            let style = &self.nodes[&cell.id].layout.style;
+            let width = specified_width(style, horizontal_edges(style));
+            for slot in &mut widths[cell.column..cell.column + cell.columns] {
+                if slot.is_none() {
+                    *slot = width.map(|width| per_column(width, cell.columns as f32));
+                }
+            }
+        }
+
+        widths
+    }
+
+    /// Computes the min-content and max-content widths of the columns from their cells
+    fn auto_columns(&mut self, grid: &TableGrid, spacing: f32) -> Vec<tracks::Column> {
+        let cells = grid
+            .cells
+            .iter()
+            .map(|cell| {
+                let style = self.nodes[&cell.id].layout.style.clone();
+                CellWidths {
+                    column: cell.column,
+                    span: cell.columns,
+                    min: self.measure_cell(cell.id, None, AvailableSpace::MinContent).size.width,
+                    max: self.measure_cell(cell.id, None, AvailableSpace::MaxContent).size.width,
+                    specified: specified_width(&style, horizontal_edges(&style)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        tracks::auto_columns(&self.column_widths(grid), &cells, spacing)
+    }
+
+    /// Measures the content of a cell in the given width, and how it is aligned in its rows
+    fn cell_content(&mut self, cell: NodeId, width: f32) -> CellContent {
+        let style = self.nodes[&cell].layout.style.clone();
+        let output = self.measure_cell(cell, Some(width), AvailableSpace::Definite(width));
+
+        // The height of a cell is only its least height, as the cell is as high as its rows
+        let edges = style.padding.resolve_or_zero(None) + style.border.resolve_or_zero(None);
+        let specified = border_box(
+            &style,
+            style.size.height.maybe_resolve(None::<f32>),
+            edges.top + edges.bottom,
+        );
+        let height = output.size.height.max(specified.unwrap_or(0.0));
+
+        let baseline = output.first_baselines.y.unwrap_or(output.size.height - edges.bottom);
+
+        CellContent {
+            height,
+            auto_height: output.size.height,
+            baseline,
+            align: CellAlign::from_keyword(self.keyword(cell, "vertical-align")),
+        }
+    }
+
+    /// Lays out a cell to measure its content. The size of a cell is only the least size of its
+    /// columns and rows, so it is left out of the measurement.
+    fn measure_cell(&mut self, cell: NodeId, width: Option<f32>, available: AvailableSpace) -> LayoutOutput {
+        let size = self
+            .nodes
+            .get_mut(&cell)
+            .map(|node| std::mem::replace(&mut node.layout.style.size, Size::auto()));
+
+        let output = self.compute_child_layout(
+            taffy_id(cell),
+            LayoutInput {
+                known_dimensions: Size { width, height: None },
+                parent_size: Size::NONE,
+                available_space: Size {
+                    width: available,
+                    height: AvailableSpace::MaxContent,
+                },
+                sizing_mode: SizingMode::InherentSize,
+                axis: RequestedAxis::Both,
+                run_mode: RunMode::ComputeSize,
+                vertical_margins_are_collapsible: Line::FALSE,
+            },
+        );
+
+        if let (Some(size), Some(node)) = (size, self.nodes.get_mut(&cell)) {
+            node.layout.style.size = size;
+        }
+
+        output
+    }
+
+    /// Moves the content of a box down, for the vertical alignment of a cell
+    fn shift_content(&mut self, id: NodeId, offset: f32) {
+        if offset <= 0.0 {
+            return;
+        }
+
+        let children = self.nodes[&id].children.clone();
+        for child in children {
+            if let Some(node) = self.nodes.get_mut(&child) {
+                node.layout.unrounded.location.y += offset;
+            }
+        }
+        if let Some(inline_layout) = self.nodes.get_mut(&id).and_then(|node| node.inline_layout.as_mut()) {
+            for line in &mut inline_layout.lines {
+                line.y += offset;
+                for fragment in &mut line.fragments {
+                    fragment.y += offset;
+                }
+            }
+        }
+    }
+
+    /// Sets the layout of a row, row group, column or column group
+    fn set_table_part_layout(&mut self, id: NodeId, order: usize, location: Point<f32>, size: Size<f32>) {
+        let layout = Layout {
+            order: order as u32,
+            location,
+            size,
+            content_size: size,
+            ..Layout::new()
+        };
+        self.set_unrounded_layout(taffy_id(id), &layout);
+    }
+
+    /// Returns the horizontal and vertical `border-spacing` of a table, which is zero when its
+    /// borders are collapsed
+    fn border_spacing(&self, table: NodeId) -> Size<f32> {
+        if self.keyword(table, "border-collapse") == "collapse" {
+            return Size::ZERO;
+        }
+
+        let Some(property) = self.nodes[&table].properties.get("border-spacing") else {
+            return Size::ZERO;
+        };
+        let lengths = match property.as_list() {
+            Some(list) => list.iter().filter_map(|length| length.as_px()).collect::<Vec<_>>(),
+            None => property.as_px().into_iter().collect(),
+        };
+
+        match lengths[..] {
+            [horizontal, vertical, ..] => Size {
+                width: horizontal,
+                height: vertical,
+            },
+            [both] => Size {
+                width: both,
+                height: both,
+            },
+            [] => Size::ZERO,
+        }
+    }
+
+    /// Resolves the borders of a table in the collapsing border model (CSS 2.1, 17.6.2). Each edge
+    /// of the grid gets the widest border of the cells, rows, row groups and table along it, and
+    /// the cells and table get half of the edges around them. Tables have no padding in this model.
+    fn collapse_borders(&mut self, table: NodeId) {
+        let grid = self.table_grid(table);
+        let (rows, columns) = (grid.rows.len(), grid.column_count);
+        // Horizontal edges above each row and below the last one, and vertical edges left of each
+        // column and right of the last one
+        let mut horizontal = vec![vec![0.0_f32; columns]; rows + 1];
+        let mut vertical = vec![vec![0.0_f32; columns + 1]; rows];
+
+        let border = |id: NodeId| self.nodes[&id].layout.style.border.resolve_or_zero(None::<f32>);
+        let mut apply = |border: Rect<f32>, rows: Range<usize>, columns: Range<usize>| {
+            for column in columns.clone() {
+                horizontal[rows.start][column] = horizontal[rows.start][column].max(border.top);
+                horizontal[rows.end][column] = horizontal[rows.end][column].max(border.bottom);
+            }
+            for row in rows {
+                vertical[row][columns.start] = vertical[row][columns.start].max(border.left);
+                vertical[row][columns.end] = vertical[row][columns.end].max(border.right);
+            }
+        };
+
+        apply(border(table), 0..rows, 0..columns);
+        for (group, group_rows) in &grid.groups {
+            if let Some(group) = group {
+                apply(border(*group), group_rows.clone(), 0..columns);
+            }
+        }
+        for (row, id) in grid.rows.iter().enumerate() {
+            apply(border(*id), row..row + 1, 0..columns);
+        }
+        for cell in &grid.cells {
+            apply(
+                border(cell.id),
+                cell.row..cell.row + cell.rows,
+                cell.column..cell.column + cell.columns,
+            );
+        }
+
+        let half = |width: f32| LengthPercentage::Length(width / 2.0);
+        let widest = |edges: &mut dyn Iterator<Item = f32>| edges.fold(0.0, f32::max);
+        for cell in &grid.cells {
+            let (cell_rows, cell_columns) = (cell.row..cell.row + cell.rows, cell.column..cell.column + cell.columns);
+            let edges = Rect {
+                left: widest(&mut cell_rows.clone().map(|row| vertical[row][cell.column])),
+                right: widest(&mut cell_rows.clone().map(|row| vertical[row][cell_columns.end])),
+                top: widest(&mut cell_columns.clone().map(|column| horizontal[cell.row][column])),
+                bottom: widest(&mut cell_columns.clone().map(|column| horizontal[cell_rows.end][column])),
+            };
+            if let Some(node) = self.nodes.get_mut(&cell.id) {
+                node.layout.style.border = edges.map(half);
+            }
+        }
+
+        let edges = Rect {
+            left: widest(&mut vertical.iter().map(|row| row[0])),
+            right: widest(&mut vertical.iter().map(|row| row[columns])),
+            top: widest(&mut horizontal[0].iter().copied()),
+            bottom: widest(&mut horizontal[rows].iter().copied()),
+        };
+        let parts = grid
+            .groups
+            .iter()
+            .filter_map(|(group, _)| *group)
+            .chain(grid.rows.iter().copied())
+            .collect::<Vec<_>>();
+        for part in parts {
+            if let Some(node) = self.nodes.get_mut(&part) {
+                node.layout.style.border = Rect::zero();
+            }
+        }
+        if let Some(node) = self.nodes.get_mut(&table) {
+            node.layout.style.border = edges.map(half);
+            node.layout.style.padding = Rect::zero();
+        }
+    }
+
+    fn keyword(&self, id: NodeId, name: &str) -> &str {
+        self.nodes
+            .get(&id)
+            .and_then(|node| node.properties.get(name))
+            .and_then(|property| property.as_keyword())
+            .unwrap_or_default()
+    }
+}
+
+impl TableGrid {
+    /// Adds a row group to the grid, and places the cells of its rows in the first free slots
+    fn add_group(
+        &mut self,
+        group: Option<NodeId>,
+        rows: Vec<NodeId>,
+        occupied: &mut Vec<Vec<bool>>,
+        cells: impl Fn(NodeId) -> Vec<(NodeId, usize, usize)>,
+    ) {
+        let start = self.rows.len();
+        let end = start + rows.len();
+        occupied.resize(end, Vec::new());
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let row_index = start + index;
+            self.rows.push(row);
+
+            let mut column = 0;
+            for (id, columns, rows) in cells(row) {
+                while occupied[row_index].get(column).copied().unwrap_or(false) {
+                    column += 1;
+                }
+
+                // Cells do not span beyond their row group
+                let rows = match rows {
+                    0 => end - row_index,
+                    rows => rows.min(end - row_index),
+                };
+                for slots in &mut occupied[row_index..row_index + rows] {
+                    if slots.len() < column + columns {
+                        slots.resize(column + columns, false);
+                    }
+                    slots[column..column + columns].fill(true);
+                }
+
+                self.cells.push(GridCell {
+                    id,
+                    row: row_index,
+                    column,
+                    rows,
+                    columns,
+                });
+                column += columns;
+            }
+        }
+
+        self.groups.push((group, start..end));
+    }
+
+    /// Returns the number of columns given by the column elements so far
+    fn column_count_of_columns(&self) -> usize {
+        self.columns.iter().map(|(_, columns)| columns.end).max().unwrap_or(0)
+    }
+}
+
+/// Returns the width of a cell or column from its style, with `edges` added to a length for the
+/// padding and borders of the cell
+fn specified_width(style: &Style, edges: f32) -> Option<SpecifiedWidth> {
+    match style.size.width {
+        Dimension::Length(width) => Some(SpecifiedWidth::Fixed(match style.box_sizing {
+            taffy::BoxSizing::ContentBox => width + edges,
+            taffy::BoxSizing::BorderBox => width.max(edges),
+        })),
+        Dimension::Percent(fraction) => Some(SpecifiedWidth::Percent(fraction * 100.0)),
+        Dimension::Auto => None,
+    }
+}
+
+/// Returns the part of a width that spans several columns that goes to each of them
+fn per_column(width: SpecifiedWidth, count: f32) -> SpecifiedWidth {
+    match width {
+        SpecifiedWidth::Fixed(width) => SpecifiedWidth::Fixed(width / count),
+        SpecifiedWidth::Percent(percent) => SpecifiedWidth::Percent(percent / count),
+    }
+}
+
+fn horizontal_edges(style: &Style) -> f32 {
+    let edges = style.padding.resolve_or_zero(None) + style.border.resolve_or_zero(None);
+    edges.left + edges.right
+}
+
+/// Returns the offset of the content of a cell in its rows for its `vertical-align`, given the
+/// height of its rows and the baseline of its first row
+fn cell_offset(content: &CellContent, height: f32, baseline: Option<f32>) -> f32 {
+    let offset = match content.align {
+        CellAlign::Top => 0.0,
+        CellAlign::Middle => (height - content.auto_height) / 2.0,
+        CellAlign::Bottom => height - content.auto_height,
+        CellAlign::Baseline => baseline.map_or(0.0, |baseline| baseline - content.baseline),
+    };
+
+    offset.max(0.0)
+}
+
+fn sum(sizes: &[f32]) -> f32 {
+    sizes.iter().sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{node, rect, TestConfig, TestDocument};
+    use crate::util::render_tree::RenderTree;
+
+    fn layout(html: &str, css: &str) -> (TestDocument, RenderTree<TestConfig>) {
+        let doc = TestDocument::with_css(html, &format!("body {{ margin: 0; font: 10px monospace }} {css}"));
+        let tree = doc.layout(800, 600);
+        (doc, tree)
+    }
+
+    fn line_y(tree: &RenderTree<TestConfig>, doc: &TestDocument, id: &str) -> f32 {
+        node(tree, doc, id).inline_layout.as_ref().unwrap().lines[0].y
+    }
+
+    fn baseline(tree: &RenderTree<TestConfig>, doc: &TestDocument, id: &str) -> f32 {
+        let line = &node(tree, doc, id).inline_layout.as_ref().unwrap().lines[0];
+        line.y + line.baseline
+    }
+
+    #[test]
+    fn grid() {
+        let (doc, tree) = layout(
+            "<table><tr><td id=a colspan=2>aaaa</td><td id=b rowspan=2>b</td></tr>\
+             <tr><td id=c>c</td><td id=d>dd</td></tr><tr><td id=e colspan=3>e</td></tr></table>",
+            "table { border-spacing: 2px } td { padding: 1px }",
+        );
+
+        // The spanning cell is wider than its columns, which grow in proportion to their widths
+        assert_eq!(rect(&tree, &doc, "a"), (2.0, 2.0, 26.0, 14.0));
+        assert_eq!(rect(&tree, &doc, "c"), (2.0, 18.0, 9.0, 13.0));
+        assert_eq!(rect(&tree, &doc, "d"), (13.0, 18.0, 15.0, 13.0));
+        assert_eq!(rect(&tree, &doc, "b"), (30.0, 2.0, 8.0, 29.0));
+        assert_eq!(rect(&tree, &doc, "e"), (2.0, 33.0, 36.0, 14.0));
+    }
+
+    #[test]
+    fn fixed_layout() {
+        let (doc, tree) = layout(
+            "<table id=t><tr><td id=a>aaaaaaaaaaaaaaaaaaaaaaaaa</td><td id=b></td><td id=c></td></tr>\
+             <tr><td id=d>x</td></tr></table>",
+            "table { table-layout: fixed; width: 300px; border-spacing: 0 } td { padding: 0 } #a { width: 100px }",
+        );
+
+        // Only the first row sizes the columns, and the content does not widen them
+        assert_eq!(rect(&tree, &doc, "t"), (0.0, 0.0, 300.0, 23.0));
+        assert_eq!(rect(&tree, &doc, "a"), (0.0, 0.0, 100.0, 12.0));
+        assert_eq!(rect(&tree, &doc, "b"), (100.0, 0.0, 100.0, 12.0));
+        assert_eq!(rect(&tree, &doc, "c"), (200.0, 0.0, 100.0, 12.0));
+        assert_eq!(rect(&tree, &doc, "d"), (0.0, 12.0, 100.0, 11.0));
+    }
+
+    #[test]
+    fn auto_layout() {
+        let (doc, tree) = layout(
+            "<table id=t><tr><td id=a>aaa bbb</td><td id=b>cc</td></tr></table>\
+             <table id=w><tr><td id=p>x</td><td id=q>y</td></tr></table>",
+            "table { border-spacing: 0 } td { padding: 0 } #w { width: 400px } #p { width: 25% }",
+        );
+
+        // A table with an auto width shrinks to the max-content widths of its columns
+        assert_eq!(rect(&tree, &doc, "t"), (0.0, 0.0, 54.0, 12.0));
+        assert_eq!(rect(&tree, &doc, "a"), (0.0, 0.0, 42.0, 12.0));
+        assert_eq!(rect(&tree, &doc, "b"), (42.0, 0.0, 12.0, 12.0));
+
+        assert_eq!(rect(&tree, &doc, "p"), (0.0, 12.0, 100.0, 11.0));
+        assert_eq!(rect(&tree, &doc, "q"), (100.0, 12.0, 300.0, 11.0));
+    }
+
+    #[test]
+    fn collapsed_borders() {
+        let (doc, tree) = layout(
+            "<table id=t><tr><td id=a>a</td><td id=b>b</td></tr></table>",
+            "table { border-collapse: collapse; border: 4px solid } \
+             td { border: 2px solid; padding: 0 } #b { border-left-width: 6px }",
+        );
+
+        // The widest border wins on each edge, and the boxes share it half and half
+        assert_eq!(rect(&tree, &doc, "t"), (0.0, 0.0, 26.0, 20.0));
+        assert_eq!(rect(&tree, &doc, "a"), (2.0, 2.0, 11.0, 16.0));
+        assert_eq!(rect(&tree, &doc, "b"), (13.0, 2.0, 11.0, 16.0));
+    }
+
+    #[test]
+    fn captions() {
+        let (doc, tree) = layout(
+            "<table id=t><caption id=top>top caption</caption><tr><td id=a>a</td></tr>\
+             <caption id=bottom>x</caption></table>",
+            "table { border-spacing: 0 } td { padding: 0 } #bottom { caption-side: bottom }",
+        );
+
+        // The captions are as wide as the widest min-content width of the table and its captions
+        assert_eq!(rect(&tree, &doc, "top"), (0.0, 0.0, 42.0, 23.0));
+        assert_eq!(rect(&tree, &doc, "t"), (0.0, 23.0, 6.0, 12.0));
+        assert_eq!(rect(&tree, &doc, "bottom"), (0.0, 35.0, 42.0, 12.0));
+    }
+
+    #[test]
+    fn vertical_align() {
+        let (doc, tree) = layout(
+            "<table><tr><td id=a>a</td><td id=b>b</td><td id=c>c</td><td id=m>m</td><td id=o>o</td></tr></table>",
+            "table { border-spacing: 0 } td { padding: 0; vertical-align: baseline } #b { font-size: 20px } \
+             #c { height: 50px; vertical-align: bottom } #m { vertical-align: middle } #o { vertical-align: top }",
+        );
+
+        for id in ["a", "b", "c", "m", "o"] {
+            assert_eq!(rect(&tree, &doc, id).3, 50.0);
+        }
+
+        // The baselines of the baseline cells line up, and the other cells align their content in the row
+        assert_eq!(line_y(&tree, &doc, "b"), 0.0);
+        assert_eq!(baseline(&tree, &doc, "a"), baseline(&tree, &doc, "b"));
+        assert_eq!(line_y(&tree, &doc, "c"), 50.0 - 11.640625);
+        assert_eq!(line_y(&tree, &doc, "m"), (50.0 - 11.640625) / 2.0);
+        assert_eq!(line_y(&tree, &doc, "o"), 0.0);
+    }
+}
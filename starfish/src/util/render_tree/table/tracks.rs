@@ -0,0 +1,370 @@
+/// The width a cell or column element gives to its columns with its `width` property
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum SpecifiedWidth {
+    Fixed(f32),
+    Percent(f32),
+}
+
+/// The widths of a column in the automatic table layout, from the cells in it and its column
+/// element. Widths include the padding and borders of the cells.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct Column {
+    pub(super) min: f32,
+    pub(super) max: f32,
+    /// Set when a cell or column element gives the column a fixed width, which is then its `max`
+    pub(super) fixed: bool,
+    pub(super) percent: Option<f32>,
+}
+
+/// The intrinsic widths of a cell, and the columns it spans
+#[derive(Debug, Clone, Copy)]
+pub(super) struct CellWidths {
+    pub(super) column: usize,
+    pub(super) span: usize,
+    pub(super) min: f32,
+    pub(super) max: f32,
+    pub(super) specified: Option<SpecifiedWidth>,
+}
+
+/// Computes the widths of the columns from the cells in them, for the automatic table layout (CSS
+/// 2.1, 17.5.2.2). Cells that span several columns are distributed over them after the others, and
+/// `spacing` is the horizontal border spacing between the spanned columns.
+pub(super) fn auto_columns(specified: &[Option<SpecifiedWidth>], cells: &[CellWidths], spacing: f32) -> Vec<Column> {
+    let mut columns = specified
+        .iter()
+        .map(|width| match width {
+            Some(SpecifiedWidth::Fixed(width)) => Column {
+                max: *width,
+                fixed: true,
+                ..Column::default()
+            },
+            Some(SpecifiedWidth::Percent(percent)) => Column {
+                percent: Some(*percent),
+                ..Column::default()
+            },
+            None => Column::default(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut cells = cells.to_vec();
+    cells.sort_by_key(|cell| cell.span);
+
+    for cell in cells {
+        let fixed = match cell.specified {
+            Some(SpecifiedWidth::Fixed(width)) => Some(width.max(cell.min)),
+            _ => None,
+        };
+        let max = fixed.unwrap_or(cell.max).max(cell.min);
+        let percent = match cell.specified {
+            Some(SpecifiedWidth::Percent(percent)) => Some(percent),
+            _ => None,
+        };
+
+        let Some(spanned) = columns.get_mut(cell.column..cell.column + cell.span) else {
+            continue;
+        };
+        if let [column] = spanned {
+            column.min = column.min.max(cell.min);
+            column.max = column.max.max(max);
+            column.fixed |= fixed.is_some();
+            column.percent = match (column.percent, percent) {
+                (Some(column), Some(cell)) => Some(column.max(cell)),
+                (column, cell) => column.or(cell),
+            };
+            continue;
+        }
+
+        let inner = spacing * (cell.span - 1) as f32;
+        let weights = spanned.iter().map(|column| column.max).collect::<Vec<_>>();
+        grow(spanned, cell.min - inner, &weights, |column| &mut column.min);
+        grow(spanned, max - inner, &weights, |column| &mut column.max);
+    }
+
+    for column in &mut columns {
+        column.max = column.max.max(column.min);
+    }
+
+    columns
+}
+
+/// Grows the given sizes of the columns until they add up to `target`, in proportion to `weights`
+fn grow(columns: &mut [Column], target: f32, weights: &[f32], size: impl Fn(&mut Column) -> &mut f32) {
+    let mut sizes = columns.iter_mut().map(|column| *size(column)).collect::<Vec<_>>();
+    let extra = target - sizes.iter().sum::<f32>();
+    distribute(&mut sizes, extra, weights);
+    for (column, value) in columns.iter_mut().zip(sizes) {
+        *size(column) = value;
+    }
+}
+
+/// Returns the min-content width of the columns together
+pub(super) fn min_width(columns: &[Column]) -> f32 {
+    columns.iter().map(|column| column.min).sum()
+}
+
+/// Returns the max-content width of the columns together. Percentage columns make the table as wide
+/// as needed to give them their percentage of it.
+pub(super) fn max_width(columns: &[Column]) -> f32 {
+    let max = columns.iter().map(|column| column.max).sum::<f32>();
+
+    let percent = columns
+        .iter()
+        .filter_map(|column| column.percent)
+        .sum::<f32>()
+        .min(100.0);
+    let rest = columns
+        .iter()
+        .filter(|column| column.percent.is_none())
+        .map(|column| column.max)
+        .sum::<f32>();
+    let for_percentages = columns
+        .iter()
+        .filter_map(|column| Some(column.max / (column.percent? / 100.0)))
+        .filter(|width| width.is_finite())
+        .fold(0.0, f32::max);
+    let for_rest = match percent < 100.0 {
+        true => rest / (1.0 - percent / 100.0),
+        false => 0.0,
+    };
+
+    max.max(for_percentages).max(for_rest)
+}
+
+/// Distributes the given width over the columns (CSS Tables 3, 3.9.3). The width is shared between
+/// the columns in steps: first each column gets its min-content width, then percentage columns get
+/// their percentage, then fixed columns their fixed width, and then all columns their max-content
+/// width. The width between two steps is interpolated, and the width left after the last step goes
+/// to the auto columns, or else to the fixed or percentage columns.
+pub(super) fn distribute_width(columns: &[Column], width: f32) -> Vec<f32> {
+    let percentage = |column: &Column| {
+        column
+            .percent
+            .map_or(column.min, |percent| (width * percent / 100.0).max(column.min))
+    };
+    let steps: [Vec<f32>; 4] = [
+        columns.iter().map(|column| column.min).collect(),
+        columns.iter().map(percentage).collect(),
+        columns
+            .iter()
+            .map(|column| match column.fixed && column.percent.is_none() {
+                true => column.max,
+                false => percentage(column),
+            })
+            .collect(),
+        columns
+            .iter()
+            .map(|column| match column.percent {
+                Some(_) => percentage(column),
+                None => column.max,
+            })
+            .collect(),
+    ];
+
+    let mut previous: Option<&Vec<f32>> = None;
+    for step in &steps {
+        let total = step.iter().sum::<f32>();
+        if total >= width {
+            let Some(previous) = previous else {
+                return step.clone();
+            };
+            let start = previous.iter().sum::<f32>();
+            let ratio = match total > start {
+                true => (width - start) / (total - start),
+                false => 0.0,
+            };
+            return previous
+                .iter()
+                .zip(step)
+                .map(|(from, to)| from + (to - from) * ratio)
+                .collect();
+        }
+        previous = Some(step);
+    }
+
+    let mut widths = steps[3].clone();
+    let excess = width - widths.iter().sum::<f32>();
+    let auto = |column: &Column| !column.fixed && column.percent.is_none();
+    let fixed = |column: &Column| column.fixed && column.percent.is_none();
+    let receives: &dyn Fn(&Column) -> bool = if columns.iter().any(auto) {
+        &auto
+    } else if columns.iter().any(fixed) {
+        &fixed
+    } else {
+        &|_| true
+    };
+
+    let weights = columns
+        .iter()
+        .zip(&widths)
+        .map(|(column, width)| if receives(column) { *width } else { 0.0 })
+        .collect::<Vec<_>>();
+    let even = columns
+        .iter()
+        .map(|column| if receives(column) { 1.0 } else { 0.0 })
+        .collect::<Vec<_>>();
+    match weights.iter().sum::<f32>() > 0.0 {
+        true => distribute(&mut widths, excess, &weights),
+        false => distribute(&mut widths, excess, &even),
+    }
+
+    widths
+}
+
+/// Computes the widths of the columns for the fixed table layout (CSS 2.1, 17.5.2.1). The columns
+/// with a width from a column element or a cell in the first row get that width, and the other
+/// columns share the rest of the width equally. When all columns have a width, the rest is shared
+/// in proportion to them.
+pub(super) fn fixed_widths(specified: &[Option<SpecifiedWidth>], width: f32) -> Vec<f32> {
+    let mut widths = specified
+        .iter()
+        .map(|specified| match specified {
+            Some(SpecifiedWidth::Fixed(width)) => *width,
+            Some(SpecifiedWidth::Percent(percent)) => width * percent / 100.0,
+            None => 0.0,
+        })
+        .collect::<Vec<_>>();
+    let rest = width - widths.iter().sum::<f32>();
+    if rest <= 0.0 {
+        return widths;
+    }
+
+    let auto = specified
+        .iter()
+        .map(|specified| if specified.is_none() { 1.0 } else { 0.0 })
+        .collect::<Vec<_>>();
+    let weights = match auto.iter().sum::<f32>() > 0.0 {
+        true => auto,
+        false => widths.clone(),
+    };
+    distribute(&mut widths, rest, &weights);
+
+    widths
+}
+
+/// Adds `extra` to the sizes, in proportion to the weights, or equally when all weights are zero
+pub(super) fn distribute(sizes: &mut [f32], extra: f32, weights: &[f32]) {
+    if extra <= 0.0 || sizes.is_empty() {
+        return;
+    }
+
+    let total = weights.iter().sum::<f32>();
+    let count = sizes.len() as f32;
+    for (index, size) in sizes.iter_mut().enumerate() {
+        *size += match total > 0.0 {
+            true => extra * weights.get(index).copied().unwrap_or(0.0) / total,
+            false => extra / count,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(min: f32, max: f32, fixed: bool, percent: Option<f32>) -> Column {
+        Column {
+            min,
+            max,
+            fixed,
+            percent,
+        }
+    }
+
+    fn cell(column: usize, span: usize, min: f32, max: f32, specified: Option<SpecifiedWidth>) -> CellWidths {
+        CellWidths {
+            column,
+            span,
+            min,
+            max,
+            specified,
+        }
+    }
+
+    #[test]
+    fn columns_from_cells() {
+        let specified = [
+            None,
+            Some(SpecifiedWidth::Fixed(50.0)),
+            Some(SpecifiedWidth::Percent(20.0)),
+            None,
+        ];
+        let cells = [
+            // Spanning cells are distributed after the others, in proportion to the max widths
+            cell(0, 2, 100.0, 120.0, None),
+            cell(0, 1, 10.0, 30.0, None),
+            cell(1, 1, 20.0, 40.0, None),
+            cell(2, 1, 5.0, 15.0, Some(SpecifiedWidth::Percent(30.0))),
+            cell(3, 1, 8.0, 12.0, Some(SpecifiedWidth::Fixed(4.0))),
+            cell(2, 5, 1000.0, 1000.0, None),
+        ];
+
+        let columns = auto_columns(&specified, &cells, 10.0)
+            .iter()
+            .map(|column| (column.min, column.max, column.fixed, column.percent))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            [
+                (32.5, 41.25, false, None),
+                (57.5, 68.75, true, None),
+                (5.0, 15.0, false, Some(30.0)),
+                (8.0, 8.0, true, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn max_width_with_percentages() {
+        let columns = [column(10.0, 40.0, false, None), column(10.0, 20.0, false, Some(50.0))];
+        assert_eq!(min_width(&columns), 20.0);
+        assert_eq!(max_width(&columns), 80.0);
+
+        let columns = [
+            column(0.0, 30.0, false, Some(75.0)),
+            column(0.0, 5.0, false, Some(50.0)),
+        ];
+        assert_eq!(max_width(&columns), 40.0);
+    }
+
+    #[test]
+    fn distribute_width_in_steps() {
+        let columns = [
+            column(10.0, 50.0, false, None),
+            column(10.0, 20.0, false, Some(25.0)),
+            column(5.0, 30.0, true, None),
+        ];
+        assert_eq!(distribute_width(&columns, 20.0), [10.0, 10.0, 5.0]);
+        assert_eq!(distribute_width(&columns, 100.0), [45.0, 25.0, 30.0]);
+        assert_eq!(distribute_width(&columns, 200.0), [120.0, 50.0, 30.0]);
+
+        let columns = [column(5.0, 30.0, true, None), column(0.0, 0.0, false, Some(50.0))];
+        assert_eq!(distribute_width(&columns, 100.0), [50.0, 50.0]);
+    }
+
+    #[test]
+    fn fixed_layout_widths() {
+        let specified = [
+            Some(SpecifiedWidth::Fixed(100.0)),
+            Some(SpecifiedWidth::Percent(10.0)),
+            None,
+            None,
+        ];
+        assert_eq!(fixed_widths(&specified, 400.0), [100.0, 40.0, 130.0, 130.0]);
+
+        let specified = [Some(SpecifiedWidth::Fixed(100.0)), Some(SpecifiedWidth::Fixed(50.0))];
+        assert_eq!(fixed_widths(&specified, 300.0), [200.0, 100.0]);
+
+        let specified = [Some(SpecifiedWidth::Fixed(300.0)), None];
+        assert_eq!(fixed_widths(&specified, 200.0), [300.0, 0.0]);
+    }
+
+    #[test]
+    fn distribute_evenly_without_weights() {
+        let mut sizes = [1.0, 2.0];
+        distribute(&mut sizes, 4.0, &[0.0, 0.0]);
+        assert_eq!(sizes, [3.0, 4.0]);
+
+        distribute(&mut sizes, -1.0, &[1.0, 1.0]);
+        assert_eq!(sizes, [3.0, 4.0]);
+    }
+}
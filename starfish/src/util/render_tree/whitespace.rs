@@ -0,0 +1,327 @@
+use crate::{
+    interface::{
+        config::HasLayouter,
+        css3::{CssProperty, CssPropertyMap},
+    },
+    shared::node::NodeId,
+    util::render_tree::{anonymous::BoxKind, RenderNodeData, RenderTree},
+};
+
+const ZERO_WIDTH_SPACE: char = '\u{200b}';
+
+/// How white space in text is handled, from the `white-space-collapse` property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WhiteSpaceCollapse {
+    /// Sequences of white space collapse into a single space, segment breaks become spaces
+    Collapse,
+    /// All white space is removed
+    Discard,
+    /// All white space is kept
+    Preserve,
+    /// Like `Collapse`, but segment breaks are kept as forced line breaks
+    PreserveBreaks,
+    /// Spaces are kept, but tabs and segment breaks become spaces
+    PreserveSpaces,
+}
+
+impl WhiteSpaceCollapse {
+    fn from_keyword(keyword: Option<&str>) -> Self {
+        match keyword {
+            Some("discard") => Self::Discard,
+            Some("preserve" | "break-spaces") => Self::Preserve,
+            Some("preserve-breaks") => Self::PreserveBreaks,
+            Some("preserve-spaces") => Self::PreserveSpaces,
+            _ => Self::Collapse,
+        }
+    }
+
+    /// Returns true when spaces collapse, so they are removed at the start and end of lines
+    fn collapses_spaces(self) -> bool {
+        matches!(self, Self::Collapse | Self::PreserveBreaks)
+    }
+}
+
+/// A piece of the content of an inline formatting context
+enum InlineItem {
+    Text(NodeId),
+    /// An atomic inline, like an `inline-block`, which ends any sequence of white space
+    Atomic,
+}
+
+impl<C: HasLayouter<LayoutTree = Self>> RenderTree<C> {
+    /// Processes the white space of all text (CSS Text 3, 4.1). Text is collapsed across the inline
+    /// boxes of each inline formatting context, white space at the start and end of each context is
+    /// removed, and text (and anonymous boxes) that end up empty are removed from the tree.
+    pub(super) fn process_white_space(&mut self) {
+        let containers = self
+            .nodes
+            .keys()
+            .copied()
+            .filter(|id| self.is_block_container(*id))
+            .collect::<Vec<_>>();
+
+        let mut empty = Vec::new();
+        for container in containers {
+            let items = self.inline_items(container);
+            self.collapse_white_space(&items);
+            self.trim_trailing_white_space(&items);
+
+            empty.extend(items.into_iter().filter_map(|item| match item {
+                InlineItem::Text(id) => self.nodes[&id].text().is_some_and(str::is_empty).then_some(id),
+                InlineItem::Atomic => None,
+            }));
+        }
+
+        for id in empty {
+            self.remove_empty_node(id);
+        }
+    }
+
+    /// Returns the text and atomic inlines of the inline formatting context of a block container,
+    /// in order. Floats and absolutely positioned boxes are not part of the content.
+    fn inline_items(&self, container: NodeId) -> Vec<InlineItem> {
+        let mut items = Vec::new();
+
+        let Some(node) = self.nodes.get(&container) else {
+            return items;
+        };
+        let mut stack = node.children.iter().rev().copied().collect::<Vec<_>>();
+        while let Some(id) = stack.pop() {
+            match self.box_kind(id) {
+                BoxKind::Text => items.push(InlineItem::Text(id)),
+                BoxKind::Inline => stack.extend(self.nodes[&id].children.iter().rev()),
+                BoxKind::OutOfFlow => {}
+                // Block-level children mean the container has no inline formatting context
+                BoxKind::Block => return Vec::new(),
+                _ => items.push(InlineItem::Atomic),
+            }
+        }
+
+        items
+    }
+
+    /// Collapses white space and transforms segment breaks (CSS Text 3, 4.1.1). White space at the
+    /// start of the inline formatting context is removed, as it is at the start of a line.
+    fn collapse_white_space(&mut self, items: &[InlineItem]) {
+        let mut after_space = true;
+
+        for item in items {
+            let id = match item {
+                InlineItem::Text(id) => *id,
+                InlineItem::Atomic => {
+                    after_space = false;
+                    continue;
+                }
+            };
+
+            let Some(node) = self.nodes.get_mut(&id) else {
+                continue;
+            };
+            let mode = WhiteSpaceCollapse::from_keyword(
+                node.properties
+                    .get("white-space-collapse")
+                    .and_then(|property| property.as_keyword()),
+            );
+            if let RenderNodeData::Text(text) = &mut node.data {
+                text.text = collapse_text(&text.text, mode, &mut after_space);
+            }
+        }
+    }
+
+    /// Removes collapsible spaces at the end of the inline formatting context
+    fn trim_trailing_white_space(&mut self, items: &[InlineItem]) {
+        for item in items.iter().rev() {
+            let InlineItem::Text(id) = item else {
+                return;
+            };
+            let Some(node) = self.nodes.get_mut(id) else {
+                continue;
+            };
+
+            let collapses = WhiteSpaceCollapse::from_keyword(
+                node.properties
+                    .get("white-space-collapse")
+                    .and_then(|property| property.as_keyword()),
+            )
+            .collapses_spaces();
+            let RenderNodeData::Text(text) = &mut node.data else {
+                continue;
+            };
+
+            if collapses {
+                let trimmed = text.text.trim_end_matches(' ').len();
+                text.text.truncate(trimmed);
+            }
+            if !text.text.is_empty() {
+                return;
+            }
+        }
+    }
+
+    /// Removes a node, and any anonymous ancestor that becomes empty
+    fn remove_empty_node(&mut self, id: NodeId) {
+        let mut id = id;
+
+        loop {
+            let parent = self.nodes.get(&id).and_then(|node| node.parent);
+            self.remove_subtree(id);
+
+            let Some(parent_node) = parent.and_then(|parent| self.nodes.get_mut(&parent)) else {
+                return;
+            };
+            parent_node.children.retain(|child| *child != id);
+
+            let removable = parent_node.children.is_empty()
+                && parent_node.data == RenderNodeData::Anonymous
+                && matches!(
+                    parent_node
+                        .properties
+                        .get("display")
+                        .and_then(|display| display.as_keyword()),
+                    Some("block" | "inline")
+                );
+            match parent {
+                Some(parent) if removable => id = parent,
+                _ => return,
+            }
+        }
+    }
+}
+
+fn is_white_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
+
+/// Applies the white space rules of `mode` to a text. `after_space` is true when the text follows a
+/// collapsible space (or the start of a line), and is updated for the next text.
+fn collapse_text(text: &str, mode: WhiteSpaceCollapse, after_space: &mut bool) -> String {
+    match mode {
+        WhiteSpaceCollapse::Preserve => {
+            if let Some(last) = text.chars().last() {
+                *after_space = last == '\n';
+            }
+            return text.to_string();
+        }
+        WhiteSpaceCollapse::PreserveSpaces => {
+            if !text.is_empty() {
+                *after_space = false;
+            }
+            return text.chars().map(|c| if is_white_space(c) { ' ' } else { c }).collect();
+        }
+        WhiteSpaceCollapse::Discard => return text.chars().filter(|c| !is_white_space(*c)).collect(),
+        WhiteSpaceCollapse::Collapse | WhiteSpaceCollapse::PreserveBreaks => {}
+    }
+
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(text.len());
+
+    let mut pos = 0;
+    while pos < chars.len() {
+        if !is_white_space(chars[pos]) {
+            result.push(chars[pos]);
+            *after_space = false;
+            pos += 1;
+            continue;
+        }
+
+        let start = pos;
+        while pos < chars.len() && is_white_space(chars[pos]) {
+            pos += 1;
+        }
+        let breaks = chars[start..pos].iter().filter(|c| **c == '\n').count();
+
+        if breaks > 0 && mode == WhiteSpaceCollapse::PreserveBreaks {
+            // Spaces around a preserved segment break are removed
+            result.truncate(result.trim_end_matches(' ').len());
+            result.extend(std::iter::repeat_n('\n', breaks));
+            *after_space = true;
+            continue;
+        }
+
+        // A segment break next to a zero width space is removed instead of turned into a space. The
+        // rules for East Asian wide characters are not implemented.
+        let next_to_zwsp =
+            (start > 0 && chars[start - 1] == ZERO_WIDTH_SPACE) || chars.get(pos) == Some(&ZERO_WIDTH_SPACE);
+        if breaks > 0 && next_to_zwsp {
+            continue;
+        }
+
+        if !*after_space {
+            result.push(' ');
+            *after_space = true;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{body_tree, TestDocument};
+
+    fn collapse(text: &str, mode: WhiteSpaceCollapse) -> String {
+        collapse_text(text, mode, &mut false)
+    }
+
+    fn boxes(html: &str, css: &str) -> String {
+        body_tree(&TestDocument::with_css(html, css).render_tree())
+    }
+
+    #[test]
+    fn collapse_modes() {
+        assert_eq!(collapse("  a \t b\n\n c  ", WhiteSpaceCollapse::Collapse), " a b c ");
+        assert_eq!(
+            collapse("  a \t b \n\n c  ", WhiteSpaceCollapse::PreserveBreaks),
+            " a b\n\nc "
+        );
+        assert_eq!(collapse(" a\tb\nc ", WhiteSpaceCollapse::PreserveSpaces), " a b c ");
+        assert_eq!(collapse(" a\tb\nc ", WhiteSpaceCollapse::Preserve), " a\tb\nc ");
+        assert_eq!(collapse(" a\tb\nc ", WhiteSpaceCollapse::Discard), "abc");
+        // Segment breaks next to a zero width space are removed
+        assert_eq!(
+            collapse("a\u{200b}\nb\n\u{200b}c\nd", WhiteSpaceCollapse::Collapse),
+            "a\u{200b}b\u{200b}c d"
+        );
+
+        // White space collapses across texts
+        let mut after_space = true;
+        assert_eq!(
+            collapse_text(" a ", WhiteSpaceCollapse::Collapse, &mut after_space),
+            "a "
+        );
+        assert!(after_space);
+        assert_eq!(collapse_text(" b", WhiteSpaceCollapse::Collapse, &mut after_space), "b");
+        assert!(!after_space);
+    }
+
+    #[test]
+    fn white_space_processing() {
+        // Collapsible spaces are removed at the start and end of a block and between inline boxes,
+        // and text with only white space between blocks is dropped
+        assert_eq!(
+            boxes(
+                "\n  <div>\n    <p>  one  <b> two </b>  three </p>\n  <p>\n</p>\n</div>\n",
+                ""
+            ),
+            r#"body[div[p[anon["one "] b["two "] anon["three"]] p]]"#
+        );
+        assert_eq!(
+            boxes(
+                "<div>a <span class=\"ib\">b</span> c <i></i> </div>",
+                ".ib { display: inline-block }"
+            ),
+            r#"body[div[anon["a "] span[anon["b"]] anon[" c"] i]]"#
+        );
+
+        let css = ".n { white-space: nowrap } .l { white-space: pre-line } .w { white-space: pre-wrap }";
+        assert_eq!(
+            boxes("<pre>\n  a  b\n</pre><div class=\"n\">  a  b  </div>", css),
+            r#"body[pre[anon["\n  a  b\n"]] div[anon["a b"]]]"#
+        );
+        assert_eq!(
+            boxes("<div class=\"l\">  a  \n  b  </div><div class=\"w\"> a  b </div>", css),
+            r#"body[div[anon["a\nb"]] div[anon[" a  b "]]]"#
+        );
+    }
+}
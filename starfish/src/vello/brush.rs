@@ -1,10 +1,11 @@
 
 use crate::{
+    image::Image,
     interface::render_backend::Brush as TBrush,
     vello::{Color, VelloBackend},
 };
 
-use vello::peniko::Brush as VelloBrush;
+use vello::peniko::{Blob, Brush as VelloBrush, Format, Image as VelloImage};
 
 #[derive(Clone)]
 pub struct Brush(pub(crate) VelloBrush);
@@ -19,4 +20,11 @@ impl TBrush<VelloBackend> for Brush {
     fn color(color: Color) -> Self {
         Brush(VelloBrush::Solid(color.0))
     }
+
+    fn image(image: &Image) -> Self {
+        // The id of the pixels is kept, so vello can cache the image
+        let blob = Blob::from_raw_parts(image.data().clone(), image.id());
+        let image = VelloImage::new(blob, Format::Rgba8, image.width(), image.height());
+        Brush(VelloBrush::Image(image))
+    }
 }
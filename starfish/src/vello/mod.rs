@@ -3,7 +3,7 @@ use std::{
     sync::Arc
 };
 
-use crate::interface::render_backend::{RenderBackend, RenderRect, Scene as TScene, WindowHandle};
+use crate::interface::render_backend::{RenderBackend, RenderRect, RenderText, Scene as TScene, WindowHandle};
 
 use anyhow::{anyhow, Result};
 use vello::{
@@ -80,6 +80,10 @@ impl RenderBackend for VelloBackend {
         data.scene.draw_rect(rect);
     }
 
+    fn draw_text(&mut self, data: &mut Self::WindowData, text: &RenderText<Self>) {
+        data.scene.draw_text(text);
+    }
+
     fn apply_scene(&mut self, data: &mut Self::WindowData, scene: &Self::Scene, transform: Option<Self::Transform>) {
         data.scene.apply_scene(scene, transform);
     }
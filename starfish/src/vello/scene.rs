@@ -1,14 +1,14 @@
 use std::fmt::{Debug, Formatter};
 
 use crate::{
-    interface::render_backend::{RenderRect, Scene as TScene},
+    interface::render_backend::{RenderRect, RenderText, Scene as TScene},
     vello::{Border, BorderRenderOptions, Transform, VelloBackend},
 };
 
 use vello::{
     kurbo::RoundedRect,
-    peniko::Fill,
-    Scene as VelloScene,
+    peniko::{Blob, Fill, Font},
+    Glyph, Scene as VelloScene,
 };
 
 #[derive(Clone)]
@@ -52,6 +52,28 @@ impl TScene<VelloBackend> for Scene {
         }
     }
 
+    fn draw_text(&mut self, text: &RenderText<VelloBackend>) {
+        let affine = text.transform.as_ref().map(|t| t.0).unwrap_or_default();
+
+        // The id of the font data is kept, so vello can cache the glyphs of the font
+        let font = &text.font;
+        let blob = Blob::from_raw_parts(font.data().clone(), font.id());
+        let font = Font::new(blob, font.index());
+
+        let glyphs = text.glyphs.iter().map(|glyph| Glyph {
+            id: glyph.id,
+            x: glyph.x,
+            y: glyph.y,
+        });
+
+        self.0
+            .draw_glyphs(&font)
+            .font_size(text.font_size)
+            .transform(affine)
+            .brush(&text.brush.0)
+            .draw(Fill::NonZero, glyphs);
+    }
+
     fn apply_scene(&mut self, scene: &Scene, transform: Option<Transform>) {
         self.0.append(&scene.0, transform.map(|t| t.0));
     }
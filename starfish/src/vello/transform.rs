@@ -1,6 +1,6 @@
 use std::ops::{Mul, MulAssign};
 
-use crate::interface::render_backend::Transform as TTransform;
+use crate::{interface::render_backend::Transform as TTransform, shared::geo::FP};
 
 use vello::kurbo::Affine;
 
@@ -28,5 +28,11 @@ impl MulAssign for Transform {
 }
 
 impl TTransform for Transform {
+    fn translate(x: FP, y: FP) -> Self {
+        Transform(Affine::translate((x as f64, y as f64)))
+    }
 
+    fn scale(x: FP, y: FP) -> Self {
+        Transform(Affine::scale_non_uniform(x as f64, y as f64))
+    }
 }
@@ -9,8 +9,8 @@ use crate::{
         render_backend::{RenderBackend, WindowedEventLoop},
     },
     shared::{
-        geo::SizeU32,
-        types::Result,
+        geo::{FP, SizeU32},
+        types::{Point, Result},
     },
     tabs::{Tab, TabID, Tabs},
 };
@@ -18,11 +18,14 @@ use crate::{
 use anyhow::anyhow;
 use winit::{
     dpi::LogicalSize,
-    event::WindowEvent,
+    event::{MouseScrollDelta, WindowEvent},
     event_loop::{ActiveEventLoop, EventLoopProxy},
     window::{Window as WInitWindow, WindowId},
 };
 
+/// Distance the page is scrolled for each line a mouse wheel moves
+const SCROLL_LINE_HEIGHT: FP = 40.0;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WindowState<'a, B: RenderBackend> {
     Active { surface: B::ActiveWindowData<'a> },
@@ -124,6 +127,20 @@ impl<'a, C: ModuleConfiguration> Window<'a, C> {
                 backend.resize_window(&mut self.renderer_data, active_window_data, SizeU32::new(size.width, size.height))?;
                 window.request_redraw();
             },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Point::new(-x * SCROLL_LINE_HEIGHT, -y * SCROLL_LINE_HEIGHT),
+                    MouseScrollDelta::PixelDelta(position) => Point::new(-position.x as FP, -position.y as FP),
+                };
+
+                let Some(tab) = self.tabs.get_current_tab() else {
+                    return Ok(());
+                };
+
+                if tab.data.scroll(delta) {
+                    window.request_redraw();
+                }
+            },
             WindowEvent::RedrawRequested => {
                 let size = window.inner_size();
 
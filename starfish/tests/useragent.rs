@@ -0,0 +1,72 @@
+use starfish::css3::{
+    load_default_useragent_stylesheet,
+    stylesheet::{CssDeclaration, CssLogLevel, CssStylesheet, CssValue},
+};
+
+/// Returns the declarations of all rules with exactly the given selector
+fn declarations<'a>(sheet: &'a CssStylesheet, selector: &str) -> Vec<&'a CssDeclaration> {
+    sheet
+        .rules
+        .iter()
+        .filter(|rule| rule.selectors().iter().any(|s| s.to_string() == selector))
+        .flat_map(|rule| rule.declarations())
+        .collect()
+}
+
+fn value(sheet: &CssStylesheet, selector: &str, property: &str) -> Option<Vec<CssValue>> {
+    declarations(sheet, selector)
+        .into_iter()
+        .rev()
+        .find(|declaration| declaration.property == property)
+        .map(|declaration| declaration.value.clone())
+}
+
+fn keyword(name: &str) -> Vec<CssValue> {
+    vec![CssValue::Keyword(name.to_string())]
+}
+
+fn unit(value: f32, unit: &str) -> Vec<CssValue> {
+    vec![CssValue::Unit(value, unit.to_string())]
+}
+
+#[test]
+fn useragent_stylesheet_parses_without_errors() {
+    let sheet = load_default_useragent_stylesheet();
+
+    let errors = sheet
+        .parse_log
+        .iter()
+        .filter(|log| log.level == CssLogLevel::Error)
+        .map(|log| log.to_string())
+        .collect::<Vec<_>>();
+    assert!(errors.is_empty(), "errors in useragent stylesheet: {errors:#?}");
+}
+
+#[test]
+fn useragent_stylesheet_rules() {
+    let sheet = load_default_useragent_stylesheet();
+
+    assert_eq!(value(&sheet, "html", "display"), Some(keyword("block")));
+
+    assert_eq!(value(&sheet, "body", "display"), Some(keyword("block")));
+    // The margin shorthand is expanded into its longhands
+    for side in ["top", "right", "bottom", "left"] {
+        assert_eq!(value(&sheet, "body", &format!("margin-{side}")), Some(unit(8.0, "px")));
+    }
+
+    assert_eq!(value(&sheet, "p", "display"), Some(keyword("block")));
+    // `1__qem` is handled as `1em`
+    assert_eq!(value(&sheet, "p", "margin-block-start"), Some(unit(1.0, "em")));
+    assert_eq!(value(&sheet, "p", "margin-block-end"), Some(unit(1.0, "em")));
+
+    let font_sizes = [2.0, 1.5, 1.17, 1.0, 0.83, 0.67];
+    for (i, font_size) in font_sizes.into_iter().enumerate() {
+        let heading = format!("h{}", i + 1);
+        assert_eq!(value(&sheet, &heading, "display"), Some(keyword("block")));
+        assert_eq!(value(&sheet, &heading, "font-weight"), Some(keyword("bold")));
+        assert!(value(&sheet, &heading, "margin-block-start").is_some());
+        if heading != "h4" {
+            assert_eq!(value(&sheet, &heading, "font-size"), Some(unit(font_size, "em")));
+        }
+    }
+}